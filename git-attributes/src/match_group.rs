@@ -53,7 +53,9 @@ impl Pattern for Ignore {
 /// A value of an attribute pattern, which is either a macro definition or
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
 pub enum Value {
+    /// The attributes a macro expands to.
     MacroAttributes(Vec<Assignment>),
+    /// The attributes assigned to paths matching a pattern.
     Assignments(Vec<Assignment>),
 }
 
@@ -45,6 +45,59 @@ pub fn decode(data: &[u8]) -> Result<(Vec, &[u8]), decode::Error> {
     ))
 }
 
+/// Encode a bitmap given as uncompressed `words`, where bit `n` is `words[n / 64] & (1 << (n % 64))`.
+///
+/// All-zero and all-one words are run-length encoded, everything else is stored as literal words.
+pub fn encode(words: &[u64]) -> Vec {
+    use self::access::{RLW_LARGEST_LITERAL_COUNT, RLW_LARGEST_RUNNING_COUNT, RLW_RUNNING_BITS};
+
+    let num_bits = words
+        .iter()
+        .rposition(|w| *w != 0)
+        .map(|pos| pos as u64 * 64 + (64 - words[pos].leading_zeros() as u64))
+        .unwrap_or(0);
+    let words = &words[..((num_bits + 63) / 64) as usize];
+
+    let mut bits = std::vec::Vec::with_capacity(words.len() + 1);
+    let mut cursor = 0;
+    let rlw = loop {
+        let rlw = bits.len();
+        let mut marker = 0u64;
+        if let Some(first) = words.get(cursor).copied().filter(|w| *w == 0 || *w == u64::MAX) {
+            let mut run_len = 0;
+            while run_len < RLW_LARGEST_RUNNING_COUNT && words.get(cursor) == Some(&first) {
+                run_len += 1;
+                cursor += 1;
+            }
+            marker |= (first & 1) | (run_len << 1);
+        }
+        bits.push(marker);
+
+        let mut literals = 0;
+        while literals < RLW_LARGEST_LITERAL_COUNT {
+            match words.get(cursor) {
+                Some(w) if *w != 0 && *w != u64::MAX => {
+                    bits.push(*w);
+                    literals += 1;
+                    cursor += 1;
+                }
+                _ => break,
+            }
+        }
+        bits[rlw] |= literals << (1 + RLW_RUNNING_BITS);
+
+        if cursor == words.len() {
+            break rlw;
+        }
+    };
+
+    Vec {
+        num_bits: num_bits.try_into().expect("bitmaps can hold at most u32::MAX bits"),
+        bits,
+        rlw: rlw as u64,
+    }
+}
+
 mod access {
     use std::convert::{TryFrom, TryInto};
 
@@ -84,6 +137,44 @@ mod access {
             Some(())
         }
 
+        /// Return all bits as uncompressed words, with bit `n` being `words[n / 64] & (1 << (n % 64))`.
+        ///
+        /// Trailing words that are entirely zero may be omitted.
+        pub fn to_words(&self) -> std::vec::Vec<u64> {
+            let mut out = std::vec::Vec::with_capacity((self.num_bits() + 63) / 64);
+            let mut iter = self.bits.iter();
+            while let Some(word) = iter.next() {
+                let fill = if rlw_runbit_is_set(word) { u64::MAX } else { 0 };
+                out.extend(std::iter::repeat(fill).take(rlw_running_len(word) as usize));
+                for _ in 0..rlw_literal_words(word) {
+                    out.push(
+                        *iter
+                            .next()
+                            .expect("BUG: ran out of words while going through uncompressed portion"),
+                    );
+                }
+            }
+            out
+        }
+
+        /// Write the on-disk representation of this bitmap to `out`, the inverse of [`decode()`][super::decode()].
+        pub fn write_to(&self, mut out: impl std::io::Write) -> std::io::Result<()> {
+            out.write_all(&self.num_bits.to_be_bytes())?;
+            out.write_all(
+                &u32::try_from(self.bits.len())
+                    .expect("at most u32::MAX words")
+                    .to_be_bytes(),
+            )?;
+            for word in &self.bits {
+                out.write_all(&word.to_be_bytes())?;
+            }
+            out.write_all(
+                &u32::try_from(self.rlw)
+                    .expect("rlw is an index into our words")
+                    .to_be_bytes(),
+            )
+        }
+
         /// The amount of bits we are currently holding.
         pub fn num_bits(&self) -> usize {
             self.num_bits.try_into().expect("we are not on 16 bit systems")
@@ -110,8 +201,9 @@ mod access {
         w & 1 == 1
     }
 
-    const RLW_RUNNING_BITS: u64 = 4 * 8;
-    const RLW_LARGEST_RUNNING_COUNT: u64 = (1 << RLW_RUNNING_BITS) - 1;
+    pub(super) const RLW_RUNNING_BITS: u64 = 4 * 8;
+    pub(super) const RLW_LARGEST_RUNNING_COUNT: u64 = (1 << RLW_RUNNING_BITS) - 1;
+    pub(super) const RLW_LARGEST_LITERAL_COUNT: u64 = (1 << (64 - 1 - RLW_RUNNING_BITS)) - 1;
 }
 
 /// A growable collection of u64 that are seen as stream of individual bits.
@@ -2,7 +2,7 @@
 #![deny(rust_2018_idioms, missing_docs)]
 #![forbid(unsafe_code)]
 
-use std::ffi::OsString;
+use std::{ffi::OsString, path::PathBuf};
 
 /// A structure to keep settings to use when invoking a command via [`spawn()`][Prepare::spawn()], after creating it with [`prepare()`].
 pub struct Prepare {
@@ -11,6 +11,8 @@ pub struct Prepare {
     stdout: std::process::Stdio,
     stderr: std::process::Stdio,
     args: Vec<OsString>,
+    env: Vec<(OsString, OsString)>,
+    cwd: Option<PathBuf>,
     use_shell: bool,
 }
 
@@ -55,6 +57,18 @@ mod prepare {
             self.args.push(arg.into());
             self
         }
+
+        /// Set the environment variable `key` to `value` for the command, in addition to the ones it inherits.
+        pub fn env(mut self, key: impl Into<std::ffi::OsString>, value: impl Into<std::ffi::OsString>) -> Self {
+            self.env.push((key.into(), value.into()));
+            self
+        }
+
+        /// Run the command in the directory `dir` instead of the current working directory.
+        pub fn cwd(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+            self.cwd = Some(dir.into());
+            self
+        }
     }
 
     /// Finalization
@@ -83,7 +97,11 @@ mod prepare {
             cmd.stdin(prep.stdin)
                 .stdout(prep.stdout)
                 .stderr(prep.stderr)
-                .args(prep.args);
+                .args(prep.args)
+                .envs(prep.env);
+            if let Some(dir) = prep.cwd {
+                cmd.current_dir(dir);
+            }
             cmd
         }
     }
@@ -103,6 +121,8 @@ pub fn prepare(cmd: impl Into<OsString>) -> Prepare {
         stdout: std::process::Stdio::piped(),
         stderr: std::process::Stdio::inherit(),
         args: Vec::new(),
+        env: Vec::new(),
+        cwd: None,
         use_shell: false,
     }
 }
@@ -17,6 +17,25 @@ mod spawn {
         Ok(())
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn environment_variables_and_working_directory_are_passed_to_the_command() -> crate::Result {
+        use git_testtools::bstr::ByteSlice;
+        let dir = git_testtools::tempfile::TempDir::new()?;
+        let out = git_command::prepare("echo $VALUE; pwd")
+            .with_shell()
+            .env("VALUE", "hello")
+            .cwd(dir.path())
+            .spawn()?
+            .wait_with_output()?;
+        assert!(out.status.success());
+        assert_eq!(
+            out.stdout.as_bstr(),
+            format!("hello\n{}\n", dir.path().canonicalize()?.display())
+        );
+        Ok(())
+    }
+
     mod with_shell {
         use git_testtools::bstr::ByteSlice;
 
@@ -3,7 +3,7 @@ use std::borrow::Cow;
 use git_features::threading::OwnShared;
 
 use crate::{
-    file::{self, rename_section, write::ends_with_newline, MetadataFilter, SectionBodyIdsLut, SectionId, SectionMut},
+    file::{self, rename_section, write::ends_with_newline, MetadataFilter, SectionId, SectionMut},
     lookup,
     parse::{section, Event, FrontMatterEvents},
     File,
@@ -201,28 +201,7 @@ impl<'event> File<'event> {
         self.section_order
             .remove(self.section_order.iter().position(|v| *v == id)?);
         let section = self.sections.remove(&id)?;
-        let lut = self
-            .section_lookup_tree
-            .get_mut(&section.header.name)
-            .expect("lookup cache still has name to be deleted");
-        for entry in lut {
-            match section.header.subsection_name.as_deref() {
-                Some(subsection_name) => {
-                    if let SectionBodyIdsLut::NonTerminal(map) = entry {
-                        if let Some(ids) = map.get_mut(subsection_name) {
-                            ids.remove(ids.iter().position(|v| *v == id).expect("present"));
-                            break;
-                        }
-                    }
-                }
-                None => {
-                    if let SectionBodyIdsLut::Terminal(ids) = entry {
-                        ids.remove(ids.iter().position(|v| *v == id).expect("present"));
-                        break;
-                    }
-                }
-            }
-        }
+        self.remove_from_lookup(&section.header, id);
         Some(section)
     }
 
@@ -241,13 +220,7 @@ impl<'event> File<'event> {
             .ok()?
             .rev()
             .find(|id| filter(self.sections.get(id).expect("each id has a section").meta()))?;
-        self.section_order.remove(
-            self.section_order
-                .iter()
-                .position(|v| *v == id)
-                .expect("known section id"),
-        );
-        self.sections.remove(&id)
+        self.remove_section_by_id(id)
     }
 
     /// Adds the provided section to the config, returning a mutable reference
@@ -276,9 +249,8 @@ impl<'event> File<'event> {
             .section_ids_by_name_and_subname(name.as_ref(), subsection_name.into())?
             .rev()
             .next()
-            .expect("list of sections were empty, which violates invariant");
-        let section = self.sections.get_mut(&id).expect("known section-id");
-        section.header = section::Header::new(new_name, new_subsection_name)?;
+            .ok_or(rename_section::Error::Lookup(lookup::existing::Error::SectionMissing))?;
+        self.set_section_header(id, section::Header::new(new_name, new_subsection_name)?);
         Ok(())
     }
 
@@ -300,8 +272,7 @@ impl<'event> File<'event> {
             .rev()
             .find(|id| filter(self.sections.get(id).expect("each id has a section").meta()))
             .ok_or(rename_section::Error::Lookup(lookup::existing::Error::KeyMissing))?;
-        let section = self.sections.get_mut(&id).expect("known section-id");
-        section.header = section::Header::new(new_name, new_subsection_name)?;
+        self.set_section_header(id, section::Header::new(new_name, new_subsection_name)?);
         Ok(())
     }
 
@@ -111,6 +111,85 @@ impl<'event> File<'event> {
         new_section_id
     }
 
+    /// Give the section with `id` the new `header` while keeping its position, and update all lookup structures accordingly.
+    pub(crate) fn set_section_header(&mut self, id: SectionId, header: section::Header<'event>) {
+        let previous_header = std::mem::replace(
+            &mut self.sections.get_mut(&id).expect("known section-id").header,
+            header,
+        );
+        self.remove_from_lookup(&previous_header, id);
+
+        let lookup_section_order = {
+            let section_order = &self.section_order;
+            move |section_id| {
+                section_order
+                    .iter()
+                    .enumerate()
+                    .find_map(|(idx, id)| (*id == section_id).then(|| idx))
+                    .expect("section exists")
+            }
+        };
+        let own_order = lookup_section_order(id);
+        let header = &self.sections[&id].header;
+        let lookup = self.section_lookup_tree.entry(header.name.clone()).or_default();
+        let ids = match header.subsection_name.clone() {
+            Some(subsection_name) => {
+                let position = lookup
+                    .iter()
+                    .position(|node| matches!(node, SectionBodyIdsLut::NonTerminal(_)))
+                    .unwrap_or_else(|| {
+                        lookup.push(SectionBodyIdsLut::NonTerminal(HashMap::new()));
+                        lookup.len() - 1
+                    });
+                match &mut lookup[position] {
+                    SectionBodyIdsLut::NonTerminal(subsections) => subsections.entry(subsection_name).or_default(),
+                    SectionBodyIdsLut::Terminal(_) => unreachable!("found by position"),
+                }
+            }
+            None => {
+                let position = lookup
+                    .iter()
+                    .position(|node| matches!(node, SectionBodyIdsLut::Terminal(_)))
+                    .unwrap_or_else(|| {
+                        lookup.push(SectionBodyIdsLut::Terminal(Vec::new()));
+                        lookup.len() - 1
+                    });
+                match &mut lookup[position] {
+                    SectionBodyIdsLut::Terminal(ids) => ids,
+                    SectionBodyIdsLut::NonTerminal(_) => unreachable!("found by position"),
+                }
+            }
+        };
+        let insert_pos = find_insert_pos_by_order(ids, own_order, lookup_section_order);
+        ids.insert(insert_pos, id);
+    }
+
+    /// Remove the section `id` with `header` from the lookup tree, but not from any other data structure.
+    pub(crate) fn remove_from_lookup(&mut self, header: &section::Header<'event>, id: SectionId) {
+        let lut = self
+            .section_lookup_tree
+            .get_mut(&header.name)
+            .expect("lookup cache still has name to be deleted");
+        for entry in lut {
+            match header.subsection_name.as_deref() {
+                Some(subsection_name) => {
+                    if let SectionBodyIdsLut::NonTerminal(map) = entry {
+                        if let Some(ids) = map.get_mut(subsection_name) {
+                            ids.remove(ids.iter().position(|v| *v == id).expect("present"));
+                            break;
+                        }
+                    }
+                }
+                None => {
+                    if let SectionBodyIdsLut::Terminal(ids) = entry {
+                        ids.remove(ids.iter().position(|v| *v == id).expect("present"));
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
     /// Returns the mapping between section and subsection name to section ids.
     pub(crate) fn section_ids_by_name_and_subname<'a>(
         &'a self,
@@ -51,6 +51,26 @@ mod rename_section {
 
     use git_config::{file::rename_section, parse::section};
 
+    #[test]
+    fn renamed_sections_keep_their_position_and_can_be_looked_up_by_their_new_name() {
+        let mut file = git_config::File::try_from(
+            "[branch \"old\"]\n\ta = 1\n[branch \"new\"]\n\ta = 2\n[branch \"old\"]\n\tb = 3\n",
+        )
+        .unwrap();
+        while file
+            .rename_section("branch", Some("old"), "branch", Cow::from("new"))
+            .is_ok()
+        {}
+        assert_eq!(
+            file.to_string(),
+            "[branch \"new\"]\n\ta = 1\n[branch \"new\"]\n\ta = 2\n[branch \"new\"]\n\tb = 3\n"
+        );
+        assert_eq!(file.string("branch", Some("new"), "a").expect("present").as_ref(), "2");
+        assert_eq!(file.string("branch", Some("new"), "b").expect("present").as_ref(), "3");
+        assert!(file.string("branch", Some("old"), "a").is_none());
+        assert_eq!(file.sections_by_name("branch").expect("present").count(), 3);
+    }
+
     #[test]
     fn section_renaming_validates_new_name() {
         let mut file = git_config::File::try_from("[core] a = b").unwrap();
@@ -3,9 +3,15 @@
 //! With the `fast-sha1` feature, the `Sha1` hash type will use a more elaborate implementation utilizing hardware support
 //! in case it is available. Otherwise the `rustsha1` feature should be set. `fast-sha1` will take precedence.
 //! Otherwise, a minimal yet performant implementation is used instead for a decent trade-off between compile times and run-time performance.
-#[cfg(all(feature = "rustsha1", not(feature = "fast-sha1")))]
+//!
+//! With the `hardened-sha1` feature, the `Sha1` hash type detects collision attacks similar to `sha1dc` as used by `git`, and takes
+//! precedence over both of the above. Use [`Hasher::try_digest()`] to learn about detected collisions.
+//!
+//! Any of these features also makes available the `Sha256` hash type, which is used for repositories with `extensions.objectFormat = sha256`.
+//! Use [`hasher()`] to obtain an implementation suitable for a particular [`git_hash::Kind`].
+#[cfg(all(feature = "rustsha1", not(any(feature = "fast-sha1", feature = "hardened-sha1"))))]
 mod _impl {
-    use super::Sha1Digest;
+    use super::{Collision, Sha1Digest};
 
     /// A implementation of the Sha1 hash, which can be used once.
     #[derive(Default, Clone)]
@@ -20,18 +26,22 @@ mod _impl {
         pub fn digest(self) -> Sha1Digest {
             self.0.digest().bytes()
         }
+        /// Finalize the hash and produce a digest, which never fails as this implementation can't detect collisions.
+        pub fn try_digest(self) -> Result<Sha1Digest, Collision> {
+            Ok(self.digest())
+        }
     }
 }
 
 /// A 20 bytes digest produced by a [`Sha1`] hash implementation.
-#[cfg(any(feature = "fast-sha1", feature = "rustsha1"))]
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
 pub type Sha1Digest = [u8; 20];
 
-#[cfg(feature = "fast-sha1")]
+#[cfg(all(feature = "fast-sha1", not(feature = "hardened-sha1")))]
 mod _impl {
     use sha1::Digest;
 
-    use super::Sha1Digest;
+    use super::{Collision, Sha1Digest};
 
     /// A implementation of the Sha1 hash, which can be used once.
     #[derive(Default, Clone)]
@@ -46,12 +56,147 @@ mod _impl {
         pub fn digest(self) -> Sha1Digest {
             self.0.finalize().into()
         }
+        /// Finalize the hash and produce a digest, which never fails as this implementation can't detect collisions.
+        pub fn try_digest(self) -> Result<Sha1Digest, Collision> {
+            Ok(self.digest())
+        }
     }
 }
 
-#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
+#[cfg(feature = "hardened-sha1")]
+mod _impl {
+    use super::{Collision, Sha1Digest};
+
+    /// A implementation of the Sha1 hash which detects collision attacks, and which can be used once.
+    #[derive(Default, Clone)]
+    pub struct Sha1(sha1collisiondetection::Sha1CD);
+
+    impl Sha1 {
+        /// Digest the given `bytes`.
+        pub fn update(&mut self, bytes: &[u8]) {
+            self.0.update(bytes)
+        }
+        /// Finalize the hash and produce a digest.
+        ///
+        /// If a collision attack was detected, the digest is computed with mitigations in place and thus differs from
+        /// what an unhardened implementation would produce. Use [`try_digest()`][Self::try_digest()] to be informed about it.
+        pub fn digest(mut self) -> Sha1Digest {
+            let mut digest = sha1collisiondetection::Output::default();
+            self.0.finalize_into_dirty_cd(&mut digest).ok();
+            to_digest(&digest)
+        }
+        /// Finalize the hash and produce a digest, or fail if a collision attack was detected.
+        pub fn try_digest(mut self) -> Result<Sha1Digest, Collision> {
+            let mut digest = sha1collisiondetection::Output::default();
+            self.0.finalize_into_dirty_cd(&mut digest).map_err(|_| Collision)?;
+            Ok(to_digest(&digest))
+        }
+    }
+
+    fn to_digest(output: &sha1collisiondetection::Output) -> Sha1Digest {
+        let mut digest = Sha1Digest::default();
+        digest.copy_from_slice(output.as_slice());
+        digest
+    }
+}
+
+/// The error returned by [`Hasher::try_digest()`] and `Sha1::try_digest()` if a collision attack was detected while hashing.
+///
+/// Only the implementation provided by the `hardened-sha1` feature is able to detect collisions.
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Collision;
+
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
+impl std::fmt::Display for Collision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("A SHA-1 collision attack was detected while hashing")
+    }
+}
+
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
+impl std::error::Error for Collision {}
+
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
 pub use _impl::Sha1;
 
+/// A 32 bytes digest produced by a [`Sha256`] hash implementation.
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
+pub type Sha256Digest = [u8; 32];
+
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
+mod _impl_sha256 {
+    use sha2::Digest;
+
+    use super::Sha256Digest;
+
+    /// A implementation of the Sha256 hash, which can be used once.
+    #[derive(Default, Clone)]
+    pub struct Sha256(sha2::Sha256);
+
+    impl Sha256 {
+        /// Digest the given `bytes`.
+        pub fn update(&mut self, bytes: &[u8]) {
+            self.0.update(bytes)
+        }
+        /// Finalize the hash and produce a digest.
+        pub fn digest(self) -> Sha256Digest {
+            self.0.finalize().into()
+        }
+    }
+}
+
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
+pub use _impl_sha256::Sha256;
+
+/// A hash implementation for any of the supported [hash kinds][git_hash::Kind], as obtained by [`hasher()`].
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
+#[derive(Clone)]
+pub enum Hasher {
+    /// The Sha1 hash implementation.
+    Sha1(Sha1),
+    /// The Sha256 hash implementation.
+    Sha256(Sha256),
+}
+
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
+impl Hasher {
+    /// Digest the given `bytes`.
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Sha1(h) => h.update(bytes),
+            Hasher::Sha256(h) => h.update(bytes),
+        }
+    }
+
+    /// Finalize the hash and produce an object id of the kind of hash we were created with.
+    ///
+    /// Note that detected collisions go unnoticed here, use [`try_digest()`][Self::try_digest()] when hashing objects.
+    pub fn digest(self) -> git_hash::ObjectId {
+        match self {
+            Hasher::Sha1(h) => h.digest().into(),
+            Hasher::Sha256(h) => h.digest().into(),
+        }
+    }
+
+    /// Finalize the hash and produce an object id of the kind of hash we were created with, or fail if a collision
+    /// attack was detected.
+    pub fn try_digest(self) -> Result<git_hash::ObjectId, Collision> {
+        Ok(match self {
+            Hasher::Sha1(h) => h.try_digest()?.into(),
+            Hasher::Sha256(h) => h.digest().into(),
+        })
+    }
+
+    /// Return the kind of hash we produce.
+    pub fn kind(&self) -> git_hash::Kind {
+        match self {
+            Hasher::Sha1(_) => git_hash::Kind::Sha1,
+            Hasher::Sha256(_) => git_hash::Kind::Sha256,
+        }
+    }
+}
+
 /// Compute a CRC32 hash from the given `bytes`, returning the CRC32 hash.
 ///
 /// When calling this function for the first time, `previous_value` should be `0`. Otherwise it
@@ -75,10 +220,11 @@ pub fn crc32(bytes: &[u8]) -> u32 {
 }
 
 /// Produce a hasher suitable for the given kind of hash.
-#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
-pub fn hasher(kind: git_hash::Kind) -> Sha1 {
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
+pub fn hasher(kind: git_hash::Kind) -> Hasher {
     match kind {
-        git_hash::Kind::Sha1 => Sha1::default(),
+        git_hash::Kind::Sha1 => Hasher::Sha1(Sha1::default()),
+        git_hash::Kind::Sha256 => Hasher::Sha256(Sha256::default()),
     }
 }
 
@@ -93,7 +239,11 @@ pub fn hasher(kind: git_hash::Kind) -> Sha1 {
 /// * Only available with the `git-object` feature enabled due to usage of the [`git_hash::Kind`] enum and the
 ///   [`git_hash::ObjectId`] return value.
 /// * [Interrupts][crate::interrupt] are supported.
-#[cfg(all(feature = "progress", any(feature = "rustsha1", feature = "fast-sha1")))]
+/// * Detected collisions are returned as [`std::io::ErrorKind::Other`] wrapping a [`Collision`].
+#[cfg(all(
+    feature = "progress",
+    any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1")
+))]
 pub fn bytes_of_file(
     path: impl AsRef<std::path::Path>,
     num_bytes_from_start: usize,
@@ -111,7 +261,10 @@ pub fn bytes_of_file(
 }
 
 /// Similar to [`bytes_of_file`], but operates on an already open file.
-#[cfg(all(feature = "progress", any(feature = "rustsha1", feature = "fast-sha1")))]
+#[cfg(all(
+    feature = "progress",
+    any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1")
+))]
 pub fn bytes(
     mut read: impl std::io::Read,
     num_bytes_from_start: usize,
@@ -139,19 +292,21 @@ pub fn bytes(
         }
     }
 
-    let id = git_hash::ObjectId::from(hasher.digest());
+    let id = hasher
+        .try_digest()
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
     progress.show_throughput(start);
     Ok(id)
 }
 
-#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
 mod write {
-    use crate::hash::Sha1;
+    use crate::hash::Hasher;
 
     /// A utility to automatically generate a hash while writing into an inner writer.
     pub struct Write<T> {
         /// The hash implementation.
-        pub hash: Sha1,
+        pub hash: Hasher,
         /// The inner writer.
         pub inner: T,
     }
@@ -177,14 +332,12 @@ mod write {
     {
         /// Create a new hash writer which hashes all bytes written to `inner` with a hash of `kind`.
         pub fn new(inner: T, object_hash: git_hash::Kind) -> Self {
-            match object_hash {
-                git_hash::Kind::Sha1 => Write {
-                    inner,
-                    hash: Sha1::default(),
-                },
+            Write {
+                inner,
+                hash: crate::hash::hasher(object_hash),
             }
         }
     }
 }
-#[cfg(any(feature = "rustsha1", feature = "fast-sha1"))]
+#[cfg(any(feature = "rustsha1", feature = "fast-sha1", feature = "hardened-sha1"))]
 pub use write::Write;
@@ -1,13 +1,41 @@
 use git_features::hash::Sha1;
 
-#[cfg(not(feature = "fast-sha1"))]
+#[cfg(not(any(feature = "fast-sha1", feature = "hardened-sha1")))]
 #[test]
 fn size_of_sha1() {
     assert_eq!(std::mem::size_of::<Sha1>(), 96)
 }
 
-#[cfg(feature = "fast-sha1")]
+#[cfg(all(feature = "fast-sha1", not(feature = "hardened-sha1")))]
 #[test]
 fn size_of_sha1() {
     assert_eq!(std::mem::size_of::<Sha1>(), 104)
 }
+
+#[cfg(feature = "hardened-sha1")]
+#[test]
+fn size_of_sha1() {
+    assert_eq!(std::mem::size_of::<Sha1>(), 2392)
+}
+
+#[cfg(feature = "hardened-sha1")]
+mod hardened {
+    use git_features::hash::{hasher, Collision};
+
+    #[test]
+    fn regular_input_yields_the_same_digest_as_unhardened_implementations() {
+        let mut h = hasher(git_hash::Kind::Sha1);
+        h.update(b"hello world");
+        assert_eq!(
+            h.try_digest().expect("no collision"),
+            git_hash::ObjectId::from_hex(b"2aae6c35c94fcfb415dbe95f408b9ce91ee846ed").unwrap()
+        );
+    }
+
+    #[test]
+    fn collision_attacks_are_detected() {
+        let mut h = hasher(git_hash::Kind::Sha1);
+        h.update(include_bytes!("fixtures/sha-mbles-1.bin"));
+        assert_eq!(h.try_digest(), Err(Collision));
+    }
+}
@@ -9,6 +9,12 @@ mod from_hex_len {
         assert_eq!(Kind::from_hex_len(40), Some(Kind::Sha1));
     }
 
+    #[test]
+    fn some_sha256() {
+        assert_eq!(Kind::from_hex_len(41), Some(Kind::Sha256));
+        assert_eq!(Kind::from_hex_len(64), Some(Kind::Sha256));
+    }
+
     #[test]
     fn none_if_there_is_no_fit() {
         assert_eq!(Kind::from_hex_len(65), None);
@@ -12,6 +12,14 @@ mod from_hex {
         fn twenty_hex_chars_uppercase() {
             assert!(ObjectId::from_hex(b"1234567890ABCDEFAAAAAAAAAAAAAAAAAAAAAAAA").is_ok());
         }
+
+        #[test]
+        fn sixty_four_hex_chars_is_sha256() {
+            let id = ObjectId::from_hex(b"6ef19b41225c5369f1c104d45d8d85efa9b057b53b14b4b9b939dd74decc5321")
+                .expect("valid input");
+            assert_eq!(id.kind(), git_hash::Kind::Sha256);
+            assert_eq!(id, ObjectId::empty_tree(git_hash::Kind::Sha256));
+        }
     }
 
     mod invalid {
@@ -39,5 +47,13 @@ mod from_hex {
                 decode::Error::InvalidHexEncodingLength(41)
             ));
         }
+
+        #[test]
+        fn between_sha1_and_sha256() {
+            assert!(matches!(
+                ObjectId::from_hex(b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa").unwrap_err(),
+                decode::Error::InvalidHexEncodingLength(63)
+            ));
+        }
     }
 }
@@ -28,11 +28,11 @@ pub fn message<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(i: &'a [u8]
 pub fn commit<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
     i: &'a [u8],
 ) -> IResult<&'a [u8], CommitRef<'_>, E> {
-    let (i, tree) = context("tree <40 lowercase hex char>", |i| {
+    let (i, tree) = context("tree <40 or 64 lowercase hex char>", |i| {
         parse::header_field(i, b"tree", parse::hex_hash)
     })(i)?;
     let (i, parents) = context(
-        "zero or more 'parent <40 lowercase hex char>'",
+        "zero or more 'parent <40 or 64 lowercase hex char>'",
         many0(|i| parse::header_field(i, b"parent", parse::hex_hash)),
     )(i)?;
     let (i, author) = context("author <signature>", |i| {
@@ -122,7 +122,7 @@ impl<'a> CommitRefIter<'a> {
         use State::*;
         Ok(match state {
             Tree => {
-                let (i, tree) = context("tree <40 lowercase hex char>", |i| {
+                let (i, tree) = context("tree <40 or 64 lowercase hex char>", |i| {
                     parse::header_field(i, b"tree", parse::hex_hash)
                 })(i)?;
                 *state = State::Parents;
@@ -135,7 +135,7 @@ impl<'a> CommitRefIter<'a> {
             }
             Parents => {
                 let (i, parent) = context(
-                    "commit <40 lowercase hex char>",
+                    "commit <40 or 64 lowercase hex char>",
                     opt(|i| parse::header_field(i, b"parent", parse::hex_hash)),
                 )(i)?;
                 match parent {
@@ -3,9 +3,21 @@
 use crate::{BlobRef, CommitRef, CommitRefIter, Data, Kind, ObjectRef, TagRef, TagRefIter, TreeRef, TreeRefIter};
 
 impl<'a> Data<'a> {
-    /// Constructs a new data object from `kind` and `data`.
+    /// Constructs a new data object from `kind` and `data`, assuming that object ids within it are Sha1 hashes.
+    ///
+    /// Use [`with_object_hash()`][Data::with_object_hash()] to change that.
     pub fn new(kind: Kind, data: &'a [u8]) -> Data<'a> {
-        Data { kind, data }
+        Data {
+            kind,
+            data,
+            object_hash: git_hash::Kind::Sha1,
+        }
+    }
+
+    /// Set the kind of hash used by object ids within our data to `object_hash`.
+    pub fn with_object_hash(mut self, object_hash: git_hash::Kind) -> Self {
+        self.object_hash = object_hash;
+        self
     }
     /// Decodes the data in the backing slice into a [`ObjectRef`], allowing to access all of its data
     /// conveniently. The cost of parsing an object is negligible.
@@ -14,7 +26,7 @@ impl<'a> Data<'a> {
     /// using [`crate::ObjectRef::into_owned()`].
     pub fn decode(&self) -> Result<ObjectRef<'a>, crate::decode::Error> {
         Ok(match self.kind {
-            Kind::Tree => ObjectRef::Tree(TreeRef::from_bytes(self.data)?),
+            Kind::Tree => ObjectRef::Tree(TreeRef::from_bytes_with_object_hash(self.data, self.object_hash)?),
             Kind::Blob => ObjectRef::Blob(BlobRef { data: self.data }),
             Kind::Commit => ObjectRef::Commit(CommitRef::from_bytes(self.data)?),
             Kind::Tag => ObjectRef::Tag(TagRef::from_bytes(self.data)?),
@@ -25,7 +37,7 @@ impl<'a> Data<'a> {
     /// `None` if this is not a tree object.
     pub fn try_into_tree_iter(self) -> Option<TreeRefIter<'a>> {
         match self.kind {
-            Kind::Tree => Some(TreeRefIter::from_bytes(self.data)),
+            Kind::Tree => Some(TreeRefIter::from_bytes_with_object_hash(self.data, self.object_hash)),
             _ => None,
         }
     }
@@ -61,6 +73,11 @@ pub mod verify {
             desired: git_hash::ObjectId,
             actual: git_hash::ObjectId,
         },
+        #[error("A collision attack was detected while computing the id of object expected to be {desired}")]
+        Collision {
+            desired: git_hash::ObjectId,
+            source: git_features::hash::Collision,
+        },
     }
 
     impl crate::Data<'_> {
@@ -73,7 +90,10 @@ pub mod verify {
             hasher.update(&crate::encode::loose_header(self.kind, self.data.len()));
             hasher.update(self.data);
 
-            let actual_id = git_hash::ObjectId::from(hasher.digest());
+            let actual_id = hasher.try_digest().map_err(|err| Error::Collision {
+                desired: desired.into(),
+                source: err,
+            })?;
             if desired != actual_id {
                 return Err(Error::ChecksumMismatch {
                     desired: desired.into(),
@@ -202,6 +202,8 @@ impl<'a> ObjectRef<'a> {
     }
 
     /// Deserialize an object of `kind` from the given `data`.
+    ///
+    /// Note that trees are assumed to contain Sha1 ids, use [`Data::decode()`][crate::Data::decode()] for other hashes.
     pub fn from_bytes(kind: Kind, data: &'a [u8]) -> Result<ObjectRef<'a>, crate::decode::Error> {
         Ok(match kind {
             Kind::Tree => ObjectRef::Tree(TreeRef::from_bytes(data)?),
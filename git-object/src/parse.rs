@@ -66,12 +66,18 @@ fn is_hex_digit_lc(b: u8) -> bool {
 }
 
 pub fn hex_hash<'a, E: ParseError<&'a [u8]>>(i: &'a [u8]) -> IResult<&'a [u8], &'a BStr, E> {
-    take_while_m_n(
+    let (rest, hex) = take_while_m_n(
         git_hash::Kind::shortest().len_in_hex(),
         git_hash::Kind::longest().len_in_hex(),
         is_hex_digit_lc,
-    )(i)
-    .map(|(i, hex)| (i, hex.as_bstr()))
+    )(i)?;
+    if !git_hash::Kind::all().iter().any(|kind| kind.len_in_hex() == hex.len()) {
+        return Err(nom::Err::Error(E::from_error_kind(
+            i,
+            nom::error::ErrorKind::LengthValue,
+        )));
+    }
+    Ok((rest, hex.as_bstr()))
 }
 
 pub(crate) fn signature<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
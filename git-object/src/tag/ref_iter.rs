@@ -66,7 +66,7 @@ impl<'a> TagRefIter<'a> {
         use State::*;
         Ok(match state {
             Target => {
-                let (i, target) = context("object <40 lowercase hex char>", |i| {
+                let (i, target) = context("object <40 or 64 lowercase hex char>", |i| {
                     parse::header_field(i, b"object", parse::hex_hash)
                 })(i)?;
                 *state = TargetKind;
@@ -24,6 +24,23 @@ fn unsigned() -> crate::Result {
     Ok(())
 }
 
+#[test]
+fn unsigned_sha256() -> crate::Result {
+    assert_eq!(
+        CommitRef::from_bytes(&fixture_bytes("commit", "unsigned-sha256.txt"))?,
+        CommitRef {
+            tree: b"48701b726da2a7c88c9f7fb259f46749c1c037f71bc2a7a0f751ce2b6a41aa2e".as_bstr(),
+            parents: SmallVec::default(),
+            author: signature(1000000000),
+            committer: signature(1000000000),
+            encoding: None,
+            message: b"sha256\n".as_bstr(),
+            extra_headers: vec![]
+        }
+    );
+    Ok(())
+}
+
 #[test]
 fn whitespace() -> crate::Result {
     assert_eq!(
@@ -53,6 +53,30 @@ mod iter {
         );
         Ok(())
     }
+
+    #[test]
+    fn everything_sha256() -> crate::Result {
+        assert_eq!(
+            TreeRefIter::from_bytes_with_object_hash(
+                &fixture_bytes("tree", "everything-sha256.tree"),
+                git_hash::Kind::Sha256
+            )
+            .collect::<Result<Vec<_>, _>>()?,
+            vec![
+                EntryRef {
+                    mode: tree::EntryMode::BlobExecutable,
+                    filename: b"file".as_bstr(),
+                    oid: &hex_to_id("473a0f4c3be8a93681a267e3b1e9a7dcda1185436fe141f7749120a303721813")
+                },
+                EntryRef {
+                    mode: tree::EntryMode::Tree,
+                    filename: b"subdir".as_bstr(),
+                    oid: &hex_to_id("001cc91be706c78c4f70ff2e0d4034fb366e9742e04bcd0754666eeb9687b95a")
+                },
+            ]
+        );
+        Ok(())
+    }
 }
 
 mod from_bytes {
@@ -32,8 +32,8 @@ fn fixture_bytes(path: &str) -> Vec<u8> {
 fn size_in_memory() {
     let actual = std::mem::size_of::<git_object::Object>();
     assert!(
-        actual <= 264,
-        "{} <= 264: Prevent unexpected growth of what should be lightweight objects",
+        actual <= 288,
+        "{} <= 288: Prevent unexpected growth of what should be lightweight objects",
         actual
     )
 }
@@ -196,7 +196,10 @@ mod impls {
         ) -> Result<Option<(Data<'a>, Option<git_pack::data::entry::Location>)>, Self::Error> {
             if let Some(mut obj_cache) = self.object_cache.as_ref().map(|rc| rc.borrow_mut()) {
                 if let Some(kind) = obj_cache.get(&id.as_ref().to_owned(), buffer) {
-                    return Ok(Some((Data::new(kind, buffer), None)));
+                    return Ok(Some((
+                        Data::new(kind, buffer).with_object_hash(id.as_ref().kind()),
+                        None,
+                    )));
                 }
             }
             let possibly_obj = self.inner.try_find_cached(id.as_ref(), buffer, pack_cache)?;
@@ -208,7 +211,15 @@ mod impls {
             Ok(possibly_obj)
         }
 
-        fn location_by_oid(&self, id: impl AsRef<oid>, buf: &mut Vec<u8>) -> Option<git_pack::data::entry::Location> {
+        fn try_header(&self, id: impl AsRef<oid>, buffer: &mut Vec<u8>) -> Result<Option<(Kind, usize)>, Self::Error> {
+            self.inner.try_header(id, buffer)
+        }
+
+        fn location_by_oid(
+            &self,
+            id: impl AsRef<oid>,
+            buf: &mut Vec<u8>,
+        ) -> Result<Option<git_pack::data::entry::Location>, Self::Error> {
             self.inner.location_by_oid(id, buf)
         }
 
@@ -216,7 +227,7 @@ mod impls {
             self.inner.pack_offsets_and_oid(pack_id)
         }
 
-        fn entry_by_location(&self, location: &Location) -> Option<git_pack::find::Entry> {
+        fn entry_by_location(&self, location: &Location) -> Result<Option<git_pack::find::Entry>, Self::Error> {
             self.inner.entry_by_location(location)
         }
     }
@@ -131,6 +131,8 @@ pub struct Store {
     use_multi_pack_index: bool,
     /// The hash kind to use for some operations
     object_hash: git_hash::Kind,
+    /// How to access the data of packs once they are loaded.
+    pack_mapping: git_pack::data::Mapping,
 }
 
 /// Create a new cached handle to the object store with support for additional options.
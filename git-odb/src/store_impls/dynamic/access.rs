@@ -17,6 +17,11 @@ impl Store {
         self.use_multi_pack_index
     }
 
+    /// How the data of packs is accessed once they are loaded.
+    pub fn pack_mapping(&self) -> git_pack::data::Mapping {
+        self.pack_mapping
+    }
+
     /// An iterator over replacements from object-ids `X` to `X-replaced` as `(X, X-replaced)`, sorted by the original id `X`.
     pub fn replacements(&self) -> impl Iterator<Item = (git_hash::ObjectId, git_hash::ObjectId)> + '_ {
         self.replacements.iter().cloned()
@@ -52,6 +52,13 @@ mod error {
             /// The original object to lookup
             id: git_hash::ObjectId,
         },
+        #[error("The hook to obtain the missing object {} failed", .id)]
+        MissingObjectHook {
+            #[source]
+            err: Box<dyn std::error::Error + Send + Sync + 'static>,
+            /// The id of the missing object
+            id: git_hash::ObjectId,
+        },
     }
 
     #[derive(Copy, Clone)]
@@ -268,22 +275,29 @@ where
                                 }
                             },
                         };
-                        let entry = pack.entry(pack_offset);
+                        let entry = pack
+                            .entry(pack_offset)
+                            .map_err(git_pack::data::decode_entry::Error::from)?;
                         let header_size = entry.header_size();
-                        let res = match pack.decode_entry(
-                            entry,
-                            buffer,
-                            |id, _out| {
-                                index_file
-                                    .pack_offset_by_id(id)
-                                    .map(|pack_offset| git_pack::data::ResolvedBase::InPack(pack.entry(pack_offset)))
-                            },
-                            pack_cache,
-                        ) {
+                        // Base entries which can't be read must not be mistaken for bases which aren't in this pack.
+                        let base_entry_error = std::cell::RefCell::new(None::<std::io::Error>);
+                        let resolve_in_pack = |id: &oid| {
+                            index_file
+                                .pack_offset_by_id(id)
+                                .and_then(|pack_offset| match pack.entry(pack_offset) {
+                                    Ok(entry) => Some(git_pack::data::ResolvedBase::InPack(entry)),
+                                    Err(err) => {
+                                        *base_entry_error.borrow_mut() = Some(err);
+                                        None
+                                    }
+                                })
+                        };
+                        let res = match pack.decode_entry(entry, buffer, |id, _out| resolve_in_pack(id), pack_cache) {
                             Ok(r) => Ok((
                                 git_object::Data {
                                     kind: r.kind,
                                     data: buffer.as_slice(),
+                                    object_hash: id.kind(),
                                 },
                                 Some(git_pack::data::entry::Location {
                                     pack_id: pack.id,
@@ -291,6 +305,11 @@ where
                                     entry_size: r.compressed_size + header_size,
                                 }),
                             )),
+                            Err(git_pack::data::decode_entry::Error::DeltaBaseUnresolved(_))
+                                if base_entry_error.borrow().is_some() =>
+                            {
+                                Err(base_entry_error.take().expect("just checked").into())
+                            }
                             Err(git_pack::data::decode_entry::Error::DeltaBaseUnresolved(base_id)) => {
                                 // Only with multi-pack indices it's allowed to jump to refer to other packs within this
                                 // multi-pack. Otherwise this would constitute a thin pack which is only allowed in transit.
@@ -352,35 +371,39 @@ where
                                 let pack = possibly_pack
                                     .as_ref()
                                     .expect("pack to still be available like just now");
-                                let entry = pack.entry(pack_offset);
+                                let entry = pack
+                                    .entry(pack_offset)
+                                    .map_err(git_pack::data::decode_entry::Error::from)?;
                                 let header_size = entry.header_size();
                                 pack.decode_entry(
                                     entry,
                                     buffer,
-                                    |id, out| {
-                                        index_file
-                                            .pack_offset_by_id(id)
-                                            .map(|pack_offset| {
-                                                git_pack::data::ResolvedBase::InPack(pack.entry(pack_offset))
-                                            })
-                                            .or_else(|| {
-                                                (id == base_id).then(|| {
-                                                    out.resize(buf.len(), 0);
-                                                    out.copy_from_slice(buf.as_slice());
-                                                    git_pack::data::ResolvedBase::OutOfPack {
-                                                        kind: obj_kind,
-                                                        end: out.len(),
-                                                    }
-                                                })
-                                            })
+                                    |id, out| match index_file.pack_offset_by_id(id) {
+                                        Some(pack_offset) => match pack.entry(pack_offset) {
+                                            Ok(entry) => Some(git_pack::data::ResolvedBase::InPack(entry)),
+                                            Err(err) => {
+                                                *base_entry_error.borrow_mut() = Some(err);
+                                                None
+                                            }
+                                        },
+                                        None => (id == base_id).then(|| {
+                                            out.resize(buf.len(), 0);
+                                            out.copy_from_slice(buf.as_slice());
+                                            git_pack::data::ResolvedBase::OutOfPack {
+                                                kind: obj_kind,
+                                                end: out.len(),
+                                            }
+                                        }),
                                     },
                                     pack_cache,
                                 )
+                                .map_err(|err| base_entry_error.take().map_or(err, Into::into))
                                 .map(move |r| {
                                     (
                                         git_object::Data {
                                             kind: r.kind,
                                             data: buffer.as_slice(),
+                                            object_hash: id.kind(),
                                         },
                                         Some(git_pack::data::entry::Location {
                                             pack_id: pack.id,
@@ -471,10 +494,59 @@ where
     ) -> Result<Option<(git_object::Data<'a>, Option<Location>)>, Self::Error> {
         let id = id.as_ref();
         let mut snapshot = self.snapshot.borrow_mut();
+        let hook = match self.missing_object_hook.as_ref() {
+            Some(hook) => hook,
+            None => return self.try_find_cached_inner(id, buffer, pack_cache, &mut snapshot, None),
+        };
+        // The object data always spans the entire buffer, so we can recreate it to avoid borrowing `buffer` on all paths.
+        let found = self
+            .try_find_cached_inner(id, buffer, pack_cache, &mut snapshot, None)?
+            .map(|(obj, location)| (obj.kind, location));
+        if let Some((kind, location)) = found {
+            return Ok(Some((
+                git_object::Data {
+                    kind,
+                    data: buffer.as_slice(),
+                    object_hash: id.kind(),
+                },
+                location,
+            )));
+        }
+
+        drop(snapshot);
+        let obtained = hook(id).map_err(|err| Error::MissingObjectHook { err, id: id.to_owned() })?;
+        if !obtained {
+            return Ok(None);
+        }
+        let mut snapshot = self.snapshot.borrow_mut();
         self.try_find_cached_inner(id, buffer, pack_cache, &mut snapshot, None)
     }
 
-    fn location_by_oid(&self, id: impl AsRef<oid>, buf: &mut Vec<u8>) -> Option<Location> {
+    /// Loose objects only have their header decompressed, while all other objects are decoded entirely.
+    fn try_header(
+        &self,
+        id: impl AsRef<oid>,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<(git_object::Kind, usize)>, Self::Error> {
+        let id = id.as_ref();
+        let is_replaced = !self.ignore_replacements
+            && self
+                .store
+                .replacements
+                .binary_search_by(|(map_this, _)| map_this.as_ref().cmp(id))
+                .is_ok();
+        if !is_replaced {
+            let snapshot = self.snapshot.borrow();
+            for lodb in snapshot.loose_dbs.iter() {
+                if let Some(header) = lodb.try_header(id)? {
+                    return Ok(Some(header));
+                }
+            }
+        }
+        Ok(git_pack::Find::try_find(self, id, buffer)?.map(|(obj, _location)| (obj.kind, obj.data.len())))
+    }
+
+    fn location_by_oid(&self, id: impl AsRef<oid>, buf: &mut Vec<u8>) -> Result<Option<Location>, Self::Error> {
         assert!(
             matches!(self.token.as_ref(), Some(handle::Mode::KeepDeletedPacksAvailable)),
             "BUG: handle must be configured to `prevent_pack_unload()` before using this method"
@@ -496,14 +568,14 @@ where
                     {
                         let pack = match possibly_pack {
                             Some(pack) => pack,
-                            None => match self.store.load_pack(pack_id, marker).ok()? {
+                            None => match self.store.load_pack(pack_id, marker)? {
                                 Some(pack) => {
                                     *possibly_pack = Some(pack);
                                     possibly_pack.as_deref().expect("just put it in")
                                 }
                                 None => {
                                     // The pack wasn't available anymore so we are supposed to try another round with a fresh index
-                                    match self.store.load_one_index(self.refresh, snapshot.marker).ok()? {
+                                    match self.store.load_one_index(self.refresh, snapshot.marker)? {
                                         Some(new_snapshot) => {
                                             *snapshot = new_snapshot;
                                             self.clear_cache();
@@ -513,39 +585,40 @@ where
                                             // nothing new in the index, kind of unexpected to not have a pack but to also
                                             // to have no new index yet. We set the new index before removing any slots, so
                                             // this should be observable.
-                                            return None;
+                                            return Ok(None);
                                         }
                                     }
                                 }
                             },
                         };
-                        let entry = pack.entry(pack_offset);
+                        let entry = pack
+                            .entry(pack_offset)
+                            .map_err(git_pack::data::decode_entry::Error::from)?;
 
                         buf.resize(entry.decompressed_size.try_into().expect("representable size"), 0);
                         assert_eq!(pack.id, pack_id.to_intrinsic_pack_id(), "both ids must always match");
 
-                        let res = pack.decompress_entry(&entry, buf).ok().map(|entry_size_past_header| {
-                            git_pack::data::entry::Location {
-                                pack_id: pack.id,
-                                pack_offset,
-                                entry_size: entry.header_size() + entry_size_past_header,
-                            }
-                        });
+                        let entry_size_past_header = pack.decompress_entry(&entry, buf)?;
+                        let res = git_pack::data::entry::Location {
+                            pack_id: pack.id,
+                            pack_offset,
+                            entry_size: entry.header_size() + entry_size_past_header,
+                        };
 
                         if idx != 0 {
                             snapshot.indices.swap(0, idx);
                         }
-                        return res;
+                        return Ok(Some(res));
                     }
                 }
             }
 
-            match self.store.load_one_index(self.refresh, snapshot.marker).ok()? {
+            match self.store.load_one_index(self.refresh, snapshot.marker)? {
                 Some(new_snapshot) => {
                     *snapshot = new_snapshot;
                     self.clear_cache();
                 }
-                None => return None,
+                None => return Ok(None),
             }
         }
     }
@@ -576,7 +649,7 @@ where
         }
     }
 
-    fn entry_by_location(&self, location: &Location) -> Option<git_pack::find::Entry> {
+    fn entry_by_location(&self, location: &Location) -> Result<Option<git_pack::find::Entry>, Self::Error> {
         assert!(
             matches!(self.token.as_ref(), Some(handle::Mode::KeepDeletedPacksAvailable)),
             "BUG: handle must be configured to `prevent_pack_unload()` before using this method"
@@ -591,19 +664,21 @@ where
                         let pack = match possibly_pack {
                             Some(pack) => pack,
                             None => {
-                                let pack = self.store.load_pack(pack_id, marker).ok()?.expect(
+                                let pack = self.store.load_pack(pack_id, marker)?.expect(
                                 "BUG: pack must exist from previous call to location_by_oid() and must not be unloaded",
                             );
                                 *possibly_pack = Some(pack);
                                 possibly_pack.as_deref().expect("just put it in")
                             }
                         };
-                        return pack
-                            .entry_slice(location.entry_range(location.pack_offset))
-                            .map(|data| git_pack::find::Entry {
-                                data: data.to_owned(),
-                                version: pack.version(),
-                            });
+                        let range = location.entry_range(location.pack_offset);
+                        let mut data = vec![0; (range.end - range.start) as usize];
+                        pack.entry_slice(range, &mut data)
+                            .map_err(git_pack::data::decode_entry::Error::from)?;
+                        return Ok(Some(git_pack::find::Entry {
+                            data,
+                            version: pack.version(),
+                        }));
                     }
                 }
             }
@@ -151,9 +151,21 @@ pub(crate) mod index_lookup {
                 handle::SingleOrMultiIndex::Single { index, .. } => {
                     index.lookup_prefix(prefix, candidate_entries.as_mut())
                 }
-                handle::SingleOrMultiIndex::Multi { index, .. } => {
-                    index.lookup_prefix(prefix, candidate_entries.as_mut())
-                }
+                handle::SingleOrMultiIndex::Multi { index, .. } => match candidates {
+                    Some(candidates) => {
+                        // Candidates are only contiguous within a layer, so each one has to be visited.
+                        let mut res = None;
+                        for layer in index.layers() {
+                            let mut entries = 0..0;
+                            if let Some(layer_res) = layer.lookup_prefix_in_layer(prefix, Some(&mut entries)) {
+                                res = if res.is_some() { Some(Err(())) } else { Some(layer_res) };
+                                candidates.extend(entries.map(|entry| index.oid_at_index(entry).to_owned()));
+                            }
+                        }
+                        return res.map(|res| res.map(|entry_index| index.oid_at_index(entry_index).to_owned()));
+                    }
+                    None => index.lookup_prefix(prefix, None),
+                },
             }?;
 
             if let Some((candidates, entries)) = candidates.zip(candidate_entries) {
@@ -260,6 +272,7 @@ impl super::Store {
             store: self.clone(),
             refresh: RefreshMode::default(),
             ignore_replacements: false,
+            missing_object_hook: None,
             token: Some(token),
             snapshot: RefCell::new(self.collect_snapshot()),
             max_recursion_depth: Self::INITIAL_MAX_RECURSION_DEPTH,
@@ -276,6 +289,7 @@ impl super::Store {
             store: self.clone(),
             refresh: Default::default(),
             ignore_replacements: false,
+            missing_object_hook: None,
             token: Some(token),
             snapshot: RefCell::new(self.collect_snapshot()),
             max_recursion_depth: Self::INITIAL_MAX_RECURSION_DEPTH,
@@ -330,6 +344,32 @@ where
     pub fn refresh_mode(&mut self) -> RefreshMode {
         self.refresh
     }
+
+    /// Set `hook` to be called with the id of each object that couldn't be found, to allow it to be obtained from elsewhere,
+    /// like a promisor remote of a partial clone.
+    ///
+    /// If the hook returns `true`, the object database will be refreshed and the lookup is retried once.
+    /// Note that only [`try_find()`][git_pack::Find::try_find()] and its variants call the hook, but
+    /// [`contains()`][git_pack::Find::contains()] doesn't.
+    ///
+    /// Errors returned by the hook are returned by `try_find()` as well, so with a hook that fetches objects over the
+    /// network, looking up an object that is absent may block on network IO and fail with an `Err` instead of returning `Ok(None)`.
+    pub fn set_missing_object_hook(
+        &mut self,
+        hook: impl Fn(&oid) -> Result<bool, Box<dyn std::error::Error + Send + Sync + 'static>> + Send + Sync + 'static,
+    ) {
+        self.missing_object_hook = Some(Arc::new(hook));
+    }
+
+    /// Remove the hook to obtain missing objects, if one was set.
+    pub fn unset_missing_object_hook(&mut self) {
+        self.missing_object_hook = None;
+    }
+
+    /// Return `true` if a hook to obtain missing objects is set.
+    pub fn has_missing_object_hook(&self) -> bool {
+        self.missing_object_hook.is_some()
+    }
 }
 
 impl<S> Drop for super::Handle<S>
@@ -355,6 +395,7 @@ impl TryFrom<&super::Store> for super::Store {
                 object_hash: Default::default(),
                 use_multi_pack_index: false,
                 current_dir: s.current_dir.clone().into(),
+                pack_mapping: s.pack_mapping,
             },
         )
     }
@@ -367,6 +408,7 @@ impl super::Handle<Rc<super::Store>> {
         let mut cache = store.to_handle_arc();
         cache.refresh = self.refresh;
         cache.max_recursion_depth = self.max_recursion_depth;
+        cache.missing_object_hook = self.missing_object_hook.clone();
         Ok(cache)
     }
 }
@@ -387,6 +429,7 @@ where
             store: self.store.clone(),
             refresh: self.refresh,
             ignore_replacements: self.ignore_replacements,
+            missing_object_hook: self.missing_object_hook.clone(),
             token: {
                 let token = self.store.register_handle();
                 match self.token.as_ref().expect("token is always set here ") {
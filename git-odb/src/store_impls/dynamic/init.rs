@@ -19,6 +19,8 @@ pub struct Options {
     /// The current directory of the process at the time of instantiation.
     /// If unset, it will be retrieved using `std::env::current_dir()`.
     pub current_dir: Option<std::path::PathBuf>,
+    /// Determines how the data of packs is accessed, either by mapping it entirely or by reading it through windows.
+    pub pack_mapping: git_pack::data::Mapping,
 }
 
 impl Default for Options {
@@ -28,6 +30,7 @@ impl Default for Options {
             object_hash: Default::default(),
             use_multi_pack_index: true,
             current_dir: None,
+            pack_mapping: Default::default(),
         }
     }
 }
@@ -77,6 +80,7 @@ impl Store {
             object_hash,
             use_multi_pack_index,
             current_dir,
+            pack_mapping,
         }: Options,
     ) -> std::io::Result<Self> {
         let objects_dir = objects_dir.into();
@@ -118,6 +122,7 @@ impl Store {
             index: ArcSwap::new(Arc::new(SlotMapIndex::default())),
             use_multi_pack_index,
             object_hash,
+            pack_mapping,
             num_handles_stable: Default::default(),
             num_handles_unstable: Default::default(),
             num_disk_state_consolidation: Default::default(),
@@ -437,17 +437,29 @@ impl super::Store {
                 .collect::<Result<Vec<_>, _>>()?;
 
             let multi_index_info = multi_pack_index_object_hash.and_then(|hash| {
-                indices.iter().find_map(|(p, a, b)| {
-                    is_multipack_index(p)
-                        .then(|| {
-                            // we always open the multi-pack here to be able to remove indices
-                            git_pack::multi_index::File::at(p)
-                                .ok()
-                                .filter(|midx| midx.object_hash() == hash)
-                                .map(|midx| (midx, *a, *b))
-                        })
-                        .flatten()
-                })
+                indices
+                    .iter()
+                    .find_map(|(p, a, b)| {
+                        is_multipack_index(p)
+                            .then(|| {
+                                // we always open the multi-pack here to be able to remove indices
+                                git_pack::multi_index::File::at(p)
+                                    .ok()
+                                    .filter(|midx| midx.object_hash() == hash)
+                                    .map(|midx| (midx, *a, *b))
+                            })
+                            .flatten()
+                    })
+                    .or_else(|| {
+                        // like git, only use a chain of multi-pack indices if there is no standalone one
+                        let chain_path = git_pack::multi_index::chain::path(&packs);
+                        let md = std::fs::metadata(&chain_path).ok().filter(|md| md.is_file())?;
+                        let mtime = md.modified().ok()?;
+                        git_pack::multi_index::File::at(&chain_path)
+                            .ok()
+                            .filter(|midx| midx.object_hash() == hash)
+                            .map(|midx| (midx, mtime, md.len()))
+                    })
             });
             if let Some((multi_index, mtime, flen)) = multi_index_info {
                 let index_names_in_multi_index: Vec<_> =
@@ -1,8 +1,13 @@
 //! The standard object store which should fit all needs.
-use std::{cell::RefCell, ops::Deref};
+use std::{cell::RefCell, ops::Deref, sync::Arc};
 
 use crate::Store;
 
+/// A function to obtain objects that couldn't be found, for example by fetching them from a promisor remote, returning `true`
+/// if the object with the given id was made available in the object database.
+pub type MissingObjectHookFn =
+    dyn Fn(&git_hash::oid) -> Result<bool, Box<dyn std::error::Error + Send + Sync + 'static>> + Send + Sync + 'static;
+
 /// This effectively acts like a handle but exists to be usable from the actual `crate::Handle` implementation which adds caches on top.
 /// Each store is quickly cloned and contains thread-local state for shared packs.
 pub struct Handle<S>
@@ -22,6 +27,9 @@ where
     /// If true, replacements will not be performed even if these are available.
     pub ignore_replacements: bool,
 
+    /// Called when an object couldn't be found to give it a chance to be obtained from elsewhere.
+    pub(crate) missing_object_hook: Option<Arc<MissingObjectHookFn>>,
+
     pub(crate) token: Option<handle::Mode>,
     snapshot: RefCell<load_index::Snapshot>,
     packed_object_count: RefCell<Option<u64>>,
@@ -179,5 +187,35 @@ pub mod structure {
             }
             Ok(res)
         }
+
+        /// Return the paths to the indices of all packs that were received from a promisor remote, as indicated by a `.promisor`
+        /// file next to the pack data file.
+        ///
+        /// Objects in these packs may refer to objects that aren't present locally as they are expected to be obtainable from
+        /// the remote they were received from, as it's the case in partial clones.
+        pub fn promisor_pack_indices(&self) -> Result<Vec<PathBuf>, load_index::Error> {
+            let index = self.index.load();
+            if !index.is_initialized() {
+                self.consolidate_with_disk_state(true, false /*load one new index*/)?;
+            }
+            let index = self.index.load();
+            let is_promisor_pack = |data_path: &std::path::Path| data_path.with_extension("promisor").is_file();
+            let mut res = Vec::new();
+            for slot in index.slot_indices.iter().map(|idx| &self.files[*idx]) {
+                let files = slot.files.load();
+                match &**files {
+                    Some(IndexAndPacks::Index(b)) if is_promisor_pack(b.data.path()) => res.push(b.index.path().into()),
+                    Some(IndexAndPacks::MultiIndex(b)) => res.extend(
+                        b.data
+                            .iter()
+                            .map(|data| data.path())
+                            .filter(|data_path| is_promisor_pack(data_path))
+                            .map(|data_path| data_path.with_extension("idx")),
+                    ),
+                    Some(IndexAndPacks::Index(_)) | None => {}
+                }
+            }
+            Ok(res)
+        }
     }
 }
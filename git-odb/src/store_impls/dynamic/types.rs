@@ -365,7 +365,7 @@ impl IndexAndPacks {
     fn index_names_to_pack_paths(
         multi_index: &git_pack::multi_index::File,
     ) -> Vec<OnDiskFile<Arc<git_pack::data::File>>> {
-        let parent_dir = multi_index.path().parent().expect("parent present");
+        let parent_dir = multi_index.pack_dir();
         let data = multi_index
             .index_names()
             .iter()
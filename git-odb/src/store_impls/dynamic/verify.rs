@@ -145,7 +145,7 @@ impl super::Store {
                     let data = match bundle.data.loaded() {
                         Some(pack) => pack.deref(),
                         None => {
-                            pack = pack::data::File::at(bundle.data.path(), self.object_hash)?;
+                            pack = pack::data::File::at_opts(bundle.data.path(), self.object_hash, self.pack_mapping)?;
                             &pack
                         }
                     };
@@ -2,7 +2,7 @@ use std::{cmp::Ordering, collections::HashSet, fs, io::Read, path::PathBuf};
 
 use git_features::zlib;
 
-use crate::store_impls::loose::{hash_path, Store, HEADER_READ_UNCOMPRESSED_BYTES};
+use crate::store_impls::loose::{hash_path, Store, HEADER_READ_COMPRESSED_BYTES, HEADER_READ_UNCOMPRESSED_BYTES};
 
 /// Returned by [`Store::try_find()`]
 #[derive(thiserror::Error, Debug)]
@@ -124,6 +124,48 @@ impl Store {
         }
     }
 
+    /// Return the kind and size of the object identified by the given [`ObjectId`][git_hash::ObjectId] if present in this
+    /// database, decompressing only its header.
+    ///
+    /// Returns `Err` if there was an error locating or reading the object. Returns `Ok<None>` if
+    /// there was no such object.
+    pub fn try_header(&self, id: impl AsRef<git_hash::oid>) -> Result<Option<(git_object::Kind, usize)>, Error> {
+        let id = id.as_ref();
+        debug_assert_eq!(self.object_hash, id.kind());
+        let path = hash_path(id, self.path.clone());
+        let istream = match fs::File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(Error::Io {
+                    source: err,
+                    action: Self::OPEN_ACTION,
+                    path,
+                })
+            }
+        };
+
+        let mut input = Vec::with_capacity(HEADER_READ_COMPRESSED_BYTES);
+        istream
+            .take(HEADER_READ_COMPRESSED_BYTES as u64)
+            .read_to_end(&mut input)
+            .map_err(|e| Error::Io {
+                source: e,
+                action: "read",
+                path: path.to_owned(),
+            })?;
+        let mut output = [0u8; HEADER_READ_UNCOMPRESSED_BYTES];
+        let (_status, _consumed_in, consumed_out) =
+            zlib::Inflate::default()
+                .once(&input, &mut output)
+                .map_err(|e| Error::DecompressFile {
+                    source: e,
+                    path: path.to_owned(),
+                })?;
+        let (kind, size, _header_size) = git_object::decode::loose_header(&output[..consumed_out])?;
+        Ok(Some((kind, size)))
+    }
+
     fn find_inner<'a>(&self, id: &git_hash::oid, buf: &'a mut Vec<u8>) -> Result<git_object::Data<'a>, Error> {
         let path = hash_path(id, self.path.clone());
 
@@ -195,6 +237,10 @@ impl Store {
             buf.copy_within(decompressed_start + header_size.., 0);
         }
         buf.resize(size, 0);
-        Ok(git_object::Data { kind, data: buf })
+        Ok(git_object::Data {
+            kind,
+            data: buf,
+            object_hash: self.object_hash,
+        })
     }
 }
@@ -1,5 +1,7 @@
 //! An object database storing each object in a zlib compressed file with its hash in the path
 const HEADER_READ_UNCOMPRESSED_BYTES: usize = 512;
+/// Enough compressed bytes to decompress the header of a loose object, even if the stream begins with its huffman tables.
+const HEADER_READ_COMPRESSED_BYTES: usize = 1024;
 use std::path::{Path, PathBuf};
 
 use git_features::fs;
@@ -37,6 +39,11 @@ impl Store {
     pub fn object_hash(&self) -> git_hash::Kind {
         self.object_hash
     }
+
+    /// Return the path at which the loose object with `id` is stored, whether it exists or not.
+    pub fn object_path(&self, id: &git_hash::oid) -> PathBuf {
+        hash_path(id, self.path.clone())
+    }
 }
 
 fn hash_path(id: &git_hash::oid, mut root: PathBuf) -> PathBuf {
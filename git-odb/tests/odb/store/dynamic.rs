@@ -27,6 +27,7 @@ fn db_with_all_object_sources() -> crate::Result<(git_odb::Handle, tempfile::Tem
         &std::sync::atomic::AtomicBool::default(),
         git_odb::pack::multi_index::write::Options {
             object_hash: git_hash::Kind::Sha1,
+            base: None,
         },
     )?;
     Ok((git_odb::at(objects_dir.path())?, objects_dir))
@@ -158,7 +159,7 @@ fn multi_index_keep_open() -> crate::Result {
     let mut buf = Vec::new();
     use git_pack::Find;
     let location = stable_handle
-        .location_by_oid(oid, &mut buf)
+        .location_by_oid(oid, &mut buf)?
         .expect("oid exists and is packed");
 
     let non_existing_to_trigger_refresh = hex_to_id("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa");
@@ -186,7 +187,7 @@ fn multi_index_keep_open() -> crate::Result {
     );
 
     assert!(
-        git_odb::pack::Find::entry_by_location(&stable_handle, &location).is_some(),
+        git_odb::pack::Find::entry_by_location(&stable_handle, &location)?.is_some(),
         "the entry can still be found even though the location is invalid"
     );
     assert_eq!(handle.store_ref().structure()?.len(), 2);
@@ -671,6 +672,102 @@ fn missing_objects_triggers_everything_is_loaded() {
     );
 }
 
+#[test]
+fn missing_object_hook_is_called_and_may_provide_objects() -> crate::Result {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let tmp = git_testtools::tempfile::tempdir()?;
+    std::fs::create_dir(tmp.path().join("pack"))?;
+    let mut handle = git_odb::at(tmp.path())?;
+    let calls = Arc::new(AtomicUsize::default());
+    handle.set_missing_object_hook({
+        let calls = Arc::clone(&calls);
+        let pack_dir = tmp.path().join("pack");
+        move |id| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            if id == hex_to_id("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb") {
+                return Err("the remote doesn't have it".into());
+            }
+            if id != hex_to_id("0d9726f3fd2359cda58217724f12af273bf440a6") {
+                return Ok(false);
+            }
+            for extension in ["pack", "idx"] {
+                let name = format!("pack-a2bf8e71d8c18879e499335762dd95119d93d9f1.{}", extension);
+                std::fs::copy(fixture_path("objects/pack").join(&name), pack_dir.join(name))?;
+            }
+            Ok(true)
+        }
+    });
+    assert!(handle.has_missing_object_hook());
+
+    assert!(
+        !handle.contains(hex_to_id("0d9726f3fd2359cda58217724f12af273bf440a6")),
+        "contains() never calls the hook"
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    let mut buf = Vec::new();
+    assert!(handle
+        .try_find(hex_to_id("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"), &mut buf)?
+        .is_none());
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        1,
+        "the hook is called if an object is missing"
+    );
+
+    let err = handle
+        .try_find(hex_to_id("bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"), &mut buf)
+        .unwrap_err();
+    assert!(matches!(err, git_odb::store::find::Error::MissingObjectHook { .. }));
+
+    let obj = handle.find(hex_to_id("0d9726f3fd2359cda58217724f12af273bf440a6"), &mut buf)?;
+    assert_eq!(
+        obj.kind,
+        git_object::Kind::Tree,
+        "the new pack is picked up after the hook obtained it"
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    handle.find(hex_to_id("0d9726f3fd2359cda58217724f12af273bf440a6"), &mut buf)?;
+    assert_eq!(
+        calls.load(Ordering::SeqCst),
+        3,
+        "objects that exist don't call the hook"
+    );
+
+    handle.unset_missing_object_hook();
+    assert!(handle
+        .try_find(hex_to_id("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"), &mut buf)?
+        .is_none());
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    Ok(())
+}
+
+#[test]
+fn promisor_pack_indices_are_those_with_a_promisor_file() -> crate::Result {
+    let tmp = git_testtools::tempfile::tempdir()?;
+    git_testtools::copy_recursively_into_existing_dir(fixture_path("objects"), &tmp)?;
+    let handle = git_odb::at(tmp.path())?;
+    assert!(handle.store_ref().promisor_pack_indices()?.is_empty());
+
+    std::fs::write(
+        tmp.path()
+            .join("pack/pack-a2bf8e71d8c18879e499335762dd95119d93d9f1.promisor"),
+        b"",
+    )?;
+    assert_eq!(
+        handle.store_ref().promisor_pack_indices()?,
+        vec![tmp
+            .path()
+            .join("pack/pack-a2bf8e71d8c18879e499335762dd95119d93d9f1.idx")]
+    );
+    Ok(())
+}
+
 #[test]
 fn iterate_over_a_bunch_of_loose_and_packed_objects() -> crate::Result {
     let (db, _tmp) = db_with_all_object_sources()?;
@@ -687,6 +784,34 @@ fn iterate_over_a_bunch_of_loose_and_packed_objects() -> crate::Result {
     Ok(())
 }
 
+#[test]
+fn windowed_pack_access_finds_the_same_objects() -> crate::Result {
+    let db = db();
+    let windowed = git_odb::at_opts(
+        fixture_path("objects"),
+        Vec::new(),
+        git_odb::store::init::Options {
+            pack_mapping: git_odb::pack::data::Mapping::Windowed {
+                window_size: 512,
+                limit: 2048,
+            },
+            ..Default::default()
+        },
+    )?;
+    let (mut buf, mut windowed_buf) = (Vec::new(), Vec::new());
+    let mut count = 0;
+    for id in db.iter()? {
+        let id = id?;
+        let expected = db.find(id, &mut buf)?;
+        let actual = windowed.find(id, &mut windowed_buf)?;
+        assert_eq!(actual.kind, expected.kind);
+        assert_eq!(actual.data, expected.data, "{} decodes the same", id);
+        count += 1;
+    }
+    assert_eq!(count, 146);
+    Ok(())
+}
+
 #[test]
 fn auto_refresh_with_and_without_id_stability() -> crate::Result {
     let tmp = git_testtools::tempfile::TempDir::new()?;
@@ -769,10 +894,10 @@ fn auto_refresh_with_and_without_id_stability() -> crate::Result {
         let mut stable_handle = handle.clone();
         stable_handle.prevent_pack_unload();
         let location = stable_handle
-            .location_by_oid(hex_to_id("501b297447a8255d3533c6858bb692575cdefaa0"), &mut buf)
+            .location_by_oid(hex_to_id("501b297447a8255d3533c6858bb692575cdefaa0"), &mut buf)?
             .expect("object exists");
         assert!(
-            stable_handle.entry_by_location(&location).is_some(),
+            stable_handle.entry_by_location(&location)?.is_some(),
             "entries can be found by location as the pack is definitely still loaded, the index didn't change"
         );
 
@@ -781,7 +906,7 @@ fn auto_refresh_with_and_without_id_stability() -> crate::Result {
 
         assert!(
             stable_handle
-                .location_by_oid(hex_to_id("4dac9989f96bc5b5b1263b582c08f0c5f0b58542"), &mut buf)
+                .location_by_oid(hex_to_id("4dac9989f96bc5b5b1263b582c08f0c5f0b58542"), &mut buf)?
                 .is_some(),
             "it finds the object in the newly unhidden pack, which also triggers a refresh providing it with new indices"
         );
@@ -802,11 +927,11 @@ fn auto_refresh_with_and_without_id_stability() -> crate::Result {
             "the removed pack is still loaded"
         );
         assert!(
-            stable_handle.entry_by_location(&location).is_some(),
+            stable_handle.entry_by_location(&location)?.is_some(),
             "it finds the old removed location (still loaded) on the old id, it's still cached in the handle, too"
         );
         assert!(
-            stable_handle.clone().entry_by_location(&location).is_some(),
+            stable_handle.clone().entry_by_location(&location)?.is_some(),
             "handles without any internal cache also work"
         );
     }
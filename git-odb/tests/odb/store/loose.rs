@@ -84,6 +84,26 @@ mod contains {
     }
 }
 
+mod try_header {
+    use crate::{hex_to_id, store::loose::ldb};
+
+    #[test]
+    fn kind_and_size_match_the_decoded_object() -> crate::Result {
+        let store = ldb();
+        let mut buf = Vec::new();
+        for oid in store.iter().map(Result::unwrap) {
+            let obj = store.try_find(oid, &mut buf)?.expect("present");
+            assert_eq!(store.try_header(oid)?, Some((obj.kind, obj.data.len())));
+        }
+        assert_eq!(
+            store.try_header(hex_to_id("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"))?,
+            None,
+            "missing objects are no error"
+        );
+        Ok(())
+    }
+}
+
 mod lookup_prefix {
     use std::collections::HashSet;
 
@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use git_bitmap::ewah;
+
+use crate::bitmap::{File, Version};
+
+/// Access methods
+impl File {
+    /// Returns the version of the bitmap file.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+    /// Returns the path from which the bitmap file was loaded.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Returns the kind of hash function used for object ids.
+    pub fn object_hash(&self) -> git_hash::Kind {
+        self.object_hash
+    }
+    /// Returns the checksum of the pack or multi-pack index whose objects are referred to by this bitmap.
+    ///
+    /// It must match [`PackOrder::checksum()`][crate::bitmap::PackOrder::checksum()] of the pack order used with this file.
+    pub fn index_checksum(&self) -> git_hash::ObjectId {
+        self.index_checksum
+    }
+    /// Returns the amount of commits that have a bitmap.
+    pub fn num_commits(&self) -> usize {
+        self.entries.len()
+    }
+    /// Returns an iterator over the entry indices in the pack index or multi-pack index of all commits that have a bitmap.
+    pub fn commit_indices(&self) -> impl Iterator<Item = u32> + '_ {
+        self.entries.iter().map(|e| e.index)
+    }
+    /// Returns the bitmap with a bit set for each object of the given `kind`.
+    pub fn objects_of_kind(&self, kind: git_object::Kind) -> &ewah::Vec {
+        match kind {
+            git_object::Kind::Commit => &self.commits,
+            git_object::Kind::Tree => &self.trees,
+            git_object::Kind::Blob => &self.blobs,
+            git_object::Kind::Tag => &self.tags,
+        }
+    }
+    /// Returns the hash of the path at which the object at `position` was seen when the bitmap was written,
+    /// or `None` if the file doesn't contain a name-hash cache.
+    pub fn name_hash_at(&self, position: u32) -> Option<u32> {
+        self.name_hashes.as_ref()?.get(position as usize).copied()
+    }
+}
+
+impl File {
+    /// Return the uncompressed bitmap of all objects reachable from the commit at entry `index` of the pack index or
+    /// multi-pack index, or `None` if there is no bitmap for this commit.
+    ///
+    /// Bit `n` of the result is set if the object at position `n` in pack order is reachable, with bits stored as
+    /// `words[n / 64] & (1 << (n % 64))`.
+    pub fn commit_bitmap(&self, index: u32) -> Option<Vec<u64>> {
+        let mut entry_pos = *self.entry_by_index.get(&index)?;
+        let mut words = Vec::new();
+        loop {
+            let entry = &self.entries[entry_pos];
+            super::xor(&mut words, &entry.bitmap.to_words());
+            match entry.xor_offset {
+                0 => break,
+                offset => entry_pos -= offset as usize,
+            }
+        }
+        Some(words)
+    }
+}
@@ -0,0 +1,140 @@
+use std::{collections::HashMap, path::Path};
+
+use git_bitmap::ewah;
+
+use crate::bitmap::{Entry, File, Version};
+
+mod error {
+    /// The error returned by [File::at()][super::File::at()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not open bitmap file at '{path}'")]
+        Io {
+            source: std::io::Error,
+            path: std::path::PathBuf,
+        },
+        #[error("{message}")]
+        Corrupt { message: &'static str },
+        #[error("Unsupported bitmap version: {version})")]
+        UnsupportedVersion { version: u16 },
+        #[error("The {kind} bitmap could not be decoded")]
+        Bitmap {
+            kind: &'static str,
+            source: git_bitmap::ewah::decode::Error,
+        },
+        #[error("The bitmap of commit entry {entry} refers to an entry that doesn't exist")]
+        InvalidXorOffset { entry: usize },
+    }
+}
+
+pub use error::Error;
+
+/// Initialization
+impl File {
+    /// Open the bitmap file at the given `path`, expecting object ids of kind `object_hash`.
+    pub fn at(path: impl AsRef<Path>, object_hash: git_hash::Kind) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = crate::mmap::read_only(path).map_err(|source| Error::Io {
+            source,
+            path: path.to_owned(),
+        })?;
+        Self::from_bytes(&data, path, object_hash)
+    }
+
+    fn from_bytes(data: &[u8], path: &Path, object_hash: git_hash::Kind) -> Result<Self, Error> {
+        let hash_len = object_hash.len_in_bytes();
+        if data.len() < Self::HEADER_LEN + hash_len /* pack checksum */ + hash_len
+        /* trailer */
+        {
+            return Err(Error::Corrupt {
+                message: "bitmap file is truncated and too short",
+            });
+        }
+        let (header, data) = data.split_at(Self::HEADER_LEN);
+        let (signature, header) = header.split_at(4);
+        if signature != Self::SIGNATURE {
+            return Err(Error::Corrupt {
+                message: "Invalid signature",
+            });
+        }
+        let (version, header) = header.split_at(2);
+        let version = match u16::from_be_bytes([version[0], version[1]]) {
+            1 => Version::V1,
+            version => return Err(Error::UnsupportedVersion { version }),
+        };
+        let (flags, num_entries) = header.split_at(2);
+        let flags = u16::from_be_bytes([flags[0], flags[1]]);
+        let num_entries = crate::read_u32(num_entries) as usize;
+
+        let (index_checksum, data) = data.split_at(hash_len);
+        let index_checksum = git_hash::ObjectId::from(index_checksum);
+        let data = &data[..data.len() - hash_len];
+
+        let (commits, data) = decode_bitmap(data, "commits")?;
+        let (trees, data) = decode_bitmap(data, "trees")?;
+        let (blobs, data) = decode_bitmap(data, "blobs")?;
+        let (tags, mut data) = decode_bitmap(data, "tags")?;
+
+        let mut entries = Vec::with_capacity(num_entries);
+        let mut entry_by_index = HashMap::with_capacity(num_entries);
+        for entry_pos in 0..num_entries {
+            if data.len() < 4 + 1 + 1 {
+                return Err(Error::Corrupt {
+                    message: "eof while reading commit bitmap entry",
+                });
+            }
+            let (header, rest) = data.split_at(4 + 1 + 1);
+            let index = crate::read_u32(&header[..4]);
+            let xor_offset = header[4];
+            if xor_offset as usize > entry_pos {
+                return Err(Error::InvalidXorOffset { entry: entry_pos });
+            }
+            let (bitmap, rest) = decode_bitmap(rest, "commit")?;
+            entry_by_index.insert(index, entry_pos);
+            entries.push(Entry {
+                index,
+                xor_offset,
+                bitmap,
+            });
+            data = rest;
+        }
+
+        let name_hashes = (flags & Self::FLAG_HASH_CACHE != 0)
+            .then(|| {
+                let lookup_table_len = if flags & Self::FLAG_LOOKUP_TABLE != 0 {
+                    num_entries * (4 /* commit position */ + 8 /* offset */ + 4/* xor row */)
+                } else {
+                    0
+                };
+                let hashes = data
+                    .len()
+                    .checked_sub(lookup_table_len)
+                    .filter(|len| len % 4 == 0)
+                    .map(|len| &data[..len])
+                    .ok_or(Error::Corrupt {
+                        message: "name-hash cache has an invalid size",
+                    })?;
+                Ok(hashes.chunks(4).map(crate::read_u32).collect())
+            })
+            .transpose()?;
+
+        Ok(File {
+            path: path.to_owned(),
+            version,
+            object_hash,
+            index_checksum,
+            commits,
+            trees,
+            blobs,
+            tags,
+            entries,
+            entry_by_index,
+            name_hashes,
+        })
+    }
+}
+
+fn decode_bitmap<'a>(data: &'a [u8], kind: &'static str) -> Result<(ewah::Vec, &'a [u8]), Error> {
+    ewah::decode(data).map_err(|source| Error::Bitmap { kind, source })
+}
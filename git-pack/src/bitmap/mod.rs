@@ -0,0 +1,94 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use git_bitmap::ewah;
+
+/// Known bitmap file versions
+#[derive(PartialEq, Eq, Ord, PartialOrd, Debug, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum Version {
+    V1 = 1,
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Version::V1
+    }
+}
+
+/// A reachability bitmap file, typically named `pack-<checksum>.bitmap` or `multi-pack-index-<checksum>.bitmap`, which
+/// stores the set of objects reachable from selected commits as EWAH compressed bitmaps.
+///
+/// Each bit refers to an object by its position in the pack order of the pack or multi-pack index it belongs to, which is
+/// provided by a [`PackOrder`].
+pub struct File {
+    path: PathBuf,
+    version: Version,
+    object_hash: git_hash::Kind,
+    /// The checksum of the pack or multi-pack index this bitmap belongs to.
+    index_checksum: git_hash::ObjectId,
+    commits: ewah::Vec,
+    trees: ewah::Vec,
+    blobs: ewah::Vec,
+    tags: ewah::Vec,
+    entries: Vec<Entry>,
+    /// Maps an entry index of the pack or multi-pack index to the position in `entries`.
+    entry_by_index: HashMap<u32, usize>,
+    name_hashes: Option<Vec<u32>>,
+}
+
+/// A bitmap for a single commit, possibly stored as XOR against a previous one.
+struct Entry {
+    /// The index of the commit in its pack index or multi-pack index, which is in lexicographical order.
+    index: u32,
+    /// If not 0, the bitmap is stored as XOR against the entry `xor_offset` positions before this one.
+    xor_offset: u8,
+    bitmap: ewah::Vec,
+}
+
+/// Mapping between positions in bitmaps and entries of pack indices
+pub mod pack_order;
+pub use pack_order::PackOrder;
+
+mod access;
+
+/// Loading bitmap files
+pub mod init;
+
+/// Finding reachable objects with the help of bitmaps
+pub mod reachable;
+
+/// Writing bitmap files
+pub mod write;
+
+fn xor(words: &mut Vec<u64>, other: &[u64]) {
+    if words.len() < other.len() {
+        words.resize(other.len(), 0);
+    }
+    for (word, other) in words.iter_mut().zip(other) {
+        *word ^= other;
+    }
+}
+
+fn or(words: &mut Vec<u64>, other: &[u64]) {
+    if words.len() < other.len() {
+        words.resize(other.len(), 0);
+    }
+    for (word, other) in words.iter_mut().zip(other) {
+        *word |= other;
+    }
+}
+
+fn is_set(words: &[u64], position: u32) -> bool {
+    words
+        .get(position as usize / 64)
+        .map_or(false, |word| word & (1 << (position % 64)) != 0)
+}
+
+fn set(words: &mut Vec<u64>, position: u32) {
+    let word = position as usize / 64;
+    if words.len() <= word {
+        words.resize(word + 1, 0);
+    }
+    words[word] |= 1 << (position % 64);
+}
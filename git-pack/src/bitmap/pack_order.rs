@@ -0,0 +1,110 @@
+use crate::{index, multi_index};
+
+/// The error returned by [`PackOrder::from_multi_index()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The multi-pack index at '{}' has no reverse index chunk to learn the pseudo-pack order from", .path.display())]
+    MissingReverseIndex { path: std::path::PathBuf },
+}
+
+enum Index<'a> {
+    Single(&'a index::File),
+    Multi(&'a multi_index::File),
+}
+
+/// A mapping between the position of objects in pack order, which is used to identify objects in reachability bitmaps,
+/// and their entry index in a pack index or multi-pack index, which is in lexicographical order.
+///
+/// For multi-pack indices, the pack order is the order of objects in the pseudo-pack made from all packs.
+pub struct PackOrder<'a> {
+    index: Index<'a>,
+    index_at_position: Vec<u32>,
+    position_at_index: Vec<u32>,
+}
+
+/// Initialization
+impl<'a> PackOrder<'a> {
+    /// Compute the pack order of all objects in the given pack `index` by sorting them by their pack offset.
+    pub fn from_index(index: &'a index::File) -> Self {
+        let mut index_at_position: Vec<_> = (0..index.num_objects()).collect();
+        index_at_position.sort_by_key(|idx| index.pack_offset_at_index(*idx));
+        Self::from_order(Index::Single(index), index_at_position)
+    }
+
+    /// Obtain the pseudo-pack order of all objects in the multi-pack `index` from its reverse index chunk.
+    pub fn from_multi_index(index: &'a multi_index::File) -> Result<Self, Error> {
+        let index_at_position = (0..index.num_objects())
+            .map(|position| index.index_at_pseudo_pack_position(position))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| Error::MissingReverseIndex {
+                path: index.path().to_owned(),
+            })?;
+        Ok(Self::from_order(Index::Multi(index), index_at_position))
+    }
+
+    fn from_order(index: Index<'a>, index_at_position: Vec<u32>) -> Self {
+        let mut position_at_index = vec![0; index_at_position.len()];
+        for (position, index) in index_at_position.iter().enumerate() {
+            position_at_index[*index as usize] = position as u32;
+        }
+        PackOrder {
+            index,
+            index_at_position,
+            position_at_index,
+        }
+    }
+}
+
+/// Access
+impl PackOrder<'_> {
+    /// Return the amount of objects, which is one past the highest position.
+    pub fn num_objects(&self) -> u32 {
+        self.index_at_position.len() as u32
+    }
+
+    /// Return the kind of hash used for object ids.
+    pub fn object_hash(&self) -> git_hash::Kind {
+        match self.index {
+            Index::Single(index) => index.object_hash(),
+            Index::Multi(index) => index.object_hash(),
+        }
+    }
+
+    /// Return the checksum that bitmaps for our objects are expected to store, which is the checksum of the pack
+    /// or of the multi-pack index.
+    pub fn checksum(&self) -> git_hash::ObjectId {
+        match self.index {
+            Index::Single(index) => index.pack_checksum(),
+            Index::Multi(index) => index.checksum(),
+        }
+    }
+
+    /// Return the position of the object with the given `id`, or `None` if it isn't contained.
+    pub fn lookup(&self, id: impl AsRef<git_hash::oid>) -> Option<u32> {
+        match self.index {
+            Index::Single(index) => index.lookup(id),
+            Index::Multi(index) => index.lookup(id),
+        }
+        .map(|index| self.position_at_index(index))
+    }
+
+    /// Return the id of the object at the given `position`, ranging from 0 to [`num_objects()`][PackOrder::num_objects()].
+    pub fn oid_at_position(&self, position: u32) -> &git_hash::oid {
+        let index = self.index_at_position(position);
+        match self.index {
+            Index::Single(file) => file.oid_at_index(index),
+            Index::Multi(file) => file.oid_at_index(index),
+        }
+    }
+
+    /// Return the entry index in the pack index or multi-pack index of the object at `position`.
+    pub fn index_at_position(&self, position: u32) -> u32 {
+        self.index_at_position[position as usize]
+    }
+
+    /// Return the position of the object at entry `index` in the pack index or multi-pack index.
+    pub fn position_at_index(&self, index: u32) -> u32 {
+        self.position_at_index[index as usize]
+    }
+}
@@ -0,0 +1,199 @@
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use git_hash::ObjectId;
+use git_object::Kind;
+
+use crate::{
+    bitmap::{File, PackOrder},
+    FindExt,
+};
+
+/// The error returned by [`File::reachable_objects()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error<FindErr>
+where
+    FindErr: std::error::Error + 'static,
+{
+    #[error(
+        "The bitmap was written for an index with checksum {expected}, but the given pack order has checksum {actual}"
+    )]
+    ChecksumMismatch {
+        expected: git_hash::ObjectId,
+        actual: git_hash::ObjectId,
+    },
+    #[error(transparent)]
+    FindExisting(#[from] crate::find::existing::Error<FindErr>),
+    #[error("{kind} object {id} could not be decoded")]
+    ObjectDecode {
+        source: git_object::decode::Error,
+        kind: Kind,
+        id: ObjectId,
+    },
+    #[error("Operation interrupted")]
+    Interrupted,
+}
+
+/// The result of [`File::reachable_objects()`].
+#[derive(Default, Clone)]
+pub struct Outcome {
+    /// A bit for each object in pack order, set if the object at that position is reachable.
+    ///
+    /// Bit `n` is stored as `words[n / 64] & (1 << (n % 64))`.
+    pub words: Vec<u64>,
+    /// Reachable objects that are not part of the pack or multi-pack index, in the order they were encountered.
+    pub extra_objects: Vec<ObjectId>,
+}
+
+impl Outcome {
+    /// Return true if the object at `position` in pack order is reachable.
+    pub fn contains(&self, position: u32) -> bool {
+        super::is_set(&self.words, position)
+    }
+
+    /// Return an iterator over the positions in pack order of all reachable objects in the pack or multi-pack index.
+    pub fn positions(&self) -> impl Iterator<Item = u32> + '_ {
+        self.words.iter().enumerate().flat_map(|(word_idx, word)| {
+            let word = *word;
+            (0..64u32)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| word_idx as u32 * 64 + bit)
+        })
+    }
+
+    /// Return the total amount of reachable objects, including [extra objects][Outcome::extra_objects].
+    pub fn num_objects(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum::<usize>() + self.extra_objects.len()
+    }
+}
+
+impl File {
+    /// Find all objects reachable from the commits, tags, trees or blobs in `tips`, using the bitmaps of commits
+    /// to avoid traversing their history whenever possible.
+    ///
+    /// `order` must be created from the pack index or multi-pack index this bitmap belongs to, and `db` is used to
+    /// find objects that need to be traversed as they are not covered by any bitmap.
+    /// Objects that aren't contained in `order` are traversed as well and returned as [extra objects][Outcome::extra_objects].
+    pub fn reachable_objects<Find>(
+        &self,
+        order: &PackOrder<'_>,
+        tips: impl IntoIterator<Item = ObjectId>,
+        db: Find,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error<Find::Error>>
+    where
+        Find: crate::Find,
+    {
+        if order.checksum() != self.index_checksum {
+            return Err(Error::ChecksumMismatch {
+                expected: self.index_checksum,
+                actual: order.checksum(),
+            });
+        }
+        let blobs = self.blobs.to_words();
+        let mut out = Outcome::default();
+        traverse(
+            order,
+            tips,
+            db,
+            |position| super::is_set(&blobs, position),
+            |index, words| {
+                self.commit_bitmap(index)
+                    .map(|bitmap| super::or(words, &bitmap))
+                    .is_some()
+            },
+            &mut out,
+            should_interrupt,
+        )?;
+        Ok(out)
+    }
+}
+
+/// Set all objects reachable from `tips` in `out`. Objects whose position is known to be a blob according to `is_blob()`
+/// are never looked up.
+///
+/// `or_bitmap(index, words)` is called for each commit contained in `order` with its entry `index` in the pack index or
+/// multi-pack index, and returns `true` if it merged all objects reachable from it into `words`.
+pub(crate) fn traverse<Find>(
+    order: &PackOrder<'_>,
+    tips: impl IntoIterator<Item = ObjectId>,
+    db: Find,
+    is_blob: impl Fn(u32) -> bool,
+    mut or_bitmap: impl FnMut(u32, &mut Vec<u64>) -> bool,
+    out: &mut Outcome,
+    should_interrupt: &AtomicBool,
+) -> Result<(), Error<Find::Error>>
+where
+    Find: crate::Find,
+{
+    let mut seen_extra = HashSet::new();
+    let mut queue: Vec<_> = tips.into_iter().collect();
+    let mut buf = Vec::new();
+
+    // Returns true if the object was seen for the first time and wasn't known to be a blob, so it needs to be traversed.
+    let mut mark = |id: &git_hash::oid, is_known_blob: bool, out: &mut Outcome| match order.lookup(id) {
+        Some(position) => {
+            if super::is_set(&out.words, position) {
+                return false;
+            }
+            super::set(&mut out.words, position);
+            !(is_known_blob || is_blob(position))
+        }
+        None => {
+            if !seen_extra.insert(id.to_owned()) {
+                return false;
+            }
+            out.extra_objects.push(id.to_owned());
+            !is_known_blob
+        }
+    };
+
+    while let Some(id) = queue.pop() {
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+        if let Some(position) = order.lookup(id) {
+            if super::is_set(&out.words, position) {
+                continue;
+            }
+            if or_bitmap(order.index_at_position(position), &mut out.words) {
+                continue;
+            }
+        }
+        if !mark(&id, false, out) {
+            continue;
+        }
+
+        let (data, _location) = db.find(id, &mut buf)?;
+        let kind = data.kind;
+        let decode_err = |source| Error::ObjectDecode { source, kind, id };
+        match kind {
+            Kind::Commit => {
+                let mut commit = data.try_into_commit_iter().expect("kind was checked");
+                queue.push(commit.tree_id().map_err(decode_err)?);
+                queue.extend(commit.parent_ids());
+            }
+            Kind::Tag => {
+                let tag = data.try_into_tag_iter().expect("kind was checked");
+                queue.push(tag.target_id().map_err(decode_err)?);
+            }
+            Kind::Tree => {
+                for entry in data.try_into_tree_iter().expect("kind was checked") {
+                    let entry = entry.map_err(decode_err)?;
+                    match entry.mode {
+                        git_object::tree::EntryMode::Commit => {}
+                        git_object::tree::EntryMode::Tree => queue.push(entry.oid.to_owned()),
+                        _blob => {
+                            mark(entry.oid, true, out);
+                        }
+                    }
+                }
+            }
+            Kind::Blob => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Instant,
+};
+
+use git_bitmap::ewah;
+use git_features::progress::Progress;
+use git_hash::ObjectId;
+use git_object::Kind;
+
+use crate::{
+    bitmap::{reachable, File, PackOrder},
+    data, FindExt,
+};
+
+mod error {
+    /// The error returned by [File::write_from_bundle()][super::File::write_from_bundle()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error<FindErr>
+    where
+        FindErr: std::error::Error + 'static,
+    {
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+        #[error("Interrupted")]
+        Interrupted,
+        #[error("The base object of the delta at pack offset {pack_offset} is not contained in the pack")]
+        MissingDeltaBase { pack_offset: crate::data::Offset },
+        #[error("Commit {id} is not contained in the pack")]
+        CommitNotInPack { id: git_hash::ObjectId },
+        #[error("Object {id} was expected to be a commit, but it's a {actual}")]
+        NotACommit {
+            id: git_hash::ObjectId,
+            actual: git_object::Kind,
+        },
+        #[error("Object {id} is reachable from commit {commit} but not contained in the pack")]
+        ObjectNotInPack {
+            id: git_hash::ObjectId,
+            commit: git_hash::ObjectId,
+        },
+        #[error(transparent)]
+        FindExisting(#[from] crate::find::existing_iter::Error<FindErr>),
+        #[error(transparent)]
+        Traverse(#[from] crate::bitmap::reachable::Error<FindErr>),
+    }
+}
+pub use error::Error;
+
+/// The result of [`File::write_from_bundle()`].
+pub struct Outcome<P> {
+    /// The amount of commits for which a bitmap was written.
+    pub num_commits: usize,
+    /// The calculated checksum of the bitmap file.
+    pub bitmap_checksum: git_hash::ObjectId,
+    /// The input progress
+    pub progress: P,
+}
+
+impl File {
+    pub(crate) const SIGNATURE: &'static [u8] = b"BITM";
+    pub(crate) const HEADER_LEN: usize = 4 /*signature*/ +
+        2 /*version*/ +
+        2 /*flags*/ +
+        4 /*num entries*/;
+    /// All objects reachable from bitmapped commits are contained in the pack.
+    pub(crate) const FLAG_FULL_DAG: u16 = 0x1;
+    /// The file contains the hashes of the paths at which objects were found.
+    pub(crate) const FLAG_HASH_CACHE: u16 = 0x4;
+    /// The file contains a table to lookup commit bitmaps without reading all of them.
+    pub(crate) const FLAG_LOOKUP_TABLE: u16 = 0x10;
+
+    /// Write a bitmap file for the pack in `bundle` to `out`, storing the objects reachable from each commit in `commits`.
+    ///
+    /// All objects reachable from `commits` must be contained in the pack. They are traversed using `db`, while bitmaps
+    /// of commits computed earlier are used to avoid traversing the same history multiple times.
+    /// Note that no name-hash cache is written.
+    ///
+    /// Progress is sent to `progress` and interruptions checked via `should_interrupt`.
+    pub fn write_from_bundle<Find, P>(
+        bundle: &crate::Bundle,
+        commits: impl IntoIterator<Item = ObjectId>,
+        db: Find,
+        out: impl std::io::Write,
+        mut progress: P,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome<P>, Error<Find::Error>>
+    where
+        Find: crate::Find,
+        P: Progress,
+    {
+        let order = PackOrder::from_index(&bundle.index);
+        let kinds = {
+            let start = Instant::now();
+            let mut progress = progress.add_child_with_id("Collecting object kinds", *b"BWCK"); /* Bitmap Write Collecting Kinds */
+            progress.init(
+                Some(order.num_objects() as usize),
+                git_features::progress::count("objects"),
+            );
+            let kinds = object_kinds(bundle, &order, &mut progress, should_interrupt)?;
+            progress.show_throughput(start);
+            kinds
+        };
+
+        let commits = {
+            let mut buf = Vec::new();
+            let mut commits_with_time = Vec::new();
+            for id in commits {
+                let position = order.lookup(id).ok_or(Error::CommitNotInPack { id })?;
+                match kinds[position as usize] {
+                    Kind::Commit => {}
+                    actual => return Err(Error::NotACommit { id, actual }),
+                }
+                let time = db
+                    .find_commit_iter(id, &mut buf)?
+                    .0
+                    .committer()
+                    .map(|c| c.time.seconds_since_unix_epoch)
+                    .unwrap_or_default();
+                commits_with_time.push((time, position, id));
+            }
+            // Parents are typically older, so computing their bitmaps first allows to reuse them for their descendants.
+            commits_with_time.sort();
+            commits_with_time.dedup_by_key(|(_, position, _)| *position);
+            commits_with_time
+        };
+
+        let mut bitmaps = Vec::<(u32, Vec<u64>)>::with_capacity(commits.len());
+        {
+            let start = Instant::now();
+            let mut progress = progress.add_child_with_id("Computing bitmaps", *b"BWCB"); /* Bitmap Write Computing Bitmaps */
+            progress.init(Some(commits.len()), git_features::progress::count("commits"));
+            let mut bitmap_by_index = HashMap::<u32, usize>::new();
+            for (_time, position, id) in commits {
+                let mut reachable = reachable::Outcome::default();
+                reachable::traverse(
+                    &order,
+                    Some(id),
+                    &db,
+                    |position| kinds[position as usize] == Kind::Blob,
+                    |index, words| {
+                        bitmap_by_index
+                            .get(&index)
+                            .map(|bitmap_idx| super::or(words, &bitmaps[*bitmap_idx].1))
+                            .is_some()
+                    },
+                    &mut reachable,
+                    should_interrupt,
+                )?;
+                if let Some(extra) = reachable.extra_objects.first() {
+                    return Err(Error::ObjectNotInPack { id: *extra, commit: id });
+                }
+                let index = order.index_at_position(position);
+                bitmap_by_index.insert(index, bitmaps.len());
+                bitmaps.push((index, reachable.words));
+                progress.inc();
+            }
+            progress.show_throughput(start);
+        }
+
+        let mut out = git_features::hash::Write::new(out, bundle.index.object_hash());
+        out.write_all(Self::SIGNATURE)?;
+        out.write_all(&(crate::bitmap::Version::V1 as u16).to_be_bytes())?;
+        out.write_all(&Self::FLAG_FULL_DAG.to_be_bytes())?;
+        out.write_all(&(bitmaps.len() as u32).to_be_bytes())?;
+        out.write_all(order.checksum().as_slice())?;
+
+        for kind in [Kind::Commit, Kind::Tree, Kind::Blob, Kind::Tag] {
+            let mut words = Vec::new();
+            for (position, _) in kinds.iter().enumerate().filter(|(_, k)| **k == kind) {
+                super::set(&mut words, position as u32);
+            }
+            ewah::encode(&words).write_to(&mut out)?;
+        }
+        for (index, words) in &bitmaps {
+            out.write_all(&index.to_be_bytes())?;
+            out.write_all(&[0 /* xor offset */, 0 /* flags */])?;
+            ewah::encode(words).write_to(&mut out)?;
+        }
+
+        let bitmap_checksum = out.hash.digest();
+        out.inner.write_all(bitmap_checksum.as_slice())?;
+
+        Ok(Outcome {
+            num_commits: bitmaps.len(),
+            bitmap_checksum,
+            progress,
+        })
+    }
+}
+
+/// Return the kind of each object in pack order by following delta chains to their base objects.
+fn object_kinds<E>(
+    bundle: &crate::Bundle,
+    order: &PackOrder<'_>,
+    progress: &mut impl Progress,
+    should_interrupt: &AtomicBool,
+) -> Result<Vec<Kind>, Error<E>>
+where
+    E: std::error::Error + 'static,
+{
+    let offsets: Vec<_> = (0..order.num_objects())
+        .map(|position| bundle.index.pack_offset_at_index(order.index_at_position(position)))
+        .collect();
+    let mut kinds: Vec<Option<Kind>> = vec![None; offsets.len()];
+    let mut chain = Vec::new();
+    for position in 0..offsets.len() {
+        let mut cursor = position;
+        let kind = loop {
+            if let Some(kind) = kinds[cursor] {
+                break kind;
+            }
+            chain.push(cursor);
+            let pack_offset = offsets[cursor];
+            match bundle.pack.entry(pack_offset)?.header {
+                data::entry::Header::OfsDelta { base_distance } => {
+                    cursor = pack_offset
+                        .checked_sub(base_distance)
+                        .and_then(|base_offset| offsets.binary_search(&base_offset).ok())
+                        .ok_or(Error::MissingDeltaBase { pack_offset })?;
+                }
+                data::entry::Header::RefDelta { base_id } => {
+                    cursor = order.lookup(base_id).ok_or(Error::MissingDeltaBase { pack_offset })? as usize;
+                }
+                base => break base.as_kind().expect("non-delta objects have a kind"),
+            }
+        };
+        for position in chain.drain(..) {
+            kinds[position] = Some(kind);
+        }
+        progress.inc();
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+    }
+    Ok(kinds
+        .into_iter()
+        .map(|kind| kind.expect("all kinds were set"))
+        .collect())
+}
@@ -31,24 +31,32 @@ impl crate::Bundle {
         cache: &mut impl crate::cache::DecodeEntry,
     ) -> Result<(git_object::Data<'a>, crate::data::entry::Location), crate::data::decode_entry::Error> {
         let ofs = self.index.pack_offset_at_index(idx);
-        let pack_entry = self.pack.entry(ofs);
+        let pack_entry = self.pack.entry(ofs)?;
         let header_size = pack_entry.header_size();
+        let base_entry_error = std::cell::RefCell::new(None::<std::io::Error>);
         self.pack
             .decode_entry(
                 pack_entry,
                 out,
                 |id, _out| {
-                    self.index.lookup(id).map(|idx| {
-                        crate::data::ResolvedBase::InPack(self.pack.entry(self.index.pack_offset_at_index(idx)))
-                    })
+                    let idx = self.index.lookup(id)?;
+                    match self.pack.entry(self.index.pack_offset_at_index(idx)) {
+                        Ok(entry) => Some(crate::data::ResolvedBase::InPack(entry)),
+                        Err(err) => {
+                            *base_entry_error.borrow_mut() = Some(err);
+                            None
+                        }
+                    }
                 },
                 cache,
             )
+            .map_err(|err| base_entry_error.take().map_or(err, Into::into))
             .map(move |r| {
                 (
                     git_object::Data {
                         kind: r.kind,
                         data: out.as_slice(),
+                        object_hash: self.index.object_hash(),
                     },
                     crate::data::entry::Location {
                         pack_id: self.pack.id,
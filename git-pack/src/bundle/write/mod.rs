@@ -91,7 +91,7 @@ impl crate::Bundle {
                         writer: data_file.clone(),
                     },
                     pack_version,
-                    git_hash::Kind::Sha1, // Thin packs imply a pack being transported, and there we only ever know SHA1 at the moment.
+                    object_hash,
                 );
                 (Box::new(pack_entries_iter), pack_version)
             }
@@ -272,6 +272,8 @@ impl crate::Bundle {
                         let data_file = Arc::clone(&data_file);
                         move || new_pack_file_resolver(data_file)
                     },
+                    // Thin packs were completed by `LookupRefDeltaObjectsIter` which inserted all bases it could find.
+                    crate::index::traverse::no_external_bases,
                     pack_entries_iter,
                     thread_limit,
                     indexing_progress,
@@ -312,6 +314,7 @@ impl crate::Bundle {
                 outcome: crate::index::File::write_data_iter_to_stream(
                     index_kind,
                     move || new_pack_file_resolver(data_file),
+                    crate::index::traverse::no_external_bases,
                     pack_entries_iter,
                     thread_limit,
                     indexing_progress,
@@ -18,8 +18,6 @@ pub enum Error {
     Io { source: io::Error, message: &'static str },
     #[error(transparent)]
     Header(#[from] crate::data::header::decode::Error),
-    #[error("Could find object with id {id} in this pack. Thin packs are not supported")]
-    UnresolvedRefDelta { id: git_hash::ObjectId },
     #[error(transparent)]
     Tree(#[from] crate::cache::delta::Error),
     #[error("Interrupted")]
@@ -36,9 +34,9 @@ impl<T> Tree<T> {
     /// * `pack_path` is the path to the pack file itself and from which to read the entry data, which is a pack file matching the offsets
     /// returned by `get_pack_offset(…)`.
     /// * `progress` is used to track progress when creating the tree.
-    /// * `resolve_in_pack_id(git_hash::oid) -> Option<data::Offset>` takes an object ID and tries to resolve it to an object within this pack if
-    /// possible. Failing to do so aborts the operation, and this function is not expected to be called in usual packs. It's a theoretical
-    /// possibility though as old packs might have referred to their objects using the 20 bytes hash, instead of their encoded offset from the base.
+    /// * `resolve_in_pack_id(git_hash::oid) -> Option<data::Offset>` takes an object ID of the base of a ref-delta entry and tries to resolve
+    ///   it to an object within this pack. If it isn't contained in the pack, the entry is added as child of an
+    ///   [external base][Tree::add_child_with_external_base()] which has to be provided during [traversal][Tree::traverse()].
     ///
     /// Note that the sort order is ascending. The given pack file path must match the provided offsets.
     pub fn from_offsets_in_pack(
@@ -97,13 +95,10 @@ impl<T> Tree<T> {
                 Tree | Blob | Commit | Tag => {
                     tree.add_root(pack_offset, data)?;
                 }
-                RefDelta { base_id } => {
-                    resolve_in_pack_id(base_id.as_ref())
-                        .ok_or(Error::UnresolvedRefDelta { id: base_id })
-                        .and_then(|base_pack_offset| {
-                            tree.add_child(base_pack_offset, pack_offset, data).map_err(Into::into)
-                        })?;
-                }
+                RefDelta { base_id } => match resolve_in_pack_id(base_id.as_ref()) {
+                    Some(base_pack_offset) => tree.add_child(base_pack_offset, pack_offset, data)?,
+                    None => tree.add_child_with_external_base(base_id, pack_offset, data)?,
+                },
                 OfsDelta { base_distance } => {
                     let base_pack_offset = pack_offset
                         .checked_sub(base_distance)
@@ -50,6 +50,9 @@ pub struct Tree<T> {
     /// Future child offsets, associating their offset into the pack with their index in the items array.
     /// (parent_offset, child_index)
     future_child_offsets: Vec<(crate::data::Offset, usize)>,
+    /// Children whose base object is not contained in the pack, associating the base's id with the child's index in the items array.
+    /// (base_id, child_index)
+    external_base_children: Vec<(git_hash::ObjectId, u32)>,
 }
 
 impl<T> Tree<T> {
@@ -60,6 +63,7 @@ impl<T> Tree<T> {
             child_items: Vec::with_capacity(num_objects / 2),
             last_seen: None,
             future_child_offsets: Vec::new(),
+            external_base_children: Vec::new(),
         })
     }
 
@@ -148,6 +152,32 @@ impl<T> Tree<T> {
         });
         Ok(())
     }
+
+    /// Add a child whose base object with `base_id` is not contained in the pack, which itself resides at pack `offset`, and
+    /// associate custom `data` with it.
+    ///
+    /// This is the case for ref-delta entries in thin packs, whose base objects have to be provided during
+    /// [traversal][Tree::traverse()].
+    pub fn add_child_with_external_base(
+        &mut self,
+        base_id: git_hash::ObjectId,
+        offset: crate::data::Offset,
+        data: T,
+    ) -> Result<(), Error> {
+        self.assert_is_incrementing_and_update_next_offset(offset)?;
+
+        self.external_base_children
+            .push((base_id, self.child_items.len() as u32));
+
+        self.last_seen = NodeKind::Child.into();
+        self.child_items.push(Item {
+            offset,
+            next_offset: 0,
+            data,
+            children: Default::default(),
+        });
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -190,6 +220,140 @@ mod tests {
                 Ok(())
             }
         }
+
+        mod traverse {
+            use std::sync::atomic::AtomicBool;
+
+            type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+            /// Return the directory of the repository containing `thin.pack` along with all entries of that pack.
+            fn thin_pack() -> Result<(std::path::PathBuf, Vec<crate::data::input::Entry>)> {
+                let dir = git_testtools::scripted_fixture_repo_read_only("make_thin_pack.sh")?;
+                let entries = crate::data::input::BytesToEntriesIter::new_from_header(
+                    std::io::BufReader::new(std::fs::File::open(dir.join("thin.pack"))?),
+                    crate::data::input::Mode::AsIs,
+                    crate::data::input::EntryDataMode::Ignore,
+                    git_hash::Kind::Sha1,
+                )?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+                Ok((dir, entries))
+            }
+
+            #[test]
+            fn ref_deltas_with_bases_outside_of_the_pack() -> Result {
+                let (dir, entries) = thin_pack()?;
+                let pack_path = dir.join("thin.pack");
+                let loose_db = git_odb::loose::Store::at(dir.join(".git").join("objects"), git_hash::Kind::Sha1);
+                assert_eq!(
+                    entries
+                        .iter()
+                        .filter(|entry| matches!(entry.header, crate::data::entry::Header::RefDelta { .. }))
+                        .count(),
+                    1,
+                    "the changed blob is a delta against the blob of the previous commit, which isn't part of the pack"
+                );
+
+                let tree = crate::cache::delta::Tree::from_offsets_in_pack(
+                    &pack_path,
+                    entries.iter().map(|entry| (entry.pack_offset, None)),
+                    |(pack_offset, _)| *pack_offset,
+                    |_| None,
+                    git_features::progress::Discard,
+                    &AtomicBool::new(false),
+                    git_hash::Kind::Sha1,
+                )?;
+                let data = crate::data::File::at(&pack_path, git_hash::Kind::Sha1)?;
+                let outcome = tree.traverse(
+                    |slice, out| data.entry_slice(slice, out).ok(),
+                    |id, out| loose_db.try_find(id, out).ok().flatten().map(|obj| obj.kind),
+                    data.pack_end() as u64,
+                    || (),
+                    |(_, id_and_level): &mut (u64, Option<(git_hash::ObjectId, u16)>), _progress, ctx| {
+                        let kind = ctx
+                            .entry
+                            .header
+                            .as_kind()
+                            .expect("resolved objects have their base's kind");
+                        let mut hasher = git_features::hash::hasher(git_hash::Kind::Sha1);
+                        hasher.update(&git_object::encode::loose_header(kind, ctx.decompressed.len()));
+                        hasher.update(ctx.decompressed);
+                        let id = hasher
+                            .try_digest()
+                            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+                        *id_and_level = Some((id, ctx.level));
+                        Ok::<_, std::io::Error>(())
+                    },
+                    crate::cache::delta::traverse::Options {
+                        object_progress: git_features::progress::Discard,
+                        size_progress: git_features::progress::Discard,
+                        thread_limit: None,
+                        should_interrupt: &AtomicBool::new(false),
+                        object_hash: git_hash::Kind::Sha1,
+                    },
+                )?;
+
+                let mut resolved = outcome
+                    .roots
+                    .iter()
+                    .chain(outcome.children.iter())
+                    .map(|item| item.data.1.expect("every entry was resolved"))
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    resolved.iter().filter(|(_, level)| *level == 1).count(),
+                    1,
+                    "the delta has one base, even though it isn't in the pack"
+                );
+                resolved.sort();
+                let expected = std::fs::read_to_string(dir.join("objects-in-thin-pack"))?
+                    .lines()
+                    .map(|hex| git_hash::ObjectId::from_hex(hex.as_bytes()))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
+                assert_eq!(
+                    resolved.into_iter().map(|(id, _)| id).collect::<Vec<_>>(),
+                    expected,
+                    "all objects hash to what's expected, which is only the case if the delta was applied to the correct base"
+                );
+                Ok(())
+            }
+
+            #[test]
+            fn ref_deltas_with_bases_that_cannot_be_found() -> Result {
+                let (dir, entries) = thin_pack()?;
+                let pack_path = dir.join("thin.pack");
+                let tree = crate::cache::delta::Tree::from_offsets_in_pack(
+                    &pack_path,
+                    entries.iter().map(|entry| entry.pack_offset),
+                    |pack_offset| *pack_offset,
+                    |_| None,
+                    git_features::progress::Discard,
+                    &AtomicBool::new(false),
+                    git_hash::Kind::Sha1,
+                )?;
+                let data = crate::data::File::at(&pack_path, git_hash::Kind::Sha1)?;
+                let err = tree
+                    .traverse(
+                        |slice, out| data.entry_slice(slice, out).ok(),
+                        crate::index::traverse::no_external_bases,
+                        data.pack_end() as u64,
+                        || (),
+                        |_, _progress, _ctx| Ok::<_, std::io::Error>(()),
+                        crate::cache::delta::traverse::Options {
+                            object_progress: git_features::progress::Discard,
+                            size_progress: git_features::progress::Discard,
+                            thread_limit: None,
+                            should_interrupt: &AtomicBool::new(false),
+                            object_hash: git_hash::Kind::Sha1,
+                        },
+                    )
+                    .err()
+                    .expect("the base is missing");
+                assert!(matches!(
+                    err,
+                    crate::cache::delta::traverse::Error::UnresolvedRefDeltaBase { .. }
+                ));
+                Ok(())
+            }
+        }
     }
 
     #[test]
@@ -213,6 +377,6 @@ mod tests {
             _level: u16,
         }
 
-        assert_eq!(std::mem::size_of::<[Item<EntryWithDefault>; 7_500_000]>(), 840_000_000);
+        assert_eq!(std::mem::size_of::<[Item<EntryWithDefault>; 7_500_000]>(), 960_000_000);
     }
 }
@@ -7,7 +7,10 @@ use git_features::{
 };
 
 use crate::{
-    cache::delta::{traverse::util::ItemSliceSend, Item, Tree},
+    cache::delta::{
+        traverse::util::{ExternalBase, ItemSliceSend},
+        Item, Tree,
+    },
     data::EntryRange,
 };
 
@@ -36,6 +39,13 @@ pub enum Error {
         /// The base's offset which was from a resolved ref-delta that didn't actually get added to the tree
         base_pack_offset: crate::data::Offset,
     },
+    #[error(
+        "The base object {id} of a ref-delta isn't contained in the pack and could not be obtained from elsewhere"
+    )]
+    UnresolvedRefDeltaBase {
+        /// The id of the base object which couldn't be found.
+        id: git_hash::ObjectId,
+    },
 }
 
 /// Additional context passed to the `inspect_object(…)` function of the [`Tree::traverse()`] method.
@@ -87,6 +97,9 @@ where
     /// * `resolve(EntrySlice, &mut Vec<u8>) -> Option<()>` resolves the bytes in the pack for the given `EntrySlice` and stores them in the
     ///   output vector. It returns `Some(())` if the object existed in the pack, or `None` to indicate a resolution error, which would abort the
     ///   operation as well.
+    /// * `resolve_external_base(&oid, &mut Vec<u8>) -> Option<git_object::Kind>` is called for base objects of ref-delta entries which
+    ///   are not contained in the pack, for example in thin packs. It stores the fully decoded object in the output vector and returns
+    ///   its kind, or `None` if it couldn't be found, which aborts the operation. These base objects are not passed to `inspect_object`.
     /// * `pack_entries_end` marks one-past-the-last byte of the last entry in the pack, as the last entries size would otherwise
     ///   be unknown as it's not part of the index file.
     /// * `new_thread_state() -> State` is a function to create state to be used in each thread, invoked once per thread.
@@ -98,9 +111,10 @@ where
     /// This method returns a vector of all tree items, along with their potentially modified custom node data.
     ///
     /// _Note_ that this method consumed the Tree to assure safe parallel traversal with mutation support.
-    pub fn traverse<F, P1, P2, MBFN, S, E>(
+    pub fn traverse<F, RB, P1, P2, MBFN, S, E>(
         mut self,
         resolve: F,
+        resolve_external_base: RB,
         pack_entries_end: u64,
         new_thread_state: impl Fn() -> S + Send + Clone,
         inspect_object: MBFN,
@@ -114,6 +128,7 @@ where
     ) -> Result<Outcome<T>, Error>
     where
         F: for<'r> Fn(EntryRange, &'r mut Vec<u8>) -> Option<()> + Send + Clone,
+        RB: for<'r> Fn(&git_hash::oid, &'r mut Vec<u8>) -> Option<git_object::Kind> + Send + Clone,
         P1: Progress,
         P2: Progress,
         MBFN: Fn(&mut T, &mut <P1 as Progress>::SubProgress, Context<'_, S>) -> Result<(), E> + Send + Clone,
@@ -131,27 +146,32 @@ where
         size_progress.init(None, progress::bytes());
         let size_counter = size_progress.counter();
         let child_items = self.child_items.as_mut_slice();
+        let mut external_bases = ExternalBase::from_children(std::mem::take(&mut self.external_base_children));
 
         let start = std::time::Instant::now();
+        let new_thread_state = {
+            let object_progress = object_progress.clone();
+            let child_items = ItemSliceSend(child_items as *mut [Item<T>]);
+            move |thread_index| {
+                (
+                    Vec::<u8>::with_capacity(4096),
+                    lock(&object_progress)
+                        .add_child_with_id(format!("thread {}", thread_index), git_features::progress::UNKNOWN),
+                    new_thread_state(),
+                    resolve.clone(),
+                    resolve_external_base.clone(),
+                    inspect_object.clone(),
+                    ItemSliceSend(child_items.0),
+                )
+            }
+        };
         in_parallel_with_slice(
             &mut self.root_items,
             thread_limit,
+            new_thread_state.clone(),
             {
-                let object_progress = object_progress.clone();
-                let child_items = ItemSliceSend(child_items as *mut [Item<T>]);
-                move |thread_index| {
-                    (
-                        Vec::<u8>::with_capacity(4096),
-                        lock(&object_progress)
-                            .add_child_with_id(format!("thread {}", thread_index), git_features::progress::UNKNOWN),
-                        new_thread_state(),
-                        resolve.clone(),
-                        inspect_object.clone(),
-                        ItemSliceSend(child_items.0),
-                    )
-                }
-            },
-            {
+                let object_counter = object_counter.clone();
+                let size_counter = size_counter.clone();
                 move |node, state| {
                     resolve::deltas(
                         object_counter.clone(),
@@ -165,6 +185,24 @@ where
             || (!should_interrupt.load(Ordering::Relaxed)).then(|| std::time::Duration::from_millis(50)),
             |_| (),
         )?;
+        if !external_bases.is_empty() {
+            in_parallel_with_slice(
+                &mut external_bases,
+                thread_limit,
+                new_thread_state,
+                move |base, state| {
+                    resolve::deltas_of_external_base(
+                        object_counter.clone(),
+                        size_counter.clone(),
+                        base,
+                        state,
+                        object_hash.len_in_bytes(),
+                    )
+                },
+                || (!should_interrupt.load(Ordering::Relaxed)).then(|| std::time::Duration::from_millis(50)),
+                |_| (),
+            )?;
+        }
 
         lock(&object_progress).show_throughput(start);
         size_progress.show_throughput(start);
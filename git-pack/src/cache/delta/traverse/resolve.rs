@@ -1,4 +1,4 @@
-use std::{cell::RefCell, collections::BTreeMap, sync::atomic::Ordering};
+use std::{collections::BTreeMap, sync::atomic::Ordering};
 
 use git_features::{
     progress::{unit, Progress},
@@ -8,7 +8,7 @@ use git_features::{
 use crate::{
     cache::delta::{
         traverse::{
-            util::{ItemSliceSend, Node},
+            util::{ExternalBase, ItemSliceSend, Node},
             Context, Error,
         },
         Item,
@@ -16,18 +16,24 @@ use crate::{
     data::EntryRange,
 };
 
-pub(crate) fn deltas<T, F, P, MBFN, S, E>(
+/// The state of each thread, with `F` resolving pack entries, `RB` resolving bases outside of the pack and `MBFN` inspecting objects.
+pub(crate) type State<T, P, S, F, RB, MBFN> = (Vec<u8>, P, S, F, RB, MBFN, ItemSliceSend<Item<T>>);
+
+/// A decoded pack entry, the offset at which it ends in the pack, and its fully resolved object data.
+type Resolved = (crate::data::Entry, u64, Vec<u8>);
+
+pub(crate) fn deltas<T, F, RB, P, MBFN, S, E>(
     object_counter: Option<git_features::progress::StepShared>,
     size_counter: Option<git_features::progress::StepShared>,
     node: &mut crate::cache::delta::Item<T>,
-    (bytes_buf, ref mut progress, state, resolve, modify_base, child_items): &mut (
-        Vec<u8>,
+    (bytes_buf, ref mut progress, state, resolve, _resolve_external_base, modify_base, child_items): &mut State<
+        T,
         P,
         S,
         F,
+        RB,
         MBFN,
-        ItemSliceSend<Item<T>>,
-    ),
+    >,
     hash_len: usize,
 ) -> Result<(), Error>
 where
@@ -37,117 +43,228 @@ where
     MBFN: Fn(&mut T, &mut P, Context<'_, S>) -> Result<(), E>,
     E: std::error::Error + Send + Sync + 'static,
 {
-    let mut decompressed_bytes_by_pack_offset = BTreeMap::new();
-    let bytes_buf = RefCell::new(bytes_buf);
-    let decompress_from_resolver = |slice: EntryRange| -> Result<(crate::data::Entry, u64, Vec<u8>), Error> {
-        let mut bytes_buf = bytes_buf.borrow_mut();
-        bytes_buf.resize((slice.end - slice.start) as usize, 0);
-        resolve(slice.clone(), &mut bytes_buf).ok_or(Error::ResolveFailed {
-            pack_offset: slice.start,
-        })?;
-        let entry = crate::data::Entry::from_bytes(&bytes_buf, slice.start, hash_len);
-        let compressed = &bytes_buf[entry.header_size() as usize..];
-        let decompressed_len = entry.decompressed_size as usize;
-        Ok((entry, slice.end, decompress_all_at_once(compressed, decompressed_len)?))
+    let node = Node {
+        item: node,
+        child_items: child_items.0,
     };
-
-    // Traverse the tree breadth first and loose the data produced for the base as it won't be needed anymore.
-    progress.init(
-        None,
-        Some(unit::dynamic(unit::Human::new(
-            unit::human::Formatter::new(),
-            "objects",
-        ))),
+    let mut decompressed_bytes_by_pack_offset = BTreeMap::new();
+    decompressed_bytes_by_pack_offset.insert(
+        node.offset(),
+        decompress_from_resolver(resolve, bytes_buf, node.entry_slice(), hash_len)?,
     );
 
-    // each node is a base, and its children always start out as deltas which become a base after applying them.
-    // These will be pushed onto our stack until all are processed
     let root_level = 0;
-    let mut nodes: Vec<_> = vec![(
-        root_level,
-        Node {
-            item: node,
-            child_items: child_items.0,
-        },
-    )];
-    while let Some((level, mut base)) = nodes.pop() {
-        let (base_entry, entry_end, base_bytes) = if level == root_level {
-            decompress_from_resolver(base.entry_slice())?
-        } else {
-            decompressed_bytes_by_pack_offset
+    Traversal {
+        object_counter,
+        size_counter,
+        bytes_buf,
+        progress,
+        state,
+        resolve,
+        modify_base,
+        hash_len,
+        nodes: vec![(root_level, node)],
+        decompressed_bytes_by_pack_offset,
+    }
+    .run()
+}
+
+/// Resolve all pack entries using `base` as their base object, which is obtained with `resolve_external_base` as it isn't part
+/// of the pack.
+///
+/// The base itself isn't passed to `modify_base`, but all of its children are, with their level starting at 1.
+pub(crate) fn deltas_of_external_base<T, F, RB, P, MBFN, S, E>(
+    object_counter: Option<git_features::progress::StepShared>,
+    size_counter: Option<git_features::progress::StepShared>,
+    base: &mut ExternalBase,
+    (bytes_buf, ref mut progress, state, resolve, resolve_external_base, modify_base, child_items): &mut State<
+        T,
+        P,
+        S,
+        F,
+        RB,
+        MBFN,
+    >,
+    hash_len: usize,
+) -> Result<(), Error>
+where
+    T: Send,
+    F: for<'r> Fn(EntryRange, &'r mut Vec<u8>) -> Option<()>,
+    RB: for<'r> Fn(&git_hash::oid, &'r mut Vec<u8>) -> Option<git_object::Kind>,
+    P: Progress,
+    MBFN: Fn(&mut T, &mut P, Context<'_, S>) -> Result<(), E>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let mut base_bytes = Vec::new();
+    let kind = resolve_external_base(&base.id, &mut base_bytes).ok_or(Error::UnresolvedRefDeltaBase { id: base.id })?;
+
+    let mut traversal = Traversal {
+        object_counter,
+        size_counter,
+        bytes_buf,
+        progress,
+        state,
+        resolve,
+        modify_base,
+        hash_len,
+        nodes: Vec::new(),
+        decompressed_bytes_by_pack_offset: BTreeMap::new(),
+    };
+    let external_level = 0;
+    for child in base.child_nodes(child_items.0) {
+        let (child_entry, entry_end) =
+            traversal.resolve_delta(&child, crate::data::entry::Header::from_kind(kind), &base_bytes)?;
+        traversal
+            .decompressed_bytes_by_pack_offset
+            .insert(child.offset(), (child_entry, entry_end, traversal.bytes_buf.to_owned()));
+        traversal.nodes.push((external_level + 1, child));
+    }
+    traversal.run()
+}
+
+struct Traversal<'a, T, F, P, MBFN, S> {
+    object_counter: Option<git_features::progress::StepShared>,
+    size_counter: Option<git_features::progress::StepShared>,
+    bytes_buf: &'a mut Vec<u8>,
+    progress: &'a mut P,
+    state: &'a mut S,
+    resolve: &'a F,
+    modify_base: &'a MBFN,
+    hash_len: usize,
+    /// Nodes whose fully resolved object is stored in `decompressed_bytes_by_pack_offset`, along with their level.
+    nodes: Vec<(u16, Node<'a, T>)>,
+    decompressed_bytes_by_pack_offset: BTreeMap<u64, Resolved>,
+}
+
+impl<'a, T, F, P, MBFN, S, E> Traversal<'a, T, F, P, MBFN, S>
+where
+    T: Send,
+    F: for<'r> Fn(EntryRange, &'r mut Vec<u8>) -> Option<()>,
+    P: Progress,
+    MBFN: Fn(&mut T, &mut P, Context<'_, S>) -> Result<(), E>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    fn run(mut self) -> Result<(), Error> {
+        // Traverse the tree breadth first and loose the data produced for the base as it won't be needed anymore.
+        self.progress.init(
+            None,
+            Some(unit::dynamic(unit::Human::new(
+                unit::human::Formatter::new(),
+                "objects",
+            ))),
+        );
+
+        // each node is a base, and its children always start out as deltas which become a base after applying them.
+        // These will be pushed onto our stack until all are processed
+        while let Some((level, mut base)) = self.nodes.pop() {
+            let (base_entry, entry_end, base_bytes) = self
+                .decompressed_bytes_by_pack_offset
                 .remove(&base.offset())
-                .expect("we store the resolved delta buffer when done")
-        };
-
-        // anything done here must be repeated further down for leaf-nodes.
-        // This way we avoid retaining their decompressed memory longer than needed (they have no children,
-        // thus their memory can be released right away, using 18% less peak memory on the linux kernel).
-        {
-            modify_base(
-                base.data(),
-                progress,
-                Context {
-                    entry: &base_entry,
-                    entry_end,
-                    decompressed: &base_bytes,
-                    state,
-                    level,
-                },
-            )
-            .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync>)?;
-            object_counter.as_ref().map(|c| c.fetch_add(1, Ordering::SeqCst));
-            size_counter
-                .as_ref()
-                .map(|c| c.fetch_add(base_bytes.len(), Ordering::SeqCst));
-        }
+                .expect("we store the resolved delta buffer when done");
+
+            // anything done here must be repeated further down for leaf-nodes.
+            // This way we avoid retaining their decompressed memory longer than needed (they have no children,
+            // thus their memory can be released right away, using 18% less peak memory on the linux kernel).
+            self.inspect(base.data(), &base_entry, entry_end, &base_bytes, level)?;
 
-        for mut child in base.into_child_iter() {
-            let (mut child_entry, entry_end, delta_bytes) = decompress_from_resolver(child.entry_slice())?;
-            let (base_size, consumed) = crate::data::delta::decode_header_size(&delta_bytes);
-            let mut header_ofs = consumed;
-            assert_eq!(
-                base_bytes.len(),
-                base_size as usize,
-                "recorded base size in delta does not match"
-            );
-            let (result_size, consumed) = crate::data::delta::decode_header_size(&delta_bytes[consumed..]);
-            header_ofs += consumed;
-
-            let mut fully_resolved_delta_bytes = bytes_buf.borrow_mut();
-            fully_resolved_delta_bytes.resize(result_size as usize, 0);
-            crate::data::delta::apply(&base_bytes, &mut fully_resolved_delta_bytes, &delta_bytes[header_ofs..]);
-
-            // FIXME: this actually invalidates the "pack_offset()" computation, which is not obvious to consumers
-            //        at all
-            child_entry.header = base_entry.header; // assign the actual object type, instead of 'delta'
-            if child.has_children() {
-                decompressed_bytes_by_pack_offset.insert(
-                    child.offset(),
-                    (child_entry, entry_end, fully_resolved_delta_bytes.to_owned()),
-                );
-                nodes.push((level + 1, child));
-            } else {
-                modify_base(
-                    child.data(),
-                    progress,
-                    Context {
-                        entry: &child_entry,
+            for mut child in base.into_child_iter() {
+                let (child_entry, entry_end) = self.resolve_delta(&child, base_entry.header, &base_bytes)?;
+                if child.has_children() {
+                    self.decompressed_bytes_by_pack_offset
+                        .insert(child.offset(), (child_entry, entry_end, self.bytes_buf.to_owned()));
+                    self.nodes.push((level + 1, child));
+                } else {
+                    let fully_resolved_delta_bytes = std::mem::take(self.bytes_buf);
+                    let res = self.inspect(
+                        child.data(),
+                        &child_entry,
                         entry_end,
-                        decompressed: &fully_resolved_delta_bytes,
-                        state,
-                        level: level + 1,
-                    },
-                )
-                .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync>)?;
-                object_counter.as_ref().map(|c| c.fetch_add(1, Ordering::SeqCst));
-                size_counter
-                    .as_ref()
-                    .map(|c| c.fetch_add(base_bytes.len(), Ordering::SeqCst));
+                        &fully_resolved_delta_bytes,
+                        level + 1,
+                    );
+                    *self.bytes_buf = fully_resolved_delta_bytes;
+                    res?;
+                }
             }
         }
+
+        Ok(())
+    }
+
+    fn inspect(
+        &mut self,
+        data: &mut T,
+        entry: &crate::data::Entry,
+        entry_end: u64,
+        decompressed: &[u8],
+        level: u16,
+    ) -> Result<(), Error> {
+        (self.modify_base)(
+            data,
+            self.progress,
+            Context {
+                entry,
+                entry_end,
+                decompressed,
+                state: self.state,
+                level,
+            },
+        )
+        .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync>)?;
+        self.object_counter.as_ref().map(|c| c.fetch_add(1, Ordering::SeqCst));
+        self.size_counter
+            .as_ref()
+            .map(|c| c.fetch_add(decompressed.len(), Ordering::SeqCst));
+        Ok(())
+    }
+
+    /// Apply the delta stored in the pack entry of `child` to `base_bytes`, with `base_header` being the header of the fully resolved
+    /// base object, and store the result in our `bytes_buf`.
+    fn resolve_delta(
+        &mut self,
+        child: &Node<'_, T>,
+        base_header: crate::data::entry::Header,
+        base_bytes: &[u8],
+    ) -> Result<(crate::data::Entry, u64), Error> {
+        let (mut child_entry, entry_end, delta_bytes) =
+            decompress_from_resolver(self.resolve, self.bytes_buf, child.entry_slice(), self.hash_len)?;
+        let (base_size, consumed) = crate::data::delta::decode_header_size(&delta_bytes);
+        let mut header_ofs = consumed;
+        assert_eq!(
+            base_bytes.len(),
+            base_size as usize,
+            "recorded base size in delta does not match"
+        );
+        let (result_size, consumed) = crate::data::delta::decode_header_size(&delta_bytes[consumed..]);
+        header_ofs += consumed;
+
+        self.bytes_buf.resize(result_size as usize, 0);
+        crate::data::delta::apply(base_bytes, self.bytes_buf, &delta_bytes[header_ofs..]);
+
+        // FIXME: this actually invalidates the "pack_offset()" computation, which is not obvious to consumers
+        //        at all
+        child_entry.header = base_header; // assign the actual object type, instead of 'delta'
+        Ok((child_entry, entry_end))
     }
+}
 
-    Ok(())
+fn decompress_from_resolver<F>(
+    resolve: &F,
+    bytes_buf: &mut Vec<u8>,
+    slice: EntryRange,
+    hash_len: usize,
+) -> Result<Resolved, Error>
+where
+    F: for<'r> Fn(EntryRange, &'r mut Vec<u8>) -> Option<()>,
+{
+    bytes_buf.resize((slice.end - slice.start) as usize, 0);
+    resolve(slice.clone(), bytes_buf).ok_or(Error::ResolveFailed {
+        pack_offset: slice.start,
+    })?;
+    let entry = crate::data::Entry::from_bytes(bytes_buf, slice.start, hash_len);
+    let compressed = &bytes_buf[entry.header_size()..];
+    let decompressed_len = entry.decompressed_size as usize;
+    Ok((entry, slice.end, decompress_all_at_once(compressed, decompressed_len)?))
 }
 
 fn decompress_all_at_once(b: &[u8], decompressed_len: usize) -> Result<Vec<u8>, Error> {
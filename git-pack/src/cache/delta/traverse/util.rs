@@ -48,16 +48,54 @@ impl<'a, T> Node<'a, T> {
     ///
     /// Children are `Node`s referring to pack entries whose base object is this pack entry.
     pub fn into_child_iter(self) -> impl Iterator<Item = Node<'a, T>> + 'a {
-        let children = self.child_items;
-        self.item.children.iter().map(move |&index| {
-            // SAFETY: The children array is alive by the 'a lifetime.
-            // SAFETY: The index is a valid index into the children array.
-            // SAFETY: The resulting mutable pointer cannot be yielded by any other node.
-            #[allow(unsafe_code)]
-            Node {
-                item: unsafe { &mut *(children as *mut Item<T>).add(index as usize) },
-                child_items: children,
+        child_nodes(&self.item.children, self.child_items)
+    }
+}
+
+/// A base object that isn't contained in the pack, along with the indices of all pack entries that refer to it as ref-delta.
+pub struct ExternalBase {
+    pub id: git_hash::ObjectId,
+    pub children: Vec<u32>,
+}
+
+impl ExternalBase {
+    /// Group `children` by the id of their base object.
+    pub fn from_children(mut children: Vec<(git_hash::ObjectId, u32)>) -> Vec<Self> {
+        children.sort_by_key(|(id, _)| *id);
+        let mut out = Vec::<ExternalBase>::new();
+        for (id, child_index) in children {
+            match out.last_mut() {
+                Some(base) if base.id == id => base.children.push(child_index),
+                _ => out.push(ExternalBase {
+                    id,
+                    children: vec![child_index],
+                }),
             }
-        })
+        }
+        out
     }
+
+    /// Return all `Node`s referring to pack entries whose base object is this external base.
+    pub fn child_nodes<'a, T>(&'a self, child_items: *mut [Item<T>]) -> impl Iterator<Item = Node<'a, T>> + 'a
+    where
+        T: 'a,
+    {
+        child_nodes(&self.children, child_items)
+    }
+}
+
+fn child_nodes<'a, T>(indices: &'a [u32], children: *mut [Item<T>]) -> impl Iterator<Item = Node<'a, T>> + 'a
+where
+    T: 'a,
+{
+    indices.iter().map(move |&index| {
+        // SAFETY: The children array is alive by the 'a lifetime.
+        // SAFETY: The index is a valid index into the children array.
+        // SAFETY: The resulting mutable pointer cannot be yielded by any other node.
+        #[allow(unsafe_code)]
+        Node {
+            item: unsafe { &mut *(children as *mut Item<T>).add(index as usize) },
+            child_items: children,
+        }
+    })
 }
@@ -68,3 +68,209 @@ pub fn apply(base: &[u8], mut target: &mut [u8], data: &[u8]) {
     assert_eq!(i, data.len());
     assert_eq!(target.len(), 0);
 }
+
+/// The amount of bytes hashed at once to find matching blocks between base and target.
+const BLOCK_SIZE: usize = 16;
+/// The maximum amount of base offsets kept per block hash, to avoid quadratic behaviour with repetitive data.
+const MAX_OFFSETS_PER_HASH: usize = 64;
+/// The largest amount of bytes a single copy instruction can express without using its special encoding for zero.
+const MAX_COPY_SIZE: usize = 0x10000;
+/// The largest amount of bytes a single insert instruction can carry.
+const MAX_INSERT_SIZE: usize = 0x7f;
+/// A prime for polynomial rolling hashes over `BLOCK_SIZE` bytes.
+const HASH_PRIME: u32 = 0x0100_0193;
+
+/// Encode a delta which produces `target` when [applied][apply()] to `base` and append it to `out`.
+/// Return `false` if the delta would be larger than `max_size`, leaving `out` in an unspecified state.
+///
+/// The encoding is compatible with what's expected by [`decode_header_size()`] and [`apply()`], and thus with
+/// what `git` produces.
+pub fn encode(base: &[u8], target: &[u8], max_size: usize, out: &mut Vec<u8>) -> bool {
+    let start = out.len();
+    encode_header_size(base.len() as u64, out);
+    encode_header_size(target.len() as u64, out);
+
+    let index = block_index(base);
+    let max_out = start + max_size;
+    let high_power = (1..BLOCK_SIZE).fold(1u32, |p, _| p.wrapping_mul(HASH_PRIME));
+
+    let mut insert_start = 0;
+    let mut pos = 0;
+    let mut hash = (target.len() >= BLOCK_SIZE).then(|| block_hash(&target[..BLOCK_SIZE]));
+    while let Some(current_hash) = hash {
+        let mut best: Option<(usize, usize)> = None;
+        if let Some(offsets) = index.get(&current_hash) {
+            for &base_ofs in offsets {
+                let len = common_prefix_len(&base[base_ofs..], &target[pos..]);
+                if len >= BLOCK_SIZE && best.map_or(true, |(_, best_len)| len > best_len) {
+                    best = Some((base_ofs, len));
+                }
+            }
+        }
+
+        match best {
+            Some((mut base_ofs, mut len)) => {
+                let mut copy_start = pos;
+                while copy_start > insert_start && base_ofs > 0 && base[base_ofs - 1] == target[copy_start - 1] {
+                    copy_start -= 1;
+                    base_ofs -= 1;
+                    len += 1;
+                }
+                encode_insert(&target[insert_start..copy_start], out);
+                encode_copy(base_ofs, len, out);
+                if out.len() > max_out {
+                    return false;
+                }
+                pos = copy_start + len;
+                insert_start = pos;
+                hash = (target.len() - pos >= BLOCK_SIZE).then(|| block_hash(&target[pos..][..BLOCK_SIZE]));
+            }
+            None => {
+                if pos + BLOCK_SIZE >= target.len() {
+                    break;
+                }
+                hash = Some(
+                    current_hash
+                        .wrapping_sub((target[pos] as u32).wrapping_mul(high_power))
+                        .wrapping_mul(HASH_PRIME)
+                        .wrapping_add(target[pos + BLOCK_SIZE] as u32),
+                );
+                pos += 1;
+                if pos - insert_start > max_size {
+                    return false;
+                }
+            }
+        }
+    }
+    encode_insert(&target[insert_start..], out);
+    out.len() <= max_out
+}
+
+fn block_hash(block: &[u8]) -> u32 {
+    block
+        .iter()
+        .fold(0u32, |hash, b| hash.wrapping_mul(HASH_PRIME).wrapping_add(*b as u32))
+}
+
+fn block_index(base: &[u8]) -> std::collections::HashMap<u32, Vec<usize>> {
+    let mut index = std::collections::HashMap::<u32, Vec<usize>>::with_capacity(base.len() / BLOCK_SIZE);
+    // Copy instructions can only address the first 4GB of the base.
+    let indexable = &base[..base.len().min(u32::MAX as usize)];
+    for (block_idx, block) in indexable.chunks_exact(BLOCK_SIZE).enumerate() {
+        let offsets = index.entry(block_hash(block)).or_default();
+        if offsets.len() < MAX_OFFSETS_PER_HASH {
+            offsets.push(block_idx * BLOCK_SIZE);
+        }
+    }
+    index
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    let max = a.len().min(b.len()).min(u32::MAX as usize - 1);
+    a[..max].iter().zip(&b[..max]).take_while(|(a, b)| a == b).count()
+}
+
+fn encode_header_size(mut size: u64, out: &mut Vec<u8>) {
+    loop {
+        let byte = (size & 0x7f) as u8;
+        size >>= 7;
+        if size == 0 {
+            out.push(byte);
+            break;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn encode_insert(mut data: &[u8], out: &mut Vec<u8>) {
+    while !data.is_empty() {
+        let len = data.len().min(MAX_INSERT_SIZE);
+        out.push(len as u8);
+        out.extend_from_slice(&data[..len]);
+        data = &data[len..];
+    }
+}
+
+fn encode_copy(mut ofs: usize, mut len: usize, out: &mut Vec<u8>) {
+    while len != 0 {
+        let size = len.min(MAX_COPY_SIZE);
+        let cmd_pos = out.len();
+        let mut cmd = 0b1000_0000;
+        out.push(cmd);
+        for byte_idx in 0..4 {
+            let byte = (ofs >> (byte_idx * 8)) as u8;
+            if byte != 0 {
+                cmd |= 1 << byte_idx;
+                out.push(byte);
+            }
+        }
+        // a size of zero stands for `MAX_COPY_SIZE`, which is why it doesn't need to be encoded.
+        if size != MAX_COPY_SIZE {
+            for byte_idx in 0..3 {
+                let byte = (size >> (byte_idx * 8)) as u8;
+                if byte != 0 {
+                    cmd |= 0b0001_0000 << byte_idx;
+                    out.push(byte);
+                }
+            }
+        }
+        out[cmd_pos] = cmd;
+        ofs += size;
+        len -= size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, decode_header_size, encode};
+
+    fn round_trip(base: &[u8], target: &[u8]) -> usize {
+        let mut delta = Vec::new();
+        assert!(
+            encode(base, target, usize::MAX, &mut delta),
+            "unlimited deltas always fit"
+        );
+        let (base_size, consumed) = decode_header_size(&delta);
+        assert_eq!(base_size as usize, base.len());
+        let (target_size, consumed_2) = decode_header_size(&delta[consumed..]);
+        assert_eq!(target_size as usize, target.len());
+
+        let mut out = vec![0; target.len()];
+        apply(base, &mut out, &delta[consumed + consumed_2..]);
+        assert_eq!(out, target, "the delta reproduces the target");
+        delta.len()
+    }
+
+    #[test]
+    fn similar_inputs_produce_small_deltas() {
+        let base: Vec<u8> = (0..20_000u32).flat_map(|n| n.to_string().into_bytes()).collect();
+        let mut target = base.clone();
+        target.splice(1000..1010, b"inserted in the middle".iter().copied());
+        target.extend_from_slice(b"and appended at the end");
+        target.drain(50_000..51_000);
+
+        let delta_len = round_trip(&base, &target);
+        assert!(
+            delta_len < 200,
+            "{} bytes: large copies are used for what's unchanged",
+            delta_len
+        );
+    }
+
+    #[test]
+    fn dissimilar_and_small_inputs() {
+        round_trip(b"", b"");
+        round_trip(b"", b"hello");
+        round_trip(b"hello", b"");
+        round_trip(b"short base", b"short target");
+        let base: Vec<u8> = (0..=255u8).cycle().take(200_000).collect();
+        round_trip(&base, &base);
+        round_trip(&base[1..], &base[..150_000]);
+    }
+
+    #[test]
+    fn deltas_larger_than_max_size_are_rejected() {
+        let mut delta = Vec::new();
+        assert!(!encode(b"abc", b"completely different content", 10, &mut delta));
+    }
+}
@@ -45,6 +45,16 @@ impl Header {
         }
         pack_offset.checked_sub(distance)
     }
+    /// Create the header of a base object of the given `kind`.
+    pub fn from_kind(kind: git_object::Kind) -> Self {
+        use git_object::Kind::*;
+        match kind {
+            Tree => Header::Tree,
+            Blob => Header::Blob,
+            Commit => Header::Commit,
+            Tag => Header::Tag,
+        }
+    }
     /// Convert the header's object kind into [`git_object::Kind`] if possible
     pub fn as_kind(&self) -> Option<git_object::Kind> {
         use git_object::Kind::*;
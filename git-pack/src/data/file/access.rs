@@ -0,0 +1,290 @@
+use std::{
+    convert::TryInto,
+    io,
+    ops::{Deref, Range},
+    path::Path,
+    sync::Arc,
+};
+
+use memmap2::Mmap;
+use parking_lot::Mutex;
+
+use crate::data::Mapping;
+
+/// The bytes of a pack data file, either mapped in full or read through a bounded set of windows.
+pub(crate) enum Data {
+    Mapped(Mmap),
+    Windowed(Windows),
+}
+
+impl Data {
+    pub fn open(path: &Path, mapping: Mapping) -> io::Result<Self> {
+        Ok(match mapping {
+            Mapping::Whole => Data::Mapped(crate::mmap::read_only(path)?),
+            Mapping::Windowed { window_size, limit } => Data::Windowed(Windows::open(path, window_size, limit)?),
+        })
+    }
+
+    pub fn len(&self) -> u64 {
+        match self {
+            Data::Mapped(data) => data.len() as u64,
+            Data::Windowed(windows) => windows.len,
+        }
+    }
+
+    /// Return the bytes in `range`, which are borrowed from the mapping or a window if possible.
+    pub fn bytes(&self, range: Range<u64>) -> io::Result<Bytes<'_>> {
+        if range.start > range.end || range.end > self.len() {
+            return Err(out_of_bounds(range.end));
+        }
+        Ok(match self {
+            Data::Mapped(data) => Bytes::Mapped(&data[range.start as usize..range.end as usize]),
+            Data::Windowed(_) if range.start == range.end => Bytes::Owned(Vec::new()),
+            Data::Windowed(windows) => {
+                let (window_start, window) = windows.window_at(range.start)?;
+                if range.end <= window_start + window.len() as u64 {
+                    let start = (range.start - window_start) as usize;
+                    Bytes::Window {
+                        range: start..start + (range.end - range.start) as usize,
+                        data: window,
+                    }
+                } else {
+                    let mut out = vec![0; (range.end - range.start).try_into().expect("range fits into memory")];
+                    windows.read_exact_at(range.start, &mut out)?;
+                    Bytes::Owned(out)
+                }
+            }
+        })
+    }
+
+    /// Fill `out` with the bytes starting at `offset`.
+    pub fn read_exact_at(&self, offset: u64, out: &mut [u8]) -> io::Result<()> {
+        match self {
+            Data::Mapped(_) => {
+                let bytes = self.bytes(offset..offset + out.len() as u64)?;
+                out.copy_from_slice(&bytes);
+                Ok(())
+            }
+            Data::Windowed(windows) => windows.read_exact_at(offset, out),
+        }
+    }
+
+    /// Call `consume` with consecutive chunks of bytes starting at `offset` until it returns `false` or the end of the data is reached.
+    pub fn for_each_chunk_from<E>(
+        &self,
+        offset: u64,
+        mut consume: impl FnMut(&[u8]) -> Result<bool, E>,
+    ) -> Result<(), E>
+    where
+        E: From<io::Error>,
+    {
+        match self {
+            Data::Mapped(data) => {
+                let start: usize = offset.try_into().expect("offset representable by machine");
+                let chunk = data.get(start..).ok_or_else(|| out_of_bounds(offset))?;
+                consume(chunk)?;
+            }
+            Data::Windowed(windows) => {
+                let mut offset = offset;
+                while offset < windows.len {
+                    let (window_start, window) = windows.window_at(offset)?;
+                    let chunk = &window[(offset - window_start) as usize..];
+                    if !consume(chunk)? {
+                        break;
+                    }
+                    offset += chunk.len() as u64;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the mapped data if the whole file is mapped.
+    pub fn as_mapped(&self) -> Option<&[u8]> {
+        match self {
+            Data::Mapped(data) => Some(data),
+            Data::Windowed(_) => None,
+        }
+    }
+}
+
+/// Bytes of pack data as returned by [`Data::bytes()`].
+pub(crate) enum Bytes<'a> {
+    /// A slice of a fully mapped pack.
+    Mapped(&'a [u8]),
+    /// A `range` of a window, which is shared with the window cache.
+    Window { data: Arc<[u8]>, range: Range<usize> },
+    /// A copy of bytes spanning multiple windows.
+    Owned(Vec<u8>),
+}
+
+impl Deref for Bytes<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Bytes::Mapped(data) => data,
+            Bytes::Window { data, range } => &data[range.clone()],
+            Bytes::Owned(data) => data,
+        }
+    }
+}
+
+fn out_of_bounds(offset: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        format!("Pack offset {} is out of bounds", offset),
+    )
+}
+
+/// Read a file through windows of a fixed size, keeping a limited amount of them in memory.
+///
+/// Windows are read with positional reads without holding any lock, so threads only wait for each other while
+/// looking up or inserting windows.
+pub(crate) struct Windows {
+    file: std::fs::File,
+    /// Platforms without positional reads have to seek, which may only be done by one thread at a time.
+    #[cfg(not(any(unix, windows)))]
+    seek_lock: Mutex<()>,
+    len: u64,
+    window_size: u64,
+    limit: u64,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    windows: Vec<Window>,
+    /// The sum of the lengths of all `windows`.
+    num_bytes: u64,
+    /// A counter to determine which window was used least recently.
+    clock: u64,
+}
+
+struct Window {
+    start: u64,
+    data: Arc<[u8]>,
+    last_used: u64,
+}
+
+impl Windows {
+    fn open(path: &Path, window_size: usize, limit: usize) -> io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Windows {
+            file,
+            #[cfg(not(any(unix, windows)))]
+            seek_lock: Mutex::new(()),
+            len,
+            window_size: window_size.max(1) as u64,
+            limit: limit as u64,
+            state: Default::default(),
+        })
+    }
+
+    fn read_exact_at(&self, mut offset: u64, mut out: &mut [u8]) -> io::Result<()> {
+        if offset + out.len() as u64 > self.len {
+            return Err(out_of_bounds(offset + out.len() as u64));
+        }
+        while !out.is_empty() {
+            let (window_start, window) = self.window_at(offset)?;
+            let chunk = &window[(offset - window_start) as usize..];
+            let len = chunk.len().min(out.len());
+            out[..len].copy_from_slice(&chunk[..len]);
+            out = &mut out[len..];
+            offset += len as u64;
+        }
+        Ok(())
+    }
+
+    /// Return the window containing `offset` along with the offset at which it starts, reading it from disk if needed.
+    fn window_at(&self, offset: u64) -> io::Result<(u64, Arc<[u8]>)> {
+        if offset >= self.len {
+            return Err(out_of_bounds(offset));
+        }
+        if let Some(window) = self.state.lock().use_window_at(offset) {
+            return Ok(window);
+        }
+
+        let start = offset - offset % self.window_size;
+        let len = self.window_size.min(self.len - start);
+        let mut data = vec![0; len as usize];
+        self.read_exact_from_file(start, &mut data)?;
+
+        let mut state = self.state.lock();
+        // Another thread may have read the same window in the meantime.
+        if let Some(window) = state.use_window_at(offset) {
+            return Ok(window);
+        }
+        while state.num_bytes + len > self.limit {
+            let least_recently_used = match state
+                .windows
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, w)| w.last_used)
+                .map(|(idx, _)| idx)
+            {
+                Some(idx) => idx,
+                None => break,
+            };
+            let window = state.windows.swap_remove(least_recently_used);
+            state.num_bytes -= window.data.len() as u64;
+        }
+
+        let data: Arc<[u8]> = data.into();
+        state.clock += 1;
+        let clock = state.clock;
+        state.num_bytes += len;
+        state.windows.push(Window {
+            start,
+            data: data.clone(),
+            last_used: clock,
+        });
+        Ok((start, data))
+    }
+
+    #[cfg(unix)]
+    fn read_exact_from_file(&self, offset: u64, out: &mut [u8]) -> io::Result<()> {
+        std::os::unix::fs::FileExt::read_exact_at(&self.file, out, offset)
+    }
+
+    #[cfg(windows)]
+    fn read_exact_from_file(&self, mut offset: u64, mut out: &mut [u8]) -> io::Result<()> {
+        use std::os::windows::fs::FileExt;
+        while !out.is_empty() {
+            match self.file.seek_read(out, offset) {
+                Ok(0) => return Err(out_of_bounds(offset)),
+                Ok(n) => {
+                    out = &mut out[n..];
+                    offset += n as u64;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn read_exact_from_file(&self, offset: u64, out: &mut [u8]) -> io::Result<()> {
+        use std::io::{Read, Seek, SeekFrom};
+        let _lock = self.seek_lock.lock();
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(out)
+    }
+}
+
+impl State {
+    /// Return the window containing `offset` and its start, marking it as most recently used, if it is present.
+    fn use_window_at(&mut self, offset: u64) -> Option<(u64, Arc<[u8]>)> {
+        self.clock += 1;
+        let clock = self.clock;
+        let window = self
+            .windows
+            .iter_mut()
+            .find(|w| w.start <= offset && offset < w.start + w.data.len() as u64)?;
+        window.last_used = clock;
+        Some((window.start, window.data.clone()))
+    }
+}
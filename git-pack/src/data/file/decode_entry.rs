@@ -17,6 +17,8 @@ pub enum Error {
     ZlibInflate(#[from] zlib::inflate::Error),
     #[error("A delta chain could not be applied as the ref base with id {0} could not be found")]
     DeltaBaseUnresolved(git_hash::ObjectId),
+    #[error("Failed to read pack data")]
+    Io(#[from] std::io::Error),
 }
 
 #[derive(Debug)]
@@ -102,13 +104,16 @@ impl File {
     /// Obtain the [`Entry`][crate::data::Entry] at the given `offset` into the pack.
     ///
     /// The `offset` is typically obtained from the pack index file.
-    pub fn entry(&self, offset: data::Offset) -> crate::data::Entry {
+    /// An error is returned if the entry header couldn't be read, which can only happen if the pack data isn't mapped
+    /// [as a whole][crate::data::Mapping::Whole] or if `offset` is out of bounds.
+    pub fn entry(&self, offset: data::Offset) -> Result<crate::data::Entry, std::io::Error> {
         self.assure_v2();
-        let pack_offset: usize = offset.try_into().expect("offset representable by machine");
-        assert!(pack_offset <= self.data.len(), "offset out of bounds");
-
-        let object_data = &self.data[pack_offset..];
-        crate::data::Entry::from_bytes(object_data, offset, self.hash_len)
+        // The type and size, along with an encoded offset or a hash as base.
+        const MAX_HEADER_LEN: u64 = 10 + 32 + 10;
+        let header = self
+            .data
+            .bytes(offset..(offset + MAX_HEADER_LEN).min(self.pack_end() as u64).max(offset))?;
+        crate::data::Entry::from_read(&*header, offset, self.hash_len)
     }
 
     /// Decompress the object expected at the given data offset, sans pack header. This information is only
@@ -117,13 +122,16 @@ impl File {
     /// `out` is expected to be large enough to hold `entry.size` bytes.
     /// Returns the amount of packed bytes there read from the pack data file.
     fn decompress_entry_from_data_offset(&self, data_offset: data::Offset, out: &mut [u8]) -> Result<usize, Error> {
-        let offset: usize = data_offset.try_into().expect("offset representable by machine");
-        assert!(offset < self.data.len(), "entry offset out of bounds");
-
-        zlib::Inflate::default()
-            .once(&self.data[offset..], out)
-            .map_err(Into::into)
-            .map(|(_status, consumed_in, _consumed_out)| consumed_in)
+        let mut inflate = zlib::Inflate::default();
+        let (mut consumed_in, mut consumed_out) = (0, 0);
+        self.data
+            .for_each_chunk_from(data_offset, |chunk| -> Result<bool, Error> {
+                let (status, read, written) = inflate.once(chunk, &mut out[consumed_out..])?;
+                consumed_in += read;
+                consumed_out += written;
+                Ok(status != zlib::Status::StreamEnd && consumed_out < out.len())
+            })?;
+        Ok(consumed_in)
     }
 
     /// Decode an entry, resolving delta's as needed, while growing the `out` vector if there is not enough
@@ -213,7 +221,7 @@ impl File {
             });
             use crate::data::entry::Header;
             cursor = match cursor.header {
-                Header::OfsDelta { base_distance } => self.entry(cursor.base_pack_offset(base_distance)),
+                Header::OfsDelta { base_distance } => self.entry(cursor.base_pack_offset(base_distance))?,
                 Header::RefDelta { base_id } => match resolve(base_id.as_ref(), out) {
                     Some(ResolvedBase::InPack(entry)) => entry,
                     Some(ResolvedBase::OutOfPack { end, kind }) => {
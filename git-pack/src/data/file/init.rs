@@ -9,34 +9,53 @@ impl data::File {
     /// The `object_hash` is a way to read (and write) the same file format with different hashes, as the hash kind
     /// isn't stored within the file format itself.
     pub fn at(path: impl AsRef<Path>, object_hash: git_hash::Kind) -> Result<data::File, data::header::decode::Error> {
-        Self::at_inner(path.as_ref(), object_hash)
+        Self::at_inner(path.as_ref(), object_hash, data::Mapping::default())
     }
 
-    fn at_inner(path: &Path, object_hash: git_hash::Kind) -> Result<data::File, data::header::decode::Error> {
+    /// Like [`at()`][data::File::at()], but determines how the file's content is accessed with `mapping`.
+    pub fn at_opts(
+        path: impl AsRef<Path>,
+        object_hash: git_hash::Kind,
+        mapping: data::Mapping,
+    ) -> Result<data::File, data::header::decode::Error> {
+        Self::at_inner(path.as_ref(), object_hash, mapping)
+    }
+
+    fn at_inner(
+        path: &Path,
+        object_hash: git_hash::Kind,
+        mapping: data::Mapping,
+    ) -> Result<data::File, data::header::decode::Error> {
         use crate::data::header::N32_SIZE;
         let hash_len = object_hash.len_in_bytes();
 
-        let data = crate::mmap::read_only(path).map_err(|e| data::header::decode::Error::Io {
-            source: e,
+        let io_err = |source| data::header::decode::Error::Io {
+            source,
             path: path.to_owned(),
-        })?;
+        };
+        let data = data::file::access::Data::open(path, mapping).map_err(io_err)?;
         let pack_len = data.len();
-        if pack_len < N32_SIZE * 3 + hash_len {
+        if pack_len < (N32_SIZE * 3 + hash_len) as u64 {
             return Err(data::header::decode::Error::Corrupt(format!(
                 "Pack data of size {} is too small for even an empty pack with shortest hash",
                 pack_len
             )));
         }
+        let header = data.bytes(0..12).map_err(io_err)?;
         let (kind, num_objects) =
-            data::header::decode(&data[..12].try_into().expect("enough data after previous check"))?;
+            data::header::decode(&header[..].try_into().expect("enough data after previous check"))?;
+        let checksum = git_hash::ObjectId::from(&data.bytes(pack_len - hash_len as u64..pack_len).map_err(io_err)?[..]);
+        drop(header);
         Ok(data::File {
             data,
             path: path.to_owned(),
+            checksum,
             id: git_features::hash::crc32(path.as_os_str().to_string_lossy().as_bytes()),
             version: kind,
             num_objects,
             hash_len,
             object_hash,
+            mapping,
         })
     }
 }
@@ -1,3 +1,4 @@
+pub(crate) mod access;
 ///
 pub mod decode_entry;
 mod init;
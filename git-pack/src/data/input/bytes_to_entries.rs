@@ -2,7 +2,7 @@ use std::{fs, io};
 
 use git_features::{
     hash,
-    hash::Sha1,
+    hash::Hasher,
     zlib::{stream::inflate::ReadBoxed, Decompress},
 };
 use git_hash::ObjectId;
@@ -19,7 +19,7 @@ pub struct BytesToEntriesIter<BR> {
     had_error: bool,
     version: crate::data::Version,
     objects_left: u32,
-    hash: Option<Sha1>,
+    hash: Option<Hasher>,
     mode: input::Mode,
     compressed: input::EntryDataMode,
     compressed_buf: Option<Vec<u8>>,
@@ -188,7 +188,7 @@ where
             }
 
             if let Some(hash) = self.hash.take() {
-                let actual_id = git_hash::ObjectId::from(hash.digest());
+                let actual_id = hash.try_digest()?;
                 if self.mode == input::Mode::Restore {
                     id = actual_id;
                 }
@@ -202,7 +202,7 @@ where
             Some(id)
         } else if self.mode == input::Mode::Restore {
             let hash = self.hash.clone().expect("in restore mode a hash is set");
-            Some(git_hash::ObjectId::from(hash.digest()))
+            Some(hash.try_digest()?)
         } else {
             None
         })
@@ -1,5 +1,5 @@
 //! a pack data file
-use std::{convert::TryInto, path::Path};
+use std::path::Path;
 
 /// The offset to an entry into the pack data file, relative to its beginning.
 pub type Offset = u64;
@@ -7,8 +7,6 @@ pub type Offset = u64;
 /// An identifier to uniquely identify all packs loaded within a known context or namespace.
 pub type Id = u32;
 
-use memmap2::Mmap;
-
 /// An representing an full- or delta-object within a pack
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
@@ -23,6 +21,47 @@ pub struct Entry {
 
 mod file;
 pub use file::{decode_entry, verify, Header, ResolvedBase};
+
+/// Determines how the content of a pack data file is accessed, see [`File::at_opts()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mapping {
+    /// Memory-map the whole file at once.
+    ///
+    /// This is fastest, but IO errors that occur while accessing the mapped memory can't be handled and
+    /// the size of packs is limited by the available address space.
+    Whole,
+    /// Read the file through windows of `window_size` bytes each, keeping at most `limit` bytes of windows in memory
+    /// and reading them from disk as needed, similar to `core.packedGitWindowSize` and `core.packedGitLimit`.
+    ///
+    /// Note that unlike in `git`, the `limit` applies to each pack individually.
+    Windowed {
+        /// The amount of bytes to read from the file at once.
+        window_size: usize,
+        /// The maximum amount of bytes to keep in memory, even though the window that is currently needed is always kept.
+        limit: usize,
+    },
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Mapping::Whole
+    }
+}
+
+impl Mapping {
+    /// The default size of windows, matching the default of `core.packedGitWindowSize` on 64 bit systems.
+    #[cfg(target_pointer_width = "64")]
+    pub const DEFAULT_WINDOW_SIZE: usize = 1024 * 1024 * 1024;
+    /// The default size of windows, matching the default of `core.packedGitWindowSize` on 32 bit systems.
+    #[cfg(not(target_pointer_width = "64"))]
+    pub const DEFAULT_WINDOW_SIZE: usize = 32 * 1024 * 1024;
+    /// The default limit of bytes kept in memory, matching the default of `core.packedGitLimit` on 64 bit systems.
+    #[cfg(target_pointer_width = "64")]
+    pub const DEFAULT_LIMIT: usize = 8 * 1024 * 1024 * 1024;
+    /// The default limit of bytes kept in memory, matching the default of `core.packedGitLimit` on 32 bit systems.
+    #[cfg(not(target_pointer_width = "64"))]
+    pub const DEFAULT_LIMIT: usize = 256 * 1024 * 1024;
+}
 ///
 pub mod header;
 
@@ -62,8 +101,9 @@ impl Default for Version {
 
 /// A pack data file
 pub struct File {
-    data: Mmap,
+    data: file::access::Data,
     path: std::path::PathBuf,
+    checksum: git_hash::ObjectId,
     /// A value to represent this pack uniquely when used with cache lookup, or a way to identify this pack by its location on disk.
     /// The same location on disk should yield the same id.
     ///
@@ -78,6 +118,7 @@ pub struct File {
     /// based on their configuration.
     hash_len: usize,
     object_hash: git_hash::Kind,
+    mapping: Mapping,
 }
 
 /// Information about the pack data file itself
@@ -92,7 +133,7 @@ impl File {
     }
     /// The length of all mapped data, including the pack header and the pack trailer
     pub fn data_len(&self) -> usize {
-        self.data.len()
+        self.data.len() as usize
     }
     /// The kind of hash we use internally.
     pub fn object_hash(&self) -> git_hash::Kind {
@@ -100,7 +141,7 @@ impl File {
     }
     /// The position of the byte one past the last pack entry, or in other terms, the first byte of the trailing hash.
     pub fn pack_end(&self) -> usize {
-        self.data.len() - self.hash_len
+        self.data_len() - self.hash_len
     }
 
     /// The path to the pack data file on disk
@@ -108,11 +149,21 @@ impl File {
         &self.path
     }
 
-    /// Returns the pack data at the given slice if its range is contained in the mapped pack data
-    pub fn entry_slice(&self, slice: EntryRange) -> Option<&[u8]> {
-        let entry_end: usize = slice.end.try_into().expect("end of pack fits into usize");
-        let entry_start = slice.start as usize;
-        self.data.get(entry_start..entry_end)
+    /// The way the content of this pack data file is accessed.
+    pub fn mapping(&self) -> Mapping {
+        self.mapping
+    }
+
+    /// Copy the pack data at the given `slice` into `out`, which must be exactly as long as `slice`.
+    ///
+    /// An error is returned if the range isn't contained in the pack data or if it couldn't be read.
+    pub fn entry_slice(&self, slice: EntryRange, out: &mut [u8]) -> std::io::Result<()> {
+        assert_eq!(
+            slice.end.saturating_sub(slice.start),
+            out.len() as u64,
+            "output buffer must match the size of the slice"
+        );
+        self.data.read_exact_at(slice.start, out)
     }
 
     /// Returns the CRC32 of the pack data indicated by `pack_offset` and the `size` of the mapped data.
@@ -120,12 +171,12 @@ impl File {
     /// _Note:_ finding the right size is only possible by decompressing
     /// the pack entry beforehand, or by using the (to be sorted) offsets stored in an index file.
     ///
-    /// # Panics
-    ///
-    /// If `pack_offset` or `size` are pointing to a range outside of the mapped pack data.
-    pub fn entry_crc32(&self, pack_offset: Offset, size: usize) -> u32 {
-        let pack_offset: usize = pack_offset.try_into().expect("pack_size fits into usize");
-        git_features::hash::crc32(&self.data[pack_offset..pack_offset + size])
+    /// An error is returned if `pack_offset` or `size` are pointing to a range outside of the pack data,
+    /// or if it couldn't be read.
+    pub fn entry_crc32(&self, pack_offset: Offset, size: usize) -> std::io::Result<u32> {
+        self.data
+            .bytes(pack_offset..pack_offset + size as u64)
+            .map(|bytes| git_features::hash::crc32(&bytes))
     }
 }
 
@@ -1,8 +1,8 @@
 use std::io::Write;
 
-use git_features::hash;
+use git_features::{hash, progress::Progress};
 
-use crate::data::output;
+use crate::{data::output, index};
 
 /// The error returned by `next()` in the [`FromEntriesIter`] iterator.
 #[allow(missing_docs)]
@@ -37,6 +37,10 @@ pub struct FromEntriesIter<I, W> {
     /// It stores the pack offsets at which objects begin.
     /// Additionally we store if an object was invalid, and if so we will not write it nor will we allow delta objects to it.
     pack_offsets_and_validity: Vec<(u64, bool)>,
+    /// The id, pack offset and CRC32 of each written object, allowing to write an index file without reading the pack.
+    index_entries: Vec<index::write::PackEntry>,
+    /// A buffer to hold the encoded header of an entry, reused to avoid allocations.
+    header_buf: Vec<u8>,
     /// If we are done, no additional writes will occur
     is_done: bool,
 }
@@ -74,6 +78,8 @@ where
             trailer: None,
             entry_version: version,
             pack_offsets_and_validity: Vec::with_capacity(num_entries as usize),
+            index_entries: Vec::with_capacity(num_entries as usize),
+            header_buf: Vec::new(),
             written: 0,
             header_info: Some((version, num_entries)),
             is_done: false,
@@ -87,12 +93,45 @@ where
         self.output.inner
     }
 
+    /// Return the `output` implementation, for example to take the bytes written by the last call to `next()` out of a buffer.
+    pub fn write_mut(&mut self) -> &mut W {
+        &mut self.output.inner
+    }
+
     /// Returns the trailing hash over all written entries once done.
     /// It's `None` if we are not yet done writing.
     pub fn digest(&self) -> Option<git_hash::ObjectId> {
         self.trailer
     }
 
+    /// Write a pack index file of `version` for all objects written so far to `out` and return its checksum, or `None` if
+    /// we are not yet done writing the pack.
+    ///
+    /// The offsets and CRC32 of all objects are recorded while writing, so the pack doesn't have to be read again.
+    pub fn write_index(
+        &mut self,
+        version: index::Version,
+        out: impl std::io::Write,
+        progress: impl Progress,
+    ) -> Result<Option<git_hash::ObjectId>, index::write::Error> {
+        let pack_hash = match self.trailer {
+            Some(hash) => hash,
+            None => return Ok(None),
+        };
+        if version != index::Version::default() {
+            return Err(index::write::Error::Unsupported(version));
+        }
+        self.index_entries.sort_by_key(|e| e.id);
+        self.index_entries.dedup_by_key(|e| e.id);
+        Ok(Some(index::write::encode::write_to(
+            out,
+            &self.index_entries,
+            &pack_hash,
+            version,
+            progress,
+        )?))
+    }
+
     fn next_inner(&mut self) -> Result<u64, Error<E>> {
         let previous_written = self.written;
         if let Some((version, num_entries)) = self.header_info.take() {
@@ -115,17 +154,25 @@ where
                         }
                         self.written - base_offset
                     });
-                    self.written += header.write_to(entry.decompressed_size as u64, &mut self.output)? as u64;
-                    self.written += std::io::copy(&mut &*entry.compressed_data, &mut self.output)? as u64;
+                    self.header_buf.clear();
+                    header.write_to(entry.decompressed_size as u64, &mut self.header_buf)?;
+                    self.output.write_all(&self.header_buf)?;
+                    self.output.write_all(&entry.compressed_data)?;
+                    self.index_entries.push(index::write::PackEntry {
+                        id: entry.id,
+                        crc32: hash::crc32_update(hash::crc32(&self.header_buf), &entry.compressed_data),
+                        pack_offset: self.written,
+                    });
+                    self.written += (self.header_buf.len() + entry.compressed_data.len()) as u64;
                 }
             }
             None => {
                 let digest = self.output.hash.clone().digest();
-                self.output.inner.write_all(&digest[..])?;
-                self.written += digest.len() as u64;
+                self.output.inner.write_all(digest.as_slice())?;
+                self.written += digest.as_slice().len() as u64;
                 self.output.inner.flush()?;
                 self.is_done = true;
-                self.trailer = Some(git_hash::ObjectId::from(digest));
+                self.trailer = Some(digest);
             }
         };
         Ok(self.written - previous_written)
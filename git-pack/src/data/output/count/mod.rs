@@ -35,10 +35,70 @@ impl Count {
         Count {
             id: oid.into(),
             entry_pack_location: PackLocation::LookedUp(location),
+            name_hash: 0,
+            no_delta: false,
         }
     }
 }
 
+/// Produce counts for all objects in `reachable`, as computed by [`bitmap::File::reachable_objects()`][crate::bitmap::File::reachable_objects()]
+/// with `bitmap` and `order`, without looking up any object.
+///
+/// The name-hash of objects is taken from `bitmap` if it contains a name-hash cache.
+pub fn from_bitmap(
+    reachable: &crate::bitmap::reachable::Outcome,
+    order: &crate::bitmap::PackOrder<'_>,
+    bitmap: &crate::bitmap::File,
+) -> Vec<Count> {
+    let mut out = Vec::with_capacity(reachable.num_objects());
+    out.extend(reachable.positions().map(|position| Count {
+        id: order.oid_at_position(position).to_owned(),
+        entry_pack_location: PackLocation::NotLookedUp,
+        name_hash: bitmap.name_hash_at(position).unwrap_or_default(),
+        no_delta: false,
+    }));
+    out.extend(reachable.extra_objects.iter().map(|id| Count {
+        id: *id,
+        entry_pack_location: PackLocation::NotLookedUp,
+        name_hash: 0,
+        no_delta: false,
+    }));
+    out
+}
+
+/// The counts of all reachable objects along with statistics, as returned by [`reachable_from_bitmap()`].
+pub type ReachableFromBitmap = (Vec<Count>, objects::Outcome);
+
+/// Count all objects reachable from the commits, tags, trees or blobs in `tips` including the entire history of commits,
+/// with the help of `bitmap` and the `order` of the pack or multi-pack index it belongs to.
+///
+/// Returns `None` if one of the `tips` isn't contained in `order` and thus isn't covered by the bitmap, in which case the history
+/// should be walked to [count all objects][objects()] instead.
+pub fn reachable_from_bitmap<Find>(
+    bitmap: &crate::bitmap::File,
+    order: &crate::bitmap::PackOrder<'_>,
+    tips: &[ObjectId],
+    db: Find,
+    should_interrupt: &std::sync::atomic::AtomicBool,
+) -> Result<Option<ReachableFromBitmap>, crate::bitmap::reachable::Error<Find::Error>>
+where
+    Find: crate::Find,
+{
+    if tips.iter().any(|tip| order.lookup(tip).is_none()) {
+        return Ok(None);
+    }
+    let reachable = bitmap.reachable_objects(order, tips.iter().copied(), db, should_interrupt)?;
+    let counts = from_bitmap(&reachable, order, bitmap);
+    let outcome = objects::Outcome {
+        input_objects: tips.len(),
+        expanded_objects: counts.len().saturating_sub(tips.len()),
+        decoded_objects: reachable.extra_objects.len(),
+        total_objects: counts.len(),
+        bitmap_objects: counts.len() - reachable.extra_objects.len(),
+    };
+    Ok(Some((counts, outcome)))
+}
+
 #[path = "objects/mod.rs"]
 mod objects_impl;
 pub use objects_impl::{objects, objects_unthreaded};
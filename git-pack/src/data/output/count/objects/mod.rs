@@ -45,6 +45,7 @@ pub fn objects<Find, Iter, IterErr, Oid>(
         thread_limit,
         input_object_expansion,
         chunk_size,
+        attributes,
     }: Options,
 ) -> Result<find::existing::Error<Find::Error>, IterErr>
 where
@@ -66,6 +67,7 @@ where
         size: chunk_size,
     };
     let seen_objs = dashmap::DashSet::<ObjectId, HashBuildHasher>::default();
+    let delta_attributes = attributes.as_ref().map(util::delta_attributes);
     let progress = Arc::new(parking_lot::Mutex::new(progress));
 
     parallel::in_parallel(
@@ -89,10 +91,12 @@ where
         },
         {
             let seen_objs = &seen_objs;
+            let delta_attributes = delta_attributes.as_ref();
             move |oids: Vec<std::result::Result<Oid, IterErr>>, (buf1, buf2, progress)| {
                 expand::this(
                     &db,
                     input_object_expansion,
+                    delta_attributes,
                     seen_objs,
                     oids,
                     buf1,
@@ -108,12 +112,15 @@ where
 }
 
 /// Like [`objects()`] but using a single thread only to mostly save on the otherwise required overhead.
+///
+/// `attributes` are used like [`Options::attributes`].
 pub fn objects_unthreaded<Find, IterErr, Oid>(
     db: Find,
     object_ids: impl Iterator<Item = std::result::Result<Oid, IterErr>>,
     mut progress: impl Progress,
     should_interrupt: &AtomicBool,
     input_object_expansion: ObjectExpansion,
+    attributes: Option<&git_attributes::MatchGroup>,
 ) -> Result<find::existing::Error<Find::Error>, IterErr>
 where
     Find: crate::Find,
@@ -121,11 +128,13 @@ where
     IterErr: std::error::Error,
 {
     let seen_objs = RefCell::new(HashedSet::<ObjectId>::default());
+    let delta_attributes = attributes.map(util::delta_attributes);
 
     let (mut buf1, mut buf2) = (Vec::new(), Vec::new());
     expand::this(
         &db,
         input_object_expansion,
+        delta_attributes.as_ref(),
         &seen_objs,
         object_ids,
         &mut buf1,
@@ -157,6 +166,7 @@ mod expand {
     pub fn this<Find, IterErr, Oid>(
         db: &Find,
         input_object_expansion: ObjectExpansion,
+        delta_attributes: Option<&git_attributes::MatchGroup>,
         seen_objs: &impl util::InsertImmutable<ObjectId>,
         oids: impl IntoIterator<Item = std::result::Result<Oid, IterErr>>,
         buf1: &mut Vec<u8>,
@@ -176,8 +186,8 @@ mod expand {
         let mut tree_traversal_state = git_traverse::tree::breadthfirst::State::default();
         let mut tree_diff_state = git_diff::tree::State::default();
         let mut parent_commit_ids = Vec::new();
-        let mut traverse_delegate = tree::traverse::AllUnseen::new(seen_objs);
-        let mut changes_delegate = tree::changes::AllNew::new(seen_objs);
+        let mut traverse_delegate = tree::traverse::AllUnseen::new(seen_objs, delta_attributes);
+        let mut changes_delegate = tree::changes::AllNew::new(seen_objs, delta_attributes);
         let mut outcome = Outcome::default();
 
         let stats = &mut outcome;
@@ -230,7 +240,7 @@ mod expand {
                                     push_obj_count_unique(
                                         &mut out, seen_objs, &tree_id, location, progress, stats, true,
                                     );
-                                    git_object::TreeRefIter::from_bytes(obj.data)
+                                    git_object::TreeRefIter::from_bytes_with_object_hash(obj.data, obj.object_hash)
                                 };
 
                                 let objects = if parent_commit_ids.is_empty() {
@@ -277,7 +287,10 @@ mod expand {
                                                 stats,
                                                 true,
                                             );
-                                            git_object::TreeRefIter::from_bytes(parent_tree_obj.data)
+                                            git_object::TreeRefIter::from_bytes_with_object_hash(
+                                                parent_tree_obj.data,
+                                                parent_tree_obj.object_hash,
+                                            )
                                         };
 
                                         changes_delegate.clear();
@@ -295,8 +308,8 @@ mod expand {
                                     }
                                     &changes_delegate.objects
                                 };
-                                for id in objects.iter() {
-                                    out.push(id_to_count(db, buf2, id, progress, stats, allow_pack_lookups));
+                                for obj in objects.iter() {
+                                    out.push(id_to_count(db, buf2, obj, progress, stats, allow_pack_lookups)?);
                                 }
                                 break;
                             }
@@ -313,7 +326,7 @@ mod expand {
                             Tree => {
                                 traverse_delegate.clear();
                                 git_traverse::tree::breadthfirst(
-                                    git_object::TreeRefIter::from_bytes(obj.0.data),
+                                    git_object::TreeRefIter::from_bytes_with_object_hash(obj.0.data, obj.0.object_hash),
                                     &mut tree_traversal_state,
                                     |oid, buf| {
                                         stats.decoded_objects += 1;
@@ -330,8 +343,8 @@ mod expand {
                                     &mut traverse_delegate,
                                 )
                                 .map_err(Error::TreeTraverse)?;
-                                for id in traverse_delegate.non_trees.iter() {
-                                    out.push(id_to_count(db, buf1, id, progress, stats, allow_pack_lookups));
+                                for obj in traverse_delegate.non_trees.iter() {
+                                    out.push(id_to_count(db, buf1, obj, progress, stats, allow_pack_lookups)?);
                                 }
                                 break;
                             }
@@ -387,20 +400,22 @@ mod expand {
     fn id_to_count<Find: crate::Find>(
         db: &Find,
         buf: &mut Vec<u8>,
-        id: &oid,
+        obj: &util::ObjectAtPath,
         progress: &mut impl Progress,
         statistics: &mut Outcome,
         allow_pack_lookups: bool,
-    ) -> output::Count {
+    ) -> Result<output::Count, find::existing::Error<Find::Error>> {
         progress.inc();
         statistics.expanded_objects += 1;
-        output::Count {
-            id: id.to_owned(),
+        Ok(output::Count {
+            id: obj.id,
             entry_pack_location: if allow_pack_lookups {
-                PackLocation::LookedUp(db.location_by_oid(id, buf))
+                PackLocation::LookedUp(db.location_by_oid(obj.id, buf).map_err(find::existing::Error::Find)?)
             } else {
                 PackLocation::NotLookedUp
             },
-        }
+            name_hash: obj.name_hash,
+            no_delta: obj.no_delta,
+        })
     }
 }
@@ -6,25 +6,28 @@ pub mod changes {
     use git_hash::ObjectId;
     use git_object::{bstr::BStr, tree::EntryMode};
 
-    use crate::data::output::count::objects_impl::util::InsertImmutable;
+    use crate::data::output::count::objects_impl::util::{InsertImmutable, ObjectAtPath, PathTracker};
 
     pub struct AllNew<'a, H> {
-        pub objects: Vec<ObjectId>,
+        pub objects: Vec<ObjectAtPath>,
         all_seen: &'a H,
+        path: PathTracker<'a>,
     }
 
     impl<'a, H> AllNew<'a, H>
     where
         H: InsertImmutable<ObjectId>,
     {
-        pub fn new(all_seen: &'a H) -> Self {
+        pub fn new(all_seen: &'a H, delta_attributes: Option<&'a git_attributes::MatchGroup>) -> Self {
             AllNew {
                 objects: Default::default(),
                 all_seen,
+                path: PathTracker::new(delta_attributes),
             }
         }
         pub fn clear(&mut self) {
             self.objects.clear();
+            self.path.clear();
         }
     }
 
@@ -32,13 +35,21 @@ pub mod changes {
     where
         H: InsertImmutable<ObjectId>,
     {
-        fn pop_front_tracked_path_and_set_current(&mut self) {}
+        fn pop_front_tracked_path_and_set_current(&mut self) {
+            self.path.pop_front_tracked_path_and_set_current();
+        }
 
-        fn push_back_tracked_path_component(&mut self, _component: &BStr) {}
+        fn push_back_tracked_path_component(&mut self, component: &BStr) {
+            self.path.push_back_tracked_path_component(component);
+        }
 
-        fn push_path_component(&mut self, _component: &BStr) {}
+        fn push_path_component(&mut self, component: &BStr) {
+            self.path.push_path_component(component);
+        }
 
-        fn pop_path_component(&mut self) {}
+        fn pop_path_component(&mut self) {
+            self.path.pop_path_component();
+        }
 
         fn visit(&mut self, change: Change) -> Action {
             match change {
@@ -48,7 +59,7 @@ pub mod changes {
                     }
                     let inserted = self.all_seen.insert(oid);
                     if inserted {
-                        self.objects.push(oid);
+                        self.objects.push(self.path.object(oid));
                     }
                 }
                 Change::Deletion { .. } => {}
@@ -66,25 +77,28 @@ pub mod traverse {
     };
     use git_traverse::tree::{visit::Action, Visit};
 
-    use crate::data::output::count::objects_impl::util::InsertImmutable;
+    use crate::data::output::count::objects_impl::util::{InsertImmutable, ObjectAtPath, PathTracker};
 
     pub struct AllUnseen<'a, H> {
-        pub non_trees: Vec<ObjectId>,
+        pub non_trees: Vec<ObjectAtPath>,
         all_seen: &'a H,
+        path: PathTracker<'a>,
     }
 
     impl<'a, H> AllUnseen<'a, H>
     where
         H: InsertImmutable<ObjectId>,
     {
-        pub fn new(all_seen: &'a H) -> Self {
+        pub fn new(all_seen: &'a H, delta_attributes: Option<&'a git_attributes::MatchGroup>) -> Self {
             AllUnseen {
                 non_trees: Default::default(),
                 all_seen,
+                path: PathTracker::new(delta_attributes),
             }
         }
         pub fn clear(&mut self) {
             self.non_trees.clear();
+            self.path.clear();
         }
     }
 
@@ -92,13 +106,21 @@ pub mod traverse {
     where
         H: InsertImmutable<ObjectId>,
     {
-        fn pop_front_tracked_path_and_set_current(&mut self) {}
+        fn pop_front_tracked_path_and_set_current(&mut self) {
+            self.path.pop_front_tracked_path_and_set_current();
+        }
 
-        fn push_back_tracked_path_component(&mut self, _component: &BStr) {}
+        fn push_back_tracked_path_component(&mut self, component: &BStr) {
+            self.path.push_back_tracked_path_component(component);
+        }
 
-        fn push_path_component(&mut self, _component: &BStr) {}
+        fn push_path_component(&mut self, component: &BStr) {
+            self.path.push_path_component(component);
+        }
 
-        fn pop_path_component(&mut self) {}
+        fn pop_path_component(&mut self) {
+            self.path.pop_path_component();
+        }
 
         fn visit_tree(&mut self, entry: &EntryRef<'_>) -> Action {
             let inserted = self.all_seen.insert(entry.oid.to_owned());
@@ -116,7 +138,7 @@ pub mod traverse {
             }
             let inserted = self.all_seen.insert(entry.oid.to_owned());
             if inserted {
-                self.non_trees.push(entry.oid.to_owned());
+                self.non_trees.push(self.path.object(entry.oid.to_owned()));
             }
             Action::Continue
         }
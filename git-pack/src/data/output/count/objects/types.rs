@@ -12,6 +12,9 @@ pub struct Outcome {
     pub decoded_objects: usize,
     /// The total amount of encountered objects. Should be `expanded_objects + input_objects`.
     pub total_objects: usize,
+    /// The amount of objects that were counted with the help of a [reachability bitmap][crate::bitmap::File] as they are
+    /// contained in the pack it belongs to.
+    pub bitmap_objects: usize,
 }
 
 impl Outcome {
@@ -22,12 +25,14 @@ impl Outcome {
             decoded_objects,
             expanded_objects,
             total_objects,
+            bitmap_objects,
         }: Self,
     ) {
         self.input_objects += input_objects;
         self.decoded_objects += decoded_objects;
         self.expanded_objects += expanded_objects;
         self.total_objects += total_objects;
+        self.bitmap_objects += bitmap_objects;
     }
 }
 
@@ -59,7 +64,7 @@ impl Default for ObjectExpansion {
 }
 
 /// Configuration options for the pack generation functions provided in [this module][crate::data::output].
-#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 pub struct Options {
     /// The amount of threads to use at most when resolving the pack. If `None`, all logical cores are used.
@@ -70,6 +75,10 @@ pub struct Options {
     pub chunk_size: usize,
     /// The way input objects are handled
     pub input_object_expansion: ObjectExpansion,
+    /// If set, the attributes to query for the `delta` attribute of each path encountered during tree traversal.
+    /// Objects for which it is unset will be marked as [not to be delta-compressed][crate::data::output::Count::no_delta].
+    #[cfg_attr(feature = "serde1", serde(skip))]
+    pub attributes: Option<git_attributes::MatchGroup>,
 }
 
 impl Default for Options {
@@ -78,6 +87,7 @@ impl Default for Options {
             thread_limit: None,
             chunk_size: 10,
             input_object_expansion: Default::default(),
+            attributes: None,
         }
     }
 }
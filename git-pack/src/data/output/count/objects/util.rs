@@ -1,7 +1,132 @@
+use std::collections::VecDeque;
+
+use git_hash::ObjectId;
+use git_object::bstr::{BStr, BString, ByteSlice, ByteVec};
+
 pub trait InsertImmutable<Item: Eq + std::hash::Hash> {
     fn insert(&self, item: Item) -> bool;
 }
 
+/// An object id along with information derived from the path at which it was encountered.
+#[derive(Debug, Clone, Copy)]
+pub struct ObjectAtPath {
+    pub id: ObjectId,
+    pub name_hash: u32,
+    pub no_delta: bool,
+}
+
+/// Keep track of the path of the tree entry currently being visited, to derive information for packing from it.
+pub struct PathTracker<'a> {
+    path: BString,
+    path_deque: VecDeque<BString>,
+    delta_attributes: Option<&'a git_attributes::MatchGroup>,
+}
+
+impl<'a> PathTracker<'a> {
+    /// `delta_attributes` are expected to only contain patterns assigning the `delta` attribute, see [`delta_attributes()`].
+    pub fn new(delta_attributes: Option<&'a git_attributes::MatchGroup>) -> Self {
+        PathTracker {
+            path: Default::default(),
+            path_deque: Default::default(),
+            delta_attributes,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.path.clear();
+        self.path_deque.clear();
+    }
+
+    pub fn pop_front_tracked_path_and_set_current(&mut self) {
+        self.path = self
+            .path_deque
+            .pop_front()
+            .expect("every call is matched with push_tracked_path_component");
+    }
+
+    pub fn push_back_tracked_path_component(&mut self, component: &BStr) {
+        self.push_path_component(component);
+        self.path_deque.push_back(self.path.clone());
+    }
+
+    pub fn push_path_component(&mut self, component: &BStr) {
+        if !self.path.is_empty() {
+            self.path.push(b'/');
+        }
+        self.path.push_str(component);
+    }
+
+    pub fn pop_path_component(&mut self) {
+        if let Some(pos) = self.path.rfind_byte(b'/') {
+            self.path.resize(pos, 0);
+        } else {
+            self.path.clear();
+        }
+    }
+
+    /// Associate `id` with information obtained from the current path.
+    pub fn object(&self, id: ObjectId) -> ObjectAtPath {
+        ObjectAtPath {
+            id,
+            name_hash: name_hash(self.path.as_ref()),
+            no_delta: self.delta_attributes.map_or(false, |attrs| {
+                attrs
+                    .pattern_matching_relative_path(
+                        self.path.as_bstr(),
+                        Some(false),
+                        git_attributes::glob::pattern::Case::Sensitive,
+                    )
+                    .map_or(false, |m| match m.value {
+                        git_attributes::Value::Assignments(assignments) => assignments
+                            .iter()
+                            .rev()
+                            .find(|a| a.name.as_str() == "delta")
+                            .map_or(false, |a| match &a.state {
+                                git_attributes::State::Unset => true,
+                                git_attributes::State::Value(v) => v.as_str() == "false",
+                                git_attributes::State::Set | git_attributes::State::Unspecified => false,
+                            }),
+                        git_attributes::Value::MacroAttributes(_) => false,
+                    })
+            }),
+        }
+    }
+}
+
+/// Produce a hash of `path` which sorts objects of similar names close to each other, focussing on the last
+/// characters of the path, just like `git` does.
+pub fn name_hash(path: &BStr) -> u32 {
+    path.iter()
+        .filter(|b| !b.is_ascii_whitespace())
+        .fold(0u32, |hash, b| (hash >> 2).wrapping_add((*b as u32) << 24))
+}
+
+/// Return a copy of `attributes` which only contains patterns assigning the `delta` attribute, so that the last matching pattern
+/// determines its state.
+pub fn delta_attributes(attributes: &git_attributes::MatchGroup) -> git_attributes::MatchGroup {
+    git_attributes::MatchGroup {
+        patterns: attributes
+            .patterns
+            .iter()
+            .map(|list| git_attributes::PatternList {
+                patterns: list
+                    .patterns
+                    .iter()
+                    .filter(|mapping| match &mapping.value {
+                        git_attributes::Value::Assignments(assignments) => {
+                            assignments.iter().any(|a| a.name.as_str() == "delta")
+                        }
+                        git_attributes::Value::MacroAttributes(_) => false,
+                    })
+                    .cloned()
+                    .collect(),
+                source: list.source.clone(),
+                base: list.base.clone(),
+            })
+            .collect(),
+    }
+}
+
 mod trait_impls {
     use std::{cell::RefCell, hash::Hash};
 
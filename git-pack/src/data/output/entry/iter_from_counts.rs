@@ -34,7 +34,29 @@ use crate::data::output;
 ///  or keeping enough state to write a pack and then generate an index with recorded data.
 ///
 pub fn iter_from_counts<Find>(
+    counts: Vec<output::Count>,
+    db: Find,
+    progress: impl Progress + 'static,
+    options: Options,
+) -> impl Iterator<Item = Result<(SequenceId, Vec<output::Entry>), Error<Find::Error>>>
+       + parallel::reduce::Finalize<Reduce = reduce::Statistics<Error<Find::Error>>>
+where
+    Find: crate::Find + Send + Clone + 'static,
+    <Find as crate::Find>::Error: Send,
+{
+    iter_from_counts_with_thin_bases(counts, Vec::new(), db, progress, options)
+}
+
+/// Like [`iter_from_counts()`], but with `thin_bases`, which are objects the receiver of the pack is known to have and that
+/// aren't written into the pack.
+///
+/// If [`allow_thin_pack`][Options::allow_thin_pack] is set and the `mode` is [`PackCopyAndDeltaCompression`][Mode::PackCopyAndDeltaCompression],
+/// they are considered as delta bases for unpacked objects, and deltas against them refer to their base by id, similar to
+/// the preferred bases of `git pack-objects --thin`. Their [name hash][output::Count::name_hash] is used to sort them close
+/// to similar objects, so only objects at paths similar to the ones of the objects in the pack should be passed.
+pub fn iter_from_counts_with_thin_bases<Find>(
     mut counts: Vec<output::Count>,
+    thin_bases: Vec<output::Count>,
     db: Find,
     mut progress: impl Progress + 'static,
     Options {
@@ -77,7 +99,11 @@ where
                         use crate::data::output::count::PackLocation::*;
                         match count.entry_pack_location {
                             LookedUp(_) => continue,
-                            NotLookedUp => count.entry_pack_location = LookedUp(db.location_by_oid(count.id, buf)),
+                            // Objects whose location can't be obtained are looked up again when creating their entry,
+                            // which is when the error is reported.
+                            NotLookedUp => {
+                                count.entry_pack_location = LookedUp(db.location_by_oid(count.id, buf).ok().flatten())
+                            }
                         }
                     }
                     progress.lock().inc_by(chunk_size);
@@ -90,7 +116,7 @@ where
         progress.lock().show_throughput(start);
     }
     let counts_range_by_pack_id = match mode {
-        Mode::PackCopyAndBaseObjects => {
+        Mode::PackCopyAndBaseObjects | Mode::PackCopyAndDeltaCompression { .. } => {
             let mut progress = progress.add_child_with_id("sorting", *b"ECSE"); /* Entries from Counts Sorting Entries */
             progress.init(Some(counts.len()), git_features::progress::count("counts"));
             let start = std::time::Instant::now();
@@ -98,8 +124,8 @@ where
             use crate::data::output::count::PackLocation::*;
             counts.sort_by(|lhs, rhs| match (&lhs.entry_pack_location, &rhs.entry_pack_location) {
                 (LookedUp(None), LookedUp(None)) => Ordering::Equal,
-                (LookedUp(Some(_)), LookedUp(None)) => Ordering::Greater,
-                (LookedUp(None), LookedUp(Some(_))) => Ordering::Less,
+                (LookedUp(Some(_)), LookedUp(None)) => Ordering::Less,
+                (LookedUp(None), LookedUp(Some(_))) => Ordering::Greater,
                 (LookedUp(Some(lhs)), LookedUp(Some(rhs))) => lhs
                     .pack_id
                     .cmp(&rhs.pack_id)
@@ -108,8 +134,8 @@ where
             });
 
             let mut index: Vec<(u32, std::ops::Range<usize>)> = Vec::new();
-            let mut chunks_pack_start = counts.partition_point(|e| e.entry_pack_location.is_none());
-            let mut slice = &counts[chunks_pack_start..];
+            let mut chunks_pack_start = 0;
+            let mut slice = &counts[..counts.partition_point(|e| !e.entry_pack_location.is_none())];
             while !slice.is_empty() {
                 let current_pack_id = slice[0].entry_pack_location.as_ref().expect("packed object").pack_id;
                 let pack_end = slice.partition_point(|e| {
@@ -126,13 +152,41 @@ where
             index
         }
     };
+    // Unpacked objects are last so they can be delta-compressed against packed objects, which are written first.
+    let num_packed = counts.partition_point(|e| !e.entry_pack_location.is_none());
+    let (window, max_depth, delta_chunks, num_delta_candidates) = match mode {
+        Mode::PackCopyAndBaseObjects => (0, 0, Vec::new(), 0),
+        Mode::PackCopyAndDeltaCompression { window, depth } => {
+            let (chunks, num_candidates) = delta::prepare(
+                &mut counts,
+                num_packed,
+                if allow_thin_pack { &thin_bases } else { &[] },
+                &db,
+                window,
+                depth,
+                chunk_size,
+                thread_limit,
+                version,
+                &mut progress,
+            );
+            (window, depth, chunks, num_candidates)
+        }
+    };
+    let unpacked_start = num_packed + num_delta_candidates;
+    let work: Vec<_> = util::ChunkRanges::new(chunk_size, num_packed)
+        .map(Work::Counts)
+        .chain(delta_chunks.into_iter().map(Work::Deltas))
+        .chain(
+            util::ChunkRanges::new(chunk_size, counts.len() - unpacked_start)
+                .map(|range| Work::Counts(range.start + unpacked_start..range.end + unpacked_start)),
+        )
+        .collect();
 
     let counts = Arc::new(counts);
     let progress = Arc::new(parking_lot::Mutex::new(progress));
-    let chunks = util::ChunkRanges::new(chunk_size, counts.len());
 
     parallel::reduce::Stepwise::new(
-        chunks.enumerate(),
+        work.into_iter().enumerate(),
         thread_limit,
         {
             let progress = Arc::clone(&progress);
@@ -147,18 +201,29 @@ where
         },
         {
             let counts = Arc::clone(&counts);
-            move |(chunk_id, chunk_range): (SequenceId, std::ops::Range<usize>), (buf, progress)| {
+            move |(chunk_id, work): (SequenceId, Work), (buf, progress)| {
+                let mut stats = Outcome::default();
+                let chunk_range = match work {
+                    Work::Counts(range) => range,
+                    Work::Deltas(chunk) => {
+                        progress.init(Some(chunk.counts.len()), git_features::progress::count("objects"));
+                        let out = delta::search(&chunk, &counts, &db, window, max_depth, buf, progress, &mut stats)?;
+                        return Ok((chunk_id, out, stats));
+                    }
+                };
                 let mut out = Vec::new();
                 let chunk = &counts[chunk_range];
-                let mut stats = Outcome::default();
                 let mut pack_offsets_to_id = None;
                 progress.init(Some(chunk.len()), git_features::progress::count("objects"));
 
-                for count in chunk.iter() {
+                for count in chunk {
                     out.push(match count
                         .entry_pack_location
                         .as_ref()
-                        .and_then(|l| db.entry_by_location(l).map(|pe| (l, pe)))
+                        .map(|l| db.entry_by_location(l).map(|pe| pe.map(|pe| (l, pe))))
+                        .transpose()
+                        .map_err(Error::FindExisting)?
+                        .flatten()
                     {
                         Some((location, pack_entry)) => {
                             if let Some((cached_pack_id, _)) = &pack_offsets_to_id {
@@ -234,6 +299,291 @@ where
     )
 }
 
+/// A unit of work to produce entries for.
+enum Work {
+    /// Create entries for the counts in the given range.
+    Counts(std::ops::Range<usize>),
+    /// Create entries for the unpacked delta candidates of the given chunk.
+    Deltas(delta::Chunk),
+}
+
+mod delta {
+    use std::{collections::VecDeque, sync::Arc};
+
+    use git_features::{parallel, progress::Progress};
+    use git_hash::ObjectId;
+
+    use super::{Error, Outcome};
+    use crate::data::output;
+
+    /// An object which may be delta-compressed or serve as delta base.
+    #[derive(Clone)]
+    struct Candidate {
+        id: ObjectId,
+        /// The index of the count this candidate belongs to, which is meaningless for [thin bases][Role::ThinBase].
+        count_index: usize,
+        kind: git_object::Kind,
+        size: usize,
+        name_hash: u32,
+        role: Role,
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Role {
+        /// An unpacked object to create an entry for, which may be delta-compressed.
+        Unpacked,
+        /// An undeltified object in a pack which is copied as is, and only serves as base.
+        Packed,
+        /// An object which isn't written as it's known to the receiver, and only serves as base referred to by id.
+        ThinBase,
+    }
+
+    /// A portion of the sorted delta candidates which are searched for deltas together.
+    pub struct Chunk {
+        candidates: Vec<Candidate>,
+        /// The range of counts of the unpacked candidates in this chunk.
+        pub counts: std::ops::Range<usize>,
+    }
+
+    /// Find all objects in `counts` which may be delta-compressed, along with the packed objects which aren't deltas and may serve
+    /// as their base, and group them into chunks in which each object is close to similar objects.
+    ///
+    /// `counts` are expected to start with `num_packed` packed objects. The unpacked objects that follow are reordered so the delta
+    /// candidates among them come first, in the order they are searched in. `thin_bases` are only used as bases.
+    ///
+    /// Returns the chunks to search and the amount of unpacked candidates.
+    #[allow(clippy::too_many_arguments)]
+    pub fn prepare<Find>(
+        counts: &mut [output::Count],
+        num_packed: usize,
+        thin_bases: &[output::Count],
+        db: &Find,
+        window: usize,
+        max_depth: usize,
+        chunk_size: usize,
+        thread_limit: Option<usize>,
+        version: crate::data::Version,
+        progress: &mut impl Progress,
+    ) -> (Vec<Chunk>, usize)
+    where
+        Find: crate::Find + Send + Clone,
+    {
+        if window == 0 || max_depth == 0 {
+            return (Vec::new(), 0);
+        }
+        let mut headers = vec![None; counts.len()];
+        {
+            let progress = Arc::new(parking_lot::Mutex::new(
+                progress.add_child_with_id("finding delta candidates", *b"ECFD"),
+            )); /* Entries from Counts Finding Delta candidates */
+            progress
+                .lock()
+                .init(Some(counts.len()), git_features::progress::count("objects"));
+            let enough_counts_present = counts.len() > 4_000;
+            let start = std::time::Instant::now();
+            parallel::in_parallel_if(
+                || enough_counts_present,
+                counts.chunks(chunk_size).zip(headers.chunks_mut(chunk_size)),
+                thread_limit,
+                |_n| Vec::<u8>::new(),
+                {
+                    let progress = Arc::clone(&progress);
+                    let db = db.clone();
+                    move |(counts, headers), buf| {
+                        let chunk_size = counts.len();
+                        for (count, header) in counts.iter().zip(headers.iter_mut()).filter(|(c, _)| !c.no_delta) {
+                            *header = match count.entry_pack_location.as_ref() {
+                                Some(location) => db.entry_by_location(location).ok().flatten().and_then(|entry| {
+                                    let pack_entry =
+                                        crate::data::Entry::from_bytes(&entry.data, 0, count.id.as_slice().len());
+                                    pack_entry
+                                        .header
+                                        .as_kind()
+                                        .filter(|_| entry.version == version)
+                                        .map(|kind| (kind, pack_entry.decompressed_size as usize))
+                                }),
+                                None => db.try_header(count.id, buf).ok().flatten(),
+                            };
+                        }
+                        progress.lock().inc_by(chunk_size);
+                        Ok::<_, ()>(())
+                    }
+                },
+                parallel::reduce::IdentityWithResult::<(), ()>::default(),
+            )
+            .expect("infallible - objects we can't find are not delta-compressed");
+            progress.lock().show_throughput(start);
+        }
+
+        let mut candidates: Vec<_> = headers
+            .iter()
+            .enumerate()
+            .filter_map(|(count_index, header)| {
+                header.map(|(kind, size)| Candidate {
+                    id: counts[count_index].id,
+                    count_index,
+                    kind,
+                    size,
+                    name_hash: counts[count_index].name_hash,
+                    role: if count_index < num_packed {
+                        Role::Packed
+                    } else {
+                        Role::Unpacked
+                    },
+                })
+            })
+            .collect();
+        let mut buf = Vec::new();
+        candidates.extend(thin_bases.iter().filter_map(|base| {
+            let (kind, size) = db.try_header(base.id, &mut buf).ok().flatten()?;
+            Some(Candidate {
+                id: base.id,
+                count_index: usize::MAX,
+                kind,
+                size,
+                name_hash: base.name_hash,
+                role: Role::ThinBase,
+            })
+        }));
+        candidates.sort_by(|lhs, rhs| {
+            lhs.kind
+                .cmp(&rhs.kind)
+                .then(lhs.name_hash.cmp(&rhs.name_hash))
+                .then(rhs.size.cmp(&lhs.size))
+        });
+
+        let unpacked = &mut counts[num_packed..];
+        let mut sorted_unpacked = Vec::with_capacity(unpacked.len());
+        for candidate in candidates.iter_mut().filter(|c| c.role == Role::Unpacked) {
+            sorted_unpacked.push(unpacked[candidate.count_index - num_packed].clone());
+            candidate.count_index = num_packed + sorted_unpacked.len() - 1;
+        }
+        let num_unpacked_candidates = sorted_unpacked.len();
+        sorted_unpacked.extend(
+            unpacked
+                .iter()
+                .zip(&headers[num_packed..])
+                .filter(|(_, header)| header.is_none())
+                .map(|(count, _)| count.clone()),
+        );
+        sorted_unpacked[num_unpacked_candidates..].sort_by_key(|count| count.name_hash);
+        unpacked.clone_from_slice(&sorted_unpacked);
+
+        // Windows don't reach across chunks, so larger chunks lead to better compression at the cost of less parallelism.
+        // Their size must not depend on the amount of threads to produce the same pack on every machine.
+        let search_chunk_size = window.saturating_mul(1_000);
+        let chunks = candidates
+            .chunks(search_chunk_size)
+            .filter_map(|candidates| {
+                let mut unpacked = candidates
+                    .iter()
+                    .filter(|c| c.role == Role::Unpacked)
+                    .map(|c| c.count_index);
+                let first = unpacked.next()?;
+                let last = unpacked.next_back().unwrap_or(first);
+                Some(Chunk {
+                    candidates: candidates.to_vec(),
+                    counts: first..last + 1,
+                })
+            })
+            .collect();
+        (chunks, num_unpacked_candidates)
+    }
+
+    /// Create entries for all unpacked candidates in `chunk`, in order, by delta-compressing them against up to `window` preceding
+    /// candidates with chains of deltas of up to `max_depth`, or by storing them as base objects.
+    ///
+    /// Each candidate is decoded only once.
+    #[allow(clippy::too_many_arguments)]
+    pub fn search<Find>(
+        chunk: &Chunk,
+        counts: &[output::Count],
+        db: &Find,
+        window: usize,
+        max_depth: usize,
+        buf: &mut Vec<u8>,
+        progress: &mut impl Progress,
+        stats: &mut Outcome,
+    ) -> Result<Vec<output::Entry>, Error<Find::Error>>
+    where
+        Find: crate::Find,
+    {
+        struct Base {
+            count_index: usize,
+            /// The id of a thin base, which is referred to by id instead of by index.
+            thin_id: Option<ObjectId>,
+            kind: git_object::Kind,
+            depth: usize,
+            data: Vec<u8>,
+        }
+        let mut out = Vec::with_capacity(chunk.counts.len());
+        let mut recent = VecDeque::<Base>::with_capacity(window);
+        let mut delta = Vec::new();
+        for candidate in &chunk.candidates {
+            let target = match db.try_find(candidate.id, buf).map_err(Error::FindExisting)? {
+                Some((obj, _location)) => obj.data.to_vec(),
+                None => {
+                    if candidate.role == Role::Unpacked {
+                        stats.missing_objects += 1;
+                        out.push(output::Entry::invalid());
+                        progress.inc();
+                    }
+                    continue;
+                }
+            };
+            let mut depth = 0;
+            if candidate.role == Role::Unpacked {
+                let mut max_size = (target.len() / 2).saturating_sub(candidate.id.as_slice().len());
+                let mut best = None;
+                for base in recent.iter().rev() {
+                    if max_size == 0 {
+                        break;
+                    }
+                    if base.kind != candidate.kind || base.depth >= max_depth || base.data.len() < target.len() / 32 {
+                        continue;
+                    }
+                    delta.clear();
+                    if crate::data::delta::encode(&base.data, &target, max_size, &mut delta) {
+                        max_size = delta.len() - 1;
+                        best = Some((base.count_index, base.thin_id, base.depth, std::mem::take(&mut delta)));
+                    }
+                }
+                let count = &counts[candidate.count_index];
+                out.push(match best {
+                    Some((base_index, thin_id, base_depth, data)) => {
+                        depth = base_depth + 1;
+                        stats.delta_compressed_objects += 1;
+                        match thin_id {
+                            Some(base_id) => {
+                                stats.ref_delta_objects += 1;
+                                output::Entry::from_delta_against_id(count, base_id, &data)?
+                            }
+                            None => output::Entry::from_delta(count, base_index, &data)?,
+                        }
+                    }
+                    None => {
+                        stats.decoded_and_recompressed_objects += 1;
+                        output::Entry::from_data(count, &git_object::Data::new(candidate.kind, &target))?
+                    }
+                });
+                progress.inc();
+            }
+
+            if recent.len() == window {
+                recent.pop_front();
+            }
+            recent.push_back(Base {
+                count_index: candidate.count_index,
+                thin_id: (candidate.role == Role::ThinBase).then(|| candidate.id),
+                kind: candidate.kind,
+                depth,
+                data: target,
+            });
+        }
+        Ok(out)
+    }
+}
+
 mod util {
     #[derive(Clone)]
     pub struct ChunkRanges {
@@ -325,6 +675,8 @@ mod types {
         pub objects_copied_from_pack: usize,
         /// The amount of objects that ref to their base as ref-delta, an indication for a thin back being created.
         pub ref_delta_objects: usize,
+        /// The amount of objects that were newly delta-compressed against another object in the pack.
+        pub delta_compressed_objects: usize,
     }
 
     impl Outcome {
@@ -335,12 +687,14 @@ mod types {
                 missing_objects,
                 objects_copied_from_pack,
                 ref_delta_objects,
+                delta_compressed_objects,
             }: Self,
         ) {
             self.decoded_and_recompressed_objects += decoded_objects;
             self.missing_objects += missing_objects;
             self.objects_copied_from_pack += objects_copied_from_pack;
             self.ref_delta_objects += ref_delta_objects;
+            self.delta_compressed_objects += delta_compressed_objects;
         }
     }
 
@@ -353,6 +707,28 @@ mod types {
         /// from existing pack compression and spending the smallest possible time on compressing unpacked objects at
         /// the cost of bandwidth.
         PackCopyAndBaseObjects,
+        /// Like [`PackCopyAndBaseObjects`][Mode::PackCopyAndBaseObjects], but non-packed objects will be delta-compressed
+        /// against each other and against packed objects which aren't deltas themselves, if possible. Objects are sorted by kind,
+        /// path and size to find good delta bases in a sliding window, similar to what `git pack-objects` does.
+        ///
+        /// Packed objects are still copied as is so existing deltas against them remain valid. Deltas are computed
+        /// while entries are produced, so only the entries of the chunks in flight are held in memory, and each object
+        /// in the window is decoded only once.
+        ///
+        /// Objects marked as [not to be delta-compressed][crate::data::output::Count::no_delta] are always written as base objects.
+        PackCopyAndDeltaCompression {
+            /// The amount of preceding objects to try as delta base for each object, similar to `pack.window`.
+            window: usize,
+            /// The maximum length of a chain of deltas, similar to `pack.depth`.
+            depth: usize,
+        },
+    }
+
+    impl Mode {
+        /// The default `window` size for [`PackCopyAndDeltaCompression`][Mode::PackCopyAndDeltaCompression], matching `git`.
+        pub const DEFAULT_WINDOW: usize = 10;
+        /// The default `depth` for [`PackCopyAndDeltaCompression`][Mode::PackCopyAndDeltaCompression], matching `git`.
+        pub const DEFAULT_DEPTH: usize = 50;
     }
 
     /// Configuration options for the pack generation functions provided in [this module][crate::data::output].
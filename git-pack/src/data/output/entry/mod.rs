@@ -6,7 +6,7 @@ use crate::{data, data::output, find};
 
 ///
 pub mod iter_from_counts;
-pub use iter_from_counts::iter_from_counts;
+pub use iter_from_counts::{iter_from_counts, iter_from_counts_with_thin_bases};
 
 /// The kind of pack entry to be written
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
@@ -132,17 +132,33 @@ impl output::Entry {
             id: count.id.to_owned(),
             kind: Kind::Base(obj.kind),
             decompressed_size: obj.data.len(),
-            compressed_data: {
-                let mut out = git_features::zlib::stream::deflate::Write::new(Vec::new());
-                if let Err(err) = std::io::copy(&mut &*obj.data, &mut out) {
-                    match err.kind() {
-                        std::io::ErrorKind::Other => return Err(Error::ZlibDeflate(err)),
-                        err => unreachable!("Should never see other errors than zlib, but got {:?}", err,),
-                    }
-                };
-                out.flush()?;
-                out.into_inner()
+            compressed_data: deflate(obj.data)?,
+        })
+    }
+
+    /// Create a new instance from a previously counted object whose data is represented by the given `delta`
+    /// against the object at `base_index`, which must have been written before.
+    pub fn from_delta(count: &output::Count, base_index: usize, delta: &[u8]) -> Result<Self, Error> {
+        Ok(output::Entry {
+            id: count.id.to_owned(),
+            kind: Kind::DeltaRef {
+                object_index: base_index,
             },
+            decompressed_size: delta.len(),
+            compressed_data: deflate(delta)?,
+        })
+    }
+
+    /// Create a new instance from a previously counted object whose data is represented by the given `delta`
+    /// against the object with `base_id`, which isn't part of the pack but known to be present on the receiving side.
+    ///
+    /// Such entries are only valid in thin packs.
+    pub fn from_delta_against_id(count: &output::Count, base_id: ObjectId, delta: &[u8]) -> Result<Self, Error> {
+        Ok(output::Entry {
+            id: count.id.to_owned(),
+            kind: Kind::DeltaOid { id: base_id },
+            decompressed_size: delta.len(),
+            compressed_data: deflate(delta)?,
         })
     }
 
@@ -179,3 +195,15 @@ impl output::Entry {
         }
     }
 }
+
+fn deflate(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = git_features::zlib::stream::deflate::Write::new(Vec::new());
+    if let Err(err) = std::io::copy(&mut &*data, &mut out) {
+        match err.kind() {
+            std::io::ErrorKind::Other => return Err(Error::ZlibDeflate(err)),
+            err => unreachable!("Should never see other errors than zlib, but got {:?}", err,),
+        }
+    };
+    out.flush()?;
+    Ok(out.into_inner())
+}
@@ -14,6 +14,12 @@ pub struct Count {
     pub id: ObjectId,
     /// A way to locate a pack entry in the object database, only available if the object is in a pack.
     pub entry_pack_location: count::PackLocation,
+    /// A hash of the path at which the object was found, or 0 if it isn't known.
+    ///
+    /// It's used to sort objects of similar names close to each other when searching for delta bases.
+    pub name_hash: u32,
+    /// If `true`, the object should not be stored as delta, as indicated by the `delta` attribute being unset for its path.
+    pub no_delta: bool,
 }
 
 /// An entry to be written to a file.
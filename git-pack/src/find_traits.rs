@@ -42,16 +42,34 @@ pub trait Find {
         pack_cache: &mut impl crate::cache::DecodeEntry,
     ) -> Result<Option<(git_object::Data<'a>, Option<data::entry::Location>)>, Self::Error>;
 
+    /// Obtain the kind and the decompressed size of the object with `id`, or `None` if it isn't present in the database.
+    ///
+    /// The default implementation decodes the entire object into `buffer` using [`Find::try_find()`], which implementations
+    /// should avoid if they can read the object header on its own.
+    fn try_header(
+        &self,
+        id: impl AsRef<git_hash::oid>,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Option<(git_object::Kind, usize)>, Self::Error> {
+        Ok(self
+            .try_find(id, buffer)?
+            .map(|(obj, _location)| (obj.kind, obj.data.len())))
+    }
+
     /// Find the packs location where an object with `id` can be found in the database, or `None` if there is no pack
-    /// holding the object.
+    /// holding the object, or an error if the pack holding it couldn't be read.
     ///
     /// _Note_ that this is always None if the object isn't packed.
-    fn location_by_oid(&self, id: impl AsRef<git_hash::oid>, buf: &mut Vec<u8>) -> Option<data::entry::Location>;
+    fn location_by_oid(
+        &self,
+        id: impl AsRef<git_hash::oid>,
+        buf: &mut Vec<u8>,
+    ) -> Result<Option<data::entry::Location>, Self::Error>;
 
     /// Obtain a vector of all offsets, in index order, along with their object id.
     fn pack_offsets_and_oid(&self, pack_id: u32) -> Option<Vec<(data::Offset, git_hash::ObjectId)>>;
 
-    /// Return the [`find::Entry`] for `location` if it is backed by a pack.
+    /// Return the [`find::Entry`] for `location` if it is backed by a pack, or an error if the pack couldn't be read.
     ///
     /// Note that this is only in the interest of avoiding duplicate work during pack generation.
     /// Pack locations can be obtained from [`Find::try_find()`].
@@ -60,7 +78,7 @@ pub trait Find {
     ///
     /// Custom implementations might be interested in providing their own meta-data with `object`,
     /// which currently isn't possible as the `Locate` trait requires GATs to work like that.
-    fn entry_by_location(&self, location: &data::entry::Location) -> Option<find::Entry>;
+    fn entry_by_location(&self, location: &data::entry::Location) -> Result<Option<find::Entry>, Self::Error>;
 }
 
 mod ext {
@@ -181,7 +199,19 @@ mod find_impls {
             (*self).try_find_cached(id, buffer, pack_cache)
         }
 
-        fn location_by_oid(&self, id: impl AsRef<oid>, buf: &mut Vec<u8>) -> Option<data::entry::Location> {
+        fn try_header(
+            &self,
+            id: impl AsRef<oid>,
+            buffer: &mut Vec<u8>,
+        ) -> Result<Option<(git_object::Kind, usize)>, Self::Error> {
+            (*self).try_header(id, buffer)
+        }
+
+        fn location_by_oid(
+            &self,
+            id: impl AsRef<oid>,
+            buf: &mut Vec<u8>,
+        ) -> Result<Option<data::entry::Location>, Self::Error> {
             (*self).location_by_oid(id, buf)
         }
 
@@ -189,7 +219,7 @@ mod find_impls {
             (*self).pack_offsets_and_oid(pack_id)
         }
 
-        fn entry_by_location(&self, location: &data::entry::Location) -> Option<find::Entry> {
+        fn entry_by_location(&self, location: &data::entry::Location) -> Result<Option<find::Entry>, Self::Error> {
             (*self).entry_by_location(location)
         }
     }
@@ -213,7 +243,19 @@ mod find_impls {
             self.deref().try_find_cached(id, buffer, pack_cache)
         }
 
-        fn location_by_oid(&self, id: impl AsRef<oid>, buf: &mut Vec<u8>) -> Option<data::entry::Location> {
+        fn try_header(
+            &self,
+            id: impl AsRef<oid>,
+            buffer: &mut Vec<u8>,
+        ) -> Result<Option<(git_object::Kind, usize)>, Self::Error> {
+            self.deref().try_header(id, buffer)
+        }
+
+        fn location_by_oid(
+            &self,
+            id: impl AsRef<oid>,
+            buf: &mut Vec<u8>,
+        ) -> Result<Option<data::entry::Location>, Self::Error> {
             self.deref().location_by_oid(id, buf)
         }
 
@@ -221,7 +263,7 @@ mod find_impls {
             self.deref().pack_offsets_and_oid(pack_id)
         }
 
-        fn entry_by_location(&self, object: &data::entry::Location) -> Option<find::Entry> {
+        fn entry_by_location(&self, object: &data::entry::Location) -> Result<Option<find::Entry>, Self::Error> {
             self.deref().entry_by_location(object)
         }
     }
@@ -245,7 +287,19 @@ mod find_impls {
             self.deref().try_find_cached(id, buffer, pack_cache)
         }
 
-        fn location_by_oid(&self, id: impl AsRef<oid>, buf: &mut Vec<u8>) -> Option<data::entry::Location> {
+        fn try_header(
+            &self,
+            id: impl AsRef<oid>,
+            buffer: &mut Vec<u8>,
+        ) -> Result<Option<(git_object::Kind, usize)>, Self::Error> {
+            self.deref().try_header(id, buffer)
+        }
+
+        fn location_by_oid(
+            &self,
+            id: impl AsRef<oid>,
+            buf: &mut Vec<u8>,
+        ) -> Result<Option<data::entry::Location>, Self::Error> {
             self.deref().location_by_oid(id, buf)
         }
 
@@ -253,7 +307,7 @@ mod find_impls {
             self.deref().pack_offsets_and_oid(pack_id)
         }
 
-        fn entry_by_location(&self, location: &data::entry::Location) -> Option<find::Entry> {
+        fn entry_by_location(&self, location: &data::entry::Location) -> Result<Option<find::Entry>, Self::Error> {
             self.deref().entry_by_location(location)
         }
     }
@@ -277,7 +331,19 @@ mod find_impls {
             self.deref().try_find_cached(id, buffer, pack_cache)
         }
 
-        fn location_by_oid(&self, id: impl AsRef<oid>, buf: &mut Vec<u8>) -> Option<data::entry::Location> {
+        fn try_header(
+            &self,
+            id: impl AsRef<oid>,
+            buffer: &mut Vec<u8>,
+        ) -> Result<Option<(git_object::Kind, usize)>, Self::Error> {
+            self.deref().try_header(id, buffer)
+        }
+
+        fn location_by_oid(
+            &self,
+            id: impl AsRef<oid>,
+            buf: &mut Vec<u8>,
+        ) -> Result<Option<data::entry::Location>, Self::Error> {
             self.deref().location_by_oid(id, buf)
         }
 
@@ -285,7 +351,7 @@ mod find_impls {
             self.deref().pack_offsets_and_oid(pack_id)
         }
 
-        fn entry_by_location(&self, location: &data::entry::Location) -> Option<find::Entry> {
+        fn entry_by_location(&self, location: &data::entry::Location) -> Result<Option<find::Entry>, Self::Error> {
             self.deref().entry_by_location(location)
         }
     }
@@ -75,7 +75,10 @@ macro_rules! izip {
 
 use memmap2::Mmap;
 
-/// The version of an index file
+/// The version of an index file.
+///
+/// Note that the kind of hash used in an index isn't implied by its version, but by the object format of the repository,
+/// which is available as [`File::object_hash()`].
 #[derive(PartialEq, Eq, Ord, PartialOrd, Debug, Hash, Clone, Copy)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
@@ -90,15 +93,6 @@ impl Default for Version {
     }
 }
 
-impl Version {
-    /// The kind of hash to produce to be compatible to this kind of index
-    pub fn hash(&self) -> git_hash::Kind {
-        match self {
-            Version::V1 | Version::V2 => git_hash::Kind::Sha1,
-        }
-    }
-}
-
 /// A way to indicate if a lookup, despite successful, was ambiguous or yielded exactly
 /// one result in the particular index.
 pub type PrefixLookupResult = Result<EntryIndex, ()>;
@@ -30,6 +30,12 @@ pub enum Error<E: std::error::Error + Send + Sync + 'static> {
         offset: u64,
         kind: git_object::Kind,
     },
+    #[error("A collision attack was detected while hashing the {kind} object at offset {offset}")]
+    Collision {
+        offset: u64,
+        kind: git_object::Kind,
+        source: git_features::hash::Collision,
+    },
     #[error(
         "The CRC32 of {kind} object at offset {offset} didn't match the checksum in the index file: expected {expected}, got {actual}"
     )]
@@ -19,7 +19,7 @@ pub use types::{Algorithm, SafetyCheck, Statistics};
 
 /// Traversal options for [`index::File::traverse()`].
 #[derive(Debug, Clone)]
-pub struct Options<F> {
+pub struct Options<F, R> {
     /// The algorithm to employ.
     pub traversal: Algorithm,
     /// If `Some`, only use the given amount of threads. Otherwise, the amount of threads to use will be selected based on
@@ -29,19 +29,51 @@ pub struct Options<F> {
     pub check: SafetyCheck,
     /// A function to create a pack cache
     pub make_pack_lookup_cache: F,
+    /// A function `(id, buf) -> Option<git_object::Kind>` to write the object with `id` into `buf` and return its kind, used to obtain
+    /// base objects of ref-delta entries which are not contained in the pack, like it's the case in thin packs.
+    /// Use [`no_external_bases`] if all bases are expected to be in the pack, or [`external_bases_from()`] to look them up in an object database.
+    pub resolve_external_base: R,
 }
 
-impl Default for Options<fn() -> crate::cache::Never> {
+impl Default for Options<fn() -> crate::cache::Never, ResolveExternalBaseFn> {
     fn default() -> Self {
         Options {
             check: Default::default(),
             traversal: Default::default(),
             thread_limit: None,
             make_pack_lookup_cache: || crate::cache::Never,
+            resolve_external_base: no_external_bases,
         }
     }
 }
 
+/// The type of [`no_external_bases()`].
+pub type ResolveExternalBaseFn = fn(&git_hash::oid, &mut Vec<u8>) -> Option<git_object::Kind>;
+
+/// An implementation of a `resolve_external_base` function that never finds any object, a no-op.
+pub fn no_external_bases(_: &git_hash::oid, _: &mut Vec<u8>) -> Option<git_object::Kind> {
+    None
+}
+
+/// Create a `resolve_external_base` function which looks up base objects of ref-delta entries in `db`, typically an object database,
+/// which is needed to resolve thin packs whose bases aren't contained in the pack itself.
+///
+/// Objects that can't be found or fail to be read are treated as missing, which aborts the traversal.
+pub fn external_bases_from<Find>(
+    db: Find,
+) -> impl for<'r> Fn(&git_hash::oid, &'r mut Vec<u8>) -> Option<git_object::Kind> + Send + Clone
+where
+    Find: crate::Find + Send + Clone,
+{
+    move |id, out| {
+        let mut buf = Vec::new();
+        let (obj, _location) = db.try_find(id, &mut buf).ok()??;
+        out.clear();
+        out.extend_from_slice(obj.data);
+        Some(obj.kind)
+    }
+}
+
 /// The outcome of the [`traverse()`][index::File::traverse()] method.
 pub struct Outcome<P> {
     /// The checksum obtained when hashing the file, which matched the checksum contained within the file.
@@ -74,7 +106,10 @@ impl index::File {
     ///
     /// Use [`thread_limit`][Options::thread_limit] to further control parallelism and [`check`][SafetyCheck] to define how much the passed
     /// objects shall be verified beforehand.
-    pub fn traverse<P, C, Processor, E, F>(
+    ///
+    /// Ref-delta entries whose base objects aren't contained in the pack are resolved with
+    /// [`resolve_external_base`][Options::resolve_external_base], which typically looks them up in an object database.
+    pub fn traverse<P, C, Processor, E, F, R>(
         &self,
         pack: &crate::data::File,
         progress: P,
@@ -85,7 +120,8 @@ impl index::File {
             thread_limit,
             check,
             make_pack_lookup_cache,
-        }: Options<F>,
+            resolve_external_base,
+        }: Options<F, R>,
     ) -> Result<Outcome<P>, Error<E>>
     where
         P: Progress,
@@ -98,6 +134,7 @@ impl index::File {
             &mut <P::SubProgress as Progress>::SubProgress,
         ) -> Result<(), E>,
         F: Fn() -> C + Send + Clone,
+        R: for<'r> Fn(&git_hash::oid, &'r mut Vec<u8>) -> Option<git_object::Kind> + Send + Clone,
     {
         match traversal {
             Algorithm::Lookup => self.traverse_with_lookup(
@@ -109,6 +146,7 @@ impl index::File {
                     thread_limit,
                     check,
                     make_pack_lookup_cache,
+                    resolve_external_base,
                 },
             ),
             Algorithm::DeltaTreeLookup => self.traverse_with_index(
@@ -116,7 +154,11 @@ impl index::File {
                 new_processor,
                 progress,
                 should_interrupt,
-                crate::index::traverse::with_index::Options { check, thread_limit },
+                crate::index::traverse::with_index::Options {
+                    check,
+                    thread_limit,
+                    resolve_external_base,
+                },
             ),
         }
     }
@@ -159,6 +201,7 @@ impl index::File {
         buf: &mut Vec<u8>,
         progress: &mut P,
         index_entry: &crate::index::Entry,
+        resolve_external_base: &impl Fn(&git_hash::oid, &mut Vec<u8>) -> Option<git_object::Kind>,
         processor: &mut impl FnMut(git_object::Kind, &[u8], &index::Entry, &mut P) -> Result<(), E>,
     ) -> Result<crate::data::decode_entry::Outcome, Error<E>>
     where
@@ -166,15 +209,23 @@ impl index::File {
         P: Progress,
         E: std::error::Error + Send + Sync + 'static,
     {
-        let pack_entry = pack.entry(index_entry.pack_offset);
+        let pack_entry = pack.entry(index_entry.pack_offset).map_err(|err| Error::PackDecode {
+            source: err.into(),
+            id: index_entry.oid,
+            offset: index_entry.pack_offset,
+        })?;
         let pack_entry_data_offset = pack_entry.data_offset;
         let entry_stats = pack
             .decode_entry(
                 pack_entry,
                 buf,
-                |id, _| {
-                    self.lookup(id)
-                        .map(|index| crate::data::ResolvedBase::InPack(pack.entry(self.pack_offset_at_index(index))))
+                |id, out| match self.lookup(id) {
+                    Some(index) => pack
+                        .entry(self.pack_offset_at_index(index))
+                        .ok()
+                        .map(crate::data::ResolvedBase::InPack),
+                    None => resolve_external_base(id, out)
+                        .map(|kind| crate::data::ResolvedBase::OutOfPack { kind, end: out.len() }),
                 },
                 cache,
             )
@@ -207,7 +258,7 @@ fn process_entry<P, E>(
     decompressed: &[u8],
     progress: &mut P,
     index_entry: &crate::index::Entry,
-    pack_entry_crc32: impl FnOnce() -> u32,
+    pack_entry_crc32: impl FnOnce() -> std::io::Result<u32>,
     processor: &mut impl FnMut(git_object::Kind, &[u8], &index::Entry, &mut P) -> Result<(), E>,
 ) -> Result<(), Error<E>>
 where
@@ -219,7 +270,11 @@ where
         hasher.update(&git_object::encode::loose_header(object_kind, decompressed.len()));
         hasher.update(decompressed);
 
-        let actual_oid = git_hash::ObjectId::from(hasher.digest());
+        let actual_oid = hasher.try_digest().map_err(|err| Error::Collision {
+            offset: index_entry.pack_offset,
+            kind: object_kind,
+            source: err,
+        })?;
         if actual_oid != index_entry.oid {
             return Err(Error::PackObjectMismatch {
                 actual: actual_oid,
@@ -229,7 +284,11 @@ where
             });
         }
         if let Some(desired_crc32) = index_entry.crc32 {
-            let actual_crc32 = pack_entry_crc32();
+            let actual_crc32 = pack_entry_crc32().map_err(|err| Error::PackDecode {
+                source: err.into(),
+                id: index_entry.oid,
+                offset: index_entry.pack_offset,
+            })?;
             if actual_crc32 != desired_crc32 {
                 return Err(Error::Crc32Mismatch {
                     actual: actual_crc32,
@@ -9,13 +9,25 @@ use crate::{
 };
 
 /// Traversal options for [`traverse_with_index()`][index::File::traverse_with_index()]
-#[derive(Default)]
-pub struct Options {
+pub struct Options<R> {
     /// If `Some`, only use the given amount of threads. Otherwise, the amount of threads to use will be selected based on
     /// the amount of available logical cores.
     pub thread_limit: Option<usize>,
     /// The kinds of safety checks to perform.
     pub check: crate::index::traverse::SafetyCheck,
+    /// A function to obtain base objects of ref-delta entries which aren't contained in the pack.
+    /// See [`index::traverse::Options::resolve_external_base`] for details.
+    pub resolve_external_base: R,
+}
+
+impl Default for Options<index::traverse::ResolveExternalBaseFn> {
+    fn default() -> Self {
+        Options {
+            thread_limit: None,
+            check: Default::default(),
+            resolve_external_base: index::traverse::no_external_bases,
+        }
+    }
 }
 
 /// Traversal with index
@@ -24,13 +36,17 @@ impl index::File {
     /// at the cost of memory.
     ///
     /// For more details, see the documentation on the [`traverse()`][index::File::traverse()] method.
-    pub fn traverse_with_index<P, Processor, E>(
+    pub fn traverse_with_index<P, Processor, E, R>(
         &self,
         pack: &crate::data::File,
         new_processor: impl Fn() -> Processor + Send + Clone,
         mut progress: P,
         should_interrupt: &AtomicBool,
-        Options { check, thread_limit }: Options,
+        Options {
+            check,
+            thread_limit,
+            resolve_external_base,
+        }: Options<R>,
     ) -> Result<Outcome<P>, Error<E>>
     where
         P: Progress,
@@ -41,6 +57,7 @@ impl index::File {
             &mut <P::SubProgress as Progress>::SubProgress,
         ) -> Result<(), E>,
         E: std::error::Error + Send + Sync + 'static,
+        R: for<'r> Fn(&git_hash::oid, &'r mut Vec<u8>) -> Option<git_object::Kind> + Send + Clone,
     {
         let (verify_result, traversal_result) = parallel::join(
             {
@@ -81,7 +98,8 @@ impl index::File {
                     self.object_hash,
                 )?;
                 let mut outcome = digest_statistics(tree.traverse(
-                    |slice, out| pack.entry_slice(slice).map(|entry| out.copy_from_slice(entry)),
+                    |slice, out| pack.entry_slice(slice, out).ok(),
+                    resolve_external_base,
                     pack.pack_end() as u64,
                     new_processor,
                     |data,
@@ -109,9 +127,9 @@ impl index::File {
                                 // TODO: Fix this - we overwrite the header of 'data' which also changes the computed entry size,
                                 // causing index and pack to seemingly mismatch. This is surprising, and should be done differently.
                                 // debug_assert_eq!(&data.index_entry.pack_offset, &pack_entry.pack_offset());
-                                git_features::hash::crc32(
-                                    pack.entry_slice(data.index_entry.pack_offset..entry_end)
-                                        .expect("slice pointing into the pack (by now data is verified)"),
+                                pack.entry_crc32(
+                                    data.index_entry.pack_offset,
+                                    (entry_end - data.index_entry.pack_offset) as usize,
                                 )
                             },
                             processor,
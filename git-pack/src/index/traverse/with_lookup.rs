@@ -9,7 +9,7 @@ use super::{Error, Reducer};
 use crate::{data, index, index::util};
 
 /// Traversal options for [`traverse()`][crate::index::File::traverse_with_lookup()]
-pub struct Options<F> {
+pub struct Options<F, R> {
     /// If `Some`, only use the given amount of threads. Otherwise, the amount of threads to use will be selected based on
     /// the amount of available logical cores.
     pub thread_limit: Option<usize>,
@@ -17,14 +17,18 @@ pub struct Options<F> {
     pub check: crate::index::traverse::SafetyCheck,
     /// A function to create a pack cache
     pub make_pack_lookup_cache: F,
+    /// A function to obtain base objects of ref-delta entries which aren't contained in the pack.
+    /// See [`index::traverse::Options::resolve_external_base`] for details.
+    pub resolve_external_base: R,
 }
 
-impl Default for Options<fn() -> crate::cache::Never> {
+impl Default for Options<fn() -> crate::cache::Never, index::traverse::ResolveExternalBaseFn> {
     fn default() -> Self {
         Options {
             check: Default::default(),
             thread_limit: None,
             make_pack_lookup_cache: || crate::cache::Never,
+            resolve_external_base: index::traverse::no_external_bases,
         }
     }
 }
@@ -39,7 +43,7 @@ impl index::File {
     /// waste while decoding objects.
     ///
     /// For more details, see the documentation on the [`traverse()`][index::File::traverse()] method.
-    pub fn traverse_with_lookup<P, C, Processor, E, F>(
+    pub fn traverse_with_lookup<P, C, Processor, E, F, R>(
         &self,
         new_processor: impl Fn() -> Processor + Send + Clone,
         pack: &crate::data::File,
@@ -49,7 +53,8 @@ impl index::File {
             thread_limit,
             check,
             make_pack_lookup_cache,
-        }: Options<F>,
+            resolve_external_base,
+        }: Options<F, R>,
     ) -> Result<Outcome<P>, Error<E>>
     where
         P: Progress,
@@ -62,6 +67,7 @@ impl index::File {
             &mut <P::SubProgress as Progress>::SubProgress,
         ) -> Result<(), E>,
         F: Fn() -> C + Send + Clone,
+        R: for<'r> Fn(&git_hash::oid, &'r mut Vec<u8>) -> Option<git_object::Kind> + Send + Clone,
     {
         let (verify_result, traversal_result) = parallel::join(
             {
@@ -108,6 +114,7 @@ impl index::File {
                         (
                             make_pack_lookup_cache(),
                             new_processor(),
+                            resolve_external_base.clone(),
                             Vec::with_capacity(2048), // decode buffer
                             lock(&reduce_progress)
                                 .add_child_with_id(format!("thread {}", index), git_features::progress::UNKNOWN), // per thread progress
@@ -121,7 +128,7 @@ impl index::File {
                     thread_limit,
                     state_per_thread,
                     |entries: &[index::Entry],
-                     (cache, ref mut processor, buf, progress)|
+                     (cache, ref mut processor, resolve_external_base, buf, progress)|
                      -> Result<Vec<data::decode_entry::Outcome>, Error<_>> {
                         progress.init(
                             Some(entries.len()),
@@ -140,6 +147,7 @@ impl index::File {
                                 buf,
                                 progress,
                                 index_entry,
+                                resolve_external_base,
                                 processor,
                             );
                             progress.inc();
@@ -192,6 +192,7 @@ impl index::File {
                         thread_limit,
                         check: index::traverse::SafetyCheck::All,
                         make_pack_lookup_cache,
+                        resolve_external_base: index::traverse::no_external_bases,
                     },
                 )
                 .map(|o| integrity::Outcome {
@@ -232,13 +233,14 @@ impl index::File {
             use git_object::Kind::*;
             match object_kind {
                 Tree | Commit | Tag => {
-                    let object = git_object::ObjectRef::from_bytes(object_kind, buf).map_err(|err| {
-                        integrity::Error::ObjectDecode {
+                    let object = git_object::Data::new(object_kind, buf)
+                        .with_object_hash(index_entry.oid.kind())
+                        .decode()
+                        .map_err(|err| integrity::Error::ObjectDecode {
                             source: err,
                             kind: object_kind,
                             id: index_entry.oid,
-                        }
-                    })?;
+                        })?;
                     if let Mode::HashCrc32DecodeEncode = verify_mode {
                         encode_buf.clear();
                         object
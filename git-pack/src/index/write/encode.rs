@@ -10,9 +10,40 @@ use git_features::{
 
 use crate::index::{util::Count, V2_SIGNATURE};
 
+/// The information stored for each object in an index file.
+pub(crate) trait Entry {
+    fn id(&self) -> &git_hash::oid;
+    fn crc32(&self) -> u32;
+    fn pack_offset(&self) -> crate::data::Offset;
+}
+
+impl Entry for crate::cache::delta::Item<crate::index::write::TreeEntry> {
+    fn id(&self) -> &git_hash::oid {
+        &self.data.id
+    }
+    fn crc32(&self) -> u32 {
+        self.data.crc32
+    }
+    fn pack_offset(&self) -> crate::data::Offset {
+        self.offset
+    }
+}
+
+impl Entry for crate::index::write::PackEntry {
+    fn id(&self) -> &git_hash::oid {
+        &self.id
+    }
+    fn crc32(&self) -> u32 {
+        self.crc32
+    }
+    fn pack_offset(&self) -> crate::data::Offset {
+        self.pack_offset
+    }
+}
+
 pub(crate) fn write_to(
     out: impl io::Write,
-    entries_sorted_by_oid: Vec<crate::cache::delta::Item<crate::index::write::TreeEntry>>,
+    entries_sorted_by_oid: &[impl Entry],
     pack_hash: &git_hash::ObjectId,
     kind: crate::index::Version,
     mut progress: impl Progress,
@@ -27,7 +58,7 @@ pub(crate) fn write_to(
     // Write header
     let mut out = Count::new(std::io::BufWriter::with_capacity(
         8 * 4096,
-        hash::Write::new(out, kind.hash()),
+        hash::Write::new(out, pack_hash.kind()),
     ));
     out.write_all(V2_SIGNATURE)?;
     out.write_all(&(kind as u32).to_be_bytes())?;
@@ -35,7 +66,7 @@ pub(crate) fn write_to(
     progress.init(Some(4), progress::steps());
     let start = std::time::Instant::now();
     let _info = progress.add_child_with_id("writing fan-out table", *b"info");
-    let fan_out = fanout(entries_sorted_by_oid.iter().map(|e| e.data.id.first_byte()));
+    let fan_out = fanout(entries_sorted_by_oid.iter().map(|e| e.id().first_byte()));
 
     for value in fan_out.iter() {
         out.write_all(&value.to_be_bytes())?;
@@ -43,30 +74,30 @@ pub(crate) fn write_to(
 
     progress.inc();
     let _info = progress.add_child_with_id("writing ids", *b"info");
-    for entry in &entries_sorted_by_oid {
-        out.write_all(entry.data.id.as_slice())?;
+    for entry in entries_sorted_by_oid {
+        out.write_all(entry.id().as_bytes())?;
     }
 
     progress.inc();
     let _info = progress.add_child_with_id("writing crc32", *b"info");
-    for entry in &entries_sorted_by_oid {
-        out.write_all(&entry.data.crc32.to_be_bytes())?;
+    for entry in entries_sorted_by_oid {
+        out.write_all(&entry.crc32().to_be_bytes())?;
     }
 
     progress.inc();
     let _info = progress.add_child_with_id("writing offsets", *b"info");
     {
         let mut offsets64 = Vec::<u64>::new();
-        for entry in &entries_sorted_by_oid {
-            let offset: u32 = if entry.offset > LARGE_OFFSET_THRESHOLD {
+        for entry in entries_sorted_by_oid {
+            let offset: u32 = if entry.pack_offset() > LARGE_OFFSET_THRESHOLD {
                 assert!(
                     offsets64.len() < LARGE_OFFSET_THRESHOLD as usize,
                     "Encoding breakdown - way too many 64bit offsets"
                 );
-                offsets64.push(entry.offset);
+                offsets64.push(entry.pack_offset());
                 ((offsets64.len() - 1) as u32) | HIGH_BIT
             } else {
-                entry.offset as u32
+                entry.pack_offset() as u32
             };
             out.write_all(&offset.to_be_bytes())?;
         }
@@ -79,14 +110,14 @@ pub(crate) fn write_to(
 
     let bytes_written_without_trailer = out.bytes;
     let mut out = out.inner.into_inner()?;
-    let index_hash: git_hash::ObjectId = out.hash.digest().into();
+    let index_hash = out.hash.digest();
     out.inner.write_all(index_hash.as_slice())?;
     out.inner.flush()?;
 
     progress.inc();
     progress.show_throughput_with(
         start,
-        (bytes_written_without_trailer + 20) as usize,
+        (bytes_written_without_trailer + index_hash.as_slice().len() as u64) as usize,
         progress::bytes().expect("unit always set"),
         progress::MessageLevel::Success,
     );
@@ -13,6 +13,13 @@ pub(crate) struct TreeEntry {
     pub crc32: u32,
 }
 
+/// An object as written into a pack, as recorded by [`FromEntriesIter`][crate::data::output::bytes::FromEntriesIter].
+pub(crate) struct PackEntry {
+    pub id: git_hash::ObjectId,
+    pub crc32: u32,
+    pub pack_offset: crate::data::Offset,
+}
+
 /// Information gathered while executing [`write_data_iter_to_stream()`][crate::index::File::write_data_iter_to_stream]
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
@@ -35,6 +42,9 @@ impl crate::index::File {
     /// `entries` iterator.
     ///
     /// * `kind` is the version of pack index to produce, use [`crate::index::Version::default()`] if in doubt.
+    /// * `resolve_external_base(&oid, &mut Vec<u8>) -> Option<git_object::Kind>` provides the base objects of ref-delta entries, use
+    ///    [`no_external_bases`][crate::index::traverse::no_external_bases()] if there are none or
+    ///    [`external_bases_from()`][crate::index::traverse::external_bases_from()] to look them up in an object database.
     /// * `tread_limit` is used for a parallel tree traversal for obtaining object hashes with optimal performance.
     /// * `root_progress` is the top-level progress to stay informed about the progress of this potentially long-running
     ///    computation.
@@ -44,15 +54,17 @@ impl crate::index::File {
     ///
     /// # Remarks
     ///
-    /// * neither in-pack nor out-of-pack Ref Deltas are supported here, these must have been resolved beforehand.
+    /// * all Ref Deltas are resolved through `resolve_external_base`, even if their base is contained in the pack, as object ids
+    ///   of entries aren't known before the traversal. Thin packs that are to be written to disk should have been completed beforehand.
     /// * `make_resolver()` will only be called after the iterator stopped returning elements and produces a function that
     /// provides all bytes belonging to a pack entry writing them to the given mutable output `Vec`.
     /// It should return `None` if the entry cannot be resolved from the pack that produced the `entries` iterator, causing
     /// the write operation to fail.
     #[allow(clippy::too_many_arguments)]
-    pub fn write_data_iter_to_stream<F, F2>(
+    pub fn write_data_iter_to_stream<F, F2, R>(
         version: crate::index::Version,
         make_resolver: F,
+        resolve_external_base: R,
         entries: impl Iterator<Item = Result<crate::data::input::Entry, crate::data::input::Error>>,
         thread_limit: Option<usize>,
         mut root_progress: impl Progress,
@@ -64,6 +76,7 @@ impl crate::index::File {
     where
         F: FnOnce() -> io::Result<F2>,
         F2: for<'r> Fn(crate::data::EntryRange, &'r mut Vec<u8>) -> Option<()> + Send + Clone,
+        R: for<'r> Fn(&git_hash::oid, &'r mut Vec<u8>) -> Option<git_object::Kind> + Send + Clone,
     {
         if version != crate::index::Version::default() {
             return Err(Error::Unsupported(version));
@@ -111,7 +124,16 @@ impl crate::index::File {
                         },
                     )?;
                 }
-                RefDelta { .. } => return Err(Error::IteratorInvariantNoRefDelta),
+                RefDelta { base_id } => {
+                    tree.add_child_with_external_base(
+                        base_id,
+                        pack_offset,
+                        TreeEntry {
+                            id: object_hash.null(),
+                            crc32,
+                        },
+                    )?;
+                }
                 OfsDelta { base_distance } => {
                     let base_pack_offset =
                         crate::data::entry::Header::verified_base_pack_offset(pack_offset, base_distance).ok_or(
@@ -155,6 +177,7 @@ impl crate::index::File {
         let sorted_pack_offsets_by_oid = {
             let traverse::Outcome { roots, children } = tree.traverse(
                 resolver,
+                resolve_external_base,
                 pack_entries_end,
                 || (),
                 |data,
@@ -164,8 +187,7 @@ impl crate::index::File {
                      decompressed: bytes,
                      ..
                  }| {
-                    modify_base(data, entry, bytes, version.hash());
-                    Ok::<_, Error>(())
+                    modify_base(data, entry, bytes, object_hash)
                 },
                 traverse::Options {
                     object_progress: root_progress.add_child_with_id("Resolving", *b"IWRO"), /* Index Write Resolve Objects */
@@ -194,13 +216,16 @@ impl crate::index::File {
                 let header = crate::data::header::encode(pack_version, 0);
                 let mut hasher = git_features::hash::hasher(object_hash);
                 hasher.update(&header);
-                git_hash::ObjectId::from(hasher.digest())
+                hasher.try_digest().map_err(|err| Error::Collision {
+                    pack_offset: 0,
+                    source: err,
+                })?
             }
             None => return Err(Error::IteratorInvariantTrailer),
         };
         let index_hash = encode::write_to(
             out,
-            sorted_pack_offsets_by_oid,
+            &sorted_pack_offsets_by_oid,
             &pack_hash,
             version,
             root_progress.add_child_with_id("writing index file", *b"IWBW"), /* Index Write Bytes Written */
@@ -220,15 +245,28 @@ impl crate::index::File {
     }
 }
 
-fn modify_base(entry: &mut TreeEntry, pack_entry: &crate::data::Entry, decompressed: &[u8], hash: git_hash::Kind) {
-    fn compute_hash(kind: git_object::Kind, bytes: &[u8], object_hash: git_hash::Kind) -> git_hash::ObjectId {
+fn modify_base(
+    entry: &mut TreeEntry,
+    pack_entry: &crate::data::Entry,
+    decompressed: &[u8],
+    hash: git_hash::Kind,
+) -> Result<(), Error> {
+    fn compute_hash(
+        kind: git_object::Kind,
+        bytes: &[u8],
+        object_hash: git_hash::Kind,
+    ) -> Result<git_hash::ObjectId, git_features::hash::Collision> {
         let mut hasher = git_features::hash::hasher(object_hash);
         hasher.update(&git_object::encode::loose_header(kind, bytes.len()));
         hasher.update(bytes);
-        git_hash::ObjectId::from(hasher.digest())
+        hasher.try_digest()
     }
 
     let object_kind = pack_entry.header.as_kind().expect("base object as source of iteration");
-    let id = compute_hash(object_kind, decompressed, hash);
+    let id = compute_hash(object_kind, decompressed, hash).map_err(|err| Error::Collision {
+        pack_offset: pack_entry.pack_offset(),
+        source: err,
+    })?;
     entry.id = id;
+    Ok(())
 }
@@ -45,6 +45,9 @@ pub mod index;
 ///
 pub mod multi_index;
 
+///
+pub mod bitmap;
+
 ///
 pub mod verify;
 
@@ -28,46 +28,71 @@ impl File {
     pub fn version(&self) -> Version {
         self.version
     }
-    /// Returns the path from which the multi-index file was loaded.
+    /// Returns the path from which the multi-index file was loaded, which is the chain file for the top-most layer of a chain.
     ///
     /// Note that it might have changed in the mean time, or might have been removed as well.
     pub fn path(&self) -> &Path {
-        &self.path
+        self.chain_path.as_deref().unwrap_or(&self.path)
+    }
+    /// Returns the directory containing the index files listed in [`index_names()`][File::index_names()], along with their packs.
+    pub fn pack_dir(&self) -> &Path {
+        &self.pack_dir
     }
     /// Returns the amount of indices stored in this multi-index file. It's the same as [File::index_names().len()][File::index_names()],
     /// and returned as one past the highest known index.
     pub fn num_indices(&self) -> PackIndex {
-        self.num_indices
+        self.num_indices_in_base + self.num_indices
     }
     /// Returns the total amount of objects available for lookup, and returned as one past the highest known entry index
     pub fn num_objects(&self) -> EntryIndex {
-        self.num_objects
+        self.num_objects_in_base + self.num_objects
+    }
+    /// Returns the layer this one is based on if it is part of a chain of multi-pack indices.
+    pub fn base(&self) -> Option<&File> {
+        self.base.as_deref()
+    }
+    /// Returns an iterator over all layers of the chain this file is part of, starting with this one and ending with
+    /// the bottom-most layer.
+    ///
+    /// Files that aren't part of a chain yield only themselves.
+    pub fn layers(&self) -> impl Iterator<Item = &File> {
+        std::iter::successors(Some(self), |layer| layer.base())
     }
     /// Returns the kind of hash function used for object ids available in this index.
     pub fn object_hash(&self) -> git_hash::Kind {
         self.object_hash
     }
-    /// Returns the checksum over the entire content of the file (excluding the checksum itself).
+    /// Returns the checksum over the entire content of the file (excluding the checksum itself), which is the one of this layer
+    /// if it is part of a chain.
     ///
     /// It can be used to validate it didn't change after creation.
     pub fn checksum(&self) -> git_hash::ObjectId {
         git_hash::ObjectId::from(&self.data[self.data.len() - self.hash_len..])
     }
-    /// Return all names of index files (`*.idx`) whose objects we contain.
+    /// Return all names of index files (`*.idx`) whose objects we contain, including the ones of all base layers.
     ///
-    /// The corresponding pack can be found by replacing the `.idx` extension with `.pack`.
+    /// The corresponding pack can be found by replacing the `.idx` extension with `.pack`, both are located in the [`pack_dir()`][File::pack_dir()].
     pub fn index_names(&self) -> &[PathBuf] {
         &self.index_names
     }
 }
 
 impl File {
+    /// Return the layer containing the entry at `index` along with the index of the entry within that layer.
+    fn layer_at_index(&self, index: EntryIndex) -> (&File, usize) {
+        let mut layer = self;
+        while index < layer.num_objects_in_base {
+            layer = layer.base().expect("base layers contain all lower entry indices");
+        }
+        (layer, (index - layer.num_objects_in_base) as usize)
+    }
+
     /// Return the object id at the given `index`, which ranges from 0 to [File::num_objects()].
     pub fn oid_at_index(&self, index: EntryIndex) -> &git_hash::oid {
-        debug_assert!(index < self.num_objects, "index out of bounds");
-        let index: usize = index as usize;
-        let start = self.lookup_ofs + index * self.hash_len;
-        git_hash::oid::from_bytes_unchecked(&self.data[start..][..self.hash_len])
+        debug_assert!(index < self.num_objects(), "index out of bounds");
+        let (layer, index) = self.layer_at_index(index);
+        let start = layer.lookup_ofs + index * layer.hash_len;
+        git_hash::oid::from_bytes_unchecked(&layer.data[start..][..layer.hash_len])
     }
 
     /// Given a `prefix`, find an object that matches it uniquely within this index and return `Some(Ok(entry_index))`.
@@ -77,28 +102,79 @@ impl File {
     ///
     /// Pass `candidates` to obtain the set of entry-indices matching `prefix`, with the same return value as
     /// one would have received if it remained `None`. It will be empty if no object matched the `prefix`.
+    /// As `candidates` can only represent matches within one layer of a chain, it will only contain the
+    /// ones of the top-most layer with matches. Use [`lookup_prefix_in_layer()`][File::lookup_prefix_in_layer()]
+    /// on all [`layers()`][File::layers()] to obtain all of them.
+    pub fn lookup_prefix(
+        &self,
+        prefix: git_hash::Prefix,
+        mut candidates: Option<&mut Range<EntryIndex>>,
+    ) -> Option<PrefixLookupResult> {
+        let mut res = None;
+        let mut layer_candidates = candidates.as_ref().map(|_| 0..0);
+        for layer in self.layers() {
+            let layer_res = match layer.lookup_prefix_in_layer(prefix, layer_candidates.as_mut()) {
+                Some(layer_res) => layer_res,
+                None => continue,
+            };
+            match res {
+                None => {
+                    res = Some(layer_res);
+                    if let Some((candidates, layer_candidates)) =
+                        candidates.as_deref_mut().zip(layer_candidates.clone())
+                    {
+                        *candidates = layer_candidates;
+                    }
+                }
+                Some(_) => return Some(Err(())),
+            }
+        }
+        if res.is_none() {
+            if let Some(candidates) = candidates {
+                *candidates = 0..0;
+            }
+        }
+        res
+    }
+
+    /// Like [`lookup_prefix()`][File::lookup_prefix()], but only considers the objects in this layer, ignoring all of its base layers.
     ///
+    /// Entry indices are still valid for use with the top-most layer of the chain.
     // NOTE: pretty much the same things as in `index::File::lookup`, change things there
     //       as well.
-    pub fn lookup_prefix(
+    pub fn lookup_prefix_in_layer(
         &self,
         prefix: git_hash::Prefix,
         candidates: Option<&mut Range<EntryIndex>>,
     ) -> Option<PrefixLookupResult> {
-        crate::index::access::lookup_prefix(
+        let offset = self.num_objects_in_base;
+        let mut layer_candidates = candidates.as_ref().map(|_| 0..0);
+        let res = crate::index::access::lookup_prefix(
             prefix,
-            candidates,
+            layer_candidates.as_mut(),
             &self.fan,
-            |idx| self.oid_at_index(idx),
+            |idx| self.oid_at_index(offset + idx),
             self.num_objects,
-        )
+        );
+        if let Some((candidates, layer_candidates)) = candidates.zip(layer_candidates) {
+            *candidates = if layer_candidates.is_empty() {
+                0..0
+            } else {
+                offset + layer_candidates.start..offset + layer_candidates.end
+            };
+        }
+        res.map(|res| res.map(|idx| offset + idx))
     }
 
     /// Find the index ranging from 0 to [File::num_objects()] that belongs to data associated with `id`, or `None` if it wasn't found.
     ///
     /// Use this index for finding additional information via [`File::pack_id_and_pack_offset_at_index()`].
     pub fn lookup(&self, id: impl AsRef<git_hash::oid>) -> Option<EntryIndex> {
-        crate::index::access::lookup(id, &self.fan, |idx| self.oid_at_index(idx))
+        let id = id.as_ref();
+        self.layers().find_map(|layer| {
+            let offset = layer.num_objects_in_base;
+            crate::index::access::lookup(id, &layer.fan, |idx| layer.oid_at_index(offset + idx)).map(|idx| offset + idx)
+        })
     }
 
     /// Given the `index` ranging from 0 to [File::num_objects()], return the pack index and its absolute offset into the pack.
@@ -106,20 +182,21 @@ impl File {
     /// The pack-index refers to an entry in the [`index_names`][File::index_names()] list, from which the pack can be derived.
     pub fn pack_id_and_pack_offset_at_index(&self, index: EntryIndex) -> (PackIndex, data::Offset) {
         const OFFSET_ENTRY_SIZE: usize = 4 + 4;
-        let index = index as usize;
-        let start = self.offsets_ofs + index * OFFSET_ENTRY_SIZE;
+        let (layer, index) = self.layer_at_index(index);
+        let start = layer.offsets_ofs + index * OFFSET_ENTRY_SIZE;
 
         const HIGH_BIT: u32 = 1 << 31;
 
-        let pack_index = crate::read_u32(&self.data[start..][..4]);
-        let offset = &self.data[start + 4..][..4];
+        // Pack indices are stored relative to their layer, but are numbered across the whole chain just like entry indices.
+        let pack_index = layer.num_indices_in_base + crate::read_u32(&layer.data[start..][..4]);
+        let offset = &layer.data[start + 4..][..4];
         let ofs32 = crate::read_u32(offset);
         let pack_offset = if (ofs32 & HIGH_BIT) == HIGH_BIT {
             // We determine if large offsets are actually larger than 4GB and if not, we don't use the high-bit to signal anything
             // but allow the presence of the large-offset chunk to signal what's happening.
-            if let Some(offsets_64) = self.large_offsets_ofs {
+            if let Some(offsets_64) = layer.large_offsets_ofs {
                 let from = offsets_64 + (ofs32 ^ HIGH_BIT) as usize * 8;
-                crate::read_u64(&self.data[from..][..8])
+                crate::read_u64(&layer.data[from..][..8])
             } else {
                 ofs32 as u64
             }
@@ -129,9 +206,24 @@ impl File {
         (pack_index, pack_offset)
     }
 
-    /// Return an iterator over all entries within this file.
+    /// Return the entry index of the object at `position` in the pseudo-pack, or `None` if this file doesn't have a reverse index
+    /// or if `position` is out of bounds.
+    ///
+    /// The pseudo-pack order is the order in which [reachability bitmaps][crate::bitmap] refer to objects.
+    /// In a chain, the positions of objects in base layers come first.
+    pub fn index_at_pseudo_pack_position(&self, position: u32) -> Option<EntryIndex> {
+        if position < self.num_objects_in_base {
+            return self.base()?.index_at_pseudo_pack_position(position);
+        }
+        let ofs = self.reverse_index_ofs?;
+        let position = position - self.num_objects_in_base;
+        (position < self.num_objects)
+            .then(|| self.num_objects_in_base + crate::read_u32(&self.data[ofs + position as usize * 4..][..4]))
+    }
+
+    /// Return an iterator over all entries within this file, including the ones in all base layers.
     pub fn iter(&self) -> impl Iterator<Item = Entry> + '_ {
-        (0..self.num_objects).map(move |idx| {
+        (0..self.num_objects()).map(move |idx| {
             let (pack_index, pack_offset) = self.pack_id_and_pack_offset_at_index(idx);
             Entry {
                 oid: self.oid_at_index(idx).to_owned(),
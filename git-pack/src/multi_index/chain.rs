@@ -0,0 +1,46 @@
+use std::path::{Path, PathBuf};
+
+use crate::multi_index::init::Error;
+
+/// The name of the directory within the pack directory which contains the chain file and its layers.
+pub const DIR_NAME: &str = "multi-pack-index.d";
+/// The name of the file listing the checksums of all layers of a chain, one per line, starting with the bottom-most layer.
+pub const FILE_NAME: &str = "multi-pack-index-chain";
+
+/// Return the path to the chain file for the given `pack_dir`.
+pub fn path(pack_dir: impl AsRef<Path>) -> PathBuf {
+    pack_dir.as_ref().join(DIR_NAME).join(FILE_NAME)
+}
+
+/// Return the path of the layer with `checksum` in the `chain_dir`, the directory containing the chain file.
+pub fn layer_path(chain_dir: impl AsRef<Path>, checksum: &git_hash::oid) -> PathBuf {
+    chain_dir
+        .as_ref()
+        .join(format!("multi-pack-index-{}.midx", checksum.to_hex()))
+}
+
+/// Read the checksums of all layers listed in the chain file at `path`, starting with the bottom-most layer.
+pub(crate) fn read(path: &Path) -> Result<Vec<git_hash::ObjectId>, Error> {
+    let content = std::fs::read(path).map_err(|source| Error::Io {
+        source,
+        path: path.to_owned(),
+    })?;
+    content
+        .split(|b| *b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            git_hash::ObjectId::from_hex(line).map_err(|_| Error::Corrupt {
+                message: "The multi-index chain contains an invalid layer checksum",
+            })
+        })
+        .collect()
+}
+
+/// Write `layers`, starting with the bottom-most one, in the format of a chain file to `out`.
+pub(crate) fn write(layers: &[git_hash::ObjectId], mut out: impl std::io::Write) -> std::io::Result<()> {
+    for layer in layers {
+        writeln!(out, "{}", layer)?;
+    }
+    Ok(())
+}
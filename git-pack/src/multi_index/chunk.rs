@@ -274,3 +274,19 @@ pub mod large_offsets {
         8 * large_offsets as u64
     }
 }
+
+/// Information about the reverse index, which maps positions in the pseudo-pack to entry indices.
+///
+/// The pseudo-pack is made by concatenating all objects in packs in order of their pack index, with the preferred pack
+/// going first, and with duplicates removed. Reachability bitmaps refer to objects by their position in the pseudo-pack.
+pub mod reverse_index {
+    use std::ops::Range;
+
+    /// The id uniquely identifying the reverse index table.
+    pub const ID: git_chunk::Id = *b"RIDX";
+
+    /// Returns true if the `offset` range seems to match the size required for `num_objects`.
+    pub fn is_valid(offset: &Range<usize>, num_objects: u32) -> bool {
+        offset.end - offset.start == num_objects as usize * 4
+    }
+}
@@ -1,6 +1,10 @@
-use std::{convert::TryFrom, path::Path};
+use std::{
+    convert::TryFrom,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
 
-use crate::multi_index::{chunk, File, Version};
+use crate::multi_index::{chain, chunk, File, Version};
 
 mod error {
     use crate::multi_index::chunk;
@@ -40,6 +44,8 @@ pub use error::Error;
 /// Initialization
 impl File {
     /// Open the multi-index file at the given `path`.
+    ///
+    /// If `path` points to a `multi-pack-index-chain` file, all layers of the chain are opened instead.
     pub fn at(path: impl AsRef<Path>) -> Result<Self, Error> {
         Self::try_from(path.as_ref())
     }
@@ -49,6 +55,50 @@ impl TryFrom<&Path> for File {
     type Error = Error;
 
     fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        if path.file_name() == Some(OsStr::new(chain::FILE_NAME)) {
+            return Self::chain_at(path);
+        }
+        let pack_dir = path.parent().expect("must be in a directory").to_owned();
+        Self::layer_at(path, None, pack_dir)
+    }
+}
+
+impl File {
+    fn chain_at(chain_path: &Path) -> Result<Self, Error> {
+        let chain_dir = chain_path.parent().expect("must be in a directory");
+        let pack_dir = chain_dir.parent().expect("chain directory is in the pack directory");
+        let layers = chain::read(chain_path)?;
+
+        let mut base = None::<File>;
+        for (num_base_layers, checksum) in layers.into_iter().enumerate() {
+            let layer = Self::layer_at(&chain::layer_path(chain_dir, &checksum), base, pack_dir.to_owned())?;
+            if layer.checksum() != checksum {
+                return Err(Error::Corrupt {
+                    message: "The checksum of a multi-index layer didn't match its name in the chain",
+                });
+            }
+            if layer.num_base_files != 0 && layer.num_base_files as usize != num_base_layers {
+                return Err(Error::Corrupt {
+                    message: "The number of base files of a multi-index layer didn't match its position in the chain",
+                });
+            }
+            if let Some(base) = &layer.base {
+                if base.object_hash != layer.object_hash {
+                    return Err(Error::Corrupt {
+                        message: "All layers of a multi-index chain must use the same object hash",
+                    });
+                }
+            }
+            base = Some(layer);
+        }
+        let mut file = base.ok_or(Error::Corrupt {
+            message: "The multi-index chain doesn't list any layers",
+        })?;
+        file.chain_path = Some(chain_path.to_owned());
+        Ok(file)
+    }
+
+    fn layer_at(path: &Path, base: Option<File>, pack_dir: PathBuf) -> Result<Self, Error> {
         let data = crate::mmap::read_only(path).map_err(|source| Error::Io {
             source,
             path: path.to_owned(),
@@ -66,7 +116,7 @@ impl TryFrom<&Path> for File {
             });
         }
 
-        let (version, object_hash, num_chunks, num_indices) = {
+        let (version, object_hash, num_chunks, num_base_files, num_indices) = {
             let (signature, data) = data.split_at(4);
             if signature != Self::SIGNATURE {
                 return Err(Error::Corrupt {
@@ -85,18 +135,23 @@ impl TryFrom<&Path> for File {
             let (num_chunks, data) = data.split_at(1);
             let num_chunks = num_chunks[0];
 
-            let (_num_base_files, data) = data.split_at(1); // TODO: handle base files once it's clear what this does
+            let (num_base_files, data) = data.split_at(1);
+            let num_base_files = num_base_files[0];
 
             let (num_indices, _) = data.split_at(4);
             let num_indices = crate::read_u32(num_indices);
 
-            (version, object_hash, num_chunks, num_indices)
+            (version, object_hash, num_chunks, num_base_files, num_indices)
         };
 
         let chunks = git_chunk::file::Index::from_bytes(&data, Self::HEADER_LEN, num_chunks as u32)?;
 
         let index_names = chunks.data_by_id(&data, chunk::index_names::ID)?;
         let index_names = chunk::index_names::from_bytes(index_names, num_indices)?;
+        let index_names = match &base {
+            Some(base) => base.index_names.iter().cloned().chain(index_names).collect(),
+            None => index_names,
+        };
 
         let fan = chunks.data_by_id(&data, chunk::fanout::ID)?;
         let fan = chunk::fanout::from_bytes(fan).ok_or(Error::MultiPackFanSize)?;
@@ -129,6 +184,17 @@ impl TryFrom<&Path> for File {
             })
             .ok()
             .transpose()?;
+        let reverse_index = chunks
+            .validated_usize_offset_by_id(chunk::reverse_index::ID, |offset| {
+                chunk::reverse_index::is_valid(&offset, num_objects)
+                    .then(|| offset)
+                    .ok_or(Error::InvalidChunkSize {
+                        id: chunk::reverse_index::ID,
+                        message: "The chunk with the pseudo-pack order doesn't have the correct size",
+                    })
+            })
+            .ok()
+            .transpose()?;
 
         let checksum_offset = chunks.highest_offset() as usize;
         let trailer = &data[checksum_offset..];
@@ -139,9 +205,14 @@ impl TryFrom<&Path> for File {
             });
         }
 
+        let (num_indices_in_base, num_objects_in_base) = base
+            .as_ref()
+            .map_or((0, 0), |base| (base.num_indices(), base.num_objects()));
         Ok(File {
             data,
             path: path.to_owned(),
+            chain_path: None,
+            pack_dir,
             version,
             hash_len: object_hash.len_in_bytes(),
             object_hash,
@@ -150,8 +221,13 @@ impl TryFrom<&Path> for File {
             lookup_ofs: lookup.start,
             offsets_ofs: offsets.start,
             large_offsets_ofs: large_offsets.map(|r| r.start),
+            reverse_index_ofs: reverse_index.map(|r| r.start),
             num_objects,
             num_indices,
+            num_base_files,
+            base: base.map(Box::new),
+            num_indices_in_base,
+            num_objects_in_base,
         })
     }
 }
@@ -24,21 +24,39 @@ pub type EntryIndex = u32;
 
 /// A representation of an index file for multiple packs at the same time, typically stored in a file
 /// named 'multi-pack-index'.
+///
+/// It can also be the top-most layer of a chain of multi-pack indices as listed in `multi-pack-index.d/multi-pack-index-chain`,
+/// in which case all its methods operate on all layers. Entry indices and pack indices are numbered across the whole chain,
+/// starting at the bottom-most layer.
 pub struct File {
     data: Mmap,
     path: std::path::PathBuf,
+    /// The path to the chain file if this is the top-most layer of a chain.
+    chain_path: Option<PathBuf>,
+    /// The directory containing the indices we refer to.
+    pack_dir: PathBuf,
     version: Version,
     hash_len: usize,
     object_hash: git_hash::Kind,
-    /// The amount of pack files contained within
+    /// The amount of pack files contained within this layer
     num_indices: u32,
+    /// The amount of objects contained within this layer
     num_objects: u32,
+    /// The amount of base files as stored in the header, which is zero even for layers of a chain if written by `git`.
+    num_base_files: u8,
+    /// The layer below this one, if this file is part of a chain.
+    base: Option<Box<File>>,
+    /// The amount of pack files in all layers below this one.
+    num_indices_in_base: u32,
+    /// The amount of objects in all layers below this one.
+    num_objects_in_base: u32,
 
     fan: [u32; 256],
     index_names: Vec<PathBuf>,
     lookup_ofs: usize,
     offsets_ofs: usize,
     large_offsets_ofs: Option<usize>,
+    reverse_index_ofs: Option<usize>,
 }
 
 ///
@@ -55,3 +73,6 @@ pub mod chunk;
 
 ///
 pub mod init;
+
+///
+pub mod chain;
@@ -58,13 +58,25 @@ pub mod checksum {
 impl File {
     /// Validate that our [`checksum()`][File::checksum()] matches the actual contents
     /// of this index file, and return it if it does.
+    ///
+    /// If this file is part of a chain, the checksums of all base layers are validated as well.
     pub fn verify_checksum(
         &self,
-        progress: impl Progress,
+        mut progress: impl Progress,
         should_interrupt: &AtomicBool,
     ) -> Result<git_hash::ObjectId, checksum::Error> {
+        for base in self.layers().skip(1) {
+            crate::verify::checksum_on_disk_or_mmap(
+                &base.path,
+                &base.data,
+                base.checksum(),
+                base.object_hash,
+                progress.add_child_with_id(format!("{}: checksum", base.path.display()), *b"MVBC"), /* Multiindex Verify Base Checksum */
+                should_interrupt,
+            )?;
+        }
         crate::verify::checksum_on_disk_or_mmap(
-            self.path(),
+            &self.path,
             &self.data,
             self.checksum(),
             self.object_hash,
@@ -126,7 +138,7 @@ impl File {
         C: crate::cache::DecodeEntry,
         F: Fn() -> C + Send + Clone,
     {
-        let parent = self.path.parent().expect("must be in a directory");
+        let parent = self.pack_dir();
 
         let actual_index_checksum = self
             .verify_checksum(
@@ -136,54 +148,54 @@ impl File {
             .map_err(integrity::Error::from)
             .map_err(index::traverse::Error::Processor)?;
 
-        if let Some(first_invalid) = crate::verify::fan(&self.fan) {
-            return Err(index::traverse::Error::Processor(integrity::Error::Fan {
-                index: first_invalid,
-            }));
-        }
+        for layer in self.layers() {
+            if let Some(first_invalid) = crate::verify::fan(&layer.fan) {
+                return Err(index::traverse::Error::Processor(integrity::Error::Fan {
+                    index: first_invalid,
+                }));
+            }
 
-        if self.num_objects == 0 {
-            return Err(index::traverse::Error::Processor(integrity::Error::Empty));
+            if layer.num_objects == 0 {
+                return Err(index::traverse::Error::Processor(integrity::Error::Empty));
+            }
         }
 
         let mut pack_traverse_statistics = Vec::new();
 
         let operation_start = Instant::now();
         let mut total_objects_checked = 0;
-        let mut pack_ids_and_offsets = Vec::with_capacity(self.num_objects as usize);
+        let mut pack_ids_and_offsets = Vec::with_capacity(self.num_objects() as usize);
         {
             let order_start = Instant::now();
             let mut progress = progress.add_child_with_id("checking oid order", *b"MVOR"); /* Multiindex Verify Oid oRder */
             progress.init(
-                Some(self.num_objects as usize),
+                Some(self.num_objects() as usize),
                 git_features::progress::count("objects"),
             );
 
-            for entry_index in 0..(self.num_objects - 1) {
-                let lhs = self.oid_at_index(entry_index);
-                let rhs = self.oid_at_index(entry_index + 1);
+            for entry_index in 0..self.num_objects() {
+                // objects are only ordered within their layer
+                if entry_index + 1 < self.num_objects() && !self.is_first_index_of_layer(entry_index + 1) {
+                    let lhs = self.oid_at_index(entry_index);
+                    let rhs = self.oid_at_index(entry_index + 1);
 
-                if rhs.cmp(lhs) != Ordering::Greater {
-                    return Err(index::traverse::Error::Processor(integrity::Error::OutOfOrder {
-                        index: entry_index,
-                    }));
+                    if rhs.cmp(lhs) != Ordering::Greater {
+                        return Err(index::traverse::Error::Processor(integrity::Error::OutOfOrder {
+                            index: entry_index,
+                        }));
+                    }
                 }
                 let (pack_id, _) = self.pack_id_and_pack_offset_at_index(entry_index);
                 pack_ids_and_offsets.push((pack_id, entry_index));
                 progress.inc();
             }
-            {
-                let entry_index = self.num_objects - 1;
-                let (pack_id, _) = self.pack_id_and_pack_offset_at_index(entry_index);
-                pack_ids_and_offsets.push((pack_id, entry_index));
-            }
             // sort by pack-id to allow handling all indices matching a pack while its open.
             pack_ids_and_offsets.sort_by(|l, r| l.0.cmp(&r.0));
             progress.show_throughput(order_start);
         };
 
         progress.init(
-            Some(self.num_indices as usize),
+            Some(self.num_indices() as usize),
             git_features::progress::count("indices"),
         );
 
@@ -277,6 +289,7 @@ impl File {
                                 offset,
                                 kind,
                             },
+                            Collision { offset, kind, source } => Collision { offset, kind, source },
                             Crc32Mismatch {
                                 expected,
                                 actual,
@@ -297,7 +310,8 @@ impl File {
         }
 
         assert_eq!(
-            self.num_objects as usize, total_objects_checked,
+            self.num_objects() as usize,
+            total_objects_checked,
             "BUG: our slicing should allow to visit all objects"
         );
 
@@ -310,4 +324,8 @@ impl File {
             progress,
         })
     }
+
+    fn is_first_index_of_layer(&self, index: crate::multi_index::EntryIndex) -> bool {
+        self.layers().any(|layer| layer.num_objects_in_base == index)
+    }
 }
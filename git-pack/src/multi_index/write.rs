@@ -1,11 +1,12 @@
 use std::{
     convert::TryInto,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::atomic::{AtomicBool, Ordering},
     time::{Instant, SystemTime},
 };
 
 use git_features::progress::Progress;
+use git_tempfile::{AutoRemove, ContainingDirectory};
 
 use crate::multi_index;
 
@@ -20,6 +21,15 @@ mod error {
         Interrupted,
         #[error(transparent)]
         OpenIndex(#[from] crate::index::init::Error),
+        #[error(transparent)]
+        OpenChain(#[from] crate::multi_index::init::Error),
+        #[error("The lock on the multi-pack-index chain could not be obtained")]
+        LockAcquire(#[from] git_lock::acquire::Error),
+        #[error("The existing multi-index chain uses {actual:?} object ids, but {expected:?} was requested")]
+        ObjectHashMismatch {
+            expected: git_hash::Kind,
+            actual: git_hash::Kind,
+        },
     }
 }
 pub use error::Error;
@@ -34,9 +44,14 @@ pub(crate) struct Entry {
 }
 
 /// Options for use in [`multi_index::File::write_from_index_paths()`].
-pub struct Options {
+pub struct Options<'a> {
     /// The kind of hash to use for objects and to expect in the input files.
     pub object_hash: git_hash::Kind,
+    /// If set, write a layer to be put on top of `base` in a chain of multi-pack indices, containing only the objects of indices
+    /// that aren't yet contained in any of the layers of `base`.
+    ///
+    /// See [`multi_index::File::append_layer_to_chain()`] for a way to update the chain on disk as well.
+    pub base: Option<&'a multi_index::File>,
 }
 
 /// The result of [`multi_index::File::write_from_index_paths()`].
@@ -64,13 +79,16 @@ impl multi_index::File {
         out: impl std::io::Write,
         mut progress: P,
         should_interrupt: &AtomicBool,
-        Options { object_hash }: Options,
+        Options { object_hash, base }: Options<'_>,
     ) -> Result<Outcome<P>, Error>
     where
         P: Progress,
     {
         let out = git_features::hash::Write::new(out, object_hash);
         let (index_paths_sorted, index_filenames_sorted) = {
+            if let Some(base) = base {
+                index_paths.retain(|path| !is_in_layers(base, path));
+            }
             index_paths.sort();
             let file_names = index_paths
                 .iter()
@@ -116,6 +134,9 @@ impl multi_index::File {
                     .then_with(|| l.pack_index.cmp(&r.pack_index))
             });
             entries.dedup_by_key(|e| e.id);
+            if let Some(base) = base {
+                entries.retain(|e| base.lookup(e.id).is_none());
+            }
             progress.inc_by(entries.len());
             progress.show_throughput(start);
             if should_interrupt.load(Ordering::Relaxed) {
@@ -195,7 +216,7 @@ impl multi_index::File {
         }
 
         // write trailing checksum
-        let multi_index_checksum: git_hash::ObjectId = out.inner.hash.digest().into();
+        let multi_index_checksum = out.inner.hash.digest();
         out.inner.inner.write_all(multi_index_checksum.as_slice())?;
         out.progress.show_throughput(write_start);
 
@@ -220,4 +241,83 @@ impl multi_index::File {
 
         Ok(Self::HEADER_LEN)
     }
+
+    /// Append a new layer to the chain of multi-pack indices in `pack_dir` which contains the objects of all indices at
+    /// `index_paths` that aren't yet contained in the chain, creating the chain if it doesn't exist yet.
+    ///
+    /// The new layer is written to `multi-pack-index.d/multi-pack-index-<checksum>.midx` before it is added to the chain file.
+    /// Return `None` if all indices are already contained in the chain, leaving it unchanged.
+    ///
+    /// The chain file is locked before it is read and written through its lock, with `lock_mode` determining how to wait
+    /// for it, so that concurrent appends can't drop each other's layers.
+    pub fn append_layer_to_chain<P>(
+        pack_dir: impl AsRef<Path>,
+        index_paths: Vec<PathBuf>,
+        mut progress: P,
+        should_interrupt: &AtomicBool,
+        object_hash: git_hash::Kind,
+        lock_mode: git_lock::acquire::Fail,
+    ) -> Result<Option<Outcome<P>>, Error>
+    where
+        P: Progress,
+    {
+        let pack_dir = pack_dir.as_ref();
+        let chain_path = multi_index::chain::path(pack_dir);
+        let chain_dir = chain_path.parent().expect("chain file is in a directory");
+        let mut chain_lock =
+            git_lock::File::acquire_to_update_resource(&chain_path, lock_mode, Some(pack_dir.to_owned()))?;
+        let base = if chain_path.is_file() {
+            let base = multi_index::File::at(&chain_path)?;
+            if base.object_hash() != object_hash {
+                return Err(Error::ObjectHashMismatch {
+                    expected: object_hash,
+                    actual: base.object_hash(),
+                });
+            }
+            Some(base)
+        } else {
+            None
+        };
+        if index_paths
+            .iter()
+            .all(|path| base.as_ref().map_or(false, |base| is_in_layers(base, path)))
+        {
+            return Ok(None);
+        }
+
+        let mut layer_file = git_tempfile::new(chain_dir, ContainingDirectory::Exists, AutoRemove::Tempfile)?;
+        let outcome = Self::write_from_index_paths(
+            index_paths,
+            &mut layer_file,
+            progress.add_child_with_id("write layer", *b"MPWL"), /* Multiindex from Paths Write Layer */
+            should_interrupt,
+            Options {
+                object_hash,
+                base: base.as_ref(),
+            },
+        )?;
+        layer_file
+            .persist(multi_index::chain::layer_path(chain_dir, &outcome.multi_index_checksum))
+            .map_err(|err| err.error)?;
+
+        let mut layers: Vec<_> = base
+            .iter()
+            .flat_map(|base| base.layers().map(|layer| layer.checksum()))
+            .collect();
+        layers.reverse();
+        layers.push(outcome.multi_index_checksum);
+        multi_index::chain::write(&layers, &mut chain_lock)?;
+        chain_lock.commit().map_err(|err| err.error)?;
+
+        Ok(Some(Outcome {
+            multi_index_checksum: outcome.multi_index_checksum,
+            progress,
+        }))
+    }
+}
+
+fn is_in_layers(file: &multi_index::File, index_path: &Path) -> bool {
+    index_path.file_name().map_or(false, |name| {
+        file.index_names().iter().any(|known| known == Path::new(name))
+    })
 }
@@ -15,6 +15,10 @@ pub mod checksum {
             expected: git_hash::ObjectId,
             actual: git_hash::ObjectId,
         },
+        #[error("The file could not be read to compute its checksum")]
+        Io(#[from] std::io::Error),
+        #[error("A collision attack was detected while computing the checksum")]
+        Collision(#[from] git_features::hash::Collision),
     }
 }
 
@@ -52,7 +56,7 @@ pub fn checksum_on_disk_or_mmap(
             hasher.update(&data[..data_len_without_trailer]);
             progress.inc_by(data_len_without_trailer);
             progress.show_throughput(start);
-            git_hash::ObjectId::from(hasher.digest())
+            hasher.try_digest()?
         }
     };
 
@@ -62,3 +66,32 @@ pub fn checksum_on_disk_or_mmap(
         Err(checksum::Error::Mismatch { actual, expected })
     }
 }
+
+/// Calculate the hash of the given kind over the first `num_bytes_from_start` bytes of the file at `data_path`.
+/// `Ok(desired_hash)` is returned if the hash matches `expected`.
+pub fn checksum_on_disk(
+    data_path: &Path,
+    num_bytes_from_start: usize,
+    expected: git_hash::ObjectId,
+    object_hash: git_hash::Kind,
+    mut progress: impl Progress,
+    should_interrupt: &AtomicBool,
+) -> Result<git_hash::ObjectId, checksum::Error> {
+    let actual = match git_features::hash::bytes_of_file(
+        data_path,
+        num_bytes_from_start,
+        object_hash,
+        &mut progress,
+        should_interrupt,
+    ) {
+        Ok(id) => id,
+        Err(err) if err.kind() == std::io::ErrorKind::Interrupted => return Err(checksum::Error::Interrupted),
+        Err(err) => return Err(err.into()),
+    };
+
+    if actual == expected {
+        Ok(actual)
+    } else {
+        Err(checksum::Error::Mismatch { actual, expected })
+    }
+}
@@ -0,0 +1,46 @@
+#!/bin/bash
+set -eu -o pipefail
+
+function write_history() {
+  mkdir -p a/b
+  for round in $(seq 20); do
+    seq "$round" > "file-$(( round % 7 ))"
+    echo "$round" > a/b/nested
+    git add .
+    git commit -qm "$round"
+    if [ "$round" = 10 ]; then
+      git tag -m "annotated" v1
+      git checkout -q -b side
+      echo side > side
+      git add side
+      git commit -qm "side"
+      git checkout -q main
+    fi
+  done
+}
+
+function list_reachable_objects() {
+  git rev-list --objects main side v1 | cut -d ' ' -f 1 | sort > reachable-objects
+  git rev-list --objects main~5 | cut -d ' ' -f 1 | sort > reachable-objects-from-main-5
+}
+
+git init -q single
+(cd single
+  git checkout -q -b main
+  write_history
+  git repack -adbq
+  list_reachable_objects
+)
+
+git init -q multi
+(cd multi
+  git checkout -q -b main
+  write_history
+  git repack -dq
+  echo more > more
+  git add more
+  git commit -qm "more"
+  git repack -dq
+  git multi-pack-index write --bitmap
+  list_reachable_objects
+)
@@ -0,0 +1,14 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git checkout -q -b main
+
+# each round adds a pack along with a layer of the multi-pack-index chain referring to it
+for round in $(seq 3); do
+  seq "$round" > "file-$round"
+  git add .
+  git commit -qm "$round"
+  git repack -dq
+  git multi-pack-index write --incremental
+done
@@ -0,0 +1,16 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git checkout -q -b main
+
+seq 1000 > file
+git add file
+git commit -qm "base"
+
+seq 1001 > file
+git add file
+git commit -qm "appended"
+
+printf 'main\n^main~1\n' | git pack-objects -q --thin --delta-base-offset --revs --stdout > thin.pack
+git rev-list --objects main~1..main | cut -d ' ' -f 1 | sort > objects-in-thin-pack
@@ -0,0 +1,288 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use git_bitmap::ewah;
+use git_hash::ObjectId;
+use git_pack::bitmap;
+
+fn repo(name: &str) -> PathBuf {
+    git_testtools::scripted_fixture_repo_read_only("make_pack_bitmap_repo.sh")
+        .expect("test fixture exists")
+        .join(name)
+}
+
+fn db(repo: &Path) -> git_odb::HandleArc {
+    let store = git_odb::Store::at_opts(
+        repo.join(".git/objects"),
+        Vec::new(),
+        git_odb::store::init::Options::default(),
+    )
+    .expect("valid object database");
+    let mut cache = Arc::new(store).to_cache_arc();
+    cache.prevent_pack_unload();
+    cache
+}
+
+fn pack_file_with_extension(repo: &Path, extension: &str) -> PathBuf {
+    std::fs::read_dir(repo.join(".git/objects/pack"))
+        .expect("pack directory exists")
+        .map(|entry| entry.expect("valid entry").path())
+        .find(|path| path.extension().and_then(|ext| ext.to_str()) == Some(extension))
+        .expect("file with extension exists")
+}
+
+fn expected_objects(repo: &Path, name: &str) -> BTreeSet<ObjectId> {
+    std::fs::read_to_string(repo.join(name))
+        .expect("fixture lists objects")
+        .lines()
+        .map(|hex| ObjectId::from_hex(hex.as_bytes()).expect("valid hex"))
+        .collect()
+}
+
+fn tips(repo: &Path, names: &[&str]) -> Vec<ObjectId> {
+    names
+        .iter()
+        .map(|name| {
+            let hex = std::fs::read_to_string(repo.join(".git").join(name)).expect("loose ref");
+            ObjectId::from_hex(hex.trim().as_bytes()).expect("valid hex")
+        })
+        .collect()
+}
+
+fn reachable_ids(outcome: &bitmap::reachable::Outcome, order: &bitmap::PackOrder<'_>) -> BTreeSet<ObjectId> {
+    outcome
+        .positions()
+        .map(|position| order.oid_at_position(position).to_owned())
+        .chain(outcome.extra_objects.iter().cloned())
+        .collect()
+}
+
+#[test]
+fn ewah_encoding_round_trips() {
+    for words in [
+        vec![],
+        vec![0],
+        vec![1],
+        vec![u64::MAX, u64::MAX, 0, 0, 0, 5, 7, u64::MAX, 0, 1 << 63],
+        vec![0, 0, 0, 0x8000_0000_0000_0001],
+    ] {
+        let bitmap = ewah::encode(&words);
+        let mut buf = Vec::new();
+        bitmap.write_to(&mut buf).unwrap();
+        let (decoded, rest) = ewah::decode(&buf).unwrap();
+        assert!(rest.is_empty());
+
+        let trailing_zeros = words.iter().rev().take_while(|w| **w == 0).count();
+        assert_eq!(decoded.to_words(), words[..words.len() - trailing_zeros]);
+        let mut expected_bits = Vec::new();
+        for (idx, word) in words.iter().enumerate() {
+            expected_bits.extend((0..64).filter(|bit| word & (1 << bit) != 0).map(|bit| idx * 64 + bit));
+        }
+        let mut actual_bits = Vec::new();
+        decoded.for_each_set_bit(|bit| {
+            actual_bits.push(bit);
+            Some(())
+        });
+        assert_eq!(actual_bits, expected_bits);
+        assert_eq!(decoded.num_bits(), expected_bits.last().map_or(0, |bit| bit + 1));
+    }
+}
+
+#[test]
+fn pack_bitmap_yields_reachable_objects() -> crate::Result {
+    let repo = repo("single");
+    let index = git_pack::index::File::at(pack_file_with_extension(&repo, "idx"), git_hash::Kind::Sha1)?;
+    let order = bitmap::PackOrder::from_index(&index);
+    let file = bitmap::File::at(pack_file_with_extension(&repo, "bitmap"), git_hash::Kind::Sha1)?;
+    assert_eq!(file.index_checksum(), index.pack_checksum());
+    assert!(file.num_commits() > 0, "git writes bitmaps for at least the tips");
+    assert!(
+        file.name_hash_at(0).is_some(),
+        "git writes a name-hash cache by default"
+    );
+
+    let db = db(&repo);
+    let outcome = file.reachable_objects(
+        &order,
+        tips(&repo, &["refs/heads/main", "refs/heads/side", "refs/tags/v1"]),
+        &db,
+        &AtomicBool::new(false),
+    )?;
+    assert!(outcome.extra_objects.is_empty(), "all objects are in the pack");
+    assert_eq!(
+        reachable_ids(&outcome, &order),
+        expected_objects(&repo, "reachable-objects")
+    );
+
+    let counts = git_pack::data::output::count::from_bitmap(&outcome, &order, &file);
+    assert_eq!(counts.len(), outcome.num_objects());
+    assert_eq!(
+        counts.iter().map(|c| c.id).collect::<BTreeSet<_>>(),
+        expected_objects(&repo, "reachable-objects")
+    );
+    assert!(
+        counts.iter().any(|c| c.name_hash != 0),
+        "name hashes are taken from the cache"
+    );
+
+    let main = tips(&repo, &["refs/heads/main"])[0];
+    let mut buf = Vec::new();
+    let mut parent = main;
+    for _ in 0..5 {
+        use git_pack::FindExt;
+        parent = db
+            .find_commit_iter(parent, &mut buf)?
+            .0
+            .parent_ids()
+            .next()
+            .expect("linear history");
+    }
+    let outcome = file.reachable_objects(&order, Some(parent), &db, &AtomicBool::new(false))?;
+    assert_eq!(
+        reachable_ids(&outcome, &order),
+        expected_objects(&repo, "reachable-objects-from-main-5"),
+        "commits without bitmap are traversed until a bitmap is found"
+    );
+    Ok(())
+}
+
+#[test]
+fn counting_uses_the_bitmap_if_all_tips_are_covered() -> crate::Result {
+    let repo = repo("single");
+    let index = git_pack::index::File::at(pack_file_with_extension(&repo, "idx"), git_hash::Kind::Sha1)?;
+    let order = bitmap::PackOrder::from_index(&index);
+    let file = bitmap::File::at(pack_file_with_extension(&repo, "bitmap"), git_hash::Kind::Sha1)?;
+    let db = db(&repo);
+
+    let tips = tips(&repo, &["refs/heads/main", "refs/heads/side", "refs/tags/v1"]);
+    let (counts, outcome) =
+        git_pack::data::output::count::reachable_from_bitmap(&file, &order, &tips, &db, &AtomicBool::new(false))?
+            .expect("all tips are in the pack");
+    assert_eq!(
+        counts.iter().map(|c| c.id).collect::<BTreeSet<_>>(),
+        expected_objects(&repo, "reachable-objects")
+    );
+    assert_eq!(outcome.input_objects, 3);
+    assert_eq!(outcome.total_objects, counts.len());
+    assert_eq!(
+        outcome.bitmap_objects,
+        counts.len(),
+        "all objects were obtained from the bitmap"
+    );
+
+    let not_in_pack = git_hash::Kind::Sha1.null();
+    assert!(
+        git_pack::data::output::count::reachable_from_bitmap(
+            &file,
+            &order,
+            &[tips[0], not_in_pack],
+            &db,
+            &AtomicBool::new(false)
+        )?
+        .is_none(),
+        "tips that aren't covered by the bitmap require a traversal"
+    );
+    Ok(())
+}
+
+#[test]
+fn multi_pack_index_bitmap_yields_reachable_objects() -> crate::Result {
+    let repo = repo("multi");
+    let index = git_pack::multi_index::File::at(repo.join(".git/objects/pack/multi-pack-index"))?;
+    assert!(index.num_indices() > 1);
+    let order = bitmap::PackOrder::from_multi_index(&index)?;
+    let file = bitmap::File::at(
+        repo.join(format!(
+            ".git/objects/pack/multi-pack-index-{}.bitmap",
+            index.checksum()
+        )),
+        git_hash::Kind::Sha1,
+    )?;
+    assert_eq!(file.index_checksum(), index.checksum());
+
+    let outcome = file.reachable_objects(
+        &order,
+        tips(&repo, &["refs/heads/main", "refs/heads/side", "refs/tags/v1"]),
+        db(&repo),
+        &AtomicBool::new(false),
+    )?;
+    assert!(outcome.extra_objects.is_empty(), "all objects are in the packs");
+    assert_eq!(
+        reachable_ids(&outcome, &order),
+        expected_objects(&repo, "reachable-objects")
+    );
+    Ok(())
+}
+
+#[test]
+fn mismatching_pack_order_is_rejected() -> crate::Result {
+    let single = repo("single");
+    let multi = repo("multi");
+    let index = git_pack::multi_index::File::at(multi.join(".git/objects/pack/multi-pack-index"))?;
+    let order = bitmap::PackOrder::from_multi_index(&index)?;
+    let file = bitmap::File::at(pack_file_with_extension(&single, "bitmap"), git_hash::Kind::Sha1)?;
+    assert!(matches!(
+        file.reachable_objects(&order, None, db(&single), &AtomicBool::new(false)),
+        Err(bitmap::reachable::Error::ChecksumMismatch { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn write_and_read_back() -> crate::Result {
+    let repo = repo("single");
+    let bundle = git_pack::Bundle::at(pack_file_with_extension(&repo, "pack"), git_hash::Kind::Sha1)?;
+    let db = db(&repo);
+    let tips = tips(&repo, &["refs/heads/main", "refs/heads/side"]);
+
+    let dir = tempfile::TempDir::new()?;
+    let path = dir.path().join("pack.bitmap");
+    let outcome = bitmap::File::write_from_bundle(
+        &bundle,
+        tips.iter().cloned(),
+        &db,
+        std::fs::File::create(&path)?,
+        git_features::progress::Discard,
+        &AtomicBool::new(false),
+    )?;
+    assert_eq!(outcome.num_commits, 2);
+
+    let file = bitmap::File::at(&path, git_hash::Kind::Sha1)?;
+    assert_eq!(file.num_commits(), 2);
+    assert_eq!(file.name_hash_at(0), None, "no name-hash cache is written");
+
+    let order = bitmap::PackOrder::from_index(&bundle.index);
+    let git_file = bitmap::File::at(pack_file_with_extension(&repo, "bitmap"), git_hash::Kind::Sha1)?;
+    for kind in [
+        git_object::Kind::Commit,
+        git_object::Kind::Tree,
+        git_object::Kind::Blob,
+        git_object::Kind::Tag,
+    ] {
+        assert_eq!(
+            file.objects_of_kind(kind).to_words(),
+            git_file.objects_of_kind(kind).to_words(),
+            "type bitmaps are the same as the ones written by git"
+        );
+    }
+
+    let mut expected = expected_objects(&repo, "reachable-objects");
+    let tag = git_hash::ObjectId::from_hex(
+        std::fs::read_to_string(repo.join(".git/refs/tags/v1"))?
+            .trim()
+            .as_bytes(),
+    )?;
+    expected.remove(&tag);
+    for tip in &tips {
+        let index = bundle.index.lookup(tip).expect("tip in pack");
+        let words = file.commit_bitmap(index).expect("tips have bitmaps");
+        assert!(words.iter().any(|w| *w != 0));
+    }
+
+    let outcome = file.reachable_objects(&order, tips, &db, &AtomicBool::new(false))?;
+    assert_eq!(reachable_ids(&outcome, &order), expected);
+    Ok(())
+}
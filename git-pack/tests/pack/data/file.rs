@@ -100,7 +100,7 @@ mod decode_entry {
         }
 
         let p = pack_at(SMALL_PACK);
-        let entry = p.entry(offset);
+        let entry = p.entry(offset).expect("valid offset");
         let mut buf = Vec::new();
         p.decode_entry(entry, &mut buf, resolve_with_panic, &mut cache::Never)
             .expect("valid offset provides valid entry");
@@ -151,7 +151,7 @@ mod decompress_entry {
 
     fn decompress_entry_at_offset(offset: u64) -> Vec<u8> {
         let p = pack_at(SMALL_PACK);
-        let entry = p.entry(offset);
+        let entry = p.entry(offset).expect("valid offset");
 
         let size = entry.decompressed_size as usize;
         let mut buf = Vec::with_capacity(size);
@@ -163,3 +163,110 @@ mod decompress_entry {
         buf
     }
 }
+
+mod windowed {
+    use std::sync::atomic::AtomicBool;
+
+    use git_features::progress;
+    use git_odb::pack;
+
+    use crate::{
+        fixture_path,
+        pack::{INDEX_V2, PACK_FOR_INDEX_V2},
+    };
+
+    const TINY_WINDOWS: pack::data::Mapping = pack::data::Mapping::Windowed {
+        window_size: 1024,
+        limit: 4096,
+    };
+
+    fn decode_all(
+        pack: &pack::data::File,
+        index: &pack::index::File,
+    ) -> Result<Vec<(git_hash::ObjectId, git_object::Kind, Vec<u8>)>, pack::data::decode_entry::Error> {
+        let mut out = Vec::new();
+        for entry in index.iter() {
+            let mut buf = Vec::new();
+            let outcome = pack.decode_entry(
+                pack.entry(entry.pack_offset)?,
+                &mut buf,
+                |id, _| {
+                    index
+                        .lookup(id)
+                        .and_then(|idx| pack.entry(index.pack_offset_at_index(idx)).ok())
+                        .map(pack::data::ResolvedBase::InPack)
+                },
+                &mut pack::cache::Never,
+            )?;
+            out.push((entry.oid, outcome.kind, buf));
+        }
+        Ok(out)
+    }
+
+    #[test]
+    fn decodes_the_same_objects_as_whole_mapping() -> crate::Result {
+        let index = pack::index::File::at(fixture_path(INDEX_V2), git_hash::Kind::Sha1)?;
+        let whole = pack::data::File::at(fixture_path(PACK_FOR_INDEX_V2), git_hash::Kind::Sha1)?;
+        let windowed = pack::data::File::at_opts(fixture_path(PACK_FOR_INDEX_V2), git_hash::Kind::Sha1, TINY_WINDOWS)?;
+        assert!(
+            windowed.data_len() > 4096,
+            "the pack must be larger than the limit to exercise eviction"
+        );
+        assert_eq!(windowed.mapping(), TINY_WINDOWS);
+        assert_eq!(windowed.checksum(), whole.checksum());
+        assert_eq!(
+            windowed.verify_checksum(progress::Discard, &AtomicBool::new(false))?,
+            whole.checksum()
+        );
+
+        let expected = decode_all(&whole, &index)?;
+        assert_eq!(expected.len(), index.num_objects() as usize);
+        assert_eq!(decode_all(&windowed, &index)?, expected);
+        for entry in index.iter() {
+            assert_eq!(
+                windowed.entry_crc32(entry.pack_offset, windowed.entry(entry.pack_offset)?.header_size())?,
+                whole.entry_crc32(entry.pack_offset, whole.entry(entry.pack_offset)?.header_size())?,
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_reads_through_shared_windows_decode_the_same_objects() -> crate::Result {
+        let index = std::sync::Arc::new(pack::index::File::at(fixture_path(INDEX_V2), git_hash::Kind::Sha1)?);
+        let whole = pack::data::File::at(fixture_path(PACK_FOR_INDEX_V2), git_hash::Kind::Sha1)?;
+        let windowed = std::sync::Arc::new(pack::data::File::at_opts(
+            fixture_path(PACK_FOR_INDEX_V2),
+            git_hash::Kind::Sha1,
+            TINY_WINDOWS,
+        )?);
+        let expected = decode_all(&whole, &index)?;
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let (windowed, index) = (windowed.clone(), index.clone());
+                std::thread::spawn(move || decode_all(&windowed, &index))
+            })
+            .collect();
+        for thread in threads {
+            assert_eq!(thread.join().expect("no panic")?, expected);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn truncated_packs_yield_io_errors_instead_of_crashing() -> crate::Result {
+        let index = pack::index::File::at(fixture_path(INDEX_V2), git_hash::Kind::Sha1)?;
+        let dir = tempfile::tempdir()?;
+        let truncated_pack = dir.path().join("truncated.pack");
+        let data = std::fs::read(fixture_path(PACK_FOR_INDEX_V2))?;
+        std::fs::write(&truncated_pack, &data[..data.len() / 2])?;
+
+        let pack = pack::data::File::at_opts(&truncated_pack, git_hash::Kind::Sha1, TINY_WINDOWS)?;
+        assert!(
+            matches!(decode_all(&pack, &index), Err(pack::data::decode_entry::Error::Io(_))),
+            "objects past the end of the file can't be read"
+        );
+        Ok(())
+    }
+}
@@ -116,12 +116,14 @@ fn traversals() -> crate::Result {
                     expanded_objects: 0,
                     decoded_objects: 16,
                     total_objects: 16,
+                    bitmap_objects: 0,
                 },
                 output::entry::iter_from_counts::Outcome {
                     decoded_and_recompressed_objects: 0,
                     missing_objects: 0,
                     objects_copied_from_pack: 16,
                     ref_delta_objects: 0,
+                    delta_compressed_objects: 0,
                 },
                 hex_to_id("b920bbb055e1efb9080592a409d3975738b6efb3"),
                 None,
@@ -149,12 +151,14 @@ fn traversals() -> crate::Result {
                     expanded_objects: 102,
                     decoded_objects: 18,
                     total_objects: 103,
+                    bitmap_objects: 0,
                 },
                 output::entry::iter_from_counts::Outcome {
                     decoded_and_recompressed_objects: 0,
                     missing_objects: 0,
                     objects_copied_from_pack: 103,
                     ref_delta_objects: 74,
+                    delta_compressed_objects: 0,
                 },
                 hex_to_id("25114bd8820b393c402cd53ad8ec7f6a84bb0633"),
                 Some(hex_to_id("29ab9797aff1ca826afb699680356695d19c5acb")),
@@ -182,12 +186,14 @@ fn traversals() -> crate::Result {
                     expanded_objects: 102,
                     decoded_objects: 18,
                     total_objects: 103,
+                    bitmap_objects: 0,
                 },
                 output::entry::iter_from_counts::Outcome {
                     decoded_and_recompressed_objects: 74,
                     missing_objects: 0,
                     objects_copied_from_pack: 29,
                     ref_delta_objects: 0,
+                    delta_compressed_objects: 0,
                 },
                 hex_to_id("d83d42128e40957c5174920189a0390b5a70f446"),
                 None,
@@ -203,12 +209,14 @@ fn traversals() -> crate::Result {
                     expanded_objects: 852,
                     decoded_objects: 57,
                     total_objects: 868,
+                    bitmap_objects: 0,
                 },
                 output::entry::iter_from_counts::Outcome {
                     decoded_and_recompressed_objects: 0,
                     missing_objects: 0,
                     objects_copied_from_pack: 868,
                     ref_delta_objects: 0,
+                    delta_compressed_objects: 0,
                 },
                 hex_to_id("542ad1d1c7c762ea4e36907570ff9e4b5b7dde1b"),
                 None,
@@ -224,12 +232,14 @@ fn traversals() -> crate::Result {
                     expanded_objects: 866,
                     decoded_objects: 208,
                     total_objects: 868,
+                    bitmap_objects: 0,
                 },
                 output::entry::iter_from_counts::Outcome {
                     decoded_and_recompressed_objects: 0,
                     missing_objects: 0,
                     objects_copied_from_pack: 868,
                     ref_delta_objects: 0,
+                    delta_compressed_objects: 0,
                 },
                 hex_to_id("542ad1d1c7c762ea4e36907570ff9e4b5b7dde1b"),
                 None,
@@ -321,6 +331,261 @@ fn traversals() -> crate::Result {
     Ok(())
 }
 
+#[test]
+fn delta_compression_of_loose_objects() -> crate::Result {
+    let db = db(DbKind::DeterministicGeneratedContentLoose)?;
+    for (attributes, expected_entries_outcome, expected_pack_hash) in [
+        (
+            None,
+            output::entry::iter_from_counts::Outcome {
+                decoded_and_recompressed_objects: 329,
+                missing_objects: 0,
+                objects_copied_from_pack: 0,
+                ref_delta_objects: 0,
+                delta_compressed_objects: 535,
+            },
+            hex_to_id("8340cff77007ccf6f6c3c45bd4b713204b584019"),
+        ),
+        (
+            Some(b"a/** -delta\n*.txt delta=false\n".as_slice()),
+            output::entry::iter_from_counts::Outcome {
+                decoded_and_recompressed_objects: 712,
+                missing_objects: 0,
+                objects_copied_from_pack: 0,
+                ref_delta_objects: 0,
+                delta_compressed_objects: 152,
+            },
+            hex_to_id("ab3e2d7e1bbe6258fea888e2b5e8b46a99323b78"),
+        ),
+    ] {
+        let attributes = attributes.map(|bytes| git_attributes::MatchGroup {
+            patterns: vec![git_attributes::PatternList::from_bytes(bytes, "attributes", None)],
+        });
+        let commits = commit::Ancestors::new(
+            Some(hex_to_id("dfcb5e39ac6eb30179808bbab721e8a28ce1b52e")),
+            commit::ancestors::State::default(),
+            {
+                let db = db.clone();
+                move |oid, buf| db.find_commit_iter(oid, buf).map(|t| t.0)
+            },
+        )
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+        let (counts, _stats) = output::count::objects(
+            db.clone(),
+            commits.into_iter().map(Ok::<_, Infallible>),
+            progress::Discard,
+            &AtomicBool::new(false),
+            count::objects::Options {
+                input_object_expansion: count::objects::ObjectExpansion::TreeAdditionsComparedToAncestor,
+                thread_limit: Some(1),
+                attributes: attributes.clone(),
+                ..Default::default()
+            },
+        )?;
+        assert!(
+            counts.iter().all(|c| c.entry_pack_location.is_none()),
+            "all objects are loose"
+        );
+        let num_no_delta = counts.iter().filter(|c| c.no_delta).count();
+        if attributes.is_some() {
+            assert_ne!(num_no_delta, 0, "paths below 'a/' are marked");
+        } else {
+            assert_eq!(num_no_delta, 0);
+        }
+        let no_delta_ids: std::collections::HashSet<_> = counts.iter().filter(|c| c.no_delta).map(|c| c.id).collect();
+        let num_counts = counts.len();
+
+        let mut entries_iter = output::entry::iter_from_counts(
+            counts,
+            db.clone(),
+            progress::Discard,
+            output::entry::iter_from_counts::Options {
+                mode: output::entry::iter_from_counts::Mode::PackCopyAndDeltaCompression {
+                    window: output::entry::iter_from_counts::Mode::DEFAULT_WINDOW,
+                    depth: output::entry::iter_from_counts::Mode::DEFAULT_DEPTH,
+                },
+                ..Default::default()
+            },
+        );
+        let entries: Vec<_> = InOrderIter::from(entries_iter.by_ref())
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(entries.len(), num_counts);
+        let stats = entries_iter.finalize()?;
+        assert_eq!(stats, expected_entries_outcome);
+        assert!(
+            entries
+                .iter()
+                .filter(|e| no_delta_ids.contains(&e.id))
+                .all(|e| matches!(e.kind, output::entry::Kind::Base(_))),
+            "objects with the delta attribute unset are never deltified"
+        );
+        for (idx, entry) in entries.iter().enumerate() {
+            if let output::entry::Kind::DeltaRef { object_index } = entry.kind {
+                assert!(object_index < idx, "bases are always written before their deltas");
+            }
+        }
+
+        write_and_verify(db.clone(), entries, expected_pack_hash, None)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn undeltified_packed_objects_serve_as_delta_bases() -> crate::Result {
+    let db = db(DbKind::DeterministicGeneratedContentPartiallyPacked)?;
+    let commits = commit::Ancestors::new(
+        Some(hex_to_id("dfcb5e39ac6eb30179808bbab721e8a28ce1b52e")),
+        commit::ancestors::State::default(),
+        {
+            let db = db.clone();
+            move |oid, buf| db.find_commit_iter(oid, buf).map(|t| t.0)
+        },
+    )
+    .map(Result::unwrap)
+    .collect::<Vec<_>>();
+    let (counts, _stats) = output::count::objects(
+        db.clone(),
+        commits.into_iter().map(Ok::<_, Infallible>),
+        progress::Discard,
+        &AtomicBool::new(false),
+        count::objects::Options {
+            input_object_expansion: count::objects::ObjectExpansion::TreeAdditionsComparedToAncestor,
+            thread_limit: Some(1),
+            ..Default::default()
+        },
+    )?;
+    let num_counts = counts.len();
+    let num_packed = counts.iter().filter(|c| !c.entry_pack_location.is_none()).count();
+    assert!(
+        num_packed != 0 && num_packed != num_counts,
+        "some objects are packed, others are loose"
+    );
+
+    let mut entries_iter = output::entry::iter_from_counts(
+        counts,
+        db.clone(),
+        progress::Discard,
+        output::entry::iter_from_counts::Options {
+            mode: output::entry::iter_from_counts::Mode::PackCopyAndDeltaCompression {
+                window: output::entry::iter_from_counts::Mode::DEFAULT_WINDOW,
+                depth: output::entry::iter_from_counts::Mode::DEFAULT_DEPTH,
+            },
+            ..Default::default()
+        },
+    );
+    let entries: Vec<_> = InOrderIter::from(entries_iter.by_ref())
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+    assert_eq!(entries.len(), num_counts);
+    let stats = entries_iter.finalize()?;
+    assert_eq!(
+        stats.objects_copied_from_pack, num_packed,
+        "packed objects are copied as is, even if they serve as delta base"
+    );
+    assert!(
+        entries[..num_packed]
+            .iter()
+            .all(|e| matches!(e.kind, output::entry::Kind::Base(_))),
+        "the pack has no deltas"
+    );
+    assert!(
+        entries[num_packed..]
+            .iter()
+            .any(|e| matches!(e.kind, output::entry::Kind::DeltaRef { object_index } if object_index < num_packed)),
+        "loose objects are delta-compressed against packed objects"
+    );
+    for (idx, entry) in entries.iter().enumerate() {
+        if let output::entry::Kind::DeltaRef { object_index } = entry.kind {
+            assert!(object_index < idx, "bases are always written before their deltas");
+        }
+    }
+
+    write_and_verify(db, entries, hex_to_id("e930bf8f6e7182b89f48293bcf6cb87b1577b6fa"), None)
+}
+
+#[test]
+fn thin_bases_serve_as_delta_bases_without_being_written() -> crate::Result {
+    let db = db(DbKind::DeterministicGeneratedContentPartiallyPacked)?;
+    let commits = commit::Ancestors::new(
+        Some(hex_to_id("dfcb5e39ac6eb30179808bbab721e8a28ce1b52e")),
+        commit::ancestors::State::default(),
+        {
+            let db = db.clone();
+            move |oid, buf| db.find_commit_iter(oid, buf).map(|t| t.0)
+        },
+    )
+    .map(Result::unwrap)
+    .collect::<Vec<_>>();
+    let (counts, _stats) = output::count::objects(
+        db.clone(),
+        commits.into_iter().map(Ok::<_, Infallible>),
+        progress::Discard,
+        &AtomicBool::new(false),
+        count::objects::Options {
+            input_object_expansion: count::objects::ObjectExpansion::TreeAdditionsComparedToAncestor,
+            thread_limit: Some(1),
+            ..Default::default()
+        },
+    )?;
+    // Pretend the receiver has all packed objects, and only send the loose ones.
+    let (thin_bases, counts): (Vec<_>, Vec<_>) = counts.into_iter().partition(|c| !c.entry_pack_location.is_none());
+    let thin_base_ids: std::collections::HashSet<_> = thin_bases.iter().map(|c| c.id).collect();
+    let num_counts = counts.len();
+
+    for allow_thin_pack in [false, true] {
+        let mut entries_iter = output::entry::iter_from_counts_with_thin_bases(
+            counts.clone(),
+            thin_bases.clone(),
+            db.clone(),
+            progress::Discard,
+            output::entry::iter_from_counts::Options {
+                mode: output::entry::iter_from_counts::Mode::PackCopyAndDeltaCompression {
+                    window: output::entry::iter_from_counts::Mode::DEFAULT_WINDOW,
+                    depth: output::entry::iter_from_counts::Mode::DEFAULT_DEPTH,
+                },
+                allow_thin_pack,
+                ..Default::default()
+            },
+        );
+        let entries: Vec<_> = InOrderIter::from(entries_iter.by_ref())
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(entries.len(), num_counts, "thin bases are never written");
+        let stats = entries_iter.finalize()?;
+        let num_thin_deltas = entries
+            .iter()
+            .filter(|e| matches!(e.kind, output::entry::Kind::DeltaOid { id } if thin_base_ids.contains(&id)))
+            .count();
+        assert_eq!(stats.ref_delta_objects, num_thin_deltas);
+        if allow_thin_pack {
+            assert_ne!(
+                num_thin_deltas, 0,
+                "loose objects are delta-compressed against thin bases"
+            );
+        } else {
+            assert_eq!(
+                num_thin_deltas, 0,
+                "thin bases are ignored unless thin packs are allowed"
+            );
+        }
+        assert!(
+            entries
+                .iter()
+                .all(|e| !matches!(e.kind, output::entry::Kind::DeltaOid { id } if !thin_base_ids.contains(&id))),
+            "deltas against ids only refer to thin bases"
+        );
+    }
+    Ok(())
+}
+
 #[test]
 fn empty_pack_is_allowed() {
     write_and_verify(
@@ -344,8 +609,9 @@ fn write_and_verify(
         .write(true)
         .create_new(true)
         .open(&pack_file_path)?;
+    let index_file_path = tmp_dir.path().join("new.idx");
+    let num_entries = entries.len();
     let (num_written_bytes, pack_hash) = {
-        let num_entries = entries.len();
         let mut pack_writer = output::bytes::FromEntriesIter::new(
             std::iter::once(Ok::<_, entry::iter_from_counts::Error<git_odb::store::find::Error>>(
                 entries,
@@ -363,6 +629,13 @@ fn write_and_verify(
         );
         // verify we can still get the original parts back
         let hash = pack_writer.digest().expect("digest is available when iterator is done");
+        pack_writer
+            .write_index(
+                pack::index::Version::V2,
+                std::fs::File::create(&index_file_path)?,
+                progress::Discard,
+            )?
+            .expect("index can be written once the pack is done");
         let _ = pack_writer.input;
         let _ = pack_writer.into_write();
         (n, hash)
@@ -397,6 +670,20 @@ fn write_and_verify(
         .expect("directory set"),
         object_hash,
     )?;
+    let index = pack::index::File::at(&index_file_path, object_hash)?;
+    assert_eq!(index.pack_checksum(), pack_hash);
+    assert_eq!(
+        index.num_objects() as usize,
+        num_entries,
+        "the index written alongside the pack contains all entries"
+    );
+    if expected_thin_pack_hash.is_none() {
+        assert_eq!(
+            std::fs::read(&index_file_path)?,
+            std::fs::read(bundle.index.path())?,
+            "the index written alongside the pack is the same as the one created by indexing the pack"
+        );
+    }
     if let Some(thin_pack_checksum) = expected_thin_pack_hash {
         let actual_checksum = bundle
             .pack
@@ -6,7 +6,7 @@ use git_pack::data::output;
 fn size_of_entry() {
     assert_eq!(
         std::mem::size_of::<output::Entry>(),
-        80,
+        112,
         "The size of the structure shouldn't change unexpectedly"
     )
 }
@@ -15,7 +15,7 @@ fn size_of_entry() {
 fn size_of_count() {
     assert_eq!(
         std::mem::size_of::<output::Count>(),
-        56,
+        72,
         "The size of the structure shouldn't change unexpectedly"
     )
 }
@@ -23,6 +23,8 @@ fn size_of_count() {
 enum DbKind {
     DeterministicGeneratedContent,
     DeterministicGeneratedContentMultiIndex,
+    DeterministicGeneratedContentLoose,
+    DeterministicGeneratedContentPartiallyPacked,
 }
 
 fn db(kind: DbKind) -> crate::Result<git_odb::HandleArc> {
@@ -30,6 +32,8 @@ fn db(kind: DbKind) -> crate::Result<git_odb::HandleArc> {
     let name = match kind {
         DeterministicGeneratedContent => "make_pack_gen_repo.sh",
         DeterministicGeneratedContentMultiIndex => "make_pack_gen_repo_multi_index.sh",
+        DeterministicGeneratedContentLoose => "make_pack_gen_repo_loose.sh",
+        DeterministicGeneratedContentPartiallyPacked => "make_pack_gen_repo_partially_packed.sh",
     };
     let path: PathBuf = git_testtools::scripted_fixture_repo_read_only(name)?
         .join(".git")
@@ -151,6 +151,7 @@ mod version {
                 let outcome = pack::index::File::write_data_iter_to_stream(
                     desired_kind,
                     move || Ok(resolve),
+                    index::traverse::no_external_bases,
                     pack_iter,
                     None,
                     progress::Discard,
@@ -234,6 +235,83 @@ mod version {
             Ok(())
         }
 
+        #[test]
+        fn write_to_stream_resolves_thin_packs_with_bases_in_the_object_database(
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            let dir = git_testtools::scripted_fixture_repo_read_only("make_thin_pack.sh")?;
+            let pack_path = dir.join("thin.pack");
+            let pack_iter = pack::data::input::BytesToEntriesIter::new_from_header(
+                io::BufReader::new(fs::File::open(&pack_path)?),
+                input::Mode::Verify,
+                input::EntryDataMode::Crc32,
+                git_hash::Kind::Sha1,
+            )?;
+            let pack_version = pack_iter.version();
+            let data = std::sync::Arc::new(pack::data::File::at(&pack_path, git_hash::Kind::Sha1)?);
+            let odb = git_odb::at(dir.join(".git").join("objects"))?;
+
+            let tmp = tempfile::TempDir::new()?;
+            let index_path = tmp.path().join("thin.idx");
+            let outcome = pack::index::File::write_data_iter_to_stream(
+                pack::index::Version::default(),
+                {
+                    let data = data.clone();
+                    move || Ok(move |entry: EntryRange, out: &mut Vec<u8>| data.entry_slice(entry, out).ok())
+                },
+                index::traverse::external_bases_from(odb),
+                pack_iter,
+                None,
+                progress::Discard,
+                fs::File::create(&index_path)?,
+                &AtomicBool::new(false),
+                git_hash::Kind::Sha1,
+                pack_version,
+            )?;
+
+            let index = index::File::at(&index_path, git_hash::Kind::Sha1)?;
+            let expected = fs::read_to_string(dir.join("objects-in-thin-pack"))?
+                .lines()
+                .map(|hex| git_hash::ObjectId::from_hex(hex.as_bytes()))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(outcome.num_objects as usize, expected.len());
+            assert_eq!(
+                index.iter().map(|entry| entry.oid).collect::<Vec<_>>(),
+                expected,
+                "the delta against the base in the object database was resolved to the right object"
+            );
+
+            let err = pack::index::File::write_data_iter_to_stream(
+                pack::index::Version::default(),
+                {
+                    let data = data.clone();
+                    move || Ok(move |entry: EntryRange, out: &mut Vec<u8>| data.entry_slice(entry, out).ok())
+                },
+                index::traverse::no_external_bases,
+                pack::data::input::BytesToEntriesIter::new_from_header(
+                    io::BufReader::new(fs::File::open(&pack_path)?),
+                    input::Mode::Verify,
+                    input::EntryDataMode::Crc32,
+                    git_hash::Kind::Sha1,
+                )?,
+                None,
+                progress::Discard,
+                io::sink(),
+                &AtomicBool::new(false),
+                git_hash::Kind::Sha1,
+                pack_version,
+            )
+            .unwrap_err();
+            assert!(
+                matches!(err, index::write::Error::TreeTraversal(_))
+                    && err
+                        .to_string()
+                        .contains("isn't contained in the pack and could not be obtained"),
+                "without an object database, the base can't be found: {:?}",
+                err
+            );
+            Ok(())
+        }
+
         #[test]
         fn lookup_missing() {
             let file = index::File::at(&fixture_path(INDEX_V2), git_hash::Kind::Sha1).unwrap();
@@ -412,7 +490,7 @@ fn pack_lookup() -> Result<(), Box<dyn std::error::Error>> {
         let sorted_offsets = idx.sorted_offsets();
         assert_eq!(num_objects, sorted_offsets.len());
         for idx_entry in idx.iter() {
-            let pack_entry = pack.entry(idx_entry.pack_offset);
+            let pack_entry = pack.entry(idx_entry.pack_offset)?;
             assert_ne!(pack_entry.data_offset, idx_entry.pack_offset);
             assert!(sorted_offsets.binary_search(&idx_entry.pack_offset).is_ok());
         }
@@ -425,7 +503,7 @@ fn pack_lookup() -> Result<(), Box<dyn std::error::Error>> {
 
             let mut buf = Vec::new();
             buf.resize(entry.decompressed_size as usize, 0);
-            let pack_entry = pack.entry(offset_from_index);
+            let pack_entry = pack.entry(offset_from_index)?;
             assert_eq!(
                 pack_entry.pack_offset(),
                 entry.pack_offset,
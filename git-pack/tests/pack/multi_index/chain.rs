@@ -0,0 +1,202 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+use git_features::progress;
+use git_pack::multi_index::{chain, File};
+use git_testtools::fixture_path;
+
+/// Copy all packs of the fixture into `dir` and return the paths to their indices, sorted.
+fn copy_packs_into(dir: &Path) -> crate::Result<Vec<PathBuf>> {
+    let mut indices = Vec::new();
+    for entry in std::fs::read_dir(fixture_path("objects/pack"))? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) == Some("idx") {
+            let index = dir.join(path.file_name().expect("present"));
+            std::fs::copy(&path, &index)?;
+            std::fs::copy(path.with_extension("pack"), index.with_extension("pack"))?;
+            indices.push(index);
+        }
+    }
+    indices.sort();
+    assert_eq!(indices.len(), 3);
+    Ok(indices)
+}
+
+fn append(pack_dir: &Path, index_paths: &[PathBuf]) -> crate::Result<Option<git_hash::ObjectId>> {
+    Ok(File::append_layer_to_chain(
+        pack_dir,
+        index_paths.to_vec(),
+        progress::Discard,
+        &AtomicBool::new(false),
+        git_hash::Kind::Sha1,
+        git_lock::acquire::Fail::Immediately,
+    )?
+    .map(|outcome| outcome.multi_index_checksum))
+}
+
+fn standalone(index_paths: &[PathBuf]) -> crate::Result<(File, tempfile::TempDir)> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("multi-pack-index");
+    File::write_from_index_paths(
+        index_paths.to_vec(),
+        std::fs::File::create(&path)?,
+        progress::Discard,
+        &AtomicBool::new(false),
+        git_pack::multi_index::write::Options {
+            object_hash: git_hash::Kind::Sha1,
+            base: None,
+        },
+    )?;
+    Ok((File::at(path)?, dir))
+}
+
+#[test]
+fn appended_layers_can_be_read_as_chain_and_lookup_objects_across_all_layers() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let indices = copy_packs_into(dir.path())?;
+
+    let first = append(dir.path(), &indices[1..2])?.expect("new layer");
+    assert_eq!(
+        append(dir.path(), &indices[1..2])?,
+        None,
+        "nothing is written if all indices are already in the chain"
+    );
+    let second = append(dir.path(), &indices)?.expect("new layer");
+    assert_eq!(
+        std::fs::read_to_string(chain::path(dir.path()))?,
+        format!("{}\n{}\n", first, second),
+        "the bottom-most layer comes first"
+    );
+
+    let file = File::at(chain::path(dir.path()))?;
+    assert_eq!(file.path(), chain::path(dir.path()));
+    assert_eq!(file.pack_dir(), dir.path());
+    assert_eq!(file.checksum(), second, "the checksum is the one of the top-most layer");
+    assert_eq!(
+        file.layers().map(|layer| layer.checksum()).collect::<Vec<_>>(),
+        vec![second, first]
+    );
+    let base = file.base().expect("two layers");
+    assert!(base.base().is_none());
+    assert_eq!(base.num_indices(), 1);
+    assert_eq!(file.num_indices(), 3);
+    assert_eq!(
+        file.index_names(),
+        vec![
+            indices[1].file_name().expect("present"),
+            indices[0].file_name().expect("present"),
+            indices[2].file_name().expect("present"),
+        ],
+        "index names of base layers come first"
+    );
+
+    let (expected, _tmp) = standalone(&indices)?;
+    assert_eq!(file.num_objects(), expected.num_objects());
+    assert_eq!(file.iter().count(), expected.num_objects() as usize);
+    for expected_entry in expected.iter() {
+        let entry_index = file.lookup(&expected_entry.oid).expect("all objects are in the chain");
+        assert_eq!(file.oid_at_index(entry_index), expected_entry.oid);
+        let (pack_index, pack_offset) = file.pack_id_and_pack_offset_at_index(entry_index);
+        assert_eq!(pack_offset, expected_entry.pack_offset);
+        assert_eq!(
+            file.index_names()[pack_index as usize],
+            expected.index_names()[expected_entry.pack_index as usize],
+            "pack indices are numbered across the whole chain"
+        );
+
+        let prefix = git_hash::Prefix::new(expected_entry.oid, 12)?;
+        let mut candidates = 0..0;
+        assert_eq!(file.lookup_prefix(prefix, Some(&mut candidates)), Some(Ok(entry_index)));
+        assert_eq!(candidates, entry_index..entry_index + 1);
+    }
+
+    assert_eq!(
+        file.verify_integrity(progress::Discard, &AtomicBool::new(false), Default::default())?
+            .actual_index_checksum,
+        second
+    );
+    Ok(())
+}
+
+#[test]
+fn appending_fails_while_the_chain_is_locked() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let indices = copy_packs_into(dir.path())?;
+    let first = append(dir.path(), &indices[..1])?.expect("new layer");
+
+    let lock = git_lock::Marker::acquire_to_hold_resource(
+        chain::path(dir.path()),
+        git_lock::acquire::Fail::Immediately,
+        None,
+    )?;
+    assert!(matches!(
+        append(dir.path(), &indices[1..])
+            .expect_err("locked")
+            .downcast_ref::<git_pack::multi_index::write::Error>(),
+        Some(git_pack::multi_index::write::Error::LockAcquire(_))
+    ));
+    drop(lock);
+
+    let second = append(dir.path(), &indices[1..])?.expect("new layer");
+    let file = File::at(chain::path(dir.path()))?;
+    assert_eq!(
+        file.layers().map(|layer| layer.checksum()).collect::<Vec<_>>(),
+        [second, first],
+        "the chain is written through the lock once it is available"
+    );
+    Ok(())
+}
+
+#[test]
+fn layers_with_mismatching_checksums_are_rejected() -> crate::Result {
+    let dir = tempfile::tempdir()?;
+    let indices = copy_packs_into(dir.path())?;
+    let layer = append(dir.path(), &indices)?.expect("new layer");
+
+    let chain_dir = chain::path(dir.path()).parent().expect("in directory").to_owned();
+    let other_checksum = git_hash::ObjectId::empty_tree(git_hash::Kind::Sha1);
+    std::fs::rename(
+        chain::layer_path(&chain_dir, &layer),
+        chain::layer_path(&chain_dir, &other_checksum),
+    )?;
+    std::fs::write(chain::path(dir.path()), format!("{}\n", other_checksum))?;
+    assert!(matches!(
+        File::at(chain::path(dir.path())),
+        Err(git_pack::multi_index::init::Error::Corrupt { .. })
+    ));
+
+    std::fs::write(chain::path(dir.path()), "not a checksum\n")?;
+    assert!(matches!(
+        File::at(chain::path(dir.path())),
+        Err(git_pack::multi_index::init::Error::Corrupt { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn chains_written_by_git_refer_to_packs_of_their_own_layer() -> crate::Result {
+    if git_testtools::should_skip_as_git_version_is_smaller_than(2, 47, 0) {
+        return Ok(());
+    }
+    let pack_dir =
+        git_testtools::scripted_fixture_repo_read_only("make_pack_multi_index_chain.sh")?.join(".git/objects/pack");
+    let file = File::at(chain::path(&pack_dir))?;
+    assert_eq!(file.layers().count(), 3, "one layer per pack");
+    assert_eq!(file.num_indices(), 3);
+
+    for entry in file.iter() {
+        let index = git_pack::index::File::at(
+            pack_dir.join(&file.index_names()[entry.pack_index as usize]),
+            git_hash::Kind::Sha1,
+        )?;
+        let index_entry = index
+            .lookup(&entry.oid)
+            .expect("pack indices of upper layers are offset by the amount of indices in their base");
+        assert_eq!(index.pack_offset_at_index(index_entry), entry.pack_offset);
+    }
+
+    file.verify_integrity(progress::Discard, &AtomicBool::new(false), Default::default())?;
+    Ok(())
+}
@@ -26,6 +26,7 @@ fn from_paths() -> crate::Result {
         &AtomicBool::new(false),
         git_pack::multi_index::write::Options {
             object_hash: git_hash::Kind::Sha1,
+            base: None,
         },
     )?;
 
@@ -110,10 +110,28 @@ mod with_io {
 
         /// Turns on all modern features for V1 and all supported features for V2, returning them as a vector of features.
         /// Note that this is the basis for any fetch operation as these features fulfil basic requirements and reasonably up-to-date servers.
+        /// The object format of the server is requested as well unless it's SHA1, which servers assume by default.
         pub fn default_features(
             &self,
             version: git_transport::Protocol,
             server_capabilities: &Capabilities,
+        ) -> Vec<Feature> {
+            let mut features = self.default_features_inner(version, server_capabilities);
+            // Servers assume SHA1 unless told otherwise, and refuse to talk to clients using a different object format.
+            if let Some(object_format) = server_capabilities
+                .capability("object-format")
+                .and_then(|c| c.value())
+                .filter(|format| *format != "sha1")
+            {
+                features.push(("object-format", Some(object_format.to_string().into())));
+            }
+            features
+        }
+
+        fn default_features_inner(
+            &self,
+            version: git_transport::Protocol,
+            server_capabilities: &Capabilities,
         ) -> Vec<Feature> {
             match self {
                 Command::Fetch => match version {
@@ -197,7 +215,7 @@ mod with_io {
                             continue;
                         }
                         match *feature {
-                            "agent" => {}
+                            "agent" | "object-format" => {}
                             _ => panic!("{}: V2 feature/capability {} is not supported", self.as_str(), feature),
                         }
                     }
@@ -45,6 +45,25 @@ mod v1 {
                     "we don't enforce include-tag or no-progress"
                 );
             }
+
+            #[test]
+            fn it_requests_the_object_format_of_the_server_unless_it_is_sha1() {
+                assert_eq!(
+                    Command::Fetch.default_features(
+                        git_transport::Protocol::V1,
+                        &capabilities("side-band-64k object-format=sha256")
+                    ),
+                    &[("side-band-64k", None), ("object-format", Some("sha256".into()))]
+                );
+                assert_eq!(
+                    Command::Fetch.default_features(
+                        git_transport::Protocol::V1,
+                        &capabilities("side-band-64k object-format=sha1")
+                    ),
+                    &[("side-band-64k", None)],
+                    "sha1 is assumed by default"
+                );
+            }
         }
     }
 }
@@ -114,9 +114,19 @@ impl Arguments {
         self.prefixed("deepen-since ", seconds_since_unix_epoch);
     }
     /// Deepen the commit history in a relative instead of absolute fashion.
+    ///
+    /// In protocol V1 this is communicated as capability of the first `want` line, so it must be called before
+    /// the first [`want()`][Self::want()].
     pub fn deepen_relative(&mut self) {
         assert!(self.deepen_relative, "'deepen-relative' feature required");
-        self.args.push("deepen-relative".into());
+        match self.version {
+            git_transport::Protocol::V1 => self
+                .features_for_first_want
+                .as_mut()
+                .expect("deepen-relative must be set before the first want in protocol V1")
+                .push("deepen-relative".into()),
+            git_transport::Protocol::V2 => self.args.push("deepen-relative".into()),
+        }
     }
     /// Do not include commits reachable by the given `ref_path` when deepening the history.
     pub fn deepen_not(&mut self, ref_path: &BStr) {
@@ -152,6 +162,8 @@ impl Arguments {
                 deepen_relative = has("deepen-relative");
                 let baked_features = features
                     .iter()
+                    // The server deepens relatively if the capability is present, so it's only added on demand.
+                    .filter(|(n, _)| *n != "deepen-relative")
                     .map(|(n, v)| match v {
                         Some(v) => format!("{}={}", n, v),
                         None => n.to_string(),
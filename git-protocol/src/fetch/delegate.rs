@@ -98,6 +98,11 @@ pub trait DelegateBlocking {
     /// enough state to be able to walk down from your tips on each call, if they are not in common, and keep setting `have`
     /// for those which are in common if that helps teaching the server about our state and to acknowledge their existence on _their_ end.
     /// This method is called until the other side signals they are ready to send a pack.
+    /// Once `previous_response` contains [`Acknowledgement::Ready`][crate::fetch::response::Acknowledgement::Ready],
+    /// `done` is sent along with the `haves` of this call no matter which action is returned.
+    ///
+    /// On connections which persist across rounds, like `ssh`, `git` or `file`, the server remembers all `haves` of previous
+    /// rounds in protocol V1, so only new ones have to be sent.
     /// Return `Action::Close` if you want to give up before finding a common base. This can happen if the remote repository
     /// has radically changed so there are no bases, or they are very far in the past, causing all objects to be sent.
     fn negotiate(
@@ -31,26 +31,27 @@ fn parse_v2_section<T>(
 }
 
 impl Response {
-    /// Parse a response of the given `version` of the protocol from `reader`.
+    /// Parse a response of the given `version` of the protocol from `reader`, with `options` describing the negotiation
+    /// round it belongs to.
     pub fn from_line_reader(
         version: Protocol,
         reader: &mut impl client::ExtendedBufRead,
+        response::Options {
+            client_expects_pack,
+            no_done,
+        }: response::Options,
     ) -> Result<Response, response::Error> {
         match version {
             Protocol::V1 => {
                 let mut line = String::new();
                 let mut acks = Vec::<Acknowledgement>::new();
                 let mut shallows = Vec::<ShallowUpdate>::new();
+                let mut saw_ready = false;
                 let has_pack = 'lines: loop {
                     line.clear();
                     let peeked_line = match reader.peek_data_line() {
                         Some(Ok(Ok(line))) => String::from_utf8_lossy(line),
-                        // This special case (hang/block forever) deals with a single NAK being a legitimate EOF sometimes
-                        // Note that this might block forever in stateful connections as there it's not really clear
-                        // if something will be following or not by just looking at the response. Instead you have to know
-                        // the arguments sent to the server and count response lines based on intricate knowledge on how the
-                        // server works.
-                        // For now this is acceptable, as V2 can be used as a workaround, which also is the default.
+                        // Servers may close the connection after a negotiation round, which is the end of the response.
                         Some(Err(err)) if err.kind() == io::ErrorKind::UnexpectedEof => break 'lines false,
                         Some(Err(err)) => return Err(err.into()),
                         Some(Ok(Err(err))) => return Err(err.into()),
@@ -72,10 +73,17 @@ impl Response {
                         }
                     };
 
+                    let is_nak = matches!(Acknowledgement::from_line(&peeked_line), Ok(Acknowledgement::Nak));
                     if Response::parse_v1_ack_or_shallow_or_assume_pack(&mut acks, &mut shallows, &peeked_line) {
                         break 'lines true;
                     }
                     assert_ne!(reader.read_line(&mut line)?, 0, "consuming a peeked line works");
+                    saw_ready |= acks.last() == Some(&Acknowledgement::Ready);
+                    // A NAK ends each negotiation round, and only if we are done or the server is ready a pack will follow.
+                    // Reading on would block forever on stateful connections as the server waits for our next round.
+                    if is_nak && !client_expects_pack && !(no_done && saw_ready) {
+                        break 'lines false;
+                    }
                 };
                 Ok(Response {
                     acks,
@@ -37,6 +37,18 @@ impl From<std::io::Error> for Error {
     }
 }
 
+/// Options for use in [`Response::from_line_reader()`], describing the negotiation round the response belongs to.
+///
+/// They are needed in protocol V1 to know where the response ends, as servers don't close stateful connections after a
+/// negotiation round but wait for the next one.
+#[derive(Default, PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub struct Options {
+    /// If `true`, `done` was sent in the round this is the response to, so a pack is expected to follow.
+    pub client_expects_pack: bool,
+    /// If `true`, the `no-done` capability was negotiated, which makes the server send a pack as soon as it is ready.
+    pub no_done: bool,
+}
+
 /// An 'ACK' line received from the server.
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
 #[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
@@ -204,6 +204,34 @@ mod v1 {
         );
     }
 
+    #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+    async fn deepen_relative_is_a_capability_sent_only_on_demand() {
+        for relative in [false, true] {
+            let mut out = Vec::new();
+            let mut t = transport(&mut out, true);
+            let mut arguments = arguments_v1(["feature-a", "shallow", "deepen-relative"].iter().copied());
+
+            arguments.deepen(1);
+            if relative {
+                arguments.deepen_relative();
+            }
+            arguments.want(id("7b333369de1221f9bfbbe03a3a13e9a09bc1c907"));
+            arguments.send(&mut t, true).await.expect("sending to buffer to work");
+            let expected: &[u8] = if relative {
+                b"0054want 7b333369de1221f9bfbbe03a3a13e9a09bc1c907 feature-a shallow deepen-relative
+000ddeepen 1
+00000009done
+"
+            } else {
+                b"0044want 7b333369de1221f9bfbbe03a3a13e9a09bc1c907 feature-a shallow
+000ddeepen 1
+00000009done
+"
+            };
+            assert_eq!(out.as_bstr(), expected.as_bstr());
+        }
+    }
+
     #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
     async fn haves_and_wants_for_fetch_stateful() {
         let mut out = Vec::new();
@@ -6,7 +6,7 @@ use maybe_async::maybe_async;
 
 use crate::{
     credentials,
-    fetch::{Action, Arguments, Delegate, Error, Response},
+    fetch::{response, response::Acknowledgement, Action, Arguments, Delegate, Error, Response},
     indicate_end_of_interaction, Command,
 };
 
@@ -117,6 +117,7 @@ where
 
     Response::check_required_features(protocol_version, &fetch_features)?;
     let sideband_all = fetch_features.iter().any(|(n, _)| *n == "sideband-all");
+    let no_done = fetch_features.iter().any(|(n, _)| *n == "no-done");
     fetch_features.push(("agent", Some(Cow::Owned(agent))));
     let mut arguments = Arguments::new(protocol_version, fetch_features);
     let mut previous_response = None::<Response>;
@@ -126,11 +127,24 @@ where
         progress.set_name(format!("negotiate (round {})", round));
         round += 1;
         let action = delegate.negotiate(&refs, &mut arguments, previous_response.as_ref())?;
-        let mut reader = arguments.send(&mut transport, action == Action::Cancel).await?;
+        // Once the server is ready to send a pack, it waits for us to be done.
+        let server_is_ready = previous_response
+            .as_ref()
+            .map_or(false, |r| r.acknowledgements().contains(&Acknowledgement::Ready));
+        let is_done = action == Action::Cancel || server_is_ready;
+        let mut reader = arguments.send(&mut transport, is_done).await?;
         if sideband_all {
             setup_remote_progress(&mut progress, &mut reader);
         }
-        let response = Response::from_line_reader(protocol_version, &mut reader).await?;
+        let response = Response::from_line_reader(
+            protocol_version,
+            &mut reader,
+            response::Options {
+                client_expects_pack: is_done,
+                no_done,
+            },
+        )
+        .await?;
         previous_response = if response.has_pack() {
             progress.step();
             progress.set_name("receiving pack");
@@ -140,10 +154,10 @@ where
             delegate.receive_pack(reader, progress, &refs, &response).await?;
             break 'negotiation;
         } else {
-            match action {
-                Action::Cancel => break 'negotiation,
-                Action::Continue => Some(response),
+            if is_done {
+                break 'negotiation;
             }
+            Some(response)
         }
     }
     if matches!(protocol_version, git_transport::Protocol::V2)
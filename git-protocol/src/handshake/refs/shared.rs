@@ -121,6 +121,10 @@ pub(in crate::handshake::refs) fn parse_v1(
     if path.is_empty() {
         return Err(Error::MalformedV1RefLine(trimmed.to_owned()));
     }
+    if path == "capabilities^{}" {
+        // Repositories without any ref advertise a placeholder to be able to transmit their capabilities.
+        return Ok(());
+    }
     match path.strip_suffix("^{}") {
         Some(stripped) => {
             let (previous_path, tag) =
@@ -0,0 +1,125 @@
+#[cfg(feature = "async-client")]
+use futures_lite::io::{AsyncBufReadExt, AsyncWriteExt};
+use git_features::progress::Progress;
+use git_transport::{
+    client,
+    client::{Capabilities, ExtendedBufRead, Transport},
+    packetline::{PacketLineRef, StreamingPeekableIter},
+};
+use maybe_async::maybe_async;
+#[cfg(feature = "blocking-client")]
+use std::io::{BufRead, Write};
+
+use super::{capabilities_to_send, Error, Options, Outcome, Update};
+
+/// Send `updates` to the server on the other side of `transport` with `options` along with the chunks of a `pack`,
+/// which requires a prior [handshake][crate::push::handshake()] that yielded the server `capabilities`, and return the report
+/// of the server with the status of each reference. `progress` is used to emit the progress messages sent by the server, and `agent` is
+/// the name of the git client to present as `agent`, like `"my-app (v2.0)"`.
+///
+/// `pack` yields the bytes of a pack with all objects the server needs to apply `updates` in chunks, which are sent once all update
+/// commands were sent. It must be present unless all `updates` are deletions. Each chunk is written as soon as it's produced, so the
+/// pack can be generated while it's sent, and with async transports the writes don't block the executor.
+///
+/// Note that if there are no `updates`, the interaction with the server ends without sending anything and the returned
+/// report is empty.
+#[allow(clippy::result_large_err)]
+#[maybe_async]
+pub async fn push<T, P>(
+    mut transport: T,
+    capabilities: &Capabilities,
+    updates: &[Update],
+    pack: Option<impl Iterator<Item = std::io::Result<Vec<u8>>>>,
+    options: &Options,
+    progress: &mut P,
+    agent: impl Into<String>,
+) -> Result<Outcome, Error>
+where
+    T: Transport,
+    P: Progress,
+    P::SubProgress: 'static,
+{
+    if updates.is_empty() {
+        crate::indicate_end_of_interaction(transport).await?;
+        return Ok(Outcome::default());
+    }
+    let features = match capabilities_to_send(capabilities, updates, options, crate::agent(agent)) {
+        Ok(features) => features,
+        Err(err) => {
+            crate::indicate_end_of_interaction(transport).await?;
+            return Err(err);
+        }
+    };
+    if pack.is_none() && !updates.iter().all(Update::is_deletion) {
+        crate::indicate_end_of_interaction(transport).await?;
+        return Err(Error::MissingPack);
+    }
+    let with_report = features.iter().any(|f| f.starts_with("report-status"));
+    let with_sideband = features.iter().any(|f| f.starts_with("side-band"));
+
+    progress.set_name("send update commands");
+    let mut writer = transport.request(
+        client::WriteMode::OneLfTerminatedLinePerWriteCall,
+        client::MessageKind::Flush,
+    )?;
+    for (idx, update) in updates.iter().enumerate() {
+        let mut line = format!("{} {} ", update.old_id, update.new_id).into_bytes();
+        line.extend_from_slice(&update.name);
+        if idx == 0 {
+            line.push(0);
+            line.extend_from_slice(features.join(" ").as_bytes());
+        }
+        writer.write_all(&line).await?;
+    }
+    writer.write_message(client::MessageKind::Flush).await?;
+    if !options.push_options.is_empty() {
+        for push_option in &options.push_options {
+            writer.write_all(push_option).await?;
+        }
+        writer.write_message(client::MessageKind::Flush).await?;
+    }
+
+    let (mut writer, mut reader) = writer.into_parts();
+    if let Some(pack) = pack {
+        progress.set_name("send pack");
+        for chunk in pack {
+            writer.write_all(&chunk?).await?;
+        }
+    }
+    writer.flush().await?;
+    drop(writer);
+
+    let mut outcome = Outcome::default();
+    if !with_report {
+        return Ok(outcome);
+    }
+    progress.set_name("read report");
+    if with_sideband {
+        reader.set_progress_handler(Some(Box::new({
+            let mut remote_progress = progress.add_child("remote");
+            move |is_err: bool, data: &[u8]| {
+                crate::RemoteProgress::translate_to_progress(is_err, data, &mut remote_progress)
+            }
+        }) as client::HandleProgress));
+        let mut lines = StreamingPeekableIter::new(&mut reader, &[PacketLineRef::Flush]);
+        while let Some(line) = lines.read_line().await {
+            let line = line??;
+            let line = match line.as_bstr() {
+                Some(line) => line,
+                None => continue,
+            };
+            outcome.parse_report_line(line.strip_suffix(b"\n").unwrap_or(line).into())?;
+        }
+    } else {
+        loop {
+            let line = reader.fill_buf().await?;
+            if line.is_empty() {
+                break;
+            }
+            outcome.parse_report_line(line.strip_suffix(b"\n").unwrap_or(line).into())?;
+            let len = line.len();
+            reader.consume(len);
+        }
+    }
+    Ok(outcome)
+}
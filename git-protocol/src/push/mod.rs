@@ -0,0 +1,194 @@
+use bstr::{BStr, BString, ByteSlice};
+use git_hash::ObjectId;
+
+/// An instruction to the server to change the reference at `name` from `old_id` to `new_id`, as sent with [`push()`][crate::push()].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Update {
+    /// The object id the reference is expected to point to on the server, or the null id if it is expected to not exist yet.
+    pub old_id: ObjectId,
+    /// The object id the reference should point to after the update, or the null id if it should be deleted.
+    pub new_id: ObjectId,
+    /// The full name of the reference on the server, like `refs/heads/main`.
+    pub name: BString,
+}
+
+impl Update {
+    /// Return `true` if this update deletes the reference on the server.
+    pub fn is_deletion(&self) -> bool {
+        self.new_id.is_null()
+    }
+}
+
+/// Options for use in [`push()`][crate::push()].
+#[derive(Default, PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Options {
+    /// If `true`, ask the server to apply either all updates or none of them, failing if it doesn't support the `atomic` capability.
+    pub atomic: bool,
+    /// Strings to pass to the hooks of the server, similar to `git push --push-option`, which requires the server to support
+    /// the `push-options` capability if not empty.
+    pub push_options: Vec<BString>,
+    /// If `true`, ask the server not to send progress information if it supports the `quiet` capability.
+    pub quiet: bool,
+}
+
+/// The status of a single reference as reported by the server after a [`push()`][crate::push()].
+#[derive(Default, PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct RefStatus {
+    /// The name of the reference as sent in the respective [`Update`].
+    pub name: BString,
+    /// `None` if the update was applied, or the reason the server gave for rejecting it.
+    pub rejection: Option<BString>,
+    /// The name of the reference that was actually updated if it differs from `name`, as reported with `report-status-v2`.
+    pub refname: Option<BString>,
+    /// The object id the reference pointed to before the update if it differs from the one that was sent, as reported with `report-status-v2`.
+    pub old_id: Option<ObjectId>,
+    /// The object id the reference points to after the update if it differs from the one that was sent, as reported with `report-status-v2`.
+    pub new_id: Option<ObjectId>,
+    /// If `true`, the reference was updated to an object that doesn't have its previous object as ancestor, as reported with `report-status-v2`.
+    pub forced_update: bool,
+}
+
+impl RefStatus {
+    /// Return `true` if the server applied the update to the reference.
+    pub fn is_ok(&self) -> bool {
+        self.rejection.is_none()
+    }
+}
+
+/// The report of the server as returned by [`push()`][crate::push()].
+#[derive(Default, PartialEq, Eq, Debug, Hash, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The reason the server gave for failing to unpack the pack we sent, or `None` if it succeeded.
+    pub unpack_error: Option<BString>,
+    /// The status of each reference in the order reported by the server.
+    ///
+    /// It's empty if the server doesn't support the `report-status` capability or if there was nothing to push.
+    pub refs: Vec<RefStatus>,
+}
+
+impl Outcome {
+    /// Return `true` if the pack was unpacked by the server and all references were updated.
+    pub fn is_success(&self) -> bool {
+        self.unpack_error.is_none() && self.refs.iter().all(RefStatus::is_ok)
+    }
+
+    /// Parse a single `line` of a `report-status` or `report-status-v2` report, without trailing newline, and add it to our state.
+    fn parse_report_line(&mut self, line: &BStr) -> Result<(), Error> {
+        let malformed = || Error::MalformedReportLine { line: line.to_owned() };
+        let (token, rest) = line.split_once_str(" ").unwrap_or((line, &[]));
+        match token {
+            b"unpack" => {
+                if rest != b"ok" {
+                    self.unpack_error = Some(rest.into());
+                }
+            }
+            b"ok" if !rest.is_empty() => self.refs.push(RefStatus {
+                name: rest.into(),
+                ..Default::default()
+            }),
+            b"ng" => {
+                let (name, reason) = rest.split_once_str(" ").ok_or_else(malformed)?;
+                self.refs.push(RefStatus {
+                    name: name.into(),
+                    rejection: Some(reason.into()),
+                    ..Default::default()
+                })
+            }
+            b"option" => {
+                let status = self.refs.last_mut().ok_or_else(malformed)?;
+                let (key, value) = rest.split_once_str(" ").unwrap_or((rest, &[]));
+                let parse_id = || ObjectId::from_hex(value).map_err(|_| malformed());
+                match key {
+                    b"refname" => status.refname = Some(value.into()),
+                    b"old-oid" => status.old_id = Some(parse_id()?),
+                    b"new-oid" => status.new_id = Some(parse_id()?),
+                    b"forced-update" => status.forced_update = true,
+                    _ => {}
+                }
+            }
+            _ => return Err(malformed()),
+        }
+        Ok(())
+    }
+}
+
+/// The name of the capabilities to send along with `updates` according to `options`, given the server `capabilities`.
+fn capabilities_to_send(
+    capabilities: &git_transport::client::Capabilities,
+    updates: &[Update],
+    options: &Options,
+    agent: String,
+) -> Result<Vec<String>, Error> {
+    if updates.iter().any(Update::is_deletion) && !capabilities.contains("delete-refs") {
+        return Err(Error::MissingServerCapability { feature: "delete-refs" });
+    }
+    let mut out = Vec::new();
+    for (name, needed) in [
+        ("atomic", options.atomic),
+        ("push-options", !options.push_options.is_empty()),
+    ] {
+        if needed {
+            if !capabilities.contains(name) {
+                return Err(Error::MissingServerCapability { feature: name });
+            }
+            out.push(name.to_owned());
+        }
+    }
+    if let Some(name) = ["report-status-v2", "report-status"]
+        .into_iter()
+        .find(|name| capabilities.contains(name))
+    {
+        out.push(name.into());
+    }
+    if let Some(name) = ["side-band-64k", "side-band"]
+        .into_iter()
+        .find(|name| capabilities.contains(name))
+    {
+        out.push(name.into());
+    }
+    if options.quiet && capabilities.contains("quiet") {
+        out.push("quiet".into());
+    }
+    if let Some(object_format) = capabilities.capability("object-format").and_then(|c| c.value()) {
+        out.push(format!("object-format={}", object_format));
+    }
+    if capabilities.contains("agent") {
+        out.push(format!("agent={}", agent));
+    }
+    Ok(out)
+}
+
+mod error {
+    use bstr::BString;
+    use git_transport::client;
+
+    /// The error returned by [`push()`][crate::push()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not send the update commands or the pack, or could not read the report of the server")]
+        Io(#[from] std::io::Error),
+        #[error(transparent)]
+        Transport(#[from] client::Error),
+        #[error("The server doesn't support the '{feature}' capability which is needed for this push")]
+        MissingServerCapability { feature: &'static str },
+        #[error("A pack must be sent unless all updates are deletions")]
+        MissingPack,
+        #[error("The report of the server could not be decoded")]
+        DecodeReport(#[from] git_transport::packetline::decode::Error),
+        #[error("The report line {line:?} could not be parsed")]
+        MalformedReportLine { line: BString },
+    }
+}
+pub use error::Error;
+
+mod handshake;
+pub use handshake::receive_pack as handshake;
+
+pub(crate) mod function;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,76 @@
+use git_testtools::hex_to_id as oid;
+
+use crate::push::{Error, Outcome, RefStatus};
+
+fn parse(lines: &[&str]) -> Result<Outcome, Error> {
+    let mut out = Outcome::default();
+    for line in lines {
+        out.parse_report_line((*line).into())?;
+    }
+    Ok(out)
+}
+
+#[test]
+fn report_status_v2_options_apply_to_the_preceding_status() -> Result<(), Error> {
+    let out = parse(&[
+        "unpack ok",
+        "ok refs/for/main/topic",
+        "option refname refs/changes/23/123/1",
+        "option old-oid 0000000000000000000000000000000000000000",
+        "option new-oid 8481a5730022eb19bcb0d2a17bebb31366805036",
+        "ok refs/heads/main",
+        "option forced-update",
+        "ng refs/heads/feature non-fast-forward",
+    ])?;
+    assert!(!out.is_success());
+    assert_eq!(
+        out,
+        Outcome {
+            unpack_error: None,
+            refs: vec![
+                RefStatus {
+                    name: "refs/for/main/topic".into(),
+                    refname: Some("refs/changes/23/123/1".into()),
+                    old_id: Some(git_hash::Kind::Sha1.null()),
+                    new_id: Some(oid("8481a5730022eb19bcb0d2a17bebb31366805036")),
+                    ..Default::default()
+                },
+                RefStatus {
+                    name: "refs/heads/main".into(),
+                    forced_update: true,
+                    ..Default::default()
+                },
+                RefStatus {
+                    name: "refs/heads/feature".into(),
+                    rejection: Some("non-fast-forward".into()),
+                    ..Default::default()
+                }
+            ]
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn unpack_errors_are_recorded() -> Result<(), Error> {
+    let out = parse(&["unpack index-pack abnormal exit", "ng refs/heads/main unpacker error"])?;
+    assert_eq!(out.unpack_error, Some("index-pack abnormal exit".into()));
+    assert!(!out.is_success());
+    Ok(())
+}
+
+#[test]
+fn malformed_lines_are_an_error() {
+    for lines in [
+        &["unexpected"][..],
+        &["option refname refs/heads/main"],
+        &["ng refs/heads/main"],
+        &["ok refs/heads/main", "option old-oid invalid"],
+    ] {
+        assert!(
+            matches!(parse(lines), Err(Error::MalformedReportLine { .. })),
+            "{:?}",
+            lines
+        );
+    }
+}
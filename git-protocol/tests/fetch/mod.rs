@@ -15,7 +15,7 @@ type Cursor = std::io::Cursor<Vec<u8>>;
 type Cursor = futures_lite::io::Cursor<Vec<u8>>;
 
 #[allow(clippy::result_large_err)]
-fn helper_unused(_action: git_credentials::helper::Action) -> git_credentials::protocol::Result {
+pub fn helper_unused(_action: git_credentials::helper::Action) -> git_credentials::protocol::Result {
     panic!("Call to credentials helper is unexpected")
 }
 
@@ -104,6 +104,46 @@ impl fetch::DelegateBlocking for CloneRefInWantDelegate {
     }
 }
 
+/// A delegate which sends `have` lines in multiple rounds and records the acknowledgements of the server.
+#[derive(Default)]
+pub struct NegotiatingDelegate {
+    /// The `have` lines to send, one batch per round.
+    haves_per_round: Vec<Vec<git_hash::ObjectId>>,
+    /// The acknowledgements of each server response, the last one being the response with the pack.
+    acks_per_round: Vec<Vec<fetch::response::Acknowledgement>>,
+    /// Number of bytes received of the final packfile.
+    pack_bytes: usize,
+}
+
+impl fetch::DelegateBlocking for NegotiatingDelegate {
+    fn negotiate(
+        &mut self,
+        refs: &[handshake::Ref],
+        arguments: &mut Arguments,
+        previous_response: Option<&Response>,
+    ) -> io::Result<Action> {
+        match previous_response {
+            Some(response) => self.acks_per_round.push(response.acknowledgements().to_owned()),
+            None => {
+                for r in refs {
+                    if let Some(id) = r.unpack().1 {
+                        arguments.want(id);
+                    }
+                }
+            }
+        }
+        Ok(match self.haves_per_round.get(self.acks_per_round.len()) {
+            Some(haves) => {
+                for id in haves {
+                    arguments.have(id);
+                }
+                Action::Continue
+            }
+            None => Action::Cancel,
+        })
+    }
+}
+
 #[derive(Default)]
 pub struct LsRemoteDelegate {
     refs: Vec<handshake::Ref>,
@@ -152,7 +192,21 @@ mod blocking_io {
     use git_features::progress::Progress;
     use git_protocol::{fetch, fetch::Response, handshake, handshake::Ref};
 
-    use crate::fetch::{CloneDelegate, CloneRefInWantDelegate, LsRemoteDelegate};
+    use crate::fetch::{CloneDelegate, CloneRefInWantDelegate, LsRemoteDelegate, NegotiatingDelegate};
+
+    impl fetch::Delegate for NegotiatingDelegate {
+        fn receive_pack(
+            &mut self,
+            mut input: impl io::BufRead,
+            _progress: impl Progress,
+            _refs: &[Ref],
+            response: &Response,
+        ) -> io::Result<()> {
+            self.acks_per_round.push(response.acknowledgements().to_owned());
+            self.pack_bytes = io::copy(&mut input, &mut io::sink())? as usize;
+            Ok(())
+        }
+    }
 
     impl fetch::Delegate for CloneDelegate {
         fn receive_pack(
@@ -208,7 +262,22 @@ mod async_io {
     use git_features::progress::Progress;
     use git_protocol::{fetch, fetch::Response, handshake, handshake::Ref};
 
-    use crate::fetch::{CloneDelegate, CloneRefInWantDelegate, LsRemoteDelegate};
+    use crate::fetch::{CloneDelegate, CloneRefInWantDelegate, LsRemoteDelegate, NegotiatingDelegate};
+
+    #[async_trait(?Send)]
+    impl fetch::Delegate for NegotiatingDelegate {
+        async fn receive_pack(
+            &mut self,
+            mut input: impl AsyncBufRead + Unpin + 'async_trait,
+            _progress: impl Progress,
+            _refs: &[Ref],
+            response: &Response,
+        ) -> io::Result<()> {
+            self.acks_per_round.push(response.acknowledgements().to_owned());
+            self.pack_bytes = futures_lite::io::copy(&mut input, &mut futures_lite::io::sink()).await? as usize;
+            Ok(())
+        }
+    }
 
     #[async_trait(?Send)]
     impl fetch::Delegate for CloneDelegate {
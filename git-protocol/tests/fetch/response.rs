@@ -29,7 +29,15 @@ mod v1 {
         async fn clone() -> crate::Result {
             let mut provider = mock_reader("v1/clone-only.response");
             let mut reader = provider.as_read_without_sidebands();
-            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader).await?;
+            let r = fetch::Response::from_line_reader(
+                Protocol::V1,
+                &mut reader,
+                fetch::response::Options {
+                    client_expects_pack: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
             assert_eq!(r.acknowledgements(), &[Acknowledgement::Nak]);
             assert!(r.has_pack());
             let mut buf = Vec::new();
@@ -42,7 +50,15 @@ mod v1 {
         async fn shallow_clone() -> crate::Result {
             let mut provider = mock_reader("v1/clone-deepen-1.response");
             let mut reader = provider.as_read_without_sidebands();
-            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader).await?;
+            let r = fetch::Response::from_line_reader(
+                Protocol::V1,
+                &mut reader,
+                fetch::response::Options {
+                    client_expects_pack: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
             assert_eq!(
                 r.shallow_updates(),
                 &[ShallowUpdate::Shallow(id("808e50d724f604f69ab93c6da2919c014667bedb"))]
@@ -59,7 +75,15 @@ mod v1 {
         async fn empty_shallow_clone_due_to_depth_being_too_high() -> crate::Result {
             let mut provider = mock_reader("v1/clone-deepen-5.response");
             let mut reader = provider.as_read_without_sidebands();
-            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader).await?;
+            let r = fetch::Response::from_line_reader(
+                Protocol::V1,
+                &mut reader,
+                fetch::response::Options {
+                    client_expects_pack: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
             assert!(r.shallow_updates().is_empty());
             assert_eq!(r.acknowledgements(), &[Acknowledgement::Nak]);
             assert!(r.has_pack());
@@ -72,7 +96,12 @@ mod v1 {
         #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
         async fn fetch_acks_without_pack() -> crate::Result {
             let mut provider = mock_reader("v1/fetch-no-pack.response");
-            let r = fetch::Response::from_line_reader(Protocol::V1, &mut provider.as_read_without_sidebands()).await?;
+            let r = fetch::Response::from_line_reader(
+                Protocol::V1,
+                &mut provider.as_read_without_sidebands(),
+                Default::default(),
+            )
+            .await?;
             assert_eq!(
                 r.acknowledgements(),
                 &[
@@ -84,11 +113,75 @@ mod v1 {
             Ok(())
         }
 
+        #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+        async fn fetch_acks_of_each_round_on_stateful_connection() -> crate::Result {
+            let mut provider = mock_reader("v1/fetch-multi-round.response");
+            while let Some(line) = provider.read_line().await {
+                line??;
+            }
+            provider.reset();
+
+            let common = |hex: &str| Acknowledgement::Common(id(hex));
+            let r = fetch::Response::from_line_reader(
+                Protocol::V1,
+                &mut provider.as_read_without_sidebands(),
+                Default::default(),
+            )
+            .await?;
+            assert_eq!(r.acknowledgements(), &[Acknowledgement::Nak]);
+            assert!(!r.has_pack(), "the server waits for the next round after its NAK");
+
+            let r = fetch::Response::from_line_reader(
+                Protocol::V1,
+                &mut provider.as_read_without_sidebands(),
+                Default::default(),
+            )
+            .await?;
+            assert_eq!(
+                r.acknowledgements(),
+                &[
+                    common("2b97ca25eacb49399bbac338f4d30abeed977745"),
+                    common("d885e50946b92930715c4372a46e068be5ecc6d5"),
+                    Acknowledgement::Ready,
+                    Acknowledgement::Nak,
+                ]
+            );
+            assert!(!r.has_pack(), "without no-done the server waits for us to be done");
+
+            let mut reader = provider.as_read_without_sidebands();
+            let r = fetch::Response::from_line_reader(
+                Protocol::V1,
+                &mut reader,
+                fetch::response::Options {
+                    client_expects_pack: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
+            assert_eq!(
+                r.acknowledgements(),
+                &[common("d885e50946b92930715c4372a46e068be5ecc6d5")]
+            );
+            assert!(r.has_pack());
+            let mut buf = Vec::new();
+            let bytes_read = reader.read_to_end(&mut buf).await?;
+            assert_eq!(bytes_read, 1197, "should be able to read the whole pack");
+            Ok(())
+        }
+
         #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
         async fn fetch_acks_and_pack() -> crate::Result {
             let mut provider = mock_reader("v1/fetch.response");
             let mut reader = provider.as_read_without_sidebands();
-            let r = fetch::Response::from_line_reader(Protocol::V1, &mut reader).await?;
+            let r = fetch::Response::from_line_reader(
+                Protocol::V1,
+                &mut reader,
+                fetch::response::Options {
+                    no_done: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
             assert_eq!(
                 r.acknowledgements(),
                 &[
@@ -131,7 +224,15 @@ mod v2 {
                 );
                 let mut provider = mock_reader(&fixture);
                 let mut reader = provider.as_read_without_sidebands();
-                let r = fetch::Response::from_line_reader(Protocol::V2, &mut reader).await?;
+                let r = fetch::Response::from_line_reader(
+                    Protocol::V2,
+                    &mut reader,
+                    fetch::response::Options {
+                        client_expects_pack: true,
+                        ..Default::default()
+                    },
+                )
+                .await?;
                 assert!(r.acknowledgements().is_empty(), "it should go straight to the packfile");
                 assert!(r.has_pack());
                 reader.set_progress_handler(Some(Box::new(|_is_err, _text| ())));
@@ -146,7 +247,15 @@ mod v2 {
         async fn shallow_clone() -> crate::Result {
             let mut provider = mock_reader("v2/clone-deepen-1.response");
             let mut reader = provider.as_read_without_sidebands();
-            let r = fetch::Response::from_line_reader(Protocol::V2, &mut reader).await?;
+            let r = fetch::Response::from_line_reader(
+                Protocol::V2,
+                &mut reader,
+                fetch::response::Options {
+                    client_expects_pack: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
             assert!(r.acknowledgements().is_empty(), "it should go straight to the packfile");
             assert_eq!(
                 r.shallow_updates(),
@@ -163,7 +272,15 @@ mod v2 {
         async fn empty_shallow_clone() -> crate::Result {
             let mut provider = mock_reader("v2/clone-deepen-5.response");
             let mut reader = provider.as_read_without_sidebands();
-            let r = fetch::Response::from_line_reader(Protocol::V2, &mut reader).await?;
+            let r = fetch::Response::from_line_reader(
+                Protocol::V2,
+                &mut reader,
+                fetch::response::Options {
+                    client_expects_pack: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
             assert!(r.acknowledgements().is_empty(), "it should go straight to the packfile");
             assert!(r.shallow_updates().is_empty(), "it should go straight to the packfile");
             assert!(r.has_pack());
@@ -177,7 +294,15 @@ mod v2 {
         async fn clone_with_sidebands() -> crate::Result {
             let mut provider = mock_reader("v2/clone-only-2.response");
             let mut reader = provider.as_read_without_sidebands();
-            let r = fetch::Response::from_line_reader(Protocol::V2, &mut reader).await?;
+            let r = fetch::Response::from_line_reader(
+                Protocol::V2,
+                &mut reader,
+                fetch::response::Options {
+                    client_expects_pack: true,
+                    ..Default::default()
+                },
+            )
+            .await?;
             assert!(r.acknowledgements().is_empty(), "it should go straight to the packfile");
             assert!(r.has_pack());
 
@@ -198,7 +323,12 @@ mod v2 {
         #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
         async fn fetch_acks_without_pack() -> crate::Result {
             let mut provider = mock_reader("v2/fetch-no-pack.response");
-            let r = fetch::Response::from_line_reader(Protocol::V2, &mut provider.as_read_without_sidebands()).await?;
+            let r = fetch::Response::from_line_reader(
+                Protocol::V2,
+                &mut provider.as_read_without_sidebands(),
+                Default::default(),
+            )
+            .await?;
             assert_eq!(r.acknowledgements(), &[Acknowledgement::Nak,]);
             Ok(())
         }
@@ -208,7 +338,7 @@ mod v2 {
             let mut provider = mock_reader("v2/fetch-err-line.response");
             provider.fail_on_err_lines(true);
             let mut sidebands = provider.as_read_without_sidebands();
-            match fetch::Response::from_line_reader(Protocol::V2, &mut sidebands).await {
+            match fetch::Response::from_line_reader(Protocol::V2, &mut sidebands, Default::default()).await {
                 Ok(_) => panic!("need error response"),
                 Err(err) => match err {
                     fetch::response::Error::UploadPack(err) => {
@@ -223,7 +353,7 @@ mod v2 {
         async fn fetch_acks_and_pack() -> crate::Result {
             let mut provider = mock_reader("v2/fetch.response");
             let mut reader = provider.as_read_without_sidebands();
-            let r = fetch::Response::from_line_reader(Protocol::V2, &mut reader).await?;
+            let r = fetch::Response::from_line_reader(Protocol::V2, &mut reader, Default::default()).await?;
             assert_eq!(
                 r.acknowledgements(),
                 &[
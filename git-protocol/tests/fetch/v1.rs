@@ -3,7 +3,9 @@ use git_features::progress;
 use git_protocol::{handshake, FetchConnection};
 use git_transport::Protocol;
 
-use crate::fetch::{helper_unused, oid, transport, CloneDelegate, LsRemoteDelegate};
+use git_protocol::fetch::response::Acknowledgement;
+
+use crate::fetch::{helper_unused, oid, transport, CloneDelegate, LsRemoteDelegate, NegotiatingDelegate};
 
 #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
 async fn clone() -> crate::Result {
@@ -33,6 +35,90 @@ async fn clone() -> crate::Result {
     Ok(())
 }
 
+#[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+async fn fetch_with_multiple_negotiation_rounds_on_stateful_connection() -> crate::Result {
+    for (fixture, no_done) in [
+        ("v1/fetch-multi-round.response", false),
+        ("v1/fetch-multi-round-no-done.response", true),
+    ] {
+        let mut delegate = NegotiatingDelegate {
+            haves_per_round: vec![
+                vec![
+                    oid("1111111111111111111111111111111111111111"),
+                    oid("2222222222222222222222222222222222222222"),
+                ],
+                vec![
+                    oid("2b97ca25eacb49399bbac338f4d30abeed977745"),
+                    oid("d885e50946b92930715c4372a46e068be5ecc6d5"),
+                ],
+                vec![oid("32cb513c83932d3c14302ae008b2f3c0955f3157")],
+            ],
+            ..Default::default()
+        };
+        let mut transport = transport(
+            Vec::new(),
+            fixture,
+            Protocol::V1,
+            git_transport::client::git::ConnectMode::Daemon,
+        );
+        git_protocol::fetch(
+            &mut transport,
+            &mut delegate,
+            helper_unused,
+            progress::Discard,
+            FetchConnection::TerminateOnSuccessfulCompletion,
+            "agent",
+        )
+        .await?;
+
+        let common = |hex: &str| Acknowledgement::Common(oid(hex));
+        let second_round = vec![
+            common("2b97ca25eacb49399bbac338f4d30abeed977745"),
+            common("d885e50946b92930715c4372a46e068be5ecc6d5"),
+            Acknowledgement::Ready,
+            Acknowledgement::Nak,
+        ];
+        let mut expected_acks = vec![vec![Acknowledgement::Nak], second_round];
+        if !no_done {
+            expected_acks.push(vec![common("d885e50946b92930715c4372a46e068be5ecc6d5")]);
+        }
+        assert_eq!(
+            delegate.acks_per_round, expected_acks,
+            "{fixture}: each round ends with the NAK the server sends before waiting for the next one"
+        );
+        assert_eq!(
+            delegate.pack_bytes, 586,
+            "{fixture}: the pack follows the last response"
+        );
+
+        let sent = transport.into_inner().1;
+        let line = |text: &str| format!("{:04x}{}", text.len() + 4, text);
+        let mut expected_sent = line("git-upload-pack does/not/matter\0");
+        expected_sent += &line(&format!(
+            "want bcf73f62236e78bb9ec59f3a33bc6c67a729b65b thin-pack side-band-64k ofs-delta shallow deepen-since deepen-not multi_ack_detailed{} agent=git/agent\n",
+            if no_done { " no-done" } else { "" }
+        ));
+        expected_sent += &line("want bcf73f62236e78bb9ec59f3a33bc6c67a729b65b\n");
+        expected_sent += "0000";
+        for round in &delegate.haves_per_round[..2] {
+            for id in round {
+                expected_sent += &line(&format!("have {}\n", id));
+            }
+            expected_sent += "0000";
+        }
+        if !no_done {
+            expected_sent += &line("have 32cb513c83932d3c14302ae008b2f3c0955f3157\n");
+            expected_sent += &line("done\n");
+        }
+        assert_eq!(
+            sent.as_bstr(),
+            expected_sent.as_bytes().as_bstr(),
+            "{fixture}: only new haves are sent in each round, and done once the server is ready unless it sends the pack right away"
+        );
+    }
+    Ok(())
+}
+
 #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
 async fn ls_remote() -> crate::Result {
     let out = Vec::new();
@@ -0,0 +1,156 @@
+use bstr::ByteSlice;
+use git_features::progress;
+use git_protocol::push::{self, Options, RefStatus, Update};
+use git_transport::Protocol;
+
+use crate::fetch::{helper_unused, oid, transport};
+
+fn updates() -> Vec<Update> {
+    let null = git_hash::Kind::Sha1.null();
+    vec![
+        Update {
+            old_id: oid("0f9f0310bd33ea4fe571a743c3d96e79bb377089"),
+            new_id: oid("8481a5730022eb19bcb0d2a17bebb31366805036"),
+            name: "refs/heads/main".into(),
+        },
+        Update {
+            old_id: oid("7d11f575634d44934f650038e516311da5de95d9"),
+            new_id: null,
+            name: "refs/heads/feature".into(),
+        },
+        Update {
+            old_id: null,
+            new_id: oid("8481a5730022eb19bcb0d2a17bebb31366805036"),
+            name: "refs/heads/new".into(),
+        },
+    ]
+}
+
+fn expected_refs() -> Vec<RefStatus> {
+    vec![
+        RefStatus {
+            name: "refs/heads/main".into(),
+            ..Default::default()
+        },
+        RefStatus {
+            name: "refs/heads/feature".into(),
+            rejection: Some("deletion prohibited".into()),
+            ..Default::default()
+        },
+        RefStatus {
+            name: "refs/heads/new".into(),
+            ..Default::default()
+        },
+    ]
+}
+
+const PACK: &[u8] = b"PACK-placeholder";
+
+fn pack() -> impl Iterator<Item = std::io::Result<Vec<u8>>> {
+    PACK.chunks(5).map(|chunk| Ok(chunk.to_vec()))
+}
+
+#[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+async fn report_status_v2_with_sideband_and_push_options() -> crate::Result {
+    let mut transport = transport(
+        Vec::new(),
+        "v1/push-report-status-v2.response",
+        Protocol::V1,
+        git_transport::client::git::ConnectMode::Daemon,
+    );
+    let handshake = push::handshake(&mut transport, helper_unused, Vec::new(), &mut progress::Discard).await?;
+    assert_eq!(handshake.server_protocol_version, Protocol::V1);
+    assert_eq!(handshake.refs.as_ref().map(|r| r.len()), Some(2));
+
+    let outcome = git_protocol::push(
+        &mut transport,
+        &handshake.capabilities,
+        &updates(),
+        Some(pack()),
+        &Options {
+            push_options: vec!["ci.skip".into()],
+            ..Default::default()
+        },
+        &mut progress::Discard,
+        "test",
+    )
+    .await?;
+    assert_eq!(outcome.unpack_error, None);
+    assert_eq!(outcome.refs, expected_refs());
+    assert!(!outcome.is_success(), "one reference was rejected");
+
+    let mut expected = b"0025git-receive-pack does/not/matter\0".to_vec();
+    expected.extend_from_slice(b"00b40f9f0310bd33ea4fe571a743c3d96e79bb377089 8481a5730022eb19bcb0d2a17bebb31366805036 refs/heads/main\0push-options report-status-v2 side-band-64k object-format=sha1 agent=git/test\n");
+    expected.extend_from_slice(
+        b"00697d11f575634d44934f650038e516311da5de95d9 0000000000000000000000000000000000000000 refs/heads/feature\n",
+    );
+    expected.extend_from_slice(
+        b"00650000000000000000000000000000000000000000 8481a5730022eb19bcb0d2a17bebb31366805036 refs/heads/new\n0000",
+    );
+    expected.extend_from_slice(b"000cci.skip\n0000");
+    expected.extend_from_slice(PACK);
+    assert_eq!(transport.into_inner().1.as_bstr(), expected.as_bstr());
+    Ok(())
+}
+
+#[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+async fn report_status_without_sideband() -> crate::Result {
+    let mut transport = transport(
+        Vec::new(),
+        "v1/push-report-status.response",
+        Protocol::V1,
+        git_transport::client::git::ConnectMode::Daemon,
+    );
+    let handshake = push::handshake(&mut transport, helper_unused, Vec::new(), &mut progress::Discard).await?;
+    let outcome = git_protocol::push(
+        &mut transport,
+        &handshake.capabilities,
+        &updates(),
+        Some(pack()),
+        &Options::default(),
+        &mut progress::Discard,
+        "test",
+    )
+    .await?;
+    assert_eq!(outcome.unpack_error, None);
+    assert_eq!(outcome.refs, expected_refs());
+
+    let written = transport.into_inner().1;
+    assert!(
+        written
+            .find(b"refs/heads/main\0report-status agent=git/test\n".as_bstr())
+            .is_some(),
+        "only capabilities supported by the server are requested"
+    );
+    Ok(())
+}
+
+#[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+async fn unsupported_capabilities_are_an_error() -> crate::Result {
+    let mut transport = transport(
+        Vec::new(),
+        "v1/push-report-status.response",
+        Protocol::V1,
+        git_transport::client::git::ConnectMode::Daemon,
+    );
+    let handshake = push::handshake(&mut transport, helper_unused, Vec::new(), &mut progress::Discard).await?;
+    let err = git_protocol::push(
+        &mut transport,
+        &handshake.capabilities,
+        &updates(),
+        Some(pack()),
+        &Options {
+            atomic: true,
+            ..Default::default()
+        },
+        &mut progress::Discard,
+        "test",
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        push::Error::MissingServerCapability { feature: "atomic" }
+    ));
+    Ok(())
+}
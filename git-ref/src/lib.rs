@@ -14,6 +14,8 @@
 //!     * one reference maps to a file on disk
 //!   * **packed**
 //!     * references are stored in a single human-readable file, along with their targets if they are symbolic.
+//! * **[reftable][reftable::Store]**
+//!   * references and their logs are stored in a stack of binary tables, which scales to large amounts of references.
 //!
 //! ## Feature Flags
 #![cfg_attr(
@@ -31,7 +33,7 @@ use git_object::bstr::{BStr, BString};
 
 #[path = "store/mod.rs"]
 mod store_impl;
-pub use store_impl::{file, packed};
+pub use store_impl::{file, packed, reftable};
 
 mod fullname;
 ///
@@ -73,34 +75,62 @@ pub mod store {
         }
     }
 
+    /// The format in which references are stored, as configured by `extensions.refStorage`.
+    #[derive(Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
+    pub enum StorageFormat {
+        /// Loose references as files along with a `packed-refs` file, the default if `extensions.refStorage` isn't set.
+        Files,
+        /// A stack of tables in the `reftable` directory.
+        Reftable,
+    }
+
+    impl Default for StorageFormat {
+        fn default() -> Self {
+            StorageFormat::Files
+        }
+    }
+
+    impl StorageFormat {
+        /// Parse the `value` of the `extensions.refStorage` configuration key, or return `None` if it is unknown.
+        pub fn from_bytes(value: &[u8]) -> Option<Self> {
+            Some(match value {
+                b"files" => StorageFormat::Files,
+                b"reftable" => StorageFormat::Reftable,
+                _ => return None,
+            })
+        }
+    }
+
     /// A thread-local handle for interacting with a [`Store`][crate::Store] to find and iterate references.
-    #[derive(Clone)]
-    #[allow(dead_code)]
-    pub(crate) struct Handle {
+    #[derive(Debug, Clone)]
+    pub struct Handle {
         /// A way to access shared state with the requirement that interior mutability doesn't leak or is incorporated into error types
         /// if it could. The latter can't happen if references to said internal aren't ever returned.
         state: handle::State,
     }
 
-    #[allow(dead_code)]
+    #[derive(Debug, Clone)]
     pub(crate) enum State {
         Loose { store: file::Store },
+        Reftable { store: reftable::Store },
     }
 
     pub(crate) mod general;
+    pub use general::init;
 
     ///
     #[path = "general/handle/mod.rs"]
     mod handle;
-    pub use handle::find;
+    pub use handle::{find, iter, namespace, reflog, transaction, Transaction};
 
-    use crate::file;
+    use crate::{file, reftable};
 }
 
-/// The git reference store.
-/// TODO: Figure out if handles are needed at all, which depends on the ref-table implementation.
-#[allow(dead_code)]
-pub(crate) struct Store {
+/// The git reference store, which uses either [loose files][file::Store] or [reftables][reftable::Store] as backend.
+///
+/// Use [`Store::to_handle()`] to obtain a [`Handle`][store::Handle] for finding, iterating and editing references.
+#[derive(Debug, Clone)]
+pub struct Store {
     inner: store::State,
 }
 
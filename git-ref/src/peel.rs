@@ -6,6 +6,40 @@ pub fn none(
     Ok(Some((git_object::Kind::Commit, &[])))
 }
 
+/// Peel the object `reference` points to by following tags until a non-tag object is found, using `find` to look up objects,
+/// and store the result in `reference`.
+///
+/// `reference` must not be symbolic.
+pub(crate) fn objects_in_place<E: std::error::Error + Send + Sync + 'static>(
+    reference: &mut crate::Reference,
+    mut find: impl FnMut(git_hash::ObjectId, &mut Vec<u8>) -> Result<Option<(git_object::Kind, &[u8])>, E>,
+) -> Result<git_hash::ObjectId, to_id::Error> {
+    let mut buf = Vec::new();
+    let mut oid = reference.target.try_id().expect("peeled ref").to_owned();
+    let peeled_id = loop {
+        let (kind, data) = find(oid, &mut buf)
+            .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync + 'static>)?
+            .ok_or_else(|| to_id::Error::NotFound {
+                oid,
+                name: reference.name.0.clone(),
+            })?;
+        match kind {
+            git_object::Kind::Tag => {
+                oid = git_object::TagRefIter::from_bytes(data)
+                    .target_id()
+                    .map_err(|_err| to_id::Error::NotFound {
+                        oid,
+                        name: reference.name.0.clone(),
+                    })?;
+            }
+            _ => break oid,
+        };
+    };
+    reference.peeled = Some(peeled_id);
+    reference.target = crate::Target::Peeled(peeled_id);
+    Ok(peeled_id)
+}
+
 ///
 pub mod to_id {
     use std::path::PathBuf;
@@ -14,12 +48,15 @@ pub mod to_id {
 
     use crate::file;
 
-    /// The error returned by [`crate::file::ReferenceExt::peel_to_id_in_place()`].
+    /// The error returned by [`crate::file::ReferenceExt::peel_to_id_in_place()`] and
+    /// [`crate::store::Handle::peel_to_id_in_place()`].
     #[derive(Debug, thiserror::Error)]
     #[allow(missing_docs)]
     pub enum Error {
         #[error("Could not follow a single level of a symbolic reference")]
         Follow(#[from] file::find::existing::Error),
+        #[error("Could not follow a single level of a symbolic reference")]
+        FollowInStore(#[from] crate::store::find::existing::Error),
         #[error("Aborting due to reference cycle with first seen path being {start_absolute:?}")]
         Cycle { start_absolute: PathBuf },
         #[error("Refusing to follow more than {max_depth} levels of indirection")]
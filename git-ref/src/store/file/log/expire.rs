@@ -0,0 +1,430 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write as _,
+};
+
+use git_hash::ObjectId;
+use git_object::tree::EntryMode;
+
+pub use error::Error;
+
+use crate::{
+    file::{self, ReferenceExt},
+    peel, FullNameRef, Target,
+};
+
+/// How to write a reflog and its reference after entries were removed from it, similar to the flags of `git reflog expire`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Write {
+    /// If `true`, set the previous object id of each kept entry to the new object id of the kept entry before it,
+    /// like `--rewrite`, so that the reflog stays consistent.
+    pub rewrite: bool,
+    /// If `true`, set the reference to the new object id of the most recent kept entry, like `--updateref`.
+    /// Symbolic references are never changed.
+    pub update_ref: bool,
+    /// If `true`, don't change anything but report what would have been done, like `--dry-run`.
+    pub dry_run: bool,
+}
+
+/// Options for use in [`file::Store::reflog_expire()`], similar to the flags of `git reflog expire`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// Remove all entries older than the given time in seconds since the unix epoch, typically derived from `gc.reflogExpire`.
+    pub older_than: Option<u32>,
+    /// Remove entries older than the given time in seconds since the unix epoch if their previous or new object id
+    /// can't be reached from the tip of the reference, typically derived from `gc.reflogExpireUnreachable`.
+    ///
+    /// The tips of all references are used for `HEAD`, whose entries are likely to refer to commits of other branches.
+    pub unreachable_older_than: Option<u32>,
+    /// If `true`, remove all entries that refer to missing objects, or to commits which miss objects in their trees
+    /// or their ancestry, like `--stale-fix`.
+    pub stale_fix: bool,
+    /// How to write the reflog and its reference.
+    pub write: Write,
+}
+
+/// The outcome of [`file::Store::reflog_expire()`] and [`file::Store::reflog_delete()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// The amount of entries that were kept.
+    pub kept: usize,
+    /// The amount of entries that were removed, or that would have been removed in a dry-run.
+    pub removed: usize,
+}
+
+/// The commits reachable from a set of tips, which are computed only when needed.
+struct Reachable {
+    tips: Vec<ObjectId>,
+    commits: Option<HashSet<ObjectId>>,
+}
+
+impl file::Store {
+    /// Remove entries from the reflog of the reference with the given full `name` according to `options`, similar to
+    /// `git reflog expire`, and return how many entries were kept and removed.
+    ///
+    /// `find` is used to lookup objects for determining whether or not entries are reachable or stale, and `lock_fail_mode`
+    /// determines how to wait for the lock on the reference, which is the same lock that transactions use to prevent changes
+    /// to the reference and its reflog.
+    ///
+    /// Entries are reachable if their object id is either null or a commit that can be reached from the tip of the reference.
+    /// If the reference doesn't point to a commit, all entries older than [`Options::unreachable_older_than`] are removed.
+    /// The reflog of `HEAD` is an exception, as the tips of all references are used no matter what `HEAD` points to.
+    pub fn reflog_expire<E>(
+        &self,
+        name: &FullNameRef,
+        options: Options,
+        lock_fail_mode: git_lock::acquire::Fail,
+        mut find: impl FnMut(ObjectId, &mut Vec<u8>) -> Result<Option<(git_object::Kind, &[u8])>, E>,
+    ) -> Result<Outcome, Error>
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let mut reachable = Reachable {
+            tips: self.reflog_expire_tips(name, &mut find)?,
+            commits: None,
+        };
+        let mut complete = Complete::default();
+        let mut buf = Vec::new();
+        self.rewrite_reflog(name, options.write, lock_fail_mode, |_index, line| {
+            let time = line.signature.time.seconds_since_unix_epoch;
+            if options.older_than.map_or(false, |older_than| time < older_than) {
+                return Ok(true);
+            }
+            let ids = [line.previous_oid(), line.new_oid()];
+            if options.stale_fix {
+                for id in ids.iter().filter(|id| !id.is_null()) {
+                    if !complete.contains(*id, &mut find, &mut buf)? {
+                        return Ok(true);
+                    }
+                }
+            }
+            if options
+                .unreachable_older_than
+                .map_or(false, |older_than| time < older_than)
+            {
+                if reachable.tips.is_empty() {
+                    return Ok(true);
+                }
+                for id in ids.iter().filter(|id| !id.is_null()) {
+                    if !reachable.contains(*id, &mut find, &mut buf)? {
+                        return Ok(true);
+                    }
+                }
+            }
+            Ok(false)
+        })
+    }
+
+    /// Remove the reflog entries at the given `indices` from the reflog of the reference with the given full `name`,
+    /// similar to `git reflog delete`, with `0` being the most recent entry as in `name@{0}`.
+    ///
+    /// `write` determines how to write the reflog and the reference, and `lock_fail_mode` determines how to wait for the lock
+    /// on the reference.
+    pub fn reflog_delete(
+        &self,
+        name: &FullNameRef,
+        indices: &[usize],
+        write: Write,
+        lock_fail_mode: git_lock::acquire::Fail,
+    ) -> Result<Outcome, Error> {
+        self.rewrite_reflog(name, write, lock_fail_mode, |index, _line| Ok(indices.contains(&index)))
+    }
+
+    /// Return the commits to consider reachable for the reflog of `name`, which is empty if it isn't pointing to a commit.
+    ///
+    /// For `HEAD`, these are the tips of all references even if `HEAD` itself is unborn or missing, just like in `git`.
+    fn reflog_expire_tips<E>(
+        &self,
+        name: &FullNameRef,
+        mut find: impl FnMut(ObjectId, &mut Vec<u8>) -> Result<Option<(git_object::Kind, &[u8])>, E>,
+    ) -> Result<Vec<ObjectId>, Error>
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let mut tip_of = |mut reference: crate::Reference| -> Result<Option<ObjectId>, Error> {
+            let id = match reference.peel_to_id_in_place(self, &mut find) {
+                Ok(id) => id,
+                // Unborn references and those pointing to missing objects don't make anything reachable, just like in `git`.
+                Err(
+                    peel::to_id::Error::NotFound { .. }
+                    | peel::to_id::Error::Follow(file::find::existing::Error::NotFound { .. }),
+                ) => return Ok(None),
+                Err(err) => return Err(Error::PeelReference(err)),
+            };
+            let mut buf = Vec::new();
+            let kind = find(id, &mut buf)
+                .map_err(|err| Error::FindObject(err.into()))?
+                .map(|(kind, _)| kind);
+            Ok((kind == Some(git_object::Kind::Commit)).then(|| id))
+        };
+        let tip = match self.try_find(name)? {
+            Some(reference) => tip_of(reference)?,
+            None => None,
+        };
+        let mut tips: Vec<_> = tip.into_iter().collect();
+        if name.as_bstr() != "HEAD" {
+            return Ok(tips);
+        }
+        for reference in self.iter()?.all()? {
+            tips.extend(tip_of(reference?)?);
+        }
+        Ok(tips)
+    }
+
+    /// Rewrite the reflog of `name` while holding the lock on the reference, and drop all entries for which `prune(index, line)`
+    /// returns `true`, with `index` being `0` for the most recent entry.
+    fn rewrite_reflog(
+        &self,
+        name: &FullNameRef,
+        write: Write,
+        lock_fail_mode: git_lock::acquire::Fail,
+        mut prune: impl FnMut(usize, &file::log::LineRef<'_>) -> Result<bool, Error>,
+    ) -> Result<Outcome, Error> {
+        let (base, relative_path) = self.reference_path_with_base(name);
+        let mut ref_lock = git_lock::File::acquire_to_update_resource(
+            base.join(relative_path.as_ref()),
+            lock_fail_mode,
+            Some(base.clone().into_owned()),
+        )?;
+
+        let mut buf = Vec::new();
+        let lines = match self.reflog_iter(name, &mut buf)? {
+            Some(lines) => lines.collect::<Result<Vec<_>, _>>()?,
+            None => return Ok(Outcome::default()),
+        };
+        let num_lines = lines.len();
+        let mut outcome = Outcome::default();
+        let mut kept = Vec::with_capacity(num_lines);
+        for (index, line) in lines.iter().enumerate() {
+            if prune(num_lines - index - 1, line)? {
+                outcome.removed += 1;
+            } else {
+                outcome.kept += 1;
+                kept.push(line.to_owned());
+            }
+        }
+        if write.dry_run || outcome.removed == 0 {
+            return Ok(outcome);
+        }
+
+        if write.rewrite {
+            let mut previous = self.object_hash.null();
+            for line in &mut kept {
+                line.previous_oid = previous;
+                previous = line.new_oid;
+            }
+        }
+        let (log_base, log_relative_path) = self.reflog_base_and_relative_path(name);
+        let mut log_lock = git_lock::File::acquire_to_update_resource(
+            log_base.join(log_relative_path.as_ref()),
+            lock_fail_mode,
+            Some(log_base),
+        )?;
+        for line in &kept {
+            line.write_to(&mut log_lock)?;
+        }
+        log_lock.commit().map_err(|err| err.error)?;
+
+        let new_tip = kept.last().map(|line| line.new_oid).filter(|id| !id.is_null());
+        if let Some(new_tip) = new_tip.filter(|_| write.update_ref) {
+            let is_peeled = self
+                .try_find(name)?
+                .map_or(false, |r| matches!(r.target, Target::Peeled(_)));
+            if is_peeled {
+                writeln!(ref_lock, "{}", new_tip)?;
+                ref_lock.commit().map_err(|err| err.error)?;
+            }
+        }
+        Ok(outcome)
+    }
+}
+
+impl Reachable {
+    fn contains<E>(
+        &mut self,
+        id: ObjectId,
+        mut find: impl FnMut(ObjectId, &mut Vec<u8>) -> Result<Option<(git_object::Kind, &[u8])>, E>,
+        buf: &mut Vec<u8>,
+    ) -> Result<bool, Error>
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        if self.commits.is_none() {
+            let mut commits = HashSet::new();
+            let mut queue = self.tips.clone();
+            while let Some(id) = queue.pop() {
+                if !commits.insert(id) {
+                    continue;
+                }
+                if let Some((git_object::Kind::Commit, data)) =
+                    find(id, buf).map_err(|err| Error::FindObject(err.into()))?
+                {
+                    queue.extend(git_object::CommitRefIter::from_bytes(data).parent_ids());
+                }
+            }
+            self.commits = Some(commits);
+        }
+        Ok(self.commits.as_ref().expect("just set").contains(&id))
+    }
+}
+
+/// Objects whose completeness was determined, similar to what `git reflog expire --stale-fix` uses to find stale entries.
+///
+/// Commits are complete if they and all of their ancestors exist, and if the trees of all of them are complete.
+/// Trees are complete if all trees and blobs they contain, recursively, exist.
+#[derive(Default)]
+struct Complete {
+    commits: HashMap<ObjectId, bool>,
+    trees: HashMap<ObjectId, bool>,
+    blobs: HashSet<ObjectId>,
+}
+
+impl Complete {
+    /// Return `true` if `id` peels to a complete commit, or `false` if it or any object needed by it is missing.
+    fn contains<E>(
+        &mut self,
+        id: ObjectId,
+        mut find: impl FnMut(ObjectId, &mut Vec<u8>) -> Result<Option<(git_object::Kind, &[u8])>, E>,
+        buf: &mut Vec<u8>,
+    ) -> Result<bool, Error>
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let mut id = id;
+        loop {
+            match find(id, buf).map_err(|err| Error::FindObject(err.into()))? {
+                Some((git_object::Kind::Commit, _)) => break,
+                Some((git_object::Kind::Tag, data)) => match git_object::TagRefIter::from_bytes(data).target_id() {
+                    Ok(target) => id = target,
+                    Err(_) => return Ok(false),
+                },
+                Some(_) | None => return Ok(false),
+            }
+        }
+        if let Some(is_complete) = self.commits.get(&id) {
+            return Ok(*is_complete);
+        }
+
+        // Like `git`, don't bother finding out which commit is incomplete and consider all commits seen here incomplete.
+        let mut seen = vec![id];
+        let mut queue = vec![id];
+        let mut trees = Vec::new();
+        let mut is_complete = true;
+        while let Some(id) = queue.pop() {
+            let (tree_id, parent_ids) = match find(id, buf).map_err(|err| Error::FindObject(err.into()))? {
+                Some((git_object::Kind::Commit, data)) => match git_object::CommitRefIter::from_bytes(data).tree_id() {
+                    Ok(tree_id) => (
+                        tree_id,
+                        git_object::CommitRefIter::from_bytes(data)
+                            .parent_ids()
+                            .collect::<Vec<_>>(),
+                    ),
+                    Err(_) => {
+                        is_complete = false;
+                        break;
+                    }
+                },
+                Some(_) | None => {
+                    is_complete = false;
+                    break;
+                }
+            };
+            trees.push(tree_id);
+            for parent_id in parent_ids {
+                match self.commits.get(&parent_id) {
+                    Some(true) => {}
+                    Some(false) => is_complete = false,
+                    None if !seen.contains(&parent_id) => {
+                        seen.push(parent_id);
+                        queue.push(parent_id);
+                    }
+                    None => {}
+                }
+            }
+            if !is_complete {
+                break;
+            }
+        }
+        if is_complete {
+            for tree_id in trees {
+                if !self.tree(tree_id, &mut find, buf)? {
+                    is_complete = false;
+                    break;
+                }
+            }
+        }
+        self.commits.extend(seen.into_iter().map(|id| (id, is_complete)));
+        Ok(is_complete)
+    }
+
+    /// Return `true` if the tree with `id` and all objects it contains, recursively, exist.
+    fn tree<E>(
+        &mut self,
+        id: ObjectId,
+        find: &mut impl FnMut(ObjectId, &mut Vec<u8>) -> Result<Option<(git_object::Kind, &[u8])>, E>,
+        buf: &mut Vec<u8>,
+    ) -> Result<bool, Error>
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        if let Some(is_complete) = self.trees.get(&id) {
+            return Ok(*is_complete);
+        }
+        let entries = match find(id, buf).map_err(|err| Error::FindObject(err.into()))? {
+            Some((git_object::Kind::Tree, data)) => git_object::TreeRefIter::from_bytes(data)
+                .map(|entry| entry.map(|entry| (entry.mode, entry.oid.to_owned())))
+                .collect::<Result<Vec<_>, _>>()
+                .ok(),
+            Some(_) | None => None,
+        };
+        let mut is_complete = entries.is_some();
+        for (mode, oid) in entries.into_iter().flatten() {
+            is_complete = match mode {
+                EntryMode::Tree => self.tree(oid, find, buf)?,
+                EntryMode::Blob | EntryMode::BlobExecutable | EntryMode::Link => {
+                    self.blobs.contains(&oid)
+                        || match find(oid, buf).map_err(|err| Error::FindObject(err.into()))? {
+                            Some((git_object::Kind::Blob, _)) => self.blobs.insert(oid),
+                            Some(_) | None => false,
+                        }
+                }
+                // Submodule commits aren't expected to be present.
+                EntryMode::Commit => true,
+            };
+            if !is_complete {
+                break;
+            }
+        }
+        self.trees.insert(id, is_complete);
+        Ok(is_complete)
+    }
+}
+
+mod error {
+    use crate::{file, packed, peel};
+
+    /// The error returned by [file::Store::reflog_expire()][crate::file::Store::reflog_expire()] and
+    /// [file::Store::reflog_delete()][crate::file::Store::reflog_delete()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The lock on the reference or its reflog could not be obtained")]
+        LockAcquire(#[from] git_lock::acquire::Error),
+        #[error("The reflog could not be read")]
+        ReadLog(#[from] file::log::Error),
+        #[error("A reflog line could not be decoded")]
+        Decode(#[from] file::log::iter::decode::Error),
+        #[error("The reference could not be looked up")]
+        FindReference(#[from] file::find::Error),
+        #[error("The packed-refs file could not be opened to find the tips of all references")]
+        PackedOpen(#[from] packed::buffer::open::Error),
+        #[error("A reference could not be read while finding the tips of all references")]
+        IterReferences(#[from] file::iter::loose_then_packed::Error),
+        #[error("A reference could not be peeled to find its tip")]
+        PeelReference(#[source] peel::to_id::Error),
+        #[error("An object could not be looked up")]
+        FindObject(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+        #[error("The reflog or the reference could not be written, or the references could not be traversed")]
+        Io(#[from] std::io::Error),
+    }
+}
@@ -100,5 +100,11 @@ pub mod transaction;
 ///
 pub mod packed;
 
+///
+pub mod pack_refs;
+
+///
+pub mod rename;
+
 mod raw_ext;
 pub use raw_ext::ReferenceExt;
@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+use git_hash::ObjectId;
+
+pub use error::Error;
+
+use crate::{
+    file::{self, transaction::PackedRefs},
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    Category, FullName, Target,
+};
+
+/// Options for use in [`file::Store::pack_refs()`], similar to the flags of `git pack-refs`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// If `true`, pack all loose references like `--all`. Otherwise only tags and references which are already
+    /// in the `packed-refs` file are packed.
+    pub all: bool,
+    /// If `true`, delete the loose references after they have been packed like `--prune`, which is what `git` does by default.
+    pub prune: bool,
+}
+
+/// The outcome of [`file::Store::pack_refs()`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    /// The amount of loose references that were written into the `packed-refs` file.
+    pub packed: usize,
+    /// The amount of loose references that were skipped as the objects they point to don't exist.
+    pub skipped_broken: usize,
+}
+
+impl file::Store {
+    /// Move loose references into the `packed-refs` file according to `options`, similar to `git pack-refs`, and return
+    /// how many references were packed.
+    ///
+    /// The `packed-refs` file is rewritten sorted and fully peeled, that is with the object each annotated tag ultimately
+    /// points to on its own line, using `find` to lookup objects. Symbolic references, references private to a worktree
+    /// and references pointing to missing objects are never packed.
+    ///
+    /// All packed references are locked while the `packed-refs` file is written and are only deleted afterwards if
+    /// [`Options::prune`] is set, and only if they didn't change in the meantime. `ref_files_lock_fail_mode` and
+    /// `packed_refs_lock_fail_mode` determine how to wait for these locks, as in [`file::Transaction::prepare()`].
+    pub fn pack_refs<'a, E>(
+        &'a self,
+        options: Options,
+        ref_files_lock_fail_mode: git_lock::acquire::Fail,
+        packed_refs_lock_fail_mode: git_lock::acquire::Fail,
+        mut find: impl FnMut(ObjectId, &mut Vec<u8>) -> Result<Option<git_object::Kind>, E> + 'a,
+    ) -> Result<Outcome, Error>
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let already_packed: HashSet<FullName> = match self.cached_packed_buffer()? {
+            Some(buffer) if !options.all => buffer
+                .iter()?
+                .map(|r| r.map(|r| r.name.to_owned()))
+                .collect::<Result<_, _>>()?,
+            _ => HashSet::new(),
+        };
+
+        let mut outcome = Outcome::default();
+        let mut edits = Vec::new();
+        let mut buf = Vec::new();
+        for reference in self.loose_iter()? {
+            let reference = reference?;
+            let id = match reference.target {
+                Target::Peeled(id) => id,
+                Target::Symbolic(_) => continue,
+            };
+            if reference
+                .name
+                .category()
+                .map_or(false, |category| category.is_worktree_private())
+            {
+                continue;
+            }
+            if !options.all && reference.name.category() != Some(Category::Tag) {
+                let packed_name = match &self.namespace {
+                    Some(namespace) => namespace.clone().into_namespaced_name(reference.name.as_ref()),
+                    None => reference.name.clone(),
+                };
+                if !already_packed.contains(&packed_name) {
+                    continue;
+                }
+            }
+            if find(id, &mut buf)
+                .map_err(|err| Error::FindObject(err.into()))?
+                .is_none()
+            {
+                outcome.skipped_broken += 1;
+                continue;
+            }
+            edits.push(RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: "pack-refs".into(),
+                    },
+                    expected: PreviousValue::MustExistAndMatch(Target::Peeled(id)),
+                    new: Target::Peeled(id),
+                },
+                name: reference.name,
+                deref: false,
+            });
+        }
+        if edits.is_empty() {
+            return Ok(outcome);
+        }
+
+        let find: Box<file::transaction::FindObjectFn<'a>> =
+            Box::new(move |id, buf| find(id, buf).map_err(|err| Box::new(err) as _));
+        let edits = self
+            .transaction()
+            .packed_refs(if options.prune {
+                PackedRefs::DeletionsAndNonSymbolicUpdatesRemoveLooseSourceReference(find)
+            } else {
+                PackedRefs::DeletionsAndNonSymbolicUpdates(find)
+            })
+            .prepare(edits, ref_files_lock_fail_mode, packed_refs_lock_fail_mode)?
+            // As the values don't change, no reflog is written and the committer isn't used.
+            .commit(git_actor::SignatureRef::default())?;
+        outcome.packed = edits.len();
+        Ok(outcome)
+    }
+}
+
+mod error {
+    use crate::{file, packed};
+
+    /// The error returned by [file::Store::pack_refs()][crate::file::Store::pack_refs()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The packed-refs file could not be opened")]
+        PackedOpen(#[from] packed::buffer::open::Error),
+        #[error("The packed-refs file could not be iterated")]
+        PackedIter(#[from] packed::iter::Error),
+        #[error("The loose references could not be traversed")]
+        Io(#[from] std::io::Error),
+        #[error("A loose reference could not be read")]
+        LooseIter(#[from] file::iter::loose_then_packed::Error),
+        #[error("An object could not be looked up")]
+        FindObject(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+        #[error("The references could not be locked or prepared for packing")]
+        Prepare(#[from] file::transaction::prepare::Error),
+        #[error("The packed-refs file could not be written or the loose references could not be deleted")]
+        Commit(#[from] file::transaction::commit::Error),
+    }
+}
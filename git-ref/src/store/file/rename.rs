@@ -0,0 +1,181 @@
+use std::path::Path;
+
+use git_object::bstr::BString;
+
+pub use error::Error;
+
+use crate::{
+    file,
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    FullName, FullNameRef, Reference, Target,
+};
+
+/// The path relative to the base of all reflogs to which the reflog of a reference is moved while it is renamed, like `git` does.
+const TMP_RENAMED_LOG: &str = "refs/.tmp-renamed-log";
+
+/// Options for use in [`file::Store::rename_reference()`], similar to the flags of `git branch -m`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// If `true`, overwrite the reference to rename to if it exists, along with its reflog, like `git branch -M`.
+    pub force: bool,
+    /// The message of the reflog entry written for the renamed reference, like `Branch: renamed refs/heads/a to refs/heads/b`.
+    pub message: BString,
+}
+
+impl file::Store {
+    /// Rename the reference with the full name `from` to `to` according to `options` and move its reflog along with it,
+    /// similar to `git branch -m`, and return the renamed reference.
+    ///
+    /// Only references pointing to an object can be renamed, and the reference at `to` must not exist unless
+    /// [`Options::force`] is set. The reflog of the renamed reference receives an entry with [`Options::message`],
+    /// using `committer`, and `ref_files_lock_fail_mode` and `packed_refs_lock_fail_mode` determine how to wait for locks,
+    /// as in [`file::Transaction::prepare()`].
+    ///
+    /// Similar to `git`, the old reference is deleted before the new one is created, and an attempt is made to restore it
+    /// along with its reflog if the new reference can't be created.
+    pub fn rename_reference(
+        &self,
+        from: &FullNameRef,
+        to: &FullNameRef,
+        options: Options,
+        committer: git_actor::SignatureRef<'_>,
+        ref_files_lock_fail_mode: git_lock::acquire::Fail,
+        packed_refs_lock_fail_mode: git_lock::acquire::Fail,
+    ) -> Result<Reference, Error> {
+        let reference = self.find(from.as_partial_name())?;
+        let id = match reference.target {
+            Target::Peeled(id) => id,
+            Target::Symbolic(_) => return Err(Error::Symbolic { name: from.to_owned() }),
+        };
+        if from == to {
+            return Ok(reference);
+        }
+        let existing_target = self.try_find(to.as_partial_name())?.map(|r| r.target);
+        if existing_target.is_some() && !options.force {
+            return Err(Error::Exists { name: to.to_owned() });
+        }
+
+        let from_log = self.reflog_path(from);
+        let tmp_log = self.reflog_base_and_relative_path(from).0.join(TMP_RENAMED_LOG);
+        let has_log = from_log.is_file();
+        if has_log {
+            move_file(&from_log, &tmp_log)?;
+        }
+
+        let mut deletions = vec![deletion(from.to_owned(), Target::Peeled(id))];
+        if let Some(target) = existing_target {
+            deletions.push(deletion(to.to_owned(), target));
+        }
+        if let Err(err) = self
+            .transaction()
+            .prepare(deletions, ref_files_lock_fail_mode, packed_refs_lock_fail_mode)
+            .map_err(Error::from)
+            .and_then(|t| t.commit(committer).map_err(Error::from))
+        {
+            if has_log {
+                move_file(&tmp_log, &from_log).ok();
+            }
+            return Err(err);
+        }
+
+        let to_log = self.reflog_path(to);
+        let res = if has_log {
+            move_file(&tmp_log, &to_log).map_err(Error::from)
+        } else {
+            Ok(())
+        }
+        .and_then(|_| {
+            self.transaction()
+                .prepare(
+                    Some(creation(to.to_owned(), id, options.message.clone())),
+                    ref_files_lock_fail_mode,
+                    packed_refs_lock_fail_mode,
+                )?
+                .commit(committer)?;
+            Ok(())
+        });
+        if let Err(err) = res {
+            if has_log {
+                let log = if to_log.is_file() { &to_log } else { &tmp_log };
+                move_file(log, &from_log).ok();
+            }
+            self.transaction()
+                .prepare(
+                    Some(creation(from.to_owned(), id, options.message)),
+                    ref_files_lock_fail_mode,
+                    packed_refs_lock_fail_mode,
+                )
+                .map_err(Error::from)
+                .and_then(|t| t.commit(committer).map_err(Into::into))
+                .ok();
+            return Err(err);
+        }
+        Ok(Reference {
+            name: to.to_owned(),
+            target: Target::Peeled(id),
+            peeled: None,
+        })
+    }
+}
+
+fn deletion(name: FullName, target: Target) -> RefEdit {
+    RefEdit {
+        change: Change::Delete {
+            expected: PreviousValue::MustExistAndMatch(target),
+            log: RefLog::AndReference,
+        },
+        name,
+        deref: false,
+    }
+}
+
+fn creation(name: FullName, id: git_hash::ObjectId, message: BString) -> RefEdit {
+    RefEdit {
+        change: Change::Update {
+            log: LogChange {
+                mode: RefLog::AndReference,
+                force_create_reflog: false,
+                message,
+            },
+            expected: PreviousValue::MustNotExist,
+            new: Target::Peeled(id),
+        },
+        name,
+        deref: false,
+    }
+}
+
+/// Move the file at `from` to `to`, creating leading directories and removing empty directories in the way as needed.
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if to.is_dir() {
+        git_tempfile::remove_dir::empty_depth_first(to)?;
+    }
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::rename(from, to)
+}
+
+mod error {
+    use crate::{file, FullName};
+
+    /// The error returned by [file::Store::rename_reference()][crate::file::Store::rename_reference()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The reference to rename could not be found")]
+        Find(#[from] file::find::existing::Error),
+        #[error("The reference to rename to could not be looked up")]
+        Lookup(#[from] file::find::Error),
+        #[error("The symbolic reference {} can't be renamed", name.as_bstr())]
+        Symbolic { name: FullName },
+        #[error("The reference {} already exists", name.as_bstr())]
+        Exists { name: FullName },
+        #[error("The reflog could not be moved")]
+        Io(#[from] std::io::Error),
+        #[error("The references could not be locked or prepared for renaming")]
+        Prepare(#[from] file::transaction::prepare::Error),
+        #[error("The old reference could not be deleted or the new one could not be written")]
+        Commit(#[from] file::transaction::commit::Error),
+    }
+}
@@ -0,0 +1,93 @@
+use std::path::Path;
+
+pub use error::Error;
+
+use crate::{file, reftable, store, store::handle, Reference};
+
+/// A platform to obtain iterators over all references, or prefixed ones, sorted by their name.
+pub struct Platform<'s> {
+    inner: PlatformInner<'s>,
+}
+
+enum PlatformInner<'s> {
+    Loose(file::iter::Platform<'s>),
+    Reftable(&'s reftable::Store),
+}
+
+/// An iterator over references sorted by their name, regardless of the backend that stores them.
+pub struct Iter<'p> {
+    inner: IterInner<'p>,
+}
+
+enum IterInner<'p> {
+    Loose(Box<file::iter::LooseThenPacked<'p, 'p>>),
+    Reftable(reftable::iter::Iter),
+}
+
+impl<'p> Iterator for Iter<'p> {
+    type Item = Result<Reference, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            IterInner::Loose(iter) => iter.next().map(|res| res.map_err(Into::into)),
+            IterInner::Reftable(iter) => iter.next().map(Ok),
+        }
+    }
+}
+
+impl<'s> Platform<'s> {
+    /// Return an iterator over all references, sorted by their name.
+    pub fn all(&self) -> Result<Iter<'_>, Error> {
+        Ok(Iter {
+            inner: match &self.inner {
+                PlatformInner::Loose(platform) => IterInner::Loose(Box::new(platform.all()?)),
+                PlatformInner::Reftable(store) => IterInner::Reftable(store.iter()?),
+            },
+        })
+    }
+
+    /// As [`all()`][Platform::all()], but filters by `prefix`, i.e. "refs/heads/".
+    ///
+    /// A prefix naming a directory of references like "refs/heads" is treated as "refs/heads/" by all backends.
+    pub fn prefixed(&self, prefix: impl AsRef<Path>) -> Result<Iter<'_>, Error> {
+        Ok(Iter {
+            inner: match &self.inner {
+                PlatformInner::Loose(platform) => IterInner::Loose(Box::new(platform.prefixed(prefix)?)),
+                PlatformInner::Reftable(store) => IterInner::Reftable(store.iter_prefixed(prefix)?),
+            },
+        })
+    }
+}
+
+impl store::Handle {
+    /// Return a platform to obtain iterators over all references, or prefixed ones, sorted by their name.
+    ///
+    /// Loose stores check their packed buffer for modifications when the platform is created, and reftable stores
+    /// take a snapshot of their tables for each iterator.
+    pub fn iter(&self) -> Result<Platform<'_>, Error> {
+        Ok(Platform {
+            inner: match &self.state {
+                handle::State::Loose { store } => PlatformInner::Loose(store.iter()?),
+                handle::State::Reftable { store } => PlatformInner::Reftable(store),
+            },
+        })
+    }
+}
+
+mod error {
+    use crate::{file, packed, reftable};
+
+    /// The error returned by [store::Handle::iter()][crate::store::Handle::iter()] and its iterators.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The packed-refs file could not be opened")]
+        PackedOpen(#[from] packed::buffer::open::Error),
+        #[error("The loose references could not be traversed")]
+        Io(#[from] std::io::Error),
+        #[error("An error occurred while iterating the loose file database")]
+        Loose(#[from] file::iter::loose_then_packed::Error),
+        #[error("An error occurred while iterating the reftable database")]
+        Reftable(#[from] reftable::iter::Error),
+    }
+}
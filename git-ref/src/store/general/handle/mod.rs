@@ -1,43 +1,149 @@
-#![allow(dead_code)]
-use crate::{store, Namespace};
+use std::path::Path;
 
-#[derive(Clone)]
+use crate::{
+    store::{self, StorageFormat, WriteReflog},
+    Namespace,
+};
+
+#[derive(Debug, Clone)]
 pub(crate) enum State {
     Loose { store: crate::file::Store },
+    Reftable { store: crate::reftable::Store },
 }
 
 impl crate::Store {
-    /// Return a new handle which sees all references if `namespace` is `None` or all read and write operations are limited
-    /// to the given `namespace` if `Some`.
+    /// Return a new handle which sees all references.
     pub fn to_handle(&self) -> store::Handle {
-        Self::new_handle_inner(&self.inner, None)
+        Self::new_handle_inner(&self.inner)
+    }
+
+    /// As above, but all read and write operations are limited to the given `namespace` if `Some`.
+    ///
+    /// Note that namespaces are only supported if references are stored as files.
+    pub fn to_handle_namespaced(&self, namespace: Option<Namespace>) -> Result<store::Handle, namespace::Error> {
+        let mut handle = self.to_handle();
+        handle.set_namespace(namespace)?;
+        Ok(handle)
     }
 
-    /// As above, but supports a namespace to be set
-    pub fn to_handle_namespaced(&self, namespace: Option<Namespace>) -> store::Handle {
-        Self::new_handle_inner(&self.inner, namespace)
+    /// Return the format in which references are stored.
+    pub fn storage_format(&self) -> StorageFormat {
+        match &self.inner {
+            store::State::Loose { .. } => StorageFormat::Files,
+            store::State::Reftable { .. } => StorageFormat::Reftable,
+        }
     }
 
-    fn new_handle_inner(state: &store::State, namespace: Option<Namespace>) -> store::Handle {
+    /// Return the `.git` directory the store is operating on.
+    pub fn git_dir(&self) -> &Path {
+        match &self.inner {
+            store::State::Loose { store } => store.git_dir(),
+            store::State::Reftable { store } => store.git_dir(),
+        }
+    }
+
+    fn new_handle_inner(state: &store::State) -> store::Handle {
         store::Handle {
             state: match state {
-                store::State::Loose { store } => store::handle::State::Loose {
+                store::State::Loose { store } => State::Loose {
                     store: {
                         let mut store = store.clone();
-                        store.namespace = namespace;
+                        store.namespace = None;
                         store
                     },
                 },
+                store::State::Reftable { store } => State::Reftable { store: store.clone() },
+            },
+        }
+    }
+}
+
+impl store::Handle {
+    /// Return the format in which references are stored.
+    pub fn storage_format(&self) -> StorageFormat {
+        match &self.state {
+            State::Loose { .. } => StorageFormat::Files,
+            State::Reftable { .. } => StorageFormat::Reftable,
+        }
+    }
+
+    /// Return the `.git` directory the store is operating on.
+    pub fn git_dir(&self) -> &Path {
+        match &self.state {
+            State::Loose { store } => store.git_dir(),
+            State::Reftable { store } => store.git_dir(),
+        }
+    }
+
+    /// Return the namespace all read and write operations are limited to, if set.
+    pub fn namespace(&self) -> Option<&Namespace> {
+        match &self.state {
+            State::Loose { store } => store.namespace.as_ref(),
+            State::Reftable { .. } => None,
+        }
+    }
+
+    /// Limit all read and write operations to `namespace`, or remove the limitation if `None`, and return the previous one.
+    ///
+    /// Namespaces are only supported if references are stored as files, and setting one fails otherwise.
+    pub fn set_namespace(&mut self, namespace: Option<Namespace>) -> Result<Option<Namespace>, namespace::Error> {
+        match &mut self.state {
+            State::Loose { store } => Ok(std::mem::replace(&mut store.namespace, namespace)),
+            State::Reftable { .. } => match namespace {
+                Some(_) => Err(namespace::Error::Unsupported),
+                None => Ok(None),
             },
         }
     }
+
+    /// Return the way reflogs are written when editing references.
+    pub fn write_reflog(&self) -> WriteReflog {
+        match &self.state {
+            State::Loose { store } => store.write_reflog,
+            State::Reftable { store } => store.write_reflog,
+        }
+    }
+
+    /// Set the way reflogs are written when editing references to `mode`.
+    pub fn set_write_reflog(&mut self, mode: WriteReflog) {
+        match &mut self.state {
+            State::Loose { store } => store.write_reflog = mode,
+            State::Reftable { store } => store.write_reflog = mode,
+        }
+    }
+
+    /// Return the loose reference store if references are stored as files, for access to functionality only it provides.
+    pub fn loose_store(&self) -> Option<&crate::file::Store> {
+        match &self.state {
+            State::Loose { store } => Some(store),
+            State::Reftable { .. } => None,
+        }
+    }
+}
+
+///
+pub mod namespace {
+    /// The error returned by [store::Handle::set_namespace()][crate::store::Handle::set_namespace()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Namespaces are not supported for references stored as reftables")]
+        Unsupported,
+    }
 }
 
 ///
 pub mod find;
 
-mod iter {
-    // impl store::Handle {
-    //     pub fn iter<'p, 's>(&'s self, packed: Option<&'p packed::Buffer>) -> std::io::Result<LooseThenPacked<'p, 's>> {
-    // }
-}
+///
+pub mod iter;
+
+///
+pub mod peel;
+
+///
+pub mod reflog;
+
+///
+pub mod transaction;
+pub use transaction::Transaction;
@@ -0,0 +1,67 @@
+use std::collections::BTreeSet;
+
+use git_hash::ObjectId;
+
+use crate::{file::ReferenceExt, peel, store, store::handle, Reference, Target};
+
+impl store::Handle {
+    /// Follow all symbolic targets of `reference` and peel the object it ultimately points to until a non-tag object is found,
+    /// using `find` to look up objects, and store the result in `reference`.
+    ///
+    /// This is the backend-agnostic version of [`ReferenceExt::peel_to_id_in_place()`].
+    pub fn peel_to_id_in_place<E: std::error::Error + Send + Sync + 'static>(
+        &self,
+        reference: &mut Reference,
+        find: impl FnMut(ObjectId, &mut Vec<u8>) -> Result<Option<(git_object::Kind, &[u8])>, E>,
+    ) -> Result<ObjectId, peel::to_id::Error> {
+        let store = match &self.state {
+            handle::State::Loose { store } => return reference.peel_to_id_in_place(store, find),
+            handle::State::Reftable { store } => store,
+        };
+        if let Some(peeled) = reference.peeled {
+            reference.target = Target::Peeled(peeled);
+            return Ok(peeled);
+        }
+        if reference.target.kind() == crate::Kind::Symbolic {
+            let mut seen = BTreeSet::new();
+            while let Some(next) = self.follow(reference) {
+                let next = next?;
+                if seen.contains(&next.name) {
+                    return Err(peel::to_id::Error::Cycle {
+                        start_absolute: store.git_dir().join(reference.name.to_path()),
+                    });
+                }
+                *reference = next;
+                seen.insert(reference.name.clone());
+                const MAX_REF_DEPTH: usize = 5;
+                if seen.len() == MAX_REF_DEPTH {
+                    return Err(peel::to_id::Error::DepthLimitExceeded {
+                        max_depth: MAX_REF_DEPTH,
+                    });
+                }
+            }
+            if let Some(peeled) = reference.peeled {
+                reference.target = Target::Peeled(peeled);
+                return Ok(peeled);
+            }
+        }
+        peel::objects_in_place(reference, find)
+    }
+
+    /// Follow the symbolic `reference` one level and return the reference it refers to.
+    ///
+    /// Returns `None` if this is not a symbolic reference, hence the leaf of the chain.
+    pub fn follow(&self, reference: &Reference) -> Option<Result<Reference, store::find::existing::Error>> {
+        match reference.peeled {
+            Some(peeled) => Some(Ok(Reference {
+                name: reference.name.clone(),
+                target: Target::Peeled(peeled),
+                peeled: None,
+            })),
+            None => match &reference.target {
+                Target::Peeled(_) => None,
+                Target::Symbolic(full_name) => Some(self.find(full_name.as_ref())),
+            },
+        }
+    }
+}
@@ -0,0 +1,129 @@
+pub use error::Error;
+
+use crate::{file, log, store, store::handle, FullNameRef};
+
+impl store::Handle {
+    /// Return all entries of the reflog of the reference with the given full `name`, with the oldest entry first,
+    /// or `None` if there is no reflog.
+    pub fn reflog(&self, name: &FullNameRef) -> Result<Option<Vec<log::Line>>, Error> {
+        match &self.state {
+            handle::State::Loose { store } => {
+                let mut buf = Vec::new();
+                match store.reflog_iter(name, &mut buf)? {
+                    Some(iter) => Ok(Some(
+                        iter.map(|line| line.map(|line| line.to_owned()))
+                            .collect::<Result<_, _>>()?,
+                    )),
+                    None => Ok(None),
+                }
+            }
+            handle::State::Reftable { store } => Ok(store.reflog(name)?),
+        }
+    }
+
+    /// Return true if a reflog exists for the reference with the given full `name`.
+    pub fn reflog_exists(&self, name: &FullNameRef) -> Result<bool, Error> {
+        match &self.state {
+            handle::State::Loose { store } => Ok(store.reflog_path(name).is_file()),
+            handle::State::Reftable { store } => Ok(store.reflog_exists(name)?),
+        }
+    }
+
+    /// Return a platform for obtaining forward or reverse iterators over the reflog of the reference with the given full `name`.
+    pub fn log_iter<'a>(&self, name: &'a FullNameRef) -> Platform<'a, '_> {
+        Platform {
+            store: self,
+            name,
+            buf: Vec::new(),
+        }
+    }
+}
+
+/// A platform to obtain iterators over the reflog of a reference, regardless of the backend that stores it.
+#[must_use = "Iterators should be obtained from this platform"]
+pub struct Platform<'a, 's> {
+    store: &'s store::Handle,
+    name: &'a FullNameRef,
+    buf: Vec<u8>,
+}
+
+/// An iterator over the lines of a reflog, regardless of the backend that stores it.
+pub struct Iter<'p> {
+    inner: IterInner<'p>,
+}
+
+enum IterInner<'p> {
+    LooseForward(file::log::iter::Forward<'p>),
+    LooseReverse(file::log::iter::Reverse<'p, std::fs::File>),
+    Reftable(std::vec::IntoIter<log::Line>),
+    ReftableReverse(std::iter::Rev<std::vec::IntoIter<log::Line>>),
+}
+
+impl<'p> Iterator for Iter<'p> {
+    type Item = Result<log::Line, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            IterInner::LooseForward(iter) => iter
+                .next()
+                .map(|res| res.map(|line| line.to_owned()).map_err(Into::into)),
+            IterInner::LooseReverse(iter) => iter.next().map(|res| res.map_err(Into::into)),
+            IterInner::Reftable(iter) => iter.next().map(Ok),
+            IterInner::ReftableReverse(iter) => iter.next().map(Ok),
+        }
+    }
+}
+
+impl<'a, 's> Platform<'a, 's> {
+    /// Return an iterator over all log-lines, most recent to oldest, or `None` if there is no reflog.
+    ///
+    /// For loose stores, this iterator is very expensive in terms of I/O operations and shouldn't be used to read more than
+    /// the last few entries of the log.
+    pub fn rev(&mut self) -> Result<Option<Iter<'_>>, Error> {
+        Ok(match &self.store.state {
+            handle::State::Loose { store } => {
+                self.buf.clear();
+                self.buf.resize(512, 0);
+                store.reflog_iter_rev(self.name, &mut self.buf)?.map(|iter| Iter {
+                    inner: IterInner::LooseReverse(iter),
+                })
+            }
+            handle::State::Reftable { store } => store.reflog(self.name)?.map(|lines| Iter {
+                inner: IterInner::ReftableReverse(lines.into_iter().rev()),
+            }),
+        })
+    }
+
+    /// Return an iterator over all log-lines, oldest to most recent, or `None` if there is no reflog.
+    pub fn all(&mut self) -> Result<Option<Iter<'_>>, Error> {
+        Ok(match &self.store.state {
+            handle::State::Loose { store } => {
+                self.buf.clear();
+                store.reflog_iter(self.name, &mut self.buf)?.map(|iter| Iter {
+                    inner: IterInner::LooseForward(iter),
+                })
+            }
+            handle::State::Reftable { store } => store.reflog(self.name)?.map(|lines| Iter {
+                inner: IterInner::Reftable(lines.into_iter()),
+            }),
+        })
+    }
+}
+
+mod error {
+    use crate::{file, reftable};
+
+    /// The error returned by [store::Handle::reflog()][crate::store::Handle::reflog()] and the reflog [`Platform`][super::Platform].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The reflog could not be read from the loose file database")]
+        Loose(#[from] file::log::Error),
+        #[error("A reflog line could not be decoded")]
+        LooseDecode(#[from] file::log::iter::decode::Error),
+        #[error("The reflog could not be read in reverse from the loose file database")]
+        LooseReverse(#[from] file::log::iter::reverse::Error),
+        #[error("The reflog could not be read from the reftable database")]
+        Reftable(#[from] reftable::reflog::Error),
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{
+    file::{self, transaction::PackedRefs},
+    reftable, store,
+    store::handle,
+    transaction::RefEdit,
+};
+
+/// A transaction on a [`Handle`][store::Handle], which performs its edits using the backend of the underlying store.
+pub struct Transaction<'s, 'p> {
+    inner: Inner<'s, 'p>,
+}
+
+enum Inner<'s, 'p> {
+    Loose(file::Transaction<'s, 'p>),
+    Reftable(reftable::Transaction<'s>),
+}
+
+impl store::Handle {
+    /// Open a transaction, which inherits the namespace of this handle for loose stores.
+    pub fn transaction(&self) -> Transaction<'_, '_> {
+        Transaction {
+            inner: match &self.state {
+                handle::State::Loose { store } => Inner::Loose(store.transaction()),
+                handle::State::Reftable { store } => Inner::Reftable(store.transaction()),
+            },
+        }
+    }
+}
+
+impl<'s, 'p> Transaction<'s, 'p> {
+    /// Configure the way packed refs are handled during the transaction.
+    ///
+    /// This is ignored by reftable stores, which don't have packed refs.
+    pub fn packed_refs(self, packed_refs: PackedRefs<'p>) -> Self {
+        Transaction {
+            inner: match self.inner {
+                Inner::Loose(t) => Inner::Loose(t.packed_refs(packed_refs)),
+                Inner::Reftable(t) => Inner::Reftable(t),
+            },
+        }
+    }
+
+    /// Prepare for calling [`commit(…)`][Transaction::commit()] in a way that can be rolled back perfectly.
+    ///
+    /// `lock_fail_mode` determines how to wait for the locks on loose refs for loose stores, or the lock on the list of tables
+    /// for reftable stores. `packed_refs_lock_fail_mode` determines how to wait for the lock on the packed-refs file, and is
+    /// ignored by reftable stores.
+    pub fn prepare(
+        self,
+        edits: impl IntoIterator<Item = RefEdit>,
+        lock_fail_mode: git_lock::acquire::Fail,
+        packed_refs_lock_fail_mode: git_lock::acquire::Fail,
+    ) -> Result<Self, prepare::Error> {
+        Ok(Transaction {
+            inner: match self.inner {
+                Inner::Loose(t) => Inner::Loose(t.prepare(edits, lock_fail_mode, packed_refs_lock_fail_mode)?),
+                Inner::Reftable(t) => Inner::Reftable(t.prepare(edits, lock_fail_mode)?),
+            },
+        })
+    }
+
+    /// Rollback all intermediate state and return the `RefEdits` as we know them thus far.
+    ///
+    /// Note that they have been altered compared to what was initially provided as they have
+    /// been split and know about their current state on disk.
+    pub fn rollback(self) -> Vec<RefEdit> {
+        match self.inner {
+            Inner::Loose(t) => t.rollback(),
+            Inner::Reftable(t) => t.rollback(),
+        }
+    }
+
+    /// Make all [prepared][Transaction::prepare()] edits permanent and return the performed edits which represent the
+    /// current state of the affected refs in the ref store in that instant.
+    /// `committer` is used in the reflog.
+    pub fn commit(self, committer: git_actor::SignatureRef<'_>) -> Result<Vec<RefEdit>, commit::Error> {
+        Ok(match self.inner {
+            Inner::Loose(t) => t.commit(committer)?,
+            Inner::Reftable(t) => t.commit(committer)?,
+        })
+    }
+}
+
+impl std::fmt::Debug for Transaction<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.inner {
+            Inner::Loose(t) => t.fmt(f),
+            Inner::Reftable(t) => t.fmt(f),
+        }
+    }
+}
+
+///
+pub mod prepare {
+    use crate::{file, reftable};
+
+    /// The error returned by [`Transaction::prepare()`][super::Transaction::prepare()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Loose(#[from] file::transaction::prepare::Error),
+        #[error(transparent)]
+        Reftable(#[from] reftable::transaction::prepare::Error),
+    }
+}
+
+///
+pub mod commit {
+    use crate::{file, reftable};
+
+    /// The error returned by [`Transaction::commit()`][super::Transaction::commit()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Loose(#[from] file::transaction::commit::Error),
+        #[error(transparent)]
+        Reftable(#[from] reftable::transaction::commit::Error),
+    }
+}
@@ -1,38 +1,84 @@
 use std::path::PathBuf;
 
-use crate::store::WriteReflog;
+use crate::store::{StorageFormat, WriteReflog};
 
 mod error {
-    /// The error returned by [crate::Store::at()].
+    /// The error returned by [crate::Store::at()] and [crate::Store::for_linked_worktree()].
     #[derive(Debug, thiserror::Error)]
     #[allow(missing_docs)]
     pub enum Error {
         #[error("There was an error accessing the store's directory")]
         Io(#[from] std::io::Error),
+        #[error("Linked worktrees are not supported for references stored as reftables")]
+        ReftableLinkedWorktree,
     }
 }
 
 pub use error::Error;
 
-use crate::file;
+use crate::{file, reftable};
 
-#[allow(dead_code)]
 impl crate::Store {
     /// Create a new store at the given location, typically the `.git/` directory.
     ///
-    /// `object_hash` defines the kind of hash to assume when dealing with refs.
+    /// `format` is the way references are stored, typically obtained from `extensions.refStorage`,
+    /// and `object_hash` defines the kind of hash to assume when dealing with refs.
     pub fn at(
         git_dir: impl Into<PathBuf>,
+        format: StorageFormat,
         reflog_mode: WriteReflog,
         object_hash: git_hash::Kind,
     ) -> Result<Self, Error> {
-        // for now, just try to read the directory - later we will do that naturally as we have to figure out if it's a ref-table or not.
         let git_dir = git_dir.into();
         std::fs::read_dir(&git_dir)?;
         Ok(crate::Store {
-            inner: crate::store::State::Loose {
-                store: file::Store::at(git_dir, reflog_mode, object_hash),
+            inner: match format {
+                StorageFormat::Files => crate::store::State::Loose {
+                    store: file::Store::at(git_dir, reflog_mode, object_hash),
+                },
+                StorageFormat::Reftable => crate::store::State::Reftable {
+                    store: reftable::Store::at(git_dir, reflog_mode, object_hash),
+                },
             },
         })
     }
+
+    /// Like [`at()`][crate::Store::at()], but for _linked_ work-trees which use `git_dir` as private ref store and `common_dir`
+    /// for shared references.
+    ///
+    /// Note that this is only supported if references are stored as files.
+    pub fn for_linked_worktree(
+        git_dir: impl Into<PathBuf>,
+        common_dir: impl Into<PathBuf>,
+        format: StorageFormat,
+        reflog_mode: WriteReflog,
+        object_hash: git_hash::Kind,
+    ) -> Result<Self, Error> {
+        let git_dir = git_dir.into();
+        std::fs::read_dir(&git_dir)?;
+        Ok(crate::Store {
+            inner: match format {
+                StorageFormat::Files => crate::store::State::Loose {
+                    store: file::Store::for_linked_worktree(git_dir, common_dir, reflog_mode, object_hash),
+                },
+                StorageFormat::Reftable => return Err(Error::ReftableLinkedWorktree),
+            },
+        })
+    }
+}
+
+impl From<file::Store> for crate::Store {
+    fn from(store: file::Store) -> Self {
+        crate::Store {
+            inner: crate::store::State::Loose { store },
+        }
+    }
+}
+
+impl From<reftable::Store> for crate::Store {
+    fn from(store: reftable::Store) -> Self {
+        crate::Store {
+            inner: crate::store::State::Reftable { store },
+        }
+    }
 }
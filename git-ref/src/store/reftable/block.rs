@@ -0,0 +1,319 @@
+//! Reading and writing of individual blocks and the prefix-compressed records within them.
+use std::borrow::Cow;
+
+use crate::store_impl::reftable::decode::Error;
+
+pub(crate) const REF: u8 = b'r';
+pub(crate) const LOG: u8 = b'g';
+pub(crate) const OBJ: u8 = b'o';
+pub(crate) const INDEX: u8 = b'i';
+
+/// The size of a block header, consisting of the block type and a 24 bit length.
+pub(crate) const HEADER_LEN: usize = 4;
+
+pub(crate) mod varint {
+    /// Append `value` to `out` in the variable-length encoding used by reftables, which differs from the one used by LEB128.
+    pub(crate) fn put(out: &mut Vec<u8>, mut value: u64) {
+        let mut buf = [0u8; 10];
+        let mut pos = buf.len() - 1;
+        buf[pos] = (value & 0x7f) as u8;
+        value >>= 7;
+        while value != 0 {
+            value -= 1;
+            pos -= 1;
+            buf[pos] = 0x80 | (value & 0x7f) as u8;
+            value >>= 7;
+        }
+        out.extend_from_slice(&buf[pos..]);
+    }
+
+    /// Decode a variable-length integer at the beginning of `data` and return it along with the amount of bytes consumed.
+    pub(crate) fn get(data: &[u8]) -> Option<(u64, usize)> {
+        let mut bytes = data.iter();
+        let mut byte = *bytes.next()?;
+        let mut value = u64::from(byte & 0x7f);
+        let mut consumed = 1;
+        while byte & 0x80 != 0 {
+            byte = *bytes.next()?;
+            consumed += 1;
+            value = value.checked_add(1)?.checked_mul(128)? | u64::from(byte & 0x7f);
+        }
+        Some((value, consumed))
+    }
+}
+
+pub(crate) fn be24(data: &[u8]) -> usize {
+    (usize::from(data[0]) << 16) | (usize::from(data[1]) << 8) | usize::from(data[2])
+}
+
+pub(crate) fn put_be24(out: &mut [u8], value: usize) {
+    out[0] = (value >> 16) as u8;
+    out[1] = (value >> 8) as u8;
+    out[2] = value as u8;
+}
+
+/// A block as it is stored in a table, with log blocks being inflated.
+pub(crate) struct Block<'a> {
+    /// All bytes of the block from its beginning, including the file header in the first block, up to the end of the restart table.
+    data: Cow<'a, [u8]>,
+    /// The offset at which the first record starts.
+    records_start: usize,
+    /// The offset at which the restart table starts, which is where the records end.
+    restarts_start: usize,
+    /// The amount of bytes the block occupies in the file, including padding or its compressed size.
+    pub len_in_file: usize,
+}
+
+impl<'a> Block<'a> {
+    /// Decode the block at `start` within `file`, whose block header starts at `start + header_off`.
+    ///
+    /// Uncompressed blocks are assumed to be padded to `block_size` unless they are followed by another block right away,
+    /// and the section they are in ends at `section_end`.
+    pub(crate) fn at(
+        file: &'a [u8],
+        start: usize,
+        header_off: usize,
+        block_size: usize,
+        section_end: usize,
+    ) -> Result<Self, Error> {
+        let header = file
+            .get(start + header_off..start + header_off + HEADER_LEN)
+            .ok_or(Error::Corrupt {
+                message: "truncated block header",
+            })?;
+        let kind = header[0];
+        let len = be24(&header[1..]);
+        let records_start = header_off + HEADER_LEN;
+        if len < records_start + 2 {
+            return Err(Error::Corrupt {
+                message: "block is too small",
+            });
+        }
+        let (data, len_in_file) = if kind == LOG {
+            let mut data = vec![0; len];
+            let mut inflate = git_features::zlib::Inflate::default();
+            let (status, consumed_in, written_out) = inflate
+                .once(
+                    &file[start + records_start..section_end.max(start + records_start)],
+                    &mut data[records_start..],
+                )
+                .map_err(Error::Inflate)?;
+            if status != git_features::zlib::Status::StreamEnd || written_out != len - records_start {
+                return Err(Error::Corrupt {
+                    message: "log block could not be inflated entirely",
+                });
+            }
+            (Cow::Owned(data), records_start + consumed_in)
+        } else {
+            let data = file.get(start..start + len).ok_or(Error::Corrupt {
+                message: "block exceeds the table",
+            })?;
+            let is_padded = start + len < section_end && file[start + len] == 0;
+            (Cow::Borrowed(data), if is_padded { block_size.max(len) } else { len })
+        };
+        if !matches!(kind, REF | LOG | OBJ | INDEX) {
+            return Err(Error::Corrupt {
+                message: "unknown block type",
+            });
+        }
+        let num_restarts = usize::from(u16::from_be_bytes([data[len - 2], data[len - 1]]));
+        let restarts_start = (len - 2)
+            .checked_sub(num_restarts * 3)
+            .filter(|pos| *pos >= records_start)
+            .ok_or(Error::Corrupt {
+                message: "restart table exceeds block",
+            })?;
+        Ok(Block {
+            data,
+            records_start,
+            restarts_start,
+            len_in_file,
+        })
+    }
+
+    /// Iterate all records in this block.
+    pub(crate) fn records(&self) -> Records<'_> {
+        Records {
+            data: &self.data[..self.restarts_start],
+            pos: self.records_start,
+            key: Vec::new(),
+        }
+    }
+
+    /// Iterate the records of this block starting at the last restart point whose key is smaller than or equal to `key`.
+    pub(crate) fn records_near(&self, key: &[u8]) -> Result<Records<'_>, Error> {
+        let restarts = &self.data[self.restarts_start..self.data.len() - 2];
+        let num_restarts = restarts.len() / 3;
+        let records = &self.data[..self.restarts_start];
+        let restart_key = |idx: usize| restart_key(records, be24(&restarts[idx * 3..]));
+        let (mut lo, mut hi) = (0, num_restarts);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if restart_key(mid)? <= key {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        let pos = match lo {
+            0 => self.records_start,
+            idx => be24(&restarts[(idx - 1) * 3..]),
+        };
+        Ok(Records {
+            data: &self.data[..self.restarts_start],
+            pos,
+            key: Vec::new(),
+        })
+    }
+}
+
+/// An iterator-like type over the records of a block.
+pub(crate) struct Records<'a> {
+    data: &'a [u8],
+    pos: usize,
+    key: Vec<u8>,
+}
+
+/// Return the key of the record at the restart point `pos` in `data`, which is stored without prefix.
+fn restart_key(data: &[u8], pos: usize) -> Result<&[u8], Error> {
+    let corrupt = || Error::Corrupt {
+        message: "malformed restart point",
+    };
+    let (prefix_len, consumed) = data.get(pos..).and_then(varint::get).ok_or_else(corrupt)?;
+    if prefix_len != 0 {
+        return Err(corrupt());
+    }
+    let pos = pos + consumed;
+    let (suffix_and_type, consumed) = varint::get(&data[pos..]).ok_or_else(corrupt)?;
+    let pos = pos + consumed;
+    data.get(pos..pos + (suffix_and_type >> 3) as usize).ok_or_else(corrupt)
+}
+
+impl<'a> Records<'a> {
+    /// Decode the key of the next record and return its type along with the position of its value, or `None` if there is no more record.
+    fn advance(&mut self) -> Result<Option<(u8, usize)>, Error> {
+        if self.pos >= self.data.len() {
+            return Ok(None);
+        }
+        let corrupt = || Error::Corrupt {
+            message: "malformed record key",
+        };
+        let data = &self.data[self.pos..];
+        let (prefix_len, a) = varint::get(data).ok_or_else(corrupt)?;
+        let (suffix_and_type, b) = varint::get(&data[a..]).ok_or_else(corrupt)?;
+        let prefix_len = prefix_len as usize;
+        let suffix_len = (suffix_and_type >> 3) as usize;
+        if prefix_len > self.key.len() {
+            return Err(corrupt());
+        }
+        let suffix = data.get(a + b..a + b + suffix_len).ok_or_else(corrupt)?;
+        self.key.truncate(prefix_len);
+        self.key.extend_from_slice(suffix);
+        self.pos += a + b + suffix_len;
+        Ok(Some(((suffix_and_type & 0x7) as u8, self.pos)))
+    }
+
+    /// Decode the next record with `decode(key, value_type, value)`, which returns the decoded record along with the amount of
+    /// bytes its value occupies, or return `None` if there is no more record.
+    pub(crate) fn next_record<T>(
+        &mut self,
+        decode: impl FnOnce(&[u8], u8, &[u8]) -> Result<(T, usize), Error>,
+    ) -> Result<Option<T>, Error> {
+        let (value_type, value_start) = match self.advance()? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let (record, value_len) = decode(&self.key, value_type, &self.data[value_start..])?;
+        self.pos = value_start + value_len;
+        Ok(Some(record))
+    }
+}
+
+/// Assembles records into a block while keeping track of restart points.
+pub(crate) struct Writer {
+    kind: u8,
+    buf: Vec<u8>,
+    block_size: usize,
+    restart_interval: usize,
+    restarts: Vec<usize>,
+    last_key: Vec<u8>,
+    num_records: usize,
+}
+
+impl Writer {
+    /// Create a new block of `kind` whose data is prefixed with `header`, which is the file header for the first block in a table.
+    pub(crate) fn new(kind: u8, header: &[u8], block_size: usize, restart_interval: usize) -> Self {
+        let mut buf = Vec::with_capacity(block_size);
+        buf.extend_from_slice(header);
+        buf.extend_from_slice(&[kind, 0, 0, 0]);
+        Writer {
+            kind,
+            buf,
+            block_size,
+            restart_interval: restart_interval.max(1),
+            restarts: Vec::new(),
+            last_key: Vec::new(),
+            num_records: 0,
+        }
+    }
+
+    /// Return true if there is no record in this block yet.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.num_records == 0
+    }
+
+    /// Add a record with `key`, `value_type` and the encoded `value`, or return false if it doesn't fit into the block anymore.
+    pub(crate) fn add(&mut self, key: &[u8], value_type: u8, value: &[u8]) -> bool {
+        let is_forced_restart = self.num_records % self.restart_interval == 0;
+        let prefix_len = if is_forced_restart {
+            0
+        } else {
+            self.last_key.iter().zip(key).take_while(|(a, b)| a == b).count()
+        };
+        let mut record = Vec::with_capacity(key.len() - prefix_len + value.len() + 8);
+        varint::put(&mut record, prefix_len as u64);
+        varint::put(
+            &mut record,
+            (((key.len() - prefix_len) as u64) << 3) | u64::from(value_type),
+        );
+        record.extend_from_slice(&key[prefix_len..]);
+        record.extend_from_slice(value);
+
+        let is_restart = prefix_len == 0;
+        let num_restarts = self.restarts.len() + usize::from(is_restart);
+        if self.buf.len() + record.len() + num_restarts * 3 + 2 > self.block_size {
+            return false;
+        }
+        if is_restart {
+            self.restarts.push(self.buf.len());
+        }
+        self.buf.extend_from_slice(&record);
+        self.last_key.clear();
+        self.last_key.extend_from_slice(key);
+        self.num_records += 1;
+        true
+    }
+
+    /// Finish the block by writing the restart table and return its bytes as they should be written, with log blocks being
+    /// compressed. `header_len` is the length of the header passed upon creation.
+    pub(crate) fn finish(mut self, header_len: usize) -> std::io::Result<Vec<u8>> {
+        for restart in &self.restarts {
+            let mut pos = [0u8; 3];
+            put_be24(&mut pos, *restart);
+            self.buf.extend_from_slice(&pos);
+        }
+        self.buf.extend_from_slice(&(self.restarts.len() as u16).to_be_bytes());
+        let len = self.buf.len();
+        put_be24(&mut self.buf[header_len + 1..], len);
+        if self.kind != LOG {
+            return Ok(self.buf);
+        }
+
+        use std::io::Write;
+        let records_start = header_len + HEADER_LEN;
+        let mut out = git_features::zlib::stream::deflate::Write::new(self.buf[..records_start].to_vec());
+        out.write_all(&self.buf[records_start..])?;
+        out.flush()?;
+        Ok(out.into_inner())
+    }
+}
@@ -0,0 +1,104 @@
+pub use error::Error;
+
+use crate::store_impl::reftable::{self, stack, transaction::commit_tables_list, Stack};
+
+impl reftable::Store {
+    /// Merge all tables into a single one, which drops all deletions along with the references and log entries they shadow.
+    /// `lock_fail_mode` determines how to wait for the lock on the list of tables.
+    ///
+    /// Returns `false` if there was nothing to compact as there is no more than one table.
+    pub fn compact(&self, lock_fail_mode: git_lock::acquire::Fail) -> Result<bool, Error> {
+        self.compact_inner(lock_fail_mode, |sizes| (sizes.len() > 1).then(|| 0))
+    }
+
+    /// Merge the newest tables so that each table is at least twice as large as all newer tables combined, which keeps
+    /// the amount of tables logarithmic in the amount of transactions.
+    ///
+    /// Returns `false` if the stack didn't need to be compacted or if another process holds the lock on the list of tables.
+    pub(crate) fn auto_compact(&self) -> Result<bool, Error> {
+        match self.compact_inner(git_lock::acquire::Fail::Immediately, geometric_segment_start) {
+            Err(Error::LockAcquire(_)) => Ok(false),
+            res => res,
+        }
+    }
+
+    /// Compact all tables starting at the index returned by `segment_start(table_sizes)`, if it returns `Some`.
+    fn compact_inner(
+        &self,
+        lock_fail_mode: git_lock::acquire::Fail,
+        segment_start: impl FnOnce(&[usize]) -> Option<usize>,
+    ) -> Result<bool, Error> {
+        let dir = self.reftable_dir();
+        let tables_list = dir.join(stack::TABLES_LIST);
+        if !tables_list.is_file() {
+            return Ok(false);
+        }
+        let lock = git_lock::File::acquire_to_update_resource(&tables_list, lock_fail_mode, None)?;
+        let stack = Stack::at(&dir)?;
+        let tables = stack.tables();
+        let sizes: Vec<_> = tables.iter().map(|t| t.data.len()).collect();
+        let start = match segment_start(&sizes) {
+            Some(start) => start,
+            None => return Ok(false),
+        };
+
+        let segment = &tables[start..];
+        let keep_deletions = start != 0;
+        let refs = stack::merged_refs(segment, b"", keep_deletions)?;
+        let logs = stack::merged_logs(segment, keep_deletions)?;
+        let table = self.write_table(
+            &dir,
+            refs,
+            logs,
+            segment[0].min_update_index(),
+            segment[segment.len() - 1].max_update_index(),
+        )?;
+        let names = stack.table_names();
+        commit_tables_list(
+            lock,
+            names[..start]
+                .iter()
+                .map(|name| name.to_string_lossy().into_owned())
+                .chain(Some(table.clone())),
+        )?;
+        for name in &names[start..] {
+            if name.to_string_lossy() != table {
+                std::fs::remove_file(dir.join(name)).ok();
+            }
+        }
+        self.force_refresh_stack().ok();
+        Ok(true)
+    }
+}
+
+/// Return the index of the first table to compact so that each remaining table is at least twice as large as all newer tables
+/// combined, given the `sizes` of all tables with the oldest first.
+fn geometric_segment_start(sizes: &[usize]) -> Option<usize> {
+    let mut start = sizes.len().checked_sub(1)?;
+    let mut newer_size = sizes[start];
+    while start > 0 && sizes[start - 1] < 2 * newer_size {
+        start -= 1;
+        newer_size += sizes[start];
+    }
+    (start + 1 < sizes.len()).then(|| start)
+}
+
+mod error {
+    use crate::store_impl::reftable::{decode, stack, write};
+
+    /// The error returned by [reftable::Store::compact()][crate::reftable::Store::compact()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The lock for the list of tables could not be obtained")]
+        LockAcquire(#[from] git_lock::acquire::Error),
+        #[error("The tables could not be loaded")]
+        LoadStack(#[from] stack::load::Error),
+        #[error("A table could not be read")]
+        Decode(#[from] decode::Error),
+        #[error("The compacted table could not be written")]
+        WriteTable(#[from] write::Error),
+        #[error("The list of tables could not be updated")]
+        Io(#[from] std::io::Error),
+    }
+}
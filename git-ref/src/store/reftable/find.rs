@@ -0,0 +1,136 @@
+use std::convert::TryInto;
+
+pub use error::Error;
+
+use crate::{
+    bstr::BString,
+    store_impl::reftable::{self, Stack},
+    PartialNameRef, Reference,
+};
+
+impl reftable::Store {
+    /// Find a single reference by the given `path` which is required to be a valid reference name.
+    ///
+    /// Returns `Ok(None)` if no such ref exists.
+    ///
+    /// ### Note
+    ///
+    /// * The lookup algorithm follows the one in [the git documentation][git-lookup-docs].
+    /// * The list of tables is checked for modifications each time the method is called.
+    ///
+    /// [git-lookup-docs]: https://github.com/git/git/blob/5d5b1473453400224ebb126bf3947e0a3276bdf5/Documentation/revisions.txt#L34-L46
+    pub fn try_find<'a, Name, E>(&self, partial: Name) -> Result<Option<Reference>, Error>
+    where
+        Name: TryInto<&'a PartialNameRef, Error = E>,
+        Error: From<E>,
+    {
+        let partial = partial.try_into()?;
+        match self.stack()? {
+            Some(stack) => find_in_stack(&stack, partial),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Find `partial_name` in `stack` using the same rules as git.
+pub(crate) fn find_in_stack(stack: &Stack, partial_name: &PartialNameRef) -> Result<Option<Reference>, Error> {
+    let mut buf = BString::default();
+    let mut find = |inbetween: &str, partial_name: &PartialNameRef, add_refs_prefix: bool| {
+        let full_name = partial_name.construct_full_name_ref(add_refs_prefix, inbetween, &mut buf);
+        stack
+            .find_ref(full_name.as_bstr())
+            .map(|record| record.and_then(|record| record.into_reference()))
+    };
+    if partial_name.looks_like_full_name() {
+        if let Some(r) = find("", partial_name, false)? {
+            return Ok(Some(r));
+        }
+    }
+    for inbetween in &["", "tags", "heads", "remotes"] {
+        if let Some(r) = find(inbetween, partial_name, true)? {
+            return Ok(Some(r));
+        }
+    }
+    find(
+        "remotes",
+        partial_name
+            .to_owned()
+            .join("HEAD")
+            .expect("HEAD is valid name")
+            .as_ref(),
+        true,
+    )
+    .map_err(Into::into)
+}
+
+mod error {
+    use std::convert::Infallible;
+
+    use crate::store_impl::reftable::{decode, stack};
+
+    /// The error returned by [reftable::Store::try_find()][crate::reftable::Store::try_find()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The ref name or path is not a valid ref name")]
+        RefnameValidation(#[from] crate::name::Error),
+        #[error("The tables could not be loaded")]
+        LoadStack(#[from] stack::load::Error),
+        #[error("A table could not be read")]
+        Decode(#[from] decode::Error),
+    }
+
+    impl From<Infallible> for Error {
+        fn from(_: Infallible) -> Self {
+            unreachable!("this impl is needed to allow passing a known valid partial path as parameter")
+        }
+    }
+}
+
+///
+pub mod existing {
+    use std::convert::TryInto;
+
+    pub use error::Error;
+
+    use crate::{
+        store_impl::reftable::{self, find},
+        PartialNameRef, Reference,
+    };
+
+    impl reftable::Store {
+        /// Similar to [`reftable::Store::try_find()`] but a non-existing ref is treated as error.
+        pub fn find<'a, Name, E>(&self, partial: Name) -> Result<Reference, Error>
+        where
+            Name: TryInto<&'a PartialNameRef, Error = E>,
+            crate::name::Error: From<E>,
+        {
+            let path = partial
+                .try_into()
+                .map_err(|err| Error::Find(find::Error::RefnameValidation(err.into())))?;
+            match self.try_find(path) {
+                Ok(Some(r)) => Ok(r),
+                Ok(None) => Err(Error::NotFound {
+                    name: path.to_partial_path().to_owned(),
+                }),
+                Err(err) => Err(err.into()),
+            }
+        }
+    }
+
+    mod error {
+        use std::path::PathBuf;
+
+        use crate::store_impl::reftable::find;
+
+        /// The error returned by [reftable::Store::find()][crate::reftable::Store::find()].
+        #[derive(Debug, thiserror::Error)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            #[error("An error occurred while trying to find a reference")]
+            Find(#[from] find::Error),
+            #[error("The ref partially named {name:?} could not be found")]
+            NotFound { name: PathBuf },
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    store::WriteReflog,
+    store_impl::reftable::{self, stack, SharedStackSnapshot, Stack},
+};
+
+impl reftable::Store {
+    /// Create a new instance at the given `git_dir`, which contains the `reftable` directory.
+    ///
+    /// `object_hash` defines the kind of hash to use when writing tables.
+    pub fn at(git_dir: impl Into<PathBuf>, write_reflog: WriteReflog, object_hash: git_hash::Kind) -> Self {
+        reftable::Store {
+            git_dir: git_dir.into(),
+            object_hash,
+            write_reflog,
+            write_options: Default::default(),
+            stack: Default::default(),
+        }
+    }
+
+    /// Return the `.git` directory containing the `reftable` directory.
+    pub fn git_dir(&self) -> &Path {
+        &self.git_dir
+    }
+
+    /// Return the directory containing all tables and their list.
+    pub fn reftable_dir(&self) -> PathBuf {
+        self.git_dir.join("reftable")
+    }
+
+    /// The kind of hash to use when writing tables.
+    pub fn object_hash(&self) -> git_hash::Kind {
+        self.object_hash
+    }
+
+    /// Return a possibly cached snapshot of all tables, or `None` if there is no list of tables yet.
+    ///
+    /// The list of tables is checked for modifications each time it's called.
+    pub fn stack(&self) -> Result<Option<SharedStackSnapshot>, stack::load::Error> {
+        let dir = self.reftable_dir();
+        let tables_list = dir.join(stack::TABLES_LIST);
+        self.stack.recent_snapshot(
+            || tables_list.metadata().and_then(|m| m.modified()).ok(),
+            || Stack::at(&dir).map(Some),
+        )
+    }
+
+    pub(crate) fn force_refresh_stack(&self) -> Result<(), stack::load::Error> {
+        let dir = self.reftable_dir();
+        self.stack.force_refresh(|| {
+            let modified = match dir.join(stack::TABLES_LIST).metadata() {
+                Ok(metadata) => metadata.modified()?,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+            Stack::at(&dir).map(|stack| Some((modified, stack)))
+        })
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::Path;
+
+pub use error::Error;
+
+use crate::{
+    store_impl::reftable::{self, record},
+    Reference,
+};
+
+/// An iterator over references in a reftable store, sorted by name.
+pub struct Iter {
+    inner: std::vec::IntoIter<record::Ref>,
+}
+
+impl Iterator for Iter {
+    type Item = Reference;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.by_ref().find_map(record::Ref::into_reference)
+    }
+}
+
+impl reftable::Store {
+    /// Return an iterator over all references in `refs/`, sorted by name.
+    ///
+    /// The iterator operates on a snapshot of all tables taken when it's created.
+    pub fn iter(&self) -> Result<Iter, Error> {
+        self.iter_prefixed("refs/")
+    }
+
+    /// Return an iterator over all references whose full name starts with `prefix`, like `refs/heads/`, sorted by name.
+    ///
+    /// Like in loose stores, a `prefix` naming a directory of references like `refs/heads` is treated as `refs/heads/`,
+    /// so `refs/heads-other` won't be returned.
+    pub fn iter_prefixed(&self, prefix: impl AsRef<Path>) -> Result<Iter, Error> {
+        let prefix = git_path::into_bstr(prefix.as_ref());
+        let prefix = git_path::to_unix_separators_on_windows(prefix);
+        let keep_deletions = false;
+        let refs = match self.stack()? {
+            Some(stack) => {
+                let mut refs = Vec::new();
+                if !prefix.is_empty() && !prefix.ends_with(b"/") {
+                    let mut directory = prefix.clone().into_owned();
+                    directory.push(b'/');
+                    refs = stack.refs_with_prefix(directory.as_ref(), keep_deletions)?;
+                }
+                if refs.is_empty() {
+                    refs = stack.refs_with_prefix(prefix.as_ref(), keep_deletions)?;
+                }
+                refs
+            }
+            None => Vec::new(),
+        };
+        Ok(Iter {
+            inner: refs.into_iter(),
+        })
+    }
+}
+
+mod error {
+    use crate::store_impl::reftable::{decode, stack};
+
+    /// The error returned by [reftable::Store::iter()][crate::reftable::Store::iter()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The tables could not be loaded")]
+        LoadStack(#[from] stack::load::Error),
+        #[error("A table could not be read")]
+        Decode(#[from] decode::Error),
+    }
+}
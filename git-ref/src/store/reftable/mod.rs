@@ -0,0 +1,96 @@
+//! A store for references in the [reftable format][format], as used by git repositories with `extensions.refStorage = reftable`.
+//!
+//! References and their logs are kept in a stack of immutable tables in the `reftable/` directory, whose order is defined
+//! by `reftable/tables.list`. Each transaction adds a new table on top of the stack, and tables are compacted geometrically
+//! to keep the stack small.
+//!
+//! [format]: https://git-scm.com/docs/reftable
+use std::{ops::Range, path::PathBuf};
+
+use git_features::threading::OwnShared;
+
+use crate::store::WriteReflog;
+
+/// A store for references in the reftable format.
+#[derive(Debug, Clone)]
+pub struct Store {
+    /// The `.git` directory containing the `reftable/` directory.
+    git_dir: PathBuf,
+    /// The kind of hash to use when writing tables.
+    object_hash: git_hash::Kind,
+    /// The way to handle reflog edits
+    pub write_reflog: WriteReflog,
+    /// The way newly written tables are laid out.
+    pub write_options: write::Options,
+    /// The stack as it was last read from disk, shared among clones of this store.
+    stack: OwnShared<git_features::fs::MutableSnapshot<Stack>>,
+}
+
+/// A single immutable table containing references and their logs.
+pub struct Table {
+    path: PathBuf,
+    data: Vec<u8>,
+    header_len: usize,
+    block_size: usize,
+    min_update_index: u64,
+    max_update_index: u64,
+    object_hash: git_hash::Kind,
+    /// The offsets of all reference blocks along with the key of their first record, for binary searches.
+    ref_blocks: Vec<(usize, Vec<u8>)>,
+    /// The section containing log blocks, if there is one.
+    logs: Option<Range<usize>>,
+}
+
+/// The tables of a store in the order they are listed in `tables.list`, with the oldest table first.
+#[derive(Debug, Default)]
+pub struct Stack {
+    tables: Vec<Table>,
+}
+
+/// An up-to-date snapshot of the stack of tables.
+pub type SharedStackSnapshot = git_features::fs::SharedSnapshot<Stack>;
+
+mod block;
+///
+pub mod record;
+mod table;
+///
+pub mod write;
+
+///
+pub mod decode {
+    /// The error returned when decoding a [`Table`][super::Table].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The table could not be read")]
+        Io(#[from] std::io::Error),
+        #[error("The table is corrupt: {message}")]
+        Corrupt { message: &'static str },
+        #[error("Reftable version {version} is not supported")]
+        UnsupportedVersion { version: u8 },
+        #[error("A log block could not be decompressed")]
+        Inflate(#[source] git_features::zlib::inflate::Error),
+    }
+}
+
+///
+pub mod stack;
+
+mod init;
+
+///
+pub mod find;
+
+///
+pub mod iter;
+
+///
+pub mod reflog;
+
+///
+pub mod transaction;
+pub use transaction::Transaction;
+
+///
+pub mod compact;
@@ -0,0 +1,270 @@
+use std::convert::TryFrom;
+
+use git_hash::ObjectId;
+use git_object::bstr::{BString, ByteSlice};
+
+use crate::{
+    log,
+    store_impl::reftable::{block::varint, decode::Error},
+    FullName, Reference, Target,
+};
+
+/// The value of a reference as stored in a reftable.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub enum Value {
+    /// The reference was deleted, shadowing its values in older tables.
+    Deletion,
+    /// The reference points to an object.
+    Peeled {
+        /// The object the reference points to.
+        target: ObjectId,
+        /// The object `target` peels to if it is an annotated tag.
+        peeled: Option<ObjectId>,
+    },
+    /// The reference points to another reference.
+    Symbolic(FullName),
+}
+
+/// A reference record as stored in a reftable.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Ref {
+    /// The full name of the reference.
+    pub name: FullName,
+    /// The index of the update which produced this record.
+    pub update_index: u64,
+    /// The value of the reference.
+    pub value: Value,
+}
+
+impl Ref {
+    /// Convert this record into a reference, or return `None` if it is a deletion.
+    pub fn into_reference(self) -> Option<Reference> {
+        let (target, peeled) = match self.value {
+            Value::Deletion => return None,
+            Value::Peeled { target, peeled } => (Target::Peeled(target), peeled),
+            Value::Symbolic(name) => (Target::Symbolic(name), None),
+        };
+        Some(Reference {
+            name: self.name,
+            target,
+            peeled,
+        })
+    }
+}
+
+/// A reflog record as stored in a reftable.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Log {
+    /// The full name of the reference the log entry belongs to.
+    pub name: FullName,
+    /// The index of the update which produced this log entry, which orders the entries of a reference.
+    pub update_index: u64,
+    /// The log entry itself, or `None` if it was deleted to shadow the entry of the same update index in older tables.
+    pub line: Option<log::Line>,
+}
+
+impl Log {
+    /// Return the key under which this record is stored, which sorts the most recent entries of a reference first.
+    pub(crate) fn key(&self) -> Vec<u8> {
+        log_key(self.name.as_bstr(), self.update_index)
+    }
+}
+
+pub(crate) fn log_key(name: &[u8], update_index: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(name.len() + 9);
+    key.extend_from_slice(name);
+    key.push(0);
+    key.extend_from_slice(&(u64::MAX - update_index).to_be_bytes());
+    key
+}
+
+const REF_DELETION: u8 = 0;
+const REF_VALUE: u8 = 1;
+const REF_VALUE_AND_PEELED: u8 = 2;
+const REF_SYMBOLIC: u8 = 3;
+
+const LOG_DELETION: u8 = 0;
+const LOG_UPDATE: u8 = 1;
+
+fn corrupt(message: &'static str) -> Error {
+    Error::Corrupt { message }
+}
+
+fn name(key: &[u8]) -> Result<FullName, Error> {
+    FullName::try_from(key.as_bstr()).map_err(|_| corrupt("invalid reference name"))
+}
+
+/// A cursor to decode record values with.
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn varint(&mut self) -> Result<u64, Error> {
+        let (value, consumed) = varint::get(&self.data[self.pos..]).ok_or_else(|| corrupt("malformed varint"))?;
+        self.pos += consumed;
+        Ok(value)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| corrupt("record value exceeds block"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn string(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.varint()? as usize;
+        self.bytes(len)
+    }
+
+    fn id(&mut self, object_hash: git_hash::Kind) -> Result<ObjectId, Error> {
+        self.bytes(object_hash.len_in_bytes()).map(ObjectId::from)
+    }
+}
+
+pub(crate) fn decode_ref(
+    key: &[u8],
+    value_type: u8,
+    value: &[u8],
+    min_update_index: u64,
+    object_hash: git_hash::Kind,
+) -> Result<(Ref, usize), Error> {
+    let mut cursor = Cursor { data: value, pos: 0 };
+    let update_index = min_update_index
+        .checked_add(cursor.varint()?)
+        .ok_or_else(|| corrupt("update index overflow"))?;
+    let value = match value_type {
+        REF_DELETION => Value::Deletion,
+        REF_VALUE => Value::Peeled {
+            target: cursor.id(object_hash)?,
+            peeled: None,
+        },
+        REF_VALUE_AND_PEELED => Value::Peeled {
+            target: cursor.id(object_hash)?,
+            peeled: Some(cursor.id(object_hash)?),
+        },
+        REF_SYMBOLIC => Value::Symbolic(name(cursor.string()?)?),
+        _ => return Err(corrupt("unknown reference value type")),
+    };
+    Ok((
+        Ref {
+            name: name(key)?,
+            update_index,
+            value,
+        },
+        cursor.pos,
+    ))
+}
+
+/// Encode the value of `record` into `out` and return its value type.
+pub(crate) fn encode_ref(record: &Ref, min_update_index: u64, out: &mut Vec<u8>) -> u8 {
+    varint::put(out, record.update_index - min_update_index);
+    match &record.value {
+        Value::Deletion => REF_DELETION,
+        Value::Peeled { target, peeled: None } => {
+            out.extend_from_slice(target.as_bytes());
+            REF_VALUE
+        }
+        Value::Peeled {
+            target,
+            peeled: Some(peeled),
+        } => {
+            out.extend_from_slice(target.as_bytes());
+            out.extend_from_slice(peeled.as_bytes());
+            REF_VALUE_AND_PEELED
+        }
+        Value::Symbolic(name) => {
+            varint::put(out, name.as_bstr().len() as u64);
+            out.extend_from_slice(name.as_bstr());
+            REF_SYMBOLIC
+        }
+    }
+}
+
+pub(crate) fn decode_log(
+    key: &[u8],
+    value_type: u8,
+    value: &[u8],
+    object_hash: git_hash::Kind,
+) -> Result<(Log, usize), Error> {
+    let (name_bytes, inverted_update_index) = match key.len().checked_sub(9) {
+        Some(name_len) if key[name_len] == 0 => (&key[..name_len], &key[name_len + 1..]),
+        _ => return Err(corrupt("malformed log key")),
+    };
+    let update_index = u64::MAX - u64::from_be_bytes(inverted_update_index.try_into().expect("8 bytes"));
+    let mut cursor = Cursor { data: value, pos: 0 };
+    let line = match value_type {
+        LOG_DELETION => None,
+        LOG_UPDATE => {
+            let previous_oid = cursor.id(object_hash)?;
+            let new_oid = cursor.id(object_hash)?;
+            let name = cursor.string()?.into();
+            let email = cursor.string()?.into();
+            let seconds = cursor.varint()?;
+            let offset_in_minutes = i16::from_be_bytes(cursor.bytes(2)?.try_into().expect("2 bytes"));
+            let mut message = cursor.string()?;
+            if let Some(stripped) = message.strip_suffix(b"\n") {
+                message = stripped;
+            }
+            Some(log::Line {
+                previous_oid,
+                new_oid,
+                signature: git_actor::Signature {
+                    name,
+                    email,
+                    time: git_actor::Time {
+                        seconds_since_unix_epoch: u32::try_from(seconds)
+                            .map_err(|_| corrupt("log time is out of range"))?,
+                        offset_in_seconds: i32::from(offset_in_minutes) * 60,
+                        sign: if offset_in_minutes < 0 {
+                            git_actor::Sign::Minus
+                        } else {
+                            git_actor::Sign::Plus
+                        },
+                    },
+                },
+                message: BString::from(message),
+            })
+        }
+        _ => return Err(corrupt("unknown log value type")),
+    };
+    Ok((
+        Log {
+            name: name(name_bytes)?,
+            update_index,
+            line,
+        },
+        cursor.pos,
+    ))
+}
+
+/// Encode the value of `record` into `out` and return its value type.
+pub(crate) fn encode_log(record: &Log, out: &mut Vec<u8>) -> u8 {
+    let line = match &record.line {
+        None => return LOG_DELETION,
+        Some(line) => line,
+    };
+    out.extend_from_slice(line.previous_oid.as_bytes());
+    out.extend_from_slice(line.new_oid.as_bytes());
+    for field in [&line.signature.name, &line.signature.email] {
+        varint::put(out, field.len() as u64);
+        out.extend_from_slice(field);
+    }
+    varint::put(out, u64::from(line.signature.time.seconds_since_unix_epoch));
+    out.extend_from_slice(&((line.signature.time.offset_in_seconds / 60) as i16).to_be_bytes());
+    let message_len = if line.message.is_empty() {
+        0
+    } else {
+        line.message.len() + 1
+    };
+    varint::put(out, message_len as u64);
+    if !line.message.is_empty() {
+        out.extend_from_slice(&line.message);
+        out.push(b'\n');
+    }
+    LOG_UPDATE
+}
@@ -0,0 +1,34 @@
+pub use error::Error;
+
+use crate::{log, store_impl::reftable, FullNameRef};
+
+impl reftable::Store {
+    /// Return all entries of the reflog of the reference with the given full `name`, with the oldest entry first,
+    /// or `None` if there is no reflog.
+    pub fn reflog(&self, name: &FullNameRef) -> Result<Option<Vec<log::Line>>, Error> {
+        let logs = match self.stack()? {
+            Some(stack) => stack.logs_of(name.as_bstr())?,
+            None => Vec::new(),
+        };
+        Ok((!logs.is_empty()).then(|| logs.into_iter().filter_map(|log| log.line).collect()))
+    }
+
+    /// Return true if a reflog exists for the reference with the given full `name`.
+    pub fn reflog_exists(&self, name: &FullNameRef) -> Result<bool, Error> {
+        Ok(self.reflog(name)?.is_some())
+    }
+}
+
+mod error {
+    use crate::store_impl::reftable::{decode, stack};
+
+    /// The error returned by [reftable::Store::reflog()][crate::reftable::Store::reflog()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The tables could not be loaded")]
+        LoadStack(#[from] stack::load::Error),
+        #[error("A table could not be read")]
+        Decode(#[from] decode::Error),
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    store_impl::reftable::{record, Stack, Table},
+    FullName,
+};
+
+/// The name of the file listing all tables of a stack.
+pub const TABLES_LIST: &str = "tables.list";
+
+///
+pub mod load {
+    use std::path::PathBuf;
+
+    /// The error returned by [`Stack::at()`][super::Stack::at()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The list of tables could not be read")]
+        Io(#[from] std::io::Error),
+        #[error("The table at {path:?} could not be loaded")]
+        Table {
+            path: PathBuf,
+            source: crate::store_impl::reftable::decode::Error,
+        },
+    }
+}
+
+/// Return the names of the tables listed in `tables.list` in `dir`, oldest first.
+pub(crate) fn table_names(dir: &Path) -> std::io::Result<Vec<String>> {
+    match std::fs::read_to_string(dir.join(TABLES_LIST)) {
+        Ok(list) => Ok(list.lines().filter(|l| !l.is_empty()).map(ToOwned::to_owned).collect()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Initialization
+impl Stack {
+    /// Load all tables listed in the `tables.list` file in the reftable `dir`. A missing list results in an empty stack.
+    pub fn at(dir: impl AsRef<Path>) -> Result<Self, load::Error> {
+        let dir = dir.as_ref();
+        let mut attempts_left = 3;
+        'retry: loop {
+            let mut tables = Vec::new();
+            for name in table_names(dir)? {
+                let path = dir.join(name);
+                match Table::at(&path) {
+                    Ok(table) => tables.push(table),
+                    // The table may have been removed by a concurrent compaction, which also changed the list.
+                    Err(crate::store_impl::reftable::decode::Error::Io(err))
+                        if err.kind() == std::io::ErrorKind::NotFound && attempts_left > 0 =>
+                    {
+                        attempts_left -= 1;
+                        continue 'retry;
+                    }
+                    Err(source) => return Err(load::Error::Table { path, source }),
+                }
+            }
+            return Ok(Stack { tables });
+        }
+    }
+}
+
+/// Access
+impl Stack {
+    /// The tables of this stack, with the oldest one first.
+    pub fn tables(&self) -> &[Table] {
+        &self.tables
+    }
+
+    /// The largest update index of all tables, or 0 if there is none.
+    pub fn max_update_index(&self) -> u64 {
+        self.tables.last().map_or(0, |t| t.max_update_index())
+    }
+
+    /// Find the reference with the full `name` as seen when looking at all tables, or `None` if it doesn't exist or was deleted.
+    pub fn find_ref(&self, name: &[u8]) -> Result<Option<record::Ref>, crate::store_impl::reftable::decode::Error> {
+        for table in self.tables.iter().rev() {
+            if let Some(record) = table.find_ref(name)? {
+                return Ok((record.value != record::Value::Deletion).then(|| record));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return all references whose name starts with `prefix` as seen when looking at all tables, sorted by name.
+    ///
+    /// If `keep_deletions` is true, deletions are returned as well.
+    pub fn refs_with_prefix(
+        &self,
+        prefix: &[u8],
+        keep_deletions: bool,
+    ) -> Result<Vec<record::Ref>, crate::store_impl::reftable::decode::Error> {
+        merged_refs(&self.tables, prefix, keep_deletions)
+    }
+
+    /// Return the log of the reference with the full `name` as seen when looking at all tables, with the oldest entry first.
+    pub fn logs_of(&self, name: &[u8]) -> Result<Vec<record::Log>, crate::store_impl::reftable::decode::Error> {
+        let mut logs = BTreeMap::new();
+        for table in &self.tables {
+            for log in table.logs_of(name)? {
+                logs.insert(log.update_index, log);
+            }
+        }
+        Ok(logs.into_values().filter(|log| log.line.is_some()).collect())
+    }
+}
+
+impl Stack {
+    /// Return the file names of all tables.
+    pub(crate) fn table_names(&self) -> Vec<PathBuf> {
+        self.tables
+            .iter()
+            .map(|t| t.path().file_name().expect("tables have a file name").into())
+            .collect()
+    }
+}
+
+/// Merge the references starting with `prefix` of all `tables`, with tables later in the list taking precedence.
+pub(crate) fn merged_refs(
+    tables: &[Table],
+    prefix: &[u8],
+    keep_deletions: bool,
+) -> Result<Vec<record::Ref>, crate::store_impl::reftable::decode::Error> {
+    let mut refs = BTreeMap::new();
+    for table in tables {
+        for record in table.refs_with_prefix(prefix)? {
+            refs.insert(record.name.clone(), record);
+        }
+    }
+    Ok(refs
+        .into_values()
+        .filter(|r| keep_deletions || r.value != record::Value::Deletion)
+        .collect())
+}
+
+/// Merge the logs of all `tables`, with tables later in the list taking precedence.
+pub(crate) fn merged_logs(
+    tables: &[Table],
+    keep_deletions: bool,
+) -> Result<Vec<record::Log>, crate::store_impl::reftable::decode::Error> {
+    let mut logs = BTreeMap::<(FullName, u64), record::Log>::new();
+    for table in tables {
+        for log in table.logs()? {
+            logs.insert((log.name.clone(), log.update_index), log);
+        }
+    }
+    Ok(logs
+        .into_values()
+        .filter(|log| keep_deletions || log.line.is_some())
+        .collect())
+}
@@ -0,0 +1,379 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::store_impl::reftable::{
+    block::{self, Block},
+    decode::Error,
+    record, Table,
+};
+
+pub(crate) const MAGIC: &[u8; 4] = b"REFT";
+const SHA1_ID: &[u8; 4] = b"sha1";
+const SHA256_ID: &[u8; 4] = b"s256";
+
+/// Return the size of the file header for `version`.
+pub(crate) fn header_len(version: u8) -> usize {
+    if version == 1 {
+        24
+    } else {
+        28
+    }
+}
+
+/// Return the size of the file footer for `version`.
+pub(crate) fn footer_len(version: u8) -> usize {
+    header_len(version) + 44
+}
+
+/// Encode the file header, using version 2 of the format only for hashes other than SHA1.
+pub(crate) fn header(
+    object_hash: git_hash::Kind,
+    block_size: u32,
+    min_update_index: u64,
+    max_update_index: u64,
+) -> Vec<u8> {
+    let version = match object_hash {
+        git_hash::Kind::Sha1 => 1,
+        git_hash::Kind::Sha256 => 2,
+    };
+    let mut out = Vec::with_capacity(header_len(version));
+    out.extend_from_slice(MAGIC);
+    out.push(version);
+    let mut block_size_bytes = [0; 3];
+    block::put_be24(&mut block_size_bytes, block_size as usize);
+    out.extend_from_slice(&block_size_bytes);
+    out.extend_from_slice(&min_update_index.to_be_bytes());
+    out.extend_from_slice(&max_update_index.to_be_bytes());
+    if version != 1 {
+        out.extend_from_slice(match object_hash {
+            git_hash::Kind::Sha1 => SHA1_ID,
+            git_hash::Kind::Sha256 => SHA256_ID,
+        });
+    }
+    out
+}
+
+fn be64(data: &[u8]) -> u64 {
+    u64::from_be_bytes(data[..8].try_into().expect("8 bytes"))
+}
+
+/// Initialization
+impl Table {
+    /// Open the table at `path`.
+    pub fn at(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        let data = std::fs::read(&path)?;
+        Self::from_bytes(data, path)
+    }
+
+    /// Decode the table in `data`, which was read from `path`.
+    pub fn from_bytes(data: Vec<u8>, path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let corrupt = |message| Error::Corrupt { message };
+        if data.len() < 5 || &data[..4] != MAGIC {
+            return Err(corrupt("missing reftable signature"));
+        }
+        let version = data[4];
+        if version != 1 && version != 2 {
+            return Err(Error::UnsupportedVersion { version });
+        }
+        let (header_len, footer_len) = (header_len(version), footer_len(version));
+        if data.len() < header_len + footer_len {
+            return Err(corrupt("table is too small"));
+        }
+        let footer_start = data.len() - footer_len;
+        let footer = &data[footer_start..];
+        if footer[..header_len] != data[..header_len] {
+            return Err(corrupt("footer doesn't match header"));
+        }
+        let crc_start = footer_len - 4;
+        let expected_crc = u32::from_be_bytes(footer[crc_start..].try_into().expect("4 bytes"));
+        if git_features::hash::crc32(&footer[..crc_start]) != expected_crc {
+            return Err(corrupt("footer checksum mismatch"));
+        }
+        let object_hash = if version == 1 {
+            git_hash::Kind::Sha1
+        } else {
+            match &data[24..28] {
+                id if id == SHA1_ID => git_hash::Kind::Sha1,
+                id if id == SHA256_ID => git_hash::Kind::Sha256,
+                _ => return Err(corrupt("unknown hash id")),
+            }
+        };
+        let block_size = block::be24(&data[5..8]);
+        let min_update_index = be64(&data[8..]);
+        let max_update_index = be64(&data[16..]);
+
+        let positions = &footer[header_len..];
+        let ref_index_position = be64(positions) as usize;
+        let obj_position = (be64(&positions[8..]) >> 5) as usize;
+        let log_position = be64(&positions[24..]) as usize;
+        let log_index_position = be64(&positions[32..]) as usize;
+
+        let first_block_kind = (footer_start > header_len).then(|| data[header_len]);
+        let section_end =
+            |candidates: &[usize]| candidates.iter().copied().find(|pos| *pos != 0).unwrap_or(footer_start);
+        let refs = (first_block_kind == Some(block::REF))
+            .then(|| 0..section_end(&[ref_index_position, obj_position, log_position]));
+        let logs = if first_block_kind == Some(block::LOG) {
+            Some(0..section_end(&[log_index_position]))
+        } else {
+            (log_position != 0).then(|| log_position..section_end(&[log_index_position]))
+        };
+        if [&refs, &logs]
+            .iter()
+            .filter_map(|range| range.as_ref())
+            .any(|range| range.start > range.end || range.end > footer_start)
+        {
+            return Err(corrupt("section positions are out of bounds"));
+        }
+
+        let mut table = Table {
+            path: path.into(),
+            data,
+            header_len,
+            block_size,
+            min_update_index,
+            max_update_index,
+            object_hash,
+            logs,
+            ref_blocks: Vec::new(),
+        };
+        if let Some(refs) = refs {
+            table.ref_blocks = table.block_offsets(refs, block::REF)?;
+        }
+        Ok(table)
+    }
+}
+
+/// Access
+impl Table {
+    /// The path from which the table was read.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The smallest update index of all records in this table.
+    pub fn min_update_index(&self) -> u64 {
+        self.min_update_index
+    }
+
+    /// The largest update index of all records in this table.
+    pub fn max_update_index(&self) -> u64 {
+        self.max_update_index
+    }
+
+    /// The kind of hash used for all object ids in this table.
+    pub fn object_hash(&self) -> git_hash::Kind {
+        self.object_hash
+    }
+
+    /// Find the reference record with the given full `name`, which may be a [deletion][record::Value::Deletion].
+    pub fn find_ref(&self, name: &[u8]) -> Result<Option<record::Ref>, Error> {
+        let idx = self
+            .ref_blocks
+            .partition_point(|(_, first_key)| first_key.as_slice() <= name);
+        let (offset, _) = match idx.checked_sub(1).map(|idx| &self.ref_blocks[idx]) {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let block = self.block_at(*offset, self.data.len())?;
+        let mut records = block.records_near(name)?;
+        while let Some(record) = records.next_record(|key, value_type, value| {
+            if key < name {
+                return Ok((None, ref_value_len(value_type, value, self.object_hash)?));
+            }
+            record::decode_ref(key, value_type, value, self.min_update_index, self.object_hash)
+                .map(|(record, len)| (Some(record), len))
+        })? {
+            if let Some(record) = record {
+                return Ok((record.name.as_bstr() == name).then(|| record));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return all reference records whose name starts with `prefix`, in order, including [deletions][record::Value::Deletion].
+    pub fn refs_with_prefix(&self, prefix: &[u8]) -> Result<Vec<record::Ref>, Error> {
+        let start = self
+            .ref_blocks
+            .partition_point(|(_, first_key)| first_key.as_slice() <= prefix)
+            .saturating_sub(1);
+        let mut out = Vec::new();
+        for (idx, (offset, _)) in self.ref_blocks.iter().enumerate().skip(start) {
+            let block = self.block_at(*offset, self.data.len())?;
+            let mut records = if idx == start {
+                block.records_near(prefix)?
+            } else {
+                block.records()
+            };
+            let mut past_prefix = false;
+            while let Some(record) = records.next_record(|key, value_type, value| {
+                if !key.starts_with(prefix) {
+                    past_prefix = key > prefix;
+                    return Ok((None, ref_value_len(value_type, value, self.object_hash)?));
+                }
+                record::decode_ref(key, value_type, value, self.min_update_index, self.object_hash)
+                    .map(|(record, len)| (Some(record), len))
+            })? {
+                if past_prefix {
+                    return Ok(out);
+                }
+                out.extend(record);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Return all log records of the reference with the given full `name`, most recent first, including deletions.
+    pub fn logs_of(&self, name: &[u8]) -> Result<Vec<record::Log>, Error> {
+        let mut prefix = name.to_vec();
+        prefix.push(0);
+        let mut out = Vec::new();
+        self.visit_logs(|key, value_type, value| {
+            if !key.starts_with(&prefix) {
+                let keep_going = key < prefix.as_slice();
+                return Ok((keep_going, log_value_len(value_type, value, self.object_hash)?));
+            }
+            let (record, len) = record::decode_log(key, value_type, value, self.object_hash)?;
+            out.push(record);
+            Ok((true, len))
+        })?;
+        Ok(out)
+    }
+
+    /// Return all log records in this table, ordered by reference name and most recent first.
+    pub fn logs(&self) -> Result<Vec<record::Log>, Error> {
+        let mut out = Vec::new();
+        self.visit_logs(|key, value_type, value| {
+            let (record, len) = record::decode_log(key, value_type, value, self.object_hash)?;
+            out.push(record);
+            Ok((true, len))
+        })?;
+        Ok(out)
+    }
+}
+
+impl Table {
+    /// Return the header offset of a block at `offset`, which is non-zero only for the first block in the file.
+    fn header_off(&self, offset: usize) -> usize {
+        if offset == 0 {
+            self.header_len
+        } else {
+            0
+        }
+    }
+
+    fn block_at(&self, offset: usize, section_end: usize) -> Result<Block<'_>, Error> {
+        Block::at(
+            &self.data,
+            offset,
+            self.header_off(offset),
+            self.block_size,
+            section_end,
+        )
+    }
+
+    /// Return the offsets of all blocks of `kind` in `section` along with the key of their first record.
+    fn block_offsets(&self, section: Range<usize>, kind: u8) -> Result<Vec<(usize, Vec<u8>)>, Error> {
+        let mut out = Vec::new();
+        let mut offset = section.start;
+        while offset < section.end {
+            let header_off = self.header_off(offset);
+            if self.data[offset + header_off] != kind {
+                break;
+            }
+            let block = self.block_at(offset, section.end)?;
+            let mut first_key = None;
+            block.records().next_record(|key, _value_type, _value| {
+                first_key = Some(key.to_vec());
+                Ok(((), 0))
+            })?;
+            out.extend(first_key.map(|key| (offset, key)));
+            offset += block.len_in_file;
+        }
+        Ok(out)
+    }
+
+    /// Call `visit(key, value_type, value)` on all log records in order until it returns `false`, along with the length of the value.
+    fn visit_logs(&self, mut visit: impl FnMut(&[u8], u8, &[u8]) -> Result<(bool, usize), Error>) -> Result<(), Error> {
+        let section = match &self.logs {
+            Some(section) => section.clone(),
+            None => return Ok(()),
+        };
+        let mut offset = section.start;
+        while offset < section.end {
+            if self.data[offset + self.header_off(offset)] != block::LOG {
+                break;
+            }
+            let block = self.block_at(offset, section.end)?;
+            let mut records = block.records();
+            while let Some(keep_going) = records.next_record(&mut visit)? {
+                if !keep_going {
+                    return Ok(());
+                }
+            }
+            offset += block.len_in_file;
+        }
+        Ok(())
+    }
+}
+
+/// Return the length of a reference value without decoding it.
+fn ref_value_len(value_type: u8, value: &[u8], object_hash: git_hash::Kind) -> Result<usize, Error> {
+    let corrupt = || Error::Corrupt {
+        message: "malformed reference value",
+    };
+    let (_, mut len) = block::varint::get(value).ok_or_else(corrupt)?;
+    let hash_len = object_hash.len_in_bytes();
+    len += match value_type {
+        0 => 0,
+        1 => hash_len,
+        2 => 2 * hash_len,
+        3 => {
+            let (target_len, consumed) = block::varint::get(&value[len..]).ok_or_else(corrupt)?;
+            consumed + target_len as usize
+        }
+        _ => return Err(corrupt()),
+    };
+    if len > value.len() {
+        return Err(corrupt());
+    }
+    Ok(len)
+}
+
+/// Return the length of a log value without decoding it.
+fn log_value_len(value_type: u8, value: &[u8], object_hash: git_hash::Kind) -> Result<usize, Error> {
+    let corrupt = || Error::Corrupt {
+        message: "malformed log value",
+    };
+    if value_type == 0 {
+        return Ok(0);
+    }
+    let mut len = 2 * object_hash.len_in_bytes();
+    let skip_string = |len: &mut usize| -> Result<(), Error> {
+        let (string_len, consumed) = value.get(*len..).and_then(block::varint::get).ok_or_else(corrupt)?;
+        *len += consumed + string_len as usize;
+        Ok(())
+    };
+    skip_string(&mut len)?;
+    skip_string(&mut len)?;
+    let (_time, consumed) = value.get(len..).and_then(block::varint::get).ok_or_else(corrupt)?;
+    len += consumed + 2;
+    skip_string(&mut len)?;
+    if len > value.len() {
+        return Err(corrupt());
+    }
+    Ok(len)
+}
+
+impl std::fmt::Debug for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Table")
+            .field("path", &self.path)
+            .field("min_update_index", &self.min_update_index)
+            .field("max_update_index", &self.max_update_index)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,457 @@
+use std::{io::Write, path::Path};
+
+use git_hash::ObjectId;
+use git_object::bstr::BString;
+
+use crate::{
+    log,
+    store::WriteReflog,
+    store_impl::reftable::{self, find::find_in_stack, record, stack, write, Stack},
+    transaction::{Change, PreviousValue, RefEdit, RefEditsExt, RefLog},
+    FullNameRef, Target,
+};
+
+/// A transaction on a reftable store, which adds a single table with all of its changes.
+pub struct Transaction<'s> {
+    store: &'s reftable::Store,
+    /// The lock on `tables.list`, held from preparation to commit to prevent concurrent changes.
+    lock: Option<git_lock::File>,
+    /// The stack as it was when the lock was obtained.
+    stack: Option<Stack>,
+    updates: Option<Vec<Edit>>,
+}
+
+#[derive(Debug)]
+struct Edit {
+    update: RefEdit,
+    /// Set if this update is coming from a symbolic reference and used to make it appear like it is the one that is handled,
+    /// instead of the referent reference.
+    parent_index: Option<usize>,
+    /// For symbolic refs, this is the previous OID to put into the reflog instead of our own previous value. It's the
+    /// peeled value of the leaf referent.
+    leaf_referent_previous_oid: Option<ObjectId>,
+}
+
+impl Edit {
+    fn name(&self) -> BString {
+        self.update.name.0.clone()
+    }
+}
+
+impl std::borrow::Borrow<RefEdit> for Edit {
+    fn borrow(&self) -> &RefEdit {
+        &self.update
+    }
+}
+
+impl std::borrow::BorrowMut<RefEdit> for Edit {
+    fn borrow_mut(&mut self) -> &mut RefEdit {
+        &mut self.update
+    }
+}
+
+impl reftable::Store {
+    /// Open a transaction which will add a table with all of its edits on top of the stack.
+    pub fn transaction(&self) -> Transaction<'_> {
+        Transaction {
+            store: self,
+            lock: None,
+            stack: None,
+            updates: None,
+        }
+    }
+
+    fn should_write_reflog(
+        &self,
+        stack: &Stack,
+        name: &FullNameRef,
+        force_create_reflog: bool,
+    ) -> Result<bool, reftable::decode::Error> {
+        Ok(match self.write_reflog {
+            WriteReflog::Disable => false,
+            WriteReflog::Always => true,
+            WriteReflog::Normal => {
+                force_create_reflog || should_autocreate_reflog(name) || !stack.logs_of(name.as_bstr())?.is_empty()
+            }
+        })
+    }
+
+    /// Write a table with `refs` and `logs` to `dir` and return its file name.
+    pub(crate) fn write_table(
+        &self,
+        dir: &Path,
+        refs: Vec<record::Ref>,
+        logs: Vec<record::Log>,
+        min_update_index: u64,
+        max_update_index: u64,
+    ) -> Result<String, write::Error> {
+        let mut buf = Vec::new();
+        write::table(
+            refs,
+            logs,
+            min_update_index,
+            max_update_index,
+            self.object_hash,
+            self.write_options,
+            &mut buf,
+        )?;
+        let name = format!(
+            "0x{:012x}-0x{:012x}-{:08x}.ref",
+            min_update_index,
+            max_update_index,
+            git_features::hash::crc32(&buf)
+        );
+        let mut lock =
+            git_lock::File::acquire_to_update_resource(dir.join(&name), git_lock::acquire::Fail::Immediately, None)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+        lock.with_mut(|file| file.write_all(&buf))?;
+        lock.commit().map_err(|err| err.error)?;
+        Ok(name)
+    }
+}
+
+fn should_autocreate_reflog(name: &FullNameRef) -> bool {
+    let name = name.as_bstr();
+    name.starts_with(b"refs/heads/")
+        || name.starts_with(b"refs/remotes/")
+        || name.starts_with(b"refs/notes/")
+        || name.starts_with(b"refs/worktree/")
+        || name == "HEAD"
+}
+
+/// Write `names` into the `lock` of the list of tables and commit it.
+pub(crate) fn commit_tables_list(
+    mut lock: git_lock::File,
+    names: impl IntoIterator<Item = String>,
+) -> std::io::Result<()> {
+    lock.with_mut(|file| {
+        for name in names {
+            writeln!(file, "{}", name)?;
+        }
+        Ok(())
+    })?;
+    lock.commit().map_err(|err| err.error)?;
+    Ok(())
+}
+
+impl<'s> Transaction<'s> {
+    /// Prepare for calling [`commit(…)`][Transaction::commit()] by locking the list of tables, which will fail according to
+    /// `lock_fail_mode` if it is locked already, and by checking all `edits` against the current state of the stack.
+    ///
+    /// If the operation succeeds, the transaction can be committed or dropped to cause a rollback automatically.
+    pub fn prepare(
+        mut self,
+        edits: impl IntoIterator<Item = RefEdit>,
+        lock_fail_mode: git_lock::acquire::Fail,
+    ) -> Result<Self, prepare::Error> {
+        use prepare::Error;
+        assert!(self.updates.is_none(), "BUG: Must not call prepare(…) multiple times");
+        let dir = self.store.reftable_dir();
+        let lock = git_lock::File::acquire_to_update_resource(
+            dir.join(stack::TABLES_LIST),
+            lock_fail_mode,
+            Some(self.store.git_dir.clone()),
+        )?;
+        let stack = Stack::at(&dir)?;
+
+        let mut updates: Vec<_> = edits
+            .into_iter()
+            .map(|update| Edit {
+                update,
+                parent_index: None,
+                leaf_referent_previous_oid: None,
+            })
+            .collect();
+        updates
+            .pre_process(
+                |name| find_in_stack(&stack, name).ok().flatten().map(|r| r.target),
+                |idx, update| Edit {
+                    update,
+                    parent_index: Some(idx),
+                    leaf_referent_previous_oid: None,
+                },
+            )
+            .map_err(Error::PreprocessingFailed)?;
+
+        for cid in 0..updates.len() {
+            let change = &mut updates[cid];
+            let full_name = change.name();
+            let existing = stack
+                .find_ref(change.update.name.as_bstr())?
+                .and_then(record::Ref::into_reference);
+            match &mut change.update.change {
+                Change::Delete { expected, .. } => {
+                    match (&expected, &existing) {
+                        (PreviousValue::MustNotExist, _) => {
+                            panic!("BUG: MustNotExist constraint makes no sense if references are to be deleted")
+                        }
+                        (PreviousValue::ExistingMustMatch(_), None)
+                        | (PreviousValue::MustExist, Some(_))
+                        | (PreviousValue::Any, _) => {}
+                        (PreviousValue::MustExist, None) | (PreviousValue::MustExistAndMatch(_), None) => {
+                            return Err(Error::DeleteReferenceMustExist {
+                                full_name: full_name.clone(),
+                            })
+                        }
+                        (PreviousValue::MustExistAndMatch(previous), Some(existing))
+                        | (PreviousValue::ExistingMustMatch(previous), Some(existing)) => {
+                            if *previous != existing.target {
+                                return Err(Error::ReferenceOutOfDate {
+                                    full_name: full_name.clone(),
+                                    expected: previous.clone(),
+                                    actual: existing.target.clone(),
+                                });
+                            }
+                        }
+                    }
+                    if let Some(existing) = existing {
+                        *expected = PreviousValue::MustExistAndMatch(existing.target);
+                    }
+                }
+                Change::Update { expected, new, .. } => {
+                    match (&expected, &existing) {
+                        (PreviousValue::Any, _)
+                        | (PreviousValue::MustExist, Some(_))
+                        | (PreviousValue::MustNotExist, None)
+                        | (PreviousValue::ExistingMustMatch(_), None) => {}
+                        (PreviousValue::MustExist, None) => {
+                            return Err(Error::MustExist {
+                                full_name: full_name.clone(),
+                                expected: Target::Peeled(self.store.object_hash.null()),
+                            });
+                        }
+                        (PreviousValue::MustNotExist, Some(existing)) => {
+                            if existing.target != *new {
+                                return Err(Error::MustNotExist {
+                                    full_name: full_name.clone(),
+                                    actual: existing.target.clone(),
+                                    new: new.clone(),
+                                });
+                            }
+                        }
+                        (PreviousValue::MustExistAndMatch(previous), Some(existing))
+                        | (PreviousValue::ExistingMustMatch(previous), Some(existing)) => {
+                            if *previous != existing.target {
+                                return Err(Error::ReferenceOutOfDate {
+                                    full_name: full_name.clone(),
+                                    expected: previous.clone(),
+                                    actual: existing.target.clone(),
+                                });
+                            }
+                        }
+                        (PreviousValue::MustExistAndMatch(previous), None) => {
+                            return Err(Error::MustExist {
+                                full_name: full_name.clone(),
+                                expected: previous.clone(),
+                            });
+                        }
+                    }
+                    if let Some(existing) = existing {
+                        *expected = PreviousValue::MustExistAndMatch(existing.target);
+                    }
+                }
+            }
+
+            // traverse parent chain from leaf/peeled ref and set the leaf previous oid accordingly
+            // to help with their reflog entries
+            if let (Some(crate::TargetRef::Peeled(oid)), Some(parent_idx)) =
+                (change.update.change.previous_value(), change.parent_index)
+            {
+                let oid = oid.to_owned();
+                let mut parent_idx_cursor = Some(parent_idx);
+                while let Some(parent) = parent_idx_cursor.take().map(|idx| &mut updates[idx]) {
+                    parent_idx_cursor = parent.parent_index;
+                    parent.leaf_referent_previous_oid = Some(oid);
+                }
+            }
+        }
+        self.lock = Some(lock);
+        self.stack = Some(stack);
+        self.updates = Some(updates);
+        Ok(self)
+    }
+
+    /// Rollback all intermediate state and return the `RefEdits` as we know them thus far.
+    ///
+    /// Note that they have been altered compared to what was initially provided as they have
+    /// been split and know about their current state in the stack.
+    ///
+    /// # Note
+    ///
+    /// A rollback happens automatically as this instance is dropped as well.
+    pub fn rollback(self) -> Vec<RefEdit> {
+        self.updates
+            .map(|updates| updates.into_iter().map(|u| u.update).collect())
+            .unwrap_or_default()
+    }
+
+    /// Make all [prepared][Transaction::prepare()] edits permanent by writing them into a new table on top of the stack,
+    /// and return the performed edits which represent the current state of the affected refs in the ref store in that instant.
+    /// `committer` is used in the reflog.
+    ///
+    /// Afterwards the stack is compacted automatically if it grew too large, which is not considered an error if it fails.
+    pub fn commit(self, committer: git_actor::SignatureRef<'_>) -> Result<Vec<RefEdit>, commit::Error> {
+        let updates = self.updates.expect("BUG: must call prepare before commit");
+        let stack = self.stack.expect("prepared transactions have a stack");
+        let lock = self.lock.expect("prepared transactions hold a lock");
+        let store = self.store;
+        let update_index = stack.max_update_index() + 1;
+
+        let mut refs = Vec::new();
+        let mut logs = Vec::new();
+        for change in &updates {
+            assert!(!change.update.deref, "Deref mode is turned into splits and turned off");
+            let name = &change.update.name;
+            match &change.update.change {
+                Change::Update { log, new, expected } => {
+                    let is_unchanged =
+                        matches!(expected, PreviousValue::MustExistAndMatch(previous) if previous == new);
+                    if log.mode == RefLog::AndReference && !is_unchanged {
+                        refs.push(record::Ref {
+                            name: name.clone(),
+                            update_index,
+                            value: match new {
+                                Target::Peeled(id) => record::Value::Peeled {
+                                    target: *id,
+                                    peeled: None,
+                                },
+                                Target::Symbolic(name) => record::Value::Symbolic(name.clone()),
+                            },
+                        });
+                    }
+                    let log_update = match new {
+                        Target::Symbolic(_) => {
+                            // no reflog for symref changes, unless the ref is new and we can obtain a peeled id
+                            // identified by the expectation of what could be there, as is the case when cloning.
+                            match expected {
+                                PreviousValue::ExistingMustMatch(Target::Peeled(oid)) => {
+                                    Some((Some(ObjectId::null(oid.kind())), *oid))
+                                }
+                                _ => None,
+                            }
+                        }
+                        Target::Peeled(new_oid) => {
+                            let previous = match expected {
+                                PreviousValue::MustExistAndMatch(Target::Peeled(oid)) => Some(*oid),
+                                _ => None,
+                            }
+                            .or(change.leaf_referent_previous_oid);
+                            Some((previous, *new_oid))
+                        }
+                    };
+                    if let Some((previous, new_oid)) = log_update {
+                        let do_update = previous != Some(new_oid);
+                        if do_update && store.should_write_reflog(&stack, name.as_ref(), log.force_create_reflog)? {
+                            logs.push(record::Log {
+                                name: name.clone(),
+                                update_index,
+                                line: Some(log::Line {
+                                    previous_oid: previous.unwrap_or_else(|| store.object_hash.null()),
+                                    new_oid,
+                                    signature: committer.to_owned(),
+                                    message: log.message.clone(),
+                                }),
+                            });
+                        }
+                    }
+                }
+                Change::Delete { log: mode, .. } => {
+                    if *mode == RefLog::AndReference {
+                        refs.push(record::Ref {
+                            name: name.clone(),
+                            update_index,
+                            value: record::Value::Deletion,
+                        });
+                    }
+                    logs.extend(
+                        stack
+                            .logs_of(name.as_bstr())?
+                            .into_iter()
+                            .map(|entry| record::Log { line: None, ..entry }),
+                    );
+                }
+            }
+        }
+
+        if !refs.is_empty() || !logs.is_empty() {
+            let dir = store.reftable_dir();
+            let table = store.write_table(&dir, refs, logs, update_index, update_index)?;
+            let names = stack
+                .table_names()
+                .into_iter()
+                .map(|name| name.to_string_lossy().into_owned())
+                .chain(Some(table));
+            commit_tables_list(lock, names)?;
+            // Always refresh ourselves right away to avoid races. Errors aren't critical as reading the stack will be retried later.
+            store.force_refresh_stack().ok();
+            store.auto_compact().ok();
+        }
+        Ok(updates.into_iter().map(|edit| edit.update).collect())
+    }
+}
+
+///
+pub mod prepare {
+    use git_object::bstr::BString;
+
+    use crate::{
+        store_impl::reftable::{decode, stack},
+        Target,
+    };
+
+    /// The error returned by [`Transaction::prepare()`][super::Transaction::prepare()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The lock for the list of tables could not be obtained")]
+        LockAcquire(#[from] git_lock::acquire::Error),
+        #[error("The tables could not be loaded")]
+        LoadStack(#[from] stack::load::Error),
+        #[error("A table could not be read")]
+        Decode(#[from] decode::Error),
+        #[error("Edit preprocessing failed with an error")]
+        PreprocessingFailed(#[source] std::io::Error),
+        #[error("The reference {full_name:?} for deletion did not exist")]
+        DeleteReferenceMustExist { full_name: BString },
+        #[error("Reference {full_name:?} was not supposed to exist when writing it with value {new:?}, but actual content was {actual:?}")]
+        MustNotExist {
+            full_name: BString,
+            actual: Target,
+            new: Target,
+        },
+        #[error("Reference {full_name:?} was supposed to exist with value {expected}, but didn't.")]
+        MustExist { full_name: BString, expected: Target },
+        #[error("The reference {full_name:?} should have content {expected}, actual content was {actual}")]
+        ReferenceOutOfDate {
+            full_name: BString,
+            expected: Target,
+            actual: Target,
+        },
+    }
+}
+
+///
+pub mod commit {
+    use crate::store_impl::reftable::{decode, write};
+
+    /// The error returned by [`Transaction::commit()`][super::Transaction::commit()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("A table could not be read")]
+        Decode(#[from] decode::Error),
+        #[error("The new table could not be written")]
+        WriteTable(#[from] write::Error),
+        #[error("The list of tables could not be updated")]
+        Io(#[from] std::io::Error),
+    }
+}
+
+impl std::fmt::Debug for Transaction<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Transaction")
+            .field("store", self.store)
+            .field("edits", &self.updates.as_ref().map(|u| u.len()))
+            .finish_non_exhaustive()
+    }
+}
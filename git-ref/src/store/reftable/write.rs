@@ -0,0 +1,173 @@
+use std::io;
+
+use git_object::bstr::BString;
+
+use crate::store_impl::reftable::{block, record, table};
+
+/// Options for writing reftables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Options {
+    /// The size of blocks to pad reference blocks to, which also limits the size of log blocks before compression.
+    pub block_size: u32,
+    /// The amount of records after which a record is written with its full key, to allow binary searches within blocks.
+    pub restart_interval: u16,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            block_size: 4096,
+            restart_interval: 16,
+        }
+    }
+}
+
+/// The error returned by [`table()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not write reftable")]
+    Io(#[from] io::Error),
+    #[error("The record for reference {name:?} doesn't fit into a block")]
+    RecordTooLarge { name: BString },
+}
+
+/// Keeps track of the amount of bytes written and the padding to write before the next block.
+struct Output<'a> {
+    out: &'a mut dyn io::Write,
+    written: usize,
+    pending_padding: usize,
+}
+
+impl Output<'_> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        const ZEROS: [u8; 512] = [0; 512];
+        while self.pending_padding != 0 {
+            let len = self.pending_padding.min(ZEROS.len());
+            self.out.write_all(&ZEROS[..len])?;
+            self.pending_padding -= len;
+            self.written += len;
+        }
+        self.out.write_all(bytes)?;
+        self.written += bytes.len();
+        Ok(())
+    }
+
+    /// Return the position at which the next block will be written.
+    fn next_block_position(&self) -> usize {
+        self.written + self.pending_padding
+    }
+}
+
+/// Write a table containing `refs` and `logs` with the given range of update indices to `out`, using the kind of hash
+/// as defined by `object_hash` and `options` to define the layout of blocks.
+///
+/// `refs` must have unique names, and `logs` must be unique by name and update index.
+pub fn table(
+    mut refs: Vec<record::Ref>,
+    mut logs: Vec<record::Log>,
+    min_update_index: u64,
+    max_update_index: u64,
+    object_hash: git_hash::Kind,
+    options: Options,
+    out: &mut dyn io::Write,
+) -> Result<(), Error> {
+    refs.sort_by(|a, b| a.name.cmp(&b.name));
+    logs.sort_by_cached_key(|log| log.key());
+
+    let header = table::header(object_hash, options.block_size, min_update_index, max_update_index);
+    let block_size = options.block_size as usize;
+    let restart_interval = usize::from(options.restart_interval);
+    let mut out = Output {
+        out,
+        written: 0,
+        pending_padding: 0,
+    };
+    let new_block = |kind: u8, out: &Output<'_>| {
+        let header: &[u8] = if out.next_block_position() == 0 { &header } else { &[] };
+        (
+            block::Writer::new(kind, header, block_size, restart_interval),
+            header.len(),
+        )
+    };
+    let flush =
+        |(block, header_len): (block::Writer, usize), out: &mut Output<'_>, is_padded: bool| -> io::Result<()> {
+            let bytes = block.finish(header_len)?;
+            out.write(&bytes)?;
+            if is_padded {
+                out.pending_padding = block_size.saturating_sub(bytes.len());
+            }
+            Ok(())
+        };
+
+    let mut value = Vec::new();
+    if !refs.is_empty() {
+        let mut current = new_block(block::REF, &out);
+        for record in &refs {
+            value.clear();
+            let value_type = record::encode_ref(record, min_update_index, &mut value);
+            if !current.0.add(record.name.as_bstr(), value_type, &value) {
+                if current.0.is_empty() {
+                    return Err(Error::RecordTooLarge {
+                        name: record.name.as_bstr().into(),
+                    });
+                }
+                flush(current, &mut out, true)?;
+                current = new_block(block::REF, &out);
+                if !current.0.add(record.name.as_bstr(), value_type, &value) {
+                    return Err(Error::RecordTooLarge {
+                        name: record.name.as_bstr().into(),
+                    });
+                }
+            }
+        }
+        flush(current, &mut out, true)?;
+    }
+
+    let mut log_position = 0;
+    if !logs.is_empty() {
+        log_position = out.next_block_position();
+        let mut current = new_block(block::LOG, &out);
+        for record in &logs {
+            value.clear();
+            let value_type = record::encode_log(record, &mut value);
+            let key = record.key();
+            if !current.0.add(&key, value_type, &value) {
+                if current.0.is_empty() {
+                    return Err(Error::RecordTooLarge {
+                        name: record.name.as_bstr().into(),
+                    });
+                }
+                flush(current, &mut out, false)?;
+                current = new_block(block::LOG, &out);
+                if !current.0.add(&key, value_type, &value) {
+                    return Err(Error::RecordTooLarge {
+                        name: record.name.as_bstr().into(),
+                    });
+                }
+            }
+        }
+        flush(current, &mut out, false)?;
+    }
+
+    if out.next_block_position() == 0 {
+        out.write(&header)?;
+    }
+    let mut footer = header.clone();
+    let ref_index_position = 0u64;
+    let obj_position_and_id_len = 0u64;
+    let obj_index_position = 0u64;
+    let log_index_position = 0u64;
+    for position in [
+        ref_index_position,
+        obj_position_and_id_len,
+        obj_index_position,
+        log_position as u64,
+        log_index_position,
+    ] {
+        footer.extend_from_slice(&position.to_be_bytes());
+    }
+    footer.extend_from_slice(&git_features::hash::crc32(&footer).to_be_bytes());
+    out.write(&footer)?;
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use git_odb::Find;
+use git_ref::file::pack_refs::{Options, Outcome};
+
+fn store() -> crate::Result<(git_testtools::tempfile::TempDir, git_ref::file::Store, git_odb::Handle)> {
+    let dir = git_testtools::scripted_fixture_repo_writable("make_repo_for_pack_refs.sh")?;
+    let git_dir = dir.path().join(".git");
+    let odb = git_odb::at(git_dir.join("objects"))?;
+    let store = git_ref::file::Store::at(git_dir, git_ref::store::WriteReflog::Normal, git_hash::Kind::Sha1);
+    Ok((dir, store, odb))
+}
+
+fn pack_refs(
+    store: &git_ref::file::Store,
+    odb: &git_odb::Handle,
+    options: Options,
+) -> Result<Outcome, git_ref::file::pack_refs::Error> {
+    store.pack_refs(
+        options,
+        git_lock::acquire::Fail::Immediately,
+        git_lock::acquire::Fail::Immediately,
+        |id, buf| -> Result<Option<git_object::Kind>, git_odb::store::find::Error> {
+            Ok(odb.try_find(id, buf)?.map(|d| d.kind))
+        },
+    )
+}
+
+fn loose_names(store: &git_ref::file::Store) -> crate::Result<Vec<String>> {
+    Ok(store
+        .loose_iter()?
+        .map(|r| r.map(|r| r.name.as_bstr().to_string()))
+        .collect::<Result<_, _>>()?)
+}
+
+#[test]
+fn all_with_prune_produces_the_same_packed_refs_as_git() -> crate::Result {
+    let (dir, store, odb) = store()?;
+    assert_eq!(
+        pack_refs(&store, &odb, Options { all: true, prune: true })?,
+        Outcome {
+            packed: 3,
+            skipped_broken: 1
+        }
+    );
+    assert_eq!(
+        loose_names(&store)?,
+        ["refs/bisect/bad", "refs/heads/broken", "refs/heads/symbolic"],
+        "worktree-private, broken and symbolic references stay loose"
+    );
+    let packed_refs = std::fs::read(dir.path().join(".git").join("packed-refs"))?;
+    assert!(packed_refs.starts_with(b"# pack-refs with: peeled fully-peeled sorted \n"));
+
+    let (git_dir, ..) = store_packed_by_git()?;
+    let expected = std::fs::read(git_dir.path().join(".git").join("packed-refs"))?;
+    assert_eq!(
+        git_ref::bstr::BStr::new(&packed_refs),
+        git_ref::bstr::BStr::new(&expected),
+        "tags are peeled and all references are sorted"
+    );
+
+    assert_eq!(
+        pack_refs(&store, &odb, Options { all: true, prune: true })?,
+        Outcome {
+            packed: 0,
+            skipped_broken: 1
+        },
+        "there is nothing left to pack"
+    );
+    Ok(())
+}
+
+fn store_packed_by_git() -> crate::Result<(git_testtools::tempfile::TempDir, git_ref::file::Store, git_odb::Handle)> {
+    let (dir, store, odb) = store()?;
+    let status = std::process::Command::new("git")
+        .args(["pack-refs", "--all", "--prune"])
+        .current_dir(dir.path())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+    assert!(status.success());
+    Ok((dir, store, odb))
+}
+
+#[test]
+fn by_default_only_tags_and_already_packed_references_are_packed() -> crate::Result {
+    let (_dir, store, odb) = store()?;
+    let main_before = store.find("main")?.target;
+    assert_eq!(
+        pack_refs(&store, &odb, Options::default())?,
+        Outcome {
+            packed: 2,
+            skipped_broken: 0
+        }
+    );
+    assert_eq!(
+        loose_names(&store)?,
+        [
+            "refs/bisect/bad",
+            "refs/heads/broken",
+            "refs/heads/loose",
+            "refs/heads/main",
+            "refs/heads/symbolic",
+            "refs/tags/annotated-twice"
+        ],
+        "without pruning, loose references are kept"
+    );
+
+    let packed = store.open_packed_buffer()?.expect("exists");
+    let names: Vec<_> = packed
+        .iter()?
+        .map(|r| r.map(|r| r.name.as_bstr().to_string()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        names,
+        [
+            "refs/heads/main",
+            "refs/heads/packed",
+            "refs/tags/annotated",
+            "refs/tags/annotated-twice",
+            "refs/tags/lightweight"
+        ],
+        "the loose branch isn't packed"
+    );
+    assert_eq!(
+        packed.find("main")?.target(),
+        main_before.into_id(),
+        "the outdated packed reference was updated"
+    );
+    let annotated_twice = packed.find("annotated-twice")?;
+    assert_eq!(
+        annotated_twice.object(),
+        packed.find("annotated")?.object(),
+        "nested tags are peeled to the commit they ultimately point to"
+    );
+    assert_ne!(annotated_twice.target(), annotated_twice.object());
+    Ok(())
+}
+
+#[test]
+fn unreadable_loose_references_abort_packing() -> crate::Result {
+    let (dir, store, odb) = store()?;
+    let packed_refs_path = dir.path().join(".git").join("packed-refs");
+    let packed_refs_before = std::fs::read(&packed_refs_path)?;
+    let corrupt = dir.path().join(".git").join("refs").join("heads").join("corrupt");
+    std::fs::write(&corrupt, b"not a reference\n")?;
+    assert!(
+        matches!(
+            pack_refs(&store, &odb, Options { all: true, prune: true }),
+            Err(git_ref::file::pack_refs::Error::LooseIter(_))
+        ),
+        "packing can't proceed if a loose reference can't be read"
+    );
+    assert_eq!(
+        std::fs::read(&packed_refs_path)?,
+        packed_refs_before,
+        "nothing was packed"
+    );
+    Ok(())
+}
@@ -65,3 +65,303 @@ mod iter_rev {
         Ok(())
     }
 }
+
+mod expire {
+    use std::convert::TryInto;
+
+    use git_odb::Find;
+    use git_ref::{
+        file::log::expire::{Options, Outcome, Write},
+        FullNameRef,
+    };
+
+    /// Between the time of the first entries and the time of the most recent commits.
+    const CUTOFF: u32 = 947_000_000;
+
+    fn store() -> crate::Result<(git_testtools::tempfile::TempDir, git_ref::file::Store, git_odb::Handle)> {
+        store_from("make_repo_for_reflog_expiry.sh")
+    }
+
+    fn store_from(
+        script_name: &str,
+    ) -> crate::Result<(git_testtools::tempfile::TempDir, git_ref::file::Store, git_odb::Handle)> {
+        let dir = git_testtools::scripted_fixture_repo_writable(script_name)?;
+        let git_dir = dir.path().join(".git");
+        let odb = git_odb::at(git_dir.join("objects"))?;
+        let store = git_ref::file::Store::at(git_dir, git_ref::store::WriteReflog::Normal, git_hash::Kind::Sha1);
+        Ok((dir, store, odb))
+    }
+
+    fn name(name: &str) -> &FullNameRef {
+        name.try_into().expect("valid")
+    }
+
+    fn messages(store: &git_ref::file::Store, name: &str) -> crate::Result<Vec<String>> {
+        let mut buf = Vec::new();
+        Ok(store
+            .reflog_iter(name, &mut buf)?
+            .expect("exists")
+            .map(|line| line.map(|line| line.message.to_string()))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn new_oids(store: &git_ref::file::Store, name: &str) -> crate::Result<Vec<git_hash::ObjectId>> {
+        let mut buf = Vec::new();
+        Ok(store
+            .reflog_iter(name, &mut buf)?
+            .expect("exists")
+            .map(|line| line.map(|line| line.new_oid()))
+            .collect::<Result<_, _>>()?)
+    }
+
+    fn expire(
+        store: &git_ref::file::Store,
+        odb: &git_odb::Handle,
+        ref_name: &str,
+        options: Options,
+    ) -> Result<Outcome, git_ref::file::log::expire::Error> {
+        store.reflog_expire(
+            name(ref_name),
+            options,
+            git_lock::acquire::Fail::Immediately,
+            |id, buf| -> Result<Option<(git_object::Kind, &[u8])>, git_odb::store::find::Error> {
+                Ok(odb.try_find(id, buf)?.map(|d| (d.kind, d.data)))
+            },
+        )
+    }
+
+    #[test]
+    fn old_entries_are_removed() -> crate::Result {
+        let (_dir, store, odb) = store()?;
+        let options = Options {
+            older_than: Some(CUTOFF),
+            ..Default::default()
+        };
+        assert_eq!(
+            expire(&store, &odb, "refs/heads/main", options)?,
+            Outcome { kept: 1, removed: 4 }
+        );
+        assert_eq!(messages(&store, "refs/heads/main")?, ["commit: c4"]);
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_entries_are_removed_and_head_considers_all_references() -> crate::Result {
+        let (_dir, store, odb) = store()?;
+        let options = Options {
+            unreachable_older_than: Some(CUTOFF),
+            ..Default::default()
+        };
+        assert_eq!(
+            expire(&store, &odb, "refs/heads/main", options)?,
+            Outcome { kept: 2, removed: 3 },
+            "c2 and c3 were reset away"
+        );
+        assert_eq!(
+            messages(&store, "refs/heads/main")?,
+            ["commit (initial): c1", "commit: c4"]
+        );
+
+        assert_eq!(
+            expire(&store, &odb, "HEAD", options)?,
+            Outcome { kept: 5, removed: 3 },
+            "the commit of the 'other' branch is still reachable"
+        );
+        assert_eq!(
+            messages(&store, "HEAD")?,
+            [
+                "commit (initial): c1",
+                "commit: c4",
+                "checkout: moving from main to other",
+                "commit: o1",
+                "checkout: moving from other to main"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn head_considers_all_references_even_if_it_is_unborn() -> crate::Result {
+        let (_dir, store, odb) = store()?;
+        std::fs::write(store.git_dir().join("HEAD"), b"ref: refs/heads/unborn\n")?;
+        let options = Options {
+            unreachable_older_than: Some(CUTOFF),
+            ..Default::default()
+        };
+        assert_eq!(
+            expire(&store, &odb, "HEAD", options)?,
+            Outcome { kept: 5, removed: 3 },
+            "only entries not reachable from any reference are removed"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tips_that_fail_to_resolve_abort_without_removing_entries() -> crate::Result {
+        let (dir, store, odb) = store()?;
+        let options = Options {
+            unreachable_older_than: Some(CUTOFF),
+            ..Default::default()
+        };
+        let before = messages(&store, "refs/heads/main")?;
+        let tip = *new_oids(&store, "refs/heads/main")?.last().expect("at least one entry");
+        let err = store
+            .reflog_expire(
+                name("refs/heads/main"),
+                options,
+                git_lock::acquire::Fail::Immediately,
+                |id, buf| -> Result<Option<(git_object::Kind, &[u8])>, std::io::Error> {
+                    if id == tip {
+                        return Err(std::io::Error::new(std::io::ErrorKind::Other, "transient"));
+                    }
+                    Ok(odb.try_find(id, buf).expect("valid odb").map(|d| (d.kind, d.data)))
+                },
+            )
+            .expect_err("the tip can't be peeled");
+        assert!(
+            matches!(err, git_ref::file::log::expire::Error::PeelReference(_)),
+            "{:?}",
+            err
+        );
+        assert_eq!(messages(&store, "refs/heads/main")?, before, "nothing was removed");
+
+        let before = messages(&store, "HEAD")?;
+        std::fs::write(dir.path().join(".git/refs/heads/corrupt"), b"not a reference\n")?;
+        assert!(
+            matches!(
+                expire(&store, &odb, "HEAD", options),
+                Err(git_ref::file::log::expire::Error::IterReferences(_))
+            ),
+            "the tips of all references are needed for HEAD"
+        );
+        assert_eq!(messages(&store, "HEAD")?, before, "nothing was removed");
+        Ok(())
+    }
+
+    #[test]
+    fn stale_entries_are_removed() -> crate::Result {
+        let (_dir, store, odb) = store()?;
+        let c3 = new_oids(&store, "refs/heads/main")?[2].to_hex().to_string();
+        std::fs::remove_file(store.git_dir().join("objects").join(&c3[..2]).join(&c3[2..]))?;
+        let odb = {
+            drop(odb);
+            git_odb::at(store.git_dir().join("objects"))?
+        };
+        let options = Options {
+            stale_fix: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            expire(&store, &odb, "refs/heads/main", options)?,
+            Outcome { kept: 3, removed: 2 }
+        );
+        assert_eq!(
+            messages(&store, "refs/heads/main")?,
+            ["commit (initial): c1", "commit: c2", "commit: c4"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn entries_of_commits_with_incomplete_trees_or_ancestors_are_stale() -> crate::Result {
+        let (dir, store, odb) = store_from("make_repo_for_reflog_stale_fix.sh")?;
+        let blob = std::fs::read_to_string(dir.path().join("blob-to-remove"))?;
+        let blob = blob.trim();
+        std::fs::remove_file(store.git_dir().join("objects").join(&blob[..2]).join(&blob[2..]))?;
+        let odb = {
+            drop(odb);
+            git_odb::at(store.git_dir().join("objects"))?
+        };
+        let options = Options {
+            stale_fix: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            expire(&store, &odb, "refs/heads/main", options)?,
+            Outcome { kept: 2, removed: 3 },
+            "c2 misses a blob in a nested tree, c3 has c2 as parent, and the reset came from c3"
+        );
+        assert_eq!(
+            messages(&store, "refs/heads/main")?,
+            ["commit (initial): c1", "commit: c4"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dry_runs_and_locked_references_leave_the_reflog_untouched() -> crate::Result {
+        let (_dir, store, odb) = store()?;
+        let before = messages(&store, "refs/heads/main")?;
+        let options = Options {
+            older_than: Some(u32::MAX),
+            write: Write {
+                dry_run: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            expire(&store, &odb, "refs/heads/main", options)?,
+            Outcome { kept: 0, removed: 5 }
+        );
+        assert_eq!(messages(&store, "refs/heads/main")?, before);
+
+        let _lock = git_lock::Marker::acquire_to_hold_resource(
+            store.git_dir().join("refs/heads/main"),
+            git_lock::acquire::Fail::Immediately,
+            None,
+        )?;
+        assert!(matches!(
+            expire(&store, &odb, "refs/heads/main", Options::default()),
+            Err(git_ref::file::log::expire::Error::LockAcquire(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn single_entries_can_be_deleted_with_rewrite_and_update_ref() -> crate::Result {
+        let (_dir, store, _odb) = store()?;
+        let outcome = store.reflog_delete(
+            name("refs/heads/main"),
+            &[1, 2],
+            Write {
+                rewrite: true,
+                ..Default::default()
+            },
+            git_lock::acquire::Fail::Immediately,
+        )?;
+        assert_eq!(outcome, Outcome { kept: 3, removed: 2 });
+        let mut buf = Vec::new();
+        let lines = store
+            .reflog_iter("refs/heads/main", &mut buf)?
+            .expect("exists")
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            lines.iter().map(|l| l.message.to_string()).collect::<Vec<_>>(),
+            ["commit (initial): c1", "commit: c2", "commit: c4"]
+        );
+        assert_eq!(
+            lines[2].previous_oid(),
+            lines[1].new_oid(),
+            "the previous id follows the last kept entry"
+        );
+
+        let c2 = lines[1].new_oid();
+        store.reflog_delete(
+            name("refs/heads/main"),
+            &[0],
+            Write {
+                update_ref: true,
+                ..Default::default()
+            },
+            git_lock::acquire::Fail::Immediately,
+        )?;
+        assert_eq!(
+            store.find("main")?.target.try_id(),
+            Some(c2.as_ref()),
+            "the reference is set to the most recent entry"
+        );
+        assert_eq!(messages(&store, "HEAD")?.len(), 8, "HEAD is symbolic and unaffected");
+        Ok(())
+    }
+}
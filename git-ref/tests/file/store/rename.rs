@@ -0,0 +1,112 @@
+use std::convert::TryInto;
+
+use git_ref::{
+    file::rename::{Error, Options},
+    FullNameRef, Target,
+};
+
+use crate::file::store_writable;
+
+fn rename(store: &git_ref::file::Store, from: &str, to: &str, force: bool) -> Result<git_ref::Reference, Error> {
+    let from: &FullNameRef = from.try_into().expect("valid");
+    let to: &FullNameRef = to.try_into().expect("valid");
+    store.rename_reference(
+        from,
+        to,
+        Options {
+            force,
+            message: format!("Branch: renamed {} to {}", from.as_bstr(), to.as_bstr()).into(),
+        },
+        git_actor::SignatureRef::default(),
+        git_lock::acquire::Fail::Immediately,
+        git_lock::acquire::Fail::Immediately,
+    )
+}
+
+fn reflog_messages(store: &git_ref::file::Store, name: &str) -> crate::Result<Option<Vec<String>>> {
+    let mut buf = Vec::new();
+    Ok(match store.reflog_iter(name, &mut buf)? {
+        Some(iter) => Some(
+            iter.map(|line| line.map(|line| line.message.to_string()))
+                .collect::<Result<_, _>>()?,
+        ),
+        None => None,
+    })
+}
+
+#[test]
+fn loose_and_packed_references_are_renamed_along_with_their_reflog() -> crate::Result {
+    let (_dir, store) = store_writable("make_repo_for_pack_refs.sh")?;
+    for (from, to) in [
+        ("refs/heads/loose", "refs/heads/renamed"),
+        ("refs/heads/packed", "refs/heads/packed/nested"),
+    ] {
+        let expected_id = store.find(from)?.target.into_id();
+        let renamed = rename(&store, from, to, false)?;
+        assert_eq!(renamed.name.as_bstr(), to);
+        assert_eq!(renamed.target, Target::Peeled(expected_id));
+
+        assert!(store.try_find(from)?.is_none(), "the old reference is gone");
+        assert_eq!(store.find(to)?.target.into_id(), expected_id);
+        assert_eq!(
+            reflog_messages(&store, from)?,
+            None,
+            "the reflog was moved away from the old reference"
+        );
+        let messages = reflog_messages(&store, to)?.expect("moved reflog");
+        assert_eq!(
+            messages,
+            [
+                "branch: Created from main".to_string(),
+                format!("Branch: renamed {} to {}", from, to)
+            ],
+            "the previous reflog entries are kept"
+        );
+    }
+    assert!(
+        store
+            .open_packed_buffer()?
+            .expect("exists")
+            .try_find("refs/heads/packed")?
+            .is_none(),
+        "the old reference was removed from the packed-refs file as well"
+    );
+    Ok(())
+}
+
+#[test]
+fn existing_references_are_only_overwritten_if_forced() -> crate::Result {
+    let (_dir, store) = store_writable("make_repo_for_pack_refs.sh")?;
+    let loose_id = store.find("loose")?.target.into_id();
+    assert!(matches!(
+        rename(&store, "refs/heads/loose", "refs/heads/main", false),
+        Err(Error::Exists { .. })
+    ));
+    assert!(store.try_find("loose")?.is_some(), "nothing changed");
+    assert_eq!(
+        reflog_messages(&store, "refs/heads/loose")?.map(|m| m.len()),
+        Some(1),
+        "the reflog is still in place"
+    );
+
+    rename(&store, "refs/heads/loose", "refs/heads/main", true)?;
+    assert!(store.try_find("loose")?.is_none());
+    assert_eq!(store.find("main")?.target.into_id(), loose_id);
+    assert_eq!(
+        reflog_messages(&store, "refs/heads/main")?.expect("moved reflog").len(),
+        2,
+        "the reflog of the overwritten reference is replaced"
+    );
+    Ok(())
+}
+
+#[test]
+fn symbolic_references_cannot_be_renamed() -> crate::Result {
+    let (_dir, store) = store_writable("make_repo_for_pack_refs.sh")?;
+    assert!(matches!(
+        rename(&store, "refs/heads/symbolic", "refs/heads/other", false),
+        Err(Error::Symbolic { .. })
+    ));
+    assert!(store.try_find("symbolic")?.is_some());
+    Ok(())
+}
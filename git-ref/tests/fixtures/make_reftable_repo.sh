@@ -0,0 +1,23 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q --ref-format=reftable
+git config core.logAllRefUpdates true
+
+git checkout -q -b main
+git commit -q --allow-empty -m c1
+git commit -q --allow-empty -m c2
+git tag -m "annotated" v1
+git commit -q --allow-empty -m c3
+
+# enough branches for git to write more than a few ref blocks, which makes it add a ref index.
+for i in $(seq 1 2000); do
+  echo "create refs/heads/branch-with-a-rather-long-name-$i HEAD~1"
+done | git update-ref -m "create branch" --stdin
+git symbolic-ref refs/remotes/origin/HEAD refs/heads/main
+
+# compact everything into a single table
+git pack-refs --all
+
+git show-ref --head > show-ref.list
+git reflog show --format='%H %gs' refs/heads/main > main-reflog.list
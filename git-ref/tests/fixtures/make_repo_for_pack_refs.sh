@@ -0,0 +1,18 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+
+git checkout -q -b main
+git commit -q --allow-empty -m c1
+git tag -a -m "annotated" annotated
+git tag lightweight
+git branch packed
+git pack-refs --all
+
+git commit -q --allow-empty -m c2
+git branch loose
+git -c advice.nestedTag=false tag -a -m "annotated of annotated" annotated-twice annotated
+git symbolic-ref refs/heads/symbolic refs/heads/main
+git update-ref refs/bisect/bad HEAD
+echo 0000000000000000000000000000000000000001 > .git/refs/heads/broken
@@ -0,0 +1,16 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+
+git checkout -q -b main
+git commit -q --allow-empty -m c1
+git commit -q --allow-empty -m c2
+git commit -q --allow-empty -m c3
+
+git reset -q --hard HEAD~2
+GIT_COMMITTER_DATE="2000-01-10 00:00:00 +0000" git commit -q --allow-empty -m c4
+
+git checkout -q -b other
+GIT_COMMITTER_DATE="2000-01-10 00:00:00 +0000" git commit -q --allow-empty -m o1
+git checkout -q main
@@ -0,0 +1,16 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+
+git checkout -q -b main
+git commit -q --allow-empty -m c1
+mkdir -p dir/sub
+echo content > dir/sub/file
+git add dir
+git commit -q -m c2
+git rev-parse HEAD:dir/sub/file > blob-to-remove
+git commit -q --allow-empty -m c3
+
+git reset -q --hard HEAD~2
+git commit -q --allow-empty -m c4
@@ -12,6 +12,8 @@ mod packed;
 #[cfg(not(feature = "internal-testing-git-features-parallel"))]
 mod reference;
 #[cfg(not(feature = "internal-testing-git-features-parallel"))]
+mod reftable;
+#[cfg(not(feature = "internal-testing-git-features-parallel"))]
 mod store;
 #[cfg(not(feature = "internal-testing-git-features-parallel"))]
 mod transaction;
@@ -0,0 +1,148 @@
+use std::{convert::TryInto, path::Path, process::Command};
+
+use git_hash::ObjectId;
+use git_ref::{reftable, FullNameRef, Target};
+
+use crate::reftable::{commit, delete, update};
+
+fn store_at(git_dir: &Path) -> reftable::Store {
+    reftable::Store::at(git_dir, git_ref::store::WriteReflog::Normal, git_hash::Kind::Sha1)
+}
+
+fn name(name: &str) -> &FullNameRef {
+    name.try_into().expect("valid")
+}
+
+/// Return `(oid, name)` pairs from `file` with lines like `<oid> <rest>`.
+fn read_list(file: impl AsRef<Path>) -> crate::Result<Vec<(ObjectId, String)>> {
+    std::fs::read_to_string(file)?
+        .lines()
+        .map(|line| {
+            let (oid, rest) = line.split_once(' ').expect("oid and name");
+            Ok((ObjectId::from_hex(oid.as_bytes())?, rest.to_owned()))
+        })
+        .collect()
+}
+
+fn git(git_dir: &Path, args: &[&str]) -> crate::Result<String> {
+    let out = Command::new("git").arg("--git-dir").arg(git_dir).args(args).output()?;
+    assert!(
+        out.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&out.stderr)
+    );
+    Ok(String::from_utf8(out.stdout)?)
+}
+
+#[test]
+fn tables_written_by_git_with_index_and_log_blocks_can_be_read() -> crate::Result {
+    if git_testtools::should_skip_as_git_version_is_smaller_than(2, 45, 0) {
+        return Ok(());
+    }
+    let dir = git_testtools::scripted_fixture_repo_read_only("make_reftable_repo.sh")?;
+    let store = store_at(&dir.join(".git"));
+
+    let stack = store.stack()?.expect("tables present");
+    assert_eq!(stack.tables().len(), 1, "all tables were compacted into one");
+    let table_name = std::fs::read_to_string(store.reftable_dir().join("tables.list"))?;
+    let data = std::fs::read(store.reftable_dir().join(table_name.trim()))?;
+    let (header_len, footer_len) = if data[4] == 1 { (24, 68) } else { (28, 72) };
+    let footer = &data[data.len() - footer_len..];
+    let ref_index_position = u64::from_be_bytes(footer[header_len..][..8].try_into()?);
+    assert_ne!(
+        ref_index_position, 0,
+        "git wrote a ref index, which is skipped when reading"
+    );
+    assert!(
+        !stack.tables()[0].logs()?.is_empty(),
+        "the table has a log block as well"
+    );
+
+    let expected = read_list(dir.join("show-ref.list"))?;
+    assert!(expected.len() > 2000);
+    let actual: Vec<_> = store.iter()?.collect();
+    assert_eq!(actual.len(), expected.len(), "all references were found");
+    for (reference, (expected_id, expected_name)) in actual.iter().zip(&expected) {
+        assert_eq!(reference.name.as_bstr(), expected_name.as_str());
+        let id = match &reference.target {
+            Target::Peeled(id) => *id,
+            Target::Symbolic(target) => store.find(target.as_ref())?.target.try_id().expect("peeled").to_owned(),
+        };
+        assert_eq!(id, *expected_id, "{}", expected_name);
+        assert_eq!(
+            store.find(expected_name.as_str())?.name,
+            reference.name,
+            "lookups work as well"
+        );
+    }
+    assert_eq!(
+        store.find("HEAD")?.target,
+        Target::Symbolic("refs/heads/main".try_into()?),
+        "HEAD is stored in the table, not in the HEAD file"
+    );
+
+    let mut expected = read_list(dir.join("main-reflog.list"))?;
+    expected.reverse();
+    let log = store.reflog(name("refs/heads/main"))?.expect("present");
+    assert_eq!(
+        log.iter()
+            .map(|line| (line.new_oid, line.message.to_string()))
+            .collect::<Vec<_>>(),
+        expected
+    );
+    Ok(())
+}
+
+#[test]
+fn tables_written_on_top_of_those_written_by_git_are_accepted_by_git() -> crate::Result {
+    if git_testtools::should_skip_as_git_version_is_smaller_than(2, 45, 0) {
+        return Ok(());
+    }
+    let dir = git_testtools::scripted_fixture_repo_writable("make_reftable_repo.sh")?;
+    let git_dir = dir.path().join(".git");
+    let store = store_at(&git_dir);
+    let head_id = store.find("main")?.target.try_id().expect("peeled").to_owned();
+
+    commit(
+        &store,
+        [
+            update("refs/heads/new", Target::Peeled(head_id), "created by us"),
+            delete("refs/heads/branch-with-a-rather-long-name-42"),
+        ],
+    )?;
+    assert_eq!(store.stack()?.expect("present").tables().len(), 2, "we added a table");
+
+    assert_eq!(
+        git(&git_dir, &["rev-parse", "refs/heads/new"])?.trim(),
+        head_id.to_string()
+    );
+    assert_eq!(
+        git(&git_dir, &["reflog", "show", "--format=%gs", "refs/heads/new"])?.trim(),
+        "created by us"
+    );
+    let refs = git(&git_dir, &["for-each-ref", "--format=%(refname)"])?;
+    assert!(refs.lines().any(|name| name == "refs/heads/new"));
+    assert!(
+        !refs
+            .lines()
+            .any(|name| name == "refs/heads/branch-with-a-rather-long-name-42"),
+        "deletions are seen by git"
+    );
+
+    git(
+        &git_dir,
+        &["update-ref", "-m", "by git", "refs/heads/after", "refs/heads/new"],
+    )?;
+    git(&git_dir, &["pack-refs", "--all"])?;
+    assert_eq!(
+        store.find("after")?.target,
+        Target::Peeled(head_id),
+        "git can add to and compact our stack"
+    );
+    assert_eq!(
+        store.reflog(name("refs/heads/new"))?.expect("present")[0].message,
+        "created by us"
+    );
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use std::convert::TryInto;
+
+use git_hash::ObjectId;
+use git_ref::{
+    reftable,
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    Target,
+};
+
+pub(crate) use crate::file::transaction::prepare_and_commit::committer;
+
+fn store() -> crate::Result<(tempfile::TempDir, reftable::Store)> {
+    let dir = tempfile::tempdir()?;
+    let store = reftable::Store::at(dir.path(), git_ref::store::WriteReflog::Normal, git_hash::Kind::Sha1);
+    Ok((dir, store))
+}
+
+fn id(n: u8) -> ObjectId {
+    ObjectId::from([n; 20])
+}
+
+pub(crate) fn update(name: &str, new: Target, message: &str) -> RefEdit {
+    RefEdit {
+        change: Change::Update {
+            log: LogChange {
+                mode: RefLog::AndReference,
+                force_create_reflog: false,
+                message: message.into(),
+            },
+            expected: PreviousValue::Any,
+            new,
+        },
+        name: name.try_into().expect("valid"),
+        deref: false,
+    }
+}
+
+fn delete(name: &str) -> RefEdit {
+    RefEdit {
+        change: Change::Delete {
+            expected: PreviousValue::MustExist,
+            log: RefLog::AndReference,
+        },
+        name: name.try_into().expect("valid"),
+        deref: false,
+    }
+}
+
+fn commit(store: &reftable::Store, edits: impl IntoIterator<Item = RefEdit>) -> crate::Result<Vec<RefEdit>> {
+    Ok(store
+        .transaction()
+        .prepare(edits, git_lock::acquire::Fail::Immediately)?
+        .commit(committer().to_ref())?)
+}
+
+mod git;
+mod table;
+mod transaction;
@@ -0,0 +1,142 @@
+use git_ref::reftable::{
+    record::{self, Value},
+    write, Table,
+};
+
+use crate::reftable::{committer, id};
+
+fn refs(count: usize) -> Vec<record::Ref> {
+    (0..count)
+        .map(|n| record::Ref {
+            name: format!("refs/tags/build-{:06}", n).try_into().expect("valid"),
+            update_index: 1 + (n % 3) as u64,
+            value: match n % 4 {
+                0 => Value::Peeled {
+                    target: id(n as u8),
+                    peeled: Some(id(!n as u8)),
+                },
+                1 => Value::Symbolic("refs/heads/main".try_into().expect("valid")),
+                2 => Value::Deletion,
+                _ => Value::Peeled {
+                    target: id(n as u8),
+                    peeled: None,
+                },
+            },
+        })
+        .collect()
+}
+
+fn logs(count: usize) -> Vec<record::Log> {
+    (0..count)
+        .map(|n| record::Log {
+            name: if n % 2 == 0 { "HEAD" } else { "refs/heads/main" }
+                .try_into()
+                .expect("valid"),
+            update_index: 1 + n as u64 / 2,
+            line: (n % 5 != 4).then(|| git_ref::log::Line {
+                previous_oid: id(n as u8),
+                new_oid: id((n as u8).wrapping_add(1)),
+                signature: committer(),
+                message: format!("message {}", n).into(),
+            }),
+        })
+        .collect()
+}
+
+fn write_table(refs: Vec<record::Ref>, logs: Vec<record::Log>, options: write::Options) -> crate::Result<Table> {
+    let mut buf = Vec::new();
+    write::table(refs, logs, 1, 3, git_hash::Kind::Sha1, options, &mut buf)?;
+    Ok(Table::from_bytes(buf, "table.ref")?)
+}
+
+#[test]
+fn refs_spanning_many_blocks_can_be_found_and_iterated() -> crate::Result {
+    let expected = refs(2000);
+    let options = write::Options {
+        block_size: 256,
+        restart_interval: 4,
+    };
+    let table = write_table(expected.clone(), Vec::new(), options)?;
+    assert_eq!(table.min_update_index(), 1);
+    assert_eq!(table.max_update_index(), 3);
+    assert_eq!(table.object_hash(), git_hash::Kind::Sha1);
+
+    for record in &expected {
+        assert_eq!(
+            table.find_ref(record.name.as_bstr())?.as_ref(),
+            Some(record),
+            "every record can be found by binary search"
+        );
+    }
+    assert_eq!(table.find_ref(b"refs/tags/build-000000-missing")?, None);
+    assert_eq!(table.find_ref(b"refs/heads/main")?, None, "before the first record");
+    assert_eq!(table.find_ref(b"refs/tags/zzz")?, None, "past the last record");
+
+    assert_eq!(table.refs_with_prefix(b"")?, expected);
+    assert_eq!(
+        table.refs_with_prefix(b"refs/tags/build-0012")?,
+        expected[1200..1300].to_vec(),
+        "prefixes may span multiple blocks"
+    );
+    assert!(table.refs_with_prefix(b"refs/heads/")?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn logs_are_compressed_and_ordered_with_the_most_recent_entry_first() -> crate::Result {
+    let mut expected = logs(500);
+    let table = write_table(refs(10), expected.clone(), write::Options::default())?;
+
+    expected.sort_by(|a, b| a.name.cmp(&b.name).then(b.update_index.cmp(&a.update_index)));
+    assert_eq!(table.logs()?, expected);
+    let head_logs = table.logs_of(b"HEAD")?;
+    assert_eq!(head_logs.len(), 250);
+    assert!(head_logs
+        .windows(2)
+        .all(|logs| logs[0].update_index > logs[1].update_index));
+    assert_eq!(
+        head_logs
+            .last()
+            .and_then(|log| log.line.as_ref())
+            .map(|line| &line.message),
+        Some(&"message 0".into()),
+        "messages are stored with a trailing newline that is removed when reading"
+    );
+    assert!(table.logs_of(b"refs/heads/other")?.is_empty());
+    assert_eq!(table.refs_with_prefix(b"")?, refs(10), "refs are unaffected");
+    Ok(())
+}
+
+#[test]
+fn tables_with_only_logs_or_nothing_at_all_can_be_read() -> crate::Result {
+    let expected = logs(3);
+    let table = write_table(Vec::new(), expected.clone(), write::Options::default())?;
+    assert!(table.refs_with_prefix(b"")?.is_empty());
+    assert_eq!(table.logs()?.len(), expected.len());
+
+    let table = write_table(Vec::new(), Vec::new(), write::Options::default())?;
+    assert!(table.refs_with_prefix(b"")?.is_empty());
+    assert!(table.logs()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn corruption_is_detected() -> crate::Result {
+    let mut buf = Vec::new();
+    write::table(
+        refs(3),
+        Vec::new(),
+        1,
+        3,
+        git_hash::Kind::Sha1,
+        Default::default(),
+        &mut buf,
+    )?;
+    let last = buf.len() - 1;
+    buf[last] ^= 1;
+    assert!(matches!(
+        Table::from_bytes(buf, "table.ref"),
+        Err(git_ref::reftable::decode::Error::Corrupt { .. })
+    ));
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use std::convert::TryInto;
+
+use git_ref::{
+    reftable,
+    transaction::{Change, PreviousValue, RefEdit},
+    FullNameRef, Target,
+};
+
+use crate::reftable::{commit, delete, id, store, update};
+
+fn name(name: &str) -> &FullNameRef {
+    name.try_into().expect("valid")
+}
+
+#[test]
+fn updates_are_visible_to_lookups_iteration_and_reflogs() -> crate::Result {
+    let (_dir, store) = store()?;
+    assert_eq!(store.try_find("main")?, None, "there are no tables yet");
+    assert_eq!(store.iter()?.count(), 0);
+
+    let edits = commit(
+        &store,
+        [
+            update("refs/heads/main", Target::Peeled(id(1)), "first"),
+            update("HEAD", Target::Symbolic("refs/heads/main".try_into()?), ""),
+            update("refs/tags/v1", Target::Peeled(id(2)), "tag"),
+            update(
+                "refs/remotes/origin/HEAD",
+                Target::Symbolic("refs/remotes/origin/main".try_into()?),
+                "",
+            ),
+            update("refs/remotes/origin/main", Target::Peeled(id(1)), "fetch"),
+        ],
+    )?;
+    assert_eq!(edits.len(), 5);
+
+    assert_eq!(store.find("main")?.target, Target::Peeled(id(1)));
+    assert_eq!(store.find("v1")?.name.as_bstr(), "refs/tags/v1");
+    assert_eq!(
+        store.find("origin")?.target,
+        Target::Symbolic("refs/remotes/origin/main".try_into()?),
+        "partial names are resolved like git does"
+    );
+    assert_eq!(
+        store.find("HEAD")?.target,
+        Target::Symbolic("refs/heads/main".try_into()?)
+    );
+    assert!(matches!(
+        store.find("missing"),
+        Err(reftable::find::existing::Error::NotFound { .. })
+    ));
+
+    let names: Vec<_> = store.iter()?.map(|r| r.name.as_bstr().to_string()).collect();
+    assert_eq!(
+        names,
+        [
+            "refs/heads/main",
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main",
+            "refs/tags/v1"
+        ],
+        "HEAD isn't part of the iteration, which is sorted"
+    );
+    assert_eq!(store.iter_prefixed("refs/remotes/")?.count(), 2);
+
+    let log = store.reflog(name("refs/heads/main"))?.expect("present");
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].previous_oid, git_hash::Kind::Sha1.null());
+    assert_eq!(log[0].new_oid, id(1));
+    assert_eq!(log[0].message, "first");
+    assert_eq!(log[0].signature, crate::reftable::committer());
+    assert!(
+        !store.reflog_exists(name("refs/tags/v1"))?,
+        "tags don't get a reflog by default"
+    );
+    assert!(
+        !store.reflog_exists(name("HEAD"))?,
+        "symbolic refs don't get a reflog entry when changed"
+    );
+
+    let mut edit = update("HEAD", Target::Peeled(id(3)), "commit");
+    edit.deref = true;
+    let edits = commit(&store, Some(edit))?;
+    assert_eq!(edits.len(), 2, "the referent is updated as well");
+    assert_eq!(store.find("main")?.target, Target::Peeled(id(3)));
+    assert_eq!(
+        store.find("HEAD")?.target,
+        Target::Symbolic("refs/heads/main".try_into()?)
+    );
+
+    let log = store.reflog(name("refs/heads/main"))?.expect("present");
+    assert_eq!(log.len(), 2, "the oldest entry comes first");
+    assert_eq!((log[1].previous_oid, log[1].new_oid), (id(1), id(3)));
+    let log = store.reflog(name("HEAD"))?.expect("present");
+    assert_eq!(log.len(), 1);
+    assert_eq!((log[0].previous_oid, log[0].new_oid), (id(1), id(3)));
+    Ok(())
+}
+
+#[test]
+fn deletions_remove_the_reference_and_its_log() -> crate::Result {
+    let (_dir, store) = store()?;
+    commit(
+        &store,
+        [
+            update("refs/heads/main", Target::Peeled(id(1)), "first"),
+            update("refs/heads/feature", Target::Peeled(id(2)), "branch"),
+        ],
+    )?;
+    commit(
+        &store,
+        Some(update("refs/heads/feature", Target::Peeled(id(3)), "commit")),
+    )?;
+    assert_eq!(store.reflog(name("refs/heads/feature"))?.map(|log| log.len()), Some(2));
+
+    let edits = commit(&store, Some(delete("refs/heads/feature")))?;
+    assert!(
+        matches!(&edits[0].change, Change::Delete { expected: PreviousValue::MustExistAndMatch(Target::Peeled(previous)), .. } if *previous == id(3)),
+        "the previous value is provided"
+    );
+    assert_eq!(store.try_find("feature")?, None);
+    assert!(!store.reflog_exists(name("refs/heads/feature"))?);
+    assert_eq!(store.iter()?.count(), 1);
+    assert_eq!(
+        store.find("main")?.target,
+        Target::Peeled(id(1)),
+        "other refs are unaffected"
+    );
+
+    assert!(matches!(
+        store
+            .transaction()
+            .prepare(Some(delete("refs/heads/feature")), git_lock::acquire::Fail::Immediately),
+        Err(reftable::transaction::prepare::Error::DeleteReferenceMustExist { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn expectations_are_checked_and_locks_prevent_concurrent_transactions() -> crate::Result {
+    let (_dir, store) = store()?;
+    commit(&store, Some(update("refs/heads/main", Target::Peeled(id(1)), "")))?;
+
+    let mut edit = update("refs/heads/main", Target::Peeled(id(2)), "");
+    if let Change::Update { expected, .. } = &mut edit.change {
+        *expected = PreviousValue::MustExistAndMatch(Target::Peeled(id(9)));
+    }
+    assert!(matches!(
+        store
+            .transaction()
+            .prepare(Some(edit), git_lock::acquire::Fail::Immediately),
+        Err(reftable::transaction::prepare::Error::ReferenceOutOfDate { .. })
+    ));
+
+    let mut edit = update("refs/heads/main", Target::Peeled(id(2)), "");
+    if let Change::Update { expected, .. } = &mut edit.change {
+        *expected = PreviousValue::MustNotExist;
+    }
+    assert!(matches!(
+        store
+            .transaction()
+            .prepare(Some(edit), git_lock::acquire::Fail::Immediately),
+        Err(reftable::transaction::prepare::Error::MustNotExist { .. })
+    ));
+
+    let prepared = store.transaction().prepare(
+        Some(update("refs/heads/other", Target::Peeled(id(2)), "")),
+        git_lock::acquire::Fail::Immediately,
+    )?;
+    assert!(matches!(
+        store.transaction().prepare(
+            Some(update("refs/heads/third", Target::Peeled(id(3)), "")),
+            git_lock::acquire::Fail::Immediately
+        ),
+        Err(reftable::transaction::prepare::Error::LockAcquire(_))
+    ));
+    let edits: Vec<RefEdit> = prepared.rollback();
+    assert_eq!(edits.len(), 1);
+    assert_eq!(
+        store.try_find("other")?,
+        None,
+        "rolled back transactions have no effect"
+    );
+    Ok(())
+}
+
+#[test]
+fn tables_are_compacted_automatically_and_on_demand() -> crate::Result {
+    let (_dir, mut store) = store()?;
+    // Small blocks keep padding from dominating table sizes, so the stack keeps more than one table.
+    store.write_options.block_size = 256;
+    for n in 0..20u8 {
+        commit(
+            &store,
+            [
+                update(&format!("refs/heads/branch-{}", n), Target::Peeled(id(n)), "create"),
+                update("refs/heads/main", Target::Peeled(id(n + 1)), "commit"),
+            ],
+        )?;
+    }
+    commit(&store, Some(delete("refs/heads/branch-3")))?;
+
+    let stack = store.stack()?.expect("tables exist");
+    let num_tables = stack.tables().len();
+    assert!(
+        num_tables <= 5,
+        "the stack grows only logarithmically, got {} tables",
+        num_tables
+    );
+    assert_eq!(stack.max_update_index(), 21);
+    drop(stack);
+
+    assert_eq!(
+        store.compact(git_lock::acquire::Fail::Immediately)?,
+        num_tables > 1,
+        "compaction only happens if there is more than one table"
+    );
+    let stack = store.stack()?.expect("tables exist");
+    assert_eq!(stack.tables().len(), 1);
+    assert_eq!(
+        stack.refs_with_prefix(b"", true)?.len(),
+        20,
+        "deletions are removed when compacting everything"
+    );
+    assert_eq!(
+        std::fs::read_dir(store.reftable_dir())?.count(),
+        2,
+        "only the list and the compacted table remain"
+    );
+    assert!(!store.compact(git_lock::acquire::Fail::Immediately)?, "nothing to do");
+
+    assert_eq!(store.iter()?.count(), 20);
+    assert_eq!(store.find("main")?.target, Target::Peeled(id(20)));
+    assert_eq!(store.try_find("branch-3")?, None);
+    assert_eq!(store.reflog(name("refs/heads/main"))?.map(|log| log.len()), Some(20));
+    Ok(())
+}
@@ -18,3 +18,162 @@ fn is_send_and_sync() {
     assert_type(&store);
     assert_type(store);
 }
+
+#[cfg(not(feature = "internal-testing-git-features-parallel"))]
+mod handle {
+    use std::convert::TryInto;
+
+    use git_ref::{
+        store::{StorageFormat, WriteReflog},
+        FullNameRef, Target,
+    };
+
+    use crate::file::transaction::prepare_and_commit::committer;
+
+    fn update(name: &str, new: Target) -> git_ref::transaction::RefEdit {
+        crate::reftable::update(name, new, "update")
+    }
+
+    fn store_at(name: &str) -> crate::Result<git_ref::Store> {
+        let path = git_testtools::scripted_fixture_repo_read_only(name)?;
+        Ok(git_ref::Store::at(
+            path.join(".git"),
+            StorageFormat::Files,
+            WriteReflog::Normal,
+            git_hash::Kind::Sha1,
+        )?)
+    }
+
+    fn name(name: &str) -> &FullNameRef {
+        name.try_into().expect("valid")
+    }
+
+    #[test]
+    fn loose_stores_find_and_iterate_loose_and_packed_refs() -> crate::Result {
+        let store = store_at("make_packed_ref_repository.sh")?;
+        assert_eq!(store.storage_format(), StorageFormat::Files);
+        let handle = store.to_handle();
+        let file_store = git_ref::file::Store::at(store.git_dir(), WriteReflog::Normal, git_hash::Kind::Sha1);
+
+        let main = handle.find("main")?;
+        assert_eq!(main.name.as_bstr(), "refs/heads/main");
+        assert_eq!(main, file_store.find("main")?, "the packed buffer is used as well");
+        assert!(handle.try_find("does-not-exist")?.is_none());
+        assert!(matches!(
+            handle.find("does-not-exist"),
+            Err(git_ref::store::find::existing::Error::NotFound { .. })
+        ));
+
+        let platform = handle.iter()?;
+        assert_eq!(
+            platform.all()?.map(|r| r.ok().map(|r| r.name)).collect::<Vec<_>>(),
+            file_store
+                .iter()?
+                .all()?
+                .map(|r| r.ok().map(|r| r.name))
+                .collect::<Vec<_>>(),
+            "the same references are produced, and the broken one is an error"
+        );
+        assert_eq!(platform.prefixed("refs/heads/")?.count(), 4);
+
+        assert_eq!(
+            handle.reflog(name("refs/heads/main"))?.map(|log| log.len()),
+            Some(1),
+            "branch creation is logged"
+        );
+        assert!(!handle.reflog_exists(name("refs/heads/does-not-exist"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn transactions_use_the_backend_of_the_store() -> crate::Result {
+        for format in [StorageFormat::Files, StorageFormat::Reftable] {
+            let dir = git_testtools::tempfile::tempdir()?;
+            let store = git_ref::Store::at(dir.path(), format, WriteReflog::Normal, git_hash::Kind::Sha1)?;
+            assert_eq!(store.storage_format(), format);
+            let handle = store.to_handle();
+            let id = git_hash::ObjectId::from([1; 20]);
+
+            let edits = handle
+                .transaction()
+                .prepare(
+                    [
+                        update("refs/heads/main", Target::Peeled(id)),
+                        update("HEAD", Target::Symbolic("refs/heads/main".try_into()?)),
+                    ],
+                    git_lock::acquire::Fail::Immediately,
+                    git_lock::acquire::Fail::Immediately,
+                )?
+                .commit(committer().to_ref())?;
+            assert_eq!(edits.len(), 2);
+
+            assert_eq!(handle.find("main")?.target, Target::Peeled(id));
+            assert_eq!(
+                handle.find("HEAD")?.target,
+                Target::Symbolic("refs/heads/main".try_into()?)
+            );
+            assert_eq!(
+                handle
+                    .iter()?
+                    .all()?
+                    .map(|r| r.map(|r| r.name))
+                    .collect::<Result<Vec<_>, _>>()?,
+                vec!["refs/heads/main".try_into()?],
+                "{:?}: HEAD isn't part of the iteration",
+                format
+            );
+            assert_eq!(handle.iter()?.prefixed("refs/tags/")?.count(), 0);
+
+            let log = handle.reflog(name("refs/heads/main"))?.expect("created");
+            assert_eq!(log.len(), 1);
+            assert_eq!(log[0].new_oid, id);
+            assert_eq!(log[0].message, "update");
+
+            let rolled_back = handle
+                .transaction()
+                .prepare(
+                    Some(update("refs/heads/other", Target::Peeled(id))),
+                    git_lock::acquire::Fail::Immediately,
+                    git_lock::acquire::Fail::Immediately,
+                )?
+                .rollback();
+            assert_eq!(rolled_back.len(), 1);
+            assert!(handle.try_find("other")?.is_none());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn prefixes_naming_a_directory_only_match_its_references_in_all_backends() -> crate::Result {
+        for format in [StorageFormat::Files, StorageFormat::Reftable] {
+            let dir = git_testtools::tempfile::tempdir()?;
+            let store = git_ref::Store::at(dir.path(), format, WriteReflog::Normal, git_hash::Kind::Sha1)?;
+            let handle = store.to_handle();
+            let id = git_hash::ObjectId::from([1; 20]);
+            handle
+                .transaction()
+                .prepare(
+                    [
+                        update("refs/heads/main", Target::Peeled(id)),
+                        update("refs/heads-other", Target::Peeled(id)),
+                    ],
+                    git_lock::acquire::Fail::Immediately,
+                    git_lock::acquire::Fail::Immediately,
+                )?
+                .commit(committer().to_ref())?;
+
+            let names = |prefix: &str| -> crate::Result<Vec<String>> {
+                Ok(handle
+                    .iter()?
+                    .prefixed(prefix)?
+                    .map(|r| r.map(|r| r.name.as_bstr().to_string()))
+                    .collect::<Result<_, _>>()?)
+            };
+            assert_eq!(names("refs/heads")?, ["refs/heads/main"], "{:?}", format);
+            assert_eq!(names("refs/heads/")?, ["refs/heads/main"], "{:?}", format);
+            assert_eq!(names("refs/heads/ma")?, ["refs/heads/main"], "{:?}", format);
+            assert_eq!(names("refs/heads-")?, ["refs/heads-other"], "{:?}", format);
+        }
+        Ok(())
+    }
+}
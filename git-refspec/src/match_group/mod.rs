@@ -16,12 +16,23 @@ impl<'a> MatchGroup<'a> {
             specs: specs.into_iter().filter(|s| s.op == Operation::Fetch).collect(),
         }
     }
+
+    /// Take all the push ref specs from `specs` get a match group ready.
+    pub fn from_push_specs(specs: impl IntoIterator<Item = RefSpecRef<'a>>) -> Self {
+        MatchGroup {
+            specs: specs.into_iter().filter(|s| s.op == Operation::Push).collect(),
+        }
+    }
 }
 
 /// Matching
 impl<'a> MatchGroup<'a> {
-    /// Match all `items` against all fetch specs present in this group, returning deduplicated mappings from source to destination.
-    /// Note that this method only makes sense if the specs are indeed fetch specs and may panic otherwise.
+    /// Match all `items` against all specs present in this group, returning deduplicated mappings from source to destination.
+    ///
+    /// For fetch specs, `items` are the references of the remote, and for push specs they are the local references.
+    /// Push specs without source side, i.e. those deleting remote references or pushing all matching branches, don't
+    /// participate in matching and have to be handled by the caller. Push specs without destination have their source
+    /// as destination, like `main` being equivalent to `refs/heads/main:refs/heads/main`.
     ///
     /// Note that negative matches are not part of the return value, so they are not observable but will be used to remove mappings.
     pub fn match_remotes<'item>(self, mut items: impl Iterator<Item = Item<'item>> + Clone) -> Outcome<'a, 'item> {
@@ -39,6 +50,7 @@ impl<'a> MatchGroup<'a> {
             .map(Matcher::from)
             .enumerate()
             .map(|(idx, m)| match m.lhs {
+                None => None,
                 Some(Needle::Object(id)) => {
                     push_unique(Mapping {
                         item_index: None,
@@ -62,6 +74,10 @@ impl<'a> MatchGroup<'a> {
                 if let Some(matcher) = matcher {
                     let (matched, rhs) = matcher.matches_lhs(item);
                     if matched {
+                        let rhs = match rhs {
+                            None if spec.op == Operation::Push => Some(item.full_ref_name.to_owned().into()),
+                            rhs => rhs,
+                        };
                         push_unique(Mapping {
                             item_index: Some(item_index),
                             lhs: SourceRef::FullName(item.full_ref_name),
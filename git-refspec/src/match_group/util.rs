@@ -21,7 +21,7 @@ impl<'a> Matcher<'a> {
             (Some(lhs), None) => (lhs.matches(item).is_match(), None),
             (Some(lhs), Some(rhs)) => lhs.matches(item).into_match_outcome(rhs, item),
             (None, None) | (None, Some(_)) => {
-                unreachable!("For all we know, the lefthand side is never empty. Push specs might change that.")
+                unreachable!("Specs without lefthand side don't get a matcher")
             }
         }
     }
@@ -184,3 +184,59 @@ mod multiple {
         )
     }
 }
+
+mod push {
+    use git_refspec::{match_group::Item, parse::Operation, MatchGroup};
+
+    fn mappings(specs: &[&str]) -> Vec<String> {
+        let id = git_hash::ObjectId::from_hex(b"78b1c1be9421b33a49a7a8176d93eeeafa112da1").unwrap();
+        let names = ["HEAD", "refs/heads/main", "refs/heads/f1", "refs/tags/v1"];
+        let specs: Vec<_> = specs
+            .iter()
+            .map(|spec| git_refspec::parse((*spec).into(), Operation::Push).unwrap())
+            .collect();
+        let out = MatchGroup::from_push_specs(specs).match_remotes(names.iter().map(|name| Item {
+            full_ref_name: (*name).into(),
+            target: &id,
+            object: None,
+        }));
+        out.mappings
+            .into_iter()
+            .map(|m| format!("{}:{}", m.lhs.to_owned(), m.rhs.expect("always set for push specs")))
+            .collect()
+    }
+
+    #[test]
+    fn specs_without_destination_push_to_the_same_name() {
+        assert_eq!(
+            mappings(&["main", "v1", "refs/heads/f1"]),
+            [
+                "refs/heads/main:refs/heads/main",
+                "refs/tags/v1:refs/tags/v1",
+                "refs/heads/f1:refs/heads/f1"
+            ]
+        );
+    }
+
+    #[test]
+    fn specs_with_destination_and_globs() {
+        assert_eq!(
+            mappings(&[
+                "main:other",
+                "+refs/heads/*:refs/heads/backup/*",
+                "HEAD:refs/heads/head"
+            ]),
+            [
+                "refs/heads/main:refs/heads/other",
+                "refs/heads/main:refs/heads/backup/main",
+                "refs/heads/f1:refs/heads/backup/f1",
+                "HEAD:refs/heads/head"
+            ]
+        );
+    }
+
+    #[test]
+    fn deletions_and_matching_specs_do_not_participate() {
+        assert!(mappings(&[":refs/heads/f1", ":"]).is_empty());
+    }
+}
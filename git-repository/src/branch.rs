@@ -0,0 +1,118 @@
+//! Errors for creating, renaming and deleting branches, similar to `git branch`.
+
+///
+pub mod create {
+    /// The error returned by [Repository::create_branch(…)][crate::Repository::create_branch()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        NameValidation(#[from] git_validate::reference::name::Error),
+        #[error(transparent)]
+        RevParse(#[from] crate::revision::spec::parse::single::Error),
+        #[error(transparent)]
+        FindObject(#[from] crate::object::find::existing::Error),
+        #[error(transparent)]
+        PeelToCommit(#[from] crate::object::peel::to_kind::Error),
+        #[error(transparent)]
+        Head(#[from] crate::reference::find::existing::Error),
+        #[error(transparent)]
+        FindExisting(#[from] crate::reference::find::Error),
+        #[error("A branch named '{}' already exists", name.as_bstr())]
+        Exists { name: git_ref::FullName },
+        #[error("Cannot force update the branch '{}' as it is checked out", name.as_bstr())]
+        CheckedOut { name: git_ref::FullName },
+        #[error(transparent)]
+        IsCheckedOut(#[from] super::is_checked_out::Error),
+        #[error(transparent)]
+        Edit(#[from] crate::reference::edit::Error),
+    }
+}
+
+///
+pub mod is_checked_out {
+    /// The error returned when checking if a branch is checked out in any worktree of the repository.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Head(#[from] crate::reference::find::existing::Error),
+        #[error("Could not open another worktree of the repository")]
+        OpenWorktree(#[from] Box<crate::open::Error>),
+    }
+}
+
+///
+pub mod update_config {
+    /// The error returned when updating the `branch.<name>.*` sections in the configuration file of the repository.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not lock the configuration file of the repository")]
+        AcquireLock(#[from] git_lock::acquire::Error),
+        #[error("Could not open the configuration file of the repository")]
+        Open(#[from] git_config::file::init::from_paths::Error),
+        #[error("Could not write the configuration file of the repository")]
+        Write(#[from] std::io::Error),
+        #[error("Could not move the written configuration file into place")]
+        Commit(#[from] git_lock::commit::Error<git_lock::File>),
+        #[error("The configuration could not be re-read after it was changed")]
+        Reread(#[from] crate::config::Error),
+    }
+}
+
+///
+pub mod rename {
+    /// The error returned by [Repository::rename_branch(…)][crate::Repository::rename_branch()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        NameValidation(#[from] git_validate::reference::name::Error),
+        #[error(transparent)]
+        Head(#[from] crate::reference::find::existing::Error),
+        #[error("Cannot force update the branch '{}' as it is checked out", name.as_bstr())]
+        CheckedOut { name: git_ref::FullName },
+        #[error(transparent)]
+        IsCheckedOut(#[from] super::is_checked_out::Error),
+        #[error(transparent)]
+        Rename(#[from] git_ref::file::rename::Error),
+        #[error(transparent)]
+        SetHead(#[from] crate::head::set::Error),
+        #[error("Branches can only be renamed if references are stored as files")]
+        UnsupportedStorageFormat,
+        #[error(transparent)]
+        Edit(#[from] crate::reference::edit::Error),
+        #[error(transparent)]
+        UpdateConfig(#[from] super::update_config::Error),
+        #[error("Could not interpret core.filesRefLockTimeout or core.packedRefsTimeout, it must be the number in milliseconds to wait for locks or negative to wait forever")]
+        LockTimeoutConfiguration(#[from] git_config::value::Error),
+    }
+}
+
+///
+pub mod delete {
+    /// The error returned by [Repository::delete_branch(…)][crate::Repository::delete_branch()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        NameValidation(#[from] git_validate::reference::name::Error),
+        #[error(transparent)]
+        Find(#[from] crate::reference::find::existing::Error),
+        #[error(transparent)]
+        Peel(#[from] crate::reference::peel::Error),
+        #[error(transparent)]
+        Traverse(#[from] git_traverse::commit::ancestors::Error),
+        #[error("Cannot delete the branch '{}' as it is checked out", name.as_bstr())]
+        CheckedOut { name: git_ref::FullName },
+        #[error(transparent)]
+        IsCheckedOut(#[from] super::is_checked_out::Error),
+        #[error("The branch '{}' is not fully merged into HEAD", name.as_bstr())]
+        NotFullyMerged { name: git_ref::FullName },
+        #[error(transparent)]
+        Edit(#[from] crate::reference::edit::Error),
+        #[error(transparent)]
+        UpdateConfig(#[from] super::update_config::Error),
+    }
+}
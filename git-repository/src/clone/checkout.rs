@@ -28,7 +28,11 @@ pub mod main_worktree {
         #[error(transparent)]
         IndexCheckout(
             #[from]
-            git_worktree::index::checkout::Error<git_odb::find::existing_object::Error<git_odb::store::find::Error>>,
+            Box<
+                git_worktree::index::checkout::Error<
+                    git_odb::find::existing_object::Error<git_odb::store::find::Error>,
+                >,
+            >,
         ),
         #[error("Failed to reopen object database as Arc (only if thread-safety wasn't compiled in)")]
         OpenArcOdb(#[from] std::io::Error),
@@ -36,6 +40,9 @@ pub mod main_worktree {
         FindHead(#[from] crate::reference::find::existing::Error),
         #[error("The HEAD reference could not be located")]
         PeelHeadToId(#[from] crate::head::peel::Error),
+        #[cfg(feature = "blocking-network-client")]
+        #[error("Could not fetch the blobs to check out from the promisor remote")]
+        FetchPromisedObjects(#[from] Box<crate::promisor::fetch::Error>),
     }
 
     /// Modification
@@ -45,6 +52,8 @@ pub mod main_worktree {
         ///
         /// Note that this is a no-op if the remote was empty, leaving this repository empty as well. This can be validated by checking
         /// if the `head()` of the returned repository is not unborn.
+        ///
+        /// In partial clones, all blobs to check out that are missing are fetched from the promisor remote at once beforehand.
         pub fn main_worktree(
             &mut self,
             mut progress: impl crate::Progress,
@@ -73,6 +82,23 @@ pub mod main_worktree {
                 })?;
             let mut index = git_index::File::from_state(index, repo.index_path());
 
+            #[cfg(feature = "blocking-network-client")]
+            if repo.promisor_remote_name().is_some() {
+                repo.fetch_promised_objects(
+                    index
+                        .entries()
+                        .iter()
+                        .filter(|entry| {
+                            entry.mode != git_index::entry::Mode::COMMIT
+                                && !git_odb::Find::contains(&repo.objects, entry.id)
+                        })
+                        .map(|entry| entry.id),
+                    git_features::progress::Discard,
+                    should_interrupt,
+                )
+                .map_err(Box::new)?;
+            }
+
             let mut opts = repo.config.checkout_options(repo.git_dir())?;
             opts.destination_is_initially_empty = true;
 
@@ -94,7 +120,8 @@ pub mod main_worktree {
                 &mut bytes,
                 should_interrupt,
                 opts,
-            )?;
+            )
+            .map_err(Box::new)?;
             files.show_throughput(start);
             bytes.show_throughput(start);
 
@@ -28,6 +28,8 @@ pub enum Error {
     },
     #[error("Failed to update HEAD with values from remote")]
     HeadUpdate(#[from] crate::reference::edit::Error),
+    #[error("Failed to reopen the repository after adjusting its object format to match the remote")]
+    Reopen(#[from] crate::open::Error),
 }
 
 /// Modification
@@ -44,7 +46,7 @@ impl PrepareFetch {
     #[cfg(feature = "blocking-network-client")]
     pub fn fetch_only<P>(
         &mut self,
-        progress: P,
+        mut progress: P,
         should_interrupt: &std::sync::atomic::AtomicBool,
     ) -> Result<(Repository, crate::remote::fetch::Outcome), Error>
     where
@@ -68,56 +70,92 @@ impl PrepareFetch {
                 .unwrap_or_else(|| Ok("origin".into()))?,
         };
 
-        let mut remote = repo
-            .remote_at(self.url.clone())?
-            .with_refspec(
-                format!("+refs/heads/*:refs/remotes/{remote_name}/*").as_str(),
-                crate::remote::Direction::Fetch,
-            )
-            .expect("valid static spec");
-        if let Some(f) = self.configure_remote.as_mut() {
-            remote = f(remote)?;
-        }
-
-        let config = util::write_remote_to_local_config_file(&mut remote, remote_name.clone())?;
-
-        // Add HEAD after the remote was written to config, we need it to know what to checkout later, and assure
-        // the ref that HEAD points to is present no matter what.
-        remote.fetch_specs.push(
-            git_refspec::parse(
-                format!("HEAD:refs/remotes/{remote_name}/HEAD").as_str().into(),
-                git_refspec::parse::Operation::Fetch,
-            )
-            .expect("valid")
-            .to_owned(),
-        );
-        let pending_pack: crate::remote::fetch::Prepare<'_, '_, _, _> = remote
-            .connect(crate::remote::Direction::Fetch, progress)?
-            .prepare_fetch(self.fetch_options.clone())?;
-        if pending_pack.ref_map().object_hash != repo.object_hash() {
-            unimplemented!("configure repository to expect a different object hash as advertised by the server")
+        // The remote dictates the object format, which we only learn after the handshake. If it differs from what the
+        // repository was initialized with, adjust the repository accordingly and continue with the same connection.
+        let mut object_hash_changed = false;
+        let mut handshaken_transport = None;
+        loop {
+            let mut remote = repo
+                .remote_at(self.url.clone())?
+                .with_refspec(
+                    format!("+refs/heads/*:refs/remotes/{remote_name}/*").as_str(),
+                    crate::remote::Direction::Fetch,
+                )
+                .expect("valid static spec");
+            if let Some(f) = self.configure_remote.as_mut() {
+                remote = f(remote)?;
+            }
+
+            let config =
+                util::write_remote_to_local_config_file(&mut remote, remote_name.clone(), self.filter.as_ref())?;
+
+            // Add HEAD after the remote was written to config, we need it to know what to checkout later, and assure
+            // the ref that HEAD points to is present no matter what.
+            remote.fetch_specs.push(
+                git_refspec::parse(
+                    format!("HEAD:refs/remotes/{remote_name}/HEAD").as_str().into(),
+                    git_refspec::parse::Operation::Fetch,
+                )
+                .expect("valid")
+                .to_owned(),
+            );
+            let pending_pack: crate::remote::fetch::Prepare<'_, '_, _, _> = match handshaken_transport.take() {
+                Some((transport, ref_map)) => remote
+                    .to_connection_with_transport(transport, &mut progress)
+                    .prepare_fetch_with_ref_map(ref_map),
+                None => remote
+                    .connect(crate::remote::Direction::Fetch, &mut progress)?
+                    .prepare_fetch(self.fetch_options.clone())?,
+            };
+            let remote_object_hash = pending_pack.ref_map().object_hash;
+            if remote_object_hash != repo.object_hash() {
+                if object_hash_changed {
+                    return Err(crate::remote::fetch::Error::IncompatibleObjectHash {
+                        local: repo.object_hash(),
+                        remote: remote_object_hash,
+                    }
+                    .into());
+                }
+                handshaken_transport = Some(pending_pack.into_transport_and_ref_map());
+                drop(remote);
+                util::set_object_hash_in_local_config_file(repo, remote_object_hash)?;
+                object_hash_changed = true;
+                continue;
+            }
+            let reflog_message = {
+                let mut b = self.url.to_bstring();
+                b.insert_str(0, "clone: from ");
+                b
+            };
+            let mut pending_pack = pending_pack
+                .with_write_packed_refs_only(true)
+                .with_reflog_message(RefLogMessage::Override {
+                    message: reflog_message.clone(),
+                })
+                .with_shallow(self.shallow.clone());
+            if let Some(filter) = &self.filter {
+                pending_pack = pending_pack.with_filter(filter.clone());
+            }
+            let outcome = pending_pack.receive(should_interrupt)?;
+
+            util::replace_changed_local_config_file(repo, config);
+            if self.filter.is_some() {
+                crate::promisor::set_missing_object_hook(
+                    &mut repo.objects,
+                    &repo.config.resolved,
+                    repo.refs.git_dir(),
+                    &repo.options,
+                );
+            }
+            util::update_head(
+                repo,
+                &outcome.ref_map.remote_refs,
+                reflog_message.as_ref(),
+                &remote_name,
+            )?;
+
+            return Ok((self.repo.take().expect("still present"), outcome));
         }
-        let reflog_message = {
-            let mut b = self.url.to_bstring();
-            b.insert_str(0, "clone: from ");
-            b
-        };
-        let outcome = pending_pack
-            .with_write_packed_refs_only(true)
-            .with_reflog_message(RefLogMessage::Override {
-                message: reflog_message.clone(),
-            })
-            .receive(should_interrupt)?;
-
-        util::replace_changed_local_config_file(repo, config);
-        util::update_head(
-            repo,
-            &outcome.ref_map.remote_refs,
-            reflog_message.as_ref(),
-            &remote_name,
-        )?;
-
-        Ok((self.repo.take().expect("still present"), outcome))
     }
 
     /// Similar to [`fetch_only()`][Self::fetch_only()`], but passes ownership to a utility type to configure a checkout operation.
@@ -144,6 +182,24 @@ impl PrepareFetch {
         self.fetch_options = opts;
         self
     }
+    /// Make this a shallow clone with the history limited as described by `shallow`, like `git clone --depth`,
+    /// `--shallow-since` or `--shallow-exclude`.
+    #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+    pub fn with_shallow(mut self, shallow: crate::remote::fetch::Shallow) -> Self {
+        self.shallow = shallow;
+        self
+    }
+
+    /// Make this a partial clone which omits objects as described by the filter `spec`, like `git clone --filter=blob:none`.
+    ///
+    /// The remote will be configured as promisor remote with the filter persisted in `remote.<name>.partialCloneFilter`,
+    /// which is used by future fetches, and omitted objects will be fetched from it when they are needed.
+    #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+    pub fn with_filter(mut self, spec: impl Into<crate::bstr::BString>) -> Self {
+        self.filter = Some(spec.into());
+        self
+    }
+
     /// Use `f` to apply arbitrary changes to the remote that is about to be used to fetch a pack.
     ///
     /// The passed in `remote` will be un-named and pre-configured to be a default remote as we know it from git-clone.
@@ -15,17 +15,64 @@ use crate::{
 pub fn write_remote_to_local_config_file(
     remote: &mut crate::Remote<'_>,
     remote_name: String,
+    filter: Option<&crate::bstr::BString>,
 ) -> Result<git_config::File<'static>, Error> {
     let mut metadata = git_config::file::Metadata::from(git_config::Source::Local);
     let config_path = remote.repo.git_dir().join("config");
     metadata.path = Some(config_path.clone());
     let mut config =
         git_config::File::from_paths_metadata(Some(metadata), Default::default())?.expect("one file to load");
-    remote.save_as_to(remote_name, &mut config)?;
+    remote.save_as_to(remote_name.clone(), &mut config)?;
+    if let Some(filter) = filter {
+        write_partial_clone_config(&mut config, &remote_name, filter.as_ref());
+    }
     std::fs::write(config_path, config.to_bstring())?;
     Ok(config)
 }
 
+/// Configure the remote named `remote_name` as promisor remote which omits objects according to `filter`, and make the
+/// repository a partial clone that obtains missing objects from it.
+fn write_partial_clone_config(config: &mut git_config::File<'static>, remote_name: &str, filter: &BStr) {
+    config
+        .set_raw_value("remote", Some(remote_name), "promisor", "true")
+        .expect("valid static key");
+    config
+        .set_raw_value("remote", Some(remote_name), "partialCloneFilter", filter)
+        .expect("valid static key");
+    config
+        .set_raw_value("core", None, "repositoryFormatVersion", "1")
+        .expect("valid static key");
+    config
+        .set_raw_value("extensions", None, "partialClone", remote_name)
+        .expect("valid static key");
+}
+
+/// Persist `object_hash` as the object format of the freshly initialized `repo` and reopen it so all stores pick it up.
+#[allow(clippy::result_large_err)]
+pub fn set_object_hash_in_local_config_file(repo: &mut Repository, object_hash: git_hash::Kind) -> Result<(), Error> {
+    let mut metadata = git_config::file::Metadata::from(git_config::Source::Local);
+    let config_path = repo.git_dir().join("config");
+    metadata.path = Some(config_path.clone());
+    let mut config =
+        git_config::File::from_paths_metadata(Some(metadata), Default::default())?.expect("one file to load");
+    let uses_extensions = object_hash != git_hash::Kind::Sha1;
+    config
+        .set_raw_value(
+            "core",
+            None,
+            "repositoryFormatVersion",
+            if uses_extensions { "1" } else { "0" },
+        )
+        .expect("valid static key");
+    config
+        .set_raw_value("extensions", None, "objectFormat", object_hash.as_str())
+        .expect("valid static key");
+    std::fs::write(config_path, config.to_bstring())?;
+
+    *repo = crate::ThreadSafeRepository::open_opts(repo.git_dir(), repo.options.clone())?.to_thread_local();
+    Ok(())
+}
+
 pub fn replace_changed_local_config_file(repo: &mut Repository, mut config: git_config::File<'static>) {
     let repo_config = git_features::threading::OwnShared::make_mut(&mut repo.config.resolved);
     let ids_to_remove: Vec<_> = repo_config
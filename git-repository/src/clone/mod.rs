@@ -15,6 +15,12 @@ pub struct PrepareFetch {
     /// Options for preparing a fetch operation.
     #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
     fetch_options: crate::remote::ref_map::Options,
+    /// How to shorten the history of the clone, if at all.
+    #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+    shallow: crate::remote::fetch::Shallow,
+    /// The filter to omit objects with to create a partial clone, if set.
+    #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+    filter: Option<crate::bstr::BString>,
     /// The url to clone from
     #[cfg_attr(not(feature = "blocking-network-client"), allow(dead_code))]
     url: git_url::Url,
@@ -65,6 +71,10 @@ impl PrepareFetch {
             url,
             #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
             fetch_options: Default::default(),
+            #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+            shallow: Default::default(),
+            #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+            filter: None,
             repo: Some(repo),
             remote_name: None,
             configure_remote: None,
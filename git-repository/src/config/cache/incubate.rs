@@ -9,6 +9,7 @@ pub(crate) struct StageOne {
     pub is_bare: bool,
     pub lossy: Option<bool>,
     pub object_hash: git_hash::Kind,
+    pub ref_storage: git_ref::store::StorageFormat,
     pub reflog: Option<git_ref::store::WriteReflog>,
 }
 
@@ -47,6 +48,8 @@ impl StageOne {
                 config.string("extensions", None, "objectFormat").map(|format| {
                     if format.as_ref().eq_ignore_ascii_case(b"sha1") {
                         Ok(git_hash::Kind::Sha1)
+                    } else if format.as_ref().eq_ignore_ascii_case(b"sha256") {
+                        Ok(git_hash::Kind::Sha256)
                     } else {
                         Err(Error::UnsupportedObjectFormat {
                             name: format.to_vec().into(),
@@ -56,6 +59,16 @@ impl StageOne {
             })
             .transpose()?
             .unwrap_or(git_hash::Kind::Sha1);
+        let ref_storage = (repo_format_version == 1)
+            .then(|| config.string("extensions", None, "refStorage"))
+            .flatten()
+            .map(|format| {
+                git_ref::store::StorageFormat::from_bytes(format.as_ref()).ok_or_else(|| Error::UnsupportedRefStorage {
+                    name: format.into_owned(),
+                })
+            })
+            .transpose()?
+            .unwrap_or_default();
 
         let reflog = util::query_refupdates(&config, lenient)?;
         Ok(StageOne {
@@ -64,6 +77,7 @@ impl StageOne {
             is_bare,
             lossy,
             object_hash,
+            ref_storage,
             reflog,
         })
     }
@@ -15,6 +15,7 @@ impl Cache {
             lossy,
             is_bare,
             object_hash,
+            ref_storage: _,
             reflog: _,
         }: StageOne,
         git_dir: &std::path::Path,
@@ -125,11 +126,13 @@ impl Cache {
         let reflog = util::query_refupdates(&config, lenient_config)?;
         let ignore_case = config_bool(&config, "core.ignoreCase", false, lenient_config)?;
         let use_multi_pack_index = config_bool(&config, "core.multiPackIndex", true, lenient_config)?;
+        let pack_mapping = util::pack_mapping(&config, lenient_config)?;
         let object_kind_hint = util::disambiguate_hint(&config);
         // NOTE: When adding a new initial cache, consider adjusting `reread_values_and_clear_caches()` as well.
         Ok(Cache {
             resolved: config.into(),
             use_multi_pack_index,
+            pack_mapping,
             object_hash,
             object_kind_hint,
             reflog,
@@ -61,6 +61,45 @@ pub(crate) fn query_refupdates(
     }
 }
 
+/// Use windowed access to pack data if any of `core.packedGitWindowSize` or `core.packedGitLimit` is set,
+/// using the default for the respective other value.
+pub(crate) fn pack_mapping(
+    config: &git_config::File<'static>,
+    lenient_config: bool,
+) -> Result<git_pack::data::Mapping, Error> {
+    use git_pack::data::Mapping;
+    let size = |key: &'static str| -> Result<Option<usize>, Error> {
+        let (section, value_name) = key.split_once('.').expect("valid section.key format");
+        let value = match config.integer(section, None, value_name) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        value
+            .ok()
+            .and_then(|value| usize::try_from(value).ok())
+            .filter(|value| *value > 0)
+            .map(Some)
+            .ok_or_else(|| Error::DecodeInteger {
+                key,
+                value: config
+                    .string(section, None, value_name)
+                    .expect("value exists")
+                    .into_owned(),
+            })
+            .with_leniency(lenient_config)
+    };
+    let window_size = size("core.packedGitWindowSize")?;
+    let limit = size("core.packedGitLimit")?;
+    Ok(if window_size.is_none() && limit.is_none() {
+        Mapping::Whole
+    } else {
+        Mapping::Windowed {
+            window_size: window_size.unwrap_or(Mapping::DEFAULT_WINDOW_SIZE),
+            limit: limit.unwrap_or(Mapping::DEFAULT_LIMIT),
+        }
+    })
+}
+
 // TODO: Use a specialization here once trait specialization is stabilized. Would be perfect here for `T: Default`.
 pub trait ApplyLeniency {
     fn with_leniency(self, is_lenient: bool) -> Self;
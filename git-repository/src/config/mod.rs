@@ -59,12 +59,16 @@ pub enum Error {
     FromEnv(#[from] git_config::file::init::from_env::Error),
     #[error("Cannot handle objects formatted as {:?}", .name)]
     UnsupportedObjectFormat { name: BString },
+    #[error("Cannot handle references stored as {:?}", .name)]
+    UnsupportedRefStorage { name: BString },
     #[error("The value for '{}' cannot be empty", .key)]
     EmptyValue { key: &'static str },
     #[error("Invalid value for 'core.abbrev' = '{}'. It must be between 4 and {}", .value, .max)]
     CoreAbbrev { value: BString, max: u8 },
     #[error("Value '{}' at key '{}' could not be decoded as boolean", .value, .key)]
     DecodeBoolean { key: String, value: BString },
+    #[error("Value '{}' at key '{}' could not be decoded as positive integer", .value, .key)]
+    DecodeInteger { key: &'static str, value: BString },
     #[error(transparent)]
     PathInterpolation(#[from] git_config::path::interpolate::Error),
     #[error("Configuration overrides at open or init time could not be applied.")]
@@ -168,6 +172,8 @@ pub(crate) struct Cache {
     pub object_hash: git_hash::Kind,
     /// If true, multi-pack indices, whether present or not, may be used by the object database.
     pub use_multi_pack_index: bool,
+    /// How pack data should be accessed, windowed if `core.packedGitWindowSize` or `core.packedGitLimit` are set.
+    pub pack_mapping: git_pack::data::Mapping,
     /// The representation of `core.logallrefupdates`, or `None` if the variable wasn't set.
     pub reflog: Option<git_ref::store::WriteReflog>,
     /// The configured user agent for presentation to servers.
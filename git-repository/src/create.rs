@@ -118,6 +118,10 @@ pub struct Options {
     /// If set, use these filesystem capabilities to populate the respective git-config fields.
     /// If `None`, the directory will be probed.
     pub fs_capabilities: Option<git_worktree::fs::Capabilities>,
+    /// The kind of hash to use for objects in the new repository, which is Sha1 by default.
+    ///
+    /// Anything else sets the `extensions.objectFormat` configuration key, which requires `core.repositoryFormatVersion = 1`.
+    pub object_hash: git_hash::Kind,
 }
 
 /// Create a new `.git` repository of `kind` within the possibly non-existing `directory`
@@ -131,6 +135,7 @@ pub fn into(
     Options {
         fs_capabilities,
         destination_must_be_empty,
+        object_hash,
     }: Options,
 ) -> Result<git_discover::repository::Path, Error> {
     let mut dot_git = directory.into();
@@ -211,7 +216,11 @@ pub fn into(
             let caps = fs_capabilities.unwrap_or_else(|| git_worktree::fs::Capabilities::probe(&dot_git));
             let mut core = config.new_section("core", None).expect("valid section name");
 
-            core.push(key("repositoryformatversion"), Some("0".into()));
+            let uses_extensions = object_hash != git_hash::Kind::Sha1;
+            core.push(
+                key("repositoryformatversion"),
+                Some(if uses_extensions { "1" } else { "0" }.into()),
+            );
             core.push(key("filemode"), Some(bool(caps.executable_bit).into()));
             core.push(key("bare"), Some(bool(bare).into()));
             core.push(key("logallrefupdates"), Some(bool(!bare).into()));
@@ -219,6 +228,10 @@ pub fn into(
             core.push(key("ignorecase"), Some(bool(caps.ignore_case).into()));
             core.push(key("precomposeunicode"), Some(bool(caps.precompose_unicode).into()));
         }
+        if object_hash != git_hash::Kind::Sha1 {
+            let mut extensions = config.new_section("extensions", None).expect("valid section name");
+            extensions.push(key("objectformat"), Some(object_hash.as_str().into()));
+        }
         let mut cursor = PathCursor(&mut dot_git);
         let config_path = cursor.at("config");
         std::fs::write(config_path, &config.to_bstring()).map_err(|err| Error::IoWrite {
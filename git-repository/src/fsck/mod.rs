@@ -0,0 +1,301 @@
+//! Check the connectivity and validity of all objects in the object database, similar to `git fsck`.
+use std::collections::BTreeMap;
+
+use git_hash::ObjectId;
+use git_ref::FullName;
+
+use crate::bstr::BString;
+
+pub(crate) mod object;
+
+/// How a finding of a [`Check`] is treated, as configured by `fsck.<check>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// The finding is reported and makes the repository fail the check.
+    Error,
+    /// The finding is reported but doesn't make the repository fail the check.
+    Warn,
+    /// The finding isn't reported.
+    Ignore,
+}
+
+impl Severity {
+    /// Parse a severity from a configuration `value` like `error`, `warn` or `ignore`.
+    pub fn from_bytes(value: &[u8]) -> Option<Self> {
+        Some(match value {
+            b"error" => Severity::Error,
+            b"warn" => Severity::Warn,
+            b"ignore" => Severity::Ignore,
+            _ => return None,
+        })
+    }
+}
+
+macro_rules! checks {
+    ($($variant:ident => $name:literal, $severity:ident, $description:literal;)*) => {
+        /// A check performed on the content of objects, identified by the same name git uses for its `fsck.<check>` configuration.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[allow(missing_docs)]
+        pub enum Check {
+            $($variant),*
+        }
+
+        impl Check {
+            /// All checks we perform.
+            pub const ALL: &'static [Check] = &[$(Check::$variant),*];
+
+            /// The name of the check as used in `fsck.<name>` configuration keys, like `badDate`.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Check::$variant => $name),*
+                }
+            }
+
+            /// The severity of the check if it isn't configured otherwise.
+            pub fn default_severity(&self) -> Severity {
+                match self {
+                    $(Check::$variant => Severity::$severity),*
+                }
+            }
+
+            /// A description of what the check found.
+            pub fn description(&self) -> &'static str {
+                match self {
+                    $(Check::$variant => $description),*
+                }
+            }
+        }
+    };
+}
+
+checks! {
+    BadDate => "badDate", Error, "invalid author/committer line - bad date";
+    BadEmail => "badEmail", Error, "invalid author/committer line - bad email";
+    BadFilemode => "badFilemode", Warn, "contains bad file modes";
+    BadName => "badName", Error, "invalid author/committer line - bad name";
+    BadObjectSha1 => "badObjectSha1", Error, "invalid 'object' line format - bad sha1";
+    BadParentSha1 => "badParentSha1", Error, "invalid 'parent' line format - bad sha1";
+    BadTagName => "badTagName", Warn, "invalid 'tag' name";
+    BadTimezone => "badTimezone", Error, "invalid author/committer line - bad time zone";
+    BadTree => "badTree", Error, "cannot be parsed as a tree";
+    BadTreeSha1 => "badTreeSha1", Error, "invalid 'tree' line format - bad sha1";
+    BadType => "badType", Error, "invalid 'type' value";
+    DuplicateEntries => "duplicateEntries", Error, "contains duplicate file entries";
+    EmptyName => "emptyName", Warn, "contains empty pathname";
+    FullPathname => "fullPathname", Warn, "contains full pathnames";
+    HasDot => "hasDot", Warn, "contains '.'";
+    HasDotdot => "hasDotdot", Warn, "contains '..'";
+    HasDotgit => "hasDotgit", Warn, "contains '.git'";
+    MissingAuthor => "missingAuthor", Error, "invalid format - expected 'author' line";
+    MissingCommitter => "missingCommitter", Error, "invalid format - expected 'committer' line";
+    MissingEmail => "missingEmail", Error, "invalid author/committer line - missing email";
+    MissingNameBeforeEmail => "missingNameBeforeEmail", Error, "invalid author/committer line - missing name before email";
+    MissingObject => "missingObject", Error, "invalid format - expected 'object' line";
+    MissingSpaceBeforeDate => "missingSpaceBeforeDate", Error, "invalid author/committer line - missing space before date";
+    MissingSpaceBeforeEmail => "missingSpaceBeforeEmail", Error, "invalid author/committer line - missing space before email";
+    MissingTagEntry => "missingTagEntry", Error, "invalid format - expected 'tag' line";
+    MissingTaggerEntry => "missingTaggerEntry", Warn, "invalid format - expected 'tagger' line";
+    MissingTree => "missingTree", Error, "invalid format - expected 'tree' line";
+    MissingTypeEntry => "missingTypeEntry", Error, "invalid format - expected 'type' line";
+    MultipleAuthors => "multipleAuthors", Error, "invalid format - multiple 'author' lines";
+    NulInCommit => "nulInCommit", Warn, "NUL byte in the commit object body";
+    NulInHeader => "nulInHeader", Error, "NUL byte in the object header";
+    NullSha1 => "nullSha1", Warn, "contains entries pointing to null sha1";
+    TreeNotSorted => "treeNotSorted", Error, "not properly sorted";
+    UnterminatedHeader => "unterminatedHeader", Error, "unterminated header";
+    ZeroPaddedDate => "zeroPaddedDate", Error, "invalid author/committer line - zero-padded date";
+    ZeroPaddedFilemode => "zeroPaddedFilemode", Warn, "contains zero-padded file modes";
+}
+
+impl Check {
+    /// Find the check with the given `name`, compared case-insensitively like configuration keys.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Check::ALL
+            .iter()
+            .find(|check| check.name().eq_ignore_ascii_case(name))
+            .copied()
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name(), self.description())
+    }
+}
+
+/// Options for use in [`Repository::fsck()`][crate::Repository::fsck()].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// The severity of checks which differs from their [default severity][Check::default_severity()],
+    /// typically obtained from `fsck.<check>` configuration with [`Repository::fsck_options()`][crate::Repository::fsck_options()].
+    pub severities: BTreeMap<Check, Severity>,
+}
+
+impl Options {
+    /// Return the severity of findings of `check`.
+    pub fn severity(&self, check: Check) -> Severity {
+        self.severities
+            .get(&check)
+            .copied()
+            .unwrap_or_else(|| check.default_severity())
+    }
+}
+
+/// A problem with the content of an object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// The id of the object with the problem.
+    pub id: ObjectId,
+    /// The kind of the object with the problem.
+    pub kind: git_object::Kind,
+    /// The check which found the problem.
+    pub check: Check,
+    /// The severity with which the problem is reported, which is never [`Severity::Ignore`].
+    pub severity: Severity,
+}
+
+/// The place an object is referenced from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The object is the target of a reference.
+    Reference(FullName),
+    /// The object is mentioned in the log of a reference.
+    Reflog(FullName),
+    /// The object is a blob in the index at the given path.
+    Index(BString),
+    /// The object is linked to by another object.
+    Object(ObjectId),
+}
+
+/// An object that is referenced, but missing from the object database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Missing {
+    /// The id of the missing object.
+    pub id: ObjectId,
+    /// The kind the object is expected to have, if known.
+    pub kind: Option<git_object::Kind>,
+    /// The first place the object was found to be referenced from.
+    pub referenced_by: Source,
+}
+
+/// A link from one object to another one which is missing or has an unexpected kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    /// The object containing the link.
+    pub from: ObjectId,
+    /// The object the link points to.
+    pub to: ObjectId,
+    /// The kind of object the link is supposed to point to.
+    pub expected: git_object::Kind,
+    /// The actual kind of the object the link points to, or `None` if it is missing.
+    pub actual: Option<git_object::Kind>,
+}
+
+/// The way an object in the object database is corrupt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Corruption {
+    /// The object couldn't be read or decoded, for the reason in `message`.
+    Unreadable {
+        /// The error encountered when reading the object.
+        message: String,
+    },
+    /// The content of the object doesn't match its id, as it hashes to `actual`.
+    HashMismatch {
+        /// The id the content of the object actually has.
+        actual: ObjectId,
+    },
+    /// Hashing the content of the object revealed it to be part of a SHA-1 collision attack.
+    Collision,
+}
+
+/// An object which is present in the object database, but corrupt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Corrupt {
+    /// The id of the corrupt object.
+    pub id: ObjectId,
+    /// The way the object is corrupt.
+    pub corruption: Corruption,
+}
+
+/// An object along with its kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Object {
+    /// The id of the object.
+    pub id: ObjectId,
+    /// The kind of the object.
+    pub kind: git_object::Kind,
+}
+
+/// The outcome of [`Repository::fsck()`][crate::Repository::fsck()].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The amount of objects that were checked.
+    pub num_objects: usize,
+    /// Objects that couldn't be read or whose content doesn't match their id, in order of object ids.
+    ///
+    /// They aren't checked any further, and aren't reported as missing if other objects link to them.
+    pub corrupt: Vec<Corrupt>,
+    /// Problems with the content of objects, in order of object ids.
+    pub problems: Vec<Problem>,
+    /// Objects that are referenced, but not present, in order of object ids.
+    pub missing: Vec<Missing>,
+    /// Links between objects that point to missing objects or objects of the wrong kind.
+    pub broken_links: Vec<BrokenLink>,
+    /// Objects that can't be reached from references, their logs or the index, in order of object ids.
+    pub unreachable: Vec<Object>,
+    /// The unreachable objects that aren't referenced by any other object, in order of object ids.
+    pub dangling: Vec<Object>,
+}
+
+impl Outcome {
+    /// Return `true` if there are corrupt or missing objects, broken links or problems with [`Severity::Error`].
+    pub fn has_errors(&self) -> bool {
+        !self.corrupt.is_empty()
+            || !self.missing.is_empty()
+            || !self.broken_links.is_empty()
+            || self.problems.iter().any(|problem| problem.severity == Severity::Error)
+    }
+}
+
+/// Types for obtaining [`Options`] from configuration.
+pub mod options {
+    use crate::bstr::BString;
+
+    /// The error returned by [`Repository::fsck_options()`][crate::Repository::fsck_options()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The severity {value:?} of fsck.{key} is invalid. It must be one of 'error', 'warn' or 'ignore'")]
+        InvalidSeverity { key: String, value: BString },
+    }
+}
+
+/// The error returned by [`Repository::fsck()`][crate::Repository::fsck()].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    IterObjects(#[from] git_odb::store::load_index::Error),
+    #[error(transparent)]
+    IterLooseObjects(#[from] git_odb::loose::iter::Error),
+    #[error(transparent)]
+    FindObject(#[from] git_odb::store::find::Error),
+    #[error(transparent)]
+    IterReferences(#[from] git_ref::store::iter::Error),
+    #[error(transparent)]
+    FindHead(#[from] git_ref::store::find::Error),
+    #[error(transparent)]
+    ReadReflog(#[from] git_ref::store::reflog::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    OpenIndex(#[from] crate::worktree::open_index::Error),
+    #[error(transparent)]
+    OpenPromisorPackIndex(#[from] git_pack::index::init::Error),
+    #[error(transparent)]
+    ShallowCommits(#[from] crate::shallow::open::Error),
+    #[error(transparent)]
+    OpenWorktree(#[from] Box<crate::open::Error>),
+    #[error("Interrupted")]
+    Interrupted,
+}
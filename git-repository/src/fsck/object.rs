@@ -0,0 +1,267 @@
+use std::{cmp::Ordering, collections::HashSet};
+
+use git_hash::ObjectId;
+use git_object::Kind;
+
+use crate::{bstr::ByteSlice, fsck::Check};
+
+/// Check the `data` of an object of `kind` whose ids are of `object_hash` for malformations and pass each finding to `report`.
+///
+/// Return the objects it links to along with the kind they are expected to have, which may be incomplete
+/// if the object is too malformed to be parsed entirely.
+pub(crate) fn check(
+    kind: Kind,
+    data: &[u8],
+    object_hash: git_hash::Kind,
+    report: &mut dyn FnMut(Check),
+) -> Vec<(ObjectId, Kind)> {
+    let mut links = Vec::new();
+    match kind {
+        Kind::Commit => commit(data, object_hash, &mut links, report),
+        Kind::Tag => tag(data, object_hash, &mut links, report),
+        Kind::Tree => tree(data, object_hash, &mut links, report),
+        Kind::Blob => {}
+    }
+    links
+}
+
+fn commit(data: &[u8], object_hash: git_hash::Kind, links: &mut Vec<(ObjectId, Kind)>, report: &mut dyn FnMut(Check)) {
+    if let Err(check) = commit_inner(data, object_hash, links, report) {
+        report(check);
+    }
+}
+
+fn commit_inner(
+    data: &[u8],
+    object_hash: git_hash::Kind,
+    links: &mut Vec<(ObjectId, Kind)>,
+    report: &mut dyn FnMut(Check),
+) -> Result<(), Check> {
+    let header_len = verify_header(data)?;
+    let mut buf = data.strip_prefix(b"tree ").ok_or(Check::MissingTree)?;
+    let (id, rest) = id_line(buf, object_hash).ok_or(Check::BadTreeSha1)?;
+    links.push((id, Kind::Tree));
+    buf = rest;
+    while let Some(rest) = buf.strip_prefix(b"parent ") {
+        let (id, rest) = id_line(rest, object_hash).ok_or(Check::BadParentSha1)?;
+        links.push((id, Kind::Commit));
+        buf = rest;
+    }
+    let mut num_authors = 0;
+    while let Some(rest) = buf.strip_prefix(b"author ") {
+        num_authors += 1;
+        buf = ident(rest)?;
+    }
+    match num_authors {
+        0 => return Err(Check::MissingAuthor),
+        1 => {}
+        _ => return Err(Check::MultipleAuthors),
+    }
+    ident(buf.strip_prefix(b"committer ").ok_or(Check::MissingCommitter)?)?;
+    if data[header_len..].contains(&0) {
+        report(Check::NulInCommit);
+    }
+    Ok(())
+}
+
+fn tag(data: &[u8], object_hash: git_hash::Kind, links: &mut Vec<(ObjectId, Kind)>, report: &mut dyn FnMut(Check)) {
+    if let Err(check) = tag_inner(data, object_hash, links, report) {
+        report(check);
+    }
+}
+
+fn tag_inner(
+    data: &[u8],
+    object_hash: git_hash::Kind,
+    links: &mut Vec<(ObjectId, Kind)>,
+    report: &mut dyn FnMut(Check),
+) -> Result<(), Check> {
+    verify_header(data)?;
+    let buf = data.strip_prefix(b"object ").ok_or(Check::MissingObject)?;
+    let (target, buf) = id_line(buf, object_hash).ok_or(Check::BadObjectSha1)?;
+    let (kind, buf) =
+        line(buf.strip_prefix(b"type ").ok_or(Check::MissingTypeEntry)?).ok_or(Check::MissingTypeEntry)?;
+    let kind = Kind::from_bytes(kind).map_err(|_| Check::BadType)?;
+    links.push((target, kind));
+    let (name, buf) = line(buf.strip_prefix(b"tag ").ok_or(Check::MissingTagEntry)?).ok_or(Check::MissingTagEntry)?;
+    if git_validate::tag::name(name.as_bstr()).is_err() {
+        report(Check::BadTagName);
+    }
+    match buf.strip_prefix(b"tagger ") {
+        Some(tagger) => {
+            ident(tagger)?;
+        }
+        None => report(Check::MissingTaggerEntry),
+    }
+    Ok(())
+}
+
+fn tree(data: &[u8], object_hash: git_hash::Kind, links: &mut Vec<(ObjectId, Kind)>, report: &mut dyn FnMut(Check)) {
+    let mut found = Vec::new();
+    let mut names = HashSet::new();
+    let mut previous: Option<(&[u8], bool)> = None;
+    let mut buf = data;
+    while !buf.is_empty() {
+        let (mode_bytes, mode, name, id) = match tree_entry(buf, object_hash) {
+            Some((entry, rest)) => {
+                buf = rest;
+                entry
+            }
+            None => {
+                report(Check::BadTree);
+                return;
+            }
+        };
+
+        if mode_bytes[0] == b'0' {
+            found.push(Check::ZeroPaddedFilemode);
+        }
+        let is_dir = mode == 0o40000;
+        match mode {
+            0o40000 => links.push((id, Kind::Tree)),
+            0o100644 | 0o100755 | 0o100664 | 0o120000 => links.push((id, Kind::Blob)),
+            0o160000 => {}
+            _ => found.push(Check::BadFilemode),
+        }
+        if id.is_null() {
+            found.push(Check::NullSha1);
+        }
+        if name.is_empty() {
+            found.push(Check::EmptyName);
+        }
+        if name.contains(&b'/') {
+            found.push(Check::FullPathname);
+        }
+        match name {
+            b"." => found.push(Check::HasDot),
+            b".." => found.push(Check::HasDotdot),
+            _ if name.eq_ignore_ascii_case(b".git") => found.push(Check::HasDotgit),
+            _ => {}
+        }
+        if !names.insert(name) {
+            found.push(Check::DuplicateEntries);
+        }
+        if let Some((previous_name, previous_is_dir)) = previous {
+            if tree_entry_order(previous_name, previous_is_dir, name, is_dir) == Ordering::Greater {
+                found.push(Check::TreeNotSorted);
+            }
+        }
+        previous = Some((name, is_dir));
+    }
+    found.sort();
+    found.dedup();
+    for check in found {
+        report(check);
+    }
+}
+
+type TreeEntry<'a> = (&'a [u8], u32, &'a [u8], ObjectId);
+
+/// Parse the tree entry at the beginning of `buf` into its mode as bytes and number, name and id, and return it along with
+/// the remaining bytes.
+fn tree_entry(buf: &[u8], object_hash: git_hash::Kind) -> Option<(TreeEntry<'_>, &[u8])> {
+    let space = buf.find_byte(b' ')?;
+    let (mode_bytes, rest) = (&buf[..space], &buf[space + 1..]);
+    if mode_bytes.is_empty() || !mode_bytes.iter().all(|b| (b'0'..=b'7').contains(b)) {
+        return None;
+    }
+    let mode = mode_bytes.iter().try_fold(0u32, |mode, b| {
+        mode.checked_mul(8).map(|mode| mode + u32::from(b - b'0'))
+    })?;
+    let nul = rest.find_byte(0)?;
+    let (name, rest) = (&rest[..nul], &rest[nul + 1..]);
+    let id = rest.get(..object_hash.len_in_bytes())?;
+    Some(((mode_bytes, mode, name, ObjectId::from(id)), &rest[id.len()..]))
+}
+
+/// Compare tree entry names like git does, which sorts directories as if their name had a trailing slash.
+fn tree_entry_order(a: &[u8], a_is_dir: bool, b: &[u8], b_is_dir: bool) -> Ordering {
+    let len = a.len().min(b.len());
+    a[..len].cmp(&b[..len]).then_with(|| {
+        let a = a.get(len).copied().unwrap_or(if a_is_dir { b'/' } else { 0 });
+        let b = b.get(len).copied().unwrap_or(if b_is_dir { b'/' } else { 0 });
+        a.cmp(&b)
+    })
+}
+
+/// Assure the header of a commit or tag is terminated and free of null bytes, and return its length.
+fn verify_header(data: &[u8]) -> Result<usize, Check> {
+    for (pos, byte) in data.iter().enumerate() {
+        match byte {
+            0 => return Err(Check::NulInHeader),
+            b'\n' if data.get(pos + 1) == Some(&b'\n') => return Ok(pos + 1),
+            _ => {}
+        }
+    }
+    // A header without message is fine as long as its last line is terminated.
+    if data.last() == Some(&b'\n') {
+        Ok(data.len())
+    } else {
+        Err(Check::UnterminatedHeader)
+    }
+}
+
+/// Parse the line at the beginning of `buf` and return it without newline, along with the remaining bytes.
+fn line(buf: &[u8]) -> Option<(&[u8], &[u8])> {
+    buf.find_byte(b'\n').map(|pos| (&buf[..pos], &buf[pos + 1..]))
+}
+
+/// Parse a line consisting of a hexadecimal object id only.
+fn id_line(buf: &[u8], object_hash: git_hash::Kind) -> Option<(ObjectId, &[u8])> {
+    let (hex, rest) = line(buf)?;
+    if hex.len() != object_hash.len_in_hex() || !hex.iter().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+    ObjectId::from_hex(hex).ok().map(|id| (id, rest))
+}
+
+/// Validate the identity line of an author, committer or tagger at the beginning of `buf`, like
+/// `Name <email> 1234567890 +0100`, and return the bytes past its newline.
+fn ident(buf: &[u8]) -> Result<&[u8], Check> {
+    if buf.first() == Some(&b'<') {
+        return Err(Check::MissingNameBeforeEmail);
+    }
+    let pos = buf
+        .iter()
+        .position(|b| matches!(b, b'<' | b'>' | b'\n'))
+        .ok_or(Check::MissingEmail)?;
+    match buf[pos] {
+        b'>' => return Err(Check::BadName),
+        b'\n' => return Err(Check::MissingEmail),
+        _ => {}
+    }
+    if buf[pos - 1] != b' ' {
+        return Err(Check::MissingSpaceBeforeEmail);
+    }
+    let buf = &buf[pos + 1..];
+    let pos = buf
+        .iter()
+        .position(|b| matches!(b, b'<' | b'>' | b'\n'))
+        .ok_or(Check::BadEmail)?;
+    if buf[pos] != b'>' {
+        return Err(Check::BadEmail);
+    }
+    let buf = buf[pos + 1..].strip_prefix(b" ").ok_or(Check::MissingSpaceBeforeDate)?;
+    if buf.first() == Some(&b'0') && buf.get(1) != Some(&b' ') {
+        return Err(Check::ZeroPaddedDate);
+    }
+    let num_digits = buf.iter().take_while(|b| b.is_ascii_digit()).count();
+    let date_is_valid = num_digits != 0
+        && buf.get(num_digits) == Some(&b' ')
+        && std::str::from_utf8(&buf[..num_digits])
+            .ok()
+            .and_then(|date| date.parse::<u64>().ok())
+            .is_some();
+    if !date_is_valid {
+        return Err(Check::BadDate);
+    }
+    let buf = &buf[num_digits + 1..];
+    match buf.get(..6) {
+        Some([sign, h1, h2, m1, m2, b'\n'])
+            if matches!(sign, b'+' | b'-') && [h1, h2, m1, m2].iter().all(|b| b.is_ascii_digit()) =>
+        {
+            Ok(&buf[6..])
+        }
+        _ => Err(Check::BadTimezone),
+    }
+}
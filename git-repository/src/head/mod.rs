@@ -120,3 +120,23 @@ pub mod log;
 
 ///
 pub mod peel;
+
+///
+pub mod set {
+    /// The error returned by [Repository::set_head_to_branch(…)][crate::Repository::set_head_to_branch()] and
+    /// [Repository::detach_head(…)][crate::Repository::detach_head()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        NameValidation(#[from] git_validate::reference::name::Error),
+        #[error(transparent)]
+        Head(#[from] crate::reference::find::existing::Error),
+        #[error(transparent)]
+        Find(#[from] crate::reference::find::Error),
+        #[error(transparent)]
+        Edit(#[from] crate::reference::edit::Error),
+        #[error("Could not append to the reflog of HEAD")]
+        AppendReflog(#[from] std::io::Error),
+    }
+}
@@ -149,6 +149,8 @@ pub use git_ref as refs;
 pub use git_refspec as refspec;
 pub use git_sec as sec;
 pub use git_tempfile as tempfile;
+#[cfg(feature = "git-transport")]
+pub use git_transport as transport;
 pub use git_traverse as traverse;
 pub use git_url as url;
 #[doc(inline)]
@@ -169,8 +171,8 @@ pub mod prelude {
 ///
 pub mod path;
 
-/// The standard type for a store to handle git references.
-pub type RefStore = git_ref::file::Store;
+/// The standard type for a store to handle git references, which are stored as loose files or as reftables.
+pub type RefStore = git_ref::store::Handle;
 /// A handle for finding objects in an object database, abstracting away caches for thread-local use.
 pub type OdbHandle = git_odb::Handle;
 /// A way to access git configuration
@@ -183,15 +185,25 @@ pub use types::{
     Worktree,
 };
 
+pub mod branch;
 ///
 pub mod clone;
 pub mod commit;
+pub mod fsck;
 pub mod head;
 pub mod id;
 pub mod object;
+pub mod promisor;
+#[cfg(feature = "blocking-network-server")]
+pub mod receive_pack;
 pub mod reference;
+///
+pub mod repack;
 mod repository;
+pub mod shallow;
 pub mod tag;
+#[cfg(feature = "blocking-network-server")]
+pub mod upload_pack;
 
 /// See [ThreadSafeRepository::discover()], but returns a [`Repository`] instead.
 pub fn discover(directory: impl AsRef<std::path::Path>) -> Result<Repository, discover::Error> {
@@ -149,6 +149,7 @@ impl<'repo> Object<'repo> {
     /// Obtain a fully parsed commit whose fields reference our data buffer.
     pub fn try_to_commit_ref(&self) -> Result<git_object::CommitRef<'_>, conversion::Error> {
         git_object::Data::new(self.kind, &self.data)
+            .with_object_hash(self.repo.object_hash())
             .decode()?
             .into_commit()
             .ok_or(conversion::Error::UnexpectedType {
@@ -206,6 +207,7 @@ impl<'repo> Object<'repo> {
     /// Obtain a fully parsed tag object whose fields reference our data buffer.
     pub fn try_to_tag_ref(&self) -> Result<git_object::TagRef<'_>, conversion::Error> {
         git_object::Data::new(self.kind, &self.data)
+            .with_object_hash(self.repo.object_hash())
             .decode()?
             .into_tag()
             .ok_or(conversion::Error::UnexpectedType {
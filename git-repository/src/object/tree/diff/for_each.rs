@@ -41,8 +41,12 @@ impl<'a, 'old> Platform<'a, 'old> {
             visit: for_each,
             err: None,
         };
-        git_diff::tree::Changes::from(TreeRefIter::from_bytes(&self.lhs.data)).needed_to_obtain(
-            TreeRefIter::from_bytes(&other.data),
+        git_diff::tree::Changes::from(TreeRefIter::from_bytes_with_object_hash(
+            &self.lhs.data,
+            repo.object_hash(),
+        ))
+        .needed_to_obtain(
+            TreeRefIter::from_bytes_with_object_hash(&other.data, other.repo.object_hash()),
             &mut self.state,
             |oid, buf| repo.objects.find_tree_iter(oid, buf),
             &mut delegate,
@@ -274,6 +274,8 @@ pub enum Error {
     UnsafeGitDir { path: PathBuf },
     #[error(transparent)]
     EnvironmentAccessDenied(#[from] permission::env_var::resource::Error),
+    #[error("The reference store could not be opened")]
+    RefStore(#[from] git_ref::store::init::Error),
 }
 
 impl ThreadSafeRepository {
@@ -380,10 +382,14 @@ impl ThreadSafeRepository {
         let mut refs = {
             let reflog = repo_config.reflog.unwrap_or(git_ref::store::WriteReflog::Disable);
             let object_hash = repo_config.object_hash;
+            let format = repo_config.ref_storage;
             match &common_dir {
-                Some(common_dir) => crate::RefStore::for_linked_worktree(&git_dir, common_dir, reflog, object_hash),
-                None => crate::RefStore::at(&git_dir, reflog, object_hash),
-            }
+                Some(common_dir) => {
+                    git_ref::Store::for_linked_worktree(&git_dir, common_dir, format, reflog, object_hash)
+                }
+                None => git_ref::Store::at(&git_dir, format, reflog, object_hash),
+            }?
+            .to_handle()
         };
         let head = refs.find("HEAD").ok();
         let git_install_dir = crate::path::install_dir().ok();
@@ -436,7 +442,7 @@ impl ThreadSafeRepository {
             None => {}
         }
 
-        refs.write_reflog = reflog_or_default(config.reflog, worktree_dir.is_some());
+        refs.set_write_reflog(reflog_or_default(config.reflog, worktree_dir.is_some()));
         let replacements = replacement_objects
             .clone()
             .refs_prefix()
@@ -466,6 +472,7 @@ impl ThreadSafeRepository {
                     object_hash: config.object_hash,
                     use_multi_pack_index: config.use_multi_pack_index,
                     current_dir: current_dir.to_owned().into(),
+                    pack_mapping: config.pack_mapping,
                 },
             )?),
             common_dir,
@@ -484,7 +491,6 @@ impl Repository {
     ///
     /// Similar to `reread_values_and_clear_caches_replacing_config()`, but works on the existing instance instead of a passed
     /// in one that it them makes the default.
-    #[cfg(feature = "blocking-network-client")]
     pub(crate) fn reread_values_and_clear_caches(&mut self) -> Result<(), config::Error> {
         self.config.reread_values_and_clear_caches()?;
         self.apply_changed_values();
@@ -502,7 +508,8 @@ impl Repository {
     }
 
     fn apply_changed_values(&mut self) {
-        self.refs.write_reflog = reflog_or_default(self.config.reflog, self.work_dir().is_some());
+        self.refs
+            .set_write_reflog(reflog_or_default(self.config.reflog, self.work_dir().is_some()));
     }
 }
 
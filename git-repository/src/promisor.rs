@@ -0,0 +1,185 @@
+//! Support for partial clones, whose packs were received from a promisor remote that promises to provide all objects
+//! which were omitted by the filter used when fetching.
+//!
+//! Such packs are marked with a `.promisor` file, and objects they refer to may be missing locally until they are fetched
+//! on demand.
+
+///
+#[cfg(feature = "blocking-network-client")]
+pub mod fetch {
+    /// The error returned by [`Repository::fetch_promised_objects()`][crate::Repository::fetch_promised_objects()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The repository isn't a partial clone as extensions.partialClone isn't set")]
+        NoPromisorRemote,
+        #[error(transparent)]
+        FindRemote(#[from] crate::remote::find::existing::Error),
+        #[error(transparent)]
+        RefSpec(#[from] git_refspec::parse::Error),
+        #[error(transparent)]
+        Config(#[from] crate::config::Error),
+        #[error(transparent)]
+        Connect(#[from] crate::remote::connect::Error),
+        #[error(transparent)]
+        PrepareFetch(#[from] crate::remote::fetch::prepare::Error),
+        #[error(transparent)]
+        Fetch(#[from] crate::remote::fetch::Error),
+        #[error("Could not remove the pack keep file at {}", path.display())]
+        RemovePackKeepFile {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+    }
+}
+
+/// Set a hook on `objects` of the repository with `git_dir` which fetches objects that are missing from the promisor
+/// remote, if the repository is a partial clone as indicated by `config`.
+///
+/// Objects that are found missing while a fetch is in progress are fetched together once it completes, each batch
+/// requiring its own connection to the remote.
+#[cfg(feature = "blocking-network-client")]
+pub(crate) fn set_missing_object_hook(
+    objects: &mut crate::OdbHandle,
+    config: &git_config::File<'static>,
+    git_dir: &std::path::Path,
+    options: &crate::open::Options,
+) {
+    if config.string("extensions", None, "partialClone").is_none() {
+        return;
+    }
+    let batches = std::sync::Arc::new(hook::Batches::new(git_dir.to_owned(), options.clone()));
+    objects.set_missing_object_hook(move |id| batches.fetch(id));
+}
+
+#[cfg(feature = "blocking-network-client")]
+mod hook {
+    use std::{
+        collections::HashMap,
+        path::PathBuf,
+        sync::{Condvar, Mutex, MutexGuard},
+    };
+
+    use git_hash::{oid, ObjectId};
+
+    type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+    /// Fetch missing objects from the promisor remote in batches, each of which contains all objects that were requested
+    /// while the previous batch was fetched.
+    pub(super) struct Batches {
+        git_dir: PathBuf,
+        options: crate::open::Options,
+        state: Mutex<State>,
+        fetched: Condvar,
+    }
+
+    #[derive(Default)]
+    struct State {
+        /// The objects to fetch in the next batch.
+        pending: Vec<ObjectId>,
+        /// The amount of callers waiting for the next batch.
+        pending_callers: usize,
+        /// The number of the next batch.
+        next_batch: u64,
+        /// `true` while a batch is fetched.
+        is_fetching: bool,
+        /// The outcome of fetched batches by their number, along with the amount of callers who still have to pick it up.
+        /// Errors are kept as string as they can't be cloned.
+        outcomes: HashMap<u64, (usize, Result<bool, String>)>,
+        /// The repository to fetch with, opened for the first batch and kept for all batches that follow.
+        /// It can only be kept if it can be sent across threads, which requires `max-performance-safe`.
+        #[cfg(feature = "max-performance-safe")]
+        repo: Option<crate::ThreadSafeRepository>,
+    }
+
+    impl Batches {
+        pub(super) fn new(git_dir: PathBuf, options: crate::open::Options) -> Self {
+            Batches {
+                git_dir,
+                options,
+                state: Default::default(),
+                fetched: Default::default(),
+            }
+        }
+
+        /// Fetch `id` along with all other objects requested in the meantime, and return `true` if objects were received.
+        pub(super) fn fetch(&self, id: &oid) -> Result<bool, Error> {
+            let mut state = self.lock();
+            let batch = state.next_batch;
+            state.pending.push(id.to_owned());
+            state.pending_callers += 1;
+            loop {
+                if let Some((callers, outcome)) = state.outcomes.get_mut(&batch) {
+                    *callers -= 1;
+                    let outcome = outcome.clone();
+                    if *callers == 0 {
+                        state.outcomes.remove(&batch);
+                    }
+                    return outcome.map_err(Into::into);
+                }
+                if !state.is_fetching {
+                    break;
+                }
+                state = self.fetched.wait(state).expect("no panic while holding the lock");
+            }
+
+            // Our batch is the next one as it wasn't fetched yet, and nobody else is fetching.
+            state.is_fetching = true;
+            state.next_batch += 1;
+            let ids = std::mem::take(&mut state.pending);
+            let other_callers = std::mem::take(&mut state.pending_callers) - 1;
+            #[cfg(feature = "max-performance-safe")]
+            let repo = state.repo.take();
+            #[cfg(not(feature = "max-performance-safe"))]
+            let repo = None;
+            drop(state);
+
+            let (repo, outcome) = match repo.map_or_else(|| self.open(), Ok) {
+                Ok(repo) => {
+                    let outcome = fetch_batch(&repo, ids);
+                    (Some(repo), outcome)
+                }
+                Err(err) => (None, Err(err)),
+            };
+
+            let mut state = self.lock();
+            #[cfg(feature = "max-performance-safe")]
+            {
+                state.repo = repo;
+            }
+            #[cfg(not(feature = "max-performance-safe"))]
+            drop(repo);
+            state.is_fetching = false;
+            if other_callers != 0 {
+                let shared_outcome = outcome.as_ref().map(|changed| *changed).map_err(ToString::to_string);
+                state.outcomes.insert(batch, (other_callers, shared_outcome));
+            }
+            self.fetched.notify_all();
+            outcome
+        }
+
+        fn open(&self) -> Result<crate::ThreadSafeRepository, Error> {
+            Ok(crate::ThreadSafeRepository::open_opts(
+                &self.git_dir,
+                self.options.clone(),
+            )?)
+        }
+
+        fn lock(&self) -> MutexGuard<'_, State> {
+            self.state.lock().expect("no panic while holding the lock")
+        }
+    }
+
+    fn fetch_batch(repo: &crate::ThreadSafeRepository, mut ids: Vec<ObjectId>) -> Result<bool, Error> {
+        ids.sort();
+        ids.dedup();
+        let outcome = repo.to_thread_local().fetch_promised_objects(
+            ids,
+            git_features::progress::Discard,
+            &std::sync::atomic::AtomicBool::default(),
+        )?;
+        Ok(outcome.map_or(false, |outcome| {
+            matches!(outcome.status, crate::remote::fetch::Status::Change { .. })
+        }))
+    }
+}
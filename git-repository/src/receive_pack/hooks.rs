@@ -0,0 +1,124 @@
+use std::{
+    ffi::OsString,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use crate::{bstr::BString, receive_pack::Error};
+
+/// The hooks of a repository that are run while receiving a push, along with the environment to run them in.
+pub(crate) struct Hooks {
+    dir: PathBuf,
+    cwd: PathBuf,
+    /// The quarantine directory and the object directory of the repository, if objects are still in quarantine.
+    pub quarantine: Option<(PathBuf, PathBuf)>,
+}
+
+/// The result of running a hook.
+pub(crate) struct Output {
+    pub success: bool,
+    /// Everything the hook wrote to `stdout` and `stderr`, in that order.
+    pub data: Vec<u8>,
+}
+
+impl Hooks {
+    /// Find the hooks of `repo` according to `core.hooksPath`, which are run in its `.git` directory.
+    #[allow(clippy::result_large_err)]
+    pub fn new(repo: &crate::Repository) -> Result<Self, Error> {
+        let cwd = repo.git_dir().to_owned();
+        let dir = match repo.config.trusted_file_path("core", None, "hooksPath").transpose()? {
+            Some(dir) => cwd.join(dir),
+            None => repo.common_dir().join("hooks"),
+        };
+        Ok(Hooks {
+            dir,
+            cwd,
+            quarantine: None,
+        })
+    }
+
+    /// Run the hook `name` with `args` and feed `stdin` to it, or return `None` if the hook doesn't exist.
+    /// `extra_env` is passed in addition to `GIT_DIR` and the variables describing the quarantine, if present.
+    #[allow(clippy::result_large_err)]
+    pub fn run(
+        &self,
+        name: &'static str,
+        args: &[&[u8]],
+        stdin: &[u8],
+        extra_env: &[(String, OsString)],
+    ) -> Result<Option<Output>, Error> {
+        let path = self.dir.join(name);
+        if !is_executable(&path) {
+            return Ok(None);
+        }
+        let mut cmd = git_command::prepare(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .cwd(&self.cwd);
+        for arg in args {
+            cmd = cmd.arg(git_path::from_byte_slice(arg));
+        }
+        cmd = cmd.env("GIT_DIR", ".");
+        if let Some((quarantine, objects_dir)) = &self.quarantine {
+            cmd = cmd
+                .env("GIT_QUARANTINE_PATH", quarantine)
+                .env("GIT_OBJECT_DIRECTORY", quarantine)
+                .env("GIT_ALTERNATE_OBJECT_DIRECTORIES", objects_dir);
+        }
+        for (key, value) in extra_env {
+            cmd = cmd.env(key, value);
+        }
+        let run = || -> std::io::Result<Output> {
+            let mut child = cmd.spawn()?;
+            // Write from another thread as the hook may fill up its output pipes before it reads all of its input.
+            let writer = child.stdin.take().map(|mut input| {
+                let stdin = stdin.to_owned();
+                std::thread::spawn(move || match input.write_all(&stdin) {
+                    // Hooks don't have to read their input, and may exit before we are done writing.
+                    Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+                    res => res,
+                })
+            });
+            let output = child.wait_with_output()?;
+            if let Some(writer) = writer {
+                writer.join().expect("writing doesn't panic")?;
+            }
+            let mut data = output.stdout;
+            data.extend_from_slice(&output.stderr);
+            Ok(Output {
+                success: output.status.success(),
+                data,
+            })
+        };
+        run().map(Some).map_err(|source| Error::RunHook { name, source })
+    }
+}
+
+/// Return the environment to pass `push_options` to the hooks, as `GIT_PUSH_OPTION_COUNT` and `GIT_PUSH_OPTION_<n>`.
+pub(crate) fn push_option_env(push_options: &[BString]) -> Vec<(String, OsString)> {
+    let mut env = vec![(
+        "GIT_PUSH_OPTION_COUNT".to_string(),
+        push_options.len().to_string().into(),
+    )];
+    for (idx, option) in push_options.iter().enumerate() {
+        env.push((
+            format!("GIT_PUSH_OPTION_{}", idx),
+            git_path::from_bstr(option.as_ref()).into_owned().into_os_string(),
+        ));
+    }
+    env
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map_or(false, |m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -0,0 +1,112 @@
+//! Accept pushes into a repository by speaking the server side of `git receive-pack`.
+//!
+//! See [`Repository::receive_pack()`][crate::Repository::receive_pack()] for the entry point.
+use git_hash::ObjectId;
+
+use crate::bstr::BString;
+
+pub(crate) mod hooks;
+mod quarantine;
+pub(crate) use quarantine::Quarantine;
+mod request;
+pub(crate) use request::Request;
+pub(crate) mod response;
+pub(crate) mod update;
+
+/// Options for use in [`Repository::receive_pack()`][crate::Repository::receive_pack()].
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// If `true`, serve a single request and return, similar to `git receive-pack --stateless-rpc`.
+    ///
+    /// This is what HTTP frontends need, as each HTTP request carries exactly one request of the client.
+    /// Note that the advertisement isn't sent in this mode, unless [`advertise_refs`][Options::advertise_refs] is set.
+    pub stateless_rpc: bool,
+    /// If `true`, only send the reference advertisement and return, similar to `git receive-pack --advertise-refs`.
+    pub advertise_refs: bool,
+}
+
+/// A single reference update as requested by the client, along with its outcome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Command {
+    /// The object id the client expects the reference to point to, or the null id if it expects it to not exist.
+    pub old_id: ObjectId,
+    /// The object id the reference should point to, or the null id if it should be deleted.
+    pub new_id: ObjectId,
+    /// The full name of the reference to update.
+    pub name: BString,
+    /// `None` if the reference was updated, or the reason for rejecting the update as reported to the client.
+    pub rejection: Option<BString>,
+}
+
+impl Command {
+    /// Return `true` if this command deletes its reference.
+    pub fn is_deletion(&self) -> bool {
+        self.new_id.is_null()
+    }
+
+    /// Return `true` if this command creates its reference.
+    pub fn is_creation(&self) -> bool {
+        self.old_id.is_null()
+    }
+
+    /// Return `true` if the reference was updated.
+    pub fn is_ok(&self) -> bool {
+        self.rejection.is_none()
+    }
+
+    pub(crate) fn reject(&mut self, reason: impl Into<BString>) {
+        if self.rejection.is_none() {
+            self.rejection = Some(reason.into());
+        }
+    }
+}
+
+/// The outcome of [`Repository::receive_pack()`][crate::Repository::receive_pack()].
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// All commands sent by the client in order, along with their outcome.
+    pub commands: Vec<Command>,
+    /// The reason for failing to receive the pack sent by the client, or `None` if it was received or if no pack was needed.
+    pub unpack_error: Option<BString>,
+    /// The amount of objects in the pack sent by the client.
+    pub num_objects: u64,
+    /// The push options sent by the client, as passed to the `pre-receive` and `post-receive` hooks.
+    pub push_options: Vec<BString>,
+}
+
+/// The error returned by [`Repository::receive_pack()`][crate::Repository::receive_pack()].
+///
+/// Note that problems with individual commands or with the pack are reported to the client and are part of the
+/// [`Outcome`] instead.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    PacketlineDecode(#[from] git_transport::packetline::decode::Error),
+    #[error("The client closed the connection in the middle of a request")]
+    UnexpectedEof,
+    #[error("The line {line:?} sent by the client is malformed or unexpected")]
+    MalformedLine { line: BString },
+    #[error("The client asked for the object format {name:?}, but we use {actual}")]
+    ObjectFormatMismatch { name: BString, actual: git_hash::Kind },
+    #[error(transparent)]
+    IterReferences(#[from] git_ref::store::iter::Error),
+    #[error(transparent)]
+    FindReference(#[from] git_ref::store::find::Error),
+    #[error(transparent)]
+    FindObject(#[from] git_odb::store::find::Error),
+    #[error(transparent)]
+    DecodeObject(#[from] git_object::decode::Error),
+    #[error(transparent)]
+    TraverseCommits(#[from] git_traverse::commit::ancestors::Error),
+    #[error("Could not interpret the receive.* configuration")]
+    Configuration(#[from] git_config::value::Error),
+    #[error("Could not interpolate core.hooksPath")]
+    HooksPath(#[from] git_config::path::interpolate::Error),
+    #[error("Could not run the {name:?} hook")]
+    RunHook { name: &'static str, source: std::io::Error },
+    #[error("Interrupted")]
+    Interrupted,
+}
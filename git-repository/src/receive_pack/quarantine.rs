@@ -0,0 +1,139 @@
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+use git_hash::ObjectId;
+use git_pack::FindExt;
+
+/// The error returned by [`Quarantine::receive()`], which is reported to the client as reason for failing to unpack.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ReceiveError {
+    #[error(transparent)]
+    WritePack(#[from] git_pack::bundle::write::Error),
+    #[error(transparent)]
+    OpenIndex(#[from] git_pack::index::init::Error),
+}
+
+/// A temporary object directory to receive the objects of a push, which is only moved into the main object database once
+/// the push was deemed acceptable, similar to the _quarantine environment_ of `git receive-pack`.
+///
+/// It's deleted with all its contents when dropped.
+pub(crate) struct Quarantine {
+    dir: PathBuf,
+    objects_dir: PathBuf,
+}
+
+impl Quarantine {
+    /// Create a new quarantine directory within `objects_dir`, which is set up to have `objects_dir` as alternate so that
+    /// it can be used as object directory that sees all objects.
+    pub fn new(objects_dir: &Path) -> io::Result<Self> {
+        let objects_dir = objects_dir.canonicalize()?;
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos());
+        let mut attempt = 0;
+        let dir = loop {
+            let dir = objects_dir.join(format!("incoming-{}-{:x}", std::process::id(), nanos + attempt));
+            match std::fs::create_dir(&dir) {
+                Ok(()) => break dir,
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+                Err(err) => return Err(err),
+            }
+        };
+        let quarantine = Quarantine { dir, objects_dir };
+        std::fs::create_dir(quarantine.pack_dir())?;
+        std::fs::create_dir(quarantine.dir.join("info"))?;
+        std::fs::write(
+            quarantine.dir.join("info").join("alternates"),
+            format!("{}\n", quarantine.objects_dir.display()),
+        )?;
+        Ok(quarantine)
+    }
+
+    /// The object directory of the quarantine.
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The directory into which received packs are written.
+    pub fn pack_dir(&self) -> PathBuf {
+        self.dir.join("pack")
+    }
+
+    /// The object directory of the repository, which is an alternate of the quarantine.
+    pub fn objects_dir(&self) -> &Path {
+        &self.objects_dir
+    }
+
+    /// Read the pack sent by the client from `pack` and store it in the quarantine, resolving thin packs with objects from `repo`.
+    ///
+    /// Return the ids of all objects in the pack.
+    pub fn receive(
+        &self,
+        repo: &crate::Repository,
+        pack: impl io::BufRead,
+        should_interrupt: &AtomicBool,
+    ) -> Result<HashSet<ObjectId>, ReceiveError> {
+        let outcome = git_pack::Bundle::write_to_directory(
+            pack,
+            Some(self.pack_dir()),
+            git_features::progress::Discard,
+            should_interrupt,
+            Some(Box::new({
+                let objects = repo.objects.clone();
+                move |oid, buf| objects.find(oid, buf).ok().map(|(data, _location)| data)
+            })),
+            git_pack::bundle::write::Options {
+                object_hash: repo.object_hash(),
+                ..Default::default()
+            },
+        )?;
+        Ok(match outcome.index_path {
+            Some(path) => git_pack::index::File::at(path, outcome.object_hash)?
+                .iter()
+                .map(|entry| entry.oid)
+                .collect(),
+            None => HashSet::new(),
+        })
+    }
+
+    /// Move all received packs into the object database of the repository, making them visible to everyone, and return the
+    /// `.keep` files that were moved along with them to protect them from garbage collection until references point to them.
+    ///
+    /// `.keep` files are moved first so that packs are never visible without protection, and indices are moved last so that
+    /// readers never see an index without its pack.
+    pub fn migrate(self) -> io::Result<Vec<PathBuf>> {
+        let mut files = std::fs::read_dir(self.pack_dir())?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        files.sort_by_key(|path| match path.extension().and_then(|ext| ext.to_str()) {
+            Some("keep") => 0,
+            Some("idx") => 2,
+            _ => 1,
+        });
+        let pack_dir = self.objects_dir.join("pack");
+        std::fs::create_dir_all(&pack_dir)?;
+        let mut keep_files = Vec::new();
+        for file in files {
+            let destination = pack_dir.join(file.file_name().expect("read_dir yields file names"));
+            // Packs are named by their content, so an existing one is the same.
+            if destination.exists() {
+                continue;
+            }
+            std::fs::rename(&file, &destination)?;
+            if destination.extension().map_or(false, |ext| ext == "keep") {
+                keep_files.push(destination);
+            }
+        }
+        Ok(keep_files)
+    }
+}
+
+impl Drop for Quarantine {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
@@ -0,0 +1,193 @@
+use std::io;
+
+use git_hash::ObjectId;
+use git_transport::packetline::{PacketLineRef, StreamingPeekableIter};
+
+use crate::{
+    bstr::{BString, ByteSlice},
+    receive_pack::{Command, Error},
+};
+
+/// The update commands of a client along with the capabilities it asked for.
+#[derive(Default)]
+pub(crate) struct Request {
+    pub commands: Vec<Command>,
+    pub push_options: Vec<BString>,
+    /// If `true`, the client wants to receive a `report-status` or `report-status-v2` report.
+    pub report_status: bool,
+    /// The maximum size of side-band data, or `None` if the report shouldn't be sent on a side-band.
+    pub side_band: Option<usize>,
+    /// If `true`, either all commands must succeed or none of them.
+    pub atomic: bool,
+}
+
+impl Request {
+    /// Read the commands sent by the client via `lines`, along with its push options, or return `None` if it closed
+    /// the connection or had nothing to send.
+    #[allow(clippy::result_large_err)]
+    pub fn read(
+        lines: &mut StreamingPeekableIter<impl io::Read>,
+        object_hash: git_hash::Kind,
+    ) -> Result<Option<Self>, Error> {
+        let mut out = Request::default();
+        let mut wants_push_options = false;
+        loop {
+            let line = match read_data_line(lines) {
+                Ok(Some(line)) => line,
+                Ok(None) if out.commands.is_empty() => return Ok(None),
+                Ok(None) => break,
+                Err(Error::UnexpectedEof) if out.commands.is_empty() => return Ok(None),
+                Err(err) => return Err(err),
+            };
+            // Shallow clients announce their shallow commits, which we don't need as missing objects are detected anyway.
+            if line.starts_with(b"shallow ") {
+                continue;
+            }
+            let (command, capabilities) = match line.split_once_str(b"\0") {
+                Some((command, capabilities)) if out.commands.is_empty() => (command, Some(capabilities)),
+                _ => (line.as_slice(), None),
+            };
+            out.commands
+                .push(parse_command(command).ok_or_else(|| Error::MalformedLine { line: line.clone() })?);
+            for capability in capabilities.into_iter().flat_map(|c| c.split_str(b" ")) {
+                match capability {
+                    b"report-status" | b"report-status-v2" => out.report_status = true,
+                    b"side-band" => out.side_band = out.side_band.max(Some(995)),
+                    b"side-band-64k" => out.side_band = Some(65515),
+                    b"atomic" => out.atomic = true,
+                    b"push-options" => wants_push_options = true,
+                    _ => {
+                        if let Some(name) = capability.strip_prefix(b"object-format=") {
+                            if name != object_hash.as_str().as_bytes() {
+                                return Err(Error::ObjectFormatMismatch {
+                                    name: name.into(),
+                                    actual: object_hash,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if wants_push_options {
+            while let Some(option) = read_data_line(lines)? {
+                out.push_options.push(option);
+            }
+        }
+        Ok(Some(out))
+    }
+}
+
+fn parse_command(line: &[u8]) -> Option<Command> {
+    let mut tokens = line.splitn_str(3, b" ");
+    let old_id = ObjectId::from_hex(tokens.next()?).ok()?;
+    let new_id = ObjectId::from_hex(tokens.next()?).ok()?;
+    let name = tokens.next().filter(|name| !name.is_empty())?;
+    Some(Command {
+        old_id,
+        new_id,
+        name: name.into(),
+        rejection: None,
+    })
+}
+
+/// Read the next data line without trailing newline, or `None` if a flush packet was received.
+#[allow(clippy::result_large_err)]
+fn read_data_line(lines: &mut StreamingPeekableIter<impl io::Read>) -> Result<Option<BString>, Error> {
+    match lines.read_line() {
+        None => Err(Error::UnexpectedEof),
+        Some(Err(err)) if err.kind() == io::ErrorKind::UnexpectedEof => Err(Error::UnexpectedEof),
+        Some(line) => match line?? {
+            PacketLineRef::Data(data) => Ok(Some(data.strip_suffix(b"\n").unwrap_or(data).into())),
+            PacketLineRef::Flush => Ok(None),
+            PacketLineRef::Delimiter | PacketLineRef::ResponseEnd => Err(Error::MalformedLine {
+                line: "special packet line in update commands".into(),
+            }),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use git_transport::packetline::{encode, StreamingPeekableIter};
+
+    use super::Request;
+    use crate::receive_pack::Error;
+
+    fn lines(lines: &[&str]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for line in lines {
+            match *line {
+                "0000" => encode::flush_to_write(&mut out),
+                line => encode::text_to_write(line.as_bytes(), &mut out),
+            }
+            .expect("in-memory write");
+        }
+        out
+    }
+
+    const NULL: &str = "0000000000000000000000000000000000000000";
+    const ID: &str = "1111111111111111111111111111111111111111";
+
+    #[test]
+    fn commands_capabilities_and_push_options() {
+        let input = lines(&[
+            &format!(
+                "{} {} refs/heads/main\0report-status side-band-64k atomic push-options agent=git/2.0",
+                NULL, ID
+            ),
+            &format!("{} {} refs/heads/other", ID, NULL),
+            "0000",
+            "ci.skip",
+            "0000",
+        ]);
+        let request = Request::read(
+            &mut StreamingPeekableIter::new(input.as_slice(), &[]),
+            git_hash::Kind::Sha1,
+        )
+        .expect("valid")
+        .expect("present");
+        assert_eq!(request.commands.len(), 2);
+        assert!(request.commands[0].is_creation());
+        assert!(request.commands[1].is_deletion());
+        assert_eq!(request.commands[1].name, "refs/heads/other");
+        assert_eq!(request.push_options, ["ci.skip"]);
+        assert!(request.report_status && request.atomic);
+        assert_eq!(request.side_band, Some(65515));
+    }
+
+    #[test]
+    fn nothing_to_push() {
+        for input in [lines(&["0000"]), Vec::new()] {
+            assert!(Request::read(
+                &mut StreamingPeekableIter::new(input.as_slice(), &[]),
+                git_hash::Kind::Sha1
+            )
+            .expect("valid")
+            .is_none());
+        }
+    }
+
+    #[test]
+    fn malformed_commands_and_mismatching_object_formats() {
+        for (line, is_format_mismatch) in [
+            (format!("{} refs/heads/main", ID), false),
+            (format!("{} {}", NULL, ID), false),
+            (format!("{} {} refs/heads/main\0object-format=sha256", NULL, ID), true),
+        ] {
+            let input = lines(&[&line, "0000"]);
+            let err = Request::read(
+                &mut StreamingPeekableIter::new(input.as_slice(), &[]),
+                git_hash::Kind::Sha1,
+            )
+            .err()
+            .expect("invalid");
+            assert_eq!(
+                matches!(err, Error::ObjectFormatMismatch { .. }),
+                is_format_mismatch,
+                "{}",
+                line
+            );
+        }
+    }
+}
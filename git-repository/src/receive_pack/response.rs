@@ -0,0 +1,89 @@
+use std::io;
+
+use git_transport::packetline::{encode, Channel};
+
+use crate::receive_pack::{Command, Error};
+
+/// Send the names of all references of `repo` along with the objects they point to, followed by our capabilities, to `out`.
+///
+/// Symbolic references are resolved, and those which don't resolve to an object are skipped.
+#[allow(clippy::result_large_err)]
+pub(crate) fn advertise(repo: &crate::Repository, mut out: impl io::Write) -> Result<(), Error> {
+    let capabilities = format!(
+        "report-status report-status-v2 delete-refs side-band-64k quiet atomic ofs-delta push-options object-format={} agent=git/{}",
+        repo.object_hash().as_str(),
+        crate::env::agent()
+    );
+    let mut lines = Vec::new();
+    for reference in repo.refs.iter()?.all()? {
+        let mut reference = reference?;
+        // Limit the amount of hops like `git` does to avoid cycles.
+        for _ in 0..5 {
+            let target = match &reference.target {
+                git_ref::Target::Symbolic(target) => target.clone(),
+                git_ref::Target::Peeled(_) => break,
+            };
+            match repo.refs.try_find(target.as_ref())? {
+                Some(target) => reference.target = target.target,
+                None => break,
+            }
+        }
+        if let git_ref::Target::Peeled(id) = reference.target {
+            lines.push(format!("{} {}", id, reference.name.as_bstr()));
+        }
+    }
+    match lines.first_mut() {
+        Some(first) => {
+            first.push('\0');
+            first.push_str(&capabilities);
+        }
+        None => lines.push(format!(
+            "{} capabilities^{{}}\0{}",
+            repo.object_hash().null(),
+            capabilities
+        )),
+    }
+    for line in lines {
+        encode::text_to_write(line.as_bytes(), &mut out)?;
+    }
+    encode::flush_to_write(&mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Write the `report-status` of `commands` to `out`, with `unpack_error` being the reason for failing to receive the pack.
+pub(crate) fn report(commands: &[Command], unpack_error: Option<&[u8]>, mut out: impl io::Write) -> io::Result<()> {
+    match unpack_error {
+        Some(err) => encode::text_to_write(&[b"unpack ", err].concat(), &mut out)?,
+        None => encode::text_to_write(b"unpack ok", &mut out)?,
+    };
+    for command in commands {
+        let line = match &command.rejection {
+            None => [b"ok ", command.name.as_slice()].concat(),
+            Some(reason) => [b"ng ", command.name.as_slice(), b" ", reason.as_slice()].concat(),
+        };
+        encode::text_to_write(&line, &mut out)?;
+    }
+    encode::flush_to_write(&mut out)?;
+    Ok(())
+}
+
+/// Write `data` to `out` on `channel` if `max_band_data_len` is set, or as is if `data` are no [progress][Channel::Progress] messages,
+/// which are discarded without side-band.
+pub(crate) fn write_band(
+    channel: Channel,
+    data: &[u8],
+    max_band_data_len: Option<usize>,
+    mut out: impl io::Write,
+) -> io::Result<()> {
+    match max_band_data_len {
+        Some(max_len) => {
+            for chunk in data.chunks(max_len) {
+                encode::band_to_write(channel, chunk, &mut out)?;
+            }
+        }
+        None if channel == Channel::Data => out.write_all(data)?,
+        None => {}
+    }
+    Ok(())
+}
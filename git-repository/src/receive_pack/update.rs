@@ -0,0 +1,287 @@
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use git_hash::ObjectId;
+use git_pack::{Find, FindExt};
+use git_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    Target,
+};
+
+use crate::{
+    bstr::{BStr, ByteSlice},
+    receive_pack::{Command, Error},
+};
+
+/// Reject all commands whose new objects aren't fully connected, which is when objects reachable from them are missing
+/// or can't be read.
+///
+/// `received` are the objects that were sent by the client and which can be found in `db`. The traversal stops at all other objects,
+/// which have to be present in the repository already.
+/// Only interruptions are returned as error, as all other problems only affect individual commands.
+#[allow(clippy::result_large_err)]
+pub(crate) fn check_connectivity(
+    repo: &crate::Repository,
+    db: &impl Find<Error = git_odb::store::find::Error>,
+    received: &HashSet<ObjectId>,
+    commands: &mut [Command],
+    should_interrupt: &AtomicBool,
+) -> Result<(), Error> {
+    let mut buf = Vec::new();
+    let mut connected = HashSet::new();
+    for command in commands.iter_mut().filter(|c| c.is_ok() && !c.is_deletion()) {
+        let mut seen = HashSet::new();
+        match traverse_received(
+            repo,
+            db,
+            received,
+            command.new_id,
+            &connected,
+            &mut seen,
+            &mut buf,
+            should_interrupt,
+        ) {
+            Ok(true) => connected.extend(seen),
+            Ok(false) => command.reject("missing necessary objects"),
+            Err(Error::Interrupted) => return Err(Error::Interrupted),
+            Err(err) => command.reject(format!("missing necessary objects: {}", err)),
+        }
+    }
+    Ok(())
+}
+
+/// Traverse all objects reachable from `tip` which are part of the `received` objects in `db` and add them to `seen`,
+/// and return `true` if all objects they link to are present, either because they were received, are `connected` already
+/// or are present in the repository.
+#[allow(clippy::result_large_err, clippy::too_many_arguments)]
+fn traverse_received(
+    repo: &crate::Repository,
+    db: &impl Find<Error = git_odb::store::find::Error>,
+    received: &HashSet<ObjectId>,
+    tip: ObjectId,
+    connected: &HashSet<ObjectId>,
+    seen: &mut HashSet<ObjectId>,
+    buf: &mut Vec<u8>,
+    should_interrupt: &AtomicBool,
+) -> Result<bool, Error> {
+    let mut queue = vec![tip];
+    while let Some(id) = queue.pop() {
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+        if connected.contains(&id) || !seen.insert(id) {
+            continue;
+        }
+        if !received.contains(&id) {
+            if repo.objects.contains(id) {
+                continue;
+            }
+            return Ok(false);
+        }
+        let object = match db.try_find(id, buf)? {
+            Some((object, _location)) => object,
+            None => return Ok(false),
+        };
+        match object.kind {
+            git_object::Kind::Commit => {
+                let commit = git_object::CommitRef::from_bytes(object.data)?;
+                queue.push(commit.tree());
+                queue.extend(commit.parents());
+            }
+            git_object::Kind::Tree => queue.extend(
+                git_object::TreeRef::from_bytes(object.data)?
+                    .entries
+                    .into_iter()
+                    .filter(|entry| entry.mode != git_object::tree::EntryMode::Commit)
+                    .map(|entry| entry.oid.to_owned()),
+            ),
+            git_object::Kind::Tag => queue.push(git_object::TagRefIter::from_bytes(object.data).target_id()?),
+            git_object::Kind::Blob => {}
+        }
+    }
+    Ok(true)
+}
+
+/// Reject all commands which aren't allowed by our configuration or which don't apply to the current state of the references,
+/// looking up objects in `db`.
+///
+/// Only invalid configuration is returned as error, as failing to read references or objects only affects individual commands.
+#[allow(clippy::result_large_err)]
+pub(crate) fn check_commands(
+    repo: &crate::Repository,
+    db: &impl Find<Error = git_odb::store::find::Error>,
+    commands: &mut [Command],
+) -> Result<(), Error> {
+    let config = &repo.config.resolved;
+    let deny_deletes = repo
+        .config
+        .apply_leniency(config.boolean("receive", None, "denyDeletes"))?
+        .unwrap_or(false);
+    let deny_non_fast_forwards = repo
+        .config
+        .apply_leniency(config.boolean("receive", None, "denyNonFastForwards"))?
+        .unwrap_or(false);
+    let denies = |key: &str| {
+        config
+            .string("receive", None, key)
+            .map_or(true, |value| !is_allowing(value.as_ref()))
+    };
+    let deny_current_branch = !repo.is_bare() && denies("denyCurrentBranch");
+    let deny_delete_current = denies("denyDeleteCurrent");
+    let current_branch = match repo.refs.try_find("HEAD") {
+        Ok(Some(git_ref::Reference {
+            target: Target::Symbolic(name),
+            ..
+        })) => Some(name),
+        Ok(_) => None,
+        Err(err) => {
+            // Without knowing the current branch, we can't know which updates are allowed.
+            for command in commands.iter_mut() {
+                command.reject(format!("failed to read HEAD: {}", err));
+            }
+            return Ok(());
+        }
+    };
+
+    for command in commands.iter_mut().filter(|c| c.is_ok()) {
+        let name = command.name.as_bstr();
+        if !name.starts_with(b"refs/") || git_validate::reference::name(name).is_err() {
+            command.reject("funny refname");
+            continue;
+        }
+        let is_current_branch = current_branch
+            .as_ref()
+            .map_or(false, |current| current.as_bstr() == name);
+        if command.is_deletion() {
+            if deny_deletes && name.starts_with(b"refs/heads/") {
+                command.reject("deletion prohibited");
+                continue;
+            }
+            if deny_delete_current && is_current_branch {
+                command.reject("deletion of the current branch prohibited");
+                continue;
+            }
+        } else if deny_current_branch && is_current_branch {
+            command.reject("branch is currently checked out");
+            continue;
+        }
+
+        let current = match repo.refs.try_find(name) {
+            Err(err) => {
+                command.reject(format!("failed to read ref: {}", err));
+                continue;
+            }
+            Ok(None) => None,
+            Ok(Some(git_ref::Reference {
+                target: Target::Peeled(id),
+                ..
+            })) => Some(id),
+            Ok(Some(_symbolic)) => {
+                command.reject("refusing to update symbolic reference");
+                continue;
+            }
+        };
+        if current != Some(command.old_id).filter(|id| !id.is_null()) {
+            command.reject("failed to update ref");
+            continue;
+        }
+        if deny_non_fast_forwards
+            && name.starts_with(b"refs/heads/")
+            && !command.is_creation()
+            && !command.is_deletion()
+        {
+            match is_ancestor(db, command.old_id, command.new_id) {
+                Ok(true) => {}
+                Ok(false) => command.reject("non-fast-forward"),
+                Err(err) => command.reject(format!("bad ref: {}", err)),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// If one command failed, reject all others as well with the reason `git` uses for this.
+pub(crate) fn reject_all_if_one_failed(commands: &mut [Command]) {
+    if commands.iter().any(|c| !c.is_ok()) {
+        for command in commands {
+            command.reject("atomic push failure");
+        }
+    }
+}
+
+/// Apply all commands that weren't rejected in a single transaction, and reject them all if that fails.
+pub(crate) fn apply(repo: &crate::Repository, commands: &mut [Command]) {
+    let edits: Vec<_> = commands
+        .iter()
+        .filter(|c| c.is_ok())
+        .map(|command| {
+            let expected = if command.is_creation() {
+                PreviousValue::MustNotExist
+            } else {
+                PreviousValue::MustExistAndMatch(Target::Peeled(command.old_id))
+            };
+            RefEdit {
+                change: if command.is_deletion() {
+                    Change::Delete {
+                        expected,
+                        log: RefLog::AndReference,
+                    }
+                } else {
+                    Change::Update {
+                        log: LogChange {
+                            mode: RefLog::AndReference,
+                            force_create_reflog: false,
+                            message: "push".into(),
+                        },
+                        expected,
+                        new: Target::Peeled(command.new_id),
+                    }
+                },
+                name: command.name.clone().try_into().expect("validated before"),
+                deref: false,
+            }
+        })
+        .collect();
+    if edits.is_empty() {
+        return;
+    }
+    if repo.edit_references(edits).is_err() {
+        for command in commands {
+            command.reject("failed to update ref");
+        }
+    }
+}
+
+/// Return `true` if the commit `ancestor` is reachable from the commit `id`, or `false` if one of them isn't a commit.
+#[allow(clippy::result_large_err)]
+fn is_ancestor(
+    db: &impl Find<Error = git_odb::store::find::Error>,
+    ancestor: ObjectId,
+    id: ObjectId,
+) -> Result<bool, Error> {
+    let mut buf = Vec::new();
+    for candidate in [ancestor, id] {
+        if db.try_find(candidate, &mut buf)?.map(|(object, _)| object.kind) != Some(git_object::Kind::Commit) {
+            return Ok(false);
+        }
+    }
+    for commit in git_traverse::commit::Ancestors::new(
+        Some(id),
+        git_traverse::commit::ancestors::State::default(),
+        |oid, buf| db.find_commit_iter(oid, buf).map(|t| t.0),
+    ) {
+        if commit? == ancestor {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Return `true` if `value` of one of the `receive.deny*` configuration variables allows the respective action.
+fn is_allowing(value: &BStr) -> bool {
+    ["ignore", "warn", "false", "no", "off", "0"]
+        .iter()
+        .any(|allowing| value.eq_ignore_ascii_case(allowing.as_bytes()))
+}
@@ -6,9 +6,9 @@ pub mod edit {
     #[allow(missing_docs)]
     pub enum Error {
         #[error(transparent)]
-        FileTransactionPrepare(#[from] git_ref::file::transaction::prepare::Error),
+        TransactionPrepare(#[from] git_ref::store::transaction::prepare::Error),
         #[error(transparent)]
-        FileTransactionCommit(#[from] git_ref::file::transaction::commit::Error),
+        TransactionCommit(#[from] git_ref::store::transaction::commit::Error),
         #[error(transparent)]
         NameValidation(#[from] git_validate::reference::name::Error),
         #[error("Could not interpret core.filesRefLockTimeout or core.packedRefsTimeout, it must be the number in milliseconds to wait for locks or negative to wait forever")]
@@ -16,6 +16,21 @@ pub mod edit {
     }
 }
 
+///
+pub mod pack {
+    /// The error returned by [Repository::pack_refs(…)][crate::Repository::pack_refs()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        PackRefs(#[from] git_ref::file::pack_refs::Error),
+        #[error("References can only be packed if they are stored as files")]
+        UnsupportedStorageFormat,
+        #[error("Could not interpret core.filesRefLockTimeout or core.packedRefsTimeout, it must be the number in milliseconds to wait for locks or negative to wait forever")]
+        LockTimeoutConfiguration(#[from] git_config::value::Error),
+    }
+}
+
 ///
 pub mod peel {
     /// The error returned by [Reference::peel_to_id_in_place(…)][crate::Reference::peel_to_id_in_place()] and
@@ -78,8 +93,21 @@ pub mod find {
     #[allow(missing_docs)]
     pub enum Error {
         #[error(transparent)]
-        Find(#[from] git_ref::file::find::Error),
+        Find(#[from] git_ref::store::find::Error),
         #[error(transparent)]
         PackedRefsOpen(#[from] git_ref::packed::buffer::open::Error),
     }
 }
+
+///
+pub mod set_namespace {
+    /// The error returned by [Repository::set_namespace(…)][crate::Repository::set_namespace()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        NameValidation(#[from] git_validate::refname::Error),
+        #[error(transparent)]
+        Unsupported(#[from] git_ref::store::namespace::Error),
+    }
+}
@@ -2,24 +2,23 @@
 use std::path::Path;
 
 use git_odb::pack::Find;
-use git_ref::file::ReferenceExt;
 
 /// A platform to create iterators over references.
 #[must_use = "Iterators should be obtained from this iterator platform"]
 pub struct Platform<'r> {
-    pub(crate) platform: git_ref::file::iter::Platform<'r>,
+    pub(crate) platform: git_ref::store::iter::Platform<'r>,
     pub(crate) repo: &'r crate::Repository,
 }
 
 /// An iterator over references, with or without filter.
 pub struct Iter<'r> {
-    inner: git_ref::file::iter::LooseThenPacked<'r, 'r>,
+    inner: git_ref::store::iter::Iter<'r>,
     peel: bool,
     repo: &'r crate::Repository,
 }
 
 impl<'r> Iter<'r> {
-    fn new(repo: &'r crate::Repository, platform: git_ref::file::iter::LooseThenPacked<'r, 'r>) -> Self {
+    fn new(repo: &'r crate::Repository, platform: git_ref::store::iter::Iter<'r>) -> Self {
         Iter {
             inner: platform,
             peel: false,
@@ -95,14 +94,16 @@ impl<'r> Iterator for Iter<'r> {
                 .and_then(|mut r| {
                     if self.peel {
                         let handle = &self.repo;
-                        r.peel_to_id_in_place(&handle.refs, |oid, buf| {
-                            handle
-                                .objects
-                                .try_find(oid, buf)
-                                .map(|po| po.map(|(o, _l)| (o.kind, o.data)))
-                        })
-                        .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync + 'static>)
-                        .map(|_| r)
+                        handle
+                            .refs
+                            .peel_to_id_in_place(&mut r, |oid, buf| {
+                                handle
+                                    .objects
+                                    .try_find(oid, buf)
+                                    .map(|po| po.map(|(o, _l)| (o.kind, o.data)))
+                            })
+                            .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync + 'static>)
+                            .map(|_| r)
                     } else {
                         Ok(r)
                     }
@@ -119,9 +120,9 @@ pub mod init {
     #[allow(missing_docs)]
     pub enum Error {
         #[error(transparent)]
-        Io(#[from] std::io::Error),
+        Iter(#[from] git_ref::store::iter::Error),
     }
 }
 
 /// The error returned by [references()][crate::Repository::references()].
-pub type Error = git_ref::packed::buffer::open::Error;
+pub type Error = git_ref::store::iter::Error;
@@ -1,16 +1,79 @@
 //!
+use std::{convert::TryInto, time::SystemTime};
+
 use git_object::commit::MessageRef;
-use git_ref::file::ReferenceExt;
+use git_odb::pack::Find;
+use git_ref::file::log::expire::{Options as ExpireOptions, Outcome as ExpireOutcome};
 
 use crate::{
-    bstr::{BStr, BString, ByteVec},
+    bstr::{BStr, BString, ByteSlice, ByteVec},
     Reference,
 };
 
 impl<'repo> Reference<'repo> {
     /// Return a platform for obtaining iterators over reference logs.
-    pub fn log_iter(&self) -> git_ref::file::log::iter::Platform<'_, '_> {
-        self.inner.log_iter(&self.repo.refs)
+    pub fn log_iter(&self) -> git_ref::store::reflog::Platform<'_, '_> {
+        self.repo.refs.log_iter(self.inner.name.as_ref())
+    }
+
+    /// Remove entries from the reflog of this reference according to `options`, similar to `git reflog expire`,
+    /// and return how many entries were kept and removed.
+    ///
+    /// Use [`Repository::reflog_expire_options()`][crate::Repository::reflog_expire_options()] to obtain options as
+    /// configured, and note that this waits for the lock on the reference according to `core.filesRefLockTimeout`.
+    /// This fails if references aren't stored as files.
+    pub fn log_expire(&self, options: ExpireOptions) -> Result<ExpireOutcome, expire::Error> {
+        let repo = self.repo;
+        Ok(repo
+            .refs
+            .loose_store()
+            .ok_or(expire::Error::UnsupportedStorageFormat)?
+            .reflog_expire(
+                self.inner.name.as_ref(),
+                options,
+                repo.config.lock_timeout()?.0,
+                |oid, buf| {
+                    repo.objects
+                        .try_find(oid, buf)
+                        .map(|po| po.map(|(o, _l)| (o.kind, o.data)))
+                },
+            )?)
+    }
+}
+
+impl crate::Repository {
+    /// Return options for [expiring reflogs][Reference::log_expire()] with cutoff times relative to `now` as configured by
+    /// `gc.reflogExpire` and `gc.reflogExpireUnreachable`, which default to 90 and 30 days respectively.
+    ///
+    /// `never` or `false` disable expiry, whereas `now` or `all` expire all affected entries.
+    /// Note that configuration for particular references via `gc.<pattern>.reflogExpire` isn't supported yet.
+    pub fn reflog_expire_options(&self, now: SystemTime) -> Result<ExpireOptions, expire::options::Error> {
+        let cutoff = |key: &'static str, default: &str| -> Result<Option<u32>, expire::options::Error> {
+            let value = self
+                .config
+                .resolved
+                .string("gc", None, key)
+                .map(|v| v.into_owned())
+                .unwrap_or_else(|| default.into());
+            parse_expiry(value.as_ref(), now)
+                .map_err(|source| expire::options::Error {
+                    key: format!("gc.{}", key),
+                    value,
+                    source,
+                })
+                .or_else(|err| {
+                    if self.config.lenient_config {
+                        parse_expiry(default.into(), now).map_err(|_| err)
+                    } else {
+                        Err(err)
+                    }
+                })
+        };
+        Ok(ExpireOptions {
+            older_than: cutoff("reflogExpire", "90.days.ago")?,
+            unreachable_older_than: cutoff("reflogExpireUnreachable", "30.days.ago")?,
+            ..Default::default()
+        })
     }
 }
 
@@ -34,3 +97,48 @@ pub(crate) fn commit_type_by_parents(count: usize) -> Option<&'static str> {
         _two_or_more => "merge",
     })
 }
+
+/// Parse an expiry `value` like `90.days.ago` into seconds since the unix epoch relative to `now`.
+fn parse_expiry(value: &BStr, now: SystemTime) -> Result<Option<u32>, git_date::parse::Error> {
+    let value = value.to_str_lossy().replace('.', " ");
+    Ok(match value.trim() {
+        "never" | "false" => None,
+        "now" | "all" => Some(
+            now.duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs())
+                .try_into()?,
+        ),
+        value => Some(git_date::parse(value, Some(now))?.seconds_since_unix_epoch),
+    })
+}
+
+///
+pub mod expire {
+    /// The error returned by [`Reference::log_expire()`][crate::Reference::log_expire()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The lock timeout could not be obtained from the configuration")]
+        LockTimeoutConfiguration(#[from] git_config::value::Error),
+        #[error(transparent)]
+        Expire(#[from] git_ref::file::log::expire::Error),
+        #[error("Reflogs can only be expired if references are stored as files")]
+        UnsupportedStorageFormat,
+    }
+
+    ///
+    pub mod options {
+        use crate::bstr::BString;
+
+        /// The error returned by [`Repository::reflog_expire_options()`][crate::Repository::reflog_expire_options()].
+        #[derive(Debug, thiserror::Error)]
+        #[error("The value {value:?} of {key} could not be parsed as expiry date")]
+        #[allow(missing_docs)]
+        pub struct Error {
+            pub key: String,
+            pub value: BString,
+            #[source]
+            pub source: git_date::parse::Error,
+        }
+    }
+}
@@ -1,7 +1,6 @@
 //!
 
 use git_odb::pack::Find;
-use git_ref::file::ReferenceExt;
 
 use crate::{Id, Reference};
 
@@ -10,7 +9,7 @@ pub mod iter;
 pub mod remote;
 
 mod errors;
-pub use errors::{edit, find, head_commit, head_id, peel};
+pub use errors::{edit, find, head_commit, head_id, pack, peel, set_namespace};
 
 use crate::ext::ObjectIdExt;
 
@@ -69,7 +68,7 @@ impl<'repo> Reference<'repo> {
     /// This is useful to learn where this reference is ulitmately pointing to.
     pub fn peel_to_id_in_place(&mut self) -> Result<Id<'repo>, peel::Error> {
         let repo = &self.repo;
-        let oid = self.inner.peel_to_id_in_place(&repo.refs, |oid, buf| {
+        let oid = repo.refs.peel_to_id_in_place(&mut self.inner, |oid, buf| {
             repo.objects
                 .try_find(oid, buf)
                 .map(|po| po.map(|(o, _l)| (o.kind, o.data)))
@@ -52,12 +52,20 @@ impl<'repo> Remote<'repo> {
     /// Connect to the url suitable for `direction` and return a handle through which operations can be performed.
     ///
     /// Note that the `protocol.version` configuration key affects the transport protocol used to connect,
-    /// with `2` being the default.
+    /// with `2` being the default. Pushing always uses protocol version `1` as `git receive-pack` doesn't support version `2`.
+    ///
+    /// For `ssh` urls, the `core.sshCommand` configuration key sets the ssh program to run, unless overridden by the
+    /// `GIT_SSH_COMMAND` environment variable, and `ssh.variant` or `GIT_SSH_VARIANT` determine how options are passed to it.
+    /// `gitoxide.ssh.ipVersion` can be set to `4` or `6` to have it only use IPv4 or IPv6 addresses respectively.
     ///
     /// The transport used for connection can be configured via `transport_mut().configure()` assuming the actually
     /// used transport is well known. If that's not the case, the transport can be created by hand and passed to
     /// [to_connection_with_transport()][Self::to_connection_with_transport()].
-    #[cfg(any(feature = "blocking-network-client", feature = "async-network-client-async-std"))]
+    #[cfg(any(
+        feature = "blocking-network-client",
+        feature = "async-network-client-async-std",
+        feature = "async-http-transport-reqwest"
+    ))]
     #[git_protocol::maybe_async::maybe_async]
     pub async fn connect<P>(
         &self,
@@ -68,10 +76,40 @@ impl<'repo> Remote<'repo> {
         P: Progress,
     {
         let (url, version) = self.sanitized_url_and_version(direction)?;
-        let transport = git_protocol::transport::connect(url, version).await?;
+        let options = git_protocol::transport::client::connect::Options {
+            version,
+            #[cfg(feature = "blocking-network-client")]
+            ssh: self.ssh_connect_options(),
+        };
+        let transport = git_protocol::transport::connect(url, options).await?;
         Ok(self.to_connection_with_transport(transport, progress))
     }
 
+    #[cfg(feature = "blocking-network-client")]
+    #[allow(clippy::needless_update)] // the options of the built-in ssh transport are only present if it's compiled in
+    fn ssh_connect_options(&self) -> git_protocol::transport::client::ssh::connect::Options {
+        use crate::bstr::ByteSlice;
+        use git_protocol::transport::client::ssh;
+        let config = &self.repo.config.resolved;
+        let mut trusted_only = self.repo.filter_config_section();
+        ssh::connect::Options {
+            command: config
+                .string_filter("core", None, "sshCommand", &mut trusted_only)
+                .map(|command| command.to_string()),
+            kind: config
+                .string_filter("ssh", None, "variant", &mut trusted_only)
+                .and_then(|variant| ssh::ProgramKind::from_variant(&variant.to_str_lossy())),
+            ip_version: config
+                .integer_filter("gitoxide", Some("ssh"), "ipVersion", &mut trusted_only)
+                .and_then(|version| match version.ok()? {
+                    4 => Some(ssh::connect::IpVersion::V4),
+                    6 => Some(ssh::connect::IpVersion::V6),
+                    _ => None,
+                }),
+            ..Default::default()
+        }
+    }
+
     /// Produce the sanitized URL and protocol version to use as obtained by querying the repository configuration.
     ///
     /// This can be useful when using custom transports to allow additional configuration.
@@ -120,6 +158,11 @@ impl<'repo> Remote<'repo> {
                 })
             })?;
 
+        let version = match direction {
+            crate::remote::Direction::Push => Protocol::V1,
+            crate::remote::Direction::Fetch => version,
+        };
+
         let url = self.url(direction).ok_or(Error::MissingUrl { direction })?.to_owned();
         if !self.repo.config.url_scheme()?.allow(&url.scheme) {
             return Err(Error::ProtocolDenied {
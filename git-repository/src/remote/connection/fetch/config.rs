@@ -1,6 +1,6 @@
 use std::convert::TryInto;
 
-use super::Error;
+use super::{negotiate, Error};
 use crate::Repository;
 
 pub fn index_threads(repo: &Repository) -> Result<Option<usize>, Error> {
@@ -65,3 +65,77 @@ pub fn pack_index_version(repo: &Repository) -> Result<git_pack::index::Version,
         },
     )
 }
+
+pub fn negotiation_algorithm(repo: &Repository) -> Result<negotiate::Algorithm, Error> {
+    use negotiate::Algorithm;
+    let lenient_config = repo.options.lenient_config;
+    Ok(
+        match repo.config.resolved.string("fetch", None, "negotiationAlgorithm") {
+            Some(name) if name.as_ref() == "consecutive" || name.as_ref() == "default" => Algorithm::Consecutive,
+            Some(name) if name.as_ref() == "skipping" => Algorithm::Skipping,
+            Some(name) if name.as_ref() == "noop" => Algorithm::Noop,
+            Some(_) if lenient_config => Algorithm::default(),
+            Some(name) => {
+                return Err(Error::UnknownNegotiationAlgorithm {
+                    name: name.into_owned(),
+                })
+            }
+            None => match repo
+                .config
+                .resolved
+                .boolean("feature", None, "experimental")
+                .transpose()
+            {
+                Ok(Some(true)) => Algorithm::Skipping,
+                Ok(_) => Algorithm::default(),
+                Err(_) if lenient_config => Algorithm::default(),
+                Err(err) => {
+                    return Err(Error::Configuration {
+                        message: "The configured feature.experimental is not a boolean",
+                        desired: None,
+                        source: err.into(),
+                    })
+                }
+            },
+        },
+    )
+}
+
+/// Return `true` if the remote named `remote_name` is a promisor remote, either as configured with `remote.<name>.promisor`
+/// or by being the remote of a partial clone as configured in `extensions.partialClone`.
+pub fn is_promisor(repo: &Repository, remote_name: Option<&str>) -> Result<bool, Error> {
+    let remote_name = match remote_name {
+        Some(name) => name,
+        None => return Ok(false),
+    };
+    if repo
+        .promisor_remote_name()
+        .map_or(false, |name| name.as_ref() == remote_name)
+    {
+        return Ok(true);
+    }
+    match repo
+        .config
+        .resolved
+        .boolean("remote", Some(remote_name), "promisor")
+        .transpose()
+    {
+        Ok(value) => Ok(value.unwrap_or(false)),
+        Err(_) if repo.options.lenient_config => Ok(false),
+        Err(err) => Err(Error::Configuration {
+            message: "The configured remote.<name>.promisor is not a boolean",
+            desired: None,
+            source: err.into(),
+        }),
+    }
+}
+
+/// Return the filter configured in `remote.<name>.partialCloneFilter` for the remote named `remote_name`.
+pub fn partial_clone_filter(repo: &Repository, remote_name: Option<&str>) -> Option<crate::bstr::BString> {
+    remote_name.and_then(|name| {
+        repo.config
+            .resolved
+            .string("remote", Some(name), "partialCloneFilter")
+            .map(|spec| spec.into_owned())
+    })
+}
@@ -15,8 +15,24 @@ pub enum Error {
         local: git_hash::Kind,
         remote: git_hash::Kind,
     },
+    #[error("Unknown fetch.negotiationAlgorithm {name:?}, expected one of 'consecutive', 'skipping' or 'noop'")]
+    UnknownNegotiationAlgorithm { name: crate::bstr::BString },
     #[error(transparent)]
     Negotiate(#[from] super::negotiate::Error),
+    #[error("The server doesn't support the '{feature}' capability: {description}")]
+    MissingServerFeature {
+        feature: &'static str,
+        description: &'static str,
+    },
+    #[error("Failed to write the promisor file at \"{}\"", path.display())]
+    WritePromisorFile {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    ShallowOpen(#[from] crate::shallow::open::Error),
+    #[error(transparent)]
+    ShallowWrite(#[from] crate::shallow::write::Error),
     #[error(transparent)]
     Client(#[from] git_protocol::transport::client::Error),
     #[error(transparent)]
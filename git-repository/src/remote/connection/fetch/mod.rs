@@ -115,8 +115,25 @@ where
             dry_run: DryRun::No,
             reflog_message: None,
             write_packed_refs: WritePackedRefs::Never,
+            shallow: Default::default(),
+            filter: None,
         })
     }
+
+    /// Like [`prepare_fetch()`][Self::prepare_fetch()], but reuse the `ref_map` of a prior handshake performed over our transport,
+    /// as obtained by [`Prepare::into_transport_and_ref_map()`].
+    #[cfg(feature = "blocking-network-client")]
+    pub(crate) fn prepare_fetch_with_ref_map(self, ref_map: RefMap) -> Prepare<'remote, 'repo, T, P> {
+        Prepare {
+            con: Some(self),
+            ref_map,
+            dry_run: DryRun::No,
+            reflog_message: None,
+            write_packed_refs: WritePackedRefs::Never,
+            shallow: Default::default(),
+            filter: None,
+        }
+    }
 }
 
 impl<'remote, 'repo, T, P> Prepare<'remote, 'repo, T, P>
@@ -127,6 +144,14 @@ where
     pub fn ref_map(&self) -> &RefMap {
         &self.ref_map
     }
+
+    /// Return the transport after the handshake along with the ref-map, to continue the fetch with a different remote
+    /// without performing another handshake.
+    #[cfg(feature = "blocking-network-client")]
+    pub(crate) fn into_transport_and_ref_map(mut self) -> (T, RefMap) {
+        let con = self.con.take().expect("present until the pack is received");
+        (con.transport, std::mem::take(&mut self.ref_map))
+    }
 }
 
 mod config;
@@ -145,6 +170,8 @@ where
     dry_run: DryRun,
     reflog_message: Option<RefLogMessage>,
     write_packed_refs: WritePackedRefs,
+    shallow: remote::fetch::Shallow,
+    filter: Option<BString>,
 }
 
 /// Builder
@@ -174,6 +201,25 @@ where
         self.reflog_message = reflog_message.into();
         self
     }
+
+    /// Define how the shallow boundary of the repository should change, or if a complete repository should become shallow.
+    ///
+    /// The default is to keep the shallow boundary as is.
+    pub fn with_shallow(mut self, shallow: remote::fetch::Shallow) -> Self {
+        self.shallow = shallow;
+        self
+    }
+
+    /// Ask the server to omit objects as described by the filter `spec`, like `blob:none` or `blob:limit=1m`, to perform a
+    /// partial fetch.
+    ///
+    /// The received pack will be marked as promisor pack, which promises that the omitted objects can be obtained from
+    /// the remote later.
+    /// If unset, the filter configured in `remote.<name>.partialCloneFilter` is used if the remote is a promisor remote.
+    pub fn with_filter(mut self, spec: impl Into<BString>) -> Self {
+        self.filter = Some(spec.into());
+        self
+    }
 }
 
 impl<'remote, 'repo, T, P> Drop for Prepare<'remote, 'repo, T, P>
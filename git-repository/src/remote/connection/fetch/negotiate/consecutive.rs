@@ -0,0 +1,110 @@
+use git_hash::ObjectId;
+
+use super::{Error, Flags, Graph, Negotiator, Queue, ADVERTISED, COMMON, POPPED, SEEN};
+
+/// The `consecutive` algorithm of `git`, which sends every commit reachable from our tips, newest first, until the remote
+/// acknowledged enough of them. Ancestors of commits known to be common aren't sent.
+#[derive(Default)]
+pub(crate) struct Consecutive {
+    queue: Queue,
+    non_common_revs: usize,
+}
+
+impl Consecutive {
+    fn add_to_queue(&mut self, id: ObjectId, mark: Flags, graph: &mut Graph) -> Result<(), Error> {
+        if let Some(commit) = graph.try_lookup(&id)? {
+            if commit.flags & mark == 0 {
+                commit.flags |= mark;
+                self.queue.push(commit.commit_time, id);
+                if commit.flags & COMMON == 0 {
+                    self.non_common_revs += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark `id` and all of its ancestors we have seen as common, or only its ancestors if `ancestors_only` is `true`.
+    fn mark_common(&mut self, id: ObjectId, ancestors_only: bool, graph: &mut Graph) -> Result<(), Error> {
+        let mut stack = vec![(id, ancestors_only)];
+        while let Some((id, ancestors_only)) = stack.pop() {
+            let commit = match graph.try_lookup(&id)? {
+                Some(commit) if commit.flags & COMMON == 0 => commit,
+                _ => continue,
+            };
+            if !ancestors_only {
+                commit.flags |= COMMON;
+            }
+            if commit.flags & SEEN == 0 {
+                self.add_to_queue(id, SEEN, graph)?;
+            } else {
+                if !ancestors_only && commit.flags & POPPED == 0 {
+                    self.non_common_revs = self.non_common_revs.saturating_sub(1);
+                }
+                stack.extend(commit.parents.iter().rev().map(|id| (*id, false)));
+            }
+        }
+        Ok(())
+    }
+
+    fn next_rev(&mut self, graph: &mut Graph) -> Result<Option<ObjectId>, Error> {
+        loop {
+            if self.non_common_revs == 0 {
+                return Ok(None);
+            }
+            let id = match self.queue.pop() {
+                Some(id) => id,
+                None => return Ok(None),
+            };
+            let commit = graph.get_mut(&id);
+            commit.flags |= POPPED;
+            if commit.flags & COMMON == 0 {
+                self.non_common_revs -= 1;
+            }
+            let (send, mark) = if commit.flags & COMMON != 0 {
+                // Don't send it, and ignore its ancestors.
+                (false, COMMON | SEEN)
+            } else if commit.flags & ADVERTISED != 0 {
+                // Send it, but ignore its ancestors.
+                (true, COMMON | SEEN)
+            } else {
+                (true, SEEN)
+            };
+            for parent_id in commit.parents.clone() {
+                if graph.try_lookup(&parent_id)?.map_or(false, |p| p.flags & SEEN == 0) {
+                    self.add_to_queue(parent_id, mark, graph)?;
+                }
+                if mark & COMMON != 0 {
+                    self.mark_common(parent_id, true, graph)?;
+                }
+            }
+            if send {
+                return Ok(Some(id));
+            }
+        }
+    }
+}
+
+impl Negotiator for Consecutive {
+    fn known_common(&mut self, id: ObjectId, graph: &mut Graph) -> Result<(), Error> {
+        if graph.try_lookup(&id)?.map_or(false, |c| c.flags & SEEN == 0) {
+            self.add_to_queue(id, ADVERTISED | SEEN, graph)?;
+            self.mark_common(id, true, graph)?;
+        }
+        Ok(())
+    }
+
+    fn add_tip(&mut self, id: ObjectId, graph: &mut Graph) -> Result<(), Error> {
+        self.add_to_queue(id, SEEN, graph)
+    }
+
+    fn next_have(&mut self, graph: &mut Graph) -> Option<Result<ObjectId, Error>> {
+        self.next_rev(graph).transpose()
+    }
+
+    fn in_common_with_remote(&mut self, id: ObjectId, graph: &mut Graph) -> Result<bool, Error> {
+        let known_to_be_common = graph.try_lookup(&id)?.map_or(false, |c| c.flags & COMMON != 0);
+        self.mark_common(id, false, graph)?;
+        Ok(known_to_be_common)
+    }
+}
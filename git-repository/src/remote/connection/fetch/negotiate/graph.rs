@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use git_hash::{oid, ObjectId};
+use git_odb::Find;
+use smallvec::SmallVec;
+
+use super::{Error, Flags};
+
+/// A commit as far as the negotiation is concerned.
+pub(crate) struct Commit {
+    /// The time at which the commit was committed, in seconds since the unix epoch.
+    pub commit_time: u64,
+    /// The ids of all parents of the commit.
+    pub parents: SmallVec<[ObjectId; 1]>,
+    /// The flags set by the negotiation algorithm.
+    pub flags: Flags,
+}
+
+/// A lazily populated graph of commits which are looked up in the commit-graph file first, if present, and decoded from the
+/// object database otherwise.
+pub(crate) struct Graph {
+    objects: crate::OdbHandle,
+    cache: Option<git_commitgraph::Graph>,
+    /// All commits looked up so far, or `None` if the object didn't exist or wasn't a commit.
+    map: HashMap<ObjectId, Option<Commit>>,
+    /// The sorted commits of a shallow repository whose parents are missing, which is why they are treated as having none.
+    shallow_commits: Vec<ObjectId>,
+    buf: Vec<u8>,
+}
+
+impl Graph {
+    /// Create a new graph for the commits in `repo`, using its commit-graph if it can be loaded.
+    pub fn new(repo: &crate::Repository) -> Result<Self, Error> {
+        let mut objects = repo.objects.clone();
+        // Commits we don't have are expected, and must not be fetched from a promisor remote.
+        objects.unset_missing_object_hook();
+        Ok(Graph {
+            objects,
+            cache: git_commitgraph::Graph::from_info_dir(repo.objects.store_ref().path().join("info")).ok(),
+            map: HashMap::new(),
+            shallow_commits: repo.shallow_commits()?.unwrap_or_default(),
+            buf: Vec::new(),
+        })
+    }
+
+    /// Return the commit with `id`, or `None` if it doesn't exist locally or if it isn't a commit.
+    pub fn try_lookup(&mut self, id: &ObjectId) -> Result<Option<&mut Commit>, Error> {
+        if !self.map.contains_key(id) {
+            let commit = self.load(id)?;
+            self.map.insert(*id, commit);
+        }
+        Ok(self.map.get_mut(id).and_then(Option::as_mut))
+    }
+
+    /// Return the commit with `id` which must have been [looked up][Self::try_lookup()] before.
+    pub fn get_mut(&mut self, id: &ObjectId) -> &mut Commit {
+        self.map
+            .get_mut(id)
+            .and_then(Option::as_mut)
+            .expect("commits in the queue were looked up before")
+    }
+
+    fn load(&mut self, id: &oid) -> Result<Option<Commit>, Error> {
+        let mut commit = self.load_unshallow(id)?;
+        if let Some(commit) = commit.as_mut() {
+            if self
+                .shallow_commits
+                .binary_search_by(|shallow| shallow.as_ref().cmp(id))
+                .is_ok()
+            {
+                commit.parents.clear();
+            }
+        }
+        Ok(commit)
+    }
+
+    fn load_unshallow(&mut self, id: &oid) -> Result<Option<Commit>, Error> {
+        if let Some(commit) = self.cache.as_ref().and_then(|cache| cache.commit_by_id(id)) {
+            let cache = self.cache.as_ref().expect("present");
+            let mut parents = SmallVec::new();
+            for pos in commit.iter_parents() {
+                parents.push(cache.id_at(pos?).to_owned());
+            }
+            return Ok(Some(Commit {
+                commit_time: commit.committer_timestamp(),
+                parents,
+                flags: 0,
+            }));
+        }
+        let data = match self.objects.try_find(id, &mut self.buf)? {
+            Some(data) if data.kind == git_object::Kind::Commit => data,
+            _ => return Ok(None),
+        };
+        let iter = git_object::CommitRefIter::from_bytes(data.data);
+        Ok(Some(Commit {
+            commit_time: iter.committer()?.time.seconds_since_unix_epoch.into(),
+            parents: iter.parent_ids().collect(),
+            flags: 0,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Graph;
+    use crate as git;
+
+    #[test]
+    fn shallow_commits_have_no_parents_even_if_the_commit_graph_lists_them() -> git_testtools::Result {
+        let dir = git_testtools::scripted_fixture_repo_read_only("make_shallow_repos.sh")?;
+        let repo = git::open_opts(dir.join("shallow-with-commit-graph"), git::open::Options::isolated())?;
+        let head_id = repo.head_id()?.detach();
+        assert_eq!(
+            repo.shallow_commits()?,
+            Some(vec![head_id]),
+            "the depth of the history is 1"
+        );
+
+        let mut graph = Graph::new(&repo)?;
+        let parents = graph.try_lookup(&head_id)?.expect("present").parents.clone();
+        assert!(
+            parents.is_empty(),
+            "the parent isn't traversed as it's beyond the shallow boundary"
+        );
+        Ok(())
+    }
+}
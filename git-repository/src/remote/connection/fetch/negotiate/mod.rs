@@ -0,0 +1,269 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use git_hash::ObjectId;
+use git_protocol::fetch::response::Acknowledgement;
+
+mod consecutive;
+mod graph;
+mod skipping;
+
+use graph::Graph;
+
+/// The way the negotiation is performed, as configured by `fetch.negotiationAlgorithm`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Don't send any `have` lines, which makes the server send all objects reachable from the wanted tips.
+    Noop,
+    /// Walk the commits reachable from all local references newest first and send them until the server found enough
+    /// commits in common, which is the default.
+    Consecutive,
+    /// Like [`Consecutive`][Algorithm::Consecutive], but skip an increasing amount of commits between the ones that are sent,
+    /// which finds commits in common in fewer rounds at the risk of receiving more objects than needed.
+    Skipping,
+}
+
+impl Default for Algorithm {
+    fn default() -> Self {
+        Algorithm::Consecutive
+    }
+}
+
+/// The error returned during negotiation.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("We were unable to figure out what objects the server should send after {rounds} round(s)")]
+    NegotiationFailed { rounds: usize },
+    #[error("Could not open the packed-refs file to iterate local references")]
+    OpenPackedRefs(#[from] crate::reference::iter::Error),
+    #[error("Could not iterate local references")]
+    IterReferences(#[from] crate::reference::iter::init::Error),
+    #[error("Could not read or peel a local reference")]
+    ReadReference(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error(transparent)]
+    FindCommit(#[from] git_odb::store::find::Error),
+    #[error(transparent)]
+    DecodeCommit(#[from] git_object::decode::Error),
+    #[error(transparent)]
+    CommitGraph(#[from] git_commitgraph::file::commit::Error),
+    #[error(transparent)]
+    ShallowCommits(#[from] crate::shallow::open::Error),
+}
+
+/// Flags used by the negotiation algorithms to keep track of commits.
+type Flags = u8;
+/// The commit was added to the queue.
+const SEEN: Flags = 1 << 0;
+/// The commit is known to be in common with the remote.
+const COMMON: Flags = 1 << 1;
+/// The commit is the tip of a remote reference, which makes it common with the remote as well.
+const ADVERTISED: Flags = 1 << 2;
+/// The commit was taken from the queue.
+const POPPED: Flags = 1 << 3;
+
+/// The amount of `have` lines to send in the first round.
+const INITIAL_FLUSH: usize = 16;
+/// The amount of `have` lines after which we increase the amount of haves per round more slowly.
+const LARGE_FLUSH: usize = 16384;
+/// The amount of `have` lines to send without any new commit in common before giving up, once the server acknowledged one.
+const MAX_IN_VAIN: usize = 256;
+
+/// A queue of commits, ordered by commit time with the newest first and in insertion order if times are equal.
+#[derive(Default)]
+struct Queue {
+    heap: BinaryHeap<(u64, Reverse<usize>, ObjectId)>,
+    count: usize,
+}
+
+impl Queue {
+    fn push(&mut self, commit_time: u64, id: ObjectId) {
+        self.heap.push((commit_time, Reverse(self.count), id));
+        self.count += 1;
+    }
+
+    fn pop(&mut self) -> Option<ObjectId> {
+        self.heap.pop().map(|(_, _, id)| id)
+    }
+}
+
+/// A negotiation algorithm which decides which commits to send as `have` lines.
+trait Negotiator {
+    /// Mark `id` as in common with the remote as it's the tip of one of its references, before the negotiation starts.
+    fn known_common(&mut self, id: ObjectId, graph: &mut Graph) -> Result<(), Error>;
+    /// Add `id`, the tip of one of our references, as starting point of the traversal.
+    fn add_tip(&mut self, id: ObjectId, graph: &mut Graph) -> Result<(), Error>;
+    /// Produce the next commit to send as `have`, or `None` if there is nothing left to send.
+    fn next_have(&mut self, graph: &mut Graph) -> Option<Result<ObjectId, Error>>;
+    /// Mark `id` as acknowledged by the remote to be in common, and return `true` if it was known to be in common already.
+    fn in_common_with_remote(&mut self, id: ObjectId, graph: &mut Graph) -> Result<bool, Error>;
+}
+
+struct Noop;
+
+impl Negotiator for Noop {
+    fn known_common(&mut self, _id: ObjectId, _graph: &mut Graph) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn add_tip(&mut self, _id: ObjectId, _graph: &mut Graph) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn next_have(&mut self, _graph: &mut Graph) -> Option<Result<ObjectId, Error>> {
+        None
+    }
+
+    fn in_common_with_remote(&mut self, _id: ObjectId, _graph: &mut Graph) -> Result<bool, Error> {
+        Ok(false)
+    }
+}
+
+/// The state of a negotiation which spans multiple rounds.
+pub(crate) struct State {
+    negotiator: Box<dyn Negotiator>,
+    graph: Graph,
+    /// If `true`, the server doesn't remember what we sent in previous rounds, so commits in common have to be sent again.
+    stateless: bool,
+    haves_to_send: usize,
+    in_vain: usize,
+    seen_ack: bool,
+    /// Set if the server signalled that it has enough information to produce a pack.
+    ready: bool,
+    /// All commits the server acknowledged as common.
+    common: Vec<ObjectId>,
+}
+
+impl State {
+    /// Prepare a negotiation with `algo` for obtaining the remote references in `ref_map` using our references in `repo`.
+    ///
+    /// If `stateless` is `true`, the server forgets about the commits in common after each round.
+    pub fn new(
+        algo: Algorithm,
+        repo: &crate::Repository,
+        ref_map: &crate::remote::fetch::RefMap,
+        stateless: bool,
+    ) -> Result<Self, Error> {
+        let mut graph = Graph::new(repo)?;
+        let mut negotiator: Box<dyn Negotiator> = match algo {
+            Algorithm::Noop => Box::new(Noop),
+            Algorithm::Consecutive => Box::new(consecutive::Consecutive::default()),
+            Algorithm::Skipping => Box::new(skipping::Skipping::default()),
+        };
+
+        let mut tips = Vec::new();
+        for reference in repo.references()?.all()?.peeled() {
+            let reference = reference.map_err(Error::ReadReference)?;
+            if let Some(id) = reference.target().try_id() {
+                tips.push(id.to_owned());
+            }
+        }
+        for mapping in &ref_map.mappings {
+            if let Some(id) = mapping.remote.as_id() {
+                if tips.iter().any(|tip| tip.as_ref() == id) {
+                    negotiator.known_common(id.to_owned(), &mut graph)?;
+                }
+            }
+        }
+        for tip in tips {
+            negotiator.add_tip(tip, &mut graph)?;
+        }
+
+        Ok(State {
+            negotiator,
+            graph,
+            stateless,
+            haves_to_send: INITIAL_FLUSH,
+            in_vain: 0,
+            seen_ack: false,
+            ready: false,
+            common: Vec::new(),
+        })
+    }
+}
+
+/// Add the objects of all remote references in `ref_map` to `arguments` as `want` if their local destination reference doesn't exist
+/// or points to a different object, or if the `shallow` boundary is to be changed.
+pub(crate) fn add_wants(
+    repo: &crate::Repository,
+    ref_map: &crate::remote::fetch::RefMap,
+    shallow: &crate::remote::fetch::Shallow,
+    arguments: &mut git_protocol::fetch::Arguments,
+) {
+    let changes_shallow_boundary = *shallow != crate::remote::fetch::Shallow::NoChange;
+    for mapping in &ref_map.mappings {
+        let want_id = match mapping.remote.as_id() {
+            Some(id) => id,
+            None => continue,
+        };
+        let have_id = mapping.local.as_ref().and_then(|name| {
+            repo.find_reference(name)
+                .ok()
+                .and_then(|r| r.target().try_id().map(ToOwned::to_owned))
+        });
+        if changes_shallow_boundary || have_id.as_deref() != Some(want_id) {
+            arguments.want(want_id);
+        }
+    }
+}
+
+/// Negotiate one round using `state` and add the haves for this round to `arguments`, after learning about commits in common
+/// from the `previous_response` of the server if this isn't the first round.
+/// Returns `true` if the negotiation is done from our side so the server won't keep asking.
+pub(crate) fn one_round(
+    state: &mut State,
+    arguments: &mut git_protocol::fetch::Arguments,
+    previous_response: Option<&git_protocol::fetch::Response>,
+) -> Result<bool, Error> {
+    for ack in previous_response.map(|r| r.acknowledgements()).unwrap_or_default() {
+        match ack {
+            Acknowledgement::Common(id) => {
+                let was_common = state.negotiator.in_common_with_remote(*id, &mut state.graph)?;
+                if !state.common.contains(id) {
+                    state.common.push(*id);
+                }
+                if !was_common && !state.stateless {
+                    state.in_vain = 0;
+                }
+                state.seen_ack = true;
+            }
+            Acknowledgement::Ready => state.ready = true,
+            Acknowledgement::Nak => {}
+        }
+    }
+
+    if state.stateless {
+        for id in &state.common {
+            arguments.have(id);
+        }
+    }
+    let mut haves_added = 0;
+    if !state.ready {
+        while haves_added < state.haves_to_send {
+            match state.negotiator.next_have(&mut state.graph).transpose()? {
+                Some(id) => arguments.have(id),
+                None => break,
+            }
+            haves_added += 1;
+        }
+    }
+    state.in_vain += haves_added;
+    state.haves_to_send = next_flush(state.stateless, state.haves_to_send);
+
+    Ok(state.ready || haves_added == 0 || (state.seen_ack && state.in_vain >= MAX_IN_VAIN))
+}
+
+/// Return the amount of `have` lines to send in the next round, after sending `count` in this round.
+fn next_flush(stateless: bool, count: usize) -> usize {
+    const PIPESAFE_FLUSH: usize = 32;
+    if stateless {
+        if count < LARGE_FLUSH {
+            count * 2
+        } else {
+            count * 11 / 10
+        }
+    } else if count < PIPESAFE_FLUSH {
+        count * 2
+    } else {
+        count + PIPESAFE_FLUSH
+    }
+}
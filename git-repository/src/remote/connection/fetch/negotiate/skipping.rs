@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use git_hash::ObjectId;
+
+use super::{Error, Flags, Graph, Negotiator, Queue, ADVERTISED, COMMON, POPPED, SEEN};
+
+/// The amount of commits to skip before sending the next one, which grows as long as no commit in common was found.
+#[derive(Default, Clone, Copy)]
+struct Entry {
+    original_ttl: u16,
+    ttl: u16,
+}
+
+/// The `skipping` algorithm of `git`, which walks commits like [`Consecutive`][super::consecutive::Consecutive] but skips
+/// an increasing amount of them between the ones it sends, to find commits in common in fewer rounds in long diverged histories.
+#[derive(Default)]
+pub(crate) struct Skipping {
+    queue: Queue,
+    /// The entries of all commits in the queue.
+    entries: HashMap<ObjectId, Entry>,
+    non_common_revs: usize,
+}
+
+impl Skipping {
+    fn add_to_queue(&mut self, id: ObjectId, mark: Flags, graph: &mut Graph) -> Result<bool, Error> {
+        let commit = match graph.try_lookup(&id)? {
+            Some(commit) => commit,
+            None => return Ok(false),
+        };
+        commit.flags |= mark | SEEN;
+        self.queue.push(commit.commit_time, id);
+        self.entries.insert(id, Entry::default());
+        if mark & COMMON == 0 {
+            self.non_common_revs += 1;
+        }
+        Ok(true)
+    }
+
+    /// Mark `id` and all of its ancestors we have seen as common.
+    fn mark_common(&mut self, id: ObjectId, graph: &mut Graph) -> Result<(), Error> {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let commit = match graph.try_lookup(&id)? {
+                Some(commit) if commit.flags & COMMON == 0 => commit,
+                _ => continue,
+            };
+            commit.flags |= COMMON;
+            if commit.flags & POPPED == 0 {
+                self.non_common_revs = self.non_common_revs.saturating_sub(1);
+            }
+            for parent_id in commit.parents.clone().into_iter().rev() {
+                if graph.try_lookup(&parent_id)?.map_or(false, |p| p.flags & SEEN != 0) {
+                    stack.push(parent_id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue `parent_id` as parent of the commit `id` with `entry`, and return `true` if it was queued or is still in the queue.
+    fn push_parent(
+        &mut self,
+        id: ObjectId,
+        entry: Entry,
+        parent_id: ObjectId,
+        graph: &mut Graph,
+    ) -> Result<bool, Error> {
+        let parent_flags = match graph.try_lookup(&parent_id)? {
+            Some(parent) => parent.flags,
+            None => return Ok(false),
+        };
+        if parent_flags & SEEN != 0 {
+            if parent_flags & POPPED != 0 {
+                // The parent was already popped due to clock skew, so pretend it doesn't exist.
+                return Ok(false);
+            }
+        } else if !self.add_to_queue(parent_id, 0, graph)? {
+            return Ok(false);
+        }
+
+        if graph.get_mut(&id).flags & (COMMON | ADVERTISED) != 0 {
+            self.mark_common(parent_id, graph)?;
+        } else {
+            let new_original_ttl = if entry.ttl > 0 {
+                entry.original_ttl
+            } else {
+                entry.original_ttl.saturating_mul(3) / 2 + 1
+            };
+            let new_ttl = if entry.ttl > 0 { entry.ttl - 1 } else { new_original_ttl };
+            let parent_entry = self.entries.get_mut(&parent_id).expect("queued commits have an entry");
+            if parent_entry.original_ttl < new_original_ttl {
+                parent_entry.original_ttl = new_original_ttl;
+                parent_entry.ttl = new_ttl;
+            }
+        }
+        Ok(true)
+    }
+
+    fn next_rev(&mut self, graph: &mut Graph) -> Result<Option<ObjectId>, Error> {
+        loop {
+            if self.non_common_revs == 0 {
+                return Ok(None);
+            }
+            let id = match self.queue.pop() {
+                Some(id) => id,
+                None => return Ok(None),
+            };
+            let entry = self.entries.remove(&id).expect("queued commits have an entry");
+            let commit = graph.get_mut(&id);
+            commit.flags |= POPPED;
+            let is_common = commit.flags & COMMON != 0;
+            if !is_common {
+                self.non_common_revs -= 1;
+            }
+
+            let mut parent_pushed = false;
+            for parent_id in commit.parents.clone() {
+                parent_pushed |= self.push_parent(id, entry, parent_id, graph)?;
+            }
+            // Send commits whose time to live ran out, and those without parents in the queue as we can't skip them.
+            if !is_common && (entry.ttl == 0 || !parent_pushed) {
+                return Ok(Some(id));
+            }
+        }
+    }
+}
+
+impl Negotiator for Skipping {
+    fn known_common(&mut self, id: ObjectId, graph: &mut Graph) -> Result<(), Error> {
+        if graph.try_lookup(&id)?.map_or(false, |c| c.flags & SEEN == 0) {
+            self.add_to_queue(id, ADVERTISED, graph)?;
+        }
+        Ok(())
+    }
+
+    fn add_tip(&mut self, id: ObjectId, graph: &mut Graph) -> Result<(), Error> {
+        if graph.try_lookup(&id)?.map_or(false, |c| c.flags & SEEN == 0) {
+            self.add_to_queue(id, 0, graph)?;
+        }
+        Ok(())
+    }
+
+    fn next_have(&mut self, graph: &mut Graph) -> Option<Result<ObjectId, Error>> {
+        self.next_rev(graph).transpose()
+    }
+
+    fn in_common_with_remote(&mut self, id: ObjectId, graph: &mut Graph) -> Result<bool, Error> {
+        let flags = match graph.try_lookup(&id)? {
+            // We only expect acknowledgements for commits we have sent.
+            Some(commit) if commit.flags & SEEN != 0 => commit.flags,
+            _ => return Ok(false),
+        };
+        self.mark_common(id, graph)?;
+        Ok(flags & COMMON != 0)
+    }
+}
@@ -4,6 +4,7 @@ use git_odb::FindExt;
 use git_protocol::transport::client::Transport;
 
 use crate::{
+    bstr::{BString, ByteSlice},
     remote,
     remote::{
         connection::fetch::config,
@@ -25,8 +26,21 @@ where
     ///
     /// ### Negotiation
     ///
-    /// "fetch.negotiationAlgorithm" describes algorithms `git` uses currently, with the default being `consecutive` and `skipping` being
-    /// experimented with. We currently implement something we could call 'naive' which works for now.
+    /// The commits to send as `have` lines are chosen by walking the history of all local references with the algorithm configured
+    /// in `fetch.negotiationAlgorithm`, which is `consecutive` by default or `skipping` if `feature.experimental` is set, and `noop`
+    /// to not send any. The commit-graph is used to speed up the walk if present.
+    /// The negotiation takes as many rounds as needed for the server to know which objects to send, and commits it acknowledged
+    /// as common are sent again in each round to servers which don't keep state between rounds.
+    ///
+    /// With protocol V1 over connections that persist across rounds, like `ssh`, `file` or `git`, the server remembers the commits
+    /// in common so only new `have` lines are sent in each round.
+    ///
+    /// ### Shallow Repositories
+    ///
+    /// If the repository is shallow, its shallow commits are sent to the server so it doesn't assume we have their parents.
+    /// The shallow boundary is changed as configured with [`with_shallow()`][Self::with_shallow()], and the changes sent by the
+    /// server are written to the `shallow` file once the pack was received. All remote references are wanted in that case
+    /// as their history changes even if they didn't move.
     ///
     /// ### Pack `.keep` files
     ///
@@ -60,6 +74,7 @@ where
     /// ### Configuration
     ///
     /// - `gitoxide.userAgent` is read to obtain the application user agent for git servers and for HTTP servers as well.
+    /// - `fetch.negotiationAlgorithm` and `feature.experimental` determine the negotiation algorithm.
     ///
     #[git_protocol::maybe_async::maybe_async]
     pub async fn receive(mut self, should_interrupt: &AtomicBool) -> Result<Outcome, Error> {
@@ -79,6 +94,7 @@ where
 
         git_protocol::fetch::Response::check_required_features(protocol_version, &fetch_features)?;
         let sideband_all = fetch_features.iter().any(|(n, _)| *n == "sideband-all");
+        let no_done = fetch_features.iter().any(|(n, _)| *n == "no-done");
         let mut arguments = git_protocol::fetch::Arguments::new(protocol_version, fetch_features);
         let mut previous_response = None::<git_protocol::fetch::Response>;
         let mut round = 1;
@@ -90,25 +106,43 @@ where
             });
         }
 
+        let shallow_commits = repo.shallow_commits()?;
+        let is_promisor_pack = match add_shallow_args(&mut arguments, &self.shallow, shallow_commits.as_deref())
+            .and_then(|()| add_filter_arg(&mut arguments, repo, con.remote.name(), self.filter.as_ref()))
+        {
+            Ok(is_promisor_pack) => is_promisor_pack,
+            Err(err) => {
+                git_protocol::indicate_end_of_interaction(&mut con.transport).await.ok();
+                return Err(err);
+            }
+        };
+
+        negotiate::add_wants(repo, &self.ref_map, &self.shallow, &mut arguments);
+        if arguments.is_empty() {
+            git_protocol::indicate_end_of_interaction(&mut con.transport).await.ok();
+            return Ok(Outcome {
+                ref_map: std::mem::take(&mut self.ref_map),
+                status: Status::NoChange,
+            });
+        }
+        let is_stateful = matches!(protocol_version, git_protocol::transport::Protocol::V1)
+            && con.transport.connection_persists_across_multiple_requests();
+        let mut negotiation = match config::negotiation_algorithm(repo)
+            .and_then(|algo| Ok(negotiate::State::new(algo, repo, &self.ref_map, !is_stateful)?))
+        {
+            Ok(state) => state,
+            Err(err) => {
+                git_protocol::indicate_end_of_interaction(&mut con.transport).await.ok();
+                return Err(err);
+            }
+        };
+
+        let mut shallow_updates = Vec::new();
         let reader = 'negotiation: loop {
             progress.step();
             progress.set_name(format!("negotiate (round {})", round));
 
-            let is_done = match negotiate::one_round(
-                negotiate::Algorithm::Naive,
-                round,
-                repo,
-                &self.ref_map,
-                &mut arguments,
-                previous_response.as_ref(),
-            ) {
-                Ok(_) if arguments.is_empty() => {
-                    git_protocol::indicate_end_of_interaction(&mut con.transport).await.ok();
-                    return Ok(Outcome {
-                        ref_map: std::mem::take(&mut self.ref_map),
-                        status: Status::NoChange,
-                    });
-                }
+            let is_done = match negotiate::one_round(&mut negotiation, &mut arguments, previous_response.as_ref()) {
                 Ok(is_done) => is_done,
                 Err(err) => {
                     git_protocol::indicate_end_of_interaction(&mut con.transport).await.ok();
@@ -120,7 +154,16 @@ where
             if sideband_all {
                 setup_remote_progress(progress, &mut reader);
             }
-            let response = git_protocol::fetch::Response::from_line_reader(protocol_version, &mut reader).await?;
+            let response = git_protocol::fetch::Response::from_line_reader(
+                protocol_version,
+                &mut reader,
+                git_protocol::fetch::response::Options {
+                    client_expects_pack: is_done,
+                    no_done,
+                },
+            )
+            .await?;
+            shallow_updates.extend_from_slice(response.shallow_updates());
             if response.has_pack() {
                 progress.step();
                 progress.set_name("receiving pack");
@@ -168,6 +211,14 @@ where
             git_protocol::indicate_end_of_interaction(&mut con.transport).await.ok();
         }
 
+        if let Some(bundle) = write_pack_bundle.as_ref().filter(|_| is_promisor_pack) {
+            write_promisor_file(bundle, &self.ref_map)?;
+        }
+
+        if write_pack_bundle.is_some() && !shallow_updates.is_empty() {
+            update_shallow_commits(repo, shallow_commits.unwrap_or_default(), &shallow_updates)?;
+        }
+
         let update_refs = refs::update(
             repo,
             self.reflog_message
@@ -200,6 +251,132 @@ where
     }
 }
 
+/// Add our current `shallow_commits` and the arguments to change the shallow boundary as desired by `shallow` to `args`,
+/// or fail if the server doesn't support the required capabilities.
+fn add_shallow_args(
+    args: &mut git_protocol::fetch::Arguments,
+    shallow: &fetch::Shallow,
+    shallow_commits: Option<&[git_hash::ObjectId]>,
+) -> Result<(), Error> {
+    if (shallow_commits.is_some() || *shallow != fetch::Shallow::NoChange) && !args.can_use_shallow() {
+        return Err(Error::MissingServerFeature {
+            feature: "shallow",
+            description: "shallow repositories need server support to remain shallow, otherwise bigger than expected packs are sent effectively unshallowing the repository",
+        });
+    }
+    for commit in shallow_commits.into_iter().flatten() {
+        args.shallow(commit);
+    }
+
+    let deepen_since = |args: &mut git_protocol::fetch::Arguments, cutoff: &git_date::Time| {
+        if !args.can_use_deepen_since() {
+            return Err(Error::MissingServerFeature {
+                feature: "deepen-since",
+                description: "the history can't be limited by commit time",
+            });
+        }
+        args.deepen_since(cutoff.seconds_since_unix_epoch as usize);
+        Ok(())
+    };
+    match shallow {
+        fetch::Shallow::NoChange => {}
+        fetch::Shallow::DepthAtRemote(depth) => args.deepen(depth.get() as usize),
+        fetch::Shallow::Deepen(depth) => {
+            if !args.can_use_deepen_relative() {
+                return Err(Error::MissingServerFeature {
+                    feature: "deepen-relative",
+                    description: "the history can't be deepened relative to the current shallow boundary",
+                });
+            }
+            args.deepen(*depth as usize);
+            args.deepen_relative();
+        }
+        fetch::Shallow::Since { cutoff } => deepen_since(args, cutoff)?,
+        fetch::Shallow::Exclude {
+            remote_refs,
+            since_cutoff,
+        } => {
+            if !args.can_use_deepen_not() {
+                return Err(Error::MissingServerFeature {
+                    feature: "deepen-not",
+                    description: "the history can't be limited by excluding references",
+                });
+            }
+            if let Some(cutoff) = since_cutoff {
+                deepen_since(args, cutoff)?;
+            }
+            for name in remote_refs {
+                args.deepen_not(name.as_ref());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add the `filter` to `args`, or the filter configured for the remote named `remote_name` if it's a promisor remote, and return
+/// `true` if the pack to receive is a promisor pack, or fail if the server doesn't support filters.
+fn add_filter_arg(
+    args: &mut git_protocol::fetch::Arguments,
+    repo: &crate::Repository,
+    remote_name: Option<&str>,
+    filter: Option<&BString>,
+) -> Result<bool, Error> {
+    let is_promisor_remote = config::is_promisor(repo, remote_name)?;
+    let filter = match filter {
+        Some(spec) => Some(spec.to_owned()),
+        None if is_promisor_remote => config::partial_clone_filter(repo, remote_name),
+        None => None,
+    };
+    if let Some(spec) = &filter {
+        if !args.can_use_filter() {
+            return Err(Error::MissingServerFeature {
+                feature: "filter",
+                description: "objects can't be omitted from the pack, which would receive all objects instead of performing a partial fetch",
+            });
+        }
+        args.filter(spec.to_str_lossy().as_ref());
+    }
+    Ok(is_promisor_remote || filter.is_some())
+}
+
+/// Place a `.promisor` file next to the pack in `bundle` to mark it as received from a promisor remote, which lists the
+/// remote references we obtained, like `git` does.
+fn write_promisor_file(bundle: &git_pack::bundle::write::Outcome, ref_map: &fetch::RefMap) -> Result<(), Error> {
+    use std::fmt::Write;
+    let path = match bundle.data_path.as_ref() {
+        Some(path) => path.with_extension("promisor"),
+        None => return Ok(()),
+    };
+    let mut content = String::new();
+    for mapping in &ref_map.mappings {
+        if let Some(id) = mapping.remote.as_id() {
+            match mapping.remote.as_name() {
+                Some(name) => writeln!(content, "{} {}", id, name),
+                None => writeln!(content, "{} {}", id, id),
+            }
+            .expect("writing to a string never fails");
+        }
+    }
+    std::fs::write(&path, content).map_err(|err| Error::WritePromisorFile { path, source: err })
+}
+
+/// Apply the `updates` sent by the server to our previous `shallow_commits` and write the result to the shallow file.
+fn update_shallow_commits(
+    repo: &crate::Repository,
+    mut shallow_commits: Vec<git_hash::ObjectId>,
+    updates: &[git_protocol::fetch::response::ShallowUpdate],
+) -> Result<(), Error> {
+    use git_protocol::fetch::response::ShallowUpdate;
+    for update in updates {
+        match update {
+            ShallowUpdate::Shallow(id) => shallow_commits.push(*id),
+            ShallowUpdate::Unshallow(id) => shallow_commits.retain(|commit| commit != id),
+        }
+    }
+    repo.write_shallow_commits(shallow_commits)?;
+    Ok(())
+}
+
 fn setup_remote_progress<P>(
     progress: &mut P,
     reader: &mut Box<dyn git_protocol::transport::client::ExtendedBufRead + Unpin + '_>,
@@ -28,3 +28,7 @@ pub mod ref_map;
 ///
 #[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
 pub mod fetch;
+
+///
+#[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
+pub mod push;
@@ -0,0 +1,52 @@
+/// The error returned by [`push()`][crate::remote::Connection::push()].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Cannot perform a meaningful push operation without any configured ref-specs")]
+    MissingRefSpecs,
+    #[error(transparent)]
+    Connection(#[from] crate::remote::ref_map::Error),
+    #[error(transparent)]
+    Handshake(#[from] git_protocol::handshake::Error),
+    #[error("Cannot push to a remote that uses {remote} while local repository uses {local} for object hashes")]
+    IncompatibleObjectHash {
+        local: git_hash::Kind,
+        remote: git_hash::Kind,
+    },
+    #[error(transparent)]
+    FindHead(#[from] crate::reference::find::existing::Error),
+    #[error(transparent)]
+    OpenPackedRefs(#[from] crate::reference::iter::Error),
+    #[error(transparent)]
+    InitReferenceIter(#[from] crate::reference::iter::init::Error),
+    #[error(transparent)]
+    IterReferences(Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error(transparent)]
+    MappingValidation(#[from] git_refspec::match_group::validate::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    FindObject(#[from] git_pack::find::existing::Error<git_odb::store::find::Error>),
+    #[error(transparent)]
+    DecodeObject(#[from] git_object::decode::Error),
+    #[error(transparent)]
+    TraverseCommits(#[from] git_traverse::commit::ancestors::Error),
+    #[error(transparent)]
+    CountObjects(
+        #[from]
+        git_pack::data::output::count::objects::Error<
+            git_pack::find::existing::Error<git_odb::store::find::Error>,
+            std::convert::Infallible,
+        >,
+    ),
+    #[error(transparent)]
+    CreateEntries(#[from] git_pack::data::output::entry::iter_from_counts::Error<git_odb::store::find::Error>),
+    #[error(transparent)]
+    NewEntry(#[from] git_pack::data::output::entry::Error),
+    #[error(transparent)]
+    Push(#[from] git_protocol::push::Error),
+    #[error(transparent)]
+    UpdateTrackingRefs(#[from] crate::reference::edit::Error),
+    #[error("Interrupted")]
+    Interrupted,
+}
@@ -0,0 +1,412 @@
+use std::sync::atomic::AtomicBool;
+
+use git_pack::Find;
+use git_protocol::transport::client::Transport;
+use git_ref::transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog};
+use git_refspec::{
+    instruction::Push,
+    match_group::{Item, SourceRef},
+    Instruction, MatchGroup,
+};
+
+use crate::{
+    bstr::{BStr, BString},
+    remote::{
+        connection::ref_map,
+        push::{Options, Outcome, Status, Update},
+        Connection, Direction,
+    },
+    Progress,
+};
+
+mod error;
+pub use error::Error;
+
+mod pack;
+
+impl<'remote, 'repo, T, P> Connection<'remote, 'repo, T, P>
+where
+    T: Transport,
+    P: Progress,
+    P::SubProgress: 'static,
+{
+    /// Perform a handshake with the remote, derive the reference updates from our remote's [`refspecs`][crate::Remote::refspecs()]
+    /// for _pushing_, send them along with a pack of all objects the remote doesn't have yet and return the outcome of each update
+    /// according to `options`.
+    ///
+    /// Local references are matched against the push ref-specs, with `HEAD` as destination referring to the branch `HEAD`
+    /// points to, and partial destinations being placed next to their source, so `main:other` updates `refs/heads/other`.
+    /// Updates that aren't fast-forwards or that change existing tags are rejected unless allowed by the ref-spec with `+`.
+    /// Ref-specs without source delete the matching remote reference, and `:` updates all branches which exist locally and on the remote.
+    ///
+    /// New objects are delta-compressed, and thin packs with deltas against objects the remote has are sent unless the remote
+    /// advertises the `no-thin` capability. Once the remote accepted an update,
+    /// the corresponding remote tracking reference is updated according to our remote's fetch ref-specs.
+    ///
+    /// `should_interrupt` is checked while generating the pack.
+    ///
+    /// # Consumption
+    ///
+    /// Due to management of the transport, it's cleanest to only use it for a single interaction. Thus it's consumed along with
+    /// the connection.
+    ///
+    /// ### Configuration
+    ///
+    /// - `gitoxide.userAgent` is read to obtain the application user agent for git servers and for HTTP servers as well.
+    #[allow(clippy::result_large_err)]
+    #[git_protocol::maybe_async::maybe_async]
+    pub async fn push(mut self, options: Options, should_interrupt: &AtomicBool) -> Result<Outcome, Error> {
+        if self.remote.refspecs(Direction::Push).is_empty() {
+            return Err(Error::MissingRefSpecs);
+        }
+        self.configure_transport()?;
+        let mut credentials_storage;
+        let authenticate = match self.authenticate.as_mut() {
+            Some(f) => f,
+            None => {
+                credentials_storage = self.default_credentials(Direction::Push)?;
+                &mut credentials_storage
+            }
+        };
+        let mut handshake = git_protocol::push::handshake(
+            &mut self.transport,
+            authenticate,
+            options.handshake_parameters.clone(),
+            &mut self.progress,
+        )
+        .await?;
+        let remote_refs = handshake.refs.take().unwrap_or_default();
+
+        let (mut updates, pack) = match self.prepare_push(&handshake, &remote_refs, &options, should_interrupt) {
+            Ok(res) => res,
+            Err(err) => {
+                git_protocol::indicate_end_of_interaction(&mut self.transport)
+                    .await
+                    .ok();
+                return Err(err);
+            }
+        };
+        let to_send = updates
+            .iter()
+            .filter(|update| matches!(update.status, Status::Ok { .. }))
+            .map(|update| git_protocol::push::Update {
+                old_id: update.previous.unwrap_or_else(|| self.remote.repo.object_hash().null()),
+                new_id: update.new.unwrap_or_else(|| self.remote.repo.object_hash().null()),
+                name: update.remote.clone(),
+            })
+            .collect::<Vec<_>>();
+        let num_objects = pack.as_ref().map_or(0, |(_db, plan)| plan.num_objects());
+        let object_hash = self.remote.repo.object_hash();
+        let (entries_progress, write_progress) = (
+            self.progress.add_child("creating entries"),
+            self.progress.add_child("writing pack"),
+        );
+        let mut write_error = None;
+        let pack = pack.map(|(db, plan)| {
+            let write_error = &mut write_error;
+            pack::chunks(
+                &db,
+                plan,
+                object_hash,
+                entries_progress,
+                write_progress,
+                should_interrupt,
+            )
+            .map(move |chunk| {
+                chunk.map_err(|err| match err {
+                    Error::Io(err) => err,
+                    err => {
+                        let io_err = std::io::Error::new(std::io::ErrorKind::Other, err.to_string());
+                        *write_error = Some(err);
+                        io_err
+                    }
+                })
+            })
+        });
+        let report = git_protocol::push(
+            &mut self.transport,
+            &handshake.capabilities,
+            &to_send,
+            pack,
+            &git_protocol::push::Options {
+                atomic: options.atomic,
+                push_options: options.push_options,
+                quiet: false,
+            },
+            &mut self.progress,
+            self.remote.repo.config.user_agent_tuple().1.unwrap_or_default(),
+        )
+        .await;
+        let report = match write_error {
+            Some(err) => return Err(err),
+            None => report?,
+        };
+
+        for update in updates
+            .iter_mut()
+            .filter(|update| matches!(update.status, Status::Ok { .. }))
+        {
+            if let Some(status) = report.refs.iter().find(|status| status.name == update.remote) {
+                match &status.rejection {
+                    Some(reason) => {
+                        update.status = Status::RejectedByRemote { reason: reason.clone() };
+                    }
+                    None if status.forced_update => update.status = Status::Ok { forced: true },
+                    None => {}
+                }
+            } else if report.unpack_error.is_some() {
+                update.status = Status::RejectedByRemote {
+                    reason: "unpacker error".into(),
+                };
+            }
+        }
+        let tracking_ref_edits = if report.unpack_error.is_none() {
+            self.update_tracking_refs(&updates)?
+        } else {
+            Vec::new()
+        };
+        Ok(Outcome {
+            handshake,
+            updates,
+            num_objects,
+            unpack_error: report.unpack_error,
+            tracking_ref_edits,
+        })
+    }
+
+    /// Derive all updates from our push ref-specs and the `remote_refs` along with the objects to send, if there is anything to send.
+    #[allow(clippy::result_large_err, clippy::type_complexity)]
+    fn prepare_push(
+        &mut self,
+        handshake: &git_protocol::handshake::Outcome,
+        remote_refs: &[git_protocol::handshake::Ref],
+        options: &Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<(Vec<Update>, Option<(pack::Db, pack::Plan)>), Error> {
+        let repo = self.remote.repo;
+        let object_hash = ref_map::extract_object_format(repo, handshake)?;
+        if object_hash != repo.object_hash() {
+            return Err(Error::IncompatibleObjectHash {
+                local: repo.object_hash(),
+                remote: object_hash,
+            });
+        }
+        let remote_id = |name: &BStr| {
+            remote_refs.iter().find_map(|r| {
+                let (full_ref_name, target, _peeled) = r.unpack();
+                (full_ref_name == name).then(|| target.map(ToOwned::to_owned)).flatten()
+            })
+        };
+
+        let head = repo.head()?;
+        let head_branch = head.referent_name().map(|name| name.as_bstr().to_owned());
+        let mut local_refs = Vec::new();
+        if let Some(id) = head.id() {
+            local_refs.push((BString::from("HEAD"), id.detach()));
+        }
+        for reference in repo.references()?.all()? {
+            let reference = reference.map_err(Error::IterReferences)?;
+            if let Some(id) = reference.target().try_id() {
+                local_refs.push((reference.name().as_bstr().to_owned(), id.to_owned()));
+            }
+        }
+
+        let group = MatchGroup::from_push_specs(self.remote.push_specs.iter().map(|s| s.to_ref()));
+        let mut matched = group.match_remotes(local_refs.iter().map(|(name, id)| Item {
+            full_ref_name: name.as_ref(),
+            target: id,
+            object: None,
+        }));
+        for mapping in &mut matched.mappings {
+            let qualified = match mapping.rhs.as_deref() {
+                Some(dst) if dst == "HEAD" => head_branch.clone(),
+                Some(dst) if !dst.starts_with(b"refs/") => match &mapping.lhs {
+                    SourceRef::FullName(src) => ["refs/heads/", "refs/tags/"]
+                        .iter()
+                        .find(|prefix| src.starts_with(prefix.as_bytes()))
+                        .map(|prefix| {
+                            let mut name = BString::from(*prefix);
+                            name.extend_from_slice(dst);
+                            name
+                        }),
+                    SourceRef::ObjectId(_) => None,
+                },
+                _ => continue,
+            };
+            if let Some(name) = qualified {
+                mapping.rhs = Some(name.into());
+            }
+        }
+        let (matched, _fixes) = matched.validated()?;
+
+        let mut updates = Vec::new();
+        for mapping in &matched.mappings {
+            let remote = match &mapping.rhs {
+                Some(name) => name.as_ref().to_owned(),
+                None => continue,
+            };
+            let (local, new) = match (mapping.item_index, &mapping.lhs) {
+                (Some(idx), _) => (Some(local_refs[idx].0.clone()), local_refs[idx].1),
+                (None, SourceRef::ObjectId(id)) => (None, *id),
+                (None, SourceRef::FullName(_)) => unreachable!("full names always come from items"),
+            };
+            updates.push(Update {
+                local,
+                previous: remote_id(remote.as_ref()),
+                remote,
+                new: Some(new),
+                spec_index: mapping.spec_index,
+                status: Status::Ok { forced: false },
+            });
+        }
+        for (spec_index, spec) in self.remote.push_specs.iter().enumerate() {
+            match spec.to_ref().instruction() {
+                Instruction::Push(Push::Delete { ref_or_pattern }) => {
+                    let remote = ["", "refs/", "refs/tags/", "refs/heads/"]
+                        .iter()
+                        .map(|prefix| {
+                            let mut name = BString::from(*prefix);
+                            name.extend_from_slice(ref_or_pattern);
+                            name
+                        })
+                        .find_map(|name| remote_id(name.as_ref()).map(|id| (name, id)));
+                    if let Some((remote, previous)) = remote {
+                        updates.push(Update {
+                            local: None,
+                            remote,
+                            previous: Some(previous),
+                            new: None,
+                            spec_index,
+                            status: Status::Ok { forced: false },
+                        });
+                    }
+                }
+                Instruction::Push(Push::AllMatchingBranches { .. }) => {
+                    for (name, id) in local_refs.iter().filter(|(name, _)| name.starts_with(b"refs/heads/")) {
+                        if let Some(previous) = remote_id(name.as_ref()) {
+                            updates.push(Update {
+                                local: Some(name.clone()),
+                                remote: name.clone(),
+                                previous: Some(previous),
+                                new: Some(*id),
+                                spec_index,
+                                status: Status::Ok { forced: false },
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        updates.sort_by_key(|update| update.spec_index);
+        let mut seen = std::collections::HashSet::new();
+        updates.retain(|update| seen.insert(update.remote.clone()));
+
+        let mut db = repo.objects.clone().into_arc()?;
+        db.prevent_pack_unload();
+        let mut buf = Vec::new();
+        for update in &mut updates {
+            let (previous, new) = match (update.previous, update.new) {
+                (Some(previous), Some(new)) => (previous, new),
+                _ => continue,
+            };
+            let allow_non_fast_forward = self.remote.push_specs[update.spec_index].allow_non_fast_forward();
+            update.status = if previous == new {
+                Status::UpToDate
+            } else if update.remote.starts_with(b"refs/tags/") {
+                if allow_non_fast_forward {
+                    Status::Ok { forced: true }
+                } else {
+                    Status::RejectedAlreadyExists
+                }
+            } else {
+                let is_fast_forward = db.contains(previous) && {
+                    let (previous, new) = (pack::peel(&db, previous, &mut buf)?, pack::peel(&db, new, &mut buf)?);
+                    previous.kind == git_object::Kind::Commit
+                        && new.kind == git_object::Kind::Commit
+                        && pack::is_ancestor(&db, &previous.id, new.id)?
+                };
+                match (is_fast_forward, allow_non_fast_forward) {
+                    (true, _) => Status::Ok { forced: false },
+                    (false, true) => Status::Ok { forced: true },
+                    (false, false) => Status::RejectedNonFastForward,
+                }
+            };
+        }
+        if options.atomic && updates.iter().any(|update| !update.status.is_ok()) {
+            for update in updates
+                .iter_mut()
+                .filter(|update| matches!(update.status, Status::Ok { .. }))
+            {
+                update.status = Status::RejectedAtomicPushFailed;
+            }
+        }
+
+        let tips = updates
+            .iter()
+            .filter(|update| matches!(update.status, Status::Ok { .. }))
+            .filter_map(|update| update.new)
+            .collect::<Vec<_>>();
+        let pack = if tips.is_empty() {
+            None
+        } else {
+            let remote_tips = remote_refs.iter().filter_map(|r| r.unpack().1.map(ToOwned::to_owned));
+            let plan = pack::plan(
+                &db,
+                tips,
+                remote_tips,
+                !handshake.capabilities.contains("no-thin"),
+                &mut self.progress,
+                should_interrupt,
+            )?;
+            Some((db, plan))
+        };
+        Ok((updates, pack))
+    }
+
+    /// Update the remote tracking references of all accepted `updates` according to our fetch ref-specs.
+    #[allow(clippy::result_large_err)]
+    fn update_tracking_refs(&self, updates: &[Update]) -> Result<Vec<RefEdit>, Error> {
+        let null = git_hash::ObjectId::null(git_hash::Kind::Sha1); // OK to hardcode Sha1, it's not used for matching.
+        let mut edits = Vec::new();
+        for update in updates.iter().filter(|update| update.status.is_ok()) {
+            let group = MatchGroup::from_fetch_specs(self.remote.fetch_specs.iter().map(|s| s.to_ref()));
+            let matched = group.match_remotes(std::iter::once(Item {
+                full_ref_name: update.remote.as_ref(),
+                target: update.new.as_ref().unwrap_or(&null),
+                object: None,
+            }));
+            let local = match matched.mappings.into_iter().find_map(|m| m.rhs) {
+                Some(local) => local.into_owned(),
+                None => continue,
+            };
+            let name = match local.try_into() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            edits.push(RefEdit {
+                change: match update.new {
+                    Some(new) => Change::Update {
+                        log: LogChange {
+                            mode: RefLog::AndReference,
+                            force_create_reflog: false,
+                            message: "update by push".into(),
+                        },
+                        expected: PreviousValue::Any,
+                        new: git_ref::Target::Peeled(new),
+                    },
+                    None => Change::Delete {
+                        expected: PreviousValue::Any,
+                        log: RefLog::AndReference,
+                    },
+                },
+                name,
+                deref: false,
+            });
+        }
+        if edits.is_empty() {
+            return Ok(edits);
+        }
+        Ok(self.remote.repo.edit_references(edits)?)
+    }
+}
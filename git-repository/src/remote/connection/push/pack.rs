@@ -0,0 +1,287 @@
+use std::{
+    collections::HashSet,
+    convert::Infallible,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use git_features::progress::Progress;
+use git_hash::ObjectId;
+use git_pack::{data::output, Find, FindExt};
+
+use super::Error;
+use crate::progress;
+
+pub(super) type Db = git_odb::Cache<git_odb::store::Handle<std::sync::Arc<git_odb::Store>>>;
+
+/// An object along with the tags that had to be peeled to reach it.
+pub(super) struct Peeled {
+    pub id: ObjectId,
+    pub kind: git_object::Kind,
+    pub tags: Vec<ObjectId>,
+}
+
+/// Follow `id` through all tags to the object it ultimately points to.
+#[allow(clippy::result_large_err)]
+pub(super) fn peel(db: &Db, mut id: ObjectId, buf: &mut Vec<u8>) -> Result<Peeled, Error> {
+    let mut tags = Vec::new();
+    loop {
+        let (object, _location) = db.find(id, buf)?;
+        match object.kind {
+            git_object::Kind::Tag => {
+                tags.push(id);
+                id = git_object::TagRefIter::from_bytes(object.data).target_id()?;
+            }
+            kind => return Ok(Peeled { id, kind, tags }),
+        }
+    }
+}
+
+/// Return `true` if the commit `ancestor` can be reached from the commit `tip`, including `tip` itself.
+#[allow(clippy::result_large_err)]
+pub(super) fn is_ancestor(db: &Db, ancestor: &git_hash::oid, tip: ObjectId) -> Result<bool, Error> {
+    for commit in git_traverse::commit::Ancestors::new(
+        Some(tip),
+        git_traverse::commit::ancestors::State::default(),
+        |oid, buf| db.find_commit_iter(oid, buf).map(|t| t.0),
+    ) {
+        if commit? == ancestor {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The objects to send along with the objects the remote is known to have.
+pub(super) struct Plan {
+    /// The objects to put into the pack.
+    counts: Vec<output::Count>,
+    /// The objects the remote has, which may serve as delta bases in thin packs.
+    remote_has: HashSet<ObjectId>,
+    /// The trees and blobs the remote has at paths similar to the ones of the objects to send, to delta-compress new objects against.
+    thin_bases: Vec<output::Count>,
+    /// If `true`, objects may be stored as deltas against objects the remote has.
+    allow_thin_pack: bool,
+}
+
+impl Plan {
+    /// The amount of objects that will be written into the pack.
+    pub fn num_objects(&self) -> u32 {
+        self.counts.len() as u32
+    }
+}
+
+/// Determine all objects reachable from `tips` that aren't reachable from `remote_tips`, which are the objects the remote
+/// references point to, so they can be [written][write()] into a pack later.
+///
+/// If `allow_thin_pack` is `true`, objects may be stored as deltas against objects the remote is known to have, which are
+/// the objects of packed deltas as well as the trees and blobs of the commits our new commits are based on, similar to
+/// `git pack-objects --thin`.
+#[allow(clippy::result_large_err)]
+pub(super) fn plan<P>(
+    db: &Db,
+    tips: impl IntoIterator<Item = ObjectId>,
+    remote_tips: impl IntoIterator<Item = ObjectId>,
+    allow_thin_pack: bool,
+    progress: &mut P,
+    should_interrupt: &AtomicBool,
+) -> Result<Plan, Error>
+where
+    P: Progress,
+    P::SubProgress: 'static,
+{
+    let mut buf = Vec::new();
+    let mut remote_has = HashSet::new();
+    let mut remote_commit_tips = Vec::new();
+    for tip in remote_tips {
+        if !db.contains(tip) {
+            continue;
+        }
+        let peeled = peel(db, tip, &mut buf)?;
+        remote_has.extend(peeled.tags);
+        match peeled.kind {
+            git_object::Kind::Commit => remote_commit_tips.push(peeled.id),
+            _ => {
+                remote_has.insert(peeled.id);
+            }
+        }
+    }
+
+    let (mut as_is, mut tree_contents, mut commit_tips) = (Vec::new(), Vec::new(), Vec::new());
+    for tip in tips {
+        let peeled = peel(db, tip, &mut buf)?;
+        as_is.extend(peeled.tags);
+        match peeled.kind {
+            git_object::Kind::Commit => commit_tips.push(peeled.id),
+            git_object::Kind::Blob => as_is.push(peeled.id),
+            git_object::Kind::Tree => tree_contents.push(peeled.id),
+            git_object::Kind::Tag => unreachable!("tags were peeled"),
+        }
+    }
+
+    let mut commits_progress = progress.add_child("counting commits");
+    commits_progress.init(None, progress::count("commits"));
+    let mut have_commits = HashSet::new();
+    for commit in git_traverse::commit::Ancestors::new(
+        remote_commit_tips,
+        git_traverse::commit::ancestors::State::default(),
+        |oid, buf| db.find_commit_iter(oid, buf).map(|t| t.0),
+    ) {
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+        have_commits.insert(commit?);
+    }
+    let mut commits = Vec::new();
+    for commit in git_traverse::commit::Ancestors::filtered(
+        commit_tips,
+        git_traverse::commit::ancestors::State::default(),
+        |oid, buf| db.find_commit_iter(oid, buf).map(|t| t.0),
+        |id| !have_commits.contains(id),
+    ) {
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+        commits.push(commit?);
+        commits_progress.inc();
+    }
+    let mut boundary = HashSet::new();
+    for commit in &commits {
+        let (commit, _location) = db.find(commit, &mut buf)?;
+        boundary.extend(
+            git_object::CommitRefIter::from_bytes(commit.data)
+                .parent_ids()
+                .filter(|id| have_commits.contains(id)),
+        );
+    }
+
+    let mut count = |ids: Vec<ObjectId>, expansion| -> Result<Vec<output::Count>, Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut counting_progress = progress.add_child("counting objects");
+        counting_progress.init(None, progress::count("objects"));
+        Ok(output::count::objects_unthreaded(
+            db.clone(),
+            ids.into_iter().map(Ok::<_, Infallible>),
+            counting_progress,
+            should_interrupt,
+            expansion,
+            None,
+        )?
+        .0)
+    };
+    use output::count::objects::ObjectExpansion;
+    // The remote has everything reachable from the commits our new commits are based on, which may serve as delta bases.
+    let mut thin_bases = count(boundary.iter().copied().collect(), ObjectExpansion::TreeContents)?;
+    remote_has.extend(thin_bases.iter().map(|count| count.id));
+    let mut counts = count(commits, ObjectExpansion::TreeAdditionsComparedToAncestor)?;
+    counts.extend(count(as_is, ObjectExpansion::AsIs)?);
+    counts.extend(count(tree_contents, ObjectExpansion::TreeContents)?);
+    let mut seen = HashSet::new();
+    counts
+        .retain(|count| !have_commits.contains(&count.id) && !remote_has.contains(&count.id) && seen.insert(count.id));
+
+    // Like `git`, only consider objects at the paths of the objects we send, as only these are likely to be similar.
+    if allow_thin_pack {
+        let name_hashes: HashSet<_> = counts.iter().map(|count| count.name_hash).collect();
+        let mut seen = HashSet::new();
+        thin_bases.retain(|base| {
+            !boundary.contains(&base.id) && name_hashes.contains(&base.name_hash) && seen.insert(base.id)
+        });
+    } else {
+        thin_bases.clear();
+    }
+
+    remote_has.extend(have_commits);
+    Ok(Plan {
+        counts,
+        remote_has,
+        thin_bases,
+        allow_thin_pack,
+    })
+}
+
+/// Deltas copied from our packs may use bases we don't know the remote to have, which have to be sent as whole objects instead.
+#[allow(clippy::result_large_err)]
+fn undeltify_unknown_bases(
+    db: &Db,
+    mut entries: Vec<output::Entry>,
+    remote_has: &HashSet<ObjectId>,
+    buf: &mut Vec<u8>,
+) -> Result<Vec<output::Entry>, Error> {
+    for entry in &mut entries {
+        if let output::entry::Kind::DeltaOid { id } = entry.kind {
+            if !remote_has.contains(&id) {
+                let (object, _location) = db.find(entry.id, buf)?;
+                *entry = output::Entry::from_data(&output::Count::from_data(entry.id, None), &object)?;
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Return the chunks of the pack for `plan`, creating its entries while they are consumed and using `entries_progress` and
+/// `write_progress` to show how far along it is.
+///
+/// New objects are delta-compressed against each other and against objects the remote has if thin packs are allowed.
+pub(super) fn chunks<'a>(
+    db: &Db,
+    plan: Plan,
+    object_hash: git_hash::Kind,
+    entries_progress: impl Progress + 'static,
+    mut write_progress: impl Progress + 'a,
+    should_interrupt: &'a AtomicBool,
+) -> impl Iterator<Item = Result<Vec<u8>, Error>> + 'a {
+    use output::entry::iter_from_counts;
+    let Plan {
+        counts,
+        remote_has,
+        thin_bases,
+        allow_thin_pack,
+    } = plan;
+    let num_objects = counts.len() as u32;
+    let mut buf = Vec::new();
+    let entries = git_features::parallel::InOrderIter::from(output::entry::iter_from_counts_with_thin_bases(
+        counts,
+        thin_bases,
+        db.clone(),
+        entries_progress,
+        iter_from_counts::Options {
+            mode: iter_from_counts::Mode::PackCopyAndDeltaCompression {
+                window: iter_from_counts::Mode::DEFAULT_WINDOW,
+                depth: iter_from_counts::Mode::DEFAULT_DEPTH,
+            },
+            allow_thin_pack,
+            ..Default::default()
+        },
+    ))
+    .map({
+        let db = db.clone();
+        move |entries| match entries {
+            Ok(entries) => undeltify_unknown_bases(&db, entries, &remote_has, &mut buf).map_err(Box::new),
+            Err(err) => Err(Box::new(err.into())),
+        }
+    });
+
+    write_progress.init(None, progress::bytes());
+    let mut pack_writer = output::bytes::FromEntriesIter::new(
+        entries,
+        Vec::new(),
+        num_objects,
+        git_pack::data::Version::V2,
+        object_hash,
+    );
+    std::iter::from_fn(move || {
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Some(Err(Error::Interrupted));
+        }
+        Some(match pack_writer.next()? {
+            Ok(written) => {
+                write_progress.inc_by(written as usize);
+                Ok(std::mem::take(pack_writer.write_mut()))
+            }
+            Err(output::bytes::Error::Input(err)) => Err(*err),
+            Err(output::bytes::Error::Io(err)) => Err(err.into()),
+        })
+    })
+}
@@ -6,7 +6,10 @@ use git_protocol::transport::client::Transport;
 use crate::{
     bstr,
     bstr::{BString, ByteVec},
-    remote::{connection::HandshakeWithRefs, fetch, Connection, Direction},
+    remote::{
+        connection::{AuthenticateFn, HandshakeWithRefs},
+        fetch, Connection, Direction,
+    },
 };
 
 /// The error returned by [`Connection::ref_map()`].
@@ -145,34 +148,15 @@ where
         filter_by_prefix: bool,
         extra_parameters: Vec<(String, Option<String>)>,
     ) -> Result<HandshakeWithRefs, Error> {
+        self.configure_transport()?;
         let mut credentials_storage;
-        let url = self.transport.to_url();
         let authenticate = match self.authenticate.as_mut() {
             Some(f) => f,
             None => {
-                let url = self
-                    .remote
-                    .url(Direction::Fetch)
-                    .map(ToOwned::to_owned)
-                    .unwrap_or_else(|| git_url::parse(url.as_ref()).expect("valid URL to be provided by transport"));
-                credentials_storage = self.configured_credentials(url)?;
+                credentials_storage = self.default_credentials(Direction::Fetch)?;
                 &mut credentials_storage
             }
         };
-
-        if self.transport_options.is_none() {
-            self.transport_options =
-                self.remote
-                    .repo
-                    .transport_options(url.as_ref())
-                    .map_err(|err| Error::GatherTransportConfig {
-                        source: err,
-                        url: url.into_owned(),
-                    })?;
-        }
-        if let Some(config) = self.transport_options.as_ref() {
-            self.transport.configure(&**config)?;
-        }
         let mut outcome =
             git_protocol::fetch::handshake(&mut self.transport, authenticate, extra_parameters, &mut self.progress)
                 .await?;
@@ -211,9 +195,43 @@ where
     }
 }
 
-/// Assume sha1 if server says nothing, otherwise configure anything beyond sha1 in the local repo configuration
+impl<'remote, 'repo, T, P> Connection<'remote, 'repo, T, P>
+where
+    T: Transport,
+{
+    /// Configure our transport with the options set by the user or with those derived from the configuration of the repository.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn configure_transport(&mut self) -> Result<(), Error> {
+        if self.transport_options.is_none() {
+            let url = self.transport.to_url();
+            self.transport_options =
+                self.remote
+                    .repo
+                    .transport_options(url.as_ref())
+                    .map_err(|err| Error::GatherTransportConfig {
+                        source: err,
+                        url: url.into_owned(),
+                    })?;
+        }
+        if let Some(config) = self.transport_options.as_ref() {
+            self.transport.configure(&**config)?;
+        }
+        Ok(())
+    }
+
+    /// Obtain credentials for the url of our remote in `direction`, or the url of the transport if the remote has none.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn default_credentials(&self, direction: Direction) -> Result<AuthenticateFn<'static>, Error> {
+        let url = self.remote.url(direction).map(ToOwned::to_owned).unwrap_or_else(|| {
+            git_url::parse(self.transport.to_url().as_ref()).expect("valid URL to be provided by transport")
+        });
+        Ok(self.configured_credentials(url)?)
+    }
+}
+
+/// Assume sha1 if server says nothing, otherwise use the advertised object format which the caller has to configure locally if needed.
 #[allow(clippy::result_large_err)]
-fn extract_object_format(
+pub(crate) fn extract_object_format(
     _repo: &crate::Repository,
     outcome: &git_protocol::handshake::Outcome,
 ) -> Result<git_hash::Kind, Error> {
@@ -225,6 +243,7 @@ fn extract_object_format(
             })?;
             match object_format {
                 "sha1" => git_hash::Kind::Sha1,
+                "sha256" => git_hash::Kind::Sha256,
                 unknown => return Err(Error::UnknownObjectFormat { format: unknown.into() }),
             }
         } else {
@@ -20,6 +20,53 @@ pub(crate) enum WritePackedRefs {
     Only,
 }
 
+/// How to change the shallow boundary of the repository, or whether to create one, while fetching.
+///
+/// Note that the history is only ever changed for the remote references that are fetched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
+pub enum Shallow {
+    /// Fetch all changes without changing the shallow boundary, which keeps a complete repository complete.
+    NoChange,
+    /// Receive only the given amount of commits from each remote tip, like `git fetch --depth=<depth>`.
+    ///
+    /// A depth of `1` only receives the tip commits themselves.
+    DepthAtRemote(std::num::NonZeroU32),
+    /// Extend the history of a shallow repository by the given amount of commits beyond its current shallow boundary,
+    /// like `git fetch --deepen=<depth>`.
+    Deepen(u32),
+    /// Receive only commits which are more recent than `cutoff`, like `git fetch --shallow-since=<date>`.
+    Since {
+        /// The commit time of the oldest commit to receive.
+        cutoff: git_date::Time,
+    },
+    /// Don't receive commits reachable from the given remote references, like `git fetch --shallow-exclude=<ref>`.
+    Exclude {
+        /// The names of the remote references, like `main` or `v1.0`, whose history shouldn't be received.
+        remote_refs: Vec<BString>,
+        /// Additionally receive only commits which are more recent than this time, if set.
+        since_cutoff: Option<git_date::Time>,
+    },
+}
+
+#[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
+impl Default for Shallow {
+    fn default() -> Self {
+        Shallow::NoChange
+    }
+}
+
+#[cfg(any(feature = "blocking-network-client", feature = "async-network-client"))]
+impl Shallow {
+    /// The depth `git` uses to receive the entire history of a shallow repository, making it complete.
+    const INFINITE_DEPTH: u32 = 0x7fff_ffff;
+
+    /// Receive the entire history of a shallow repository so it's not shallow anymore, like `git fetch --unshallow`.
+    pub fn undo() -> Self {
+        Shallow::DepthAtRemote(std::num::NonZeroU32::new(Self::INFINITE_DEPTH).expect("non-zero"))
+    }
+}
+
 /// Information about the relationship between our refspecs, and remote references with their local counterparts.
 #[derive(Default, Debug, Clone)]
 pub struct RefMap {
@@ -53,6 +53,10 @@ pub mod init;
 #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
 pub mod fetch;
 
+///
+#[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+pub mod push;
+
 ///
 #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
 pub mod connect;
@@ -0,0 +1,89 @@
+use crate::bstr::BString;
+
+/// Options for use in [`Connection::push()`][crate::remote::Connection::push()].
+#[derive(Default, Debug, Clone)]
+pub struct Options {
+    /// If `true`, ask the remote to apply either all reference updates or none of them, similar to `git push --atomic`.
+    ///
+    /// If set, nothing is sent to the remote if at least one update was rejected locally.
+    pub atomic: bool,
+    /// Strings to pass to the hooks of the remote, similar to `git push --push-option`.
+    pub push_options: Vec<BString>,
+    /// Parameters in the form of `(name, optional value)` to add to the handshake.
+    ///
+    /// This is useful in case of custom servers.
+    pub handshake_parameters: Vec<(String, Option<String>)>,
+}
+
+/// The status of a single reference [update][Update] after the push.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// The remote reference already points to the object we would push, so no update was sent.
+    UpToDate,
+    /// The remote accepted the update.
+    Ok {
+        /// If `true`, the object previously pointed to by the remote reference isn't an ancestor of the new one.
+        forced: bool,
+    },
+    /// The update wasn't sent as it isn't a fast-forward and the ref-spec doesn't allow non-fast-forward updates with `+`.
+    RejectedNonFastForward,
+    /// The update wasn't sent as the tag already exists on the remote and the ref-spec doesn't allow to overwrite it with `+`.
+    RejectedAlreadyExists,
+    /// The update wasn't sent as the push was [atomic][Options::atomic] and another update was rejected.
+    RejectedAtomicPushFailed,
+    /// The remote rejected the update with the given `reason`.
+    RejectedByRemote {
+        /// The reason for the rejection as given by the remote, like `non-fast-forward` or `deletion prohibited`.
+        reason: BString,
+    },
+}
+
+impl Status {
+    /// Return `true` if the remote reference now points to the object we wanted it to point to.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Status::UpToDate | Status::Ok { .. })
+    }
+}
+
+/// A single update to a remote reference as derived from the push ref-specs of a remote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Update {
+    /// The full name of the local reference we pushed, or `None` if an object id was pushed directly or the remote reference is deleted.
+    pub local: Option<BString>,
+    /// The full name of the reference on the remote side.
+    pub remote: BString,
+    /// The object the remote reference pointed to before the push, or `None` if it didn't exist.
+    pub previous: Option<git_hash::ObjectId>,
+    /// The object the remote reference should point to, or `None` if it should be deleted.
+    pub new: Option<git_hash::ObjectId>,
+    /// The index into the push ref-specs of the remote which produced this update.
+    pub spec_index: usize,
+    /// What happened to the update.
+    pub status: Status,
+}
+
+/// The outcome of [`Connection::push()`][crate::remote::Connection::push()].
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// Information provided by the server as part of the handshake.
+    ///
+    /// Note that the `refs` field is always `None` as the refs were used to compute the updates.
+    pub handshake: git_protocol::handshake::Outcome,
+    /// All updates derived from our push ref-specs, in order of the ref-specs that produced them.
+    pub updates: Vec<Update>,
+    /// The amount of objects in the pack that was sent, which is `0` if no pack was sent.
+    pub num_objects: u32,
+    /// The reason the remote gave for failing to unpack the pack we sent, or `None` if it succeeded.
+    pub unpack_error: Option<BString>,
+    /// Information collected while updating the remote tracking references of accepted updates.
+    pub tracking_ref_edits: Vec<git_ref::transaction::RefEdit>,
+}
+
+impl Outcome {
+    /// Return `true` if the remote unpacked our pack and all references are where we wanted them to be.
+    pub fn is_success(&self) -> bool {
+        self.unpack_error.is_none() && self.updates.iter().all(|update| update.status.is_ok())
+    }
+}
+
+pub use super::connection::push::Error;
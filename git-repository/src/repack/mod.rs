@@ -0,0 +1,157 @@
+use std::{path::PathBuf, time::SystemTime};
+
+use git_hash::ObjectId;
+
+/// Determine which objects are written into a new pack by [`Repository::repack()`][crate::Repository::repack()],
+/// and which packs it replaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    /// Write all objects reachable from references, their reflogs, `HEAD` and the index into a single pack and
+    /// delete all previously existing packs that don't have a `.keep` or `.promisor` file, similar to `git repack -a -d`.
+    ///
+    /// Note that unreachable objects that were previously packed are not retained.
+    AllIntoOne,
+    /// Roll up small packs and all loose objects into a new pack so that each pack contains at least `factor` times
+    /// as many objects as the next smaller pack, similar to `git repack --geometric=<factor> -d`.
+    ///
+    /// Packs with a `.keep` or `.promisor` file are not considered.
+    Geometric {
+        /// The factor by which the object count of each pack must exceed the one of the next smaller pack.
+        factor: u32,
+    },
+    /// Only write reachable loose objects into a new pack and leave all existing packs untouched, similar to `git repack -d`.
+    LooseOnly,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::LooseOnly
+    }
+}
+
+/// Options for use in [`Repository::repack()`][crate::Repository::repack()].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The way objects are selected and existing packs are replaced.
+    pub mode: Mode,
+    /// If `Some(window)`, objects that aren't yet delta-compressed in a pack are delta-compressed against up to `window`
+    /// similar objects, similar to `pack.window`.
+    /// If `None`, such objects are written as base objects.
+    pub delta_window: Option<usize>,
+    /// The maximum length of a chain of deltas when delta-compressing objects, similar to `pack.depth`.
+    pub delta_depth: usize,
+    /// If `true`, write a reachability bitmap for the new pack, which requires [`Mode::AllIntoOne`].
+    pub write_bitmap: bool,
+    /// If `true`, write a `multi-pack-index` file covering all packs after the repack.
+    /// Otherwise an existing `multi-pack-index` file will be removed if packs were removed.
+    pub write_multi_pack_index: bool,
+    /// If `Some(time)`, unreachable loose objects that weren't modified since `time` will be deleted.
+    pub prune_loose_older_than: Option<SystemTime>,
+    /// The amount of threads to use at most when creating pack entries. If `None`, all logical cores are used.
+    pub thread_limit: Option<usize>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        use git_pack::data::output::entry::iter_from_counts::Mode as PackMode;
+        Options {
+            mode: Mode::default(),
+            delta_window: Some(PackMode::DEFAULT_WINDOW),
+            delta_depth: PackMode::DEFAULT_DEPTH,
+            write_bitmap: false,
+            write_multi_pack_index: false,
+            prune_loose_older_than: None,
+            thread_limit: None,
+        }
+    }
+}
+
+/// Information about a pack written by [`Repository::repack()`][crate::Repository::repack()].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pack {
+    /// The path to the pack data file.
+    pub data_path: PathBuf,
+    /// The path to the pack index file.
+    pub index_path: PathBuf,
+    /// The path to the reachability bitmap, if one was written.
+    pub bitmap_path: Option<PathBuf>,
+    /// The amount of objects in the pack.
+    pub num_objects: u32,
+    /// The checksum of the pack data file, which is also part of its file name.
+    pub checksum: ObjectId,
+}
+
+/// The outcome of [`Repository::repack()`][crate::Repository::repack()].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The newly written pack, or `None` if there was nothing to pack.
+    pub pack: Option<Pack>,
+    /// The paths to the data files of all packs that were removed as their objects are now contained in the new pack.
+    pub removed_packs: Vec<PathBuf>,
+    /// The amount of loose objects that were deleted, either because they are now packed or because they were unreachable
+    /// and older than [`Options::prune_loose_older_than`].
+    pub removed_loose_objects: usize,
+    /// The path to the `multi-pack-index` file if one was written.
+    pub multi_pack_index: Option<PathBuf>,
+}
+
+/// The error returned by [`Repository::repack()`][crate::Repository::repack()].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Reachability bitmaps can only be written when repacking all objects into one pack")]
+    BitmapRequiresAllIntoOne,
+    #[error("The geometric factor must be at least 2, got {factor}")]
+    InvalidGeometricFactor { factor: u32 },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    IterReferences(#[from] git_ref::store::iter::Error),
+    #[error(transparent)]
+    FindHead(#[from] git_ref::store::find::Error),
+    #[error(transparent)]
+    ReadReflog(#[from] git_ref::store::reflog::Error),
+    #[error(transparent)]
+    OpenIndex(#[from] crate::worktree::open_index::Error),
+    #[error(transparent)]
+    ShallowCommits(#[from] crate::shallow::open::Error),
+    #[error("Could not open another worktree of the repository")]
+    OpenWorktree(#[from] Box<crate::open::Error>),
+    #[error(transparent)]
+    IterLooseObjects(#[from] git_odb::loose::iter::Error),
+    #[error(transparent)]
+    FindObject(#[from] git_pack::find::existing::Error<git_odb::store::find::Error>),
+    #[error(transparent)]
+    DecodeObject(#[from] git_object::decode::Error),
+    #[error(transparent)]
+    TraverseCommits(#[from] git_traverse::commit::ancestors::Error),
+    #[error(transparent)]
+    CountObjects(
+        #[from]
+        git_pack::data::output::count::objects::Error<
+            git_pack::find::existing::Error<git_odb::store::find::Error>,
+            std::convert::Infallible,
+        >,
+    ),
+    #[error(transparent)]
+    WritePack(
+        #[from]
+        git_pack::data::output::bytes::Error<
+            git_pack::data::output::entry::iter_from_counts::Error<git_odb::store::find::Error>,
+        >,
+    ),
+    #[error(transparent)]
+    WriteIndex(#[from] git_pack::index::write::Error),
+    #[error(transparent)]
+    OpenPackIndex(#[from] git_pack::index::init::Error),
+    #[error(transparent)]
+    OpenBundle(#[from] git_pack::bundle::init::Error),
+    #[error(transparent)]
+    WriteBitmap(#[from] git_pack::bitmap::write::Error<git_odb::store::find::Error>),
+    #[error(transparent)]
+    WriteMultiPackIndex(#[from] git_pack::multi_index::write::Error),
+    #[error(transparent)]
+    Persist(#[from] git_tempfile::handle::persist::Error<git_tempfile::handle::Writable>),
+    #[error("Interrupted")]
+    Interrupted,
+}
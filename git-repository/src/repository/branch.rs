@@ -0,0 +1,306 @@
+use std::{convert::TryInto, io::Write};
+
+use git_hash::ObjectId;
+use git_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    FullName, Target,
+};
+
+use crate::{branch, bstr::BString, ext::ReferenceExt, head, Reference};
+
+/// Manipulate `HEAD` and branches, similar to `git checkout`, `git symbolic-ref` and `git branch`.
+///
+/// Note that none of these methods touch the worktree or the index.
+impl crate::Repository {
+    /// Make `HEAD` point to the branch with the given `name`, like `main` and without `refs/heads/` prefix, similar to
+    /// `git symbolic-ref HEAD refs/heads/<name>`, which is what `git checkout <name>` does after updating the worktree.
+    ///
+    /// The branch doesn't have to exist, leaving `HEAD` unborn. Otherwise the reflog of `HEAD` receives an entry like
+    /// `checkout: moving from <previous> to <name>`, where `<previous>` is the name of the previously checked out branch
+    /// or the object id `HEAD` pointed to.
+    pub fn set_head_to_branch(&self, name: impl AsRef<str>) -> Result<(), head::set::Error> {
+        let name = name.as_ref();
+        let branch: FullName = format!("refs/heads/{}", name).try_into()?;
+        let message = format!("checkout: moving from {} to {}", self.head_description()?, name);
+        self.set_head(Target::Symbolic(branch), PreviousValue::Any, message.into())
+    }
+
+    /// Make `HEAD` point to the object with the given `id` directly, similar to `git checkout --detach <id>` but without
+    /// updating the worktree.
+    ///
+    /// The reflog of `HEAD` receives an entry like `checkout: moving from <previous> to <id>`, where `<previous>` is the name
+    /// of the previously checked out branch or the object id `HEAD` pointed to.
+    pub fn detach_head(&self, id: impl Into<ObjectId>) -> Result<(), head::set::Error> {
+        let id = id.into();
+        let head = self.find_reference("HEAD")?.detach();
+        let message = format!("checkout: moving from {} to {}", self.head_description()?, id);
+        self.set_head(
+            Target::Peeled(id),
+            PreviousValue::MustExistAndMatch(head.target),
+            message.into(),
+        )
+    }
+
+    /// Create the branch with the given `name`, like `feature` and without `refs/heads/` prefix, pointing to the commit
+    /// `start_point` resolves to, which can be any revision specification like `HEAD` or `main~1`, and return it.
+    ///
+    /// If `force` is `true`, an existing branch is reset to `start_point` unless it is checked out in any worktree, similar to
+    /// `git branch --force`. The reflog message is `branch: Created from <start_point>` or `branch: Reset to <start_point>`
+    /// respectively.
+    pub fn create_branch(
+        &self,
+        name: impl AsRef<str>,
+        start_point: &str,
+        force: bool,
+    ) -> Result<Reference<'_>, branch::create::Error> {
+        let branch: FullName = format!("refs/heads/{}", name.as_ref()).try_into()?;
+        let id = self
+            .rev_parse_single(start_point)?
+            .object()?
+            .peel_to_kind(git_object::Kind::Commit)?
+            .id;
+        let exists = self.try_find_reference(&branch)?.is_some();
+        if exists {
+            if !force {
+                return Err(branch::create::Error::Exists { name: branch });
+            }
+            if self.is_checked_out(&branch)? {
+                return Err(branch::create::Error::CheckedOut { name: branch });
+            }
+        }
+        let (constraint, message) = if exists {
+            (PreviousValue::Any, format!("branch: Reset to {}", start_point))
+        } else {
+            (
+                PreviousValue::MustNotExist,
+                format!("branch: Created from {}", start_point),
+            )
+        };
+        Ok(self.reference(branch, id, constraint, message)?)
+    }
+
+    /// Rename the branch `name` to `new_name`, both without `refs/heads/` prefix, similar to `git branch -m`, and return
+    /// the renamed branch.
+    ///
+    /// The reflog is moved along with the branch and receives an entry like `Branch: renamed refs/heads/<name> to refs/heads/<new_name>`,
+    /// and `HEAD` is updated if it points to the renamed branch. All `branch.<name>.*` sections of the configuration file of the
+    /// repository are renamed to `branch.<new_name>.*`.
+    ///
+    /// If `force` is `true`, an existing branch named `new_name` is overwritten unless it is checked out in any worktree, similar to `git branch -M`.
+    pub fn rename_branch(
+        &mut self,
+        name: impl AsRef<str>,
+        new_name: impl AsRef<str>,
+        force: bool,
+    ) -> Result<Reference<'_>, branch::rename::Error> {
+        let (name, new_name) = (name.as_ref(), new_name.as_ref());
+        let branch: FullName = format!("refs/heads/{}", name).try_into()?;
+        let new_branch: FullName = format!("refs/heads/{}", new_name).try_into()?;
+        let head_name = self.head_name()?;
+        if force && branch != new_branch && self.is_checked_out(&new_branch)? {
+            return Err(branch::rename::Error::CheckedOut { name: new_branch });
+        }
+
+        let message: BString = format!("Branch: renamed {} to {}", branch.as_bstr(), new_branch.as_bstr()).into();
+        let (file_lock_fail, packed_refs_lock_fail) = self.config.lock_timeout()?;
+        let renamed = self
+            .refs
+            .loose_store()
+            .ok_or(branch::rename::Error::UnsupportedStorageFormat)?
+            .rename_reference(
+                branch.as_ref(),
+                new_branch.as_ref(),
+                git_ref::file::rename::Options {
+                    force,
+                    message: message.clone(),
+                },
+                self.committer_or_default(),
+                file_lock_fail,
+                packed_refs_lock_fail,
+            )?;
+        if branch != new_branch && head_name.as_ref() == Some(&branch) {
+            self.set_head(
+                Target::Symbolic(new_branch),
+                PreviousValue::MustExistAndMatch(Target::Symbolic(branch)),
+                message,
+            )?;
+        }
+
+        if name != new_name {
+            self.update_branch_config(|config, filter| {
+                while config
+                    .rename_section_filter("branch", name, "branch", Some(new_name.to_owned().into()), filter)
+                    .is_ok()
+                {}
+            })?;
+        }
+        Ok(renamed.attach(self))
+    }
+
+    /// Delete the branch `name`, without `refs/heads/` prefix, along with its reflog and all `branch.<name>.*` sections of the
+    /// configuration file of the repository, similar to `git branch -d`.
+    ///
+    /// A branch that is checked out in any worktree can't be deleted, and unless `force` is `true`, the branch must be fully merged into
+    /// `HEAD`, i.e. the commit it points to must be reachable from `HEAD`. Note that unlike `git`, the upstream branch isn't
+    /// considered.
+    pub fn delete_branch(&mut self, name: impl AsRef<str>, force: bool) -> Result<(), branch::delete::Error> {
+        let name = name.as_ref();
+        let branch: FullName = format!("refs/heads/{}", name).try_into()?;
+        if self.is_checked_out(&branch)? {
+            return Err(branch::delete::Error::CheckedOut { name: branch });
+        }
+        let reference = self.find_reference(&branch)?;
+        let target = reference.inner.target.clone();
+        if !force {
+            let id = reference.into_fully_peeled_id()?.detach();
+            let mut is_merged = false;
+            if let Some(head_id) = self.head()?.id() {
+                for commit in head_id.ancestors().all()? {
+                    if commit?.detach() == id {
+                        is_merged = true;
+                        break;
+                    }
+                }
+            }
+            if !is_merged {
+                return Err(branch::delete::Error::NotFullyMerged { name: branch });
+            }
+        }
+
+        self.edit_reference(RefEdit {
+            change: Change::Delete {
+                expected: PreviousValue::MustExistAndMatch(target),
+                log: RefLog::AndReference,
+            },
+            name: branch,
+            deref: false,
+        })?;
+        self.update_branch_config(
+            |config, filter| {
+                while config.remove_section_filter("branch", name, filter).is_some() {}
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Return `true` if `HEAD` of any worktree of the repository points to `branch`.
+    fn is_checked_out(&self, branch: &FullName) -> Result<bool, branch::is_checked_out::Error> {
+        if self.head_name()?.as_ref() == Some(branch) {
+            return Ok(true);
+        }
+        for repo in self.other_worktrees().map_err(Box::new)? {
+            if repo.head_name()?.as_ref() == Some(branch) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Change `HEAD` to point to `new` if it matches `expected`, and add an entry with `message` to its reflog like `git symbolic-ref`
+    /// does, even if the object `HEAD` resolves to stays the same.
+    ///
+    /// Reference transactions only log changes to the object a reference points to, which is why the reflog entry is written
+    /// separately. Like the transaction would, it's only written if references are stored as files.
+    fn set_head(&self, new: Target, expected: PreviousValue, message: BString) -> Result<(), head::set::Error> {
+        let previous = self.find_reference("HEAD")?;
+        let is_change = previous.inner.target != new;
+        let previous_id = self.head()?.id().map(|id| id.detach());
+
+        let mut refs = self.refs.clone();
+        let write_reflog = refs.write_reflog();
+        refs.set_write_reflog(git_ref::store::WriteReflog::Disable);
+        let (file_lock_fail, packed_refs_lock_fail) = self
+            .config
+            .lock_timeout()
+            .map_err(crate::reference::edit::Error::from)?;
+        refs.transaction()
+            .prepare(
+                Some(RefEdit {
+                    change: Change::Update {
+                        log: LogChange {
+                            mode: RefLog::AndReference,
+                            force_create_reflog: false,
+                            message: message.clone(),
+                        },
+                        expected,
+                        new: new.clone(),
+                    },
+                    name: previous.inner.name,
+                    deref: false,
+                }),
+                file_lock_fail,
+                packed_refs_lock_fail,
+            )
+            .map_err(crate::reference::edit::Error::from)?
+            .commit(self.committer_or_default())
+            .map_err(crate::reference::edit::Error::from)?;
+
+        let new_id = match &new {
+            Target::Peeled(id) => Some(*id),
+            Target::Symbolic(name) => self
+                .try_find_reference(name.as_ref())?
+                .and_then(|r| r.inner.target.try_id().map(ToOwned::to_owned)),
+        };
+        let new_id = match new_id {
+            Some(id) if is_change && write_reflog != git_ref::store::WriteReflog::Disable => id,
+            _ => return Ok(()),
+        };
+        if self.refs.loose_store().is_none() {
+            return Ok(());
+        }
+        let log_path = self.git_dir().join("logs").join("HEAD");
+        std::fs::create_dir_all(log_path.parent().expect("always with parent directory"))?;
+        let log = std::fs::OpenOptions::new().create(true).append(true).open(log_path)?;
+        git_ref::log::Line {
+            previous_oid: previous_id.unwrap_or_else(|| self.object_hash().null()),
+            new_oid: new_id,
+            signature: self.committer_or_default().to_owned(),
+            message,
+        }
+        .write_to(log)?;
+        Ok(())
+    }
+
+    /// Return the name of the branch `HEAD` points to, or the object id it points to if it is detached, for use in reflog messages.
+    fn head_description(&self) -> Result<BString, crate::reference::find::existing::Error> {
+        let head = self.head()?;
+        Ok(match &head.kind {
+            head::Kind::Symbolic(r) => r.name.as_ref().shorten().to_owned(),
+            head::Kind::Unborn(name) => name.as_ref().shorten().to_owned(),
+            head::Kind::Detached { target, .. } => target.to_string().into(),
+        })
+    }
+
+    /// Apply `edit` to the configuration file of the repository and to our in-memory configuration, passing a `filter` that only
+    /// lets sections from the configuration file of the repository pass.
+    ///
+    /// The configuration file is locked while it's read and changed, like `git` does, so concurrent changes aren't lost.
+    fn update_branch_config(
+        &mut self,
+        edit: impl Fn(&mut git_config::File<'static>, &mut git_config::file::MetadataFilter),
+    ) -> Result<(), branch::update_config::Error> {
+        let config_path = self.common_dir().join("config");
+        let mut lock =
+            git_lock::File::acquire_to_update_resource(&config_path, git_lock::acquire::Fail::Immediately, None)?;
+        let mut metadata = git_config::file::Metadata::from(git_config::Source::Local);
+        metadata.path = Some(config_path.clone());
+        let mut config = match git_config::File::from_paths_metadata(Some(metadata), Default::default())? {
+            Some(config) => config,
+            None => return Ok(()),
+        };
+        let previous = config.to_bstring();
+        edit(&mut config, &mut |_| true);
+        let changed = config.to_bstring();
+        if changed == previous {
+            // Dropping the lock removes it without touching the configuration file.
+            return Ok(());
+        }
+        lock.with_mut(|file| file.write_all(&changed))?;
+        lock.commit()?;
+
+        let resolved = git_features::threading::OwnShared::make_mut(&mut self.config.resolved);
+        edit(resolved, &mut |meta| meta.source == git_config::Source::Local);
+        self.reread_values_and_clear_caches()?;
+        Ok(())
+    }
+}
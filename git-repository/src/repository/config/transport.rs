@@ -26,14 +26,16 @@ impl crate::Repository {
             Http | Https => {
                 #[cfg(not(any(
                     feature = "blocking-http-transport-reqwest",
-                    feature = "blocking-http-transport-curl"
+                    feature = "blocking-http-transport-curl",
+                    feature = "async-http-transport-reqwest"
                 )))]
                 {
                     Ok(None)
                 }
                 #[cfg(any(
                     feature = "blocking-http-transport-reqwest",
-                    feature = "blocking-http-transport-curl"
+                    feature = "blocking-http-transport-curl",
+                    feature = "async-http-transport-reqwest"
                 ))]
                 {
                     use std::borrow::Cow;
@@ -0,0 +1,324 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use git_features::progress::Progress;
+use git_hash::ObjectId;
+use git_odb::Find;
+
+use crate::{
+    fsck::{
+        self, BrokenLink, Check, Corrupt, Corruption, Missing, Object, Options, Outcome, Problem, Severity, Source,
+    },
+    progress,
+};
+
+/// An object in the object database along with the objects it links to and their expected kind.
+struct Node {
+    kind: git_object::Kind,
+    links: Vec<(ObjectId, git_object::Kind)>,
+}
+
+impl crate::Repository {
+    /// Obtain the options for [`fsck()`][Self::fsck()] from the `fsck.<check>` configuration of this repository.
+    ///
+    /// Keys that don't name a [check][Check] are ignored.
+    pub fn fsck_options(&self) -> Result<Options, fsck::options::Error> {
+        let mut severities = BTreeMap::new();
+        if let Some(sections) = self
+            .config
+            .resolved
+            .sections_by_name_and_filter("fsck", &mut self.filter_config_section())
+        {
+            for section in sections.filter(|section| section.header().subsection_name().is_none()) {
+                for key in section.keys() {
+                    let check = match Check::from_name(key.as_ref()) {
+                        Some(check) => check,
+                        None => continue,
+                    };
+                    let value = match section.value(key.as_ref()) {
+                        Some(value) => value,
+                        None => continue,
+                    };
+                    match Severity::from_bytes(value.as_ref()) {
+                        Some(severity) => {
+                            severities.insert(check, severity);
+                        }
+                        None if self.options.lenient_config => {}
+                        None => {
+                            return Err(fsck::options::Error::InvalidSeverity {
+                                key: check.name().into(),
+                                value: value.into_owned(),
+                            })
+                        }
+                    }
+                }
+            }
+        }
+        Ok(Options { severities })
+    }
+
+    /// Check all objects in the object database for problems with their content and for their connectivity, similar to `git fsck`,
+    /// and report problems with the severity configured in `options`.
+    ///
+    /// Objects are considered reachable if they can be reached from any reference or its reflog, or from `HEAD` or the index of any worktree,
+    /// and all other objects are reported as unreachable. Unreachable objects that no other object links to are dangling.
+    /// Object replacements are ignored.
+    ///
+    /// Objects which can't be read or whose content doesn't hash to their id are reported as corrupt and aren't checked further.
+    ///
+    /// Objects that objects in promisor packs link to aren't reported as missing, as the promisor remote of a partial clone
+    /// promises to provide them.
+    ///
+    /// Progress is sent to `progress` and interruptions are checked via `should_interrupt`.
+    pub fn fsck(
+        &self,
+        mut progress: impl Progress,
+        should_interrupt: &AtomicBool,
+        options: Options,
+    ) -> Result<Outcome, fsck::Error> {
+        let object_hash = self.object_hash();
+        let mut db = self.objects.clone();
+        db.ignore_replacements = true;
+        db.unset_missing_object_hook();
+        let mut outcome = Outcome::default();
+
+        let mut nodes = HashMap::new();
+        {
+            let objects = db.iter()?;
+            let mut progress = progress.add_child("checking objects");
+            progress.init(Some(objects.size_hint().0), progress::count("objects"));
+            let mut buf = Vec::new();
+            for id in objects {
+                if should_interrupt.load(Ordering::Relaxed) {
+                    return Err(fsck::Error::Interrupted);
+                }
+                let id = id?;
+                if nodes.contains_key(&id) || outcome.corrupt.iter().any(|corrupt| corrupt.id == id) {
+                    continue;
+                }
+                let object = match db.try_find(id, &mut buf) {
+                    Ok(Some(object)) => object,
+                    Ok(None) => continue,
+                    Err(err) => {
+                        outcome.corrupt.push(Corrupt {
+                            id,
+                            corruption: Corruption::Unreadable {
+                                message: err.to_string(),
+                            },
+                        });
+                        progress.inc();
+                        continue;
+                    }
+                };
+                let kind = object.kind;
+                let actual = {
+                    let mut hasher = git_features::hash::hasher(object_hash);
+                    hasher.update(&git_object::encode::loose_header(kind, object.data.len()));
+                    hasher.update(object.data);
+                    match hasher.try_digest() {
+                        Ok(actual) => actual,
+                        Err(_collision) => {
+                            outcome.corrupt.push(Corrupt {
+                                id,
+                                corruption: Corruption::Collision,
+                            });
+                            progress.inc();
+                            continue;
+                        }
+                    }
+                };
+                if actual != id {
+                    outcome.corrupt.push(Corrupt {
+                        id,
+                        corruption: Corruption::HashMismatch { actual },
+                    });
+                    progress.inc();
+                    continue;
+                }
+                let links = fsck::object::check(kind, object.data, object_hash, &mut |check| {
+                    let severity = options.severity(check);
+                    if severity != Severity::Ignore {
+                        outcome.problems.push(Problem {
+                            id,
+                            kind,
+                            check,
+                            severity,
+                        });
+                    }
+                });
+                nodes.insert(id, Node { kind, links });
+                progress.inc();
+            }
+        }
+        outcome.num_objects = nodes.len() + outcome.corrupt.len();
+        let corrupt = outcome.corrupt.iter().map(|corrupt| corrupt.id).collect::<HashSet<_>>();
+
+        let mut promised = HashSet::new();
+        for index_path in db.store_ref().promisor_pack_indices()? {
+            let index = git_pack::index::File::at(index_path, object_hash)?;
+            promised.extend(
+                index
+                    .iter()
+                    .filter_map(|entry| nodes.get(&entry.oid))
+                    .flat_map(|node| node.links.iter().map(|(id, _kind)| *id)),
+            );
+        }
+
+        // The parents of shallow commits are expected to be missing.
+        let shallow_commits = self.shallow_commits()?.unwrap_or_default();
+        let mut progress = progress.add_child("checking connectivity");
+        progress.init(Some(nodes.len()), progress::count("objects"));
+        let mut missing = BTreeMap::new();
+        let mut reachable = HashSet::new();
+        let mut queue = Vec::new();
+        for (id, kind, source) in self.fsck_tips()? {
+            if nodes.contains_key(&id) {
+                if reachable.insert(id) {
+                    queue.push(id);
+                }
+            } else if !promised.contains(&id) && !corrupt.contains(&id) {
+                missing.entry(id).or_insert(Missing {
+                    id,
+                    kind,
+                    referenced_by: source,
+                });
+            }
+        }
+        while let Some(id) = queue.pop() {
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(fsck::Error::Interrupted);
+            }
+            progress.inc();
+            let node = &nodes[&id];
+            let is_shallow = node.kind == git_object::Kind::Commit && shallow_commits.binary_search(&id).is_ok();
+            for &(link, expected) in &node.links {
+                if is_shallow && expected == git_object::Kind::Commit {
+                    continue;
+                }
+                let actual = nodes.get(&link).map(|node| node.kind);
+                if actual.is_none() && (promised.contains(&link) || corrupt.contains(&link)) {
+                    continue;
+                }
+                if actual != Some(expected) {
+                    outcome.broken_links.push(BrokenLink {
+                        from: id,
+                        to: link,
+                        expected,
+                        actual,
+                    });
+                }
+                match actual {
+                    Some(_) => {
+                        if reachable.insert(link) {
+                            queue.push(link);
+                        }
+                    }
+                    None => {
+                        missing.entry(link).or_insert(Missing {
+                            id: link,
+                            kind: Some(expected),
+                            referenced_by: Source::Object(id),
+                        });
+                    }
+                }
+            }
+        }
+
+        let linked = nodes
+            .values()
+            .flat_map(|node| node.links.iter().map(|(id, _kind)| *id))
+            .collect::<HashSet<_>>();
+        outcome.unreachable = nodes
+            .iter()
+            .filter(|(id, _node)| !reachable.contains(*id))
+            .map(|(id, node)| Object {
+                id: *id,
+                kind: node.kind,
+            })
+            .collect();
+        outcome.unreachable.sort();
+        outcome.dangling = outcome
+            .unreachable
+            .iter()
+            .filter(|object| !linked.contains(&object.id))
+            .copied()
+            .collect();
+        outcome.missing = missing.into_values().collect();
+        outcome.corrupt.sort_by_key(|corrupt| corrupt.id);
+        outcome.problems.sort_by_key(|problem| problem.id);
+        outcome.broken_links.sort_by_key(|link| (link.from, link.to));
+        Ok(outcome)
+    }
+
+    /// Return the objects referenced by references, their reflogs, `HEAD` and the index, along with their expected kind
+    /// and where they are referenced from.
+    fn fsck_tips(&self) -> Result<Vec<Tip>, fsck::Error> {
+        let mut tips = Vec::new();
+        for reference in self.refs.iter()?.all()? {
+            add_reference(self, &reference?, &mut tips)?;
+        }
+        add_head_and_index(self, &mut tips)?;
+
+        for repo in &self.other_worktrees().map_err(Box::new)? {
+            add_head_and_index(repo, &mut tips)?;
+            for prefix in ["refs/bisect/", "refs/rewritten/", "refs/worktree/"] {
+                for reference in repo.refs.iter()?.prefixed(prefix)? {
+                    add_reference(repo, &reference?, &mut tips)?;
+                }
+            }
+        }
+        Ok(tips)
+    }
+}
+
+type Tip = (ObjectId, Option<git_object::Kind>, Source);
+
+/// Add the object `reference` of `repo` points to and the objects of its reflog to `tips`.
+fn add_reference(
+    repo: &crate::Repository,
+    reference: &git_ref::Reference,
+    tips: &mut Vec<Tip>,
+) -> Result<(), fsck::Error> {
+    if let git_ref::Target::Peeled(id) = reference.target {
+        tips.push((id, None, Source::Reference(reference.name.clone())));
+    }
+    if let Some(log) = repo.refs.log_iter(reference.name.as_ref()).all()? {
+        for line in log {
+            let line = line?;
+            for id in [line.previous_oid, line.new_oid] {
+                if !id.is_null() {
+                    tips.push((id, None, Source::Reflog(reference.name.clone())));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add the objects that `HEAD` and the index of the worktree of `repo` point to, to `tips`.
+fn add_head_and_index(repo: &crate::Repository, tips: &mut Vec<Tip>) -> Result<(), fsck::Error> {
+    if let Some(head) = repo.refs.try_find("HEAD")? {
+        add_reference(repo, &head, tips)?;
+    }
+    match repo.index() {
+        Ok(index) => tips.extend(
+            index
+                .entries()
+                .iter()
+                .filter(|entry| entry.mode != git_index::entry::Mode::COMMIT)
+                .map(|entry| {
+                    (
+                        entry.id,
+                        Some(git_object::Kind::Blob),
+                        Source::Index(entry.path(&index).to_owned()),
+                    )
+                }),
+        ),
+        Err(crate::worktree::open_index::Error::IndexFile(git_index::file::init::Error::Io(err)))
+            if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+    Ok(())
+}
@@ -10,6 +10,17 @@ impl crate::Repository {
         linked_worktree_options: crate::open::Options,
         index: crate::worktree::IndexStorage,
     ) -> Self {
+        #[cfg(feature = "blocking-network-client")]
+        let objects = {
+            let mut objects = objects;
+            crate::promisor::set_missing_object_hook(
+                &mut objects,
+                &config.resolved,
+                refs.git_dir(),
+                &linked_worktree_options,
+            );
+            objects
+        };
         crate::Repository {
             bufs: RefCell::new(Vec::with_capacity(4)),
             work_tree,
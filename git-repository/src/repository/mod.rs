@@ -19,18 +19,27 @@ impl crate::Repository {
     }
 }
 
+mod branch;
 mod cache;
 mod config;
+mod fsck;
 pub(crate) mod identity;
 mod impls;
 mod init;
 mod location;
 mod object;
 pub(crate) mod permissions;
+mod promisor;
+#[cfg(feature = "blocking-network-server")]
+mod receive_pack;
 mod reference;
 mod remote;
+mod repack;
 mod revision;
+mod shallow;
 mod snapshots;
 mod state;
 mod thread_safe;
+#[cfg(feature = "blocking-network-server")]
+mod upload_pack;
 mod worktree;
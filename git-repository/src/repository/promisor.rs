@@ -0,0 +1,68 @@
+use std::borrow::Cow;
+
+use crate::bstr::BStr;
+
+impl crate::Repository {
+    /// Return the name of the remote which promises to provide all objects missing in this partial clone, as configured in
+    /// `extensions.partialClone`, or `None` if this repository isn't a partial clone.
+    pub fn promisor_remote_name(&self) -> Option<Cow<'_, BStr>> {
+        self.config.resolved.string("extensions", None, "partialClone")
+    }
+
+    /// Fetch the objects with `ids` from the [promisor remote][Self::promisor_remote_name()] in a single pack, providing
+    /// `progress` and checking `should_interrupt`, or return `None` if there is nothing to fetch.
+    ///
+    /// This is done automatically for each object that is looked up but missing in partial clones, but fetching many
+    /// objects at once is much faster than fetching them one by one.
+    ///
+    /// Like `git`, objects are fetched without sending any `have` lines and with the `blob:none` filter, so only the
+    /// objects with `ids` are received even if trees are among them. The received pack is marked as promisor pack
+    /// and isn't kept by a `.keep` file.
+    #[cfg(feature = "blocking-network-client")]
+    #[allow(clippy::result_large_err)]
+    pub fn fetch_promised_objects<P>(
+        &self,
+        ids: impl IntoIterator<Item = git_hash::ObjectId>,
+        progress: P,
+        should_interrupt: &std::sync::atomic::AtomicBool,
+    ) -> Result<Option<crate::remote::fetch::Outcome>, crate::promisor::fetch::Error>
+    where
+        P: crate::Progress,
+        P::SubProgress: 'static,
+    {
+        use crate::promisor::fetch::Error;
+
+        let specs: Vec<_> = ids.into_iter().map(|id| id.to_string()).collect();
+        if specs.is_empty() {
+            return Ok(None);
+        }
+        let remote_name = self.promisor_remote_name().ok_or(Error::NoPromisorRemote)?.to_string();
+
+        let mut repo = self.clone();
+        // The objects to fetch are missing locally, so looking them up while fetching must not fetch them again.
+        repo.objects.unset_missing_object_hook();
+        {
+            // Our commits usually reach the objects to fetch, so sending them as haves would make the server omit them.
+            let mut config = repo.config_snapshot_mut();
+            config
+                .set_raw_value("fetch", None, "negotiationAlgorithm", "noop")
+                .expect("valid static key");
+            config.commit()?;
+        }
+
+        let mut remote = repo.find_remote(&remote_name)?;
+        remote.replace_refspecs(specs.iter().map(String::as_str), crate::remote::Direction::Fetch)?;
+        let mut outcome = remote
+            .connect(crate::remote::Direction::Fetch, progress)?
+            .prepare_fetch(Default::default())?
+            .with_filter("blob:none")
+            .receive(should_interrupt)?;
+        // No references point to the received objects, but being in a promisor pack is enough to keep them.
+        if let crate::remote::fetch::Status::Change { write_pack_bundle, .. } = &mut outcome.status {
+            if let Some(path) = write_pack_bundle.keep_path.take() {
+                std::fs::remove_file(&path).map_err(|err| Error::RemovePackKeepFile { path, source: err })?;
+            }
+        }
+        Ok(Some(outcome))
+    }
+}
@@ -0,0 +1,152 @@
+use std::{collections::HashSet, io, sync::atomic::AtomicBool};
+
+use git_transport::packetline::{Channel, StreamingPeekableIter};
+
+use crate::receive_pack::{
+    hooks::{push_option_env, Hooks},
+    response, update, Error, Options, Outcome, Quarantine, Request,
+};
+
+impl crate::Repository {
+    /// Accept a push into this repository from a client that sends its requests through `read` and receives our responses through
+    /// `write`, similar to `git receive-pack`.
+    ///
+    /// `read` and `write` are positioned after the initial request of the client, which is read by
+    /// [`git_transport::server::accept()`] for `git` daemons, or is provided by SSH or HTTP frontends by other means.
+    ///
+    /// The pack sent by the client is received into a quarantine object directory and all objects needed by the updated references
+    /// have to be present. Then the `pre-receive` hook runs with access to the quarantined objects, which are moved into the
+    /// object database if it succeeds. After the `update` hook accepted a reference update, all updates are applied in a single
+    /// reference transaction, which either applies all of them or none if the client asked for an `atomic` push.
+    /// Finally the `post-receive` hook is informed about all applied updates.
+    ///
+    /// Hooks are found in `core.hooksPath` or in the `hooks` directory of the repository, and all output of hooks is sent to the
+    /// client if it supports a side-band.
+    /// Updates of the checked out branch of a repository with worktree are refused unless `receive.denyCurrentBranch` allows them,
+    /// and `receive.denyDeletes`, `receive.denyDeleteCurrent` and `receive.denyNonFastForwards` are respected as well.
+    ///
+    /// Interruptions are checked via `should_interrupt` while receiving the pack.
+    pub fn receive_pack(
+        &self,
+        read: impl io::Read,
+        mut write: impl io::Write,
+        options: Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        if !options.stateless_rpc || options.advertise_refs {
+            response::advertise(self, &mut write)?;
+        }
+        if options.advertise_refs {
+            return Ok(Outcome::default());
+        }
+        let mut lines = StreamingPeekableIter::new(read, &[]);
+        let Request {
+            mut commands,
+            push_options,
+            report_status,
+            side_band,
+            atomic,
+        } = match Request::read(&mut lines, self.object_hash())? {
+            Some(request) => request,
+            None => return Ok(Outcome::default()),
+        };
+        let mut outcome = Outcome {
+            push_options,
+            ..Default::default()
+        };
+
+        let quarantine = Quarantine::new(self.objects.store_ref().path())?;
+        let mut received = HashSet::new();
+        if commands.iter().any(|c| !c.is_deletion()) {
+            match quarantine.receive(self, io::BufReader::new(lines.into_inner()), should_interrupt) {
+                Ok(ids) => received = ids,
+                Err(err) => outcome.unpack_error = Some(err.to_string().into()),
+            }
+        }
+        outcome.num_objects = received.len() as u64;
+
+        let mut hooks = Hooks::new(self)?;
+        let push_option_env = push_option_env(&outcome.push_options);
+        let mut keep_files = Vec::new();
+        if outcome.unpack_error.is_some() {
+            for command in &mut commands {
+                command.reject("unpacker error");
+            }
+        } else {
+            let db = git_odb::at(quarantine.path())?;
+            update::check_connectivity(self, &db, &received, &mut commands, should_interrupt)?;
+            update::check_commands(self, &db, &mut commands)?;
+            if atomic {
+                update::reject_all_if_one_failed(&mut commands);
+            }
+        }
+
+        if commands.iter().any(|c| c.is_ok()) {
+            hooks.quarantine = Some((quarantine.path().to_owned(), quarantine.objects_dir().to_owned()));
+            let stdin = hook_input(commands.iter().filter(|c| c.is_ok()));
+            if let Some(output) = hooks.run("pre-receive", &[], &stdin, &push_option_env)? {
+                response::write_band(Channel::Progress, &output.data, side_band, &mut write)?;
+                if !output.success {
+                    for command in &mut commands {
+                        command.reject("pre-receive hook declined");
+                    }
+                }
+            }
+            hooks.quarantine = None;
+        }
+
+        if commands.iter().any(|c| c.is_ok()) {
+            keep_files = quarantine.migrate()?;
+            for command in commands.iter_mut().filter(|c| c.is_ok()) {
+                let (old_id, new_id) = (command.old_id.to_string(), command.new_id.to_string());
+                let args = [command.name.as_slice(), old_id.as_bytes(), new_id.as_bytes()];
+                if let Some(output) = hooks.run("update", &args, &[], &[])? {
+                    response::write_band(Channel::Progress, &output.data, side_band, &mut write)?;
+                    if !output.success {
+                        command.reject("hook declined");
+                    }
+                }
+            }
+            if atomic {
+                update::reject_all_if_one_failed(&mut commands);
+            }
+            update::apply(self, &mut commands);
+        }
+        for keep_file in keep_files {
+            std::fs::remove_file(keep_file).ok();
+        }
+
+        if report_status {
+            let mut report = Vec::new();
+            response::report(
+                &commands,
+                outcome.unpack_error.as_deref().map(|e| e.as_slice()),
+                &mut report,
+            )?;
+            response::write_band(Channel::Data, &report, side_band, &mut write)?;
+        }
+        if commands.iter().any(|c| c.is_ok()) {
+            let stdin = hook_input(commands.iter().filter(|c| c.is_ok()));
+            if let Some(output) = hooks.run("post-receive", &[], &stdin, &push_option_env)? {
+                response::write_band(Channel::Progress, &output.data, side_band, &mut write)?;
+            }
+        }
+        if side_band.is_some() {
+            git_transport::packetline::encode::flush_to_write(&mut write)?;
+        }
+        write.flush()?;
+        outcome.commands = commands;
+        Ok(outcome)
+    }
+}
+
+/// The input of the `pre-receive` and `post-receive` hooks, one line per command.
+fn hook_input<'a>(commands: impl Iterator<Item = &'a crate::receive_pack::Command>) -> Vec<u8> {
+    let mut out = Vec::new();
+    for command in commands {
+        out.extend_from_slice(format!("{} {} ", command.old_id, command.new_id).as_bytes());
+        out.extend_from_slice(&command.name);
+        out.push(b'\n');
+    }
+    out
+}
@@ -1,6 +1,7 @@
 use std::convert::TryInto;
 
 use git_hash::ObjectId;
+use git_odb::pack::Find;
 use git_ref::{
     transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
     FullName, PartialNameRef, Target,
@@ -46,27 +47,28 @@ impl crate::Repository {
     ///
     /// Namespaces allow to partition references, and is configured per `Easy`.
     pub fn namespace(&self) -> Option<&git_ref::Namespace> {
-        self.refs.namespace.as_ref()
+        self.refs.namespace()
     }
 
     /// Remove the currently set reference namespace and return it, affecting only this `Easy`.
     pub fn clear_namespace(&mut self) -> Option<git_ref::Namespace> {
-        self.refs.namespace.take()
+        self.refs.set_namespace(None).expect("removing a namespace never fails")
     }
 
     /// Set the reference namespace to the given value, like `"foo"` or `"foo/bar"`.
     ///
-    /// Note that this value is shared across all `Easy…` instances as the value is stored in the shared `Repository`.
+    /// Note that this value is shared across all `Easy…` instances as the value is stored in the shared `Repository`, and that
+    /// namespaces are only supported if references are stored as files.
     pub fn set_namespace<'a, Name, E>(
         &mut self,
         namespace: Name,
-    ) -> Result<Option<git_ref::Namespace>, git_validate::refname::Error>
+    ) -> Result<Option<git_ref::Namespace>, reference::set_namespace::Error>
     where
         Name: TryInto<&'a PartialNameRef, Error = E>,
         git_validate::refname::Error: From<E>,
     {
         let namespace = git_ref::namespace::expand(namespace)?;
-        Ok(self.refs.namespace.replace(namespace))
+        Ok(self.refs.set_namespace(Some(namespace))?)
     }
 
     // TODO: more tests or usage
@@ -140,6 +142,26 @@ impl crate::Repository {
             .map_err(Into::into)
     }
 
+    /// Move loose references into the `packed-refs` file according to `options`, similar to `git pack-refs`, and return
+    /// how many were packed. This fails if references aren't stored as files.
+    ///
+    /// Use [`Options::all`][git_ref::file::pack_refs::Options::all] and [`Options::prune`][git_ref::file::pack_refs::Options::prune]
+    /// for the equivalent of `git pack-refs --all --prune`. Annotated tags are written with the object they ultimately
+    /// point to, and locks are acquired according to `core.filesRefLockTimeout` and `core.packedRefsTimeout`.
+    pub fn pack_refs(
+        &self,
+        options: git_ref::file::pack_refs::Options,
+    ) -> Result<git_ref::file::pack_refs::Outcome, reference::pack::Error> {
+        let (file_lock_fail, packed_refs_lock_fail) = self.config.lock_timeout()?;
+        Ok(self
+            .refs
+            .loose_store()
+            .ok_or(reference::pack::Error::UnsupportedStorageFormat)?
+            .pack_refs(options, file_lock_fail, packed_refs_lock_fail, |oid, buf| {
+                self.objects.try_find(oid, buf).map(|po| po.map(|(o, _l)| o.kind))
+            })?)
+    }
+
     /// Return the repository head, an abstraction to help dealing with the `HEAD` reference.
     ///
     /// The `HEAD` reference can be in various states, for more information, the documentation of [`Head`][crate::Head].
@@ -205,7 +227,7 @@ impl crate::Repository {
     pub fn find_reference<'a, Name, E>(&self, name: Name) -> Result<Reference<'_>, reference::find::existing::Error>
     where
         Name: TryInto<&'a PartialNameRef, Error = E>,
-        git_ref::file::find::Error: From<E>,
+        git_ref::store::find::Error: From<E>,
     {
         self.try_find_reference(name)?
             .ok_or(reference::find::existing::Error::NotFound)
@@ -229,7 +251,7 @@ impl crate::Repository {
     pub fn try_find_reference<'a, Name, E>(&self, name: Name) -> Result<Option<Reference<'_>>, reference::find::Error>
     where
         Name: TryInto<&'a PartialNameRef, Error = E>,
-        git_ref::file::find::Error: From<E>,
+        git_ref::store::find::Error: From<E>,
     {
         let state = self;
         match state.refs.try_find(name) {
@@ -0,0 +1,588 @@
+use std::{
+    collections::HashSet,
+    convert::Infallible,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use git_features::progress::Progress;
+use git_hash::ObjectId;
+use git_pack::{
+    data::{output, output::count::PackLocation},
+    Find, FindExt,
+};
+use git_tempfile::{AutoRemove, ContainingDirectory};
+
+use crate::{
+    progress,
+    repack::{Error, Mode, Options, Outcome, Pack},
+};
+
+type Db = git_odb::Cache<git_odb::store::Handle<std::sync::Arc<git_odb::Store>>>;
+
+/// An existing pack in the `objects/pack` directory.
+struct ExistingPack {
+    index_path: PathBuf,
+    index: git_pack::index::File,
+    keep: bool,
+    /// `true` if the pack was received from a promisor remote, which makes it subject to the same rules as kept packs.
+    promisor: bool,
+}
+
+impl ExistingPack {
+    /// Return `true` if this pack must not be removed by a repack.
+    fn is_retained(&self) -> bool {
+        self.keep || self.promisor
+    }
+}
+
+/// Objects reachable from references, reflogs and the index.
+struct Reachable {
+    counts: Vec<output::Count>,
+    /// The commits that references and reflogs point to, directly or through tags.
+    commit_tips: Vec<ObjectId>,
+}
+
+impl crate::Repository {
+    /// Consolidate loose objects and packs of this repository's object database into a new pack according to `options`,
+    /// and remove all packs and loose objects that are made redundant by it.
+    ///
+    /// Objects are considered reachable if they can be reached from any reference or its reflog, `HEAD` or the index.
+    /// The new pack and its index are moved into place before any pack is removed, so readers can see all objects at all times.
+    /// Packs and loose objects in alternate object databases are never removed.
+    ///
+    /// Packs received from the promisor remote of a partial clone are retained just like packs with a `.keep` file, and
+    /// their objects aren't written into the new pack. Objects they link to which are missing are promised by the remote,
+    /// so they are neither fetched nor written.
+    ///
+    /// Progress is sent to `progress` and interruptions are checked via `should_interrupt`.
+    pub fn repack<P>(&self, mut progress: P, should_interrupt: &AtomicBool, options: Options) -> Result<Outcome, Error>
+    where
+        P: Progress,
+        P::SubProgress: 'static,
+    {
+        match options.mode {
+            Mode::AllIntoOne => {}
+            _ if options.write_bitmap => return Err(Error::BitmapRequiresAllIntoOne),
+            Mode::Geometric { factor } if factor < 2 => return Err(Error::InvalidGeometricFactor { factor }),
+            Mode::Geometric { .. } | Mode::LooseOnly => {}
+        }
+        let object_hash = self.object_hash();
+        let objects_dir = self.objects.store_ref().path().to_owned();
+        let pack_dir = objects_dir.join("pack");
+        std::fs::create_dir_all(&pack_dir)?;
+
+        let mut db = self.objects.clone().into_arc()?;
+        db.prevent_pack_unload();
+        db.ignore_replacements = true;
+        // Repacking only moves objects that are present, it must never fetch them.
+        db.unset_missing_object_hook();
+        let loose = git_odb::loose::Store::at(&objects_dir, object_hash);
+        let loose_objects = loose.iter().collect::<Result<HashSet<_>, _>>()?;
+        let existing_packs = existing_packs(&pack_dir, object_hash)?;
+        let promisor_objects = existing_packs
+            .iter()
+            .filter(|pack| pack.promisor)
+            .flat_map(|pack| pack.index.iter().map(|entry| entry.oid))
+            .collect::<HashSet<_>>();
+        let has_promisor_packs = existing_packs.iter().any(|pack| pack.promisor);
+        let is_packable = |id: &ObjectId| !promisor_objects.contains(id) && (!has_promisor_packs || db.contains(id));
+
+        let reachable = match (options.mode, options.prune_loose_older_than) {
+            (Mode::Geometric { .. }, None) => None,
+            _ => {
+                let tips = self.repack_tips(&db)?;
+                Some(reachable_objects(
+                    &db,
+                    tips,
+                    self.shallow_commits()?.unwrap_or_default(),
+                    progress.add_child("counting reachable"),
+                    should_interrupt,
+                )?)
+            }
+        };
+        let reachable_ids = options
+            .prune_loose_older_than
+            .and(reachable.as_ref())
+            .map(|reachable| reachable.counts.iter().map(|count| count.id).collect::<HashSet<_>>());
+
+        let (counts, redundant_packs) = match options.mode {
+            Mode::AllIntoOne => (
+                reachable
+                    .as_ref()
+                    .expect("computed in this mode")
+                    .counts
+                    .iter()
+                    .filter(|count| is_packable(&count.id))
+                    .cloned()
+                    .collect(),
+                existing_packs
+                    .iter()
+                    .filter(|pack| !pack.is_retained())
+                    .collect::<Vec<_>>(),
+            ),
+            Mode::LooseOnly => (
+                reachable
+                    .as_ref()
+                    .expect("computed in this mode")
+                    .counts
+                    .iter()
+                    .filter(|count| {
+                        loose_objects.contains(&count.id)
+                            && is_packable(&count.id)
+                            && !matches!(count.entry_pack_location, PackLocation::LookedUp(Some(_)))
+                    })
+                    .cloned()
+                    .collect(),
+                Vec::new(),
+            ),
+            Mode::Geometric { factor } => {
+                let mut packs = existing_packs
+                    .iter()
+                    .filter(|pack| !pack.is_retained())
+                    .collect::<Vec<_>>();
+                packs.sort_by_key(|pack| pack.index.num_objects());
+                let split = geometric_split(
+                    &packs.iter().map(|pack| pack.index.num_objects()).collect::<Vec<_>>(),
+                    factor,
+                );
+                packs.truncate(split);
+                if packs.len() < 2 && loose_objects.is_empty() {
+                    (Vec::new(), Vec::new())
+                } else {
+                    let ids = packs
+                        .iter()
+                        .flat_map(|pack| pack.index.iter().map(|entry| entry.oid))
+                        .chain(loose_objects.iter().copied())
+                        .collect::<HashSet<_>>();
+                    let mut counting_progress = progress.add_child("counting");
+                    counting_progress.init(Some(ids.len()), progress::count("objects"));
+                    let (counts, _) = output::count::objects_unthreaded(
+                        db.clone(),
+                        ids.into_iter().map(Ok::<_, Infallible>),
+                        counting_progress,
+                        should_interrupt,
+                        output::count::objects::ObjectExpansion::AsIs,
+                        None,
+                    )?;
+                    (counts, packs)
+                }
+            }
+        };
+
+        let pack = if counts.is_empty() {
+            None
+        } else {
+            let mut pack = write_pack(
+                &db,
+                counts,
+                &pack_dir,
+                object_hash,
+                &options,
+                &mut progress,
+                should_interrupt,
+            )?;
+            if options.write_bitmap {
+                let bundle = git_pack::Bundle::at(&pack.index_path, object_hash)?;
+                let mut bitmap_file = git_tempfile::new(&pack_dir, ContainingDirectory::Exists, AutoRemove::Tempfile)?;
+                git_pack::bitmap::File::write_from_bundle(
+                    &bundle,
+                    reachable
+                        .as_ref()
+                        .expect("bitmaps require all-into-one mode")
+                        .commit_tips
+                        .iter()
+                        .copied(),
+                    db.clone(),
+                    &mut bitmap_file,
+                    progress.add_child("writing bitmap"),
+                    should_interrupt,
+                )?;
+                let bitmap_path = pack.data_path.with_extension("bitmap");
+                bitmap_file.persist(&bitmap_path)?;
+                pack.bitmap_path = Some(bitmap_path);
+            }
+            Some(pack)
+        };
+        let redundant_packs = redundant_packs
+            .into_iter()
+            .filter(|existing| {
+                pack.as_ref()
+                    .map_or(true, |pack| pack.index_path != existing.index_path)
+            })
+            .collect::<Vec<_>>();
+
+        let multi_index_path = pack_dir.join("multi-pack-index");
+        let multi_pack_index = if options.write_multi_pack_index {
+            let index_paths = existing_packs
+                .iter()
+                .map(|existing| existing.index_path.clone())
+                .filter(|path| !redundant_packs.iter().any(|redundant| &redundant.index_path == path))
+                .chain(pack.as_ref().map(|pack| pack.index_path.clone()))
+                .collect::<HashSet<_>>();
+            if index_paths.is_empty() {
+                None
+            } else {
+                let mut multi_index_file =
+                    git_tempfile::new(&pack_dir, ContainingDirectory::Exists, AutoRemove::Tempfile)?;
+                git_pack::multi_index::File::write_from_index_paths(
+                    index_paths.into_iter().collect(),
+                    &mut multi_index_file,
+                    progress.add_child("writing multi-pack-index"),
+                    should_interrupt,
+                    git_pack::multi_index::write::Options {
+                        object_hash,
+                        base: None,
+                    },
+                )?;
+                remove_multi_index_extensions(&pack_dir)?;
+                multi_index_file.persist(&multi_index_path)?;
+                Some(multi_index_path)
+            }
+        } else {
+            if !redundant_packs.is_empty() {
+                remove_if_present(&multi_index_path)?;
+                remove_multi_index_extensions(&pack_dir)?;
+            }
+            None
+        };
+
+        let mut removed_packs = Vec::with_capacity(redundant_packs.len());
+        for redundant in redundant_packs {
+            remove_pack(&redundant.index_path)?;
+            removed_packs.push(redundant.index_path.with_extension("pack"));
+        }
+
+        let new_index = pack
+            .as_ref()
+            .map(|pack| git_pack::index::File::at(&pack.index_path, object_hash))
+            .transpose()?;
+        let mut removed_loose_objects = 0;
+        let mut fan_out_dirs = HashSet::new();
+        for id in &loose_objects {
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            let path = loose.object_path(id);
+            let is_packed = new_index.as_ref().map_or(false, |index| index.lookup(id).is_some());
+            let is_expired = match (options.prune_loose_older_than, reachable_ids.as_ref()) {
+                (Some(expiry), Some(reachable_ids)) if !reachable_ids.contains(id) => {
+                    std::fs::symlink_metadata(&path)?.modified()? < expiry
+                }
+                _ => false,
+            };
+            if is_packed || is_expired {
+                std::fs::remove_file(&path)?;
+                removed_loose_objects += 1;
+                if let Some(dir) = path.parent() {
+                    fan_out_dirs.insert(dir.to_owned());
+                }
+            }
+        }
+        for dir in fan_out_dirs {
+            // It's fine if other objects are still present.
+            std::fs::remove_dir(dir).ok();
+        }
+
+        Ok(Outcome {
+            pack,
+            removed_packs,
+            removed_loose_objects,
+            multi_pack_index,
+        })
+    }
+
+    /// Return the ids of all objects that references, their reflogs, `HEAD` and the index point to, and which exist in `db`.
+    ///
+    /// This includes the `HEAD`, index and private references of all other worktrees as well.
+    fn repack_tips(&self, db: &Db) -> Result<Vec<ObjectId>, Error> {
+        let mut tips = Vec::new();
+        for reference in self.refs.iter()?.all()? {
+            add_reference(self, &reference?, &mut tips)?;
+        }
+        add_head_and_index(self, &mut tips)?;
+
+        for repo in &self.other_worktrees().map_err(Box::new)? {
+            add_head_and_index(repo, &mut tips)?;
+            for prefix in ["refs/bisect/", "refs/rewritten/", "refs/worktree/"] {
+                for reference in repo.refs.iter()?.prefixed(prefix)? {
+                    add_reference(repo, &reference?, &mut tips)?;
+                }
+            }
+        }
+
+        tips.sort();
+        tips.dedup();
+        tips.retain(|id| db.contains(id));
+        Ok(tips)
+    }
+}
+
+/// Add the object `reference` points to, along with all objects mentioned in its reflog in `repo`, to `tips`.
+fn add_reference(
+    repo: &crate::Repository,
+    reference: &git_ref::Reference,
+    tips: &mut Vec<ObjectId>,
+) -> Result<(), Error> {
+    if let git_ref::Target::Peeled(id) = reference.target {
+        tips.push(id);
+    }
+    if let Some(log) = repo.refs.log_iter(reference.name.as_ref()).all()? {
+        for line in log {
+            let line = line?;
+            tips.extend([line.previous_oid, line.new_oid].into_iter().filter(|id| !id.is_null()));
+        }
+    }
+    Ok(())
+}
+
+/// Add the objects that `HEAD` and the index of the worktree of `repo` point to, to `tips`.
+fn add_head_and_index(repo: &crate::Repository, tips: &mut Vec<ObjectId>) -> Result<(), Error> {
+    if let Some(head) = repo.refs.try_find("HEAD")? {
+        add_reference(repo, &head, tips)?;
+    }
+    match repo.index() {
+        Ok(index) => tips.extend(
+            index
+                .entries()
+                .iter()
+                .filter(|entry| entry.mode != git_index::entry::Mode::COMMIT)
+                .map(|entry| entry.id),
+        ),
+        Err(crate::worktree::open_index::Error::IndexFile(git_index::file::init::Error::Io(err)))
+            if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+    Ok(())
+}
+
+/// Count all objects reachable from `tips`, which may be objects of any kind, without traversing the parents of
+/// `shallow_commits` as these don't exist in shallow repositories.
+fn reachable_objects(
+    db: &Db,
+    tips: Vec<ObjectId>,
+    shallow_commits: Vec<ObjectId>,
+    mut progress: impl Progress,
+    should_interrupt: &AtomicBool,
+) -> Result<Reachable, Error> {
+    let mut buf = Vec::new();
+    let mut commit_tips = Vec::new();
+    for tip in &tips {
+        let mut id = *tip;
+        loop {
+            let (object, _location) = db.find(id, &mut buf)?;
+            match object.kind {
+                git_object::Kind::Tag => id = git_object::TagRefIter::from_bytes(object.data).target_id()?,
+                git_object::Kind::Commit => {
+                    commit_tips.push(id);
+                    break;
+                }
+                git_object::Kind::Tree | git_object::Kind::Blob => break,
+            }
+        }
+    }
+    commit_tips.sort();
+    commit_tips.dedup();
+
+    progress.init(None, progress::count("commits"));
+    let mut commits = Vec::new();
+    for commit in git_traverse::commit::Ancestors::new(
+        commit_tips.iter().copied(),
+        git_traverse::commit::ancestors::State::default(),
+        |oid, buf| db.find_commit_iter(oid, buf).map(|t| t.0),
+    )
+    .shallow_commits(shallow_commits)
+    {
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+        commits.push(commit?);
+        progress.inc();
+    }
+
+    progress.init(None, progress::count("objects"));
+    let (counts, _) = output::count::objects_unthreaded(
+        db.clone(),
+        tips.into_iter().chain(commits).map(Ok::<_, Infallible>),
+        progress,
+        should_interrupt,
+        output::count::objects::ObjectExpansion::TreeContents,
+        None,
+    )?;
+    Ok(Reachable { counts, commit_tips })
+}
+
+/// Write a pack with all objects in `counts` along with its index into `pack_dir`.
+fn write_pack<P>(
+    db: &Db,
+    counts: Vec<output::Count>,
+    pack_dir: &Path,
+    object_hash: git_hash::Kind,
+    options: &Options,
+    progress: &mut P,
+    should_interrupt: &AtomicBool,
+) -> Result<Pack, Error>
+where
+    P: Progress,
+    P::SubProgress: 'static,
+{
+    use output::entry::iter_from_counts;
+
+    let num_objects = counts.len() as u32;
+    let mut entries = git_features::parallel::InOrderIter::from(iter_from_counts(
+        counts,
+        db.clone(),
+        progress.add_child("creating entries"),
+        iter_from_counts::Options {
+            thread_limit: options.thread_limit,
+            mode: match options.delta_window {
+                Some(window) => iter_from_counts::Mode::PackCopyAndDeltaCompression {
+                    window,
+                    depth: options.delta_depth,
+                },
+                None => iter_from_counts::Mode::PackCopyAndBaseObjects,
+            },
+            allow_thin_pack: false,
+            ..Default::default()
+        },
+    ));
+
+    let mut pack_file = git_tempfile::new(pack_dir, ContainingDirectory::Exists, AutoRemove::Tempfile)?;
+    let mut index_file = git_tempfile::new(pack_dir, ContainingDirectory::Exists, AutoRemove::Tempfile)?;
+    let mut write_progress = progress.add_child("writing pack");
+    write_progress.init(None, progress::bytes());
+    let mut pack_writer = output::bytes::FromEntriesIter::new(
+        entries.by_ref(),
+        &mut pack_file,
+        num_objects,
+        git_pack::data::Version::V2,
+        object_hash,
+    );
+    for written in pack_writer.by_ref() {
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+        write_progress.inc_by(written? as usize);
+    }
+    let checksum = pack_writer.digest().expect("iteration is done");
+    pack_writer
+        .write_index(
+            git_pack::index::Version::default(),
+            &mut index_file,
+            progress.add_child("writing index"),
+        )?
+        .expect("iteration is done");
+    drop(pack_writer);
+
+    let data_path = pack_dir.join(format!("pack-{}.pack", checksum));
+    let index_path = data_path.with_extension("idx");
+    pack_file.persist(&data_path)?;
+    index_file.persist(&index_path)?;
+    Ok(Pack {
+        data_path,
+        index_path,
+        bitmap_path: None,
+        num_objects,
+        checksum,
+    })
+}
+
+/// Return the amount of packs in `pack_sizes`, sorted by size in ascending order, that need to be combined into a new pack
+/// so that each pack is at least `factor` times larger than the next smaller one.
+///
+/// This is the same algorithm as the one used by `git repack --geometric`.
+fn geometric_split(pack_sizes: &[u32], factor: u32) -> usize {
+    let factor = u64::from(factor);
+    let mut split = pack_sizes.len().saturating_sub(1);
+    while split > 0 && u64::from(pack_sizes[split]) >= factor * u64::from(pack_sizes[split - 1]) {
+        split -= 1;
+    }
+    if split > 0 {
+        split += 1;
+    }
+    let mut total: u64 = pack_sizes[..split].iter().copied().map(u64::from).sum();
+    while split < pack_sizes.len() && u64::from(pack_sizes[split]) < factor * total {
+        total += u64::from(pack_sizes[split]);
+        split += 1;
+    }
+    split
+}
+
+fn existing_packs(pack_dir: &Path, object_hash: git_hash::Kind) -> Result<Vec<ExistingPack>, Error> {
+    let mut out = Vec::new();
+    for entry in std::fs::read_dir(pack_dir)? {
+        let index_path = entry?.path();
+        let is_pack_index = index_path.extension().map_or(false, |ext| ext == "idx")
+            && index_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| name.starts_with("pack-"));
+        if !is_pack_index {
+            continue;
+        }
+        out.push(ExistingPack {
+            index: git_pack::index::File::at(&index_path, object_hash)?,
+            keep: index_path.with_extension("keep").is_file(),
+            promisor: index_path.with_extension("promisor").is_file(),
+            index_path,
+        });
+    }
+    out.sort_by(|a, b| a.index_path.cmp(&b.index_path));
+    Ok(out)
+}
+
+/// Remove the pack with `index_path` along with all of its auxiliary files, starting with the index so it won't be picked up anymore.
+fn remove_pack(index_path: &Path) -> std::io::Result<()> {
+    for extension in ["idx", "pack", "bitmap", "rev", "mtimes", "promisor"] {
+        remove_if_present(&index_path.with_extension(extension))?;
+    }
+    Ok(())
+}
+
+/// Remove bitmaps and reverse indices of `multi-pack-index` files as they are only valid for one particular instance.
+fn remove_multi_index_extensions(pack_dir: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(pack_dir)? {
+        let path = entry?.path();
+        let is_extension = path.file_name().and_then(|name| name.to_str()).map_or(false, |name| {
+            name.starts_with("multi-pack-index-") && (name.ends_with(".bitmap") || name.ends_with(".rev"))
+        });
+        if is_extension {
+            remove_if_present(&path)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove_if_present(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::geometric_split;
+
+    #[test]
+    fn geometric_split_rolls_up_packs_violating_the_progression() {
+        assert_eq!(geometric_split(&[], 2), 0);
+        assert_eq!(geometric_split(&[5], 2), 0);
+        assert_eq!(
+            geometric_split(&[1, 2, 4, 8], 2),
+            0,
+            "a perfect progression is left alone"
+        );
+        assert_eq!(
+            geometric_split(&[1, 1, 4, 8], 2),
+            2,
+            "the two smallest packs are combined"
+        );
+        assert_eq!(
+            geometric_split(&[1, 1, 1, 3, 100], 2),
+            4,
+            "rolling up the smallest packs forces the next larger one to be rolled up as well"
+        );
+        assert_eq!(geometric_split(&[10, 10, 10], 3), 3);
+    }
+}
@@ -0,0 +1,79 @@
+use std::{io::Write, path::PathBuf};
+
+use git_hash::ObjectId;
+
+use crate::{bstr::ByteSlice, shallow};
+
+impl crate::Repository {
+    /// Return the path to the file listing the commits at the shallow boundary, which may or may not exist.
+    pub fn shallow_file(&self) -> PathBuf {
+        self.common_dir().join("shallow")
+    }
+
+    /// Return `true` if the repository is a shallow clone, i.e. if some of its commits are missing their parents.
+    pub fn is_shallow(&self) -> bool {
+        std::fs::metadata(self.shallow_file()).map_or(false, |m| m.is_file() && m.len() > 0)
+    }
+
+    /// Return the sorted list of commits whose parents are missing as the repository is a shallow clone, or `None` if
+    /// it isn't shallow.
+    pub fn shallow_commits(&self) -> Result<Option<Vec<ObjectId>>, shallow::open::Error> {
+        let path = self.shallow_file();
+        let buf = match std::fs::read(&path) {
+            Ok(buf) => buf,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(shallow::open::Error::ReadFile { path, source: err }),
+        };
+        let mut commits = buf
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let line = line.trim();
+                ObjectId::from_hex(line).map_err(|err| shallow::open::Error::DecodeHash {
+                    path: path.clone(),
+                    line: line.into(),
+                    source: err,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if commits.is_empty() {
+            return Ok(None);
+        }
+        commits.sort();
+        commits.dedup();
+        Ok(Some(commits))
+    }
+
+    /// Replace the list of commits at the shallow boundary with `commits`, or remove the shallow file if there are none
+    /// so the repository isn't shallow anymore.
+    ///
+    /// The file is locked for the duration of the operation.
+    pub fn write_shallow_commits(
+        &self,
+        commits: impl IntoIterator<Item = ObjectId>,
+    ) -> Result<(), shallow::write::Error> {
+        let mut commits: Vec<_> = commits.into_iter().collect();
+        commits.sort();
+        commits.dedup();
+
+        let path = self.shallow_file();
+        let mut lock = git_lock::File::acquire_to_update_resource(&path, git_lock::acquire::Fail::Immediately, None)?;
+        if commits.is_empty() {
+            match std::fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+            // Dropping the lock removes it without touching the resource.
+            return Ok(());
+        }
+        lock.with_mut(|file| {
+            for id in &commits {
+                writeln!(file, "{}", id)?;
+            }
+            Ok(())
+        })?;
+        lock.commit()?;
+        Ok(())
+    }
+}
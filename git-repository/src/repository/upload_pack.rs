@@ -0,0 +1,41 @@
+use std::sync::atomic::AtomicBool;
+
+use crate::upload_pack::{Error, Options, Outcome, Session};
+
+impl crate::Repository {
+    /// Serve a fetch or clone of this repository to a client that sends its requests through `read` and receives our responses
+    /// through `write`, similar to `git upload-pack`.
+    ///
+    /// `read` and `write` are positioned after the initial request of the client, which is read by
+    /// [`git_transport::server::accept()`] for `git` daemons, or is provided by SSH or HTTP frontends by other means,
+    /// and which determines the protocol version to use in `options`.
+    ///
+    /// Protocol version 2 supports the `ls-refs` and `fetch` commands with `shallow`, `filter` and `ref-in-want` support,
+    /// while version 1 serves the reference advertisement followed by a fetch.
+    /// Like `git upload-pack`, filters and wanted references must be enabled with `uploadpack.allowFilter` and
+    /// `uploadpack.allowRefInWant`, and version 1 clients may only want the objects our references point to unless
+    /// `uploadpack.allowTipSHA1InWant`, `uploadpack.allowReachableSHA1InWant` or `uploadpack.allowAnySHA1InWant` are set.
+    /// Interruptions are checked via `should_interrupt`.
+    pub fn upload_pack(
+        &self,
+        read: impl std::io::Read,
+        write: impl std::io::Write,
+        options: Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        let mut session = Session::new(self, read, write, options, should_interrupt)?;
+        let res = match options.version {
+            git_transport::Protocol::V1 => session.serve_v1(),
+            git_transport::Protocol::V2 => session.serve_v2(),
+        };
+        match res {
+            Ok(()) => Ok(session.outcome),
+            Err(err) => {
+                if err.is_caused_by_client() {
+                    session.send_error(&err).ok();
+                }
+                Err(err)
+            }
+        }
+    }
+}
@@ -37,6 +37,23 @@ impl crate::Repository {
         crate::ThreadSafeRepository::open_opts(self.common_dir(), self.options.clone()).map(Into::into)
     }
 
+    /// Open the repositories of all worktrees but ours, which includes the main worktree if this is a linked worktree.
+    ///
+    /// This is useful for operations that have to consider the private state of all worktrees, like their `HEAD`.
+    pub(crate) fn other_worktrees(&self) -> Result<Vec<crate::Repository>, crate::open::Error> {
+        let mut out = Vec::new();
+        if self.git_dir() != self.common_dir() {
+            out.push(self.main_repo()?);
+        }
+        for proxy in self.worktrees()? {
+            if proxy.git_dir() == self.git_dir() {
+                continue;
+            }
+            out.push(proxy.into_repo_with_possibly_inaccessible_worktree()?);
+        }
+        Ok(out)
+    }
+
     /// Return the currently set worktree if there is one, acting as platform providing a validated worktree base path.
     ///
     /// Note that there would be `None` if this repository is `bare` and the parent [`Repository`][crate::Repository] was instantiated without
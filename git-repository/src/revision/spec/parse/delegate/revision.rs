@@ -82,7 +82,7 @@ impl<'repo> delegate::Revision for Delegate<'repo> {
                                     self.refs[self.idx] = Some(ref_.clone());
                                     self.err.push(Error::AmbiguousRefAndObject {
                                         prefix,
-                                        reference: ref_,
+                                        reference: Box::new(ref_),
                                     });
                                     self.err.push(Error::ambiguous(candidates, prefix, self.repo));
                                     None
@@ -142,7 +142,7 @@ impl<'repo> delegate::Revision for Delegate<'repo> {
                         None => {
                             let available = platform.rev().ok().flatten().map_or(0, |it| it.count());
                             self.err.push(Error::RefLogEntryOutOfRange {
-                                reference: r.detach(),
+                                reference: Box::new(r.detach()),
                                 desired: no,
                                 available,
                             });
@@ -164,7 +164,7 @@ impl<'repo> delegate::Revision for Delegate<'repo> {
     fn nth_checked_out_branch(&mut self, branch_no: usize) -> Option<()> {
         self.unset_disambiguate_call();
         fn prior_checkouts_iter<'a>(
-            platform: &'a mut git_ref::file::log::iter::Platform<'static, '_>,
+            platform: &'a mut git_ref::store::reflog::Platform<'static, '_>,
         ) -> Result<impl Iterator<Item = (BString, ObjectId)> + 'a, Error> {
             match platform.rev().ok().flatten() {
                 Some(log) => Ok(log.filter_map(Result::ok).filter_map(|line| {
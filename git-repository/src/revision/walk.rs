@@ -46,6 +46,10 @@ impl<'repo> Platform<'repo> {
     ///
     /// It's highly recommended to set an [`object cache`][Repository::object_cache_size()] on the parent repo
     /// to greatly speed up performance if the returned id is supposed to be looked up right after.
+    ///
+    /// # Shallow Repositories
+    ///
+    /// The [shallow commits][Repository::shallow_commits()] of the repository are treated as if they had no parents.
     pub fn all(self) -> Result<revision::Walk<'repo>, git_traverse::commit::ancestors::Error> {
         let Platform {
             repo,
@@ -53,6 +57,10 @@ impl<'repo> Platform<'repo> {
             sorting,
             parents,
         } = self;
+        let shallow_commits = repo
+            .shallow_commits()
+            .map_err(|err| git_traverse::commit::ancestors::Error::ShallowCommits(err.into()))?
+            .unwrap_or_default();
         Ok(revision::Walk {
             repo,
             inner: Box::new(
@@ -62,7 +70,8 @@ impl<'repo> Platform<'repo> {
                     move |oid, buf| repo.objects.find_commit_iter(oid, buf),
                 )
                 .sorting(sorting)?
-                .parents(parents),
+                .parents(parents)
+                .shallow_commits(shallow_commits),
             ),
             is_shallow: None,
             error_on_missing_commit: false,
@@ -0,0 +1,39 @@
+//! Access and modification of the shallow boundary of a repository, as stored in the `shallow` file of its common directory.
+//!
+//! Each commit listed in that file is missing its parents, which is why traversals treat them as if they had none.
+
+///
+pub mod open {
+    use std::path::PathBuf;
+
+    use crate::bstr::BString;
+
+    /// The error returned by [`Repository::shallow_commits()`][crate::Repository::shallow_commits()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not read the shallow file at \"{}\"", path.display())]
+        ReadFile { path: PathBuf, source: std::io::Error },
+        #[error("The line {line:?} in the shallow file at \"{}\" isn't a valid object id", path.display())]
+        DecodeHash {
+            path: PathBuf,
+            line: BString,
+            source: git_hash::decode::Error,
+        },
+    }
+}
+
+///
+pub mod write {
+    /// The error returned by [`Repository::write_shallow_commits()`][crate::Repository::write_shallow_commits()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not lock the shallow file for writing")]
+        AcquireLock(#[from] git_lock::acquire::Error),
+        #[error("Could not write or remove the shallow file")]
+        Io(#[from] std::io::Error),
+        #[error("Could not move the written shallow file into place")]
+        Commit(#[from] git_lock::commit::Error<git_lock::File>),
+    }
+}
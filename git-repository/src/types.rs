@@ -138,6 +138,11 @@ pub struct Repository {
     /// A ref store with shared ownership (or the equivalent of it).
     pub refs: crate::RefStore,
     /// A way to access objects.
+    ///
+    /// With the `blocking-network-client` feature, objects of partial clones that are missing locally are fetched from
+    /// the promisor remote when looked up with `try_find()` and its variants, which then blocks on network IO. Objects
+    /// the remote doesn't have either, or that couldn't be fetched, cause an `Err` instead of `Ok(None)`.
+    /// Use `Repository::fetch_promised_objects()` to fetch many objects at once.
     pub objects: crate::OdbHandle,
 
     pub(crate) work_tree: Option<PathBuf>,
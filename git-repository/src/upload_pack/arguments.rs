@@ -0,0 +1,199 @@
+use git_hash::ObjectId;
+
+use crate::{
+    bstr::{BStr, BString, ByteSlice},
+    upload_pack::Error,
+};
+
+/// A filter to omit objects from the pack, as requested by partial clones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Filter {
+    /// Omit all blobs, `blob:none`.
+    BlobNone,
+    /// Omit all blobs larger than the given amount of bytes, `blob:limit=<n>[kmg]`.
+    BlobLimit(u64),
+    /// Omit all trees and blobs, `tree:0`.
+    TreeNone,
+}
+
+impl Filter {
+    #[allow(clippy::result_large_err)]
+    pub fn from_spec(spec: &BStr) -> Result<Self, Error> {
+        let unsupported = || Error::UnsupportedFilter { spec: spec.to_owned() };
+        Ok(match spec.as_bytes() {
+            b"blob:none" => Filter::BlobNone,
+            b"tree:0" => Filter::TreeNone,
+            limit => {
+                let limit = limit.strip_prefix(b"blob:limit=").ok_or_else(unsupported)?;
+                let (number, factor) = match limit.last().map(u8::to_ascii_lowercase) {
+                    Some(b'k') => (&limit[..limit.len() - 1], 1024),
+                    Some(b'm') => (&limit[..limit.len() - 1], 1024 * 1024),
+                    Some(b'g') => (&limit[..limit.len() - 1], 1024 * 1024 * 1024),
+                    _ => (limit, 1),
+                };
+                let number: u64 = number
+                    .to_str()
+                    .ok()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(unsupported)?;
+                Filter::BlobLimit(number.checked_mul(factor).ok_or_else(unsupported)?)
+            }
+        })
+    }
+}
+
+/// How common objects are acknowledged during V1 negotiation, if not only the first one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum MultiAck {
+    /// Acknowledge all common objects with `ACK <id> continue`, as requested with `multi_ack`.
+    Continue,
+    /// Acknowledge common objects with `ACK <id> common` and signal readiness with `ACK <id> ready`,
+    /// as requested with `multi_ack_detailed`.
+    Detailed,
+}
+
+/// The arguments of a fetch request, as sent with the `fetch` command in V2 or with the wants and haves in V1.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Arguments {
+    pub wants: Vec<ObjectId>,
+    /// Names of references to fetch, via `want-ref`.
+    pub want_refs: Vec<BString>,
+    pub haves: Vec<ObjectId>,
+    pub done: bool,
+    pub thin_pack: bool,
+    pub ofs_delta: bool,
+    pub no_progress: bool,
+    pub include_tag: bool,
+    /// The commits that are shallow on the client side.
+    pub shallow: Vec<ObjectId>,
+    pub deepen: Option<u32>,
+    pub deepen_relative: bool,
+    /// The time in seconds since epoch of the oldest commit to send.
+    pub deepen_since: Option<u32>,
+    /// Names of references whose history shouldn't be sent.
+    pub deepen_not: Vec<BString>,
+    pub filter: Option<Filter>,
+    /// The maximum size of side-band data in V1, or `None` if the pack should be sent without side-band.
+    pub side_band: Option<usize>,
+    pub multi_ack: Option<MultiAck>,
+    /// If `true`, the V1 client won't send `done` once we signalled that we are ready.
+    pub no_done: bool,
+}
+
+impl Arguments {
+    /// Return `true` if the client asked to deepen or shorten its history.
+    pub fn is_deepening(&self) -> bool {
+        self.deepen.is_some() || self.deepen_since.is_some() || !self.deepen_not.is_empty()
+    }
+
+    /// Parse a single argument `line`, which is a feature in V2, or a line following the wants in V1.
+    #[allow(clippy::result_large_err)]
+    pub fn parse_line(&mut self, line: &BStr) -> Result<(), Error> {
+        let malformed = || Error::MalformedLine { line: line.to_owned() };
+        let id = |hex: &[u8]| ObjectId::from_hex(hex).map_err(|_| malformed());
+        let (name, value) = match line.split_once_str(b" ") {
+            Some((name, value)) => (name, Some(value)),
+            None => (line.as_bytes(), None),
+        };
+        match (name, value) {
+            (b"want", Some(hex)) => self.wants.push(id(hex)?),
+            (b"want-ref", Some(name)) => self.want_refs.push(name.into()),
+            (b"have", Some(hex)) => self.haves.push(id(hex)?),
+            (b"shallow", Some(hex)) => self.shallow.push(id(hex)?),
+            (b"deepen", Some(depth)) => {
+                self.deepen = Some(
+                    depth
+                        .to_str()
+                        .ok()
+                        .and_then(|d| d.parse().ok())
+                        .filter(|d| *d > 0)
+                        .ok_or_else(malformed)?,
+                )
+            }
+            (b"deepen-since", Some(time)) => {
+                self.deepen_since = Some(time.to_str().ok().and_then(|t| t.parse().ok()).ok_or_else(malformed)?)
+            }
+            (b"deepen-not", Some(name)) => self.deepen_not.push(name.into()),
+            (b"filter", Some(spec)) => self.filter = Some(Filter::from_spec(spec.as_bstr())?),
+            (b"done", None) => self.done = true,
+            (name, None) => self.parse_feature(name),
+            _ => return Err(malformed()),
+        }
+        Ok(())
+    }
+
+    /// Parse a single capability, as they are sent with the first `want` line in V1, or as argument in V2.
+    pub fn parse_feature(&mut self, name: &[u8]) {
+        match name {
+            b"thin-pack" => self.thin_pack = true,
+            b"ofs-delta" => self.ofs_delta = true,
+            b"no-progress" => self.no_progress = true,
+            b"include-tag" => self.include_tag = true,
+            b"deepen-relative" => self.deepen_relative = true,
+            b"side-band" => self.side_band = self.side_band.max(Some(995)),
+            b"side-band-64k" => self.side_band = Some(65515),
+            b"multi_ack" => self.multi_ack = self.multi_ack.max(Some(MultiAck::Continue)),
+            b"multi_ack_detailed" => self.multi_ack = Some(MultiAck::Detailed),
+            b"no-done" => self.no_done = true,
+            // Everything else, like `agent=…`, is only informational or not supported, like `sideband-all` which we don't advertise.
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arguments, Filter};
+
+    #[test]
+    fn filters() {
+        for (spec, expected) in [
+            ("blob:none", Filter::BlobNone),
+            ("tree:0", Filter::TreeNone),
+            ("blob:limit=42", Filter::BlobLimit(42)),
+            ("blob:limit=2k", Filter::BlobLimit(2048)),
+            ("blob:limit=1M", Filter::BlobLimit(1024 * 1024)),
+        ] {
+            assert_eq!(Filter::from_spec(spec.into()).expect("valid"), expected);
+        }
+        for spec in [
+            "tree:1",
+            "blob:limit=",
+            "blob:limit=k",
+            "sparse:oid=HEAD",
+            "combine:blob:none+tree:0",
+        ] {
+            assert!(Filter::from_spec(spec.into()).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn lines() {
+        let mut args = Arguments::default();
+        for line in [
+            "want 8481a5730022eb19bcb0d2a17bebb31366805036",
+            "have 8481a5730022eb19bcb0d2a17bebb31366805036",
+            "deepen 3",
+            "deepen-relative",
+            "deepen-not refs/heads/main",
+            "deepen-since 1234",
+            "thin-pack",
+            "side-band",
+            "side-band-64k",
+            "done",
+        ] {
+            args.parse_line(line.into()).expect("valid");
+        }
+        assert_eq!(args.wants.len(), 1);
+        assert_eq!(args.haves.len(), 1);
+        assert_eq!(args.deepen, Some(3));
+        assert!(args.deepen_relative && args.thin_pack && args.done && args.is_deepening());
+        assert_eq!(args.side_band, Some(65515));
+        assert_eq!(args.deepen_since, Some(1234));
+        assert_eq!(args.deepen_not, vec!["refs/heads/main"]);
+
+        for line in ["want 123", "deepen 0", "deepen-since yesterday", "unknown value"] {
+            assert!(Arguments::default().parse_line(line.into()).is_err(), "{}", line);
+        }
+    }
+}
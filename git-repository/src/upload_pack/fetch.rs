@@ -0,0 +1,217 @@
+use std::{collections::HashSet, io};
+
+use git_hash::ObjectId;
+use git_pack::{Find, FindExt};
+use git_transport::packetline::Channel;
+
+use crate::{
+    bstr::{BString, ByteSlice},
+    upload_pack::{
+        arguments::Arguments,
+        pack,
+        refs::{peel, Ref},
+        session::{Band, Session},
+        shallow, Error,
+    },
+};
+
+/// The maximum amount of data in a side-band packet line, which is the maximum packet line length minus the band.
+pub(crate) const MAX_BAND_DATA_LEN: usize = 65515;
+
+/// Everything the client asked for, resolved against our repository.
+pub(crate) struct Wants {
+    /// All wanted objects, including those named by `want-ref`.
+    pub ids: Vec<ObjectId>,
+    /// The commits the wanted objects peel to.
+    pub commits: Vec<ObjectId>,
+    /// The references requested by `want-ref` along with the object they point to.
+    pub refs: Vec<(BString, ObjectId)>,
+}
+
+impl<'a, R, W> Session<'a, R, W>
+where
+    R: io::Read,
+    W: io::Write,
+{
+    /// Validate the wants in `args` and resolve all wanted references.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn resolve_wants(&self, args: &Arguments, refs: &[Ref]) -> Result<Wants, Error> {
+        if args.filter.is_some() && !self.config.allow_filter {
+            return Err(Error::DisabledCapability { name: "filter" });
+        }
+        if !args.want_refs.is_empty() && !self.config.allow_ref_in_want {
+            return Err(Error::DisabledCapability { name: "ref-in-want" });
+        }
+        let mut ids = Vec::with_capacity(args.wants.len());
+        for id in &args.wants {
+            if !self.db.contains(id) {
+                return Err(Error::UnknownObject { id: *id });
+            }
+            ids.push(*id);
+        }
+        if self.options.version == git_transport::Protocol::V1 {
+            self.check_v1_wants(&ids, refs)?;
+        }
+        let mut wanted_refs = Vec::new();
+        for name in &args.want_refs {
+            let id = refs
+                .iter()
+                .find(|r| &r.name == name)
+                .and_then(|r| r.target)
+                .ok_or_else(|| Error::UnknownReference { name: name.clone() })?;
+            ids.push(id);
+            wanted_refs.push((name.clone(), id));
+        }
+        let mut buf = Vec::new();
+        let mut commits = Vec::new();
+        for id in &ids {
+            let peeled = peel(&self.db, *id, &mut buf)?;
+            if peeled.kind == git_object::Kind::Commit {
+                commits.push(peeled.id);
+            }
+        }
+        Ok(Wants {
+            ids,
+            commits,
+            refs: wanted_refs,
+        })
+    }
+
+    /// Fail if a V1 client wants objects other than those our references point to, unless our configuration allows it.
+    ///
+    /// Like `git upload-pack`, commits reachable from our references may also be wanted in stateless mode, as references
+    /// may have changed since they were advertised in a previous request.
+    #[allow(clippy::result_large_err)]
+    fn check_v1_wants(&self, wants: &[ObjectId], refs: &[Ref]) -> Result<(), Error> {
+        if self.config.allow_any_wants {
+            return Ok(());
+        }
+        let tips: HashSet<_> = refs.iter().filter_map(|r| r.target).collect();
+        let mut non_tips: HashSet<_> = wants.iter().filter(|id| !tips.contains(*id)).copied().collect();
+        if non_tips.is_empty() {
+            return Ok(());
+        }
+        if self.config.allow_reachable_wants || self.options.stateless_rpc {
+            let mut buf = Vec::new();
+            let mut tip_commits = Vec::new();
+            for id in &tips {
+                let peeled = peel(&self.db, *id, &mut buf)?;
+                if peeled.kind == git_object::Kind::Commit {
+                    tip_commits.push(peeled.id);
+                }
+            }
+            for commit in git_traverse::commit::Ancestors::new(
+                tip_commits,
+                git_traverse::commit::ancestors::State::default(),
+                |oid, buf| self.db.find_commit_iter(oid, buf).map(|t| t.0),
+            ) {
+                self.check_interrupt()?;
+                non_tips.remove(&commit?);
+                if non_tips.is_empty() {
+                    return Ok(());
+                }
+            }
+        }
+        let id = wants
+            .iter()
+            .find(|id| non_tips.contains(*id))
+            .expect("at least one want is left");
+        Err(Error::UnadvertisedObject { id: *id })
+    }
+
+    /// Return `true` if we have `id`, and can thus consider it common with the client.
+    pub(crate) fn is_common(&self, id: &ObjectId) -> bool {
+        self.db.contains(id)
+    }
+
+    /// Remember that the client has `id`, which must be common with us, for use in `negotiation`.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn add_common(&self, negotiation: &mut pack::Negotiation, id: ObjectId) -> Result<(), Error> {
+        negotiation.add_common(&self.db, id, &mut Vec::new())
+    }
+
+    /// Return `true` if the negotiation can end as the client has enough objects in common with us.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn is_ready(&self, negotiation: &mut pack::Negotiation, wants: &Wants) -> Result<bool, Error> {
+        negotiation.all_wants_have_common_ancestor(&self.db, &wants.commits, self.should_interrupt)
+    }
+
+    /// Determine the shallow history to send if the client asked to deepen or shorten its history.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn shallow(&self, args: &Arguments, wants: &Wants) -> Result<Option<shallow::Shallow>, Error> {
+        if !args.is_deepening() {
+            return Ok(None);
+        }
+        let mut excluded_tips = Vec::new();
+        for name in &args.deepen_not {
+            let reference = self
+                .repo
+                .refs
+                .try_find(name.as_bstr())?
+                .ok_or_else(|| Error::UnknownReference { name: name.clone() })?;
+            let mut buf = Vec::new();
+            if let git_ref::Target::Peeled(id) = reference.target {
+                excluded_tips.push(peel(&self.db, id, &mut buf)?.id);
+            }
+        }
+        let excluded: HashSet<_> = git_traverse::commit::Ancestors::new(
+            excluded_tips,
+            git_traverse::commit::ancestors::State::default(),
+            |oid, buf| self.db.find_commit_iter(oid, buf).map(|t| t.0),
+        )
+        .collect::<Result<_, _>>()?;
+        shallow::compute(&self.db, &wants.commits, args, &excluded).map(Some)
+    }
+
+    /// Send a pack with everything the client needs to have `wants` when it has `common`, on the side-band if `max_band_data_len`
+    /// is set.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn send_pack(
+        &mut self,
+        args: &Arguments,
+        refs: &[Ref],
+        wants: &Wants,
+        common: &[ObjectId],
+        shallow: Option<&shallow::Shallow>,
+        max_band_data_len: Option<usize>,
+    ) -> Result<(), Error> {
+        self.check_interrupt()?;
+        let plan = pack::plan(&self.db, refs, args, &wants.ids, common, shallow, self.should_interrupt)?;
+        let object_hash = self.repo.object_hash();
+        let num_objects_from_bitmap = plan.num_objects_from_bitmap();
+        let num_objects = match max_band_data_len {
+            Some(max_data_len) => {
+                self.write_progress(
+                    args.no_progress,
+                    &format!("Enumerating objects: {}, done.\n", plan.num_objects()),
+                )?;
+                pack::write(
+                    &self.db,
+                    plan,
+                    args.thin_pack,
+                    args.ofs_delta,
+                    object_hash,
+                    Band {
+                        out: &mut self.out,
+                        channel: Channel::Data,
+                        max_data_len,
+                    },
+                    self.should_interrupt,
+                )?
+            }
+            None => pack::write(
+                &self.db,
+                plan,
+                args.thin_pack,
+                args.ofs_delta,
+                object_hash,
+                &mut self.out,
+                self.should_interrupt,
+            )?,
+        };
+        self.outcome.num_packs += 1;
+        self.outcome.num_objects += num_objects as u64;
+        self.outcome.num_objects_from_bitmap += num_objects_from_bitmap as u64;
+        Ok(())
+    }
+}
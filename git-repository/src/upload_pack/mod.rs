@@ -0,0 +1,128 @@
+//! Serve fetches and clones from a repository by speaking the server side of `git upload-pack`.
+//!
+//! See [`Repository::upload_pack()`][crate::Repository::upload_pack()] for the entry point.
+use crate::bstr::BString;
+
+mod arguments;
+mod fetch;
+mod pack;
+mod refs;
+mod session;
+mod shallow;
+mod v1;
+mod v2;
+
+pub(crate) use session::Session;
+
+/// Options for use in [`Repository::upload_pack()`][crate::Repository::upload_pack()].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// The version of the protocol to speak, typically the [desired version][git_transport::server::Request::desired_version]
+    /// of the client.
+    pub version: git_transport::Protocol,
+    /// If `true`, serve a single request and return, similar to `git upload-pack --stateless-rpc`.
+    ///
+    /// This is what HTTP frontends need, as each HTTP request carries exactly one request of the client.
+    /// Note that the advertisement isn't sent in this mode, unless [`advertise_refs`][Options::advertise_refs] is set.
+    pub stateless_rpc: bool,
+    /// If `true`, only send the advertisement and return, similar to `git upload-pack --advertise-refs`.
+    ///
+    /// For protocol version 1 these are the references, for version 2 the capabilities of the server.
+    pub advertise_refs: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            version: git_transport::Protocol::V2,
+            stateless_rpc: false,
+            advertise_refs: false,
+        }
+    }
+}
+
+/// The outcome of [`Repository::upload_pack()`][crate::Repository::upload_pack()].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outcome {
+    /// The amount of packs that were sent to the client.
+    pub num_packs: usize,
+    /// The amount of objects in all packs that were sent to the client.
+    pub num_objects: u64,
+    /// The amount of objects that were counted with the help of a reachability bitmap instead of traversing the history.
+    pub num_objects_from_bitmap: u64,
+}
+
+/// The error returned by [`Repository::upload_pack()`][crate::Repository::upload_pack()].
+///
+/// Errors caused by the client are also sent to the client as `ERR` packet line before they are returned.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    PacketlineDecode(#[from] git_transport::packetline::decode::Error),
+    #[error("The client closed the connection in the middle of a request")]
+    UnexpectedEof,
+    #[error("The line {line:?} sent by the client is malformed or unexpected")]
+    MalformedLine { line: BString },
+    #[error("The command {name:?} is not supported")]
+    UnknownCommand { name: BString },
+    #[error("The object {id} the client wants doesn't exist")]
+    UnknownObject { id: git_hash::ObjectId },
+    #[error("The object {id} the client wants is not one of our references")]
+    UnadvertisedObject { id: git_hash::ObjectId },
+    #[error("The client used {name:?} which isn't enabled")]
+    DisabledCapability { name: &'static str },
+    #[error("The reference {name:?} the client wants doesn't exist")]
+    UnknownReference { name: BString },
+    #[error("The filter {spec:?} is not supported")]
+    UnsupportedFilter { spec: BString },
+    #[error("The client asked for the object format {name:?}, but we use {actual}")]
+    ObjectFormatMismatch { name: BString, actual: git_hash::Kind },
+    #[error("Could not interpret the uploadpack.* configuration")]
+    Configuration(#[from] git_config::value::Error),
+    #[error(transparent)]
+    IterReferences(#[from] git_ref::store::iter::Error),
+    #[error(transparent)]
+    FindReference(#[from] git_ref::store::find::Error),
+    #[error(transparent)]
+    FindObject(#[from] git_pack::find::existing::Error<git_odb::store::find::Error>),
+    #[error(transparent)]
+    DecodeObject(#[from] git_object::decode::Error),
+    #[error(transparent)]
+    TraverseCommits(#[from] git_traverse::commit::ancestors::Error),
+    #[error(transparent)]
+    CountObjects(
+        #[from]
+        git_pack::data::output::count::objects::Error<
+            git_pack::find::existing::Error<git_odb::store::find::Error>,
+            std::convert::Infallible,
+        >,
+    ),
+    #[error(transparent)]
+    TraverseWithBitmap(#[from] git_pack::bitmap::reachable::Error<git_odb::store::find::Error>),
+    #[error(transparent)]
+    CreateEntries(#[from] git_pack::data::output::entry::iter_from_counts::Error<git_odb::store::find::Error>),
+    #[error(transparent)]
+    CreateEntry(#[from] git_pack::data::output::entry::Error),
+    #[error("Interrupted")]
+    Interrupted,
+}
+
+impl Error {
+    /// Return `true` if this error was caused by the client, and should thus be reported to it.
+    pub(crate) fn is_caused_by_client(&self) -> bool {
+        matches!(
+            self,
+            Error::MalformedLine { .. }
+                | Error::UnknownCommand { .. }
+                | Error::UnknownObject { .. }
+                | Error::UnadvertisedObject { .. }
+                | Error::DisabledCapability { .. }
+                | Error::UnknownReference { .. }
+                | Error::UnsupportedFilter { .. }
+                | Error::ObjectFormatMismatch { .. }
+        )
+    }
+}
@@ -0,0 +1,482 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    convert::Infallible,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use git_features::progress;
+use git_hash::ObjectId;
+use git_pack::{data::output, FindExt};
+
+use crate::upload_pack::{
+    arguments::{Arguments, Filter},
+    refs::{peel, Ref},
+    session::Db,
+    shallow::Shallow,
+    Error,
+};
+
+/// The objects to send to the client, along with what we know the client has.
+pub(crate) struct Plan {
+    counts: Vec<output::Count>,
+    /// Objects the client has, which may serve as delta bases in thin packs.
+    remote_has: HashSet<ObjectId>,
+    /// The amount of objects that were counted with the help of a reachability bitmap.
+    num_objects_from_bitmap: usize,
+}
+
+impl Plan {
+    pub fn num_objects(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn num_objects_from_bitmap(&self) -> usize {
+        self.num_objects_from_bitmap
+    }
+}
+
+/// Visit all commits reachable from `tips` in breadth-first order, and ask `filter` for each commit if it should be yielded.
+/// Parents are only visited for commits for which `follow_parents` returns `true`.
+#[allow(clippy::result_large_err)]
+fn walk(
+    db: &Db,
+    tips: impl IntoIterator<Item = ObjectId>,
+    mut filter: impl FnMut(&ObjectId) -> bool,
+    mut follow_parents: impl FnMut(&ObjectId) -> bool,
+    should_interrupt: &AtomicBool,
+) -> Result<Vec<ObjectId>, Error> {
+    let mut buf = Vec::new();
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    let mut queue: VecDeque<_> = tips.into_iter().collect();
+    while let Some(id) = queue.pop_front() {
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+        if !seen.insert(id) || !filter(&id) {
+            continue;
+        }
+        if follow_parents(&id) {
+            queue.extend(git_object::CommitRefIter::from_bytes(db.find(id, &mut buf)?.0.data).parent_ids());
+        }
+        out.push(id);
+    }
+    Ok(out)
+}
+
+/// The state of a negotiation, which remembers which commits are known to lead to commits the client has across rounds.
+#[derive(Default)]
+pub(crate) struct Negotiation {
+    /// Commits the client has, along with commits that are known to have one of them in their history.
+    reaches_common: HashSet<ObjectId>,
+    /// The commit time of the oldest commit the client has. Commits older than that can't have any of them in their history.
+    oldest_common: Option<u32>,
+}
+
+impl Negotiation {
+    /// Remember that the client has `id`, which must be an object we have.
+    #[allow(clippy::result_large_err)]
+    pub fn add_common(&mut self, db: &Db, id: ObjectId, buf: &mut Vec<u8>) -> Result<(), Error> {
+        let peeled = peel(db, id, buf)?;
+        if peeled.kind != git_object::Kind::Commit || !self.reaches_common.insert(peeled.id) {
+            return Ok(());
+        }
+        let time = commit_time(db, &peeled.id, buf)?;
+        self.oldest_common = Some(self.oldest_common.map_or(time, |oldest| oldest.min(time)));
+        Ok(())
+    }
+
+    /// Return `true` if each of the `want_commits` has at least one of the common commits in its history, which is when `git`
+    /// considers the negotiation complete.
+    ///
+    /// Like `git`, we don't traverse into commits older than the oldest common commit, and remember all commits that lead to
+    /// common ones so that later rounds don't have to traverse them again.
+    #[allow(clippy::result_large_err)]
+    pub fn all_wants_have_common_ancestor(
+        &mut self,
+        db: &Db,
+        want_commits: &[ObjectId],
+        should_interrupt: &AtomicBool,
+    ) -> Result<bool, Error> {
+        let oldest_common = match self.oldest_common {
+            Some(time) => time,
+            None => return Ok(false),
+        };
+        let mut buf = Vec::new();
+        for want in want_commits {
+            if self.reaches_common.contains(want) {
+                continue;
+            }
+            let mut seen = HashSet::new();
+            seen.insert(*want);
+            let mut stack = vec![(*want, parent_ids(db, want, &mut buf)?)];
+            let mut found = false;
+            while let Some((_, parents)) = stack.last_mut() {
+                if should_interrupt.load(Ordering::Relaxed) {
+                    return Err(Error::Interrupted);
+                }
+                let parent = match parents.pop() {
+                    Some(parent) => parent,
+                    None => {
+                        stack.pop();
+                        continue;
+                    }
+                };
+                if self.reaches_common.contains(&parent) {
+                    found = true;
+                    break;
+                }
+                if !seen.insert(parent) || commit_time(db, &parent, &mut buf)? < oldest_common {
+                    continue;
+                }
+                let parents = parent_ids(db, &parent, &mut buf)?;
+                stack.push((parent, parents));
+            }
+            if !found {
+                return Ok(false);
+            }
+            self.reaches_common.extend(stack.into_iter().map(|(id, _)| id));
+        }
+        Ok(true)
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn parent_ids(db: &Db, id: &ObjectId, buf: &mut Vec<u8>) -> Result<Vec<ObjectId>, Error> {
+    Ok(git_object::CommitRefIter::from_bytes(db.find(id, buf)?.0.data)
+        .parent_ids()
+        .collect())
+}
+
+#[allow(clippy::result_large_err)]
+fn commit_time(db: &Db, id: &ObjectId, buf: &mut Vec<u8>) -> Result<u32, Error> {
+    Ok(git_object::CommitRefIter::from_bytes(db.find(id, buf)?.0.data)
+        .committer()?
+        .time
+        .seconds_since_unix_epoch)
+}
+
+/// Determine which objects to send to satisfy `wants` if the client has all objects reachable from `common`,
+/// taking the `shallow` history and the filter in `args` into account.
+#[allow(clippy::result_large_err)]
+pub(crate) fn plan(
+    db: &Db,
+    refs: &[Ref],
+    args: &Arguments,
+    wants: &[ObjectId],
+    common: &[ObjectId],
+    shallow: Option<&Shallow>,
+    should_interrupt: &AtomicBool,
+) -> Result<Plan, Error> {
+    let mut buf = Vec::new();
+    let mut remote_has = HashSet::new();
+    let mut remote_commit_tips = Vec::new();
+    for id in common {
+        let peeled = peel(db, *id, &mut buf)?;
+        remote_has.extend(peeled.tags);
+        match peeled.kind {
+            git_object::Kind::Commit => remote_commit_tips.push(peeled.id),
+            _ => {
+                remote_has.insert(peeled.id);
+            }
+        }
+    }
+
+    let (mut as_is, mut tree_contents, mut commit_tips) = (Vec::new(), Vec::new(), Vec::new());
+    for id in wants {
+        let peeled = peel(db, *id, &mut buf)?;
+        as_is.extend(peeled.tags);
+        match peeled.kind {
+            git_object::Kind::Commit => commit_tips.push(peeled.id),
+            git_object::Kind::Blob => as_is.push(peeled.id),
+            git_object::Kind::Tree => tree_contents.push(peeled.id),
+            git_object::Kind::Tag => unreachable!("tags were peeled"),
+        }
+    }
+    let explicitly_wanted: HashSet<_> = as_is.iter().chain(tree_contents.iter()).copied().collect();
+
+    // Shallow histories on either side are cut off, which can't be expressed with bitmaps.
+    let bitmap = if shallow.is_none() && args.shallow.is_empty() {
+        PackWithBitmap::find(db)
+    } else {
+        None
+    };
+    let counted_with_bitmap = match &bitmap {
+        Some(bitmap) => bitmap.count(db, wants, common, should_interrupt)?,
+        None => None,
+    };
+    let mut num_objects_from_bitmap = 0;
+    let mut counts = match counted_with_bitmap {
+        Some((counts, client_has)) => {
+            remote_has.extend(client_has);
+            num_objects_from_bitmap = counts.len();
+            counts
+        }
+        None => count_by_walking(
+            db,
+            remote_commit_tips,
+            commit_tips,
+            as_is,
+            tree_contents,
+            &args.shallow,
+            shallow,
+            &mut remote_has,
+            should_interrupt,
+        )?,
+    };
+    let mut seen = HashSet::new();
+    counts.retain(|count| !remote_has.contains(&count.id) && seen.insert(count.id));
+
+    if args.include_tag {
+        for reference in refs {
+            if !reference.name.starts_with(b"refs/tags/") {
+                continue;
+            }
+            if let (Some(target), Some(peeled)) = (reference.target, reference.peeled) {
+                if seen.contains(&peeled) {
+                    for tag in peel(db, target, &mut buf)?.tags {
+                        if !remote_has.contains(&tag) && seen.insert(tag) {
+                            counts.push(output::Count::from_data(tag, None));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(filter) = args.filter {
+        let mut retained = Vec::with_capacity(counts.len());
+        for count in counts {
+            if explicitly_wanted.contains(&count.id) {
+                retained.push(count);
+                continue;
+            }
+            let object = db.find(count.id, &mut buf)?.0;
+            let keep = match (filter, object.kind) {
+                (Filter::BlobNone | Filter::TreeNone, git_object::Kind::Blob) => false,
+                (Filter::BlobLimit(limit), git_object::Kind::Blob) => (object.data.len() as u64) < limit,
+                (Filter::TreeNone, git_object::Kind::Tree) => false,
+                _ => true,
+            };
+            if keep {
+                retained.push(count);
+            }
+        }
+        counts = retained;
+    }
+
+    Ok(Plan {
+        counts,
+        remote_has,
+        num_objects_from_bitmap,
+    })
+}
+
+/// Count all objects reachable from `commit_tips` and their history which aren't reachable from `remote_commit_tips`, along with
+/// `as_is` objects and the contents of `tree_contents`, and add what the client has to `remote_has`.
+/// The parents of `client_shallow` commits are assumed to be missing on the client side.
+#[allow(clippy::result_large_err, clippy::too_many_arguments)]
+fn count_by_walking(
+    db: &Db,
+    remote_commit_tips: Vec<ObjectId>,
+    mut commit_tips: Vec<ObjectId>,
+    as_is: Vec<ObjectId>,
+    tree_contents: Vec<ObjectId>,
+    client_shallow: &[ObjectId],
+    shallow: Option<&Shallow>,
+    remote_has: &mut HashSet<ObjectId>,
+    should_interrupt: &AtomicBool,
+) -> Result<Vec<output::Count>, Error> {
+    let mut buf = Vec::new();
+    // The client doesn't have the parents of its shallow commits.
+    let client_shallow: HashSet<_> = client_shallow.iter().copied().collect();
+    let have_commits: HashSet<_> = walk(
+        db,
+        remote_commit_tips,
+        |_| true,
+        |id| !client_shallow.contains(id),
+        should_interrupt,
+    )?
+    .into_iter()
+    .collect();
+    if let Some(shallow) = shallow {
+        commit_tips.extend(shallow.unshallowed_parents(db, &mut buf)?);
+    }
+    let commits = walk(
+        db,
+        commit_tips,
+        |id| !have_commits.contains(id) && shallow.map_or(true, |s| s.kept.contains(id)),
+        |id| shallow.map_or(true, |s| !s.boundary.contains(id)),
+        should_interrupt,
+    )?;
+    let mut boundary = HashSet::new();
+    for commit in &commits {
+        if shallow.map_or(false, |s| s.boundary.contains(commit)) {
+            continue;
+        }
+        boundary.extend(
+            git_object::CommitRefIter::from_bytes(db.find(commit, &mut buf)?.0.data)
+                .parent_ids()
+                .filter(|id| have_commits.contains(id)),
+        );
+    }
+    let (shallow_commits, commits): (Vec<_>, Vec<_>) = commits
+        .into_iter()
+        .partition(|id| shallow.map_or(false, |s| s.boundary.contains(id)));
+
+    let count = |ids: Vec<ObjectId>, expansion| -> Result<Vec<output::Count>, Error> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(output::count::objects_unthreaded(
+            db.clone(),
+            ids.into_iter().map(Ok::<_, Infallible>),
+            progress::Discard,
+            should_interrupt,
+            expansion,
+            None,
+        )?
+        .0)
+    };
+    use output::count::objects::ObjectExpansion;
+    // The client has everything reachable from the commits our commits are based on, which may serve as delta bases.
+    remote_has.extend(
+        count(boundary.into_iter().collect(), ObjectExpansion::TreeContents)?
+            .into_iter()
+            .map(|count| count.id),
+    );
+    let mut counts = count(commits, ObjectExpansion::TreeAdditionsComparedToAncestor)?;
+    // The parents of shallow commits aren't sent, so there is nothing to compare them to.
+    counts.extend(count(shallow_commits, ObjectExpansion::TreeContents)?);
+    counts.extend(count(as_is, ObjectExpansion::AsIs)?);
+    counts.extend(count(tree_contents, ObjectExpansion::TreeContents)?);
+    remote_has.extend(have_commits);
+    Ok(counts)
+}
+
+/// The objects to send along with all objects the client has.
+type CountsAndClientHas = (Vec<output::Count>, HashSet<ObjectId>);
+
+/// A pack index along with the reachability bitmap written for it.
+struct PackWithBitmap {
+    index: git_pack::index::File,
+    bitmap: git_pack::bitmap::File,
+}
+
+impl PackWithBitmap {
+    /// Load the first pack in the object database of `db` that has a reachability bitmap, or `None` if there is none.
+    fn find(db: &Db) -> Option<Self> {
+        let object_hash = db.store_ref().object_hash();
+        let mut paths = std::fs::read_dir(db.store_ref().path().join("pack"))
+            .ok()?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map_or(false, |ext| ext == "bitmap"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.into_iter().find_map(|path| {
+            let bitmap = git_pack::bitmap::File::at(&path, object_hash).ok()?;
+            let index = git_pack::index::File::at(path.with_extension("idx"), object_hash).ok()?;
+            (bitmap.index_checksum() == index.pack_checksum()).then(|| PackWithBitmap { index, bitmap })
+        })
+    }
+
+    /// Count all objects reachable from `wants` that aren't reachable from `common`, and return them along with the
+    /// objects reachable from `common`, or `None` if the bitmap doesn't cover all of them.
+    #[allow(clippy::result_large_err)]
+    fn count(
+        &self,
+        db: &Db,
+        wants: &[ObjectId],
+        common: &[ObjectId],
+        should_interrupt: &AtomicBool,
+    ) -> Result<Option<CountsAndClientHas>, Error> {
+        let order = git_pack::bitmap::PackOrder::from_index(&self.index);
+        let client_has = match output::count::reachable_from_bitmap(&self.bitmap, &order, common, db, should_interrupt)?
+        {
+            Some((counts, _)) => counts.into_iter().map(|count| count.id).collect::<HashSet<_>>(),
+            None => return Ok(None),
+        };
+        Ok(
+            output::count::reachable_from_bitmap(&self.bitmap, &order, wants, db, should_interrupt)?.map(
+                |(mut counts, _)| {
+                    counts.retain(|count| !client_has.contains(&count.id));
+                    (counts, client_has)
+                },
+            ),
+        )
+    }
+}
+
+/// Write the pack for `plan` to `out` and return the amount of objects in it.
+///
+/// If `thin_pack` is `true`, objects may be deltas against objects the client has. If `ofs_delta` is `false`,
+/// deltas against objects in the same pack refer to their base by id instead of by offset.
+#[allow(clippy::result_large_err)]
+pub(crate) fn write(
+    db: &Db,
+    plan: Plan,
+    thin_pack: bool,
+    ofs_delta: bool,
+    object_hash: git_hash::Kind,
+    mut out: impl std::io::Write,
+    should_interrupt: &AtomicBool,
+) -> Result<u32, Error> {
+    use output::entry::iter_from_counts;
+    let Plan {
+        counts,
+        remote_has,
+        num_objects_from_bitmap: _,
+    } = plan;
+    let num_objects = counts.len() as u32;
+    // Deltas against objects in the same pack refer to the index of their base, so keep the ids of all objects in pack order.
+    let ids: Vec<_> = if ofs_delta {
+        Vec::new()
+    } else {
+        counts.iter().map(|count| count.id).collect()
+    };
+    let mut buf = Vec::new();
+    let entries = git_features::parallel::InOrderIter::from(iter_from_counts(
+        counts,
+        db.clone(),
+        progress::Discard,
+        iter_from_counts::Options {
+            mode: iter_from_counts::Mode::PackCopyAndBaseObjects,
+            allow_thin_pack: thin_pack,
+            ..Default::default()
+        },
+    ))
+    .map(|entries| {
+        let mut entries = entries?;
+        for entry in &mut entries {
+            match entry.kind {
+                // Deltas copied from our packs may use bases the client doesn't have, which have to be sent as whole objects instead.
+                output::entry::Kind::DeltaOid { id } if !remote_has.contains(&id) => {
+                    let (object, _location) = db.find(entry.id, &mut buf)?;
+                    *entry = output::Entry::from_data(&output::Count::from_data(entry.id, None), &object)?;
+                }
+                output::entry::Kind::DeltaRef { object_index } if !ofs_delta => {
+                    entry.kind = output::entry::Kind::DeltaOid { id: ids[object_index] };
+                }
+                _ => {}
+            }
+        }
+        Ok(entries)
+    })
+    .map(|entries: Result<_, Error>| entries.map_err(Box::new));
+
+    let mut pack_writer =
+        output::bytes::FromEntriesIter::new(entries, &mut out, num_objects, git_pack::data::Version::V2, object_hash);
+    for written in pack_writer.by_ref() {
+        if should_interrupt.load(Ordering::Relaxed) {
+            return Err(Error::Interrupted);
+        }
+        match written {
+            Ok(_) => {}
+            Err(output::bytes::Error::Input(err)) => return Err(*err),
+            Err(output::bytes::Error::Io(err)) => return Err(err.into()),
+        }
+    }
+    drop(pack_writer);
+    out.flush()?;
+    Ok(num_objects)
+}
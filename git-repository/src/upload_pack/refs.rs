@@ -0,0 +1,111 @@
+use git_hash::ObjectId;
+use git_pack::FindExt;
+
+use crate::{
+    bstr::{BString, ByteSlice},
+    upload_pack::{session::Db, Error},
+};
+
+/// A reference as advertised to the client.
+pub(crate) struct Ref {
+    /// The full name of the reference.
+    pub name: BString,
+    /// The object the reference points to after following symbolic references, or `None` if it is a symbolic reference
+    /// to an unborn branch.
+    pub target: Option<ObjectId>,
+    /// The name of the reference a symbolic reference points to.
+    pub symref_target: Option<BString>,
+    /// The object that a tag points to after peeling all tags, if `target` is a tag.
+    pub peeled: Option<ObjectId>,
+}
+
+/// An object along with the tags that had to be peeled to reach it.
+pub(crate) struct Peeled {
+    pub id: ObjectId,
+    pub kind: git_object::Kind,
+    pub tags: Vec<ObjectId>,
+}
+
+/// Follow `id` through all tags to the object it ultimately points to.
+#[allow(clippy::result_large_err)]
+pub(crate) fn peel(db: &Db, mut id: ObjectId, buf: &mut Vec<u8>) -> Result<Peeled, Error> {
+    let mut tags = Vec::new();
+    loop {
+        let object = db.find(id, buf)?.0;
+        match object.kind {
+            git_object::Kind::Tag => {
+                tags.push(id);
+                id = git_object::TagRefIter::from_bytes(object.data).target_id()?;
+            }
+            kind => return Ok(Peeled { id, kind, tags }),
+        }
+    }
+}
+
+/// Collect all references of `repo`, starting with `HEAD`, in the order they are advertised.
+///
+/// Symbolic references are resolved, and those which don't resolve to an object are skipped unless it's `HEAD`.
+#[allow(clippy::result_large_err)]
+pub(crate) fn collect(repo: &crate::Repository, db: &Db) -> Result<Vec<Ref>, Error> {
+    let mut out = Vec::new();
+    let mut buf = Vec::new();
+    if let Some(head) = repo.refs.try_find("HEAD")? {
+        if let Some(head) = resolve(repo, db, head, &mut buf)? {
+            out.push(head);
+        }
+    }
+    for reference in repo.refs.iter()?.all()? {
+        if let Some(reference) = resolve(repo, db, reference?, &mut buf)? {
+            if reference.target.is_some() {
+                out.push(reference);
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[allow(clippy::result_large_err)]
+fn resolve(
+    repo: &crate::Repository,
+    db: &Db,
+    reference: git_ref::Reference,
+    buf: &mut Vec<u8>,
+) -> Result<Option<Ref>, Error> {
+    let name = reference.name.as_bstr().to_owned();
+    let symref_target = match &reference.target {
+        git_ref::Target::Symbolic(target) => Some(target.as_bstr().to_owned()),
+        git_ref::Target::Peeled(_) => None,
+    };
+    let mut current = reference;
+    // Limit the amount of hops like `git` does to avoid cycles.
+    for _ in 0..5 {
+        match current.target {
+            git_ref::Target::Peeled(id) => {
+                let peeled = peel(db, id, buf)?;
+                return Ok(Some(Ref {
+                    name,
+                    target: Some(id),
+                    symref_target,
+                    peeled: (!peeled.tags.is_empty()).then(|| peeled.id),
+                }));
+            }
+            git_ref::Target::Symbolic(target) => match repo.refs.try_find(target.as_ref())? {
+                Some(next) => current = next,
+                None => {
+                    return Ok(Some(Ref {
+                        name,
+                        target: None,
+                        symref_target,
+                        peeled: None,
+                    }))
+                }
+            },
+        }
+    }
+    Ok(None)
+}
+
+/// Return `true` if `name` starts with any of the `prefixes`, or if there are no prefixes.
+pub(crate) fn matches_prefixes(name: &[u8], prefixes: &[BString]) -> bool {
+    prefixes.is_empty() || prefixes.iter().any(|prefix| name.starts_with(prefix.as_bytes()))
+}
@@ -0,0 +1,178 @@
+use std::{
+    io,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use git_transport::packetline::{encode, Channel, PacketLineRef, StreamingPeekableIter};
+
+use crate::{
+    bstr::{BString, ByteSlice},
+    upload_pack::{Error, Options, Outcome},
+};
+
+pub(crate) type Db = git_odb::Cache<git_odb::store::Handle<std::sync::Arc<git_odb::Store>>>;
+
+/// A packet line as sent by the client.
+pub(crate) enum Line {
+    /// A data line without its trailing newline.
+    Data(BString),
+    Flush,
+    Delimiter,
+    ResponseEnd,
+}
+
+/// The `uploadpack.*` configuration, which determines the capabilities we advertise and the requests we accept.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Config {
+    /// If `true`, V1 clients may want objects that references point to, as configured by `uploadpack.allowTipSHA1InWant`.
+    ///
+    /// As all references are advertised, this only affects the capabilities we advertise.
+    pub allow_tip_wants: bool,
+    /// If `true`, V1 clients may want commits that are reachable from references, as configured by
+    /// `uploadpack.allowReachableSHA1InWant`.
+    pub allow_reachable_wants: bool,
+    /// If `true`, V1 clients may want any object, as configured by `uploadpack.allowAnySHA1InWant`.
+    pub allow_any_wants: bool,
+    /// If `true`, clients may ask to omit objects with a `filter`, as configured by `uploadpack.allowFilter`.
+    pub allow_filter: bool,
+    /// If `true`, V2 clients may want references by name, as configured by `uploadpack.allowRefInWant`.
+    pub allow_ref_in_want: bool,
+}
+
+impl Config {
+    #[allow(clippy::result_large_err)]
+    fn new(repo: &crate::Repository) -> Result<Self, Error> {
+        let boolean = |key: &str| -> Result<bool, Error> {
+            Ok(repo
+                .config
+                .apply_leniency(repo.config.resolved.boolean("uploadpack", None, key))?
+                .unwrap_or(false))
+        };
+        let allow_any_wants = boolean("allowAnySHA1InWant")?;
+        Ok(Config {
+            allow_tip_wants: allow_any_wants || boolean("allowTipSHA1InWant")?,
+            allow_reachable_wants: allow_any_wants || boolean("allowReachableSHA1InWant")?,
+            allow_any_wants,
+            allow_filter: boolean("allowFilter")?,
+            allow_ref_in_want: boolean("allowRefInWant")?,
+        })
+    }
+}
+
+/// The state of a single connection to a client.
+pub(crate) struct Session<'a, R, W> {
+    pub repo: &'a crate::Repository,
+    pub db: Db,
+    pub config: Config,
+    lines: StreamingPeekableIter<R>,
+    pub out: W,
+    pub options: Options,
+    pub should_interrupt: &'a AtomicBool,
+    pub outcome: Outcome,
+}
+
+impl<'a, R, W> Session<'a, R, W>
+where
+    R: io::Read,
+    W: io::Write,
+{
+    #[allow(clippy::result_large_err)]
+    pub fn new(
+        repo: &'a crate::Repository,
+        read: R,
+        out: W,
+        options: Options,
+        should_interrupt: &'a AtomicBool,
+    ) -> Result<Self, Error> {
+        let mut db = repo.objects.clone().into_arc()?;
+        db.prevent_pack_unload();
+        Ok(Session {
+            repo,
+            db,
+            config: Config::new(repo)?,
+            lines: StreamingPeekableIter::new(read, &[]),
+            out,
+            options,
+            should_interrupt,
+            outcome: Outcome::default(),
+        })
+    }
+
+    /// Read the next line sent by the client, or `None` if the client closed the connection.
+    #[allow(clippy::result_large_err)]
+    pub fn read_line(&mut self) -> Result<Option<Line>, Error> {
+        Ok(Some(match self.lines.read_line() {
+            None => return Ok(None),
+            Some(Err(err)) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Some(line) => match line?? {
+                PacketLineRef::Data(data) => Line::Data(data.strip_suffix(b"\n").unwrap_or(data).as_bstr().to_owned()),
+                PacketLineRef::Flush => Line::Flush,
+                PacketLineRef::Delimiter => Line::Delimiter,
+                PacketLineRef::ResponseEnd => Line::ResponseEnd,
+            },
+        }))
+    }
+
+    /// Like [`read_line()`][Self::read_line()], but treat the end of the connection as error.
+    #[allow(clippy::result_large_err)]
+    pub fn read_line_in_request(&mut self) -> Result<Line, Error> {
+        self.read_line()?.ok_or(Error::UnexpectedEof)
+    }
+
+    pub fn write_text(&mut self, text: impl AsRef<[u8]>) -> io::Result<()> {
+        encode::text_to_write(text.as_ref(), &mut self.out).map(|_| ())
+    }
+
+    pub fn write_flush(&mut self) -> io::Result<()> {
+        encode::flush_to_write(&mut self.out)?;
+        self.out.flush()
+    }
+
+    pub fn write_delimiter(&mut self) -> io::Result<()> {
+        encode::delim_to_write(&mut self.out).map(|_| ())
+    }
+
+    pub fn send_error(&mut self, err: &Error) -> io::Result<()> {
+        encode::error_to_write(format!("upload-pack: {}", err).as_bytes(), &mut self.out)?;
+        self.out.flush()
+    }
+
+    /// Send `message` as progress information on the side-band, unless the client asked us not to.
+    pub fn write_progress(&mut self, no_progress: bool, message: &str) -> io::Result<()> {
+        if no_progress {
+            return Ok(());
+        }
+        encode::band_to_write(Channel::Progress, message.as_bytes(), &mut self.out).map(|_| ())
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn check_interrupt(&self) -> Result<(), Error> {
+        if self.should_interrupt.load(Ordering::Relaxed) {
+            Err(Error::Interrupted)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A writer which sends all data on the given side-band `channel`, in chunks of at most `max_data_len` bytes.
+pub(crate) struct Band<W> {
+    pub out: W,
+    pub channel: Channel,
+    pub max_data_len: usize,
+}
+
+impl<W: io::Write> io::Write for Band<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let chunk = &buf[..buf.len().min(self.max_data_len)];
+        encode::band_to_write(self.channel, chunk, &mut self.out)?;
+        Ok(chunk.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::{HashSet, VecDeque};
+
+use git_hash::ObjectId;
+use git_pack::FindExt;
+
+use crate::upload_pack::{arguments::Arguments, session::Db, Error};
+
+/// A change to the set of shallow commits of the client.
+pub(crate) enum Update {
+    /// The commit becomes shallow, as its parents aren't sent.
+    Shallow(ObjectId),
+    /// The commit isn't shallow anymore, as its parents are sent.
+    Unshallow(ObjectId),
+}
+
+impl Update {
+    pub fn to_line(&self) -> String {
+        match self {
+            Update::Shallow(id) => format!("shallow {}", id),
+            Update::Unshallow(id) => format!("unshallow {}", id),
+        }
+    }
+}
+
+/// The history to send to a client that asked to deepen or shorten its history.
+pub(crate) struct Shallow {
+    /// All commits that are part of the history the client will have.
+    pub kept: HashSet<ObjectId>,
+    /// The commits in `kept` whose parents aren't sent.
+    pub boundary: HashSet<ObjectId>,
+    /// The changes to the shallow commits of the client, sorted by object id.
+    pub updates: Vec<Update>,
+}
+
+impl Shallow {
+    /// Return the parents of all previously shallow commits of the client which become whole, as their history needs to be sent.
+    #[allow(clippy::result_large_err)]
+    pub fn unshallowed_parents(&self, db: &Db, buf: &mut Vec<u8>) -> Result<Vec<ObjectId>, Error> {
+        let mut out = Vec::new();
+        for update in &self.updates {
+            if let Update::Unshallow(id) = update {
+                out.extend(git_object::CommitRefIter::from_bytes(db.find(id, buf)?.0.data).parent_ids());
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Determine the history to send for `want_commits` according to the `deepen*` arguments in `args`, with `excluded` being
+/// all commits reachable from the `deepen-not` references.
+///
+/// Wanted commits are always kept, even if they are older than `deepen-since` or excluded.
+#[allow(clippy::result_large_err)]
+pub(crate) fn compute(
+    db: &Db,
+    want_commits: &[ObjectId],
+    args: &Arguments,
+    excluded: &HashSet<ObjectId>,
+) -> Result<Shallow, Error> {
+    let client_shallow: HashSet<_> = args.shallow.iter().copied().collect();
+    let mut buf = Vec::new();
+    let mut seen = HashSet::new();
+    let mut kept = HashSet::new();
+    let mut parents_of_kept = Vec::new();
+    // The depth of each commit, counted from the wants, or from the shallow commits of the client if `deepen-relative` is set.
+    // `None` means the depth doesn't matter, which is the case for commits above the shallow commits of the client.
+    let mut queue: VecDeque<_> = want_commits
+        .iter()
+        .map(|id| {
+            let depth = if args.deepen_relative {
+                client_shallow.contains(id).then(|| 0)
+            } else {
+                Some(1)
+            };
+            (*id, depth)
+        })
+        .collect();
+    while let Some((id, depth)) = queue.pop_front() {
+        if !seen.insert(id) {
+            continue;
+        }
+        let commit = git_object::CommitRefIter::from_bytes(db.find(id, &mut buf)?.0.data);
+        let committer_time = commit.committer()?.time.seconds_since_unix_epoch;
+        let keep = want_commits.contains(&id)
+            || (depth.zip(args.deepen).map_or(true, |(depth, max)| depth <= max)
+                && args.deepen_since.map_or(true, |since| committer_time >= since)
+                && !excluded.contains(&id));
+        if !keep {
+            continue;
+        }
+        kept.insert(id);
+        let parents: Vec<_> = commit.parent_ids().collect();
+        for parent in &parents {
+            let depth = if args.deepen_relative && client_shallow.contains(parent) {
+                Some(0)
+            } else {
+                depth.map(|d| d + 1)
+            };
+            queue.push_back((*parent, depth));
+        }
+        parents_of_kept.push((id, parents));
+    }
+
+    let boundary: HashSet<_> = parents_of_kept
+        .into_iter()
+        .filter_map(|(id, parents)| parents.iter().any(|p| !kept.contains(p)).then(|| id))
+        .collect();
+    let mut updates: Vec<_> = boundary
+        .iter()
+        .filter(|id| !client_shallow.contains(*id))
+        .map(|id| Update::Shallow(*id))
+        .chain(
+            client_shallow
+                .iter()
+                .filter(|id| kept.contains(*id) && !boundary.contains(*id))
+                .map(|id| Update::Unshallow(*id)),
+        )
+        .collect();
+    updates.sort_by_key(|update| match update {
+        Update::Shallow(id) | Update::Unshallow(id) => *id,
+    });
+    Ok(Shallow {
+        kept,
+        boundary,
+        updates,
+    })
+}
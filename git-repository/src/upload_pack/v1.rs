@@ -0,0 +1,215 @@
+use std::io;
+
+use crate::{
+    bstr::ByteSlice,
+    upload_pack::{
+        arguments::{Arguments, MultiAck},
+        pack,
+        refs::{self, Ref},
+        session::{Line, Session},
+        Error,
+    },
+};
+
+impl<'a, R, W> Session<'a, R, W>
+where
+    R: io::Read,
+    W: io::Write,
+{
+    /// Serve a client speaking protocol V1, which consists of the reference advertisement followed by a single fetch.
+    ///
+    /// Acknowledgements follow `git upload-pack`, depending on whether the client asked for `multi_ack` or `multi_ack_detailed`.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn serve_v1(&mut self) -> Result<(), Error> {
+        let refs = refs::collect(self.repo, &self.db)?;
+        if !self.options.stateless_rpc || self.options.advertise_refs {
+            self.advertise_refs(&refs)?;
+        }
+        if self.options.advertise_refs {
+            return Ok(());
+        }
+
+        let mut args = Arguments::default();
+        match self.read_line()? {
+            // The client only wanted to see our references.
+            None | Some(Line::Flush) => return Ok(()),
+            Some(Line::Data(line)) => {
+                let mut tokens = line.split_str(b" ");
+                match (tokens.next(), tokens.next()) {
+                    (Some(b"want"), Some(id)) => {
+                        args.parse_line([b"want ".as_slice(), id].concat().as_bstr())?;
+                        for capability in tokens {
+                            args.parse_feature(capability);
+                        }
+                    }
+                    _ => return Err(Error::MalformedLine { line }),
+                }
+            }
+            Some(Line::Delimiter | Line::ResponseEnd) => {
+                return Err(Error::MalformedLine {
+                    line: "special packet line instead of want".into(),
+                })
+            }
+        }
+        loop {
+            match self.read_line_in_request()? {
+                Line::Data(line) if !line.starts_with(b"have ") => args.parse_line(line.as_bstr())?,
+                Line::Flush => break,
+                _ => {
+                    return Err(Error::MalformedLine {
+                        line: "unexpected line in wants".into(),
+                    })
+                }
+            }
+        }
+        let wants = self.resolve_wants(&args, &refs)?;
+        let shallow = self.shallow(&args, &wants)?;
+        if let Some(shallow) = &shallow {
+            for update in &shallow.updates {
+                self.write_text(update.to_line())?;
+            }
+            self.write_flush()?;
+        }
+
+        let mut common = Vec::new();
+        let mut negotiation = pack::Negotiation::default();
+        let mut last_common = None;
+        let (mut got_common, mut got_other, mut is_ready, mut sent_ready) = (false, false, false, false);
+        loop {
+            match self.read_line()? {
+                Some(Line::Data(line)) if line == "done" => break,
+                Some(Line::Data(line)) => {
+                    let id = match line.strip_prefix(b"have ").map(git_hash::ObjectId::from_hex) {
+                        Some(Ok(id)) => id,
+                        _ => return Err(Error::MalformedLine { line }),
+                    };
+                    if self.is_common(&id) {
+                        got_common = true;
+                        last_common = Some(id);
+                        if !common.contains(&id) {
+                            common.push(id);
+                            self.add_common(&mut negotiation, id)?;
+                        }
+                        match args.multi_ack {
+                            Some(MultiAck::Detailed) => self.write_text(format!("ACK {} common", id))?,
+                            Some(MultiAck::Continue) => self.write_text(format!("ACK {} continue", id))?,
+                            None if common.len() == 1 => self.write_text(format!("ACK {}", id))?,
+                            None => {}
+                        }
+                    } else {
+                        got_other = true;
+                        if let Some(multi_ack) = args.multi_ack {
+                            is_ready = is_ready || self.is_ready(&mut negotiation, &wants)?;
+                            if is_ready {
+                                match multi_ack {
+                                    MultiAck::Detailed => {
+                                        sent_ready = true;
+                                        self.write_text(format!("ACK {} ready", id))?
+                                    }
+                                    MultiAck::Continue => self.write_text(format!("ACK {} continue", id))?,
+                                }
+                            }
+                        }
+                    }
+                }
+                Some(Line::Flush) => {
+                    if let (Some(MultiAck::Detailed), Some(id), true, false) =
+                        (args.multi_ack, last_common, got_common, got_other)
+                    {
+                        is_ready = is_ready || self.is_ready(&mut negotiation, &wants)?;
+                        if is_ready {
+                            sent_ready = true;
+                            self.write_text(format!("ACK {} ready", id))?;
+                        }
+                    }
+                    if common.is_empty() || args.multi_ack.is_some() {
+                        self.write_text("NAK")?;
+                    }
+                    if let (true, true, Some(id)) = (args.no_done, sent_ready, last_common) {
+                        self.write_text(format!("ACK {}", id))?;
+                        break;
+                    }
+                    self.out.flush()?;
+                    if self.options.stateless_rpc {
+                        return Ok(());
+                    }
+                    got_common = false;
+                    got_other = false;
+                }
+                None if self.options.stateless_rpc => return Ok(()),
+                None => return Err(Error::UnexpectedEof),
+                Some(Line::Delimiter | Line::ResponseEnd) => {
+                    return Err(Error::MalformedLine {
+                        line: "special packet line in negotiation".into(),
+                    })
+                }
+            }
+        }
+        match last_common {
+            Some(id) if args.multi_ack.is_some() && !(args.no_done && sent_ready) => {
+                self.write_text(format!("ACK {}", id))?
+            }
+            Some(_) => {}
+            None => self.write_text("NAK")?,
+        }
+
+        let max_band_data_len = args.side_band;
+        self.send_pack(&args, &refs, &wants, &common, shallow.as_ref(), max_band_data_len)?;
+        if max_band_data_len.is_some() {
+            self.write_flush()?;
+        }
+        Ok(())
+    }
+
+    fn advertise_refs(&mut self, refs: &[Ref]) -> io::Result<()> {
+        let mut capabilities = String::from(
+            "multi_ack thin-pack side-band side-band-64k ofs-delta shallow deepen-since deepen-not deepen-relative no-progress \
+             include-tag multi_ack_detailed",
+        );
+        if self.config.allow_tip_wants {
+            capabilities.push_str(" allow-tip-sha1-in-want");
+        }
+        if self.config.allow_reachable_wants {
+            capabilities.push_str(" allow-reachable-sha1-in-want");
+        }
+        capabilities.push_str(" no-done");
+        if self.config.allow_filter {
+            capabilities.push_str(" filter");
+        }
+        if let Some(head) = refs.iter().find(|r| r.name == "HEAD") {
+            if let Some(target) = head.symref_target.as_ref().filter(|_| head.target.is_some()) {
+                capabilities.push_str(&format!(" symref=HEAD:{}", target));
+            }
+        }
+        capabilities.push_str(&format!(
+            " object-format={} agent=git/{}",
+            self.repo.object_hash().as_str(),
+            crate::env::agent()
+        ));
+
+        let mut lines = Vec::new();
+        for reference in refs {
+            if let Some(target) = reference.target {
+                lines.push(format!("{} {}", target, reference.name));
+                if let Some(peeled) = reference.peeled {
+                    lines.push(format!("{} {}^{{}}", peeled, reference.name));
+                }
+            }
+        }
+        match lines.first_mut() {
+            Some(first) => {
+                first.push('\0');
+                first.push_str(&capabilities);
+            }
+            None => lines.push(format!(
+                "{} capabilities^{{}}\0{}",
+                self.repo.object_hash().null(),
+                capabilities
+            )),
+        }
+        for line in lines {
+            self.write_text(line)?;
+        }
+        self.write_flush()
+    }
+}
@@ -0,0 +1,193 @@
+use std::io;
+
+use crate::{
+    bstr::{BString, ByteSlice},
+    upload_pack::{
+        arguments::Arguments,
+        fetch::MAX_BAND_DATA_LEN,
+        pack, refs,
+        session::{Line, Session},
+        Error,
+    },
+};
+
+impl<'a, R, W> Session<'a, R, W>
+where
+    R: io::Read,
+    W: io::Write,
+{
+    /// Serve commands of a client speaking protocol V2 until it disconnects, or until the first command was handled
+    /// in stateless mode.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn serve_v2(&mut self) -> Result<(), Error> {
+        if !self.options.stateless_rpc || self.options.advertise_refs {
+            self.advertise_capabilities()?;
+        }
+        if self.options.advertise_refs {
+            return Ok(());
+        }
+        loop {
+            let command = match self.read_line()? {
+                None | Some(Line::Flush) => break,
+                Some(Line::Data(line)) => match line.strip_prefix(b"command=") {
+                    Some(command) => command.as_bstr().to_owned(),
+                    None => return Err(Error::MalformedLine { line }),
+                },
+                Some(Line::Delimiter | Line::ResponseEnd) => {
+                    return Err(Error::MalformedLine {
+                        line: "special packet line instead of command".into(),
+                    })
+                }
+            };
+            let arguments = self.read_command_arguments()?;
+            match command.as_bytes() {
+                b"ls-refs" => self.ls_refs(arguments)?,
+                b"fetch" => self.fetch_v2(arguments)?,
+                _ => return Err(Error::UnknownCommand { name: command }),
+            }
+            if self.options.stateless_rpc {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn advertise_capabilities(&mut self) -> io::Result<()> {
+        self.write_text("version 2")?;
+        self.write_text(format!("agent=git/{}", crate::env::agent()))?;
+        self.write_text("ls-refs=unborn")?;
+        let mut fetch = String::from("fetch=shallow");
+        if self.config.allow_filter {
+            fetch.push_str(" filter");
+        }
+        if self.config.allow_ref_in_want {
+            fetch.push_str(" ref-in-want");
+        }
+        self.write_text(fetch)?;
+        self.write_text("server-option")?;
+        self.write_text(format!("object-format={}", self.repo.object_hash().as_str()))?;
+        self.write_flush()
+    }
+
+    /// Read the capabilities and arguments of a command, validate the capabilities and return the arguments.
+    #[allow(clippy::result_large_err)]
+    fn read_command_arguments(&mut self) -> Result<Vec<BString>, Error> {
+        let mut arguments = Vec::new();
+        let mut in_arguments = false;
+        loop {
+            match self.read_line_in_request()? {
+                Line::Data(line) if in_arguments => arguments.push(line),
+                Line::Data(line) => {
+                    if let Some(name) = line.strip_prefix(b"object-format=") {
+                        let actual = self.repo.object_hash();
+                        if name != actual.as_str().as_bytes() {
+                            return Err(Error::ObjectFormatMismatch {
+                                name: name.into(),
+                                actual,
+                            });
+                        }
+                    }
+                }
+                Line::Delimiter if !in_arguments => in_arguments = true,
+                Line::Flush => break,
+                Line::Delimiter | Line::ResponseEnd => {
+                    return Err(Error::MalformedLine {
+                        line: "unexpected special packet line in command".into(),
+                    })
+                }
+            }
+        }
+        Ok(arguments)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn ls_refs(&mut self, arguments: Vec<BString>) -> Result<(), Error> {
+        let (mut symrefs, mut peel, mut unborn, mut prefixes) = (false, false, false, Vec::new());
+        for argument in arguments {
+            match argument.as_bytes() {
+                b"symrefs" => symrefs = true,
+                b"peel" => peel = true,
+                b"unborn" => unborn = true,
+                _ => match argument.strip_prefix(b"ref-prefix ") {
+                    Some(prefix) => prefixes.push(prefix.into()),
+                    None => return Err(Error::MalformedLine { line: argument }),
+                },
+            }
+        }
+
+        for reference in refs::collect(self.repo, &self.db)? {
+            if !refs::matches_prefixes(&reference.name, &prefixes) {
+                continue;
+            }
+            let mut line = match (reference.target, &reference.symref_target) {
+                (Some(target), _) => format!("{} {}", target, reference.name),
+                (None, Some(_)) if unborn => format!("unborn {}", reference.name),
+                (None, _) => continue,
+            };
+            if let Some(symref_target) = reference
+                .symref_target
+                .as_ref()
+                .filter(|_| symrefs || reference.target.is_none())
+            {
+                line.push_str(&format!(" symref-target:{}", symref_target));
+            }
+            if let Some(peeled) = reference.peeled.filter(|_| peel) {
+                line.push_str(&format!(" peeled:{}", peeled));
+            }
+            self.write_text(line)?;
+        }
+        self.write_flush()?;
+        Ok(())
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn fetch_v2(&mut self, arguments: Vec<BString>) -> Result<(), Error> {
+        let mut args = Arguments::default();
+        for argument in arguments {
+            args.parse_line(argument.as_bstr())?;
+        }
+        let refs = refs::collect(self.repo, &self.db)?;
+        let wants = self.resolve_wants(&args, &refs)?;
+        let common: Vec<_> = args.haves.iter().filter(|id| self.is_common(id)).copied().collect();
+        if !args.done {
+            let mut negotiation = pack::Negotiation::default();
+            for id in &common {
+                self.add_common(&mut negotiation, *id)?;
+            }
+            let ready = self.is_ready(&mut negotiation, &wants)?;
+            self.write_text("acknowledgments")?;
+            if common.is_empty() {
+                self.write_text("NAK")?;
+            }
+            for id in &common {
+                self.write_text(format!("ACK {}", id))?;
+            }
+            if !ready {
+                self.write_flush()?;
+                return Ok(());
+            }
+            self.write_text("ready")?;
+            self.write_delimiter()?;
+        }
+
+        let shallow = self.shallow(&args, &wants)?;
+        if let Some(shallow) = &shallow {
+            self.write_text("shallow-info")?;
+            for update in &shallow.updates {
+                self.write_text(update.to_line())?;
+            }
+            self.write_delimiter()?;
+        }
+        if !wants.refs.is_empty() {
+            self.write_text("wanted-refs")?;
+            for (name, id) in &wants.refs {
+                self.write_text(format!("{} {}", id, name))?;
+            }
+            self.write_delimiter()?;
+        }
+        self.write_text("packfile")?;
+        self.send_pack(&args, &refs, &wants, &common, shallow.as_ref(), Some(MAX_BAND_DATA_LEN))?;
+        self.write_flush()?;
+        Ok(())
+    }
+}
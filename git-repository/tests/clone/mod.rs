@@ -63,8 +63,8 @@ mod blocking_io {
         );
 
         assert_eq!(out.ref_map.mappings.len(), 14);
-        let packed_refs = repo
-            .refs
+        let loose_store = repo.refs.loose_store().expect("clones store references as files");
+        let packed_refs = loose_store
             .cached_packed_buffer()?
             .expect("packed refs should be present");
         assert_eq!(
@@ -73,7 +73,7 @@ mod blocking_io {
             "all non-symbolic refs should be stored"
         );
         assert_eq!(
-            repo.refs.loose_iter()?.count(),
+            loose_store.loose_iter()?.count(),
             2,
             "HEAD and an actual symbolic ref we received"
         );
@@ -161,7 +161,7 @@ mod blocking_io {
         Ok(())
     }
 
-    fn assert_reflog(log: std::io::Result<Option<git_ref::file::log::iter::Forward<'_>>>) {
+    fn assert_reflog(log: Result<Option<git_ref::store::reflog::Iter<'_>>, git_ref::store::reflog::Error>) {
         let lines = log
             .unwrap()
             .expect("log present")
@@ -178,6 +178,48 @@ mod blocking_io {
         assert!(path.is_absolute(), "{:?} must be absolute", path);
     }
 
+    #[test]
+    fn fetch_only_adopts_the_object_format_of_the_remote() -> crate::Result {
+        let remote_dir = git_testtools::tempfile::TempDir::new()?;
+        for args in [
+            &["init", "-q", "--object-format=sha256"][..],
+            &[
+                "-c",
+                "user.name=a",
+                "-c",
+                "user.email=a@example.com",
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "first",
+            ],
+        ] {
+            assert!(std::process::Command::new("git")
+                .args(args)
+                .current_dir(remote_dir.path())
+                .status()?
+                .success());
+        }
+        let tmp = git_testtools::tempfile::TempDir::new()?;
+        let mut prepare = git::prepare_clone_bare(remote_dir.path(), tmp.path())?;
+        let (repo, out) = prepare.fetch_only(git::progress::Discard, &std::sync::atomic::AtomicBool::default())?;
+        assert_eq!(out.ref_map.object_hash, git::hash::Kind::Sha256);
+        assert_eq!(
+            repo.object_hash(),
+            git::hash::Kind::Sha256,
+            "the repository was reconfigured"
+        );
+        let head = repo.head_commit()?;
+        assert_eq!(head.id.kind(), git::hash::Kind::Sha256);
+        assert_eq!(
+            head.message_raw()?,
+            "first\n",
+            "the commit was received and is readable"
+        );
+        Ok(())
+    }
+
     #[test]
     fn fetch_and_checkout() -> crate::Result {
         let tmp = git_testtools::tempfile::TempDir::new()?;
@@ -243,6 +285,284 @@ mod blocking_io {
         Ok(())
     }
 
+    mod shallow {
+        use std::sync::atomic::AtomicBool;
+
+        use git_repository as git;
+        use git_repository::remote::fetch::Shallow;
+
+        fn base_repo() -> crate::Result<git::Repository> {
+            let dir = git_testtools::scripted_fixture_repo_read_only("make_shallow_repos.sh")?;
+            Ok(git::open_opts(dir.join("base"), crate::restricted())?)
+        }
+
+        fn clone_with(
+            shallow: Shallow,
+            config: &[&str],
+        ) -> crate::Result<(git::Repository, git_testtools::tempfile::TempDir)> {
+            let tmp = git_testtools::tempfile::TempDir::new()?;
+            let (repo, _out) = git::clone::PrepareFetch::new(
+                base_repo()?.path(),
+                tmp.path(),
+                git::create::Kind::Bare,
+                Default::default(),
+                git::open::Options::isolated().config_overrides(config.iter().copied()),
+            )?
+            .with_shallow(shallow)
+            .fetch_only(git::progress::Discard, &AtomicBool::default())?;
+            Ok((repo, tmp))
+        }
+
+        fn history_len(repo: &git::Repository) -> crate::Result<usize> {
+            Ok(repo
+                .head_id()?
+                .ancestors()
+                .all()?
+                .error_on_missing_commit()
+                .collect::<Result<Vec<_>, _>>()?
+                .len())
+        }
+
+        fn id_of(spec: &str) -> crate::Result<git::ObjectId> {
+            Ok(base_repo()?.rev_parse_single(spec)?.detach())
+        }
+
+        #[test]
+        fn depth() -> crate::Result {
+            for protocol_version in ["protocol.version=1", "protocol.version=2"] {
+                let (repo, _tmp) = clone_with(
+                    Shallow::DepthAtRemote(2.try_into().expect("non-zero")),
+                    &[protocol_version],
+                )?;
+                assert_eq!(
+                    repo.shallow_commits()?,
+                    Some(vec![id_of("main~1")?]),
+                    "{protocol_version}: the oldest received commit is at the boundary"
+                );
+                assert_eq!(history_len(&repo)?, 2, "{protocol_version}");
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn since() -> crate::Result {
+            let cutoff = base_repo()?.find_object(id_of("main~2")?)?.into_commit().time()?;
+            let (repo, _tmp) = clone_with(Shallow::Since { cutoff }, &[])?;
+            assert_eq!(repo.shallow_commits()?, Some(vec![id_of("main~2")?]));
+            assert_eq!(history_len(&repo)?, 3);
+            Ok(())
+        }
+
+        #[test]
+        fn exclude() -> crate::Result {
+            let (repo, _tmp) = clone_with(
+                Shallow::Exclude {
+                    remote_refs: vec!["v7".into()],
+                    since_cutoff: None,
+                },
+                &[],
+            )?;
+            assert_eq!(
+                repo.shallow_commits()?,
+                Some(vec![id_of("main~2")?]),
+                "the commit after the excluded tag"
+            );
+            assert_eq!(history_len(&repo)?, 3);
+            Ok(())
+        }
+
+        #[test]
+        fn no_change_is_a_complete_clone() -> crate::Result {
+            let (repo, _tmp) = clone_with(Shallow::NoChange, &[])?;
+            assert!(!repo.is_shallow());
+            assert_eq!(history_len(&repo)?, 10);
+            Ok(())
+        }
+    }
+
+    mod partial {
+        use std::sync::atomic::AtomicBool;
+
+        use git_odb::Find;
+        use git_repository as git;
+
+        fn base_repo() -> crate::Result<git::Repository> {
+            let dir = git_testtools::scripted_fixture_repo_read_only("make_partial_clone_repos.sh")?;
+            Ok(git::open_opts(dir.join("base"), crate::restricted())?)
+        }
+
+        fn prepare(kind: git::create::Kind, tmp: &std::path::Path) -> crate::Result<git::clone::PrepareFetch> {
+            Ok(git::clone::PrepareFetch::new(
+                base_repo()?.path(),
+                tmp,
+                kind,
+                Default::default(),
+                git::open::Options::isolated(),
+            )?
+            .with_filter("blob:none"))
+        }
+
+        fn id_of(spec: &str) -> crate::Result<git::ObjectId> {
+            Ok(base_repo()?.rev_parse_single(spec)?.detach())
+        }
+
+        #[test]
+        fn blobless_bare_clone_marks_pack_and_remote_and_fetches_blobs_on_demand() -> crate::Result {
+            let tmp = git_testtools::tempfile::TempDir::new()?;
+            let (repo, _out) = prepare(git::create::Kind::Bare, tmp.path())?
+                .fetch_only(git::progress::Discard, &AtomicBool::default())?;
+
+            let config = repo.config_snapshot();
+            assert_eq!(config.boolean("remote.origin.promisor"), Some(true));
+            assert_eq!(
+                config.string("remote.origin.partialCloneFilter").as_deref(),
+                Some("blob:none".into())
+            );
+            assert_eq!(repo.promisor_remote_name().as_deref(), Some("origin".into()));
+            assert_eq!(
+                repo.objects.store_ref().promisor_pack_indices()?.len(),
+                1,
+                "the received pack is marked as promisor pack"
+            );
+
+            let blob = id_of("main:a")?;
+            assert!(!repo.objects.contains(blob), "blobs are not part of the clone");
+            assert!(repo.objects.contains(id_of("main:dir")?), "trees are");
+            let outcome = repo.fsck(git::progress::Discard, &AtomicBool::default(), repo.fsck_options()?)?;
+            assert!(
+                outcome.missing.is_empty() && outcome.broken_links.is_empty(),
+                "promised objects are not missing"
+            );
+
+            assert_eq!(repo.find_object(blob)?.data.as_slice(), b"a2\n", "fetched on demand");
+            assert!(repo.objects.contains(blob), "and stored locally");
+
+            let repo = git::open_opts(repo.git_dir(), crate::restricted())?;
+            assert_eq!(
+                repo.find_object(id_of("main~1:a")?)?.data.as_slice(),
+                b"a\n",
+                "opened partial clones fetch missing objects as well"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn objects_missing_on_the_remote_as_well_are_errors_and_do_not_affect_later_lookups() -> crate::Result {
+            let tmp = git_testtools::tempfile::TempDir::new()?;
+            let (repo, _out) = prepare(git::create::Kind::Bare, tmp.path())?
+                .fetch_only(git::progress::Discard, &AtomicBool::default())?;
+
+            let mut buf = Vec::new();
+            let absent = git::ObjectId::from_hex(b"0000000000000000000000000000000000000001")?;
+            assert!(
+                repo.objects.try_find(absent, &mut buf).is_err(),
+                "objects that can't be fetched fail the lookup instead of not being found"
+            );
+            for (spec, expected) in [("main:a", "a2\n"), ("main~1:a", "a\n"), ("main:dir/b", "b\n")] {
+                let object = repo
+                    .objects
+                    .try_find(id_of(spec)?, &mut buf)?
+                    .expect("fetched on demand");
+                assert_eq!(
+                    object.data,
+                    expected.as_bytes(),
+                    "each batch is fetched with the same repository"
+                );
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn fetch_promised_objects_fetches_all_of_them_at_once() -> crate::Result {
+            let tmp = git_testtools::tempfile::TempDir::new()?;
+            let (repo, _out) = prepare(git::create::Kind::Bare, tmp.path())?
+                .fetch_only(git::progress::Discard, &AtomicBool::default())?;
+
+            let blobs = [id_of("main:a")?, id_of("main~1:a")?, id_of("main:dir/b")?];
+            assert!(blobs.iter().all(|id| !repo.objects.contains(id)));
+            let outcome = repo.fetch_promised_objects(blobs, git::progress::Discard, &AtomicBool::default())?;
+            match outcome.expect("objects were fetched").status {
+                git::remote::fetch::Status::Change { write_pack_bundle, .. } => {
+                    assert!(
+                        write_pack_bundle.keep_path.is_none(),
+                        "promisor packs don't need to be kept"
+                    );
+                }
+                _ => unreachable!("objects were received"),
+            }
+            assert!(blobs.iter().all(|id| repo.objects.contains(id)));
+            assert_eq!(
+                repo.objects.store_ref().promisor_pack_indices()?.len(),
+                2,
+                "packs received from the promisor remote are promisor packs as well"
+            );
+
+            assert!(
+                repo.fetch_promised_objects(None, git::progress::Discard, &AtomicBool::default())?
+                    .is_none(),
+                "nothing to do without objects"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn repack_retains_promisor_packs_and_does_not_fetch_promised_objects() -> crate::Result {
+            let tmp = git_testtools::tempfile::TempDir::new()?;
+            let (repo, _out) = prepare(git::create::Kind::Bare, tmp.path())?
+                .fetch_only(git::progress::Discard, &AtomicBool::default())?;
+            let blob = repo.write_blob(b"new")?.detach();
+            repo.reference(
+                "refs/heads/new-blob",
+                blob,
+                git::refs::transaction::PreviousValue::MustNotExist,
+                "test",
+            )?;
+            let promisor_packs = repo.objects.store_ref().promisor_pack_indices()?;
+
+            let outcome = repo.repack(
+                git::progress::Discard,
+                &AtomicBool::default(),
+                git::repack::Options {
+                    mode: git::repack::Mode::AllIntoOne,
+                    ..Default::default()
+                },
+            )?;
+            assert!(outcome.removed_packs.is_empty(), "promisor packs are retained");
+            assert_eq!(
+                outcome.pack.expect("the loose blob was packed").num_objects,
+                1,
+                "objects in promisor packs and the objects they promise aren't written"
+            );
+            assert_eq!(outcome.removed_loose_objects, 1);
+
+            let repo = git::open_opts(repo.git_dir(), crate::restricted())?;
+            assert_eq!(repo.objects.store_ref().promisor_pack_indices()?, promisor_packs);
+            assert!(repo.objects.contains(blob));
+            assert!(
+                !repo.objects.contains(id_of("main:a")?),
+                "promised objects were not fetched"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn checkout_fetches_missing_blobs_beforehand() -> crate::Result {
+            let tmp = git_testtools::tempfile::TempDir::new()?;
+            let (mut checkout, _out) = prepare(git::create::Kind::WithWorktree, tmp.path())?
+                .fetch_then_checkout(git::progress::Discard, &AtomicBool::default())?;
+            let (repo, _outcome) = checkout.main_worktree(git::progress::Discard, &AtomicBool::default())?;
+
+            let workdir = repo.work_dir().expect("non-bare");
+            assert_eq!(std::fs::read(workdir.join("a"))?, b"a2\n");
+            assert_eq!(std::fs::read(workdir.join("dir").join("b"))?, b"b\n");
+            assert!(
+                !repo.objects.contains(id_of("main~1:a")?),
+                "blobs that aren't checked out are still missing"
+            );
+            Ok(())
+        }
+    }
+
     #[test]
     fn fetch_only_without_configuration() -> crate::Result {
         let tmp = git_testtools::tempfile::TempDir::new()?;
@@ -0,0 +1,20 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+
+git checkout -q -b main
+git commit -q --allow-empty -m c1
+git commit -q --allow-empty -m c2
+git branch merged HEAD~1
+
+git checkout -q -b unmerged
+git commit -q --allow-empty -m c3
+git checkout -q main
+
+git config branch.main.remote origin
+git config branch.main.merge refs/heads/main
+git config branch.unmerged.remote origin
+
+git branch in-worktree
+git worktree add -q wt in-worktree
@@ -0,0 +1,34 @@
+#!/bin/bash
+set -eu -o pipefail
+
+function commit() {
+  local message=${1:?} time=${2:?}
+  GIT_AUTHOR_DATE="$time +0000" GIT_COMMITTER_DATE="$time +0000" git commit -q -am "$message"
+}
+
+git init -q server
+(cd server
+  git checkout -q -b main
+  echo 0 > file && git add file
+  for n in $(seq 10); do
+    echo $n > file && commit "c$n" $((1000000000 + n * 1000))
+  done
+)
+
+git clone -q server local
+(cd local
+  git checkout -q -b local-work main~1
+  for n in $(seq 40); do
+    echo "local $n" > file && commit "l$n" $((1100000000 + n))
+  done
+  git checkout -q main
+  orphan=$(echo orphan | GIT_AUTHOR_DATE="900000000 +0000" GIT_COMMITTER_DATE="900000000 +0000" git commit-tree "$(git mktree </dev/null)")
+  git update-ref refs/remotes/origin/other "$orphan"
+  git commit-graph write --no-progress --reachable
+)
+
+(cd server
+  git checkout -q -b other
+  echo other > file && commit o1 1000020000
+  git checkout -q main
+)
@@ -0,0 +1,63 @@
+#!/bin/bash
+set -eu -o pipefail
+
+function tree_entry() {
+  local mode=${1:?} name=${2:?} id=${3:?}
+  printf '%s %s\0' "$mode" "$name"
+  echo -n "$id" | xxd -r -p
+}
+
+git init -q
+git config gc.auto 0
+git config fsck.zeroPaddedFilemode error
+git config fsck.missingTaggerEntry ignore
+
+echo a > a
+mkdir dir
+echo b > dir/b
+git add a dir
+git commit -q -m "first"
+git tag -a -m "annotated" v1
+
+git checkout -q -b side
+echo missing > missing
+git add missing
+git commit -q -m "side"
+git rev-parse HEAD:missing > missing-blob-id
+git rev-parse "HEAD^{tree}" > side-tree-id
+git checkout -q -
+
+unreachable_blob=$(echo unreachable | git hash-object -w --stdin)
+echo "$unreachable_blob" > unreachable-blob-id
+unreachable_tree=$(printf '100644 blob %s\tunreachable\n' "$unreachable_blob" | git mktree)
+echo "$unreachable_tree" > unreachable-tree-id
+git commit-tree -m "unreachable" "$unreachable_tree" > dangling-commit-id
+echo dangling | git hash-object -w --stdin > dangling-blob-id
+
+tree=$(git rev-parse "HEAD^{tree}")
+blob=$(git rev-parse HEAD:a)
+printf 'tree %s\nauthor A U Thor <author@example.com> 01234567890 +0000\ncommitter C O Mitter <committer@example.com> 1234567890 +0000\n\nzero-padded date\n' "$tree" \
+  | git hash-object -w -t commit --literally --stdin > zero-padded-date-commit-id
+printf 'object %s\ntype commit\ntag no-tagger\n\nno tagger\n' "$(git rev-parse HEAD)" \
+  | git hash-object -w -t tag --literally --stdin > no-tagger-tag-id
+tree_entry 0100644 file "$blob" | git hash-object -w -t tree --literally --stdin > zero-padded-mode-tree-id
+{
+  tree_entry 100644 b "$blob"
+  tree_entry 100644 a "$blob"
+  tree_entry 100644 a "$blob"
+} | git hash-object -w -t tree --literally --stdin > unsorted-tree-id
+
+missing_blob=$(cat missing-blob-id)
+rm ".git/objects/${missing_blob:0:2}/${missing_blob:2}"
+
+function loose_path() {
+  local id=${1:?}
+  echo ".git/objects/${id:0:2}/${id:2}"
+}
+unreadable=$(echo unreadable-content | git hash-object -w --stdin)
+echo "$unreadable" > unreadable-id
+chmod u+w "$(loose_path "$unreadable")"
+echo garbage > "$(loose_path "$unreadable")"
+hash_mismatch=$(echo hash-mismatch | git hash-object -w --stdin)
+echo "$hash_mismatch" > hash-mismatch-id
+cp -f "$(loose_path "$(cat dangling-blob-id)")" "$(loose_path "$hash_mismatch")"
@@ -0,0 +1,14 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q base
+(cd base
+  git checkout -q -b main
+  git config uploadpack.allowFilter true
+  git config uploadpack.allowAnySHA1InWant true
+
+  mkdir dir
+  echo a > a && echo b > dir/b
+  git add . && git commit -q -m c1
+  echo a2 > a && git commit -q -am c2
+)
@@ -0,0 +1,37 @@
+#!/bin/bash
+set -eu -o pipefail
+
+# IMPORTANT: keep this repo small as it's used for writes, hence will be executed for each writer!
+git init -q base
+(cd base
+  git checkout -q -b main
+  echo 1 > file && git add file && git commit -q -m c1
+  echo 2 > file && git commit -q -am c2
+  git branch other
+  git branch to-delete
+  git tag -m "annotated" v1
+)
+
+git clone -q --bare base remote.git
+git clone -q --bare base strict.git
+(cd strict.git
+  git config receive.denyDeletes true
+  git config receive.denyNonFastForwards true
+)
+git init -q --bare empty.git
+
+git clone -q remote.git local
+(cd local
+  git remote add strict "$PWD/../strict.git"
+  git remote add empty "$PWD/../empty.git"
+  git branch other origin/other
+  git branch to-delete origin/to-delete
+
+  echo 3 > file && git commit -q -am c3
+  git tag -m "annotated" v2
+
+  git checkout -q other
+  git reset -q --hard HEAD~1
+  echo diverged > file && git commit -q -am diverged
+  git checkout -q main
+)
@@ -0,0 +1,25 @@
+#!/bin/bash
+set -eu -o pipefail
+
+# IMPORTANT: keep this repo small as it's used for writes, hence will be executed for each writer!
+git init -q base
+(cd base
+  git checkout -q -b main
+  echo 1 > file && git add file && git commit -q -m c1
+  echo 2 > file && git commit -q -am c2
+  git branch other
+  git branch to-delete
+)
+
+git clone -q --bare base server.git
+git clone -q base worktree
+
+git clone -q server.git local
+(cd local
+  git branch other origin/other
+  echo 3 > file && git commit -q -am c3
+  git checkout -q other
+  git reset -q --hard HEAD~1
+  echo diverged > file && git commit -q -am diverged
+  git checkout -q main
+)
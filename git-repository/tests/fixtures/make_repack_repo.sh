@@ -0,0 +1,31 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config gc.auto 0
+
+mkdir many
+for n in $(seq 30); do
+  echo "$n" > many/$n
+done
+git add many
+git commit -q -m "many files"
+git repack -q -d
+
+for n in 1 2 3; do
+  echo "file $n" > file$n
+  git add file$n
+  git commit -q -m "file $n"
+  git repack -q -d
+done
+
+git tag -a -m "annotated" v1 HEAD~1
+echo loose > loose
+git add loose
+git commit -q -m "loose"
+echo staged > staged
+git add staged
+
+echo "unreachable" | git hash-object -w --stdin > unreachable-id
+unreachable_path=.git/objects/$(cut -c1-2 unreachable-id)/$(cut -c3- unreachable-id)
+touch -d "2000-01-01 00:00:00" "$unreachable_path"
@@ -0,0 +1,31 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config gc.auto 0
+
+echo base > base
+git add base
+git commit -q -m "base"
+git repack -q -d
+
+git worktree add -q --detach wt
+(cd wt
+  echo bisect > bisect
+  git add bisect
+  git commit -q -m "only reachable from refs/bisect/bad of the worktree"
+  git update-ref --no-deref refs/bisect/bad HEAD
+  git rev-parse HEAD > ../bisect-commit
+
+  git checkout -q --detach HEAD~1
+  echo detached > detached
+  git add detached
+  git commit -q -m "only reachable from the HEAD of the worktree"
+  git rev-parse HEAD > ../worktree-head
+
+  echo staged > staged
+  git add staged
+  git rev-parse :staged > ../staged-blob
+)
+# No reflog may keep the objects alive.
+rm -R .git/worktrees/wt/logs .git/logs
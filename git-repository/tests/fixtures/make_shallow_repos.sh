@@ -0,0 +1,28 @@
+#!/bin/bash
+set -eu -o pipefail
+
+function commit() {
+  local message=${1:?} time=${2:?}
+  GIT_AUTHOR_DATE="$time +0000" GIT_COMMITTER_DATE="$time +0000" git commit -q -am "$message"
+}
+
+git init -q base
+(cd base
+  git checkout -q -b main
+  echo 0 > file && git add file
+  for n in $(seq 10); do
+    echo $n > file && commit "c$n" $((1000000000 + n * 1000))
+    if [ "$n" = 7 ]; then
+      git tag v7
+    fi
+  done
+)
+
+git clone -q --depth 1 "file://$PWD/base" shallow
+
+# A full clone made shallow afterwards still has the parents of the shallow commit, which are also listed in its commit-graph.
+git clone -q "file://$PWD/base" shallow-with-commit-graph
+(cd shallow-with-commit-graph
+  git commit-graph write --no-progress --reachable
+  git fetch -q --depth 1 origin
+)
@@ -0,0 +1,31 @@
+#!/bin/bash
+set -eu -o pipefail
+
+function commit() {
+  local message=${1:?} time=${2:?}
+  GIT_AUTHOR_DATE="$time +0000" GIT_COMMITTER_DATE="$time +0000" git commit -q -am "$message"
+}
+
+git init -q server
+(cd server
+  git checkout -q -b main
+  git config uploadpack.allowFilter true
+  git config uploadpack.allowRefInWant true
+  echo 1 > file && seq 2000 > large && git add file large
+  commit c1 1000000000
+  echo 2 > file && commit c2 1000100000
+  echo 3 > file && commit c3 1000200000
+  git tag -m "annotated" v1
+  echo 4 > file && commit c4 1000300000
+  git branch other HEAD~1
+  git checkout -q other
+  echo other > file && commit o1 1000400000
+  git checkout -q main
+  echo 5 > file && commit c5 1000500000
+  git repack -adq
+)
+
+cp -R server server-with-bitmap
+(cd server-with-bitmap
+  git repack -adbq
+)
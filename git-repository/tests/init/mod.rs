@@ -71,6 +71,61 @@ mod non_bare {
         );
         Ok(())
     }
+    #[test]
+    fn init_with_sha256_object_format() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        let repo: git::Repository = git::ThreadSafeRepository::init_opts(
+            tmp.path(),
+            git::create::Kind::WithWorktree,
+            git::create::Options {
+                object_hash: git::hash::Kind::Sha256,
+                ..Default::default()
+            },
+            git::open::Options::isolated(),
+        )?
+        .into();
+        assert_eq!(repo.object_hash(), git::hash::Kind::Sha256);
+        assert_eq!(
+            repo.config_snapshot()
+                .string("extensions.objectFormat")
+                .expect("set")
+                .as_ref(),
+            "sha256"
+        );
+
+        let blob_id = repo.write_blob(b"")?;
+        assert_eq!(
+            blob_id.detach(),
+            git::hash::ObjectId::from_hex(b"473a0f4c3be8a93681a267e3b1e9a7dcda1185436fe141f7749120a303721813")?,
+            "hashes are computed with the configured object format"
+        );
+        let tree = git::objs::Tree {
+            entries: vec![git::objs::tree::Entry {
+                mode: git::objs::tree::EntryMode::Blob,
+                filename: "empty".into(),
+                oid: blob_id.detach(),
+            }],
+        };
+        let tree_id = repo.write_object(&tree)?;
+        let commit_id = repo.commit("HEAD", "initial", tree_id, git::commit::NO_PARENT_IDS)?;
+
+        let repo = git::open_opts(repo.git_dir(), git::open::Options::isolated())?;
+        assert_eq!(
+            repo.object_hash(),
+            git::hash::Kind::Sha256,
+            "the object format persists"
+        );
+        let tree = repo.find_object(commit_id)?.into_commit().tree()?;
+        let entries = tree.iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].oid(),
+            blob_id,
+            "trees are decoded using the 32 byte hash length"
+        );
+        Ok(())
+    }
+
     #[test]
     fn init_into_empty_directory_creates_a_dot_git_dir() -> crate::Result {
         let tmp = tempfile::tempdir()?;
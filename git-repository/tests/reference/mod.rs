@@ -19,6 +19,67 @@ mod log {
             "rebase (merge): the subject"
         );
     }
+
+    #[test]
+    fn expire_options_are_configurable() -> crate::Result {
+        use std::time::{Duration, UNIX_EPOCH};
+        const DAY: u32 = 24 * 60 * 60;
+
+        let (repo, tmp) = crate::basic_rw_repo()?;
+        let now = UNIX_EPOCH + Duration::from_secs(200 * DAY as u64);
+        let options = repo.reflog_expire_options(now)?;
+        assert_eq!(options.older_than, Some(110 * DAY), "90 days by default");
+        assert_eq!(options.unreachable_older_than, Some(170 * DAY), "30 days by default");
+
+        let mut config = std::fs::OpenOptions::new()
+            .append(true)
+            .open(repo.git_dir().join("config"))?;
+        std::io::Write::write_all(
+            &mut config,
+            b"[gc]\n\treflogExpire = never\n\treflogExpireUnreachable = 2.weeks.ago\n",
+        )?;
+        let repo = git::open_opts(tmp.path(), crate::restricted())?;
+        let options = repo.reflog_expire_options(now)?;
+        assert_eq!(options.older_than, None);
+        assert_eq!(options.unreachable_older_than, Some(186 * DAY));
+        Ok(())
+    }
+
+    #[test]
+    fn expire() -> crate::Result {
+        let (repo, _tmp) = crate::basic_rw_repo()?;
+        let main = repo.find_reference("refs/heads/main")?;
+        let mut options = git::refs::file::log::expire::Options {
+            older_than: Some(u32::MAX),
+            ..Default::default()
+        };
+        options.write.dry_run = true;
+        let outcome = main.log_expire(options)?;
+        assert_eq!((outcome.kept, outcome.removed), (0, 2), "all entries would be removed");
+        assert_eq!(main.log_iter().all()?.expect("present").count(), 2, "nothing changed");
+
+        let fixture_time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(946_771_200);
+        let outcome = main.log_expire(repo.reflog_expire_options(fixture_time)?)?;
+        assert_eq!(
+            (outcome.kept, outcome.removed),
+            (2, 0),
+            "nothing is old enough to be removed"
+        );
+
+        let outcome = main.log_expire(repo.reflog_expire_options(std::time::SystemTime::now())?)?;
+        assert_eq!(
+            (outcome.kept, outcome.removed),
+            (0, 2),
+            "everything is older than 90 days"
+        );
+        assert_eq!(main.log_iter().all()?.expect("present").count(), 0);
+        assert_eq!(
+            repo.find_reference("main")?.id(),
+            main.id(),
+            "the reference itself is unaffected"
+        );
+        Ok(())
+    }
 }
 mod find {
     use std::convert::TryInto;
@@ -84,4 +145,36 @@ fn set_target_id() {
         .starts_with("Reference \"refs/heads/main\" was supposed to exist"));
 }
 
+#[test]
+fn pack_refs() -> crate::Result {
+    let (repo, _tmp) = crate::basic_rw_repo()?;
+    let head_id = repo.head_id()?;
+    let tag = repo.tag(
+        "annotated",
+        head_id,
+        git_repository::objs::Kind::Commit,
+        None,
+        "message",
+        git_repository::refs::transaction::PreviousValue::MustNotExist,
+    )?;
+    let tag_id = tag.id();
+    assert_ne!(tag_id, head_id, "it points to a tag object");
+
+    let outcome = repo.pack_refs(git_repository::refs::file::pack_refs::Options { all: true, prune: true })?;
+    assert_eq!(outcome.packed, 2, "the main branch and the tag");
+    let loose_store = repo.refs.loose_store().expect("references are stored as files");
+    assert_eq!(loose_store.loose_iter()?.count(), 0, "all loose references were pruned");
+
+    let packed = loose_store.open_packed_buffer()?.expect("written");
+    let packed_tag = packed.find("annotated")?;
+    assert_eq!(packed_tag.target(), tag_id);
+    assert_eq!(packed_tag.object(), head_id, "annotated tags are peeled");
+    assert_eq!(
+        repo.head_id()?,
+        head_id,
+        "HEAD still resolves through the packed branch"
+    );
+    Ok(())
+}
+
 mod remote;
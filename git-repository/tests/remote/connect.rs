@@ -49,4 +49,88 @@ mod blocking_io {
             Ok(())
         }
     }
+
+    mod ssh {
+        use git_features::progress;
+        use git_repository as git;
+        use git_repository::remote::Direction::Fetch;
+
+        use crate::remote;
+
+        #[test]
+        fn configured_command_and_variant_are_used() -> crate::Result {
+            for (command, variant, expect_error) in [
+                ("ssh", None, false),
+                ("ssh", Some("simple"), true),
+                ("false", None, true),
+            ] {
+                let mut repo = remote::repo("base");
+                {
+                    let mut config = repo.config_snapshot_mut();
+                    config.set_raw_value("core", None, "sshCommand", command)?;
+                    if let Some(variant) = variant {
+                        config.set_raw_value("ssh", None, "variant", variant)?;
+                    }
+                }
+                let remote = repo.remote_at("ssh://host.example:2222/repo")?;
+                match remote.connect(Fetch, progress::Discard).map(drop) {
+                    Ok(_) => assert!(!expect_error, "{} {:?} can be told the port", command, variant),
+                    Err(git::remote::connect::Error::Connect(
+                        git::protocol::transport::client::connect::Error::Connection(err),
+                    )) => {
+                        assert!(expect_error, "{} {:?} is expected to be ssh", command, variant);
+                        assert!(
+                            matches!(
+                                err.downcast_ref::<git::protocol::transport::client::ssh::Error>(),
+                                Some(git::protocol::transport::client::ssh::Error::UnsupportedOption {
+                                    kind: git::protocol::transport::client::ssh::ProgramKind::Simple,
+                                    ..
+                                })
+                            ),
+                            "simple programs can't be told the port, and 'false' fails the probe with -G"
+                        )
+                    }
+                    Err(err) => panic!("unexpected error: {:?}", err),
+                }
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn configured_ip_version_is_passed_to_the_program() -> crate::Result {
+            for (ip_version, expected_option) in [(None, None), (Some("4"), Some("-4")), (Some("6"), Some("-6"))] {
+                let mut repo = remote::repo("base");
+                {
+                    let mut config = repo.config_snapshot_mut();
+                    config.set_raw_value("core", None, "sshCommand", "simple-ssh-program")?;
+                    config.set_raw_value("ssh", None, "variant", "simple")?;
+                    if let Some(ip_version) = ip_version {
+                        config.set_raw_value("gitoxide", Some("ssh"), "ipVersion", ip_version)?;
+                    }
+                }
+                let remote = repo.remote_at("ssh://host.example/repo")?;
+                match (remote.connect(Fetch, progress::Discard).map(drop), expected_option) {
+                    (Ok(_), None) => {}
+                    (
+                        Err(git::remote::connect::Error::Connect(
+                            git::protocol::transport::client::connect::Error::Connection(err),
+                        )),
+                        Some(expected_option),
+                    ) => assert!(
+                        matches!(
+                            err.downcast_ref::<git::protocol::transport::client::ssh::Error>(),
+                            Some(git::protocol::transport::client::ssh::Error::UnsupportedOption {
+                                kind: git::protocol::transport::client::ssh::ProgramKind::Simple,
+                                option,
+                            }) if *option == expected_option
+                        ),
+                        "simple programs can't be told to use {}, which shows it was configured",
+                        expected_option
+                    ),
+                    (res, expected_option) => panic!("unexpected result for {:?}: {:?}", expected_option, res.err()),
+                }
+            }
+            Ok(())
+        }
+    }
 }
@@ -69,7 +69,7 @@ mod blocking_and_async_io {
                 assert_eq!(update_refs.edits.len(), 1);
                 assert!(!write_pack_bundle.keep_path.as_deref().map_or(false, |p| p.is_file()), ".keep files are deleted if at least one ref-edit was made or the pack is empty");
             },
-            _ => unreachable!("Naive negotiation sends the same have and wants, resulting in an empty pack (technically no change, but we don't detect it) - empty packs are fine")
+            _ => unreachable!("The negotiation sends the same have and wants, resulting in an empty pack (technically no change, but we don't detect it) - empty packs are fine")
         }
         Ok(())
     }
@@ -102,7 +102,7 @@ mod blocking_and_async_io {
                 assert_eq!(update_refs.edits.len(), 0);
                 assert!(write_pack_bundle.keep_path.as_deref().map_or(false, |p| p.is_file()), ".keep are kept if there was no edit to bind the packs objects to our commit graph");
             },
-            _ => unreachable!("Naive negotiation sends the same have and wants, resulting in an empty pack (technically no change, but we don't detect it) - empty packs are fine")
+            _ => unreachable!("The negotiation sends the same have and wants, resulting in an empty pack (technically no change, but we don't detect it) - empty packs are fine")
         }
         Ok(())
     }
@@ -257,3 +257,210 @@ mod blocking_and_async_io {
         Ok(())
     }
 }
+
+#[cfg(feature = "blocking-network-client")]
+mod negotiation {
+    use std::sync::atomic::AtomicBool;
+
+    use git_features::progress;
+    use git_repository as git;
+    use git_repository::remote::{fetch, Direction::Fetch};
+
+    fn local_repo(strict: bool) -> crate::Result<(git::Repository, git_testtools::tempfile::TempDir)> {
+        let dir = git_testtools::scripted_fixture_repo_writable_with_args(
+            "make_fetch_negotiation_repos.sh",
+            None::<String>,
+            git_testtools::Creation::ExecuteScript,
+        )?;
+        let repo = git::open_opts(
+            dir.path().join("local"),
+            git::open::Options::isolated().strict_config(strict),
+        )?;
+        Ok((repo, dir))
+    }
+
+    fn fetch_with(
+        algorithm: Option<&str>,
+        version: git::protocol::transport::Protocol,
+        strict: bool,
+    ) -> crate::Result<(
+        Result<fetch::Outcome, git::remote::fetch::Error>,
+        git_testtools::tempfile::TempDir,
+    )> {
+        let (mut repo, tmp) = local_repo(strict)?;
+        {
+            let mut config = repo.config_snapshot_mut();
+            config.set_raw_value("protocol", None, "version", (version as u8).to_string().as_str())?;
+            if let Some(algorithm) = algorithm {
+                config.set_raw_value("fetch", None, "negotiationAlgorithm", algorithm)?;
+            }
+        }
+        let remote = repo.find_remote("origin")?;
+        let outcome = remote
+            .connect(Fetch, progress::Discard)?
+            .prepare_fetch(Default::default())?
+            .receive(&AtomicBool::default());
+        Ok((outcome, tmp))
+    }
+
+    fn received_objects(outcome: fetch::Outcome) -> u32 {
+        match outcome.status {
+            fetch::Status::Change { write_pack_bundle, .. } => write_pack_bundle.index.num_objects,
+            _ => unreachable!("the remote has new commits and this isn't a dry-run"),
+        }
+    }
+
+    #[test]
+    fn consecutive_and_skipping_only_fetch_what_is_missing() -> crate::Result {
+        for version in [
+            git::protocol::transport::Protocol::V1,
+            git::protocol::transport::Protocol::V2,
+        ] {
+            for algorithm in [None, Some("consecutive"), Some("default"), Some("skipping")] {
+                let (outcome, _tmp) = fetch_with(algorithm, version, true)?;
+                assert_eq!(
+                    received_objects(outcome?),
+                    3,
+                    "{:?} {:?}: the tip of main and the new branch, which share a tree, despite the many local commits and an unrelated tracking branch",
+                    algorithm,
+                    version
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn noop_fetches_everything() -> crate::Result {
+        for version in [
+            git::protocol::transport::Protocol::V1,
+            git::protocol::transport::Protocol::V2,
+        ] {
+            let (outcome, _tmp) = fetch_with(Some("noop"), version, true)?;
+            assert_eq!(
+                received_objects(outcome?),
+                33,
+                "{:?}: all commits, trees and blobs",
+                version
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_algorithms_are_an_error_unless_config_is_lenient() -> crate::Result {
+        let (outcome, _tmp) = fetch_with(Some("unknown"), git::protocol::transport::Protocol::V2, true)?;
+        assert!(matches!(
+            outcome.unwrap_err(),
+            git::remote::fetch::Error::UnknownNegotiationAlgorithm { name } if name == "unknown"
+        ));
+
+        let (outcome, _tmp) = fetch_with(Some("unknown"), git::protocol::transport::Protocol::V2, false)?;
+        assert_eq!(received_objects(outcome?), 3, "the default algorithm is used instead");
+        Ok(())
+    }
+}
+
+#[cfg(feature = "blocking-network-client")]
+mod shallow {
+    use std::sync::atomic::AtomicBool;
+
+    use git_features::progress;
+    use git_repository as git;
+    use git_repository::remote::{
+        fetch::{self, Shallow},
+        Direction::Fetch,
+    };
+
+    fn shallow_repo(protocol_version: &str) -> crate::Result<(git::Repository, git_testtools::tempfile::TempDir)> {
+        let dir = git_testtools::scripted_fixture_repo_writable_with_args(
+            "make_shallow_repos.sh",
+            None::<String>,
+            git_testtools::Creation::ExecuteScript,
+        )?;
+        let repo = git::open_opts(
+            dir.path().join("shallow"),
+            git::open::Options::isolated().config_overrides(Some(format!("protocol.version={protocol_version}"))),
+        )?;
+        Ok((repo, dir))
+    }
+
+    fn fetch_with(repo: &git::Repository, shallow: Shallow) -> crate::Result<fetch::Outcome> {
+        Ok(repo
+            .find_remote("origin")?
+            .connect(Fetch, progress::Discard)?
+            .prepare_fetch(Default::default())?
+            .with_shallow(shallow)
+            .receive(&AtomicBool::default())?)
+    }
+
+    fn history_len(repo: &git::Repository) -> crate::Result<usize> {
+        Ok(repo
+            .find_reference("refs/remotes/origin/main")?
+            .id()
+            .ancestors()
+            .all()?
+            .error_on_missing_commit()
+            .collect::<Result<Vec<_>, _>>()?
+            .len())
+    }
+
+    fn id_of(repo: &git::Repository, spec: &str) -> crate::Result<git::ObjectId> {
+        Ok(repo.rev_parse_single(spec)?.detach())
+    }
+
+    #[test]
+    fn fetching_without_changes_keeps_the_repository_shallow() -> crate::Result {
+        for version in ["1", "2"] {
+            let (repo, _tmp) = shallow_repo(version)?;
+            let outcome = fetch_with(&repo, Shallow::NoChange)?;
+            assert!(matches!(outcome.status, fetch::Status::NoChange), "{version}");
+            assert_eq!(
+                repo.shallow_commits()?,
+                Some(vec![repo.head_id()?.detach()]),
+                "{version}"
+            );
+            assert_eq!(history_len(&repo)?, 1, "{version}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn deepen_relative_to_the_shallow_boundary() -> crate::Result {
+        for version in ["1", "2"] {
+            let (repo, _tmp) = shallow_repo(version)?;
+            fetch_with(&repo, Shallow::Deepen(2))?;
+            assert_eq!(
+                repo.shallow_commits()?,
+                Some(vec![id_of(&repo, "origin/main~2")?]),
+                "{version}: the previous boundary was unshallowed"
+            );
+            assert_eq!(history_len(&repo)?, 3, "{version}");
+
+            fetch_with(&repo, Shallow::Deepen(1))?;
+            assert_eq!(history_len(&repo)?, 4, "{version}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn depth_at_remote() -> crate::Result {
+        let (repo, _tmp) = shallow_repo("2")?;
+        fetch_with(&repo, Shallow::DepthAtRemote(5.try_into().expect("non-zero")))?;
+        assert_eq!(repo.shallow_commits()?, Some(vec![id_of(&repo, "origin/main~4")?]));
+        assert_eq!(history_len(&repo)?, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn undo_makes_the_repository_complete() -> crate::Result {
+        for version in ["1", "2"] {
+            let (repo, _tmp) = shallow_repo(version)?;
+            fetch_with(&repo, Shallow::undo())?;
+            assert!(!repo.is_shallow(), "{version}");
+            assert!(!repo.shallow_file().exists(), "{version}");
+            assert_eq!(history_len(&repo)?, 10, "{version}");
+        }
+        Ok(())
+    }
+}
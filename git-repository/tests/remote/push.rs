@@ -0,0 +1,298 @@
+#[cfg(feature = "blocking-network-client")]
+mod blocking_io {
+    use std::sync::atomic::AtomicBool;
+
+    use git_repository as git;
+    use git_repository::{
+        bstr::ByteSlice,
+        remote::{
+            push::{Options, Status},
+            Direction::Push,
+        },
+    };
+
+    fn repo_rw() -> crate::Result<(git::Repository, git_testtools::tempfile::TempDir)> {
+        let dir = git_testtools::scripted_fixture_repo_writable_with_args(
+            "make_push_repos.sh",
+            None::<String>,
+            git_testtools::Creation::ExecuteScript,
+        )?;
+        let repo = git::open_opts(dir.path().join("local"), git::open::Options::isolated())?;
+        Ok((repo, dir))
+    }
+
+    fn push(
+        repo: &git::Repository,
+        remote_name: &str,
+        specs: &[&str],
+        options: Options,
+    ) -> crate::Result<git::remote::push::Outcome> {
+        let mut remote = repo.find_remote(remote_name)?;
+        remote.replace_refspecs(specs.iter().copied(), Push)?;
+        let outcome = remote
+            .connect(Push, git::progress::Discard)?
+            .push(options, &AtomicBool::default())?;
+        Ok(outcome)
+    }
+
+    fn remote_id(
+        dir: &git_testtools::tempfile::TempDir,
+        name: &str,
+        reference: &str,
+    ) -> crate::Result<Option<git::ObjectId>> {
+        let remote = git::open_opts(dir.path().join(name), git::open::Options::isolated())?;
+        let id = remote
+            .try_find_reference(reference)?
+            .map(|r| r.target().try_id().expect("direct ref").to_owned());
+        Ok(id)
+    }
+
+    fn statuses(outcome: &git::remote::push::Outcome) -> Vec<(&str, Status)> {
+        outcome
+            .updates
+            .iter()
+            .map(|update| (update.remote.to_str().expect("valid UTF-8"), update.status.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn fast_forwards_new_refs_and_deletions_are_sent_with_only_the_missing_objects() -> crate::Result {
+        let (repo, dir) = repo_rw()?;
+        let c3 = repo.rev_parse_single("main")?.detach();
+        let v2 = repo.rev_parse_single("refs/tags/v2")?.detach();
+        let outcome = push(
+            &repo,
+            "origin",
+            &["main", "refs/heads/main:new-branch", "refs/tags/v2", ":to-delete"],
+            Default::default(),
+        )?;
+        assert!(outcome.is_success());
+        assert_eq!(
+            statuses(&outcome),
+            vec![
+                ("refs/heads/main", Status::Ok { forced: false }),
+                ("refs/heads/new-branch", Status::Ok { forced: false }),
+                ("refs/tags/v2", Status::Ok { forced: false }),
+                ("refs/heads/to-delete", Status::Ok { forced: false }),
+            ]
+        );
+        assert_eq!(
+            outcome.updates[0].local.as_ref().map(|name| name.as_bstr()),
+            Some("refs/heads/main".into())
+        );
+        assert_eq!(outcome.updates[3].local, None);
+        assert_eq!(outcome.updates[3].new, None, "the deletion");
+        assert_eq!(
+            outcome.num_objects, 4,
+            "the new commit, its tree and blob along with the tag object, but nothing the remote already has"
+        );
+
+        assert_eq!(remote_id(&dir, "remote.git", "refs/heads/main")?, Some(c3));
+        assert_eq!(remote_id(&dir, "remote.git", "refs/heads/new-branch")?, Some(c3));
+        assert_eq!(remote_id(&dir, "remote.git", "refs/tags/v2")?, Some(v2));
+        assert_eq!(remote_id(&dir, "remote.git", "refs/heads/to-delete")?, None);
+
+        assert_eq!(
+            outcome.tracking_ref_edits.len(),
+            3,
+            "tags have no tracking reference according to the fetch ref-specs"
+        );
+        assert_eq!(repo.rev_parse_single("refs/remotes/origin/main")?, c3);
+        assert_eq!(repo.rev_parse_single("refs/remotes/origin/new-branch")?, c3);
+        assert!(repo.try_find_reference("refs/remotes/origin/to-delete")?.is_none());
+        let tracking = repo.find_reference("refs/remotes/origin/main")?;
+        let mut log = tracking.log_iter();
+        let line = log.rev()?.expect("log present").next().expect("one line")?;
+        assert_eq!(line.message, "update by push");
+
+        let outcome = push(&repo, "origin", &["main"], Default::default())?;
+        assert_eq!(statuses(&outcome), vec![("refs/heads/main", Status::UpToDate)]);
+        assert_eq!(outcome.num_objects, 0, "nothing was sent");
+        Ok(())
+    }
+
+    #[test]
+    fn all_objects_are_sent_to_empty_remotes() -> crate::Result {
+        let (repo, dir) = repo_rw()?;
+        let outcome = push(&repo, "empty", &["HEAD"], Default::default())?;
+        assert_eq!(
+            statuses(&outcome),
+            vec![("refs/heads/main", Status::Ok { forced: false })],
+            "HEAD as destination is the branch it points to"
+        );
+        assert_eq!(outcome.num_objects, 9, "three commits with a tree and a blob each");
+        assert_eq!(
+            remote_id(&dir, "empty.git", "refs/heads/main")?,
+            Some(repo.rev_parse_single("main")?.detach())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn non_fast_forwards_and_existing_tags_are_rejected_unless_forced() -> crate::Result {
+        let (repo, dir) = repo_rw()?;
+        let previous = remote_id(&dir, "remote.git", "refs/heads/other")?;
+        let outcome = push(
+            &repo,
+            "origin",
+            &["other", "refs/tags/v2:refs/tags/v1", "to-delete"],
+            Default::default(),
+        )?;
+        assert!(!outcome.is_success());
+        assert_eq!(
+            statuses(&outcome),
+            vec![
+                ("refs/heads/other", Status::RejectedNonFastForward),
+                ("refs/tags/v1", Status::RejectedAlreadyExists),
+                ("refs/heads/to-delete", Status::UpToDate),
+            ]
+        );
+        assert_eq!(outcome.num_objects, 0);
+        assert_eq!(
+            remote_id(&dir, "remote.git", "refs/heads/other")?,
+            previous,
+            "nothing changed"
+        );
+
+        let outcome = push(
+            &repo,
+            "origin",
+            &["+other", "+refs/tags/v2:refs/tags/v1"],
+            Default::default(),
+        )?;
+        assert!(outcome.is_success());
+        assert_eq!(
+            statuses(&outcome),
+            vec![
+                ("refs/heads/other", Status::Ok { forced: true }),
+                ("refs/tags/v1", Status::Ok { forced: true }),
+            ]
+        );
+        assert_eq!(
+            remote_id(&dir, "remote.git", "refs/heads/other")?,
+            Some(repo.rev_parse_single("other")?.detach())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn atomic_pushes_send_nothing_if_one_update_is_rejected() -> crate::Result {
+        let (repo, dir) = repo_rw()?;
+        let previous = remote_id(&dir, "remote.git", "refs/heads/main")?;
+        let outcome = push(
+            &repo,
+            "origin",
+            &["main", "other"],
+            Options {
+                atomic: true,
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(
+            statuses(&outcome),
+            vec![
+                ("refs/heads/main", Status::RejectedAtomicPushFailed),
+                ("refs/heads/other", Status::RejectedNonFastForward),
+            ]
+        );
+        assert_eq!(remote_id(&dir, "remote.git", "refs/heads/main")?, previous);
+        Ok(())
+    }
+
+    #[test]
+    fn rejections_by_the_remote_are_reported_per_reference() -> crate::Result {
+        let (repo, dir) = repo_rw()?;
+        let outcome = push(&repo, "strict", &["main", "+other", ":to-delete"], Default::default())?;
+        assert_eq!(outcome.unpack_error, None);
+        assert_eq!(
+            statuses(&outcome),
+            vec![
+                ("refs/heads/main", Status::Ok { forced: false }),
+                (
+                    "refs/heads/other",
+                    Status::RejectedByRemote {
+                        reason: "non-fast-forward".into()
+                    }
+                ),
+                (
+                    "refs/heads/to-delete",
+                    Status::RejectedByRemote {
+                        reason: "deletion prohibited".into()
+                    }
+                ),
+            ]
+        );
+        assert_eq!(
+            remote_id(&dir, "strict.git", "refs/heads/main")?,
+            Some(repo.rev_parse_single("main")?.detach())
+        );
+        assert!(remote_id(&dir, "strict.git", "refs/heads/to-delete")?.is_some());
+        assert_eq!(outcome.tracking_ref_edits.len(), 1, "only accepted updates are tracked");
+        assert!(repo.try_find_reference("refs/remotes/strict/main")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn new_objects_are_sent_as_deltas_against_objects_the_remote_has() -> crate::Result {
+        let (repo, dir) = repo_rw()?;
+        let git = |dir: &std::path::Path, args: &[&str]| -> crate::Result {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=a", "-c", "user.email=a@example.com"])
+                .args(args)
+                .current_dir(dir)
+                .status()?;
+            assert!(status.success(), "git {:?} failed", args);
+            Ok(())
+        };
+        let (local, remote) = (dir.path().join("local"), dir.path().join("remote.git"));
+        // Keep received packs as they are so we can see how objects were sent.
+        git(&remote, &["config", "receive.unpackLimit", "1"])?;
+        let lines: Vec<_> = (0..500).map(|n| format!("line {}\n", n)).collect();
+        std::fs::write(local.join("big"), lines.concat())?;
+        git(&local, &["add", "big"])?;
+        git(&local, &["commit", "-q", "-m", "big"])?;
+        assert!(push(&repo, "origin", &["main"], Default::default())?.is_success());
+
+        std::fs::write(local.join("big"), lines[..499].concat() + "changed\n")?;
+        git(&local, &["commit", "-q", "-am", "change big"])?;
+        let outcome = push(&repo, "origin", &["main"], Default::default())?;
+        assert!(outcome.is_success());
+        assert_eq!(
+            outcome.num_objects, 3,
+            "commit, tree and blob, but not the base of the blob"
+        );
+
+        let blob = repo.rev_parse_single("main:big")?.detach();
+        let mut num_packs_with_blob = 0;
+        for entry in std::fs::read_dir(remote.join("objects/pack"))? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("idx") {
+                continue;
+            }
+            let index = git::odb::pack::index::File::at(&path, git::hash::Kind::Sha1)?;
+            if let Some(idx) = index.lookup(blob) {
+                let pack = git::odb::pack::data::File::at(path.with_extension("pack"), git::hash::Kind::Sha1)?;
+                let entry = pack.entry(index.pack_offset_at_index(idx))?;
+                assert!(
+                    entry.header.is_delta(),
+                    "the changed blob is a delta against the previous version, which the remote has"
+                );
+                num_packs_with_blob += 1;
+            }
+        }
+        assert_eq!(num_packs_with_blob, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn push_ref_specs_are_required() -> crate::Result {
+        let (repo, _dir) = repo_rw()?;
+        let err = repo
+            .find_remote("origin")?
+            .connect(Push, git::progress::Discard)?
+            .push(Default::default(), &AtomicBool::default())
+            .unwrap_err();
+        assert!(matches!(err, git::remote::push::Error::MissingRefSpecs));
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use git_repository as git;
+use git_testtools::tempfile;
+
+fn repo_rw() -> crate::Result<(git::Repository, tempfile::TempDir)> {
+    crate::repo_rw("make_branch_repo.sh")
+}
+
+/// Return the previous and new object id along with the message of the most recent reflog entry of `name`.
+fn last_log_entry(repo: &git::Repository, name: &str) -> crate::Result<(git::ObjectId, git::ObjectId, String)> {
+    let reference = repo.find_reference(name)?;
+    let mut log = reference.log_iter();
+    let line = log.rev()?.expect("log present").next().expect("one line")?;
+    Ok((line.previous_oid, line.new_oid, line.message.to_string()))
+}
+
+fn head_name(repo: &git::Repository) -> crate::Result<String> {
+    Ok(repo.head_name()?.expect("not detached").as_bstr().to_string())
+}
+
+fn local_config(repo: &git::Repository) -> crate::Result<String> {
+    Ok(std::fs::read_to_string(repo.common_dir().join("config"))?)
+}
+
+#[test]
+fn head_can_be_set_to_branches_and_be_detached() -> crate::Result {
+    let (repo, _keep) = repo_rw()?;
+    let c2 = repo.rev_parse_single("main")?.detach();
+    let c1 = repo.rev_parse_single("main~1")?.detach();
+
+    let other = repo.create_branch("other", "main~1", false)?;
+    assert_eq!(other.name().as_bstr(), "refs/heads/other");
+    assert_eq!(other.id(), c1);
+    assert_eq!(
+        last_log_entry(&repo, "refs/heads/other")?,
+        (repo.object_hash().null(), c1, "branch: Created from main~1".into())
+    );
+
+    repo.set_head_to_branch("other")?;
+    assert_eq!(head_name(&repo)?, "refs/heads/other");
+    assert_eq!(
+        last_log_entry(&repo, "HEAD")?,
+        (c2, c1, "checkout: moving from main to other".into())
+    );
+
+    repo.detach_head(c2)?;
+    assert!(repo.head()?.is_detached());
+    assert_eq!(repo.head_id()?, c2);
+    assert_eq!(
+        last_log_entry(&repo, "HEAD")?,
+        (c1, c2, format!("checkout: moving from other to {}", c2))
+    );
+
+    repo.set_head_to_branch("main")?;
+    assert_eq!(
+        last_log_entry(&repo, "HEAD")?,
+        (c2, c2, format!("checkout: moving from {} to main", c2)),
+        "the change is logged even though the object id stays the same"
+    );
+    Ok(())
+}
+
+#[test]
+fn existing_branches_are_only_reset_if_forced_and_not_checked_out() -> crate::Result {
+    let (repo, _keep) = repo_rw()?;
+    let c2 = repo.rev_parse_single("main")?.detach();
+    assert!(matches!(
+        repo.create_branch("merged", "main", false),
+        Err(git::branch::create::Error::Exists { .. })
+    ));
+
+    let merged = repo.create_branch("merged", "main", true)?;
+    assert_eq!(merged.id(), c2);
+    assert_eq!(last_log_entry(&repo, "refs/heads/merged")?.2, "branch: Reset to main");
+
+    assert!(matches!(
+        repo.create_branch("main", "merged", true),
+        Err(git::branch::create::Error::CheckedOut { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn rename_moves_the_reflog_and_updates_head_and_configuration() -> crate::Result {
+    let (mut repo, _keep) = repo_rw()?;
+    let c2 = repo.rev_parse_single("main")?.detach();
+    let num_log_entries = repo.find_reference("main")?.log_iter().all()?.expect("present").count();
+
+    let trunk = repo.rename_branch("main", "trunk", false)?;
+    assert_eq!(trunk.name().as_bstr(), "refs/heads/trunk");
+    assert_eq!(trunk.id(), c2);
+    assert!(repo.try_find_reference("main")?.is_none(), "the old branch is gone");
+    assert_eq!(head_name(&repo)?, "refs/heads/trunk", "HEAD follows the renamed branch");
+
+    let message = "Branch: renamed refs/heads/main to refs/heads/trunk";
+    assert_eq!(
+        repo.find_reference("trunk")?
+            .log_iter()
+            .all()?
+            .expect("present")
+            .count(),
+        num_log_entries + 1,
+        "the reflog was moved and received another entry"
+    );
+    assert_eq!(last_log_entry(&repo, "refs/heads/trunk")?.2, message);
+    assert_eq!(last_log_entry(&repo, "HEAD")?.2, message);
+
+    let config = repo.config_snapshot();
+    assert_eq!(
+        config.string("branch.trunk.remote").as_deref(),
+        Some(git::bstr::BStr::new("origin"))
+    );
+    assert_eq!(
+        config.string("branch.trunk.merge").as_deref(),
+        Some(git::bstr::BStr::new("refs/heads/main")),
+        "values are left as is"
+    );
+    assert!(config.string("branch.main.remote").is_none());
+    let local_config = local_config(&repo)?;
+    assert!(local_config.contains("[branch \"trunk\"]"));
+    assert!(!local_config.contains("[branch \"main\"]"), "the change was written");
+
+    assert!(matches!(
+        repo.rename_branch("unmerged", "merged", false),
+        Err(git::branch::rename::Error::Rename(
+            git::refs::file::rename::Error::Exists { .. }
+        ))
+    ));
+    assert!(matches!(
+        repo.rename_branch("merged", "trunk", true),
+        Err(git::branch::rename::Error::CheckedOut { .. })
+    ));
+
+    repo.rename_branch("unmerged", "merged", true)?;
+    assert!(repo.try_find_reference("unmerged")?.is_none());
+    assert_eq!(
+        repo.config_snapshot().string("branch.merged.remote").as_deref(),
+        Some(git::bstr::BStr::new("origin"))
+    );
+    Ok(())
+}
+
+#[test]
+fn delete_requires_branches_to_be_merged_unless_forced() -> crate::Result {
+    let (mut repo, _keep) = repo_rw()?;
+    assert!(matches!(
+        repo.delete_branch("main", true),
+        Err(git::branch::delete::Error::CheckedOut { .. })
+    ));
+    assert!(matches!(
+        repo.delete_branch("unmerged", false),
+        Err(git::branch::delete::Error::NotFullyMerged { .. })
+    ));
+    assert!(repo.try_find_reference("unmerged")?.is_some(), "nothing changed");
+
+    repo.delete_branch("merged", false)?;
+    assert!(repo.try_find_reference("merged")?.is_none());
+
+    repo.delete_branch("unmerged", true)?;
+    assert!(repo.try_find_reference("unmerged")?.is_none());
+    assert!(
+        !repo.git_dir().join("logs/refs/heads/unmerged").exists(),
+        "the reflog is deleted as well"
+    );
+    assert!(repo.config_snapshot().string("branch.unmerged.remote").is_none());
+    assert!(!local_config(&repo)?.contains("[branch \"unmerged\"]"));
+    assert_eq!(
+        repo.config_snapshot().string("branch.main.remote").as_deref(),
+        Some(git::bstr::BStr::new("origin")),
+        "other branches are unaffected"
+    );
+    Ok(())
+}
+
+#[test]
+fn branches_checked_out_in_other_worktrees_are_protected() -> crate::Result {
+    let (mut repo, _keep) = repo_rw()?;
+    assert!(matches!(
+        repo.delete_branch("in-worktree", true),
+        Err(git::branch::delete::Error::CheckedOut { .. })
+    ));
+    assert!(matches!(
+        repo.create_branch("in-worktree", "main~1", true),
+        Err(git::branch::create::Error::CheckedOut { .. })
+    ));
+    assert!(matches!(
+        repo.rename_branch("merged", "in-worktree", true),
+        Err(git::branch::rename::Error::CheckedOut { .. })
+    ));
+    assert!(repo.try_find_reference("merged")?.is_some(), "nothing changed");
+
+    let mut linked = repo
+        .worktrees()?
+        .pop()
+        .expect("one linked worktree")
+        .into_repo_with_possibly_inaccessible_worktree()?;
+    assert!(
+        matches!(
+            linked.delete_branch("main", true),
+            Err(git::branch::delete::Error::CheckedOut { .. })
+        ),
+        "the branch of the main worktree is protected as well"
+    );
+    Ok(())
+}
+
+#[test]
+fn configuration_changes_respect_the_lock_of_the_configuration_file() -> crate::Result {
+    let (mut repo, _keep) = repo_rw()?;
+    std::fs::write(repo.common_dir().join("config.lock"), "")?;
+    assert!(matches!(
+        repo.delete_branch("unmerged", true),
+        Err(git::branch::delete::Error::UpdateConfig(
+            git::branch::update_config::Error::AcquireLock(_)
+        ))
+    ));
+    assert!(
+        local_config(&repo)?.contains("[branch \"unmerged\"]"),
+        "the configuration file is left alone while someone else changes it"
+    );
+    Ok(())
+}
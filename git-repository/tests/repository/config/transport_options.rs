@@ -1,6 +1,7 @@
 #[cfg(any(
     feature = "blocking-http-transport-reqwest",
-    feature = "blocking-http-transport-curl"
+    feature = "blocking-http-transport-curl",
+    feature = "async-http-transport-reqwest"
 ))]
 mod http {
     use git_repository as git;
@@ -0,0 +1,225 @@
+use std::{collections::BTreeMap, path::Path, sync::atomic::AtomicBool};
+
+use git_repository as git;
+use git_repository::{
+    fsck::{self, Check, Severity},
+    objs::Kind,
+    progress, ObjectId,
+};
+
+use crate::util::named_repo;
+
+fn id(repo: &git::Repository, name: &str) -> crate::Result<ObjectId> {
+    let path = repo.work_dir().unwrap_or_else(|| Path::new(".")).join(name);
+    Ok(ObjectId::from_hex(std::fs::read_to_string(path)?.trim().as_bytes())?)
+}
+
+fn object(repo: &git::Repository, name: &str, kind: Kind) -> crate::Result<fsck::Object> {
+    Ok(fsck::Object {
+        id: id(repo, name)?,
+        kind,
+    })
+}
+
+fn fsck(repo: &git::Repository, options: fsck::Options) -> crate::Result<fsck::Outcome> {
+    Ok(repo.fsck(progress::Discard, &AtomicBool::default(), options)?)
+}
+
+#[test]
+fn missing_objects_broken_links_unreachable_and_dangling_objects_are_reported() -> crate::Result {
+    let repo = named_repo("make_fsck_repo.sh")?;
+    let outcome = fsck(&repo, Default::default())?;
+
+    let missing_blob = id(&repo, "missing-blob-id")?;
+    let side_tree = id(&repo, "side-tree-id")?;
+    assert_eq!(
+        outcome.missing,
+        vec![fsck::Missing {
+            id: missing_blob,
+            kind: Some(Kind::Blob),
+            referenced_by: fsck::Source::Object(side_tree),
+        }]
+    );
+    assert_eq!(
+        outcome.broken_links,
+        vec![fsck::BrokenLink {
+            from: side_tree,
+            to: missing_blob,
+            expected: Kind::Blob,
+            actual: None,
+        }]
+    );
+
+    let mut dangling = vec![
+        object(&repo, "dangling-commit-id", Kind::Commit)?,
+        object(&repo, "dangling-blob-id", Kind::Blob)?,
+        object(&repo, "zero-padded-date-commit-id", Kind::Commit)?,
+        object(&repo, "no-tagger-tag-id", Kind::Tag)?,
+        object(&repo, "zero-padded-mode-tree-id", Kind::Tree)?,
+        object(&repo, "unsorted-tree-id", Kind::Tree)?,
+    ];
+    dangling.sort();
+    assert_eq!(outcome.dangling, dangling);
+
+    let mut unreachable = dangling;
+    unreachable.push(object(&repo, "unreachable-tree-id", Kind::Tree)?);
+    unreachable.push(object(&repo, "unreachable-blob-id", Kind::Blob)?);
+    unreachable.sort();
+    assert_eq!(
+        outcome.unreachable, unreachable,
+        "the tree and blob of the dangling commit are unreachable, but not dangling"
+    );
+    assert_eq!(
+        outcome.num_objects,
+        repo.objects.iter()?.count(),
+        "every object in the database is checked"
+    );
+    assert!(outcome.has_errors());
+    Ok(())
+}
+
+#[test]
+fn unreadable_objects_and_hash_mismatches_are_reported_as_corrupt() -> crate::Result {
+    let repo = named_repo("make_fsck_repo.sh")?;
+    let outcome = fsck(&repo, Default::default())?;
+
+    let unreadable = id(&repo, "unreadable-id")?;
+    let hash_mismatch = id(&repo, "hash-mismatch-id")?;
+    assert_eq!(outcome.corrupt.len(), 2);
+    for corrupt in &outcome.corrupt {
+        match &corrupt.corruption {
+            fsck::Corruption::Unreadable { message } => {
+                assert_eq!(corrupt.id, unreadable);
+                assert!(!message.is_empty());
+            }
+            fsck::Corruption::HashMismatch { actual } => {
+                assert_eq!(corrupt.id, hash_mismatch);
+                assert_eq!(
+                    *actual,
+                    id(&repo, "dangling-blob-id")?,
+                    "it has the content of another blob"
+                );
+            }
+            fsck::Corruption::Collision => unreachable!("there are no colliding objects in the fixture"),
+        }
+    }
+    assert!(
+        outcome
+            .unreachable
+            .iter()
+            .all(|object| object.id != unreadable && object.id != hash_mismatch),
+        "corrupt objects aren't checked any further"
+    );
+    assert!(outcome.has_errors());
+    Ok(())
+}
+
+#[test]
+fn malformed_objects_are_reported_with_their_default_severity() -> crate::Result {
+    let repo = named_repo("make_fsck_repo.sh")?;
+    let outcome = fsck(&repo, Default::default())?;
+
+    let problem = |name: &str, kind: Kind, check: Check, severity: Severity| -> crate::Result<fsck::Problem> {
+        Ok(fsck::Problem {
+            id: id(&repo, name)?,
+            kind,
+            check,
+            severity,
+        })
+    };
+    let mut expected = vec![
+        problem(
+            "zero-padded-date-commit-id",
+            Kind::Commit,
+            Check::ZeroPaddedDate,
+            Severity::Error,
+        )?,
+        problem("no-tagger-tag-id", Kind::Tag, Check::MissingTaggerEntry, Severity::Warn)?,
+        problem(
+            "zero-padded-mode-tree-id",
+            Kind::Tree,
+            Check::ZeroPaddedFilemode,
+            Severity::Warn,
+        )?,
+        problem("unsorted-tree-id", Kind::Tree, Check::DuplicateEntries, Severity::Error)?,
+        problem("unsorted-tree-id", Kind::Tree, Check::TreeNotSorted, Severity::Error)?,
+    ];
+    expected.sort_by_key(|problem| problem.id);
+    assert_eq!(outcome.problems, expected);
+    Ok(())
+}
+
+#[test]
+fn severities_can_be_configured() -> crate::Result {
+    let repo = named_repo("make_fsck_repo.sh")?;
+    let options = repo.fsck_options()?;
+    assert_eq!(
+        options.severities,
+        BTreeMap::from([
+            (Check::MissingTaggerEntry, Severity::Ignore),
+            (Check::ZeroPaddedFilemode, Severity::Error)
+        ])
+    );
+
+    let outcome = fsck(&repo, options)?;
+    let zero_padded_mode_tree = id(&repo, "zero-padded-mode-tree-id")?;
+    assert!(outcome
+        .problems
+        .iter()
+        .all(|problem| problem.check != Check::MissingTaggerEntry));
+    assert!(outcome
+        .problems
+        .iter()
+        .any(|problem| problem.id == zero_padded_mode_tree
+            && problem.check == Check::ZeroPaddedFilemode
+            && problem.severity == Severity::Error));
+    Ok(())
+}
+
+#[test]
+fn invalid_severities_are_rejected_unless_lenient() -> crate::Result {
+    let path = git_testtools::scripted_fixture_repo_read_only("make_fsck_repo.sh")?;
+    let repo = git::open_opts(
+        &path,
+        crate::restricted()
+            .config_overrides(["fsck.badDate=fatal"])
+            .strict_config(true),
+    )?;
+    assert!(matches!(
+        repo.fsck_options(),
+        Err(fsck::options::Error::InvalidSeverity { key, value }) if key == "badDate" && value == "fatal"
+    ));
+
+    let repo = git::open_opts(
+        path,
+        crate::restricted()
+            .config_overrides(["fsck.badDate=fatal"])
+            .strict_config(false),
+    )?;
+    assert!(!repo.fsck_options()?.severities.contains_key(&Check::BadDate));
+    Ok(())
+}
+
+#[test]
+fn parents_of_shallow_commits_are_not_missing() -> crate::Result {
+    let dir = git_testtools::scripted_fixture_repo_read_only("make_shallow_repos.sh")?;
+    let repo = git::open_opts(dir.join("shallow"), crate::restricted())?;
+    let outcome = fsck(&repo, Default::default())?;
+    assert_eq!(outcome.missing, Vec::new(), "the history ends at the shallow boundary");
+    assert_eq!(outcome.broken_links, Vec::new());
+    assert_eq!(outcome.unreachable, Vec::new());
+    Ok(())
+}
+
+#[test]
+fn objects_reachable_from_other_worktrees_are_reachable() -> crate::Result {
+    let repo = named_repo("make_repack_repo_with_worktree.sh")?;
+    let outcome = fsck(&repo, Default::default())?;
+    assert_eq!(
+        outcome.unreachable,
+        Vec::new(),
+        "the HEAD, index and refs/bisect/bad of the linked worktree keep its objects alive"
+    );
+    assert_eq!(outcome.dangling, Vec::new());
+    Ok(())
+}
@@ -1,11 +1,19 @@
 use git_repository::Repository;
 
+mod branch;
 mod config;
+mod fsck;
 mod object;
 mod open;
+#[cfg(feature = "blocking-network-server")]
+mod receive_pack;
 mod reference;
 mod remote;
+mod repack;
+mod shallow;
 mod state;
+#[cfg(feature = "blocking-network-server")]
+mod upload_pack;
 mod worktree;
 
 #[test]
@@ -44,3 +44,153 @@ mod submodules {
         .to_thread_local())
     }
 }
+
+mod object_database {
+    use git_repository as git;
+    use git_repository::odb::pack::data::Mapping;
+
+    fn open_with(overrides: &[&str]) -> crate::Result<git::Repository> {
+        let repo_path = git_testtools::scripted_fixture_repo_read_only("make_basic_repo.sh")?;
+        Ok(git::open_opts(
+            repo_path,
+            crate::restricted().config_overrides(overrides.iter().copied()),
+        )?)
+    }
+
+    #[test]
+    fn packs_are_mapped_entirely_by_default() -> crate::Result {
+        let repo = open_with(&[])?;
+        assert_eq!(repo.objects.store_ref().pack_mapping(), Mapping::Whole);
+        Ok(())
+    }
+
+    #[test]
+    fn packed_git_window_size_and_limit_select_windowed_access() -> crate::Result {
+        let repo = open_with(&["core.packedGitWindowSize=16k", "core.packedGitLimit=1m"])?;
+        assert_eq!(
+            repo.objects.store_ref().pack_mapping(),
+            Mapping::Windowed {
+                window_size: 16 * 1024,
+                limit: 1024 * 1024
+            }
+        );
+        repo.head_commit()?;
+
+        let repo = open_with(&["core.packedGitLimit=1m"])?;
+        assert_eq!(
+            repo.objects.store_ref().pack_mapping(),
+            Mapping::Windowed {
+                window_size: Mapping::DEFAULT_WINDOW_SIZE,
+                limit: 1024 * 1024
+            },
+            "unset values use their defaults"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_packed_git_limits_are_errors_in_strict_mode() -> crate::Result {
+        let repo_path = git_testtools::scripted_fixture_repo_read_only("make_basic_repo.sh")?;
+        let err = git::open_opts(
+            &repo_path,
+            crate::restricted()
+                .strict_config(true)
+                .config_overrides(Some("core.packedGitLimit=-5")),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            git::open::Error::Config(git::config::Error::DecodeInteger {
+                key: "core.packedGitLimit",
+                ..
+            })
+        ));
+
+        let repo = git::open_opts(
+            repo_path,
+            crate::restricted().config_overrides(Some("core.packedGitLimit=-5")),
+        )?;
+        assert_eq!(
+            repo.objects.store_ref().pack_mapping(),
+            Mapping::Whole,
+            "lenient mode ignores invalid values"
+        );
+        Ok(())
+    }
+}
+
+mod ref_storage {
+    use std::convert::TryInto;
+
+    use git_repository as git;
+    use git_repository::refs::{
+        store::{StorageFormat, WriteReflog},
+        transaction::{Change, LogChange, PreviousValue, RefEdit},
+        Target,
+    };
+    use git_testtools::tempfile;
+
+    #[test]
+    fn reftables_are_used_if_configured() -> crate::Result {
+        let tmp = tempfile::tempdir()?;
+        let repo = git::init(tmp.path())?;
+        let id = repo.write_object(git::objs::Tree::empty())?.detach();
+        let git_dir = repo.git_dir().to_owned();
+        drop(repo);
+
+        let config_path = git_dir.join("config");
+        let config = std::fs::read_to_string(&config_path)?
+            .replace("repositoryformatversion = 0", "repositoryformatversion = 1");
+        std::fs::write(&config_path, config + "[extensions]\n\trefStorage = reftable\n")?;
+
+        let store = git::refs::Store::at(
+            &git_dir,
+            StorageFormat::Reftable,
+            WriteReflog::Normal,
+            git::hash::Kind::Sha1,
+        )?;
+        let update = |name: &str, new: Target| RefEdit {
+            change: Change::Update {
+                log: LogChange::default(),
+                expected: PreviousValue::Any,
+                new,
+            },
+            name: name.try_into().expect("valid"),
+            deref: false,
+        };
+        store
+            .to_handle()
+            .transaction()
+            .prepare(
+                [
+                    update("refs/heads/main", Target::Peeled(id)),
+                    update("HEAD", Target::Symbolic("refs/heads/main".try_into()?)),
+                ],
+                git::lock::acquire::Fail::Immediately,
+                git::lock::acquire::Fail::Immediately,
+            )?
+            .commit(git::actor::Signature::empty().to_ref())?;
+
+        let mut repo = git::open_opts(&git_dir, crate::restricted())?;
+        assert_eq!(repo.refs.storage_format(), StorageFormat::Reftable);
+        assert_eq!(repo.head_id()?, id);
+        assert_eq!(repo.references()?.all()?.count(), 1);
+
+        repo.reference("refs/heads/other", id, PreviousValue::MustNotExist, "create")?;
+        assert_eq!(
+            store.to_handle().find("other")?.target,
+            Target::Peeled(id),
+            "edits go into the reftable"
+        );
+        assert!(
+            !git_dir.join("refs/heads/other").exists(),
+            "no loose reference is written"
+        );
+
+        assert!(
+            repo.set_namespace("foo").is_err(),
+            "namespaces aren't supported for reftables yet"
+        );
+        Ok(())
+    }
+}
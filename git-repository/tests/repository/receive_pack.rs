@@ -0,0 +1,513 @@
+use std::{
+    net::TcpListener,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+    sync::{atomic::AtomicBool, mpsc},
+};
+
+use git_repository as git;
+use git_repository::{
+    bstr::ByteSlice,
+    receive_pack::{Error, Options, Outcome},
+    transport::packetline,
+};
+
+fn fixture() -> crate::Result<git_testtools::tempfile::TempDir> {
+    git_testtools::scripted_fixture_repo_writable_with_args(
+        "make_receive_pack_repos.sh",
+        None::<String>,
+        git_testtools::Creation::ExecuteScript,
+    )
+}
+
+/// A `git` daemon on a random port which accepts pushes into the repository at `dir` for any path.
+struct Daemon {
+    url: String,
+    outcomes: mpsc::Receiver<Result<Outcome, String>>,
+}
+
+impl Daemon {
+    fn spawn(dir: &Path) -> crate::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("git://{}/repo", listener.local_addr()?);
+        let repo = git::open_opts(dir, git::open::Options::isolated())?.into_sync();
+        let (tx, outcomes) = mpsc::channel();
+        std::thread::spawn(move || {
+            let repo = repo.to_thread_local();
+            for stream in listener.incoming() {
+                let res = stream.map_err(Error::from).and_then(|stream| {
+                    git::transport::server::accept(&stream)
+                        .map_err(|err| Error::from(std::io::Error::new(std::io::ErrorKind::Other, err)))?;
+                    repo.receive_pack(&stream, &stream, Options::default(), &AtomicBool::default())
+                });
+                if tx.send(res.map_err(|err| err.to_string())).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Daemon { url, outcomes })
+    }
+
+    /// Return the outcome of the next served connection.
+    fn outcome(&self) -> Outcome {
+        self.outcomes
+            .recv_timeout(std::time::Duration::from_secs(30))
+            .expect("a connection was served")
+            .expect("serving succeeded")
+    }
+}
+
+fn git_output(dir: &Path, args: &[&str]) -> crate::Result<Output> {
+    Ok(Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .output()?)
+}
+
+fn git(dir: &Path, args: &[&str]) -> crate::Result<String> {
+    let out = git_output(dir, args)?;
+    assert!(
+        out.status.success(),
+        "git {:?} failed: {}",
+        args,
+        out.stderr.to_str_lossy()
+    );
+    Ok(out.stdout.to_str()?.trim().to_owned())
+}
+
+/// Push `refspecs` from the `local` repository to `daemon` and return `stderr` if the push failed.
+fn push(dir: &Path, daemon: &Daemon, refspecs: &[&str]) -> crate::Result<Result<(), String>> {
+    let mut args = vec!["push", "--porcelain", daemon.url.as_str()];
+    args.extend_from_slice(refspecs);
+    let out = git_output(&dir.join("local"), &args)?;
+    Ok(if out.status.success() {
+        Ok(())
+    } else {
+        Err(format!("{}{}", out.stdout.to_str_lossy(), out.stderr.to_str_lossy()))
+    })
+}
+
+fn rev_parse(dir: &Path, rev: &str) -> crate::Result<Option<String>> {
+    let out = git_output(dir, &["rev-parse", "--verify", "--quiet", rev])?;
+    Ok(out
+        .status
+        .success()
+        .then(|| out.stdout.to_str_lossy().trim().to_owned()))
+}
+
+fn has_object(dir: &Path, id: &str) -> crate::Result<bool> {
+    Ok(git_output(dir, &["cat-file", "-e", id])?.status.success())
+}
+
+fn quarantine_dirs(objects_dir: &Path) -> crate::Result<Vec<PathBuf>> {
+    Ok(std::fs::read_dir(objects_dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|path| {
+            path.file_name()
+                .map_or(false, |name| name.to_string_lossy().starts_with("incoming-"))
+        })
+        .collect())
+}
+
+#[cfg(unix)]
+fn install_hook(repo_dir: &Path, name: &str, script: &str) -> crate::Result {
+    use std::os::unix::fs::PermissionsExt;
+    let path = repo_dir.join("hooks").join(name);
+    std::fs::write(&path, format!("#!/bin/sh\n{}", script))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+fn statuses(outcome: &Outcome) -> Vec<(&str, Option<&str>)> {
+    outcome
+        .commands
+        .iter()
+        .map(|c| {
+            (
+                c.name.to_str().expect("valid UTF-8"),
+                c.rejection.as_ref().map(|r| r.to_str().expect("valid UTF-8")),
+            )
+        })
+        .collect()
+}
+
+/// Send `commands` along with `pack` to the repository at `dir` in a single request and return the outcome along with
+/// all lines of the report.
+fn receive(dir: &Path, commands: &[String], pack: &[u8]) -> crate::Result<(Outcome, Vec<String>)> {
+    let mut input = Vec::new();
+    for (idx, command) in commands.iter().enumerate() {
+        let line = if idx == 0 {
+            format!("{}\0report-status", command)
+        } else {
+            command.clone()
+        };
+        packetline::encode::text_to_write(line.as_bytes(), &mut input)?;
+    }
+    packetline::encode::flush_to_write(&mut input)?;
+    input.extend_from_slice(pack);
+
+    let repo = git::open_opts(dir, git::open::Options::isolated())?;
+    let mut output = Vec::new();
+    let outcome = repo.receive_pack(
+        input.as_slice(),
+        &mut output,
+        Options {
+            stateless_rpc: true,
+            ..Default::default()
+        },
+        &AtomicBool::default(),
+    )?;
+
+    let mut report = Vec::new();
+    let mut lines = packetline::StreamingPeekableIter::new(output.as_slice(), &[packetline::PacketLineRef::Flush]);
+    while let Some(line) = lines.read_line() {
+        report.push(line??.as_bstr().expect("data line").to_str()?.trim_end().to_owned());
+    }
+    Ok((outcome, report))
+}
+
+/// A pack with all objects reachable from `tips`, but without those reachable from `exclude`.
+fn pack_objects(dir: &Path, tips: &[&str], exclude: &[&str]) -> crate::Result<Vec<u8>> {
+    let mut child = Command::new("git")
+        .args(["pack-objects", "--stdout", "--revs", "-q"])
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut input = String::new();
+    for tip in tips {
+        input.push_str(&format!("{}\n", tip));
+    }
+    for rev in exclude {
+        input.push_str(&format!("^{}\n", rev));
+    }
+    std::io::Write::write_all(&mut child.stdin.take().expect("piped"), input.as_bytes())?;
+    let out = child.wait_with_output()?;
+    assert!(out.status.success());
+    Ok(out.stdout)
+}
+
+const NULL: &str = "0000000000000000000000000000000000000000";
+
+#[test]
+fn git_pushes_creations_updates_and_deletions() -> crate::Result {
+    let dir = fixture()?;
+    let server = dir.path().join("server.git");
+    let daemon = Daemon::spawn(&server)?;
+    push(dir.path(), &daemon, &["main", "main:refs/heads/new", ":to-delete"])?.expect("push succeeds");
+
+    let outcome = daemon.outcome();
+    assert_eq!(
+        statuses(&outcome),
+        vec![
+            ("refs/heads/main", None),
+            ("refs/heads/to-delete", None),
+            ("refs/heads/new", None)
+        ]
+    );
+    assert_eq!(outcome.unpack_error, None);
+    assert_eq!(outcome.num_objects, 3, "the new commit with its tree and blob");
+
+    let local = dir.path().join("local");
+    let main = rev_parse(&local, "main")?;
+    assert_eq!(rev_parse(&server, "main")?, main);
+    assert_eq!(rev_parse(&server, "new")?, main);
+    assert_eq!(rev_parse(&server, "refs/heads/to-delete")?, None);
+    git(&server, &["fsck", "--connectivity-only", "--no-dangling"])?;
+    assert!(
+        quarantine_dirs(&server.join("objects"))?.is_empty(),
+        "the quarantine is removed"
+    );
+    assert!(
+        !std::fs::read_dir(server.join("objects").join("pack"))?
+            .any(|entry| entry.map_or(false, |e| e.path().extension().map_or(false, |ext| ext == "keep"))),
+        "keep files protect packs only until the references are updated"
+    );
+    Ok(())
+}
+
+#[test]
+fn our_client_pushes_atomically_and_learns_about_rejections() -> crate::Result {
+    let dir = fixture()?;
+    let server = dir.path().join("server.git");
+    git(&server, &["config", "receive.denyNonFastForwards", "true"])?;
+    let daemon = Daemon::spawn(&server)?;
+    let local = git::open_opts(dir.path().join("local"), git::open::Options::isolated())?;
+
+    let push = |specs: &[&str], atomic: bool| -> crate::Result<git::remote::push::Outcome> {
+        let mut remote = local.remote_at(daemon.url.as_str())?;
+        remote.replace_refspecs(specs.iter().copied(), git::remote::Direction::Push)?;
+        let outcome = remote
+            .connect(git::remote::Direction::Push, git::progress::Discard)?
+            .push(
+                git::remote::push::Options {
+                    atomic,
+                    ..Default::default()
+                },
+                &AtomicBool::default(),
+            )?;
+        Ok(outcome)
+    };
+
+    let outcome = push(&["main", "+other"], true)?;
+    let reason = "non-fast-forward".into();
+    assert_eq!(
+        outcome.updates[1].status,
+        git::remote::push::Status::RejectedByRemote { reason }
+    );
+    assert_eq!(
+        outcome.updates[0].status,
+        git::remote::push::Status::RejectedByRemote {
+            reason: "atomic push failure".into()
+        }
+    );
+    assert_eq!(
+        statuses(&daemon.outcome()),
+        vec![
+            ("refs/heads/main", Some("atomic push failure")),
+            ("refs/heads/other", Some("non-fast-forward"))
+        ]
+    );
+    assert_ne!(rev_parse(&server, "main")?, rev_parse(local.path(), "main")?);
+
+    let outcome = push(&["main", "+other"], false)?;
+    assert!(outcome.updates[0].status.is_ok());
+    assert!(!outcome.updates[1].status.is_ok());
+    assert_eq!(
+        statuses(&daemon.outcome()),
+        vec![
+            ("refs/heads/main", None),
+            ("refs/heads/other", Some("non-fast-forward"))
+        ]
+    );
+    assert_eq!(rev_parse(&server, "main")?, rev_parse(local.path(), "main")?);
+    Ok(())
+}
+
+#[test]
+fn missing_objects_and_stale_expectations_are_rejected() -> crate::Result {
+    let dir = fixture()?;
+    let server = dir.path().join("server.git");
+    let local = dir.path().join("local");
+    let main = rev_parse(&local, "main")?.expect("present");
+    let server_main = rev_parse(&server, "main")?.expect("present");
+    let server_other = rev_parse(&server, "other")?.expect("present");
+    let server_previous = rev_parse(&server, "main~1")?.expect("present");
+
+    let (outcome, report) = receive(
+        &server,
+        &[
+            format!("{} {} refs/heads/main", server_main, main),
+            format!("{} {} refs/heads/new", NULL, main),
+            format!("{} {} refs/heads/other", server_previous, server_main),
+        ],
+        &pack_objects(&local, &["main^{tree}"], &[])?,
+    )?;
+    assert_eq!(
+        report,
+        [
+            "unpack ok",
+            "ng refs/heads/main missing necessary objects",
+            "ng refs/heads/new missing necessary objects",
+            "ng refs/heads/other failed to update ref",
+        ],
+        "the commit itself wasn't sent, and other doesn't point to the previous commit"
+    );
+    assert_eq!(outcome.num_objects, 2, "a tree and a blob");
+    assert!(!has_object(&server, &format!("{}^{{tree}}", main))?);
+    assert_eq!(rev_parse(&server, "other")?, Some(server_other));
+
+    let (outcome, report) = receive(
+        &server,
+        &[format!("{} {} refs/heads/main", server_main, main)],
+        b"not a pack",
+    )?;
+    assert!(outcome.unpack_error.is_some());
+    assert_eq!(report.len(), 2);
+    assert!(report[0].starts_with("unpack "), "{:?}", report);
+    assert_eq!(report[1], "ng refs/heads/main unpacker error");
+    assert!(quarantine_dirs(&server.join("objects"))?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn unreadable_references_only_reject_their_own_command() -> crate::Result {
+    let dir = fixture()?;
+    let server = dir.path().join("server.git");
+    let server_main = rev_parse(&server, "main")?.expect("present");
+    std::fs::write(server.join("refs").join("heads").join("broken"), b"garbage\n")?;
+
+    let (outcome, report) = receive(
+        &server,
+        &[
+            format!("{} {} refs/heads/broken", server_main, server_main),
+            format!("{} {} refs/heads/new", NULL, server_main),
+        ],
+        &pack_objects(&server, &["main"], &["main"])?,
+    )?;
+    assert_eq!(report.len(), 3);
+    assert!(
+        report[1].starts_with("ng refs/heads/broken failed to read ref: "),
+        "{:?}",
+        report
+    );
+    assert_eq!(report[2], "ok refs/heads/new");
+    assert!(outcome.commands[1].is_ok());
+    assert_eq!(rev_parse(&server, "new")?, Some(server_main));
+    Ok(())
+}
+
+#[test]
+fn the_checked_out_branch_of_repositories_with_worktree_is_protected() -> crate::Result {
+    let dir = fixture()?;
+    let worktree = dir.path().join("worktree");
+    let local = dir.path().join("local");
+    let before = rev_parse(&worktree, "main")?;
+    let daemon = Daemon::spawn(&worktree.join(".git"))?;
+
+    let err = push(dir.path(), &daemon, &["main", "main:refs/heads/new"])?.expect_err("main is rejected");
+    assert!(err.contains("branch is currently checked out"), "{}", err);
+    assert_eq!(
+        statuses(&daemon.outcome()),
+        vec![
+            ("refs/heads/main", Some("branch is currently checked out")),
+            ("refs/heads/new", None)
+        ]
+    );
+    assert_eq!(rev_parse(&worktree, "main")?, before);
+    assert_eq!(rev_parse(&worktree, "new")?, rev_parse(&local, "main")?);
+
+    let err = push(dir.path(), &daemon, &[":main"])?.expect_err("deletion of main is rejected");
+    assert!(err.contains("deletion of the current branch prohibited"), "{}", err);
+    daemon.outcome();
+
+    git(&worktree, &["config", "receive.denyCurrentBranch", "ignore"])?;
+    let daemon = Daemon::spawn(&worktree.join(".git"))?;
+    push(dir.path(), &daemon, &["main"])?.expect("main can be updated now");
+    assert_eq!(statuses(&daemon.outcome()), vec![("refs/heads/main", None)]);
+    assert_eq!(rev_parse(&worktree, "main")?, rev_parse(&local, "main")?);
+    Ok(())
+}
+
+#[cfg(unix)]
+mod hooks {
+    use super::{
+        daemon_with_hooks, fixture, has_object, install_hook, pack_objects, push, quarantine_dirs, receive, rev_parse,
+        statuses, NULL,
+    };
+
+    #[test]
+    fn hooks_may_write_a_lot_of_output_before_reading_their_input() -> crate::Result {
+        let dir = fixture()?;
+        let server = dir.path().join("server.git");
+        install_hook(&server, "pre-receive", "seq 100000\ncat > /dev/null\n")?;
+        let main = rev_parse(&server, "main")?.expect("present");
+        let commands: Vec<_> = (0..1000)
+            .map(|idx| format!("{} {} refs/heads/branch-{}", NULL, main, idx))
+            .collect();
+
+        let (outcome, _report) = receive(&server, &commands, &pack_objects(&server, &["main"], &["main"])?)?;
+        assert!(
+            outcome.commands.iter().all(|c| c.is_ok()),
+            "neither the hook nor we block while the other one is writing"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn pre_receive_sees_quarantined_objects_and_can_decline_all_updates() -> crate::Result {
+        let (dir, daemon) = daemon_with_hooks(&[(
+            "pre-receive",
+            r#"read old new name
+test -n "$GIT_QUARANTINE_PATH" || exit 2
+git cat-file -e "$new" || exit 3
+echo "declined $name with $GIT_PUSH_OPTION_COUNT option: $GIT_PUSH_OPTION_0"
+exit 1
+"#,
+        )])?;
+        let server = dir.path().join("server.git");
+        let before = rev_parse(&server, "main")?;
+
+        let err = push(dir.path(), &daemon, &["-o", "ci.skip", "main"])?.expect_err("declined by hook");
+        assert!(
+            err.contains("remote: declined refs/heads/main with 1 option: ci.skip"),
+            "hook output is relayed to the client: {}",
+            err
+        );
+        assert!(err.contains("pre-receive hook declined"), "{}", err);
+        let outcome = daemon.outcome();
+        assert_eq!(outcome.push_options, ["ci.skip"]);
+        assert_eq!(
+            statuses(&outcome),
+            vec![("refs/heads/main", Some("pre-receive hook declined"))]
+        );
+        assert_eq!(rev_parse(&server, "main")?, before);
+        let main = rev_parse(&dir.path().join("local"), "main")?.expect("present");
+        assert!(!has_object(&server, &main)?, "the quarantined objects were discarded");
+        assert!(quarantine_dirs(&server.join("objects"))?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn update_declines_individual_updates_and_post_receive_learns_about_the_others() -> crate::Result {
+        let (dir, daemon) = daemon_with_hooks(&[
+            (
+                "update",
+                r#"test -z "$GIT_QUARANTINE_PATH" || exit 2
+git cat-file -e "$3" || exit 3
+test "$1" != refs/heads/declined
+"#,
+            ),
+            ("post-receive", "cat > post-receive.out\n"),
+        ])?;
+        let server = dir.path().join("server.git");
+        let main = rev_parse(&dir.path().join("local"), "main")?.expect("present");
+
+        let err = push(dir.path(), &daemon, &["main", "main:refs/heads/declined"])?.expect_err("one update fails");
+        assert!(err.contains("hook declined"), "{}", err);
+        assert_eq!(
+            statuses(&daemon.outcome()),
+            vec![
+                ("refs/heads/main", None),
+                ("refs/heads/declined", Some("hook declined"))
+            ]
+        );
+        assert_eq!(rev_parse(&server, "main")?.as_ref(), Some(&main));
+        assert_eq!(rev_parse(&server, "declined")?, None);
+        let post_receive = std::fs::read_to_string(server.join("post-receive.out"))?;
+        assert_eq!(post_receive.lines().count(), 1, "only the applied update is passed");
+        assert!(post_receive.ends_with(&format!(" {} refs/heads/main\n", main)));
+
+        let err = push(
+            dir.path(),
+            &daemon,
+            &["--atomic", "main:refs/heads/a", "main:refs/heads/declined"],
+        )?
+        .expect_err("all updates fail");
+        assert!(err.contains("atomic push failure"), "{}", err);
+        assert_eq!(
+            statuses(&daemon.outcome()),
+            vec![
+                ("refs/heads/a", Some("atomic push failure")),
+                ("refs/heads/declined", Some("hook declined"))
+            ]
+        );
+        assert_eq!(rev_parse(&server, "refs/heads/a")?, None);
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn daemon_with_hooks(hooks: &[(&str, &str)]) -> crate::Result<(git_testtools::tempfile::TempDir, Daemon)> {
+    let dir = fixture()?;
+    let server = dir.path().join("server.git");
+    for (name, script) in hooks {
+        install_hook(&server, name, script)?;
+    }
+    let daemon = Daemon::spawn(&server)?;
+    Ok((dir, daemon))
+}
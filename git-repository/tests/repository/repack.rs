@@ -0,0 +1,300 @@
+use std::{path::PathBuf, sync::atomic::AtomicBool};
+
+use git_repository as git;
+use git_repository::{odb::Find, prelude::ObjectIdExt, progress, repack, ObjectId};
+
+use crate::util::repo_rw;
+
+fn pack_indices(repo: &git::Repository) -> Vec<PathBuf> {
+    let mut out = std::fs::read_dir(repo.objects.store_ref().path().join("pack"))
+        .expect("pack directory exists")
+        .map(|entry| entry.expect("valid entry").path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "idx"))
+        .collect::<Vec<_>>();
+    out.sort();
+    out
+}
+
+fn loose_objects(repo: &git::Repository) -> Vec<ObjectId> {
+    let mut out = git::odb::loose::Store::at(repo.objects.store_ref().path(), repo.object_hash())
+        .iter()
+        .collect::<Result<Vec<_>, _>>()
+        .expect("loose objects can be iterated");
+    out.sort();
+    out
+}
+
+fn all_objects(repo: &git::Repository) -> Vec<ObjectId> {
+    let mut out = repo
+        .objects
+        .iter()
+        .expect("objects can be iterated")
+        .collect::<Result<Vec<_>, _>>()
+        .expect("valid objects");
+    out.sort();
+    out.dedup();
+    out
+}
+
+fn unreachable_id(tmp: &git_testtools::tempfile::TempDir) -> crate::Result<ObjectId> {
+    let hex = std::fs::read_to_string(tmp.path().join("unreachable-id"))?;
+    Ok(ObjectId::from_hex(hex.trim().as_bytes())?)
+}
+
+/// Re-open the repository to be sure the object database sees the state on disk.
+fn reopen(repo: &git::Repository) -> crate::Result<git::Repository> {
+    Ok(git::open_opts(repo.git_dir(), crate::restricted())?)
+}
+
+fn repack(repo: &git::Repository, options: repack::Options) -> crate::Result<repack::Outcome> {
+    Ok(repo.repack(progress::Discard, &AtomicBool::default(), options)?)
+}
+
+#[test]
+fn loose_only_packs_reachable_loose_objects_and_leaves_packs_alone() -> crate::Result {
+    let (repo, tmp) = repo_rw("make_repack_repo.sh")?;
+    let packs_before = pack_indices(&repo);
+    let objects_before = all_objects(&repo);
+    assert_eq!(packs_before.len(), 4);
+    assert_eq!(
+        loose_objects(&repo).len(),
+        6,
+        "tag, commit, tree, two blobs and an unreachable blob"
+    );
+
+    let outcome = repack(&repo, repack::Options::default())?;
+    let pack = outcome.pack.expect("loose objects were packed");
+    assert_eq!(pack.num_objects, 5, "the unreachable object isn't packed");
+    assert!(outcome.removed_packs.is_empty());
+    assert_eq!(outcome.removed_loose_objects, 5);
+    assert_eq!(outcome.multi_pack_index, None);
+
+    let repo = reopen(&repo)?;
+    assert_eq!(pack_indices(&repo).len(), 5);
+    assert!(pack_indices(&repo).contains(&pack.index_path));
+    assert_eq!(
+        loose_objects(&repo),
+        vec![unreachable_id(&tmp)?],
+        "unreachable objects are kept without pruning"
+    );
+    assert_eq!(all_objects(&repo), objects_before, "no object was lost");
+    Ok(())
+}
+
+#[test]
+fn all_into_one_with_bitmap_and_pruning() -> crate::Result {
+    let (repo, tmp) = repo_rw("make_repack_repo.sh")?;
+    let unreachable = unreachable_id(&tmp)?;
+    let reachable_before = all_objects(&repo)
+        .into_iter()
+        .filter(|id| *id != unreachable)
+        .collect::<Vec<_>>();
+
+    let outcome = repack(
+        &repo,
+        repack::Options {
+            mode: repack::Mode::AllIntoOne,
+            write_bitmap: true,
+            prune_loose_older_than: Some(std::time::SystemTime::now()),
+            ..Default::default()
+        },
+    )?;
+    let pack = outcome.pack.expect("objects were packed");
+    assert_eq!(pack.num_objects as usize, reachable_before.len());
+    assert_eq!(outcome.removed_packs.len(), 4);
+    assert!(outcome.removed_packs.iter().all(|path| !path.exists()));
+    assert_eq!(
+        outcome.removed_loose_objects, 6,
+        "all packed objects and the unreachable one"
+    );
+
+    let repo = reopen(&repo)?;
+    assert_eq!(pack_indices(&repo), vec![pack.index_path.clone()]);
+    assert!(loose_objects(&repo).is_empty());
+    assert_eq!(all_objects(&repo), reachable_before);
+    assert!(!repo.objects.contains(unreachable));
+
+    let bitmap_path = pack.bitmap_path.expect("requested");
+    let bitmap = git::odb::pack::bitmap::File::at(&bitmap_path, repo.object_hash())?;
+    let index = git::odb::pack::index::File::at(&pack.index_path, repo.object_hash())?;
+    let order = git::odb::pack::bitmap::PackOrder::from_index(&index);
+    let head = repo.head_id()?.detach();
+    let reachable = bitmap.reachable_objects(&order, Some(head), &repo.objects, &AtomicBool::default())?;
+    assert_eq!(
+        reachable.num_objects(),
+        reachable_before.len() - 2,
+        "everything but the staged blob and the tag is reachable from HEAD"
+    );
+    Ok(())
+}
+
+#[test]
+fn all_into_one_keeps_unreachable_loose_objects_that_are_not_expired() -> crate::Result {
+    let (repo, tmp) = repo_rw("make_repack_repo.sh")?;
+    let outcome = repack(
+        &repo,
+        repack::Options {
+            mode: repack::Mode::AllIntoOne,
+            prune_loose_older_than: Some(std::time::UNIX_EPOCH),
+            write_multi_pack_index: true,
+            delta_window: None,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(outcome.removed_loose_objects, 5);
+    let multi_index_path = outcome.multi_pack_index.expect("requested");
+
+    let repo = reopen(&repo)?;
+    assert_eq!(loose_objects(&repo), vec![unreachable_id(&tmp)?]);
+    let multi_index = git::odb::pack::multi_index::File::at(multi_index_path)?;
+    assert_eq!(multi_index.num_indices(), 1);
+    assert_eq!(
+        multi_index.num_objects(),
+        outcome.pack.expect("written").num_objects,
+        "the multi-pack-index only refers to the new pack"
+    );
+    Ok(())
+}
+
+#[test]
+fn geometric_rolls_up_small_packs_and_loose_objects() -> crate::Result {
+    let (repo, tmp) = repo_rw("make_repack_repo.sh")?;
+    let packs_before = pack_indices(&repo);
+    let largest_pack = packs_before
+        .iter()
+        .max_by_key(|path| std::fs::metadata(path).expect("exists").len())
+        .expect("packs exist")
+        .clone();
+    let objects_before = all_objects(&repo);
+
+    let outcome = repack(
+        &repo,
+        repack::Options {
+            mode: repack::Mode::Geometric { factor: 2 },
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(outcome.removed_packs.len(), 3, "only the largest pack remains");
+    assert_eq!(outcome.removed_loose_objects, 6, "all loose objects are packed");
+    let pack = outcome.pack.expect("written");
+    assert_eq!(pack.num_objects, 3 * 3 + 6);
+
+    let repo = reopen(&repo)?;
+    assert_eq!(pack_indices(&repo).len(), 2);
+    assert!(pack_indices(&repo).contains(&largest_pack));
+    assert!(loose_objects(&repo).is_empty());
+    assert_eq!(all_objects(&repo), objects_before);
+    assert!(repo.objects.contains(unreachable_id(&tmp)?));
+
+    let outcome = repack(
+        &repo,
+        repack::Options {
+            mode: repack::Mode::Geometric { factor: 2 },
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(outcome.pack, None, "the progression is intact, there is nothing to do");
+    assert!(outcome.removed_packs.is_empty());
+    Ok(())
+}
+
+#[test]
+fn bitmaps_require_all_into_one() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_repack_repo.sh")?;
+    assert!(matches!(
+        repack(
+            &repo,
+            repack::Options {
+                write_bitmap: true,
+                ..Default::default()
+            },
+        )
+        .unwrap_err()
+        .downcast_ref::<repack::Error>(),
+        Some(repack::Error::BitmapRequiresAllIntoOne)
+    ));
+    Ok(())
+}
+
+#[test]
+fn annotated_tags_are_kept() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_repack_repo.sh")?;
+    let tag_id = repo.find_reference("v1")?.id().detach();
+    repack(
+        &repo,
+        repack::Options {
+            mode: repack::Mode::AllIntoOne,
+            ..Default::default()
+        },
+    )?;
+    let repo = reopen(&repo)?;
+    assert_eq!(tag_id.attach(&repo).object()?.kind, git::object::Kind::Tag);
+    Ok(())
+}
+
+#[test]
+fn objects_only_reachable_from_other_worktrees_are_kept() -> crate::Result {
+    let (repo, tmp) = repo_rw("make_repack_repo_with_worktree.sh")?;
+    let read_id = |name: &str| -> crate::Result<ObjectId> {
+        let hex = std::fs::read_to_string(tmp.path().join(name))?;
+        Ok(ObjectId::from_hex(hex.trim().as_bytes())?)
+    };
+    let kept = [
+        read_id("worktree-head")?,
+        read_id("bisect-commit")?,
+        read_id("staged-blob")?,
+    ];
+    let loose_before = loose_objects(&repo);
+    assert!(
+        kept.iter().all(|id| loose_before.contains(id)),
+        "none of these is reachable from the main worktree, so they are loose and subject to pruning"
+    );
+
+    repack(
+        &repo,
+        repack::Options {
+            mode: repack::Mode::AllIntoOne,
+            prune_loose_older_than: Some(std::time::SystemTime::now()),
+            ..Default::default()
+        },
+    )?;
+
+    let repo = reopen(&repo)?;
+    assert!(loose_objects(&repo).is_empty(), "everything was packed or pruned");
+    for id in kept {
+        assert!(
+            repo.objects.contains(id),
+            "{} is reachable from the HEAD, references or index of the linked worktree",
+            id
+        );
+    }
+    let worktree_head = kept[0].attach(&repo).object()?.into_commit();
+    assert!(
+        repo.objects.contains(worktree_head.tree_id()?),
+        "the worktree HEAD is traversed, too"
+    );
+    Ok(())
+}
+
+#[test]
+fn shallow_commits_are_packed_without_their_parents() -> crate::Result {
+    let tmp = git_testtools::scripted_fixture_repo_writable("make_shallow_repos.sh")?;
+    let repo = git::open_opts(tmp.path().join("shallow"), crate::restricted())?;
+    let objects_before = all_objects(&repo);
+
+    repack(
+        &repo,
+        repack::Options {
+            mode: repack::Mode::AllIntoOne,
+            ..Default::default()
+        },
+    )?;
+    let repo = reopen(&repo)?;
+    assert_eq!(pack_indices(&repo).len(), 1);
+    assert_eq!(
+        all_objects(&repo),
+        objects_before,
+        "all objects of the shallow history are kept, and the missing parent isn't looked up"
+    );
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use git_repository as git;
+
+fn repo(name: &str) -> crate::Result<git::Repository> {
+    let dir = git_testtools::scripted_fixture_repo_read_only("make_shallow_repos.sh")?;
+    Ok(git::open_opts(dir.join(name), crate::restricted())?)
+}
+
+fn repo_rw(name: &str) -> crate::Result<(git::Repository, git_testtools::tempfile::TempDir)> {
+    let dir = git_testtools::scripted_fixture_repo_writable("make_shallow_repos.sh")?;
+    let repo = git::open_opts(dir.path().join(name), crate::restricted())?;
+    Ok((repo, dir))
+}
+
+#[test]
+fn complete_repositories_are_not_shallow() -> crate::Result {
+    let repo = repo("base")?;
+    assert!(!repo.is_shallow());
+    assert_eq!(repo.shallow_commits()?, None);
+    Ok(())
+}
+
+#[test]
+fn shallow_commits_are_read_from_the_shallow_file() -> crate::Result {
+    let repo = repo("shallow")?;
+    assert!(repo.is_shallow());
+    assert_eq!(repo.shallow_file(), repo.git_dir().join("shallow"));
+    assert_eq!(
+        repo.shallow_commits()?,
+        Some(vec![repo.head_id()?.detach()]),
+        "the depth of the clone is 1"
+    );
+    Ok(())
+}
+
+#[test]
+fn traversals_treat_shallow_commits_as_if_they_had_no_parents() -> crate::Result {
+    let repo = repo("shallow")?;
+    let commits = repo
+        .head_id()?
+        .ancestors()
+        .all()?
+        .error_on_missing_commit()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        commits,
+        vec![repo.head_id()?],
+        "the parent isn't present and isn't looked up"
+    );
+    Ok(())
+}
+
+#[test]
+fn write_shallow_commits_replaces_the_shallow_file_or_removes_it() -> crate::Result {
+    let (repo, _tmp) = repo_rw("shallow")?;
+    let head_id = repo.head_id()?.detach();
+    let other_id = repo.head_commit()?.tree_id()?.detach();
+
+    repo.write_shallow_commits([head_id, other_id, head_id])?;
+    let mut expected = vec![head_id, other_id];
+    expected.sort();
+    assert_eq!(
+        repo.shallow_commits()?,
+        Some(expected),
+        "commits are sorted and deduplicated"
+    );
+
+    repo.write_shallow_commits(None)?;
+    assert!(!repo.is_shallow());
+    assert!(
+        !repo.shallow_file().exists(),
+        "the file is removed if there are no shallow commits"
+    );
+    assert_eq!(repo.shallow_commits()?, None);
+
+    repo.write_shallow_commits(None)?;
+    assert!(
+        !repo.shallow_file().exists(),
+        "removing a file that doesn't exist is fine"
+    );
+    Ok(())
+}
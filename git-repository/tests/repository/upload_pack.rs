@@ -0,0 +1,712 @@
+use std::{
+    net::TcpListener,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{atomic::AtomicBool, mpsc},
+};
+
+use git_repository as git;
+use git_repository::{
+    bstr::ByteSlice,
+    transport::{packetline, Protocol},
+    upload_pack::{Error, Options, Outcome},
+};
+
+fn server_path() -> crate::Result<PathBuf> {
+    Ok(git_testtools::scripted_fixture_repo_read_only("make_upload_pack_repos.sh")?.join("server"))
+}
+
+fn server_repo() -> crate::Result<git::Repository> {
+    Ok(git::open_opts(server_path()?, git::open::Options::isolated())?)
+}
+
+/// Like the server, but with a single pack that has a reachability bitmap.
+fn server_with_bitmap_repo() -> crate::Result<git::Repository> {
+    Ok(git::open_opts(
+        git_testtools::scripted_fixture_repo_read_only("make_upload_pack_repos.sh")?.join("server-with-bitmap"),
+        git::open::Options::isolated(),
+    )?)
+}
+
+/// A `git` daemon on a random port serving the fixture repository for any path.
+struct Daemon {
+    url: String,
+    outcomes: mpsc::Receiver<Result<Outcome, String>>,
+}
+
+impl Daemon {
+    fn spawn() -> crate::Result<Self> {
+        Self::spawn_for(server_repo()?)
+    }
+
+    fn spawn_for(repo: git::Repository) -> crate::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("git://{}/server", listener.local_addr()?);
+        let repo = repo.into_sync();
+        let (tx, outcomes) = mpsc::channel();
+        std::thread::spawn(move || {
+            let repo = repo.to_thread_local();
+            for stream in listener.incoming() {
+                let res = stream.map_err(Error::from).and_then(|stream| {
+                    let request = git::transport::server::accept(&stream)
+                        .map_err(|err| Error::from(std::io::Error::new(std::io::ErrorKind::Other, err)))?;
+                    repo.upload_pack(
+                        &stream,
+                        &stream,
+                        Options {
+                            version: request.desired_version,
+                            ..Default::default()
+                        },
+                        &AtomicBool::default(),
+                    )
+                });
+                if tx.send(res.map_err(|err| err.to_string())).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Daemon { url, outcomes })
+    }
+
+    /// Return the outcome of the next served connection.
+    fn outcome(&self) -> Outcome {
+        self.outcomes
+            .recv_timeout(std::time::Duration::from_secs(30))
+            .expect("a connection was served")
+            .expect("serving succeeded")
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> crate::Result<String> {
+    let out = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .output()?;
+    assert!(
+        out.status.success(),
+        "git {:?} failed: {}",
+        args,
+        out.stderr.to_str_lossy()
+    );
+    Ok(out.stdout.to_str()?.trim().to_owned())
+}
+
+fn commit_count(dir: &Path, rev: &str) -> crate::Result<usize> {
+    Ok(git(dir, &["rev-list", "--count", rev])?.parse()?)
+}
+
+const VERSIONS: [&str; 2] = ["2", "0"];
+
+#[test]
+fn git_clones_completely_with_both_protocol_versions() -> crate::Result {
+    let server = server_path()?;
+    for version in VERSIONS {
+        let daemon = Daemon::spawn()?;
+        let tmp = git_testtools::tempfile::TempDir::new()?;
+        git(
+            tmp.path(),
+            &[
+                "-c",
+                &format!("protocol.version={}", version),
+                "clone",
+                "-q",
+                "--bare",
+                &daemon.url,
+                "clone",
+            ],
+        )?;
+        let outcome = daemon.outcome();
+        assert_eq!(outcome.num_packs, 1, "v{}", version);
+        assert_eq!(
+            outcome.num_objects,
+            6 * 3 + 2,
+            "six commits with a tree and a blob each, plus the large file and the tag"
+        );
+        assert_eq!(outcome.num_objects_from_bitmap, 0, "there is no bitmap");
+
+        let clone = tmp.path().join("clone");
+        for rev in ["main", "other", "v1", "v1^{}", "HEAD"] {
+            assert_eq!(
+                git(&clone, &["rev-parse", rev])?,
+                git(&server, &["rev-parse", rev])?,
+                "v{}",
+                version
+            );
+        }
+        git(&clone, &["fsck", "--strict"])?;
+    }
+    Ok(())
+}
+
+#[test]
+fn git_fetches_only_what_it_does_not_have_after_negotiation() -> crate::Result {
+    for version in VERSIONS {
+        let daemon = Daemon::spawn()?;
+        let tmp = git_testtools::tempfile::TempDir::new()?;
+        let protocol = format!("protocol.version={}", version);
+        git(
+            tmp.path(),
+            &[
+                "-c",
+                &protocol,
+                "clone",
+                "-q",
+                "--bare",
+                "--single-branch",
+                "--branch=other",
+                "--no-tags",
+                &daemon.url,
+                "clone",
+            ],
+        )?;
+        assert_eq!(
+            daemon.outcome().num_objects,
+            4 * 3 + 2,
+            "c1 to c3 and o1, the large file and the tag, as git asks for it despite --no-tags"
+        );
+
+        let clone = tmp.path().join("clone");
+        git(
+            &clone,
+            &["-c", &protocol, "fetch", "-q", "--no-tags", &daemon.url, "main:main"],
+        )?;
+        assert_eq!(
+            daemon.outcome().num_objects,
+            2 * 3,
+            "only c4 and c5 with their tree and blob, v{}",
+            version
+        );
+        assert_eq!(commit_count(&clone, "main")?, 5);
+        git(&clone, &["fsck", "--strict"])?;
+    }
+    Ok(())
+}
+
+#[test]
+fn git_clones_and_fetches_with_objects_counted_by_bitmap() -> crate::Result {
+    for version in VERSIONS {
+        let daemon = Daemon::spawn_for(server_with_bitmap_repo()?)?;
+        let tmp = git_testtools::tempfile::TempDir::new()?;
+        let protocol = format!("protocol.version={}", version);
+        git(
+            tmp.path(),
+            &[
+                "-c",
+                &protocol,
+                "clone",
+                "-q",
+                "--bare",
+                "--single-branch",
+                "--branch=other",
+                "--no-tags",
+                &daemon.url,
+                "clone",
+            ],
+        )?;
+        let outcome = daemon.outcome();
+        assert_eq!(outcome.num_objects, 4 * 3 + 2, "the same as without bitmap");
+        assert_eq!(
+            outcome.num_objects_from_bitmap,
+            outcome.num_objects - 1,
+            "all objects but the included tag were counted using the bitmap, v{}",
+            version
+        );
+
+        let clone = tmp.path().join("clone");
+        git(
+            &clone,
+            &["-c", &protocol, "fetch", "-q", "--no-tags", &daemon.url, "main:main"],
+        )?;
+        let outcome = daemon.outcome();
+        assert_eq!(
+            outcome.num_objects,
+            2 * 3,
+            "objects reachable from common commits aren't sent, v{}",
+            version
+        );
+        assert_eq!(outcome.num_objects_from_bitmap, outcome.num_objects);
+        assert_eq!(commit_count(&clone, "main")?, 5);
+        git(&clone, &["fsck", "--strict"])?;
+    }
+    Ok(())
+}
+
+#[test]
+fn git_lists_references_with_symrefs_and_peeled_tags() -> crate::Result {
+    let server = server_path()?;
+    for version in VERSIONS {
+        let daemon = Daemon::spawn()?;
+        let refs = git(
+            &server,
+            &[
+                "-c",
+                &format!("protocol.version={}", version),
+                "ls-remote",
+                "--symref",
+                &daemon.url,
+            ],
+        )?;
+        assert!(
+            refs.starts_with("ref: refs/heads/main\tHEAD\n"),
+            "v{}: {}",
+            version,
+            refs
+        );
+        assert!(
+            refs.contains(&format!(
+                "{}\trefs/tags/v1^{{}}",
+                git(&server, &["rev-parse", "v1^{}"])?
+            )),
+            "v{}: {}",
+            version,
+            refs
+        );
+        assert_eq!(refs.lines().count(), 6, "v{}: {}", version, refs);
+        daemon.outcome();
+    }
+    Ok(())
+}
+
+#[test]
+fn git_shallow_clones_can_be_deepened_and_unshallowed() -> crate::Result {
+    for version in VERSIONS {
+        let daemon = Daemon::spawn()?;
+        let tmp = git_testtools::tempfile::TempDir::new()?;
+        let protocol = format!("protocol.version={}", version);
+        git(
+            tmp.path(),
+            &[
+                "-c",
+                &protocol,
+                "clone",
+                "-q",
+                "--bare",
+                "--depth=1",
+                &daemon.url,
+                "clone",
+            ],
+        )?;
+        daemon.outcome();
+        let clone = tmp.path().join("clone");
+        assert_eq!(commit_count(&clone, "main")?, 1, "v{}", version);
+        assert_eq!(
+            std::fs::read_to_string(clone.join("shallow"))?.trim(),
+            git(&clone, &["rev-parse", "main"])?
+        );
+
+        git(&clone, &["-c", &protocol, "fetch", "-q", "--deepen=2"])?;
+        daemon.outcome();
+        assert_eq!(commit_count(&clone, "main")?, 3, "v{}", version);
+
+        git(&clone, &["-c", &protocol, "fetch", "-q", "--unshallow"])?;
+        daemon.outcome();
+        assert_eq!(commit_count(&clone, "main")?, 5, "v{}", version);
+        assert!(!clone.join("shallow").exists(), "v{}", version);
+        git(&clone, &["fsck", "--strict"])?;
+    }
+    Ok(())
+}
+
+#[test]
+fn git_shallow_clones_by_date_and_excluded_references() -> crate::Result {
+    for version in VERSIONS {
+        let protocol = format!("protocol.version={}", version);
+        for (arg, expected_commits) in [("--shallow-since=1000250000", 2), ("--shallow-exclude=other", 2)] {
+            let daemon = Daemon::spawn()?;
+            let tmp = git_testtools::tempfile::TempDir::new()?;
+            git(
+                tmp.path(),
+                &[
+                    "-c",
+                    &protocol,
+                    "clone",
+                    "-q",
+                    "--bare",
+                    "--single-branch",
+                    arg,
+                    &daemon.url,
+                    "clone",
+                ],
+            )?;
+            daemon.outcome();
+            let clone = tmp.path().join("clone");
+            assert_eq!(commit_count(&clone, "main")?, expected_commits, "v{} {}", version, arg);
+            git(&clone, &["fsck", "--strict"])?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn git_partial_clones_omit_filtered_objects() -> crate::Result {
+    for version in VERSIONS {
+        let protocol = format!("protocol.version={}", version);
+        for (filter, expected_missing) in [("blob:none", 7), ("blob:limit=1k", 1), ("tree:0", 6)] {
+            let daemon = Daemon::spawn()?;
+            let tmp = git_testtools::tempfile::TempDir::new()?;
+            git(
+                tmp.path(),
+                &[
+                    "-c",
+                    &protocol,
+                    "clone",
+                    "-q",
+                    "--bare",
+                    &format!("--filter={}", filter),
+                    &daemon.url,
+                    "clone",
+                ],
+            )?;
+            daemon.outcome();
+            let clone = tmp.path().join("clone");
+            let missing = git(&clone, &["rev-list", "--objects", "--all", "--missing=print"])?
+                .lines()
+                .filter(|line| line.starts_with('?'))
+                .count();
+            assert_eq!(missing, expected_missing, "v{} {}", version, filter);
+            assert_eq!(commit_count(&clone, "--all")?, 6, "v{} {}", version, filter);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "blocking-network-client")]
+#[test]
+fn clones_with_our_own_client_using_both_protocol_versions() -> crate::Result {
+    let server = server_repo()?;
+    for version in ["2", "1"] {
+        let daemon = Daemon::spawn()?;
+        let tmp = git_testtools::tempfile::TempDir::new()?;
+        let (repo, _out) = git::clone::PrepareFetch::new(
+            daemon.url.as_str(),
+            tmp.path(),
+            git::create::Kind::Bare,
+            Default::default(),
+            git::open::Options::isolated().config_overrides([format!("protocol.version={}", version)]),
+        )?
+        .fetch_only(git::progress::Discard, &AtomicBool::default())?;
+        assert_eq!(daemon.outcome().num_packs, 1, "v{}", version);
+        for name in ["main", "other"] {
+            assert_eq!(
+                repo.find_reference(format!("refs/remotes/origin/{}", name).as_str())?
+                    .id(),
+                server.find_reference(name)?.id(),
+                "v{}",
+                version
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Encode `lines` as packet lines, with `0000` and `0001` being a flush and a delimiter.
+fn request(lines: &[&str]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for line in lines {
+        match *line {
+            "0000" => packetline::encode::flush_to_write(&mut out),
+            "0001" => packetline::encode::delim_to_write(&mut out),
+            line => packetline::encode::text_to_write(line.as_bytes(), &mut out),
+        }
+        .expect("in-memory write");
+    }
+    out
+}
+
+/// Serve `request` statelessly, and return all lines in the response up to the pack.
+fn respond(repo: &git::Repository, version: Protocol, request: &[u8]) -> (Result<Outcome, Error>, Vec<String>) {
+    let mut out = Vec::new();
+    let res = repo.upload_pack(
+        request,
+        &mut out,
+        Options {
+            version,
+            stateless_rpc: true,
+            advertise_refs: false,
+        },
+        &AtomicBool::default(),
+    );
+    let mut lines = Vec::new();
+    let mut reader = packetline::StreamingPeekableIter::new(out.as_slice(), &[]);
+    while let Some(line) = reader.read_line() {
+        let line = match line {
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            line => line.expect("no IO error"),
+        };
+        match line.expect("valid packet line") {
+            packetline::PacketLineRef::Data(data) => {
+                let line = data.to_str_lossy().trim_end().to_owned();
+                let is_pack = line == "packfile" || data.first() == Some(&1);
+                lines.push(line);
+                if is_pack {
+                    break;
+                }
+            }
+            packetline::PacketLineRef::Flush => lines.push("0000".into()),
+            packetline::PacketLineRef::Delimiter => lines.push("0001".into()),
+            packetline::PacketLineRef::ResponseEnd => lines.push("0002".into()),
+        }
+    }
+    (res, lines)
+}
+
+#[test]
+fn v2_negotiation_acknowledges_common_objects_and_is_ready_once_all_wants_are_covered() -> crate::Result {
+    let repo = server_repo()?;
+    let (main, other, c2) = (
+        repo.rev_parse_single("main")?.to_string(),
+        repo.rev_parse_single("other")?.to_string(),
+        repo.rev_parse_single("main~3")?.to_string(),
+    );
+    let unknown = "0000000000000000000000000000000000000001";
+    let (res, lines) = respond(
+        &repo,
+        Protocol::V2,
+        &request(&[
+            "command=fetch",
+            "0001",
+            &format!("want {}", main),
+            &format!("have {}", unknown),
+            "0000",
+        ]),
+    );
+    assert_eq!(res?.num_packs, 0);
+    assert_eq!(lines, ["acknowledgments", "NAK", "0000"]);
+
+    let (res, lines) = respond(
+        &repo,
+        Protocol::V2,
+        &request(&[
+            "command=fetch",
+            "0001",
+            &format!("want {}", main),
+            &format!("want {}", other),
+            &format!("have {}", c2),
+            &format!("have {}", unknown),
+            "0000",
+        ]),
+    );
+    assert_eq!(res?.num_objects, 4 * 3, "c3, c4, c5 and o1 with a tree and a blob each");
+    assert_eq!(
+        lines,
+        [
+            "acknowledgments".to_string(),
+            format!("ACK {}", c2),
+            "ready".into(),
+            "0001".into(),
+            "packfile".into()
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn v2_wanted_refs_are_resolved_and_unknown_ones_are_reported() -> crate::Result {
+    let repo = server_repo()?;
+    let (res, lines) = respond(
+        &repo,
+        Protocol::V2,
+        &request(&["command=fetch", "0001", "want-ref refs/heads/other", "done", "0000"]),
+    );
+    assert_eq!(res?.num_packs, 1);
+    assert_eq!(
+        lines,
+        [
+            "wanted-refs".to_string(),
+            format!("{} refs/heads/other", repo.rev_parse_single("other")?),
+            "0001".into(),
+            "packfile".into()
+        ]
+    );
+
+    let (res, lines) = respond(
+        &repo,
+        Protocol::V2,
+        &request(&["command=fetch", "0001", "want-ref refs/heads/missing", "done", "0000"]),
+    );
+    assert!(matches!(res, Err(Error::UnknownReference { .. })));
+    assert_eq!(
+        lines,
+        ["ERR upload-pack: The reference \"refs/heads/missing\" the client wants doesn't exist"]
+    );
+    Ok(())
+}
+
+#[test]
+fn v1_wants_are_limited_to_references_unless_configured_otherwise() -> crate::Result {
+    let repo = server_repo()?;
+    let fetch = |repo: &git::Repository, want: &str| {
+        respond(
+            repo,
+            Protocol::V1,
+            &request(&[&format!("want {} side-band-64k", want), "0000", "done"]),
+        )
+    };
+    let tree = repo.rev_parse_single("main^{tree}")?.to_string();
+    let (res, lines) = fetch(&repo, &tree);
+    assert!(matches!(res, Err(Error::UnadvertisedObject { .. })));
+    assert_eq!(
+        lines,
+        [format!(
+            "ERR upload-pack: The object {} the client wants is not one of our references",
+            tree
+        )]
+    );
+
+    let (res, lines) = fetch(&repo, &repo.rev_parse_single("main~3")?.to_string());
+    assert_eq!(
+        res?.num_packs,
+        1,
+        "in stateless mode, commits reachable from references can be wanted as references may have changed since they were advertised"
+    );
+    assert_eq!(lines[0], "NAK");
+
+    let repo = git::open_opts(
+        server_path()?,
+        git::open::Options::isolated().config_overrides(Some("uploadpack.allowAnySHA1InWant=true")),
+    )?;
+    let (res, _lines) = fetch(&repo, &tree);
+    assert_eq!(res?.num_packs, 1, "any object can be wanted if configured");
+    Ok(())
+}
+
+#[test]
+fn v2_wants_and_capabilities_are_limited_by_configuration() -> crate::Result {
+    let repo = git::open_opts(
+        server_path()?,
+        git::open::Options::isolated()
+            .config_overrides(["uploadpack.allowFilter=false", "uploadpack.allowRefInWant=false"]),
+    )?;
+    let (res, lines) = respond(
+        &repo,
+        Protocol::V2,
+        &request(&["command=fetch", "0001", "want-ref refs/heads/other", "done", "0000"]),
+    );
+    assert!(matches!(res, Err(Error::DisabledCapability { name: "ref-in-want" })));
+    assert_eq!(
+        lines,
+        ["ERR upload-pack: The client used \"ref-in-want\" which isn't enabled"]
+    );
+
+    let (res, _lines) = respond(
+        &repo,
+        Protocol::V2,
+        &request(&[
+            "command=fetch",
+            "0001",
+            &format!("want {}", repo.rev_parse_single("main")?),
+            "filter blob:none",
+            "done",
+            "0000",
+        ]),
+    );
+    assert!(matches!(res, Err(Error::DisabledCapability { name: "filter" })));
+
+    let mut out = Vec::new();
+    repo.upload_pack(
+        &b""[..],
+        &mut out,
+        Options {
+            version: Protocol::V2,
+            stateless_rpc: true,
+            advertise_refs: true,
+        },
+        &AtomicBool::default(),
+    )?;
+    assert!(out.find("fetch=shallow\n").is_some());
+
+    let mut out = Vec::new();
+    repo.upload_pack(
+        &b""[..],
+        &mut out,
+        Options {
+            version: Protocol::V1,
+            stateless_rpc: true,
+            advertise_refs: true,
+        },
+        &AtomicBool::default(),
+    )?;
+    let first = out.lines().next().expect("at least one line").to_str()?;
+    for capability in ["filter", "allow-tip-sha1-in-want", "allow-reachable-sha1-in-want"] {
+        assert!(!first.contains(capability), "{} isn't advertised", capability);
+    }
+    Ok(())
+}
+
+#[test]
+fn v2_ls_refs_supports_prefixes_symrefs_and_peeling() -> crate::Result {
+    let repo = server_repo()?;
+    let (res, lines) = respond(
+        &repo,
+        Protocol::V2,
+        &request(&[
+            "command=ls-refs",
+            "object-format=sha1",
+            "0001",
+            "symrefs",
+            "peel",
+            "ref-prefix HEAD",
+            "ref-prefix refs/tags/",
+            "0000",
+        ]),
+    );
+    res?;
+    assert_eq!(
+        lines,
+        [
+            format!("{} HEAD symref-target:refs/heads/main", repo.rev_parse_single("main")?),
+            format!(
+                "{} refs/tags/v1 peeled:{}",
+                repo.find_reference("v1")?.id(),
+                repo.rev_parse_single("v1^{}")?
+            ),
+            "0000".into()
+        ]
+    );
+
+    let (res, lines) = respond(&repo, Protocol::V2, &request(&["command=frobnicate", "0000"]));
+    assert!(matches!(res, Err(Error::UnknownCommand { .. })));
+    assert_eq!(lines, ["ERR upload-pack: The command \"frobnicate\" is not supported"]);
+    Ok(())
+}
+
+#[test]
+fn advertisements_only_contain_capabilities_or_references() -> crate::Result {
+    let repo = server_repo()?;
+    let mut out = Vec::new();
+    let outcome = repo.upload_pack(
+        &b""[..],
+        &mut out,
+        Options {
+            version: Protocol::V1,
+            stateless_rpc: true,
+            advertise_refs: true,
+        },
+        &AtomicBool::default(),
+    )?;
+    assert_eq!(outcome, Outcome::default());
+    let first = out.lines().next().expect("at least one line").to_str()?;
+    assert!(first.contains(&format!("{} HEAD\0", repo.rev_parse_single("main")?)));
+    assert!(first.contains(" symref=HEAD:refs/heads/main "));
+    assert!(out.ends_with(b"0000"));
+
+    let mut out = Vec::new();
+    repo.upload_pack(
+        &b""[..],
+        &mut out,
+        Options {
+            version: Protocol::V2,
+            stateless_rpc: true,
+            advertise_refs: true,
+        },
+        &AtomicBool::default(),
+    )?;
+    assert!(out.starts_with(b"000eversion 2\n"));
+    assert!(
+        out.find("fetch=shallow filter ref-in-want").is_some(),
+        "the fixture enables filters and wanted references"
+    );
+    Ok(())
+}
@@ -1,27 +1,27 @@
-pub use crate::client::non_io_types::connect::Error;
+pub use crate::client::non_io_types::connect::{Error, Options};
 
-#[cfg(any(feature = "async-std"))]
+#[cfg(any(feature = "async-std", feature = "async-http-client-reqwest"))]
 pub(crate) mod function {
     use std::convert::TryInto;
 
-    use crate::client::{git, non_io_types::connect::Error};
+    use crate::client::non_io_types::connect::{Error, Options};
 
     /// A general purpose connector connecting to a repository identified by the given `url`.
     ///
     /// This includes connections to
-    /// [git daemons][crate::client::git::connect()] only at the moment.
+    /// [git daemons][crate::client::git::connect()] if compiled with `async-std`,
+    /// and connections to [git repositories over https][crate::client::http::connect()] if compiled in.
     ///
-    /// Use `desired_version` to set the desired protocol version to use when connecting, but note that the server may downgrade it.
-    pub async fn connect<Url, E>(
-        url: Url,
-        desired_version: crate::Protocol,
-    ) -> Result<Box<dyn crate::client::Transport + Send>, Error>
+    /// Use `options` to set the desired protocol version to use when connecting, but note that the server may downgrade it.
+    pub async fn connect<Url, E>(url: Url, options: Options) -> Result<Box<dyn crate::client::Transport + Send>, Error>
     where
         Url: TryInto<git_url::Url, Error = E>,
         git_url::parse::Error: From<E>,
     {
+        #[cfg_attr(not(feature = "async-std"), allow(unused_mut))]
         let mut url = url.try_into().map_err(git_url::parse::Error::from)?;
         Ok(match url.scheme {
+            #[cfg(feature = "async-std")]
             git_url::Scheme::Git => {
                 if url.user().is_some() {
                     return Err(Error::UnsupportedUrlTokens {
@@ -31,16 +31,23 @@ pub(crate) mod function {
                 }
                 let path = std::mem::take(&mut url.path);
                 Box::new(
-                    git::Connection::new_tcp(
+                    crate::client::git::Connection::new_tcp(
                         url.host().expect("host is present in url"),
                         url.port,
                         path,
-                        desired_version,
+                        options.version,
                     )
                     .await
                     .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?,
                 )
             }
+            #[cfg(not(feature = "async-http-client-reqwest"))]
+            git_url::Scheme::Https | git_url::Scheme::Http => return Err(Error::CompiledWithoutHttp(url.scheme)),
+            #[cfg(feature = "async-http-client-reqwest")]
+            git_url::Scheme::Https | git_url::Scheme::Http => Box::new(crate::client::http::connect(
+                &url.to_bstring().to_string(),
+                options.version,
+            )),
             scheme => return Err(Error::UnsupportedScheme(scheme)),
         })
     }
@@ -0,0 +1,29 @@
+/// An implementation for async HTTP requests via `reqwest`.
+///
+/// Requests are sent once the headers or body of their response are read, which must happen within a `tokio` runtime.
+/// Of the [shared options][crate::client::http::Options], the low-speed limits and the `proxy_auth_method` aren't supported
+/// as proxies always use basic authentication, and `FollowRedirects::Initial` follows redirects just like `FollowRedirects::All`.
+#[derive(Default)]
+pub struct Remote {
+    /// The client to perform requests with, created from `config` when needed.
+    client: Option<reqwest::Client>,
+    /// A mechanism for configuring the remote.
+    config: crate::client::http::Options,
+}
+
+/// A function to configure a single request prior to sending it, support most complex configuration beyond what's possible with
+/// basic `git` http configuration.
+pub type ConfigureRequestFn = dyn FnMut(&mut reqwest::Request) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>
+    + Send
+    + Sync
+    + 'static;
+
+/// Options to configure the reqwest HTTP handler.
+#[derive(Default)]
+pub struct Options {
+    /// A function to configure the request that is about to be made.
+    pub configure_request: Option<Box<ConfigureRequestFn>>,
+}
+
+mod remote;
+pub use remote::{Body, Headers, PostBody};
@@ -0,0 +1,402 @@
+use std::{
+    any::Any,
+    convert::TryFrom,
+    future::Future,
+    io,
+    pin::Pin,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures_io::{AsyncBufRead, AsyncRead, AsyncWrite};
+use futures_lite::ready;
+
+use crate::client::{
+    http,
+    http::{options::FollowRedirects, reqwest::Remote},
+};
+
+type ProxyAuth = (git_credentials::helper::NextAction, Arc<Mutex<http::AuthenticateFn>>);
+type ResponseFuture = Pin<Box<dyn Future<Output = reqwest::Result<reqwest::Response>> + Send>>;
+type ChunkFuture = Pin<Box<dyn Future<Output = (reqwest::Response, reqwest::Result<Option<Vec<u8>>>)> + Send>>;
+
+/// utilities
+impl Remote {
+    fn make_request(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+        upload: bool,
+    ) -> Result<http::PostResponse<Headers, Body, PostBody>, http::Error> {
+        let mut proxy_auth = None;
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => {
+                let (client, auth) = new_client(&self.config)?;
+                proxy_auth = auth;
+                self.client = Some(client.clone());
+                client
+            }
+        };
+
+        let mut header_map = reqwest::header::HeaderMap::new();
+        for header_line in headers {
+            if let Some((key, val)) = parse_header(header_line.as_ref())? {
+                header_map.insert(key, val);
+            }
+        }
+        for header_line in &self.config.extra_headers {
+            if let Some((key, val)) = parse_header(header_line)? {
+                header_map.append(key, val);
+            }
+        }
+
+        let post_body = Arc::new(Mutex::new(Vec::new()));
+        let request = if upload { client.post(url) } else { client.get(url) }.headers(header_map);
+        let state = Arc::new(Mutex::new(State::Pending(Some(Request {
+            client,
+            request,
+            post_body: upload.then(|| post_body.clone()),
+            backend: self.config.backend.clone(),
+            proxy_auth,
+        }))));
+        Ok(http::PostResponse {
+            post_body: PostBody(post_body),
+            headers: Headers {
+                state: state.clone(),
+                buf: None,
+                pos: 0,
+            },
+            body: Body {
+                state,
+                chunk: Reading::Waiting,
+                buf: Vec::new(),
+                pos: 0,
+            },
+        })
+    }
+}
+
+/// Create a new client configured with `config`, along with the action to perform once the proxy credentials were used.
+fn new_client(config: &http::Options) -> Result<(reqwest::Client, Option<ProxyAuth>), http::Error> {
+    let mut builder = reqwest::Client::builder().redirect(match config.follow_redirects {
+        FollowRedirects::None => reqwest::redirect::Policy::none(),
+        FollowRedirects::Initial | FollowRedirects::All => reqwest::redirect::Policy::default(),
+    });
+    if let Some(user_agent) = &config.user_agent {
+        builder = builder.user_agent(user_agent.as_str());
+    }
+    if let Some(timeout) = config.connect_timeout {
+        builder = builder.connect_timeout(timeout);
+    }
+    let mut proxy_auth = None;
+    if let Some(proxy) = &config.proxy {
+        if proxy.is_empty() {
+            builder = builder.no_proxy();
+        } else {
+            let mut proxy = reqwest::Proxy::all(proxy.as_str()).map_err(init_error)?;
+            if let Some((obtain_creds_action, authenticate)) = &config.proxy_authenticate {
+                let creds = authenticate.lock().expect("no panics in other threads")(obtain_creds_action.clone())
+                    .map_err(init_error)?
+                    .expect("action to fetch credentials");
+                proxy = proxy.basic_auth(&creds.identity.username, &creds.identity.password);
+                proxy_auth = Some((creds.next, authenticate.clone()));
+            }
+            builder = builder.proxy(proxy);
+        }
+    }
+    Ok((builder.build().map_err(init_error)?, proxy_auth))
+}
+
+fn init_error(err: impl std::error::Error + Send + Sync + 'static) -> http::Error {
+    http::Error::InitHttpClient { source: Box::new(err) }
+}
+
+/// Parse `header_line` into its name and value, or return `None` if it has no value, failing if it isn't a valid header.
+fn parse_header(
+    header_line: &str,
+) -> Result<Option<(reqwest::header::HeaderName, reqwest::header::HeaderValue)>, http::Error> {
+    let invalid = || http::Error::Detail {
+        description: format!("The header line {:?} is not a valid HTTP header", header_line),
+    };
+    let colon_pos = header_line.find(':').ok_or_else(invalid)?;
+    let value = header_line[colon_pos + 1..].trim();
+    if value.is_empty() {
+        // Headers without value, like `Expect:`, only tell curl to not send them.
+        return Ok(None);
+    }
+    Ok(Some((
+        reqwest::header::HeaderName::from_str(&header_line[..colon_pos]).map_err(|_| invalid())?,
+        reqwest::header::HeaderValue::try_from(value).map_err(|_| invalid())?,
+    )))
+}
+
+/// A request which is sent once the first of its readers is polled.
+struct Request {
+    client: reqwest::Client,
+    request: reqwest::RequestBuilder,
+    post_body: Option<Arc<Mutex<Vec<u8>>>>,
+    backend: Option<Arc<Mutex<dyn Any + Send + Sync + 'static>>>,
+    proxy_auth: Option<ProxyAuth>,
+}
+
+impl Request {
+    fn send(self) -> State {
+        let mut request = self.request;
+        if let Some(post_body) = self.post_body {
+            request = request.body(std::mem::take(&mut *post_body.lock().expect("no panics while writing")));
+        }
+        let mut request = match request.build() {
+            Ok(request) => request,
+            Err(err) => return State::failed(err),
+        };
+        if let Some(ref mut request_options) = self.backend.as_ref().and_then(|backend| backend.lock().ok()) {
+            if let Some(options) = request_options.downcast_mut::<super::Options>() {
+                if let Some(configure_request) = &mut options.configure_request {
+                    if let Err(err) = configure_request(&mut request) {
+                        return State::Failed {
+                            kind: io::ErrorKind::Other,
+                            message: err.to_string(),
+                        };
+                    }
+                }
+            }
+        }
+        State::Sending {
+            response: Box::pin(self.client.execute(request)),
+            proxy_auth: self.proxy_auth,
+        }
+    }
+}
+
+/// The state of a request shared by the readers of its response.
+enum State {
+    Pending(Option<Request>),
+    Sending {
+        response: ResponseFuture,
+        proxy_auth: Option<ProxyAuth>,
+    },
+    Received {
+        headers: Option<Vec<u8>>,
+        body: Option<reqwest::Response>,
+    },
+    Failed {
+        kind: io::ErrorKind,
+        message: String,
+    },
+}
+
+impl State {
+    fn failed(err: reqwest::Error) -> Self {
+        match err.status() {
+            Some(status) => State::Failed {
+                kind: if status == reqwest::StatusCode::UNAUTHORIZED {
+                    io::ErrorKind::PermissionDenied
+                } else {
+                    io::ErrorKind::Other
+                },
+                message: format!("Received HTTP status {}", status.as_str()),
+            },
+            None => State::Failed {
+                kind: io::ErrorKind::Other,
+                message: err.to_string(),
+            },
+        }
+    }
+}
+
+/// Send the request if needed and wait for its response, to then `take` the headers or the body from it.
+fn poll_response<T>(
+    state: &Mutex<State>,
+    cx: &mut Context<'_>,
+    take: impl FnOnce(&mut Option<Vec<u8>>, &mut Option<reqwest::Response>) -> T,
+) -> Poll<io::Result<T>> {
+    let mut state = state.lock().expect("no panics while holding the lock");
+    loop {
+        match &mut *state {
+            State::Pending(request) => *state = request.take().expect("present until sent").send(),
+            State::Sending { response, proxy_auth } => {
+                let response = ready!(response.as_mut().poll(cx)).and_then(reqwest::Response::error_for_status);
+                if let Some((action, authenticate)) = proxy_auth.take() {
+                    // Failing to store or erase the proxy credentials must not fail the request.
+                    authenticate.lock().expect("no panics in other threads")(if response.is_ok() {
+                        action.store()
+                    } else {
+                        action.erase()
+                    })
+                    .ok();
+                }
+                *state = match response {
+                    Ok(response) => State::Received {
+                        headers: Some(header_lines(response.headers())),
+                        body: Some(response),
+                    },
+                    Err(err) => State::failed(err),
+                };
+            }
+            State::Received { headers, body } => return Poll::Ready(Ok(take(headers, body))),
+            State::Failed { kind, message } => return Poll::Ready(Err(io::Error::new(*kind, message.clone()))),
+        }
+    }
+}
+
+fn header_lines(headers: &reqwest::header::HeaderMap) -> Vec<u8> {
+    let mut lines = Vec::new();
+    for (name, value) in headers {
+        lines.extend_from_slice(name.as_str().as_bytes());
+        lines.push(b':');
+        lines.extend_from_slice(value.as_bytes());
+        lines.push(b'\n');
+    }
+    lines
+}
+
+fn read_from_buf(available: &[u8], out: &mut [u8]) -> usize {
+    let n = available.len().min(out.len());
+    out[..n].copy_from_slice(&available[..n]);
+    n
+}
+
+/// The headers of a response, one per line.
+pub struct Headers {
+    state: Arc<Mutex<State>>,
+    buf: Option<Vec<u8>>,
+    pos: usize,
+}
+
+impl AsyncBufRead for Headers {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.buf.is_none() {
+            let headers = ready!(poll_response(&this.state, cx, |headers, _body| headers.take()))?;
+            this.buf = Some(headers.unwrap_or_default());
+        }
+        Poll::Ready(Ok(&this.buf.as_ref().expect("set above")[this.pos..]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.get_mut().pos += amt;
+    }
+}
+
+impl AsyncRead for Headers {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let n = read_from_buf(ready!(self.as_mut().poll_fill_buf(cx))?, buf);
+        self.consume(n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+enum Reading {
+    Waiting,
+    Chunk(ChunkFuture),
+    Done,
+}
+
+fn next_chunk(mut response: reqwest::Response) -> ChunkFuture {
+    Box::pin(async move {
+        let chunk = response.chunk().await.map(|chunk| chunk.map(|bytes| bytes.to_vec()));
+        (response, chunk)
+    })
+}
+
+/// The body of a response, which is streamed as it is read.
+pub struct Body {
+    state: Arc<Mutex<State>>,
+    chunk: Reading,
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl AsyncBufRead for Body {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        while this.pos == this.buf.len() {
+            match &mut this.chunk {
+                Reading::Waiting => {
+                    let response = ready!(poll_response(&this.state, cx, |_headers, body| body.take()))?
+                        .expect("body is only taken once");
+                    this.chunk = Reading::Chunk(next_chunk(response));
+                }
+                Reading::Chunk(chunk) => {
+                    let (response, chunk) = ready!(chunk.as_mut().poll(cx));
+                    match chunk {
+                        Ok(Some(chunk)) => {
+                            this.buf = chunk;
+                            this.pos = 0;
+                            this.chunk = Reading::Chunk(next_chunk(response));
+                        }
+                        Ok(None) => this.chunk = Reading::Done,
+                        Err(err) => {
+                            this.chunk = Reading::Done;
+                            return Poll::Ready(Err(io::Error::new(io::ErrorKind::Other, err)));
+                        }
+                    }
+                }
+                Reading::Done => break,
+            }
+        }
+        Poll::Ready(Ok(&this.buf[this.pos..]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.get_mut().pos += amt;
+    }
+}
+
+impl AsyncRead for Body {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let n = read_from_buf(ready!(self.as_mut().poll_fill_buf(cx))?, buf);
+        self.consume(n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+/// The body to post, which is kept in memory until the request is sent.
+pub struct PostBody(Arc<Mutex<Vec<u8>>>);
+
+impl AsyncWrite for PostBody {
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.0.lock().expect("no panics while sending").extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl http::Http for Remote {
+    type Headers = Headers;
+    type ResponseBody = Body;
+    type PostBody = PostBody;
+
+    fn get(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<http::GetResponse<Self::Headers, Self::ResponseBody>, http::Error> {
+        self.make_request(url, headers, false).map(Into::into)
+    }
+
+    fn post(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<http::PostResponse<Self::Headers, Self::ResponseBody, Self::PostBody>, http::Error> {
+        self.make_request(url, headers, true)
+    }
+
+    fn configure(&mut self, config: &dyn Any) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+        if let Some(config) = config.downcast_ref::<http::Options>() {
+            self.config = config.clone();
+            self.client = None;
+        }
+        Ok(())
+    }
+}
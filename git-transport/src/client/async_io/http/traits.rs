@@ -0,0 +1,47 @@
+pub use crate::client::non_io_types::http::{Error, GetResponse, PostResponse};
+
+/// A trait to abstract the HTTP operations needed to power all git interactions: read via GET and write via POST.
+/// Note that 401 must be turned into `std::io::Error(PermissionDenied)`, and other non-success http stati must be transformed
+/// into `std::io::Error(Other)`
+///
+/// As [`request()`][crate::client::TransportWithoutIO::request()] isn't async, requests are expected to be sent only once
+/// the headers or the body of their response are read, which happens after all data was written to the
+/// [`post_body`][PostResponse::post_body].
+#[allow(clippy::type_complexity)]
+pub trait Http {
+    /// A type providing headers line by line.
+    type Headers: futures_io::AsyncBufRead + Unpin;
+    /// A type providing the response.
+    type ResponseBody: futures_io::AsyncBufRead + Unpin;
+    /// A type allowing to write the content to post.
+    type PostBody: futures_io::AsyncWrite + Unpin;
+
+    /// Initiate a `GET` request to `url` provided the given `headers`.
+    ///
+    /// The `headers` are provided verbatim and include both the key as well as the value.
+    fn get(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<GetResponse<Self::Headers, Self::ResponseBody>, Error>;
+
+    /// Initiate a `POST` request to `url` providing with the given `headers`.
+    ///
+    /// The `headers` are provided verbatim and include both the key as well as the value.
+    /// Note that the [`PostResponse`] contains the [`post_body`][PostResponse::post_body] field which implements
+    /// [`AsyncWrite`][futures_io::AsyncWrite] and is expected to receive the body to post to the server.
+    /// **It must be written entirely** before reading the response.
+    fn post(
+        &mut self,
+        url: &str,
+        headers: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<PostResponse<Self::Headers, Self::ResponseBody, Self::PostBody>, Error>;
+
+    /// Pass `config` which can deserialize in the implementation's configuration, as documented separately.
+    ///
+    /// The caller must know how that `config` data looks like for the intended implementation.
+    fn configure(
+        &mut self,
+        config: &dyn std::any::Any,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+}
@@ -9,5 +9,9 @@ pub use traits::{SetServiceResponse, Transport, TransportV2Ext};
 
 ///
 pub mod connect;
-#[cfg(any(feature = "async-std"))]
+#[cfg(any(feature = "async-std", feature = "async-http-client-reqwest"))]
 pub use connect::function::connect;
+
+///
+#[cfg(feature = "async-http-client")]
+pub mod http;
@@ -1,25 +1,31 @@
-pub use crate::client::non_io_types::connect::Error;
+pub use crate::client::non_io_types::connect::{Error, Options};
 
 pub(crate) mod function {
     use std::convert::TryInto;
 
-    use crate::client::{non_io_types::connect::Error, Transport};
+    use crate::client::{
+        non_io_types::connect::{Error, Options},
+        Transport,
+    };
 
     /// A general purpose connector connecting to a repository identified by the given `url`.
     ///
     /// This includes connections to
     /// [local repositories][crate::client::file::connect()],
-    /// [repositories over ssh][crate::client::ssh::connect()],
+    /// [repositories over ssh][crate::client::ssh::connect()] (or [built-in ssh][crate::client::ssh::libssh2::connect()]
+    /// if compiled in and no ssh command is configured),
     /// [git daemons][crate::client::git::connect()],
     /// and if compiled in connections to [git repositories over https][crate::client::http::connect()].
     ///
-    /// Use `desired_version` to set the desired protocol version to use when connecting, but note that the server may downgrade it.
-    pub fn connect<Url, E>(url: Url, desired_version: crate::Protocol) -> Result<Box<dyn Transport + Send>, Error>
+    /// Use `options` to set the desired protocol version to use when connecting, but note that the server may downgrade it,
+    /// along with options for specific transports.
+    pub fn connect<Url, E>(url: Url, options: Options) -> Result<Box<dyn Transport + Send>, Error>
     where
         Url: TryInto<git_url::Url, Error = E>,
         git_url::parse::Error: From<E>,
     {
         let mut url = url.try_into().map_err(git_url::parse::Error::from)?;
+        let desired_version = options.version;
         Ok(match url.scheme {
             git_url::Scheme::Ext(_) => return Err(Error::UnsupportedScheme(url.scheme)),
             git_url::Scheme::File => {
@@ -34,17 +40,34 @@ pub(crate) mod function {
                         .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?,
                 )
             }
-            git_url::Scheme::Ssh => Box::new({
+            git_url::Scheme::Ssh => {
                 let path = std::mem::take(&mut url.path);
-                crate::client::blocking_io::ssh::connect(
-                    url.host().expect("host is present in url"),
-                    path,
-                    desired_version,
-                    url.user(),
-                    url.port,
+                #[cfg(feature = "ssh-client-libssh2")]
+                if options.ssh.command().is_none() {
+                    return Ok(Box::new(
+                        crate::client::blocking_io::ssh::libssh2::connect(
+                            url.host().expect("host is present in url"),
+                            path,
+                            desired_version,
+                            url.user(),
+                            url.port,
+                            options.ssh.builtin,
+                        )
+                        .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?,
+                    ));
+                }
+                Box::new(
+                    crate::client::blocking_io::ssh::connect(
+                        url.host().expect("host is present in url"),
+                        path,
+                        desired_version,
+                        url.user(),
+                        url.port,
+                        options.ssh,
+                    )
+                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?,
                 )
-                .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?
-            }),
+            }
             git_url::Scheme::Git => {
                 if url.user().is_some() {
                     return Err(Error::UnsupportedUrlTokens {
@@ -39,9 +39,9 @@ pub struct SpawnProcessOnDemand {
     desired_version: Protocol,
     url: git_url::Url,
     pub(crate) path: BString,
-    ssh_program: Option<String>,
-    ssh_args: Vec<String>,
-    ssh_env: Vec<(&'static str, String)>,
+    pub(crate) ssh_program: Option<String>,
+    pub(crate) ssh_args: Vec<String>,
+    pub(crate) ssh_env: Vec<(&'static str, String)>,
     connection: Option<git::Connection<process::ChildStdout, process::ChildStdin>>,
     child: Option<process::Child>,
 }
@@ -130,7 +130,11 @@ impl client::Transport for SpawnProcessOnDemand {
         if self.ssh_program.is_some() {
             cmd.arg(service.as_str());
         }
-        cmd.arg("--strict").arg("--timeout=0").arg(self.path.to_os_str_lossy());
+        if service == Service::UploadPack {
+            // `git receive-pack` doesn't understand these.
+            cmd.arg("--strict").arg("--timeout=0");
+        }
+        cmd.arg(self.path.to_os_str_lossy());
 
         let mut child = cmd.spawn()?;
         self.connection = Some(git::Connection::new_for_spawned_process(
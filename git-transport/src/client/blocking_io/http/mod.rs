@@ -2,13 +2,14 @@ use std::{
     any::Any,
     borrow::Cow,
     io::{BufRead, Read},
-    sync::{Arc, Mutex},
 };
 
 use bstr::BStr;
 use git_packetline::PacketLineRef;
 pub use traits::{Error, GetResponse, Http, PostResponse};
 
+pub use crate::client::non_io_types::http::{options, AuthenticateFn, Options};
+
 use crate::{
     client::{self, capabilities, Capabilities, ExtendedBufRead, HandleProgress, MessageKind, RequestWriter},
     Protocol, Service,
@@ -30,103 +31,6 @@ pub mod reqwest;
 ///
 mod traits;
 
-///
-pub mod options {
-    /// Possible settings for the `http.followRedirects` configuration option.
-    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-    pub enum FollowRedirects {
-        /// Follow only the first redirect request, most suitable for typical git requests.
-        Initial,
-        /// Follow all redirect requests from the server unconditionally
-        All,
-        /// Follow no redirect request.
-        None,
-    }
-
-    impl Default for FollowRedirects {
-        fn default() -> Self {
-            FollowRedirects::Initial
-        }
-    }
-
-    /// The way to configure a proxy for authentication if a username is present in the configured proxy.
-    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-    pub enum ProxyAuthMethod {
-        /// Automatically pick a suitable authentication method.
-        AnyAuth,
-        ///HTTP basic authentication.
-        Basic,
-        /// Http digest authentication to prevent a password to be passed in clear text.
-        Digest,
-        /// GSS negotiate authentication.
-        Negotiate,
-        /// NTLM authentication
-        Ntlm,
-    }
-
-    impl Default for ProxyAuthMethod {
-        fn default() -> Self {
-            ProxyAuthMethod::AnyAuth
-        }
-    }
-}
-
-/// A function to authenticate a URL.
-pub type AuthenticateFn = dyn FnMut(git_credentials::helper::Action) -> git_credentials::protocol::Result + Send + Sync;
-
-/// Options to configure curl requests.
-// TODO: testing most of these fields requires a lot of effort, unless special flags to introspect ongoing requests are added.
-#[derive(Default, Clone)]
-pub struct Options {
-    /// Headers to be added to every request.
-    /// They are applied unconditionally and are expected to be valid as they occour in an HTTP request, like `header: value`, without newlines.
-    ///
-    /// Refers to `http.extraHeader` multi-var.
-    pub extra_headers: Vec<String>,
-    /// How to handle redirects.
-    ///
-    /// Refers to `http.followRedirects`.
-    pub follow_redirects: options::FollowRedirects,
-    /// Used in conjunction with `low_speed_time_seconds`, any non-0 value signals the amount of bytes per second at least to avoid
-    /// aborting the connection.
-    ///
-    /// Refers to `http.lowSpeedLimit`.
-    pub low_speed_limit_bytes_per_second: u32,
-    /// Used in conjunction with `low_speed_bytes_per_second`, any non-0 value signals the amount seconds the minimal amount
-    /// of bytes per second isn't reached.
-    ///
-    /// Refers to `http.lowSpeedTime`.
-    pub low_speed_time_seconds: u64,
-    /// A curl-style proxy declaration of the form `[protocol://][user[:password]@]proxyhost[:port]`.
-    ///
-    /// Refers to `http.proxy`.
-    pub proxy: Option<String>,
-    /// The way to authenticate against the proxy if the `proxy` field contains a username.
-    ///
-    /// Refers to `http.proxyAuthMethod`.
-    pub proxy_auth_method: options::ProxyAuthMethod,
-    /// If authentication is needed for the proxy as its URL contains a username, this method must be set to provide a password
-    /// for it before making the request, and to store it if the connection succeeds.
-    pub proxy_authenticate: Option<(git_credentials::helper::Action, Arc<std::sync::Mutex<AuthenticateFn>>)>,
-    /// The `HTTP` `USER_AGENT` string presented to an `HTTP` server, notably not the user agent present to the `git` server.
-    ///
-    /// If not overridden, it defaults to the user agent provided by `curl`, which is a deviation from how `git` handles this.
-    /// Thus it's expected from the callers to set it to their application, or use higher-level crates which make it easy to do this
-    /// more correctly.
-    ///
-    /// Using the correct user-agent might affect how the server treats the request.
-    ///
-    /// Refers to `http.userAgent`.
-    pub user_agent: Option<String>,
-    /// The amount of time we wait until aborting a connection attempt.
-    ///
-    /// If `None`, this typically defaults to 2 minutes to 5 minutes.
-    /// Refers to `gitoxide.http.connectTimeout`.
-    pub connect_timeout: Option<std::time::Duration>,
-    /// Backend specific options, if available.
-    pub backend: Option<Arc<Mutex<dyn Any + Send + Sync + 'static>>>,
-}
-
 /// The actual http client implementation, using curl
 #[cfg(feature = "http-client-curl")]
 pub type Impl = curl::Curl;
@@ -1,45 +1,4 @@
-/// The error used by the [Http] trait.
-#[derive(Debug, thiserror::Error)]
-#[allow(missing_docs)]
-pub enum Error {
-    #[error("Could initialize the http client")]
-    InitHttpClient {
-        source: Box<dyn std::error::Error + Send + Sync + 'static>,
-    },
-    #[error("{description}")]
-    Detail { description: String },
-    #[error("An IO error occurred while uploading the body of a POST request")]
-    PostBody(#[from] std::io::Error),
-}
-
-/// The return value of [Http::get()].
-pub struct GetResponse<H, B> {
-    /// The response headers.
-    pub headers: H,
-    /// The response body.
-    pub body: B,
-}
-
-/// The return value of [Http::post()].
-pub struct PostResponse<H, B, PB> {
-    /// The body to post to the server as part of the request.
-    ///
-    /// **Note**: Implementations should drop the handle to avoid deadlocks.
-    pub post_body: PB,
-    /// The headers of the post response.
-    pub headers: H,
-    /// The body of the post response.
-    pub body: B,
-}
-
-impl<A, B, C> From<PostResponse<A, B, C>> for GetResponse<A, B> {
-    fn from(v: PostResponse<A, B, C>) -> Self {
-        GetResponse {
-            headers: v.headers,
-            body: v.body,
-        }
-    }
-}
+pub use crate::client::non_io_types::http::{Error, GetResponse, PostResponse};
 
 /// A trait to abstract the HTTP operations needed to power all git interactions: read via GET and write via POST.
 /// Note that 401 must be turned into `std::io::Error(PermissionDenied)`, and other non-success http stati must be transformed
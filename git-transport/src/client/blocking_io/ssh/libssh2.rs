@@ -0,0 +1,244 @@
+use std::{
+    any::Any,
+    borrow::Cow,
+    error::Error as StdError,
+    io,
+    net::TcpStream,
+    path::{Path, PathBuf},
+};
+
+use bstr::{BStr, BString, ByteSlice};
+
+use crate::{
+    client::{self, git, MessageKind, RequestWriter, SetServiceResponse, WriteMode},
+    Protocol, Service,
+};
+
+/// Options for the built-in ssh transport.
+#[derive(Default, Debug, Clone)]
+pub struct Options {
+    /// The `known_hosts` files to verify the host key of the server with, or `~/.ssh/known_hosts` if empty.
+    pub known_hosts_files: Vec<PathBuf>,
+    /// The private key files to try for authentication if the ssh agent couldn't authenticate us, or
+    /// `~/.ssh/id_ed25519`, `~/.ssh/id_ecdsa` and `~/.ssh/id_rsa` if empty.
+    pub identity_files: Vec<PathBuf>,
+}
+
+/// The error used in [`connect()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not connect to {host}:{port}")]
+    Connect { host: String, port: u16, source: io::Error },
+    #[error(transparent)]
+    Ssh(#[from] ssh2::Error),
+    #[error("Could not read known hosts from \"{}\"", path.display())]
+    ReadKnownHosts { path: PathBuf, source: ssh2::Error },
+    #[error("The host key of {host:?} wasn't found in any known_hosts file")]
+    UnknownHostKey { host: String },
+    #[error("The host key of {host:?} doesn't match the one in the known_hosts file, someone could be eavesdropping")]
+    HostKeyMismatch { host: String },
+    #[error("The host key of {host:?} could not be verified")]
+    HostKeyCheck { host: String },
+    #[error("Could not authenticate as {user:?} with the ssh agent or any of the identity files")]
+    Authentication { user: String },
+    #[error("No user was given and it couldn't be obtained from the environment")]
+    MissingUser,
+}
+
+/// A transport talking to a remote `git` process through an in-process ssh session.
+///
+/// It can only be instantiated using [`connect()`].
+pub struct Transport {
+    session: ssh2::Session,
+    url: git_url::Url,
+    path: BString,
+    desired_version: Protocol,
+    connection: Option<git::Connection<ssh2::Stream, ssh2::Stream>>,
+    channel: Option<ssh2::Channel>,
+}
+
+impl client::TransportWithoutIO for Transport {
+    fn request(
+        &mut self,
+        write_mode: WriteMode,
+        on_into_read: MessageKind,
+    ) -> Result<RequestWriter<'_>, client::Error> {
+        self.connection
+            .as_mut()
+            .expect("handshake() to have been called first")
+            .request(write_mode, on_into_read)
+    }
+
+    fn to_url(&self) -> Cow<'_, BStr> {
+        Cow::Owned(self.url.to_bstring())
+    }
+
+    fn connection_persists_across_multiple_requests(&self) -> bool {
+        true
+    }
+
+    fn configure(&mut self, _config: &dyn Any) -> Result<(), Box<dyn StdError + Send + Sync + 'static>> {
+        Ok(())
+    }
+}
+
+impl client::Transport for Transport {
+    fn handshake<'a>(
+        &mut self,
+        service: Service,
+        extra_parameters: &'a [(&'a str, Option<&'a str>)],
+    ) -> Result<SetServiceResponse<'_>, client::Error> {
+        assert!(
+            self.connection.is_none(),
+            "cannot handshake twice with the same connection"
+        );
+        let mut channel = self.session.channel_session().map_err(io::Error::from)?;
+        // Nothing reads the stderr of the remote process, which would otherwise fill the window of the channel until it stalls.
+        channel
+            .handle_extended_data(ssh2::ExtendedData::Ignore)
+            .map_err(io::Error::from)?;
+        if self.desired_version != Protocol::V1 {
+            // Servers are free to not accept the variable, which makes them fall back to V1.
+            channel
+                .setenv("GIT_PROTOCOL", &format!("version={}", self.desired_version as usize))
+                .ok();
+        }
+        channel
+            .exec(&format!("{} {}", service.as_str(), shell_quote(self.path.as_ref())))
+            .map_err(io::Error::from)?;
+
+        self.connection = Some(git::Connection::new_for_spawned_process(
+            channel.stream(0),
+            channel.stream(0),
+            self.desired_version,
+            self.path.clone(),
+        ));
+        self.channel = Some(channel);
+        let c = self
+            .connection
+            .as_mut()
+            .expect("connection to be there right after setting it");
+        c.handshake(service, extra_parameters)
+    }
+}
+
+/// Connect to `host` using a built-in ssh client to obtain data from the repository at `path` on the remote.
+///
+/// The optional `user` identifies the user's account to which to connect and defaults to the current user, while `port`
+/// allows to specify non-standard ssh ports.
+///
+/// The host key of the server must be present in one of the `known_hosts` files of `options`, and we authenticate
+/// with the keys of the ssh agent first, and with the identity files of `options` if that fails.
+///
+/// The `desired_version` is the preferred protocol version when establishing the connection, but note that it can be
+/// downgraded by servers not supporting it.
+pub fn connect(
+    host: &str,
+    path: BString,
+    desired_version: Protocol,
+    user: Option<&str>,
+    port: Option<u16>,
+    options: Options,
+) -> Result<Transport, Error> {
+    let user = match user {
+        Some(user) => user.to_owned(),
+        None => std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .map_err(|_| Error::MissingUser)?,
+    };
+    let port_or_default = port.unwrap_or(22);
+    let stream = TcpStream::connect((host, port_or_default)).map_err(|source| Error::Connect {
+        host: host.into(),
+        port: port_or_default,
+        source,
+    })?;
+    let mut session = ssh2::Session::new()?;
+    session.set_tcp_stream(stream);
+    session.handshake()?;
+
+    verify_host_key(&session, host, port_or_default, &options.known_hosts_files)?;
+    authenticate(&session, &user, &options.identity_files)?;
+
+    let path = git_url::expand_path::for_shell(path);
+    let url = git_url::Url::from_parts(git_url::Scheme::Ssh, Some(user), Some(host.into()), port, path.clone())
+        .expect("valid url");
+    Ok(Transport {
+        session,
+        url,
+        path,
+        desired_version,
+        connection: None,
+        channel: None,
+    })
+}
+
+fn verify_host_key(session: &ssh2::Session, host: &str, port: u16, known_hosts_files: &[PathBuf]) -> Result<(), Error> {
+    let (key, _kind) = session
+        .host_key()
+        .ok_or_else(|| Error::HostKeyCheck { host: host.into() })?;
+    let mut known_hosts = session.known_hosts()?;
+    for path in files_or_defaults(known_hosts_files, &["known_hosts"]) {
+        if path.is_file() {
+            known_hosts
+                .read_file(&path, ssh2::KnownHostFileKind::OpenSSH)
+                .map_err(|source| Error::ReadKnownHosts { path, source })?;
+        }
+    }
+    match known_hosts.check_port(host, port, key) {
+        ssh2::CheckResult::Match => Ok(()),
+        ssh2::CheckResult::NotFound => Err(Error::UnknownHostKey { host: host.into() }),
+        ssh2::CheckResult::Mismatch => Err(Error::HostKeyMismatch { host: host.into() }),
+        ssh2::CheckResult::Failure => Err(Error::HostKeyCheck { host: host.into() }),
+    }
+}
+
+fn authenticate(session: &ssh2::Session, user: &str, identity_files: &[PathBuf]) -> Result<(), Error> {
+    if let Ok(mut agent) = session.agent() {
+        // Without a running agent this fails, and we fall back to the identity files.
+        if agent.connect().is_ok() && agent.list_identities().is_ok() {
+            for identity in agent.identities().unwrap_or_default() {
+                if agent.userauth(user, &identity).is_ok() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    for path in files_or_defaults(identity_files, &["id_ed25519", "id_ecdsa", "id_rsa"]) {
+        if path.is_file() && session.userauth_pubkey_file(user, None, &path, None).is_ok() {
+            return Ok(());
+        }
+    }
+    Err(Error::Authentication { user: user.into() })
+}
+
+/// Return `files`, or the files with `default_names` in the `.ssh` directory of the user's home if there are none.
+fn files_or_defaults(files: &[PathBuf], default_names: &[&str]) -> Vec<PathBuf> {
+    if !files.is_empty() {
+        return files.to_vec();
+    }
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| {
+            default_names
+                .iter()
+                .map(|name| Path::new(&home).join(".ssh").join(name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Quote `path` for use in the remote shell just like `git` does, which leaves tilde expansion to the remote `git` program.
+fn shell_quote(path: &BStr) -> String {
+    let mut quoted = String::with_capacity(path.len() + 2);
+    quoted.push('\'');
+    for c in path.to_str_lossy().chars() {
+        match c {
+            '\'' => quoted.push_str("'\\''"),
+            '!' => quoted.push_str("'\\!'"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
@@ -0,0 +1,318 @@
+use bstr::BString;
+
+use crate::client::blocking_io;
+
+///
+#[cfg(feature = "ssh-client-libssh2")]
+pub mod libssh2;
+
+mod program_kind;
+pub use program_kind::ProgramKind;
+
+///
+pub mod connect {
+    use super::ProgramKind;
+
+    /// The IP version to force the ssh program to use, as set by the `--ipv4` and `--ipv6` flags of `git`.
+    #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+    pub enum IpVersion {
+        /// Only use IPv4 addresses.
+        V4,
+        /// Only use IPv6 addresses.
+        V6,
+    }
+
+    /// Options for connecting to a remote repository using `ssh`.
+    #[derive(Default, Debug, Clone)]
+    pub struct Options {
+        /// The ssh command line to run instead of `ssh`, typically from the `core.sshCommand` configuration.
+        /// Note that the `GIT_SSH_COMMAND` environment variable takes precedence if set.
+        pub command: Option<String>,
+        /// The kind of ssh program to run, typically from the `ssh.variant` configuration, or `None` to derive it from the
+        /// name of the program and [detect][ProgramKind::detect()] it if it isn't known.
+        /// Note that the `GIT_SSH_VARIANT` environment variable takes precedence if set.
+        pub kind: Option<ProgramKind>,
+        /// The IP version the ssh program should use, or `None` to let it choose.
+        pub ip_version: Option<IpVersion>,
+        /// Options for the built-in ssh transport, used if no `command` is configured.
+        #[cfg(feature = "ssh-client-libssh2")]
+        pub builtin: super::libssh2::Options,
+    }
+
+    impl Options {
+        /// Return the ssh command line to run, from the `GIT_SSH_COMMAND` environment variable or our `command`, in that order,
+        /// or `None` if neither is set.
+        pub fn command(&self) -> Option<String> {
+            std::env::var("GIT_SSH_COMMAND").ok().or_else(|| self.command.clone())
+        }
+
+        /// Return the kind of ssh program, from the `GIT_SSH_VARIANT` environment variable or our `kind`, in that order,
+        /// or `None` if it should be derived from the program.
+        pub fn kind(&self) -> Option<ProgramKind> {
+            match std::env::var("GIT_SSH_VARIANT") {
+                Ok(variant) => ProgramKind::from_variant(&variant),
+                Err(_) => self.kind,
+            }
+        }
+    }
+}
+
+/// The error used in [`connect()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The ssh variant {kind:?} does not support {option}")]
+    UnsupportedOption { kind: ProgramKind, option: &'static str },
+}
+
+/// Connect to `host` using the ssh program to obtain data from the repository at `path` on the remote.
+///
+/// The optional `user` identifies the user's account to which to connect, while `port` allows to specify non-standard
+/// ssh ports.
+///
+/// The `desired_version` is the preferred protocol version when establishing the connection, but note that it can be
+/// downgraded by servers not supporting it.
+///
+/// The ssh program to run can be set with the `command` of `options`, and defaults to `ssh`. The way options are passed to it
+/// depends on its [kind][ProgramKind], which is configured in `options` or derived from the name of the program. Programs
+/// with unknown names are run with `-G` first, and are assumed to not support any option if that fails.
+///
+/// # Environment Variables
+///
+/// Use `GIT_SSH_COMMAND` to override the `ssh` program to execute. This can be a script dealing with using the correct
+/// ssh key, for example. `GIT_SSH_VARIANT` overrides the kind of program just like `ssh.variant` would.
+pub fn connect(
+    host: &str,
+    path: BString,
+    desired_version: crate::Protocol,
+    user: Option<&str>,
+    port: Option<u16>,
+    options: connect::Options,
+) -> Result<blocking_io::file::SpawnProcessOnDemand, Error> {
+    let ssh_cmd_line = options.command().unwrap_or_else(|| "ssh".into());
+    let mut ssh_cmd_line = ssh_cmd_line.split(' ');
+    let ssh_cmd = ssh_cmd_line.next().expect("there is always a single item");
+    let ssh_cmd_args: Vec<_> = ssh_cmd_line.collect();
+
+    let host = match user.as_ref() {
+        Some(user) => format!("{}@{}", user, host),
+        None => host.into(),
+    };
+
+    let kind = options
+        .kind()
+        .or_else(|| ProgramKind::from_program(ssh_cmd))
+        .unwrap_or_else(|| ProgramKind::detect(ssh_cmd, &ssh_cmd_args, &host, port, options.ip_version));
+    let (args, env) = kind.options(desired_version, port, options.ip_version)?;
+
+    let path = git_url::expand_path::for_shell(path);
+    let url = git_url::Url::from_parts(
+        git_url::Scheme::Ssh,
+        user.map(Into::into),
+        Some(host.clone()),
+        port,
+        path.clone(),
+    )
+    .expect("valid url");
+    Ok(blocking_io::file::SpawnProcessOnDemand::new_ssh(
+        url,
+        ssh_cmd.into(),
+        ssh_cmd_args.into_iter().map(String::from).chain(args).chain(Some(host)),
+        env,
+        path,
+        desired_version,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use bstr::ByteSlice;
+
+    use crate::{
+        client::blocking_io::ssh::{
+            connect,
+            connect::{IpVersion, Options},
+            Error, ProgramKind,
+        },
+        Protocol,
+    };
+
+    #[test]
+    fn connect_with_tilde_in_path() {
+        for (url, expected) in &[
+            ("ssh://host.xy/~/repo", "~/repo"),
+            ("ssh://host.xy/~username/repo", "~username/repo"),
+        ] {
+            let url = git_url::parse((*url).into()).expect("valid url");
+            let cmd = connect("host", url.path, Protocol::V1, None, None, Default::default()).expect("parse success");
+            assert_eq!(
+                cmd.path,
+                expected.as_bytes().as_bstr(),
+                "the path is prepared to be substituted by the remote shell"
+            );
+        }
+    }
+
+    fn options(kind: ProgramKind) -> Options {
+        Options {
+            command: Some("ssh-program --verbose".into()),
+            kind: Some(kind),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn program_kind_from_variant_and_program() {
+        assert_eq!(ProgramKind::from_variant("auto"), None);
+        assert_eq!(ProgramKind::from_variant("putty"), Some(ProgramKind::Putty));
+        assert_eq!(ProgramKind::from_variant("simple"), Some(ProgramKind::Simple));
+        assert_eq!(
+            ProgramKind::from_variant("unknown"),
+            Some(ProgramKind::Ssh),
+            "git treats unknown variants as ssh"
+        );
+
+        assert_eq!(ProgramKind::from_program("/usr/bin/ssh"), Some(ProgramKind::Ssh));
+        assert_eq!(ProgramKind::from_program("SSH.EXE"), Some(ProgramKind::Ssh));
+        assert_eq!(ProgramKind::from_program("plink.exe"), Some(ProgramKind::Plink));
+        assert_eq!(
+            ProgramKind::from_program("TortoisePlink.exe"),
+            Some(ProgramKind::TortoisePlink)
+        );
+        assert_eq!(
+            ProgramKind::from_program("putty"),
+            None,
+            "putty can only be configured, just like in git"
+        );
+        assert_eq!(ProgramKind::from_program("my-ssh"), None);
+    }
+
+    #[test]
+    fn program_kind_determines_how_options_are_passed() {
+        for (kind, version, ip_version, expected_args, expected_env) in [
+            (
+                ProgramKind::Ssh,
+                Protocol::V2,
+                Some(IpVersion::V4),
+                &[
+                    "--verbose",
+                    "-o",
+                    "SendEnv=GIT_PROTOCOL",
+                    "-4",
+                    "-p",
+                    "2222",
+                    "user@host",
+                ][..],
+                &[("GIT_PROTOCOL", "version=2")][..],
+            ),
+            (
+                ProgramKind::Ssh,
+                Protocol::V1,
+                None,
+                &["--verbose", "-p", "2222", "user@host"],
+                &[],
+            ),
+            (
+                ProgramKind::Plink,
+                Protocol::V2,
+                Some(IpVersion::V6),
+                &["--verbose", "-6", "-P", "2222", "user@host"],
+                &[],
+            ),
+            (
+                ProgramKind::Putty,
+                Protocol::V2,
+                None,
+                &["--verbose", "-P", "2222", "user@host"],
+                &[],
+            ),
+            (
+                ProgramKind::TortoisePlink,
+                Protocol::V2,
+                None,
+                &["--verbose", "-batch", "-P", "2222", "user@host"],
+                &[],
+            ),
+        ] {
+            let cmd = connect(
+                "host",
+                "/repo".into(),
+                version,
+                Some("user"),
+                Some(2222),
+                Options {
+                    ip_version,
+                    ..options(kind)
+                },
+            )
+            .expect("all options are supported");
+            assert_eq!(cmd.ssh_program.as_deref(), Some("ssh-program"));
+            assert_eq!(cmd.ssh_args, expected_args, "{:?}", kind);
+            assert_eq!(
+                cmd.ssh_env,
+                expected_env
+                    .iter()
+                    .map(|(k, v)| (*k, v.to_string()))
+                    .collect::<Vec<_>>(),
+                "{:?}",
+                kind
+            );
+        }
+    }
+
+    #[test]
+    fn simple_programs_only_receive_the_host() {
+        let cmd = connect(
+            "host",
+            "/repo".into(),
+            Protocol::V2,
+            None,
+            None,
+            options(ProgramKind::Simple),
+        )
+        .expect("no option to pass");
+        assert_eq!(cmd.ssh_args, &["--verbose", "host"]);
+        assert!(cmd.ssh_env.is_empty());
+
+        for (port, ip_version, unsupported) in [
+            (Some(2222), None, "setting the port"),
+            (None, Some(IpVersion::V4), "-4"),
+        ] {
+            let err = connect(
+                "host",
+                "/repo".into(),
+                Protocol::V2,
+                None,
+                port,
+                Options {
+                    ip_version,
+                    ..options(ProgramKind::Simple)
+                },
+            )
+            .err()
+            .expect("option isn't supported");
+            assert!(
+                matches!(err, Error::UnsupportedOption { kind: ProgramKind::Simple, option } if option == unsupported),
+                "{:?}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_programs_are_detected_as_simple_if_they_fail_with_dash_g() {
+        let cmd = connect(
+            "host",
+            "/repo".into(),
+            Protocol::V2,
+            None,
+            None,
+            Options {
+                command: Some("false".into()),
+                ..Default::default()
+            },
+        )
+        .expect("simple programs are supported");
+        assert_eq!(cmd.ssh_args, &["host"], "no option is passed to simple programs");
+    }
+}
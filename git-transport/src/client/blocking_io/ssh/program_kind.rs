@@ -0,0 +1,130 @@
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use crate::{
+    client::ssh::{connect::IpVersion, Error},
+    Protocol,
+};
+
+type EnvVar = (&'static str, String);
+
+/// The kind of ssh program, which determines how options are passed to it, as configured by `ssh.variant`.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum ProgramKind {
+    /// The `ssh` program of OpenSSH, which supports all options.
+    Ssh,
+    /// The `plink` program of PuTTY.
+    Plink,
+    /// Like [`Plink`][ProgramKind::Plink], but for `putty` itself.
+    Putty,
+    /// The `tortoiseplink` program, which is like `plink` but needs to be told to not interact with the user.
+    TortoisePlink,
+    /// A program which supports no options at all, and only receives the host and the command to run.
+    Simple,
+}
+
+impl ProgramKind {
+    /// Parse the value of the `ssh.variant` configuration or the `GIT_SSH_VARIANT` environment variable, and return `None` for
+    /// `auto` to indicate that the kind should be derived from the program.
+    ///
+    /// Unknown values are treated as [`Ssh`][ProgramKind::Ssh] just like `git` does.
+    pub fn from_variant(name: &str) -> Option<Self> {
+        Some(match name {
+            "auto" => return None,
+            "plink" => ProgramKind::Plink,
+            "putty" => ProgramKind::Putty,
+            "tortoiseplink" => ProgramKind::TortoisePlink,
+            "simple" => ProgramKind::Simple,
+            _ => ProgramKind::Ssh,
+        })
+    }
+
+    /// Derive the kind from the name of the `program`, or return `None` if it isn't known and has to be
+    /// [detected][ProgramKind::detect()].
+    pub fn from_program(program: &str) -> Option<Self> {
+        let name = Path::new(program).file_name()?.to_str()?.to_ascii_lowercase();
+        Some(match name.as_str() {
+            "ssh" | "ssh.exe" => ProgramKind::Ssh,
+            "plink" | "plink.exe" => ProgramKind::Plink,
+            "tortoiseplink" | "tortoiseplink.exe" => ProgramKind::TortoisePlink,
+            _ => return None,
+        })
+    }
+
+    /// Find out if `program` with its `args` understands the options of OpenSSH by running it with `-G` to print its
+    /// configuration for `host`, and assume it's [`Simple`][ProgramKind::Simple] if that fails.
+    pub fn detect(program: &str, args: &[&str], host: &str, port: Option<u16>, ip_version: Option<IpVersion>) -> Self {
+        let (ssh_args, _env) = ProgramKind::Ssh
+            .options(Protocol::V1, port, ip_version)
+            .expect("ssh supports all options");
+        let detected = Command::new(program)
+            .args(args)
+            .arg("-G")
+            .args(ssh_args)
+            .arg(host)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_or(false, |status| status.success());
+        if detected {
+            ProgramKind::Ssh
+        } else {
+            ProgramKind::Simple
+        }
+    }
+
+    /// Return the arguments to pass to the program before the host, and the environment variables to set, to connect to `port`
+    /// using `ip_version` and to ask for the `desired_version` of the protocol.
+    ///
+    /// Note that only [`Ssh`][ProgramKind::Ssh] can send the desired protocol version, and that
+    /// [`Simple`][ProgramKind::Simple] programs can't be told which port or IP version to use.
+    pub fn options(
+        &self,
+        desired_version: Protocol,
+        port: Option<u16>,
+        ip_version: Option<IpVersion>,
+    ) -> Result<(Vec<String>, Vec<EnvVar>), Error> {
+        let mut args = Vec::new();
+        let mut env = Vec::new();
+        if *self == ProgramKind::Ssh && desired_version != Protocol::V1 {
+            args.extend(["-o".into(), "SendEnv=GIT_PROTOCOL".into()]);
+            env.push(("GIT_PROTOCOL", format!("version={}", desired_version as usize)));
+        }
+        if let Some(ip_version) = ip_version {
+            let flag = match ip_version {
+                IpVersion::V4 => "-4",
+                IpVersion::V6 => "-6",
+            };
+            if *self == ProgramKind::Simple {
+                return Err(Error::UnsupportedOption {
+                    kind: *self,
+                    option: flag,
+                });
+            }
+            args.push(flag.into());
+        }
+        if *self == ProgramKind::TortoisePlink {
+            args.push("-batch".into());
+        }
+        if let Some(port) = port {
+            args.push(
+                match self {
+                    ProgramKind::Ssh => "-p",
+                    ProgramKind::Plink | ProgramKind::Putty | ProgramKind::TortoisePlink => "-P",
+                    ProgramKind::Simple => {
+                        return Err(Error::UnsupportedOption {
+                            kind: *self,
+                            option: "setting the port",
+                        })
+                    }
+                }
+                .into(),
+            );
+            args.push(port.to_string());
+        }
+        Ok((args, env))
+    }
+}
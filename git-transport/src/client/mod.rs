@@ -1,5 +1,7 @@
 #[cfg(feature = "async-client")]
 mod async_io;
+#[cfg(feature = "async-http-client")]
+pub use async_io::http;
 #[cfg(feature = "async-client")]
 pub use async_io::{
     connect, ExtendedBufRead, HandleProgress, RequestWriter, SetServiceResponse, Transport, TransportV2Ext,
@@ -31,6 +31,27 @@ pub enum MessageKind {
 
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
 pub(crate) mod connect {
+    /// Options for connecting to a remote with [`connect()`][crate::connect()].
+    #[derive(Default, Debug, Clone)]
+    pub struct Options {
+        /// The protocol version to use when connecting, but note that the server may downgrade it.
+        pub version: crate::Protocol,
+        /// Options to use if the scheme of the url is `ssh`.
+        #[cfg(feature = "blocking-client")]
+        pub ssh: crate::client::ssh::connect::Options,
+    }
+
+    impl From<crate::Protocol> for Options {
+        /// Use `version` when connecting, and defaults for everything else.
+        fn from(version: crate::Protocol) -> Self {
+            Options {
+                version,
+                #[cfg(feature = "blocking-client")]
+                ssh: Default::default(),
+            }
+        }
+    }
+
     /// The error used in [`connect()`][crate::connect()].
     #[derive(Debug, thiserror::Error)]
     #[allow(missing_docs)]
@@ -48,24 +69,175 @@ pub(crate) mod connect {
         },
         #[error("The '{0}' protocol is currently unsupported")]
         UnsupportedScheme(git_url::Scheme),
-        #[cfg(not(any(feature = "http-client-curl", feature = "http-client-reqwest")))]
-        #[error(
-            "'{0}' is not compiled in. Compile with the 'http-client-curl' or 'http-client-reqwest' cargo feature"
-        )]
+        #[cfg(not(any(
+            feature = "http-client-curl",
+            feature = "http-client-reqwest",
+            feature = "async-http-client-reqwest"
+        )))]
+        #[error("'{0}' is not compiled in. Compile with the 'http-client-curl', 'http-client-reqwest' or 'async-http-client-reqwest' cargo feature")]
         CompiledWithoutHttp(git_url::Scheme),
     }
 }
 
+#[cfg(any(feature = "http-client", feature = "async-http-client"))]
+pub(crate) mod http {
+    use std::{
+        any::Any,
+        sync::{Arc, Mutex},
+    };
+
+    ///
+    pub mod options {
+        /// Possible settings for the `http.followRedirects` configuration option.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum FollowRedirects {
+            /// Follow only the first redirect request, most suitable for typical git requests.
+            Initial,
+            /// Follow all redirect requests from the server unconditionally
+            All,
+            /// Follow no redirect request.
+            None,
+        }
+
+        impl Default for FollowRedirects {
+            fn default() -> Self {
+                FollowRedirects::Initial
+            }
+        }
+
+        /// The way to configure a proxy for authentication if a username is present in the configured proxy.
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub enum ProxyAuthMethod {
+            /// Automatically pick a suitable authentication method.
+            AnyAuth,
+            ///HTTP basic authentication.
+            Basic,
+            /// Http digest authentication to prevent a password to be passed in clear text.
+            Digest,
+            /// GSS negotiate authentication.
+            Negotiate,
+            /// NTLM authentication
+            Ntlm,
+        }
+
+        impl Default for ProxyAuthMethod {
+            fn default() -> Self {
+                ProxyAuthMethod::AnyAuth
+            }
+        }
+    }
+
+    /// A function to authenticate a URL.
+    pub type AuthenticateFn =
+        dyn FnMut(git_credentials::helper::Action) -> git_credentials::protocol::Result + Send + Sync;
+
+    /// Options to configure curl requests.
+    // TODO: testing most of these fields requires a lot of effort, unless special flags to introspect ongoing requests are added.
+    #[derive(Default, Clone)]
+    pub struct Options {
+        /// Headers to be added to every request.
+        /// They are applied unconditionally and are expected to be valid as they occour in an HTTP request, like `header: value`, without newlines.
+        ///
+        /// Refers to `http.extraHeader` multi-var.
+        pub extra_headers: Vec<String>,
+        /// How to handle redirects.
+        ///
+        /// Refers to `http.followRedirects`.
+        pub follow_redirects: options::FollowRedirects,
+        /// Used in conjunction with `low_speed_time_seconds`, any non-0 value signals the amount of bytes per second at least to avoid
+        /// aborting the connection.
+        ///
+        /// Refers to `http.lowSpeedLimit`.
+        pub low_speed_limit_bytes_per_second: u32,
+        /// Used in conjunction with `low_speed_bytes_per_second`, any non-0 value signals the amount seconds the minimal amount
+        /// of bytes per second isn't reached.
+        ///
+        /// Refers to `http.lowSpeedTime`.
+        pub low_speed_time_seconds: u64,
+        /// A curl-style proxy declaration of the form `[protocol://][user[:password]@]proxyhost[:port]`.
+        ///
+        /// Refers to `http.proxy`.
+        pub proxy: Option<String>,
+        /// The way to authenticate against the proxy if the `proxy` field contains a username.
+        ///
+        /// Refers to `http.proxyAuthMethod`.
+        pub proxy_auth_method: options::ProxyAuthMethod,
+        /// If authentication is needed for the proxy as its URL contains a username, this method must be set to provide a password
+        /// for it before making the request, and to store it if the connection succeeds.
+        pub proxy_authenticate: Option<(git_credentials::helper::Action, Arc<std::sync::Mutex<AuthenticateFn>>)>,
+        /// The `HTTP` `USER_AGENT` string presented to an `HTTP` server, notably not the user agent present to the `git` server.
+        ///
+        /// If not overridden, it defaults to the user agent provided by `curl`, which is a deviation from how `git` handles this.
+        /// Thus it's expected from the callers to set it to their application, or use higher-level crates which make it easy to do this
+        /// more correctly.
+        ///
+        /// Using the correct user-agent might affect how the server treats the request.
+        ///
+        /// Refers to `http.userAgent`.
+        pub user_agent: Option<String>,
+        /// The amount of time we wait until aborting a connection attempt.
+        ///
+        /// If `None`, this typically defaults to 2 minutes to 5 minutes.
+        /// Refers to `gitoxide.http.connectTimeout`.
+        pub connect_timeout: Option<std::time::Duration>,
+        /// Backend specific options, if available.
+        pub backend: Option<Arc<Mutex<dyn Any + Send + Sync + 'static>>>,
+    }
+
+    /// The error used by the [`Http`][crate::client::http::Http] trait.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could initialize the http client")]
+        InitHttpClient {
+            source: Box<dyn std::error::Error + Send + Sync + 'static>,
+        },
+        #[error("{description}")]
+        Detail { description: String },
+        #[error("An IO error occurred while uploading the body of a POST request")]
+        PostBody(#[from] std::io::Error),
+    }
+
+    /// The return value of [`Http::get()`][crate::client::http::Http::get()].
+    pub struct GetResponse<H, B> {
+        /// The response headers.
+        pub headers: H,
+        /// The response body.
+        pub body: B,
+    }
+
+    /// The return value of [`Http::post()`][crate::client::http::Http::post()].
+    pub struct PostResponse<H, B, PB> {
+        /// The body to post to the server as part of the request.
+        ///
+        /// **Note**: Implementations should drop the handle to avoid deadlocks.
+        pub post_body: PB,
+        /// The headers of the post response.
+        pub headers: H,
+        /// The body of the post response.
+        pub body: B,
+    }
+
+    impl<A, B, C> From<PostResponse<A, B, C>> for GetResponse<A, B> {
+        fn from(v: PostResponse<A, B, C>) -> Self {
+            GetResponse {
+                headers: v.headers,
+                body: v.body,
+            }
+        }
+    }
+}
+
 mod error {
     use bstr::BString;
 
     use crate::client::capabilities;
-    #[cfg(feature = "http-client")]
+    #[cfg(any(feature = "http-client", feature = "async-http-client"))]
     use crate::client::http;
 
-    #[cfg(feature = "http-client")]
+    #[cfg(any(feature = "http-client", feature = "async-http-client"))]
     type HttpError = http::Error;
-    #[cfg(not(feature = "http-client"))]
+    #[cfg(not(any(feature = "http-client", feature = "async-http-client")))]
     type HttpError = std::convert::Infallible;
 
     /// The error used in most methods of the [`client`][crate::client] module
@@ -59,12 +59,24 @@ impl Service {
 ///
 pub mod client;
 
+///
+#[cfg(feature = "blocking-server")]
+pub mod server;
+
 #[doc(inline)]
 #[cfg(any(
     feature = "blocking-client",
-    all(feature = "async-client", any(feature = "async-std"))
+    all(
+        feature = "async-client",
+        any(feature = "async-std", feature = "async-http-client-reqwest")
+    )
 ))]
 pub use client::connect;
 
 #[cfg(all(feature = "async-client", feature = "blocking-client"))]
 compile_error!("Cannot set both 'blocking-client' and 'async-client' features as they are mutually exclusive");
+
+#[cfg(all(feature = "async-client", feature = "blocking-server"))]
+compile_error!(
+    "Cannot set both 'blocking-server' and 'async-client' features as they require different modes of 'git-packetline'"
+);
@@ -0,0 +1,224 @@
+use std::io;
+
+use bstr::{BStr, BString, ByteSlice};
+
+use crate::{Protocol, Service};
+
+/// The error returned by [`accept()`] and [`Request::from_daemon_line()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("An IO error occurred when reading the request")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    PacketlineDecode(#[from] git_packetline::decode::Error),
+    #[error("The client closed the connection or sent a special packet line instead of a request")]
+    MissingRequest,
+    #[error("The request line {line:?} is malformed")]
+    MalformedRequest { line: BString },
+    #[error("The requested service {name:?} is unknown")]
+    UnknownService { name: BString },
+}
+
+/// A request of a client to a server, naming the service to invoke and the repository to invoke it on.
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(feature = "serde1", derive(serde::Serialize, serde::Deserialize))]
+pub struct Request {
+    /// The service the client would like to use.
+    pub service: Service,
+    /// The path to the repository, as sent by the client and without any interpretation.
+    pub path: BString,
+    /// The host and port the client connected to, if provided.
+    pub virtual_host: Option<(String, Option<u16>)>,
+    /// The protocol version the client would like to speak, which is [`Protocol::V1`] unless the client asked for more.
+    pub desired_version: Protocol,
+    /// Additional parameters in the form of `(name, optional value)` that aren't interpreted by us, excluding the `version`.
+    pub extra_parameters: Vec<(BString, Option<BString>)>,
+}
+
+impl Request {
+    /// Parse the first line sent by a client to a `git` daemon, like `git-upload-pack /project.git\0host=example.com\0\0version=2\0`,
+    /// with the trailing newline removed.
+    pub fn from_daemon_line(line: &[u8]) -> Result<Self, Error> {
+        let malformed = || Error::MalformedRequest { line: line.into() };
+        let mut fields = line.split_str(b"\0");
+        let (service, path) = fields
+            .next()
+            .and_then(|f| f.split_once_str(b" "))
+            .ok_or_else(malformed)?;
+        if path.is_empty() {
+            return Err(malformed());
+        }
+        let service = parse_service(service.as_bstr())?;
+
+        let mut virtual_host = None;
+        let mut parameters = Vec::new();
+        let mut in_extra_parameters = false;
+        for field in fields {
+            if field.is_empty() {
+                in_extra_parameters = true;
+                continue;
+            }
+            match field.strip_prefix(b"host=") {
+                Some(host) if !in_extra_parameters => {
+                    let host = host.to_str().map_err(|_| malformed())?;
+                    virtual_host = Some(match host.rsplit_once(':') {
+                        Some((host, port)) => (host.to_owned(), Some(port.parse().map_err(|_| malformed())?)),
+                        None => (host.to_owned(), None),
+                    });
+                }
+                _ => parameters.push(field),
+            }
+        }
+        Ok(Self::from_parameters(service, path.into(), virtual_host, parameters))
+    }
+
+    /// Create a new instance from the parts that a frontend like an SSH or HTTP server provides on its own, using
+    /// the `service` it was asked to start and the repository `path`.
+    ///
+    /// `git_protocol` is the value of the `GIT_PROTOCOL` environment variable or of the `Git-Protocol` HTTP header,
+    /// a colon separated list of parameters like `version=2`, if the client provided it.
+    pub fn from_parts(service: Service, path: BString, git_protocol: Option<&BStr>) -> Self {
+        Self::from_parameters(
+            service,
+            path,
+            None,
+            git_protocol
+                .map(|value| value.split_str(b":").filter(|p| !p.is_empty()).collect())
+                .unwrap_or_default(),
+        )
+    }
+
+    fn from_parameters(
+        service: Service,
+        path: BString,
+        virtual_host: Option<(String, Option<u16>)>,
+        parameters: Vec<&[u8]>,
+    ) -> Self {
+        let mut desired_version = Protocol::V1;
+        let mut extra_parameters = Vec::new();
+        for parameter in parameters {
+            let (name, value) = match parameter.split_once_str(b"=") {
+                Some((name, value)) => (name, Some(value)),
+                None => (parameter, None),
+            };
+            match (name, value) {
+                // Like `git`, we use the highest version asked for and ignore those we don't know.
+                (b"version", Some(b"2")) => desired_version = Protocol::V2,
+                (b"version", _) => {}
+                (name, value) => extra_parameters.push((name.into(), value.map(Into::into))),
+            }
+        }
+        Request {
+            service,
+            path,
+            virtual_host,
+            desired_version,
+            extra_parameters,
+        }
+    }
+}
+
+fn parse_service(name: &BStr) -> Result<Service, Error> {
+    Ok(match name.as_bytes() {
+        b"git-upload-pack" => Service::UploadPack,
+        b"git-receive-pack" => Service::ReceivePack,
+        _ => return Err(Error::UnknownService { name: name.into() }),
+    })
+}
+
+/// Read the first packet line sent by a client connected to a `git` daemon from `read` and parse it into a [`Request`].
+///
+/// Exactly one packet line is consumed, leaving `read` positioned at the beginning of the conversation with the service.
+pub fn accept(read: impl io::Read) -> Result<Request, Error> {
+    let mut lines = git_packetline::StreamingPeekableIter::new(read, &[]);
+    let line = lines.read_line().ok_or(Error::MissingRequest)???;
+    let line = line.as_slice().ok_or(Error::MissingRequest)?;
+    Request::from_daemon_line(line.strip_suffix(b"\n").unwrap_or(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use bstr::BString;
+
+    use super::{accept, Error, Request};
+    use crate::{Protocol, Service};
+
+    fn line(data: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        git_packetline::encode::data_to_write(data.as_bytes(), &mut out).expect("in-memory write");
+        out
+    }
+
+    #[test]
+    fn version_1_without_host() -> Result<(), Error> {
+        assert_eq!(
+            accept(line("git-upload-pack hello/world\0").as_slice())?,
+            Request {
+                service: Service::UploadPack,
+                path: "hello/world".into(),
+                virtual_host: None,
+                desired_version: Protocol::V1,
+                extra_parameters: vec![],
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn version_2_with_host_port_and_extra_parameters() -> Result<(), Error> {
+        assert_eq!(
+            accept(
+                line("git-receive-pack /path/project.git\0host=example.com:9418\0\0version=2\0key=value\0value-only\0")
+                    .as_slice()
+            )?,
+            Request {
+                service: Service::ReceivePack,
+                path: "/path/project.git".into(),
+                virtual_host: Some(("example.com".into(), Some(9418))),
+                desired_version: Protocol::V2,
+                extra_parameters: vec![
+                    ("key".into(), Some("value".into())),
+                    ("value-only".into(), None::<BString>)
+                ],
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn trailing_newlines_are_ignored() -> Result<(), Error> {
+        let request = accept(line("git-upload-pack /p\0host=h\0\n").as_slice())?;
+        assert_eq!(request.virtual_host, Some(("h".into(), None)));
+        Ok(())
+    }
+
+    #[test]
+    fn from_parts_uses_the_git_protocol_value() {
+        let request = Request::from_parts(Service::UploadPack, "/p".into(), Some("version=1:version=2:x=y".into()));
+        assert_eq!(request.desired_version, Protocol::V2);
+        assert_eq!(request.extra_parameters, vec![("x".into(), Some("y".into()))]);
+        assert_eq!(
+            Request::from_parts(Service::UploadPack, "/p".into(), None).desired_version,
+            Protocol::V1
+        );
+    }
+
+    #[test]
+    fn invalid_requests() {
+        assert!(matches!(accept(&b"0000"[..]), Err(Error::MissingRequest)));
+        assert!(matches!(accept(&b""[..]), Err(Error::Io(_))));
+        assert!(matches!(
+            accept(line("git-frobnicate /p\0").as_slice()),
+            Err(Error::UnknownService { .. })
+        ));
+        assert!(matches!(
+            accept(line("git-upload-pack\0").as_slice()),
+            Err(Error::MalformedRequest { .. })
+        ));
+        assert!(matches!(
+            accept(line("git-upload-pack /p\0host=h:port\0").as_slice()),
+            Err(Error::MalformedRequest { .. })
+        ));
+    }
+}
@@ -1,2 +1,4 @@
 #[cfg(any(feature = "http-client-curl", feature = "http-client-reqwest"))]
 mod http;
+#[cfg(feature = "ssh-client-libssh2")]
+mod ssh;
@@ -0,0 +1,285 @@
+use std::{
+    io::BufRead,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+};
+
+use git_transport::{
+    client::{self, ssh, Transport},
+    Protocol, Service,
+};
+
+/// A `sshd` serving on localhost from a temporary directory, with its own host key and a single authorized client key.
+struct Sshd {
+    dir: tempfile::TempDir,
+    port: u16,
+    child: Child,
+}
+
+impl Sshd {
+    /// Start the server, or return `None` if `sshd` isn't installed and we aren't on CI.
+    fn start() -> crate::Result<Option<Self>> {
+        let program = match find_program("sshd")? {
+            Some(program) => program,
+            None => return Ok(None),
+        };
+        let dir = tempfile::tempdir()?;
+        for key in ["host_key", "client_key"] {
+            run(Command::new("ssh-keygen")
+                .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+                .arg(dir.path().join(key)))?;
+        }
+        std::fs::copy(dir.path().join("client_key.pub"), dir.path().join("authorized_keys"))?;
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let config = dir.path().join("sshd_config");
+        std::fs::write(
+            &config,
+            format!(
+                "Port {port}\nListenAddress 127.0.0.1\nHostKey {host_key}\nAuthorizedKeysFile {authorized_keys}\n\
+                 PidFile none\nStrictModes no\nUsePAM no\nPasswordAuthentication no\nAcceptEnv GIT_PROTOCOL\n",
+                port = port,
+                host_key = dir.path().join("host_key").display(),
+                authorized_keys = dir.path().join("authorized_keys").display()
+            ),
+        )?;
+        let child = Command::new(program)
+            .arg("-D")
+            .arg("-f")
+            .arg(&config)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let sshd = Sshd { dir, port, child };
+        for _ in 0..100 {
+            if std::net::TcpStream::connect(("127.0.0.1", port)).is_ok() {
+                return Ok(Some(sshd));
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        Err("sshd didn't start listening in time".into())
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.path().join(name)
+    }
+
+    /// Write a `known_hosts` file containing our host key and return its path.
+    fn known_hosts(&self) -> crate::Result<PathBuf> {
+        let host_key = std::fs::read_to_string(self.path("host_key.pub"))?;
+        let path = self.path("known_hosts");
+        std::fs::write(&path, format!("[127.0.0.1]:{} {}", self.port, host_key))?;
+        Ok(path)
+    }
+
+    /// Create a repository with a single commit and return its path.
+    fn repository(&self) -> crate::Result<PathBuf> {
+        let path = self.path("repo");
+        run(Command::new("git").arg("init").arg("-q").arg(&path))?;
+        run(Command::new("git")
+            .arg("-C")
+            .arg(&path)
+            .args(["commit", "-q", "--allow-empty", "-m", "first"])
+            .env("GIT_AUTHOR_NAME", "author")
+            .env("GIT_AUTHOR_EMAIL", "author@example.com")
+            .env("GIT_COMMITTER_NAME", "committer")
+            .env("GIT_COMMITTER_EMAIL", "committer@example.com"))?;
+        Ok(path)
+    }
+}
+
+impl Drop for Sshd {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// An `ssh-agent` listening on a socket in the directory of an [`Sshd`], holding its client key.
+struct Agent {
+    socket: PathBuf,
+    child: Child,
+}
+
+impl Agent {
+    /// Start the agent and add the client key of `sshd`, or return `None` if `ssh-agent` isn't installed and we aren't on CI.
+    fn start(sshd: &Sshd) -> crate::Result<Option<Self>> {
+        let (program, ssh_add) = match (find_program("ssh-agent")?, find_program("ssh-add")?) {
+            (Some(program), Some(ssh_add)) => (program, ssh_add),
+            _ => return Ok(None),
+        };
+        let socket = sshd.path("agent.sock");
+        let child = Command::new(program)
+            .arg("-D")
+            .arg("-a")
+            .arg(&socket)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let agent = Agent { socket, child };
+        for _ in 0..100 {
+            if agent.socket.exists() {
+                run(Command::new(ssh_add)
+                    .arg("-q")
+                    .arg(sshd.path("client_key"))
+                    .env("SSH_AUTH_SOCK", &agent.socket))?;
+                return Ok(Some(agent));
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        Err("ssh-agent didn't create its socket in time".into())
+    }
+}
+
+impl Drop for Agent {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// Find `name` in `PATH` or the usual `sbin` directories, or return `None` to skip the test. On CI, where all programs
+/// are expected to be installed, a missing program is an error instead.
+fn find_program(name: &str) -> crate::Result<Option<PathBuf>> {
+    let program = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .chain(["/usr/sbin", "/usr/local/sbin"].iter().map(PathBuf::from))
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file());
+    if program.is_none() && git_testtools::is_ci::cached() {
+        return Err(format!("`{}` must be installed on CI", name).into());
+    }
+    Ok(program)
+}
+
+fn run(cmd: &mut Command) -> crate::Result {
+    let status = cmd.status()?;
+    if !status.success() {
+        return Err(format!("{:?} failed with {}", cmd, status).into());
+    }
+    Ok(())
+}
+
+fn current_user() -> crate::Result<String> {
+    let output = Command::new("id").arg("-un").output()?;
+    Ok(String::from_utf8(output.stdout)?.trim().to_owned())
+}
+
+fn connect_builtin(
+    sshd: &Sshd,
+    repo: &Path,
+    known_hosts: PathBuf,
+) -> Result<ssh::libssh2::Transport, ssh::libssh2::Error> {
+    connect_builtin_with_identity(sshd, repo, known_hosts, sshd.path("client_key"))
+}
+
+fn connect_builtin_with_identity(
+    sshd: &Sshd,
+    repo: &Path,
+    known_hosts: PathBuf,
+    identity_file: PathBuf,
+) -> Result<ssh::libssh2::Transport, ssh::libssh2::Error> {
+    ssh::libssh2::connect(
+        "127.0.0.1",
+        repo.to_str().expect("valid UTF-8").into(),
+        Protocol::V1,
+        Some(&current_user().expect("user is known")),
+        Some(sshd.port),
+        ssh::libssh2::Options {
+            known_hosts_files: vec![known_hosts],
+            identity_files: vec![identity_file],
+        },
+    )
+}
+
+#[test]
+fn configured_command_takes_precedence_over_the_builtin_client() {
+    let err = client::connect(
+        "ssh://host.example:2222/repo",
+        client::connect::Options {
+            version: Protocol::V1,
+            ssh: ssh::connect::Options {
+                command: Some("simple-ssh-program".into()),
+                kind: Some(ssh::ProgramKind::Simple),
+                ..Default::default()
+            },
+        },
+    )
+    .err()
+    .expect("the configured program is used, which can't be told the port");
+    match err {
+        client::connect::Error::Connection(err) => assert!(
+            matches!(
+                err.downcast_ref::<ssh::Error>(),
+                Some(ssh::Error::UnsupportedOption {
+                    kind: ssh::ProgramKind::Simple,
+                    ..
+                })
+            ),
+            "it tried to run the configured program"
+        ),
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn builtin_client_verifies_the_host_authenticates_with_a_key_file_and_performs_a_handshake() -> crate::Result {
+    let sshd = match Sshd::start()? {
+        Some(sshd) => sshd,
+        None => return Ok(()),
+    };
+    let repo = sshd.repository()?;
+    let mut transport = connect_builtin(&sshd, &repo, sshd.known_hosts()?)?;
+    let mut res = transport.handshake(Service::UploadPack, &[])?;
+    assert_eq!(res.actual_protocol, Protocol::V1);
+
+    let refs = res
+        .refs
+        .as_mut()
+        .expect("v1 protocol provides refs")
+        .lines()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(refs.len(), 2, "HEAD and the branch it points to");
+    assert!(refs[0].ends_with(" HEAD"));
+    assert!(refs[1].contains(" refs/heads/"));
+    Ok(())
+}
+
+#[test]
+fn builtin_client_rejects_unknown_host_keys() -> crate::Result {
+    let sshd = match Sshd::start()? {
+        Some(sshd) => sshd,
+        None => return Ok(()),
+    };
+    let repo = sshd.repository()?;
+    let empty_known_hosts = sshd.path("empty_known_hosts");
+    std::fs::write(&empty_known_hosts, "")?;
+    assert!(matches!(
+        connect_builtin(&sshd, &repo, empty_known_hosts),
+        Err(ssh::libssh2::Error::UnknownHostKey { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn builtin_client_authenticates_with_the_ssh_agent() -> crate::Result {
+    let sshd = match Sshd::start()? {
+        Some(sshd) => sshd,
+        None => return Ok(()),
+    };
+    let agent = match Agent::start(&sshd)? {
+        Some(agent) => agent,
+        None => return Ok(()),
+    };
+    // libssh2 only finds the agent through the environment. Other tests in this process may now try this agent as well,
+    // which doesn't hold the keys of their servers, so they fall back to their identity files as before.
+    std::env::set_var("SSH_AUTH_SOCK", &agent.socket);
+    let repo = sshd.repository()?;
+    let mut transport =
+        connect_builtin_with_identity(&sshd, &repo, sshd.known_hosts()?, sshd.path("missing_identity_file"))?;
+    let res = transport.handshake(Service::UploadPack, &[])?;
+    assert_eq!(res.actual_protocol, Protocol::V1);
+    Ok(())
+}
@@ -5,6 +5,8 @@ pub struct Ancestors<Find, Predicate, StateMut> {
     state: StateMut,
     parents: Parents,
     sorting: Sorting,
+    /// Commits whose parents are not traversed, sorted for binary search.
+    shallow_commits: Vec<git_hash::ObjectId>,
 }
 
 /// Specify how to handle commit parents during traversal.
@@ -76,6 +78,8 @@ pub mod ancestors {
         },
         #[error(transparent)]
         ObjectDecode(#[from] git_object::decode::Error),
+        #[error("The shallow commits to treat as having no parents could not be obtained")]
+        ShallowCommits(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
     }
 
     type TimeInSeconds = u32;
@@ -104,6 +108,14 @@ pub mod ancestors {
             self.parents = mode;
             self
         }
+
+        /// Treat the given `commits` as if they had no parents, which is how the boundary commits of shallow repositories
+        /// are handled as their parents aren't present in the object database.
+        pub fn shallow_commits(mut self, commits: impl IntoIterator<Item = ObjectId>) -> Self {
+            self.shallow_commits = commits.into_iter().collect();
+            self.shallow_commits.sort();
+            self
+        }
     }
 
     /// Builder
@@ -210,6 +222,7 @@ pub mod ancestors {
                 state,
                 parents: Default::default(),
                 sorting: Default::default(),
+                shallow_commits: Vec::new(),
             }
         }
     }
@@ -272,7 +285,9 @@ pub mod ancestors {
             let state = self.state.borrow_mut();
 
             let (oid, _commit_time) = state.next.pop_front()?;
+            let is_shallow = self.shallow_commits.binary_search(&oid).is_ok();
             match (self.find)(&oid, &mut state.buf) {
+                Ok(_) if is_shallow => {}
                 Ok(commit_iter) => {
                     let mut count = 0;
                     for token in commit_iter {
@@ -344,7 +359,9 @@ pub mod ancestors {
         fn next_by_topology(&mut self) -> Option<Result<ObjectId, Error>> {
             let state = self.state.borrow_mut();
             let (oid, _commit_time) = state.next.pop_front()?;
+            let is_shallow = self.shallow_commits.binary_search(&oid).is_ok();
             match (self.find)(&oid, &mut state.buf) {
+                Ok(_) if is_shallow => {}
                 Ok(commit_iter) => {
                     for token in commit_iter {
                         match token {
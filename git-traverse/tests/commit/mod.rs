@@ -11,6 +11,7 @@ mod ancestor {
         expected: &'a [&'a str],
         mode: commit::Parents,
         sorting: commit::Sorting,
+        shallow_commits: &'a [&'a str],
     }
 
     impl<'a> TraversalAssertion<'a> {
@@ -21,6 +22,7 @@ mod ancestor {
                 expected,
                 mode: Default::default(),
                 sorting: Default::default(),
+                shallow_commits: &[],
             }
         }
 
@@ -33,6 +35,11 @@ mod ancestor {
             self.sorting = sorting;
             self
         }
+
+        fn with_shallow_commits(&mut self, shallow_commits: &'a [&'a str]) -> &mut Self {
+            self.shallow_commits = shallow_commits;
+            self
+        }
     }
 
     impl TraversalAssertion<'_> {
@@ -58,6 +65,7 @@ mod ancestor {
             )
             .sorting(self.sorting)?
             .parents(self.mode)
+            .shallow_commits(self.shallow_commits.iter().copied().map(hex_to_id))
             .collect();
 
             assert_eq!(oids?, expected);
@@ -72,6 +80,7 @@ mod ancestor {
                 })
                 .sorting(self.sorting)?
                 .parents(self.mode)
+                .shallow_commits(self.shallow_commits.iter().copied().map(hex_to_id))
                 .collect();
             assert_eq!(oids?, expected);
             Ok(())
@@ -127,6 +136,22 @@ mod ancestor {
         .check()
     }
 
+    #[test]
+    fn simple_branch_with_merge_and_shallow_commit() -> crate::Result {
+        TraversalAssertion::new(
+            "make_traversal_repo_for_commits.sh",
+            &["01ec18a3ebf2855708ad3c9d244306bc1fae3e9b"],
+            &[
+                "efd9a841189668f1bab5b8ebade9cd0a1b139a37",
+                "ce2e8ffaa9608a26f7b21afc1db89cadb54fd353",
+                "9556057aee5abb06912922e9f26c46386a816822",
+                "9152eeee2328073cf23dcf8e90c949170b711659",
+            ],
+        )
+        .with_shallow_commits(&["9556057aee5abb06912922e9f26c46386a816822"])
+        .check()
+    }
+
     #[test]
     fn multiple_tips() -> crate::Result {
         TraversalAssertion::new(
@@ -222,6 +247,24 @@ mod ancestor {
         .check()
     }
 
+    #[test]
+    fn committer_date_sorted_commits_with_shallow_commits() -> crate::Result {
+        TraversalAssertion::new(
+            "make_traversal_repo_for_commits_with_dates.sh",
+            &["288e509293165cb5630d08f4185bdf2445bf6170"],
+            &[
+                "bcb05040a6925f2ff5e10d3ae1f9264f2e8c43ac",
+                "9902e3c3e8f0c569b4ab295ddf473e6de763e1e7",
+            ],
+        )
+        .with_sorting(commit::Sorting::ByCommitTimeNewestFirst)
+        .with_shallow_commits(&[
+            "bcb05040a6925f2ff5e10d3ae1f9264f2e8c43ac",
+            "9902e3c3e8f0c569b4ab295ddf473e6de763e1e7",
+        ])
+        .check()
+    }
+
     #[test]
     fn committer_date_sorted_commits_with_cutoff() -> crate::Result {
         TraversalAssertion::new(
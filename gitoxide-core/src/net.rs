@@ -32,6 +32,12 @@ mod impls {
             }
         }
     }
+
+    impl From<Protocol> for transport::client::connect::Options {
+        fn from(v: Protocol) -> Self {
+            transport::Protocol::from(v).into()
+        }
+    }
 }
 
 impl Default for Protocol {
@@ -92,6 +92,11 @@ pub struct Context<W> {
     /// This is a total, shared among all threads if `thread_limit` permits.
     /// Only used when known to be effective, namely when `expansion == ObjectExpansion::TreeDiff`.
     pub object_cache_size_in_bytes: usize,
+    /// If `Some(window)`, objects which aren't already in a pack are delta-compressed against up to `window` similar objects,
+    /// respecting the `delta` attribute in `.gitattributes` files. Otherwise they are stored as base objects.
+    pub delta_window: Option<usize>,
+    /// The maximum length of delta chains when `delta_window` is set.
+    pub delta_depth: usize,
     /// The output stream for use of additional information
     pub out: W,
 }
@@ -110,6 +115,8 @@ pub fn create<W, P>(
         statistics,
         pack_cache_size_in_bytes,
         object_cache_size_in_bytes,
+        delta_window,
+        delta_depth,
         mut out,
     }: Context<W>,
 ) -> anyhow::Result<()>
@@ -119,6 +126,9 @@ where
     P::SubProgress: 'static,
 {
     let repo = git::discover(repository_path)?.into_sync();
+    let attributes = delta_window
+        .map(|_| load_attributes(&repo.to_thread_local()))
+        .transpose()?;
     progress.init(Some(2), progress::steps());
     let tips = tips.into_iter();
     let make_cancellation_err = || anyhow!("Cancelled by user");
@@ -219,6 +229,7 @@ where
                     thread_limit,
                     chunk_size,
                     input_object_expansion,
+                    attributes,
                 },
             )?
         } else {
@@ -228,6 +239,7 @@ where
                 progress,
                 &interrupt::IS_INTERRUPTED,
                 input_object_expansion,
+                attributes.as_ref(),
             )?
         };
         stats.counts = count_stats;
@@ -245,7 +257,13 @@ where
             progress,
             pack::data::output::entry::iter_from_counts::Options {
                 thread_limit,
-                mode: pack::data::output::entry::iter_from_counts::Mode::PackCopyAndBaseObjects,
+                mode: match delta_window {
+                    Some(window) => pack::data::output::entry::iter_from_counts::Mode::PackCopyAndDeltaCompression {
+                        window,
+                        depth: delta_depth,
+                    },
+                    None => pack::data::output::entry::iter_from_counts::Mode::PackCopyAndBaseObjects,
+                },
                 allow_thin_pack: thin,
                 chunk_size,
                 version: Default::default(),
@@ -290,13 +308,28 @@ where
         write_progress.inc_by(written as usize);
     }
 
-    let hash = interruptible_output_iter
-        .into_inner()
-        .digest()
-        .expect("iteration is done");
+    let mut output_iter = interruptible_output_iter.into_inner();
+    let hash = output_iter.digest().expect("iteration is done");
     let pack_name = format!("{}.pack", hash);
-    if let (Some(pack_file), Some(dir)) = (named_tempfile_store.take(), output_directory) {
+    let index_file = match output_directory.as_ref() {
+        Some(dir) => {
+            let mut index_file = tempfile::NamedTempFile::new_in(dir.as_ref())?;
+            output_iter
+                .write_index(
+                    pack::index::Version::default(),
+                    &mut index_file,
+                    progress.add_child("writing index"),
+                )?
+                .expect("iteration is done");
+            Some(index_file)
+        }
+        None => None,
+    };
+    drop(output_iter);
+    if let (Some(pack_file), Some(index_file), Some(dir)) = (named_tempfile_store.take(), index_file, output_directory)
+    {
         pack_file.persist(dir.as_ref().join(pack_name))?;
+        index_file.persist(dir.as_ref().join(format!("{}.idx", hash)))?;
     } else {
         writeln!(out, "{}", pack_name)?;
     }
@@ -312,6 +345,78 @@ where
     Ok(())
 }
 
+/// Load the attributes from the `.gitattributes` files of all directories in the index, read from the work tree or from the index if
+/// they aren't checked out, and from `info/attributes`.
+///
+/// Like in the attribute stack of `git`, files in deeper directories take precedence over those in their parent directories,
+/// and `info/attributes` takes precedence over all of them.
+fn load_attributes(repo: &git::Repository) -> anyhow::Result<git::attrs::MatchGroup> {
+    use git::bstr::{BString, ByteSlice, ByteVec};
+
+    let mut buf = Vec::new();
+    let mut group = git::attrs::MatchGroup::default();
+    if let Some(work_dir) = repo.work_dir() {
+        let index = match repo.index() {
+            Ok(index) => Some(index),
+            Err(git::worktree::open_index::Error::IndexFile(git::index::file::init::Error::Io(err)))
+                if err.kind() == io::ErrorKind::NotFound =>
+            {
+                None
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let mut dirs = std::collections::BTreeSet::new();
+        dirs.insert(BString::default());
+        for index in index.iter() {
+            for entry in index.entries() {
+                let mut path = entry.path(index);
+                while let Some(pos) = path.rfind_byte(b'/') {
+                    path = path[..pos].as_bstr();
+                    if !dirs.insert(path.to_owned()) {
+                        break;
+                    }
+                }
+            }
+        }
+        let mut dirs: Vec<_> = dirs.into_iter().collect();
+        dirs.sort_by_key(|dir| dir.find_iter(b"/").count());
+
+        for dir in dirs {
+            let mut relative_path = dir;
+            if !relative_path.is_empty() {
+                relative_path.push_byte(b'/');
+            }
+            relative_path.push_str(".gitattributes");
+            let entry = index.as_ref().and_then(|index| {
+                index
+                    .entry_by_path_and_stage(relative_path.as_bstr(), 0)
+                    .or_else(|| index.entry_by_path_and_stage(relative_path.as_bstr(), 2))
+                    .filter(|entry| entry.mode == git::index::entry::Mode::FILE)
+                    .map(|entry| entry.id)
+            });
+            let path = work_dir.join(git::path::from_bstr(relative_path.as_bstr()));
+            // Like `git`, don't follow symlinks for attribute files that are tracked.
+            match git::attrs::PatternList::from_file(&path, Some(work_dir), entry.is_none(), &mut buf)? {
+                Some(list) => group.patterns.push(list),
+                None => {
+                    if let Some(id) = entry {
+                        let blob = repo.find_object(id)?;
+                        group
+                            .patterns
+                            .push(git::attrs::PatternList::from_bytes(&blob.data, path, Some(work_dir)));
+                    }
+                }
+            }
+        }
+    }
+    if let Some(list) =
+        git::attrs::PatternList::from_file(repo.git_dir().join("info").join("attributes"), None, true, &mut buf)?
+    {
+        group.patterns.push(list);
+    }
+    Ok(group)
+}
+
 fn print(stats: Statistics, format: OutputFormat, out: impl std::io::Write) -> anyhow::Result<()> {
     match format {
         OutputFormat::Human => human_output(stats, out).map_err(Into::into),
@@ -328,6 +433,7 @@ fn human_output(
                 expanded_objects,
                 decoded_objects,
                 total_objects,
+                bitmap_objects,
             },
         entries:
             pack::data::output::entry::iter_from_counts::Outcome {
@@ -335,6 +441,7 @@ fn human_output(
                 missing_objects,
                 objects_copied_from_pack,
                 ref_delta_objects,
+                delta_compressed_objects,
             },
     }: Statistics,
     mut out: impl std::io::Write,
@@ -344,10 +451,11 @@ fn human_output(
     #[rustfmt::skip]
     writeln!(
         out,
-        "\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}",
+        "\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}",
         "input objects", input_objects,
         "expanded objects", expanded_objects,
         "decoded objects", decoded_objects,
+        "objects from bitmap", bitmap_objects,
         "total objects", total_objects,
         width = width
     )?;
@@ -355,10 +463,11 @@ fn human_output(
     #[rustfmt::skip]
     writeln!(
         out,
-        "\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}",
+        "\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}",
         "decoded and recompressed", decoded_and_recompressed_objects,
         "pack-to-pack copies", objects_copied_from_pack,
         "ref-delta-objects", ref_delta_objects,
+        "delta-compressed objects", delta_compressed_objects,
         "missing objects", missing_objects,
         width = width
     )?;
@@ -227,6 +227,7 @@ pub fn pack_or_pack_index(
                 thread_limit,
                 check: check.into(),
                 make_pack_lookup_cache:             pack::cache::lru::StaticLinkedList::<64>::default,
+                resolve_external_base: pack::index::traverse::no_external_bases,
             },
         )
         .with_context(|| "Failed to explode the entire pack - some loose objects may have been created nonetheless")?;
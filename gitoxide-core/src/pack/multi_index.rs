@@ -30,7 +30,10 @@ pub fn create(
         &mut out,
         progress,
         should_interrupt,
-        git::odb::pack::multi_index::write::Options { object_hash },
+        git::odb::pack::multi_index::write::Options {
+            object_hash,
+            base: None,
+        },
     )?;
     out.into_inner()?.commit()?;
     Ok(())
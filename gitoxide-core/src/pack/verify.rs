@@ -160,7 +160,7 @@ where
         }
         "" => {
             match path.file_name() {
-                Some(file_name) if file_name == "multi-pack-index" => {
+                Some(file_name) if file_name == "multi-pack-index" || file_name == git::odb::pack::multi_index::chain::FILE_NAME => {
                     let multi_index = git::odb::pack::multi_index::File::at(path)?;
                     let res = multi_index.verify_integrity(progress, should_interrupt, git::odb::pack::index::verify::integrity::Options{
                         verify_mode: mode,
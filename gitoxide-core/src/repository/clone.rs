@@ -4,6 +4,9 @@ pub struct Options {
     pub format: OutputFormat,
     pub bare: bool,
     pub handshake_info: bool,
+    pub shallow: git_repository::remote::fetch::Shallow,
+    /// If set, create a partial clone which only receives objects matching this filter specification.
+    pub filter: Option<String>,
 }
 
 pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=3;
@@ -29,6 +32,8 @@ pub(crate) mod function {
             format,
             handshake_info,
             bare,
+            shallow,
+            filter,
         }: Options,
     ) -> anyhow::Result<()>
     where
@@ -50,7 +55,11 @@ pub(crate) mod function {
                 opts.permissions.config.git_binary = true;
                 opts
             },
-        )?;
+        )?
+        .with_shallow(shallow);
+        if let Some(filter) = filter {
+            prepare = prepare.with_filter(filter);
+        }
         let (mut checkout, fetch_outcome) =
             prepare.fetch_then_checkout(&mut progress, &git::interrupt::IS_INTERRUPTED)?;
 
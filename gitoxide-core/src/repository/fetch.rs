@@ -10,6 +10,9 @@ pub struct Options {
     /// If non-empty, override all ref-specs otherwise configured in the remote
     pub ref_specs: Vec<BString>,
     pub handshake_info: bool,
+    pub shallow: git::remote::fetch::Shallow,
+    /// If set, only receive objects matching this filter specification.
+    pub filter: Option<String>,
 }
 
 pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=3;
@@ -33,6 +36,8 @@ pub(crate) mod function {
             remote,
             handshake_info,
             ref_specs,
+            shallow,
+            filter,
         }: Options,
     ) -> anyhow::Result<()>
     where
@@ -47,11 +52,15 @@ pub(crate) mod function {
         if !ref_specs.is_empty() {
             remote.replace_refspecs(ref_specs.iter(), git::remote::Direction::Fetch)?;
         }
-        let res: git::remote::fetch::Outcome = remote
+        let mut prepare = remote
             .connect(git::remote::Direction::Fetch, progress)?
             .prepare_fetch(Default::default())?
             .with_dry_run(dry_run)
-            .receive(&git::interrupt::IS_INTERRUPTED)?;
+            .with_shallow(shallow);
+        if let Some(filter) = filter {
+            prepare = prepare.with_filter(filter);
+        }
+        let res: git::remote::fetch::Outcome = prepare.receive(&git::interrupt::IS_INTERRUPTED)?;
 
         if handshake_info {
             writeln!(out, "Handshake Information")?;
@@ -60,3 +60,89 @@ pub fn entries(repo: git::Repository, format: OutputFormat, mut out: impl io::Wr
 
     Ok(())
 }
+
+pub mod repack {
+    use git_repository as git;
+
+    use crate::OutputFormat;
+
+    pub struct Options {
+        pub format: OutputFormat,
+        pub mode: git::repack::Mode,
+        pub write_bitmap: bool,
+        pub write_multi_pack_index: bool,
+        /// A date like `2.weeks.ago` or `now` before which unreachable loose objects are deleted.
+        pub prune: Option<String>,
+        pub thread_limit: Option<usize>,
+        pub window: Option<usize>,
+        pub depth: usize,
+    }
+
+    pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=3;
+}
+
+pub fn repack<P>(
+    repo: git::Repository,
+    progress: P,
+    mut out: impl io::Write,
+    repack::Options {
+        format,
+        mode,
+        write_bitmap,
+        write_multi_pack_index,
+        prune,
+        thread_limit,
+        window,
+        depth,
+    }: repack::Options,
+) -> anyhow::Result<()>
+where
+    P: git::Progress,
+    P::SubProgress: 'static,
+{
+    if format != OutputFormat::Human {
+        bail!("Only human output format is supported at the moment");
+    }
+    let now = std::time::SystemTime::now();
+    let prune_loose_older_than = prune
+        .map(|date| -> anyhow::Result<_> {
+            Ok(if date == "now" {
+                now
+            } else {
+                let time = git::date::parse(&date, Some(now))?;
+                std::time::UNIX_EPOCH + std::time::Duration::from_secs(time.seconds_since_unix_epoch.into())
+            })
+        })
+        .transpose()?;
+
+    let outcome = repo.repack(
+        progress,
+        &git::interrupt::IS_INTERRUPTED,
+        git::repack::Options {
+            mode,
+            delta_window: window,
+            delta_depth: depth,
+            write_bitmap,
+            write_multi_pack_index,
+            prune_loose_older_than,
+            thread_limit,
+        },
+    )?;
+    match outcome.pack {
+        Some(pack) => {
+            writeln!(out, "{} ({} objects)", pack.data_path.display(), pack.num_objects)?;
+            if let Some(bitmap_path) = pack.bitmap_path {
+                writeln!(out, "{}", bitmap_path.display())?;
+            }
+        }
+        None => writeln!(out, "Nothing to pack")?,
+    }
+    if let Some(multi_index_path) = outcome.multi_pack_index {
+        writeln!(out, "{}", multi_index_path.display())?;
+    }
+    for removed in outcome.removed_packs {
+        writeln!(out, "removed {}", removed.display())?;
+    }
+    writeln!(out, "removed {} loose objects", outcome.removed_loose_objects)?;
+    Ok(())
+}
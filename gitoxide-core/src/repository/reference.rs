@@ -0,0 +1,38 @@
+use std::io;
+
+use anyhow::bail;
+use git_repository as git;
+
+use crate::OutputFormat;
+
+pub mod pack {
+    use crate::OutputFormat;
+
+    pub struct Options {
+        pub format: OutputFormat,
+        /// Pack all loose references, not only tags and those which are already packed.
+        pub all: bool,
+        /// Delete the loose references after packing them.
+        pub prune: bool,
+    }
+}
+
+pub fn pack(
+    repo: git::Repository,
+    mut out: impl io::Write,
+    pack::Options { format, all, prune }: pack::Options,
+) -> anyhow::Result<()> {
+    if format != OutputFormat::Human {
+        bail!("Only human output format is supported at the moment");
+    }
+    let outcome = repo.pack_refs(git::refs::file::pack_refs::Options { all, prune })?;
+    writeln!(out, "packed {} references", outcome.packed)?;
+    if outcome.skipped_broken != 0 {
+        writeln!(
+            out,
+            "skipped {} references pointing to missing objects",
+            outcome.skipped_broken
+        )?;
+    }
+    Ok(())
+}
@@ -15,6 +15,8 @@ pub struct Context {
     pub thread_limit: Option<usize>,
     pub verify_mode: pack::verify::Mode,
     pub algorithm: pack::verify::Algorithm,
+    /// If set, check all objects for validity and connectivity like `git fsck` does.
+    pub fsck: bool,
 }
 
 pub const PROGRESS_RANGE: std::ops::RangeInclusive<u8> = 1..=3;
@@ -29,6 +31,7 @@ pub fn integrity(
         thread_limit,
         verify_mode,
         algorithm,
+        fsck,
     }: Context,
 ) -> anyhow::Result<()> {
     #[cfg_attr(not(feature = "serde1"), allow(unused))]
@@ -48,13 +51,28 @@ pub fn integrity(
         index.verify_entries()?;
         index.verify_extensions(true, {
             use git::odb::FindExt;
-            let objects = repo.objects;
+            let objects = repo.objects.clone();
             move |oid, buf: &mut Vec<u8>| objects.find_tree_iter(oid, buf).ok()
         })?;
         outcome
             .progress
             .info(format!("Index at '{}' OK", index.path().display()));
     }
+    if fsck {
+        let fsck = repo.fsck(
+            outcome.progress.add_child("fsck"),
+            should_interrupt,
+            repo.fsck_options()?,
+        )?;
+        write_fsck_report(&fsck, &mut out)?;
+        if fsck.has_errors() {
+            anyhow::bail!("Object database check found errors");
+        }
+        outcome.progress.info(format!(
+            "Checked {} objects for validity and connectivity",
+            fsck.num_objects
+        ));
+    }
     match output_statistics {
         Some(OutputFormat::Human) => writeln!(out, "Human output is currently unsupported, use JSON instead")?,
         #[cfg(feature = "serde1")]
@@ -71,3 +89,49 @@ pub fn integrity(
     }
     Ok(())
 }
+
+/// Write `outcome` to `out` in the format of `git fsck`.
+fn write_fsck_report(outcome: &git::fsck::Outcome, mut out: impl std::io::Write) -> std::io::Result<()> {
+    use git::fsck::{Corruption, Severity};
+    for corrupt in &outcome.corrupt {
+        match &corrupt.corruption {
+            Corruption::Unreadable { message } => writeln!(out, "error: {}: object corrupt: {}", corrupt.id, message)?,
+            Corruption::HashMismatch { actual } => {
+                writeln!(out, "error: hash mismatch for {} (actual {})", corrupt.id, actual)?
+            }
+            Corruption::Collision => writeln!(out, "error: {}: SHA-1 collision detected", corrupt.id)?,
+        }
+    }
+    for problem in &outcome.problems {
+        let severity = match problem.severity {
+            Severity::Error => "error",
+            Severity::Warn => "warning",
+            Severity::Ignore => continue,
+        };
+        writeln!(
+            out,
+            "{} in {} {}: {}",
+            severity, problem.kind, problem.id, problem.check
+        )?;
+    }
+    for link in &outcome.broken_links {
+        match link.actual {
+            Some(actual) => writeln!(
+                out,
+                "broken link from {} to {} {} (is {})",
+                link.from, link.expected, link.to, actual
+            )?,
+            None => writeln!(out, "broken link from {} to {} {}", link.from, link.expected, link.to)?,
+        }
+    }
+    for missing in &outcome.missing {
+        match missing.kind {
+            Some(kind) => writeln!(out, "missing {} {}", kind, missing.id)?,
+            None => writeln!(out, "missing object {}", missing.id)?,
+        }
+    }
+    for object in &outcome.dangling {
+        writeln!(out, "dangling {} {}", object.kind, object.id)?;
+    }
+    Ok(())
+}
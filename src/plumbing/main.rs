@@ -15,7 +15,10 @@ use gitoxide_core::pack::verify;
 
 use crate::{
     plumbing::{
-        options::{commit, config, credential, exclude, free, index, mailmap, odb, revision, tree, Args, Subcommands},
+        options::{
+            commit, config, credential, exclude, free, index, mailmap, odb, reference, revision, tree, Args,
+            Subcommands,
+        },
         show_progress,
     },
     shared::pretty::prepare_and_run,
@@ -57,7 +60,16 @@ pub fn main() -> Result<()> {
     let format = args.format;
     let cmd = args.cmd;
     let object_hash = args.object_hash;
-    let config = args.config;
+    #[allow(unused_mut)]
+    let mut config = args.config;
+    #[cfg(feature = "gitoxide-core-blocking-client")]
+    {
+        if let Subcommands::Clone(crate::plumbing::options::clone::Platform { connect, .. })
+        | Subcommands::Fetch(crate::plumbing::options::fetch::Platform { connect, .. }) = &cmd
+        {
+            config.extend(connect.config_override());
+        }
+    }
     use git_repository as git;
     let repository = args.repository;
     enum Mode {
@@ -116,6 +128,9 @@ pub fn main() -> Result<()> {
         Subcommands::Clone(crate::plumbing::options::clone::Platform {
             handshake_info,
             bare,
+            shallow,
+            connect: _,
+            filter,
             remote,
             directory,
         }) => {
@@ -123,6 +138,8 @@ pub fn main() -> Result<()> {
                 format,
                 bare,
                 handshake_info,
+                shallow: shallow.into(),
+                filter,
             };
             prepare_and_run(
                 "clone",
@@ -138,6 +155,9 @@ pub fn main() -> Result<()> {
             dry_run,
             handshake_info,
             remote,
+            shallow,
+            connect: _,
+            filter,
             ref_spec,
         }) => {
             let opts = core::repository::fetch::Options {
@@ -145,6 +165,8 @@ pub fn main() -> Result<()> {
                 dry_run,
                 remote,
                 handshake_info,
+                shallow: shallow.into(),
+                filter,
                 ref_specs: ref_spec,
             };
             prepare_and_run(
@@ -365,6 +387,8 @@ pub fn main() -> Result<()> {
                     counting_threads,
                     object_cache_size_mb,
                     output_directory,
+                    window,
+                    depth,
                 } => {
                     let has_tips = !tips.is_empty();
                     prepare_and_run(
@@ -383,6 +407,8 @@ pub fn main() -> Result<()> {
                                 pack_cache_size_in_bytes: pack_cache_size_mb.unwrap_or(0) * 1_000_000,
                                 object_cache_size_in_bytes: object_cache_size_mb.unwrap_or(0) * 1_000_000,
                                 statistics: if statistics { Some(format) } else { None },
+                                delta_window: window,
+                                delta_depth: depth,
                                 out,
                                 expansion: expansion.unwrap_or(if has_tips {
                                     core::pack::create::ObjectExpansion::TreeTraversal
@@ -614,6 +640,7 @@ pub fn main() -> Result<()> {
                     decode,
                     re_encode,
                 },
+            fsck,
         } => prepare_and_run(
             "verify",
             verbose,
@@ -631,6 +658,7 @@ pub fn main() -> Result<()> {
                         algorithm,
                         verify_mode: verify_mode(decode, re_encode),
                         thread_limit,
+                        fsck,
                     },
                 )
             },
@@ -780,6 +808,62 @@ pub fn main() -> Result<()> {
                 None,
                 move |_progress, out, err| core::repository::odb::info(repository(Mode::Strict)?, format, out, err),
             ),
+            odb::Subcommands::Repack {
+                all,
+                geometric,
+                bitmap,
+                write_midx,
+                prune,
+                window,
+                depth,
+            } => prepare_and_run(
+                "odb-repack",
+                verbose,
+                progress,
+                progress_keep_open,
+                core::repository::odb::repack::PROGRESS_RANGE,
+                move |progress, out, _err| {
+                    core::repository::odb::repack(
+                        repository(Mode::Strict)?,
+                        progress,
+                        out,
+                        core::repository::odb::repack::Options {
+                            format,
+                            mode: match (all, geometric) {
+                                (true, _) => git::repack::Mode::AllIntoOne,
+                                (false, Some(factor)) => git::repack::Mode::Geometric { factor },
+                                (false, None) => git::repack::Mode::LooseOnly,
+                            },
+                            write_bitmap: bitmap,
+                            write_multi_pack_index: write_midx,
+                            prune,
+                            thread_limit,
+                            window: Some(window).filter(|window| *window != 0),
+                            depth,
+                        },
+                    )
+                },
+            ),
+        },
+        Subcommands::Reference(cmd) => match cmd {
+            reference::Subcommands::Pack { all, no_prune } => prepare_and_run(
+                "reference-pack",
+                verbose,
+                progress,
+                progress_keep_open,
+                None,
+                move |_progress, out, _err| {
+                    core::repository::reference::pack(
+                        repository(Mode::Strict)?,
+                        out,
+                        core::repository::reference::pack::Options {
+                            format,
+                            all,
+                            prune: !no_prune,
+                        },
+                    )
+                },
+            ),
         },
         Subcommands::Mailmap(cmd) => match cmd {
             mailmap::Subcommands::Entries => prepare_and_run(
@@ -151,7 +151,19 @@ pub mod pack {
             /// Packs produced with this option enabled are only valid in transit, but not at rest.
             thin: bool,
 
-            /// The directory into which to write the pack file.
+            #[clap(long)]
+            /// If set, objects which aren't already in a pack will be delta-compressed against up to this amount of
+            /// similar objects, like `pack.window` does in git.
+            ///
+            /// The `delta` attribute in `.gitattributes` files is respected.
+            /// If unset, these objects are stored as base objects.
+            window: Option<usize>,
+
+            #[clap(long, default_value_t = 50, requires = "window")]
+            /// The maximum length of delta chains produced when `--window` is set, like `pack.depth` does in git.
+            depth: usize,
+
+            /// The directory into which to write the pack file along with its index.
             #[clap(long, short = 'o')]
             output_directory: Option<PathBuf>,
 
@@ -64,6 +64,9 @@ pub enum Subcommands {
     /// Interact with the object database.
     #[clap(subcommand)]
     Odb(odb::Subcommands),
+    /// Interact with references.
+    #[clap(subcommand)]
+    Reference(reference::Subcommands),
     /// Interact with tree objects.
     #[clap(subcommand)]
     Tree(tree::Subcommands),
@@ -74,6 +77,11 @@ pub enum Subcommands {
     Verify {
         #[clap(flatten)]
         args: free::pack::VerifyOptions,
+        /// Also check all objects for validity and connectivity like `git fsck`.
+        ///
+        /// This reads every object and walks the entire history, so it will take longer.
+        #[clap(long)]
+        fsck: bool,
     },
     /// Query and obtain information about revisions.
     #[clap(subcommand)]
@@ -118,6 +126,39 @@ pub mod config {
     }
 }
 
+/// Options to control how to connect to the remote, similar to those of `git fetch` and `git clone`.
+#[cfg(feature = "gitoxide-core-blocking-client")]
+#[derive(Debug, clap::Parser)]
+pub struct ConnectOptions {
+    /// Only use IPv4 addresses when connecting to the remote.
+    #[clap(long, short = '4', conflicts_with = "ipv6")]
+    pub ipv4: bool,
+
+    /// Only use IPv6 addresses when connecting to the remote.
+    #[clap(long, short = '6', conflicts_with = "ipv4")]
+    pub ipv6: bool,
+}
+
+#[cfg(feature = "gitoxide-core-blocking-client")]
+impl ConnectOptions {
+    /// Return the configuration override to apply these options, if any.
+    pub fn config_override(&self) -> Option<BString> {
+        if self.ipv4 {
+            Some("gitoxide.ssh.ipVersion=4".into())
+        } else if self.ipv6 {
+            Some("gitoxide.ssh.ipVersion=6".into())
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse `input` as date relative to now, for use with shallow clones and fetches.
+#[cfg(feature = "gitoxide-core-blocking-client")]
+fn parse_date(input: &str) -> Result<git::date::Time, git::date::parse::Error> {
+    git::date::parse(input, Some(std::time::SystemTime::now()))
+}
+
 #[cfg(feature = "gitoxide-core-blocking-client")]
 pub mod fetch {
     use git_repository as git;
@@ -138,10 +179,67 @@ pub mod fetch {
         #[clap(long, short = 'r')]
         pub remote: Option<String>,
 
+        #[clap(flatten)]
+        pub shallow: ShallowOptions,
+
+        #[clap(flatten)]
+        pub connect: super::ConnectOptions,
+
+        /// Only fetch objects matching the given filter specification, like `blob:none`, and fetch missing ones on demand.
+        ///
+        /// If unset, the filter configured in `remote.<name>.partialCloneFilter` is used for promisor remotes.
+        #[clap(long, value_name = "FILTER-SPEC")]
+        pub filter: Option<String>,
+
         /// Override the built-in and configured ref-specs with one or more of the given ones.
         #[clap(parse(try_from_os_str = git::env::os_str_to_bstring))]
         pub ref_spec: Vec<git_repository::bstr::BString>,
     }
+
+    #[derive(Debug, clap::Parser)]
+    pub struct ShallowOptions {
+        /// Fetch with the history truncated to the given number of commits as seen from the remote.
+        #[clap(long, conflicts_with_all = &["shallow-since", "shallow-exclude", "deepen", "unshallow"])]
+        pub depth: Option<std::num::NonZeroU32>,
+
+        /// Extend the current shallow boundary by the given number of commits, with 0 meaning no change.
+        #[clap(long, value_name = "DEPTH", conflicts_with_all = &["depth", "shallow-since", "shallow-exclude", "unshallow"])]
+        pub deepen: Option<u32>,
+
+        /// Cutoff all history past the given date. Can be combined with shallow-exclude.
+        #[clap(long, parse(try_from_str = super::parse_date))]
+        pub shallow_since: Option<git::date::Time>,
+
+        /// Cutoff all history past the tag-name or ref-name. Can be combined with shallow-since.
+        #[clap(long, parse(try_from_os_str = git::env::os_str_to_bstring))]
+        pub shallow_exclude: Vec<git::bstr::BString>,
+
+        /// Remove the shallow boundary and fetch the entire history available on the remote.
+        #[clap(long, conflicts_with_all = &["shallow-since", "shallow-exclude", "depth", "deepen"])]
+        pub unshallow: bool,
+    }
+
+    impl From<ShallowOptions> for git::remote::fetch::Shallow {
+        fn from(opts: ShallowOptions) -> Self {
+            use git::remote::fetch::Shallow;
+            if let Some(depth) = opts.depth {
+                Shallow::DepthAtRemote(depth)
+            } else if !opts.shallow_exclude.is_empty() {
+                Shallow::Exclude {
+                    remote_refs: opts.shallow_exclude,
+                    since_cutoff: opts.shallow_since,
+                }
+            } else if let Some(cutoff) = opts.shallow_since {
+                Shallow::Since { cutoff }
+            } else if let Some(depth) = opts.deepen {
+                Shallow::Deepen(depth)
+            } else if opts.unshallow {
+                Shallow::undo()
+            } else {
+                Shallow::default()
+            }
+        }
+    }
 }
 
 #[cfg(feature = "gitoxide-core-blocking-client")]
@@ -158,12 +256,57 @@ pub mod clone {
         #[clap(long)]
         pub bare: bool,
 
+        #[clap(flatten)]
+        pub shallow: ShallowOptions,
+
+        #[clap(flatten)]
+        pub connect: super::ConnectOptions,
+
+        /// Create a partial clone which only receives objects matching the given filter specification, like `blob:none`.
+        ///
+        /// Missing objects are fetched on demand from the remote.
+        #[clap(long, value_name = "FILTER-SPEC")]
+        pub filter: Option<String>,
+
         /// The url of the remote to connect to, like `https://github.com/byron/gitoxide`.
         pub remote: OsString,
 
         /// The directory to initialize with the new repository and to which all data should be written.
         pub directory: PathBuf,
     }
+
+    #[derive(Debug, clap::Parser)]
+    pub struct ShallowOptions {
+        /// Create a shallow clone with the history truncated to the given number of commits.
+        #[clap(long, conflicts_with_all = &["shallow-since", "shallow-exclude"])]
+        pub depth: Option<std::num::NonZeroU32>,
+
+        /// Cutoff all history past the given date. Can be combined with shallow-exclude.
+        #[clap(long, parse(try_from_str = super::parse_date))]
+        pub shallow_since: Option<git_repository::date::Time>,
+
+        /// Cutoff all history past the tag-name or ref-name. Can be combined with shallow-since.
+        #[clap(long, parse(try_from_os_str = git_repository::env::os_str_to_bstring))]
+        pub shallow_exclude: Vec<git_repository::bstr::BString>,
+    }
+
+    impl From<ShallowOptions> for git_repository::remote::fetch::Shallow {
+        fn from(opts: ShallowOptions) -> Self {
+            use git_repository::remote::fetch::Shallow;
+            if let Some(depth) = opts.depth {
+                Shallow::DepthAtRemote(depth)
+            } else if !opts.shallow_exclude.is_empty() {
+                Shallow::Exclude {
+                    remote_refs: opts.shallow_exclude,
+                    since_cutoff: opts.shallow_since,
+                }
+            } else if let Some(cutoff) = opts.shallow_since {
+                Shallow::Since { cutoff }
+            } else {
+                Shallow::default()
+            }
+        }
+    }
 }
 
 #[cfg(any(feature = "gitoxide-core-async-client", feature = "gitoxide-core-blocking-client"))]
@@ -219,6 +362,60 @@ pub mod odb {
         Entries,
         /// Provide general information about the object database.
         Info,
+        /// Consolidate loose objects and packs into a new pack and remove what it makes redundant.
+        ///
+        /// By default, only reachable loose objects are packed.
+        Repack {
+            /// Write all reachable objects into a single pack and remove all other packs without a `.keep` file.
+            #[clap(long, short = 'a', conflicts_with("geometric"))]
+            all: bool,
+
+            /// Combine packs so that each pack contains at least this many times more objects than the next smaller one.
+            ///
+            /// All loose objects are packed as well.
+            #[clap(long, short = 'g')]
+            geometric: Option<u32>,
+
+            /// Write a reachability bitmap for the new pack, which requires `--all`.
+            #[clap(long, short = 'b', requires("all"))]
+            bitmap: bool,
+
+            /// Write a multi-pack-index covering all packs after the repack.
+            #[clap(long, short = 'm')]
+            write_midx: bool,
+
+            /// Delete unreachable loose objects older than the given date, like `2.weeks.ago` or `now`.
+            #[clap(long)]
+            prune: Option<String>,
+
+            /// If set, objects which aren't already delta-compressed will be delta-compressed against up to this amount of
+            /// similar objects, like `pack.window` does in git. Use 0 to disable delta compression.
+            #[clap(long, default_value_t = 10)]
+            window: usize,
+
+            /// The maximum length of delta chains produced, like `pack.depth` does in git.
+            #[clap(long, default_value_t = 50)]
+            depth: usize,
+        },
+    }
+}
+
+pub mod reference {
+    #[derive(Debug, clap::Subcommand)]
+    #[clap(visible_alias = "references")]
+    pub enum Subcommands {
+        /// Move loose references into the packed-refs file, like `git pack-refs`.
+        ///
+        /// By default, only tags and references which are already packed are moved, and their loose files are deleted.
+        Pack {
+            /// Pack all loose references, not only tags and references which are already packed.
+            #[clap(long, short = 'a')]
+            all: bool,
+
+            /// Keep the loose references after packing them.
+            #[clap(long)]
+            no_prune: bool,
+        },
     }
 }
 
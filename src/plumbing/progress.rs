@@ -739,6 +739,13 @@ static GIT_CONFIG: &[Record] = &[
             reason: "on demand, without it it's not possible to implement environment overrides via `no_proxy` or `NO_PROXY` for a list of hostnames or `*`"
         }
     },
+    Record {
+        config: "gitoxide.ssh.ipVersion",
+        usage: InModule {
+            name: "remote::connect",
+            deviation: Some("entirely new, as git only allows to choose the IP version with the --ipv4 and --ipv6 flags")
+        }
+    },
     Record {
         config: "gitoxide.http.connectTimeout",
         usage: InModule {